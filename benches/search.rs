@@ -0,0 +1,39 @@
+//! Searching 10,000 entries on one thread and on every CPU
+//!
+//! Run with `cargo bench --bench search`. The entries go in a throwaway
+//! pocket, so the real one is never touched.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pocket_cli::models::{ContentType, Entry};
+use pocket_cli::storage::StorageManager;
+
+const ENTRIES: usize = 10_000;
+
+fn fill(storage: &StorageManager) {
+    for i in 0..ENTRIES {
+        let entry = Entry::new(format!("snippet {}", i), ContentType::Code, None, vec!["bench".to_string()]);
+        let content = format!("fn snippet_{}() {{\n    // {}\n    println!(\"{}\");\n}}\n", i, "filler ".repeat(40), i);
+        storage.save_entry(&entry, &content, None).unwrap();
+    }
+}
+
+fn search(c: &mut Criterion) {
+    let home = tempfile::tempdir().unwrap();
+    std::env::set_var("POCKET_HOME", home.path());
+    let storage = StorageManager::new().unwrap();
+    fill(&storage);
+    
+    let mut group = c.benchmark_group("search 10k entries");
+    group.sample_size(10);
+    let cpus = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    for threads in [1, cpus] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        group.bench_with_input(BenchmarkId::new("threads", threads), &threads, |b, _| {
+            b.iter(|| pool.install(|| storage.search_entries("snippet_9999", None, 10).unwrap()));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, search);
+criterion_main!(benches);
@@ -0,0 +1,107 @@
+//! Encrypting snapshot archives
+//!
+//! A passphrase is stretched with Argon2id into a ChaCha20-Poly1305 key, and
+//! the archive is sealed whole, with the salt and nonce in front. age
+//! encryption is left to the `age` program, the way compression is left to
+//! `tar`.
+
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Result, Context, anyhow};
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+/// First bytes of an archive sealed with a passphrase
+const MAGIC: &[u8] = b"pocket-backup-v1\n";
+
+const SALT_LEN: usize = 16;
+
+const NONCE_LEN: usize = 12;
+
+/// Seals an archive with a passphrase
+pub(super) fn seal_with_passphrase(archive: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?).encrypt(&nonce, archive)
+        .map_err(|_| anyhow!("Failed to encrypt the backup"))?;
+    
+    let mut sealed = MAGIC.to_vec();
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Opens an archive sealed by `seal_with_passphrase`
+pub(super) fn open_with_passphrase(sealed: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let rest = sealed.strip_prefix(MAGIC)
+        .filter(|rest| rest.len() >= SALT_LEN + NONCE_LEN)
+        .ok_or_else(|| anyhow!("Invalid backup: the archive isn't encrypted with a passphrase"))?;
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    
+    ChaCha20Poly1305::new(&derive_key(passphrase, salt)?).decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt the backup; wrong passphrase?"))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive the backup key: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypts `input` to `output` for each of the age `recipients`
+pub(super) fn seal_with_age(input: &Path, output: &Path, recipients: &[String]) -> Result<()> {
+    if recipients.is_empty() {
+        anyhow::bail!("age encryption needs at least one recipient in backup.age_recipients");
+    }
+    
+    let mut command = Command::new("age");
+    for recipient in recipients {
+        command.arg("--recipient").arg(recipient);
+    }
+    let status = command.arg("--output").arg(output).arg(input)
+        .status()
+        .context("Failed to run age (is it installed?)")?;
+    if !status.success() {
+        anyhow::bail!("age exited with {}", status);
+    }
+    Ok(())
+}
+
+/// Decrypts `input` to `output` with the age identity file `identity`
+pub(super) fn open_with_age(input: &Path, output: &Path, identity: &Path) -> Result<()> {
+    let status = Command::new("age")
+        .arg("--decrypt")
+        .arg("--identity")
+        .arg(identity)
+        .arg("--output")
+        .arg(output)
+        .arg(input)
+        .status()
+        .context("Failed to run age (is it installed?)")?;
+    if !status.success() {
+        anyhow::bail!("Failed to decrypt the backup: age exited with {}", status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn opens_only_with_the_same_passphrase() {
+        let sealed = seal_with_passphrase(b"archive", "correct horse").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert!(!sealed.windows(7).any(|window| window == b"archive"));
+        
+        assert_eq!(open_with_passphrase(&sealed, "correct horse").unwrap(), b"archive");
+        assert!(open_with_passphrase(&sealed, "battery staple").is_err());
+        assert!(open_with_passphrase(b"archive", "correct horse").is_err());
+    }
+}
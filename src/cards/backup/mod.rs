@@ -0,0 +1,1135 @@
+//! Backup card for Pocket CLI
+//!
+//! This card provides functionality for backing up and restoring snippets and repositories.
+//!
+//! Backups can also be taken on a schedule: after every N commands that change
+//! storage, or once every `backup_frequency` days. Snapshots record a SHA-256
+//! checksum for every file so a restore can verify them before anything in the
+//! data directory is overwritten.
+//!
+//! Ctrl-C stops a backup or restore cleanly: a half-made snapshot is deleted,
+//! and a restore stops before it starts replacing the data directory.
+//!
+//! Snapshots can be encrypted, with a passphrase or for age recipients, and
+//! copied to remotes (see [`remote`]). Each backup is checked to restore
+//! before it counts, the same way `backup verify` checks one later.
+
+mod crypt;
+pub mod remote;
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::fs;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use chrono::{DateTime, Datelike, Utc};
+use anyhow::{Result, Context};
+use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
+
+use crate::cards::{Card, CardConfig, CardCommand};
+use crate::utils::atomic;
+use crate::utils::cancel;
+use crate::utils::suggest;
+use crate::utils::progress::Progress;
+use remote::RemoteTarget;
+
+/// File holding a snapshot's metadata
+const METADATA_FILE: &str = "metadata.json";
+
+/// File holding the checksum of every file in a snapshot
+const CHECKSUMS_FILE: &str = "checksums.json";
+
+/// Archive holding the contents of a compressed snapshot
+const ARCHIVE_FILE: &str = "data.tar.gz";
+
+/// The archive once it's encrypted with a passphrase
+const PASSPHRASE_ARCHIVE_FILE: &str = "data.tar.gz.enc";
+
+/// The archive once it's encrypted with age
+const AGE_ARCHIVE_FILE: &str = "data.tar.gz.age";
+
+/// File in the backup directory tracking the backup schedule
+const SCHEDULE_FILE: &str = "schedule.json";
+
+/// Configuration for the backup card
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupCardConfig {
+    /// Directory where backups are stored
+    pub backup_dir: PathBuf,
+    
+    /// Number of most recent backups that are always kept
+    pub max_backups: usize,
+    
+    /// Whether scheduled backups are taken automatically
+    pub auto_backup: bool,
+    
+    /// Backup frequency in days (0 means no time-based backups)
+    pub backup_frequency: u32,
+    
+    /// Back up after this many commands that change storage (0 disables the counter)
+    pub every_n_commands: u32,
+    
+    /// Also keep the newest backup of each of this many days
+    pub keep_daily: usize,
+    
+    /// Also keep the newest backup of each of this many weeks
+    pub keep_weekly: usize,
+    
+    /// Whether snapshots are stored as a gzipped tarball
+    pub compress: bool,
+    
+    /// How snapshots are encrypted; encrypted snapshots are always compressed
+    pub encryption: BackupEncryption,
+    
+    /// Environment variable holding the passphrase, for passphrase encryption
+    pub passphrase_env: String,
+    
+    /// Public keys snapshots are encrypted to, for age encryption
+    pub age_recipients: Vec<String>,
+    
+    /// age identity file that decrypts snapshots, for restoring and verifying
+    pub age_identity: Option<PathBuf>,
+    
+    /// Where else each backup is copied, by name
+    pub remotes: BTreeMap<String, RemoteTarget>,
+    
+    /// Whether each backup is checked to restore once it's taken
+    pub verify: bool,
+    
+    /// Date of the last backup
+    pub last_backup: Option<DateTime<Utc>>,
+}
+
+/// How a snapshot is encrypted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupEncryption {
+    #[default]
+    None,
+    
+    /// With a passphrase, from `passphrase_env` or asked for
+    Passphrase,
+    
+    /// For `age_recipients`, with the `age` program
+    Age,
+}
+
+impl BackupEncryption {
+    /// The file a snapshot's archive is kept in
+    fn archive_file(self) -> &'static str {
+        match self {
+            BackupEncryption::None => ARCHIVE_FILE,
+            BackupEncryption::Passphrase => PASSPHRASE_ARCHIVE_FILE,
+            BackupEncryption::Age => AGE_ARCHIVE_FILE,
+        }
+    }
+}
+
+impl Default for BackupCardConfig {
+    fn default() -> Self {
+        Self {
+            backup_dir: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("pocket")
+                .join("backups"),
+            max_backups: 5,
+            auto_backup: true,
+            backup_frequency: 1,
+            every_n_commands: 0,
+            keep_daily: 0,
+            keep_weekly: 0,
+            compress: false,
+            encryption: BackupEncryption::None,
+            passphrase_env: "POCKET_BACKUP_PASSPHRASE".to_string(),
+            age_recipients: Vec::new(),
+            age_identity: None,
+            remotes: BTreeMap::new(),
+            verify: true,
+            last_backup: None,
+        }
+    }
+}
+
+/// Metadata for a backup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupMetadata {
+    /// Unique identifier for the backup
+    pub id: String,
+    
+    /// Date and time when the backup was created
+    pub created_at: DateTime<Utc>,
+    
+    /// Description of the backup
+    pub description: String,
+    
+    /// Number of snippets in the backup
+    pub snippet_count: usize,
+    
+    /// Number of repositories in the backup
+    pub repository_count: usize,
+    
+    /// Size of the backup in bytes
+    pub size: u64,
+    
+    /// Whether the snapshot is stored as a gzipped tarball
+    #[serde(default)]
+    pub compressed: bool,
+    
+    /// SHA-256 of the archive, for compressed snapshots
+    #[serde(default)]
+    pub archive_checksum: Option<String>,
+    
+    /// How the archive is encrypted
+    #[serde(default)]
+    pub encryption: BackupEncryption,
+}
+
+/// Progress towards the next scheduled backup
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduleState {
+    /// Commands that changed storage since the last backup
+    commands_since_backup: u32,
+    
+    /// When the last backup was taken
+    last_backup: Option<DateTime<Utc>>,
+}
+
+/// Card for backing up and restoring snippets and repositories
+pub struct BackupCard {
+    /// Name of the card
+    name: String,
+    
+    /// Version of the card
+    version: String,
+    
+    /// Description of the card
+    description: String,
+    
+    /// Configuration for the card
+    config: BackupCardConfig,
+    
+    /// Path to the Pocket data directory (kept for future use)
+    _data_dir: PathBuf,
+    
+    /// The passphrase, once it's been asked for
+    passphrase: OnceLock<String>,
+}
+
+impl BackupCard {
+    /// Creates a new backup card
+    pub fn new(data_dir: impl AsRef<Path>) -> Self {
+        Self {
+            name: "backup".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            description: "Provides functionality for backing up and restoring snippets and repositories".to_string(),
+            config: BackupCardConfig::default(),
+            _data_dir: data_dir.as_ref().to_path_buf(),
+            passphrase: OnceLock::new(),
+        }
+    }
+    
+    /// Creates a backup of the current state
+    pub fn create_backup(&self, description: &str) -> Result<BackupMetadata> {
+        let metadata = self.snapshot("backup", description)?;
+        
+        // A backup that can't be restored mustn't push out older ones
+        if self.config.verify && !self.can_decrypt() {
+            log::warn!("Backup {} wasn't verified; set backup.age_identity so it can be decrypted", metadata.id);
+        } else if self.config.verify {
+            self.verify_backup(&metadata.id)
+                .with_context(|| format!("Backup {} was taken but can't be restored", metadata.id))?;
+        }
+        
+        // Any backup restarts the schedule
+        self.save_schedule(&ScheduleState {
+            commands_since_backup: 0,
+            last_backup: Some(metadata.created_at),
+        })?;
+        
+        // Prune old backups if necessary
+        self.prune_old_backups()?;
+        
+        Ok(metadata)
+    }
+    
+    /// Whether new snapshots can be decrypted here, to verify them
+    fn can_decrypt(&self) -> bool {
+        self.config.encryption != BackupEncryption::Age || self.config.age_identity.is_some()
+    }
+    
+    /// Copies the data directory into a new snapshot without pruning
+    fn snapshot(&self, prefix: &str, description: &str) -> Result<BackupMetadata> {
+        let _operation = cancel::begin();
+        
+        // Ensure the backup directory exists
+        fs::create_dir_all(&self.config.backup_dir)
+            .context("Failed to create backup directory")?;
+        
+        // Generate a unique ID for the backup
+        let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let mut backup_id = format!("{}_{}", prefix, timestamp);
+        let mut n = 1;
+        while self.config.backup_dir.join(&backup_id).exists() {
+            n += 1;
+            backup_id = format!("{}_{}_{}", prefix, timestamp, n);
+        }
+        let backup_dir = self.config.backup_dir.join(&backup_id);
+        
+        // Create the backup directory
+        fs::create_dir(&backup_dir)
+            .context("Failed to create backup directory")?;
+        
+        // A snapshot cut short, by an error or Ctrl-C, mustn't pass for a backup
+        let result = self.fill_snapshot(backup_id, &backup_dir, description);
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&backup_dir);
+        }
+        result
+    }
+    
+    /// Copies the data directory into a new snapshot's directory and records
+    /// what's in it
+    fn fill_snapshot(&self, backup_id: String, backup_dir: &Path, description: &str) -> Result<BackupMetadata> {
+        // Copy the data directory to the backup directory
+        self.copy_directory(&self._data_dir, backup_dir, "Backing up")
+            .context("Failed to copy data directory")?;
+        
+        // Count snippets and repositories
+        let snippet_count = self.count_snippets(backup_dir)?;
+        let repository_count = self.count_repositories(backup_dir)?;
+        
+        // Record a checksum for every file before it is compressed
+        let checksums = self.checksum_directory(backup_dir)?;
+        
+        let encryption = self.config.encryption;
+        let archive_checksum = if self.config.compress || encryption != BackupEncryption::None {
+            let checksum = self.compress_snapshot(backup_dir)?;
+            match encryption {
+                BackupEncryption::None => Some(checksum),
+                _ => Some(self.encrypt_snapshot(backup_dir, encryption)?),
+            }
+        } else {
+            None
+        };
+        
+        atomic::write(backup_dir.join(CHECKSUMS_FILE), serde_json::to_string_pretty(&checksums)?)
+            .context("Failed to write backup checksums")?;
+        
+        // Calculate the size of the backup
+        let size = self.directory_size(backup_dir)?;
+        
+        // Create metadata
+        let metadata = BackupMetadata {
+            id: backup_id,
+            created_at: Utc::now(),
+            description: description.to_string(),
+            snippet_count,
+            repository_count,
+            size,
+            compressed: archive_checksum.is_some(),
+            archive_checksum,
+            encryption,
+        };
+        
+        // Save metadata
+        let metadata_path = backup_dir.join(METADATA_FILE);
+        let metadata_json = serde_json::to_string_pretty(&metadata)?;
+        atomic::write(&metadata_path, metadata_json)
+            .context("Failed to write backup metadata")?;
+        
+        Ok(metadata)
+    }
+    
+    /// Restores a backup, verifying its integrity before overwriting anything
+    pub fn restore_backup(&self, backup_id: &str) -> Result<()> {
+        let _operation = cancel::begin();
+        
+        // Verify every file before touching the data directory
+        let (_extracted, source, _) = self.open_backup(backup_id)?;
+        
+        // Create a backup of the current state before restoring
+        self.snapshot("pre_restore", &format!("State before restoring {}", backup_id))
+            .context("Failed to backup current state")?;
+        
+        cancel::check()?;
+        
+        // Stopping while the data directory is replaced would leave half of it
+        cancel::shielded(|| {
+            // Clear the current data directory
+            self.clear_directory(&self._data_dir)
+                .context("Failed to clear data directory")?;
+            
+            // Copy the backup to the data directory
+            self.copy_directory(&source, &self._data_dir, "Restoring")
+                .context("Failed to restore backup")
+        })
+    }
+    
+    /// Checks that a backup would restore, without restoring it, and returns
+    /// how many files were checked
+    pub fn verify_backup(&self, backup_id: &str) -> Result<usize> {
+        let _operation = cancel::begin();
+        let (_extracted, _, checked) = self.open_backup(backup_id)?;
+        Ok(checked)
+    }
+    
+    /// Gets a backup ready to restore: reads its metadata, decrypts and
+    /// extracts its archive to a temporary directory if it has one, and
+    /// verifies every file. Returns the temporary directory, where the files
+    /// are and how many were verified
+    fn open_backup(&self, backup_id: &str) -> Result<(Option<tempfile::TempDir>, PathBuf, usize)> {
+        let backup_dir = self.config.backup_dir.join(backup_id);
+        
+        // Check if the backup exists
+        if !backup_dir.exists() {
+            return Err(self.backup_not_found(backup_id));
+        }
+        
+        // Read metadata to verify it's a valid backup
+        let metadata_path = backup_dir.join(METADATA_FILE);
+        if !metadata_path.exists() {
+            anyhow::bail!("Invalid backup: metadata.json not found");
+        }
+        let metadata: BackupMetadata = serde_json::from_str(&fs::read_to_string(&metadata_path)?)
+            .context("Invalid backup: failed to parse metadata.json")?;
+        
+        // Compressed snapshots are extracted to a temporary directory first
+        let extracted = if metadata.compressed {
+            Some(self.extract_snapshot(&backup_dir, &metadata)?)
+        } else {
+            None
+        };
+        let source = extracted.as_ref()
+            .map(|dir| dir.path().to_path_buf())
+            .unwrap_or_else(|| backup_dir.clone());
+        
+        let checked = self.verify_snapshot(&backup_dir, &source)?;
+        Ok((extracted, source, checked))
+    }
+    
+    /// Checks the files of a snapshot against its recorded checksums and
+    /// returns how many there were
+    fn verify_snapshot(&self, backup_dir: &Path, source: &Path) -> Result<usize> {
+        let checksums_path = backup_dir.join(CHECKSUMS_FILE);
+        if !checksums_path.exists() {
+            // Backups made before checksums were recorded can't be verified
+            log::warn!("Backup {} has no checksums; restoring without verification", backup_dir.display());
+            return Ok(0);
+        }
+        
+        let checksums: BTreeMap<String, String> = serde_json::from_str(&fs::read_to_string(&checksums_path)?)
+            .context("Invalid backup: failed to parse checksums.json")?;
+        
+        let mut problems = Vec::new();
+        let progress = Progress::bar(checksums.len() as u64, "Verifying");
+        for (rel_path, expected) in &checksums {
+            cancel::check()?;
+            progress.inc(1);
+            match fs::read(source.join(rel_path)) {
+                Ok(bytes) if bytes_hash(&bytes) == *expected => {},
+                Ok(_) => problems.push(format!("{} (checksum mismatch)", rel_path)),
+                Err(_) => problems.push(format!("{} (missing)", rel_path)),
+            }
+        }
+        
+        if !problems.is_empty() {
+            anyhow::bail!(
+                "Backup failed verification, nothing was restored. {} problem(s): {}",
+                problems.len(),
+                problems.join(", ")
+            );
+        }
+        
+        Ok(checksums.len())
+    }
+    
+    /// Replaces the contents of a snapshot directory with a gzipped tarball
+    /// and returns the archive's checksum
+    fn compress_snapshot(&self, backup_dir: &Path) -> Result<String> {
+        // Build the archive next to the snapshot so it doesn't include itself
+        let partial = backup_dir.with_extension("tar.gz.partial");
+        let status = Command::new("tar")
+            .arg("-czf")
+            .arg(&partial)
+            .arg("-C")
+            .arg(backup_dir)
+            .arg(".")
+            .status()
+            .context("Failed to run tar")?;
+        if !status.success() {
+            let _ = fs::remove_file(&partial);
+            anyhow::bail!("tar exited with {}", status);
+        }
+        
+        self.clear_directory(backup_dir)?;
+        let archive = backup_dir.join(ARCHIVE_FILE);
+        fs::rename(&partial, &archive)
+            .context("Failed to move backup archive into place")?;
+        
+        Ok(bytes_hash(&fs::read(&archive)?))
+    }
+    
+    /// Replaces a snapshot's archive with an encrypted one and returns the
+    /// encrypted archive's checksum
+    fn encrypt_snapshot(&self, backup_dir: &Path, encryption: BackupEncryption) -> Result<String> {
+        let archive = backup_dir.join(ARCHIVE_FILE);
+        let encrypted = backup_dir.join(encryption.archive_file());
+        match encryption {
+            BackupEncryption::None => return Ok(bytes_hash(&fs::read(&archive)?)),
+            BackupEncryption::Passphrase => {
+                let sealed = crypt::seal_with_passphrase(&fs::read(&archive)?, self.passphrase(true)?)?;
+                atomic::write(&encrypted, sealed).context("Failed to write the encrypted archive")?;
+            },
+            BackupEncryption::Age => crypt::seal_with_age(&archive, &encrypted, &self.config.age_recipients)?,
+        }
+        fs::remove_file(&archive).context("Failed to remove the unencrypted archive")?;
+        
+        Ok(bytes_hash(&fs::read(&encrypted)?))
+    }
+    
+    /// The passphrase for encrypted snapshots, from `passphrase_env` or
+    /// asked for once in a terminal
+    fn passphrase(&self, confirm: bool) -> Result<&str> {
+        if let Some(passphrase) = self.passphrase.get() {
+            return Ok(passphrase);
+        }
+        
+        let passphrase = match std::env::var(&self.config.passphrase_env) {
+            Ok(passphrase) if !passphrase.is_empty() => passphrase,
+            _ if std::io::stdin().is_terminal() => {
+                let theme = dialoguer::theme::ColorfulTheme::default();
+                let mut prompt = dialoguer::Password::with_theme(&theme).with_prompt("Backup passphrase");
+                if confirm {
+                    prompt = prompt.with_confirmation("Repeat the passphrase", "The passphrases don't match");
+                }
+                prompt.interact()?
+            },
+            _ => anyhow::bail!("Encrypted backups need a passphrase: set ${} or run pocket in a terminal", self.config.passphrase_env),
+        };
+        Ok(self.passphrase.get_or_init(|| passphrase))
+    }
+    
+    /// Verifies, decrypts and extracts a compressed snapshot to a temporary
+    /// directory
+    fn extract_snapshot(&self, backup_dir: &Path, metadata: &BackupMetadata) -> Result<tempfile::TempDir> {
+        let archive_file = metadata.encryption.archive_file();
+        let mut archive = backup_dir.join(archive_file);
+        let bytes = fs::read(&archive)
+            .with_context(|| format!("Invalid backup: {} not found", archive_file))?;
+        
+        if let Some(expected) = &metadata.archive_checksum {
+            if bytes_hash(&bytes) != *expected {
+                anyhow::bail!("Backup failed verification, nothing was restored: archive checksum mismatch");
+            }
+        }
+        
+        // Decrypted archives never sit next to the encrypted ones
+        let decrypted = tempfile::tempdir().context("Failed to create temporary directory")?;
+        match metadata.encryption {
+            BackupEncryption::None => {},
+            BackupEncryption::Passphrase => {
+                let plain = decrypted.path().join(ARCHIVE_FILE);
+                fs::write(&plain, crypt::open_with_passphrase(&bytes, self.passphrase(false)?)?)?;
+                archive = plain;
+            },
+            BackupEncryption::Age => {
+                let identity = self.config.age_identity.as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("Backup {} is encrypted with age; set backup.age_identity to decrypt it", metadata.id))?;
+                let identity = crate::utils::expand_path(&identity.to_string_lossy())?;
+                let plain = decrypted.path().join(ARCHIVE_FILE);
+                crypt::open_with_age(&archive, &plain, &identity)?;
+                archive = plain;
+            },
+        }
+        
+        let dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+        let status = Command::new("tar")
+            .arg("-xzf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(dir.path())
+            .status()
+            .context("Failed to run tar")?;
+        if !status.success() {
+            anyhow::bail!("Failed to extract backup archive: tar exited with {}", status);
+        }
+        
+        Ok(dir)
+    }
+    
+    /// Copies a backup to every remote, returning the names of those it
+    /// reached. Fails after trying them all if any couldn't be reached
+    pub fn push_to_remotes(&self, metadata: &BackupMetadata) -> Result<Vec<String>> {
+        let _operation = cancel::begin();
+        let backup_dir = self.config.backup_dir.join(&metadata.id);
+        
+        let mut pushed = Vec::new();
+        let mut failures = Vec::new();
+        for (name, remote) in &self.config.remotes {
+            cancel::check()?;
+            let _progress = Progress::spinner(&format!("Copying to {}", name));
+            match remote.push(&metadata.id, &backup_dir) {
+                Ok(()) => pushed.push(name.clone()),
+                Err(e) => failures.push(format!("{} ({})", name, e)),
+            }
+        }
+        
+        if !failures.is_empty() {
+            anyhow::bail!("Failed to copy backup {} to {}", metadata.id, failures.join(", "));
+        }
+        Ok(pushed)
+    }
+    
+    /// Counts a command that changed storage and takes a backup when one is due
+    pub fn record_command(&self) -> Result<Option<BackupMetadata>> {
+        if !self.config.auto_backup {
+            return Ok(None);
+        }
+        
+        let mut state = self.load_schedule();
+        state.commands_since_backup += 1;
+        
+        if self.backup_due(&state) {
+            return self.create_backup("Scheduled backup").map(Some);
+        }
+        
+        self.save_schedule(&state)?;
+        Ok(None)
+    }
+    
+    /// Whether the schedule calls for a backup now
+    fn backup_due(&self, state: &ScheduleState) -> bool {
+        let due_by_count = self.config.every_n_commands > 0
+            && state.commands_since_backup >= self.config.every_n_commands;
+        
+        let due_by_time = self.config.backup_frequency > 0
+            && state.last_backup.or(self.config.last_backup).is_none_or(|last| {
+                Utc::now() - last >= chrono::Duration::days(self.config.backup_frequency as i64)
+            });
+        
+        due_by_count || due_by_time
+    }
+    
+    /// Loads the schedule state, starting fresh if there is none
+    fn load_schedule(&self) -> ScheduleState {
+        fs::read_to_string(self.config.backup_dir.join(SCHEDULE_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+    
+    /// Saves the schedule state
+    fn save_schedule(&self, state: &ScheduleState) -> Result<()> {
+        fs::create_dir_all(&self.config.backup_dir)
+            .context("Failed to create backup directory")?;
+        atomic::write(self.config.backup_dir.join(SCHEDULE_FILE), serde_json::to_string_pretty(state)?)
+            .context("Failed to write backup schedule")?;
+        Ok(())
+    }
+    
+    /// Lists all available backups
+    pub fn list_backups(&self) -> Result<Vec<BackupMetadata>> {
+        // Ensure the backup directory exists
+        if !self.config.backup_dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let mut backups = Vec::new();
+        
+        // Iterate through all entries in the backup directory
+        for entry in fs::read_dir(&self.config.backup_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            
+            // Check if it's a directory
+            if path.is_dir() {
+                // Check if it contains a metadata.json file
+                let metadata_path = path.join(METADATA_FILE);
+                if metadata_path.exists() {
+                    // Read and parse the metadata
+                    let metadata_json = fs::read_to_string(&metadata_path)?;
+                    let metadata: BackupMetadata = serde_json::from_str(&metadata_json)?;
+                    backups.push(metadata);
+                }
+            }
+        }
+        
+        // Sort backups by creation date (newest first)
+        backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+        
+        Ok(backups)
+    }
+    
+    /// The error for a backup that doesn't exist, suggesting ones that do
+    fn backup_not_found(&self, backup_id: &str) -> anyhow::Error {
+        let ids: Vec<String> = self.list_backups().unwrap_or_default().into_iter().map(|b| b.id).collect();
+        anyhow::anyhow!("Backup '{}' not found{}", backup_id, suggest::did_you_mean(backup_id, ids.iter().map(String::as_str)))
+    }
+    
+    /// Deletes a backup
+    pub fn delete_backup(&self, backup_id: &str) -> Result<()> {
+        let backup_dir = self.config.backup_dir.join(backup_id);
+        
+        // Check if the backup exists
+        if !backup_dir.exists() {
+            return Err(self.backup_not_found(backup_id));
+        }
+        
+        // Delete the backup directory
+        fs::remove_dir_all(&backup_dir)
+            .context("Failed to delete backup")?;
+        
+        Ok(())
+    }
+    
+    /// Prunes backups that fall outside the retention policy
+    fn prune_old_backups(&self) -> Result<()> {
+        let backups = self.list_backups()?;
+        let keep = retained_backups(&backups, self.config.max_backups, self.config.keep_daily, self.config.keep_weekly);
+        
+        for backup in backups.iter().filter(|b| !keep.contains(&b.id)) {
+            self.delete_backup(&backup.id)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Copies a directory recursively, showing `message` while it does
+    fn copy_directory(&self, src: &Path, dst: &Path, message: &str) -> Result<()> {
+        // Create the destination directory if it doesn't exist
+        if !dst.exists() {
+            fs::create_dir_all(dst)?;
+        }
+        
+        // Iterate through all entries in the source directory, skipping the
+        // backups themselves and snapshot bookkeeping files
+        let skip_backups = !src.starts_with(&self.config.backup_dir);
+        let walker = walkdir::WalkDir::new(src).into_iter().filter_entry(|e| {
+            let rel_path = e.path().strip_prefix(src).unwrap_or(e.path());
+            !(skip_backups && e.path().starts_with(&self.config.backup_dir))
+                && rel_path != Path::new(METADATA_FILE)
+                && rel_path != Path::new(CHECKSUMS_FILE)
+        });
+        let progress = Progress::spinner(message);
+        for entry in walker {
+            cancel::check()?;
+            progress.inc(1);
+            let entry = entry?;
+            let src_path = entry.path();
+            let rel_path = src_path.strip_prefix(src)?;
+            let dst_path = dst.join(rel_path);
+            
+            if src_path.is_dir() {
+                // Create the directory in the destination
+                fs::create_dir_all(&dst_path)?;
+            } else {
+                // Copy the file
+                fs::copy(src_path, &dst_path)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Clears a directory without deleting the directory itself
+    fn clear_directory(&self, dir: &Path) -> Result<()> {
+        // Check if the directory exists
+        if !dir.exists() {
+            return Ok(());
+        }
+        
+        // Iterate through all entries in the directory
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            
+            // Never delete the backups when they live inside the directory
+            if self.config.backup_dir.starts_with(&path) {
+                continue;
+            }
+            
+            if path.is_dir() {
+                // Recursively delete the directory
+                fs::remove_dir_all(&path)?;
+            } else {
+                // Delete the file
+                fs::remove_file(&path)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Computes the checksum of every file in a directory, keyed by relative path
+    fn checksum_directory(&self, dir: &Path) -> Result<BTreeMap<String, String>> {
+        let mut checksums = BTreeMap::new();
+        
+        for entry in walkdir::WalkDir::new(dir) {
+            cancel::check()?;
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let rel_path = entry.path().strip_prefix(dir)?
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                checksums.insert(rel_path, bytes_hash(&fs::read(entry.path())?));
+            }
+        }
+        
+        Ok(checksums)
+    }
+    
+    /// Counts the number of snippets in a directory
+    fn count_snippets(&self, dir: &Path) -> Result<usize> {
+        let snippets_dir = dir.join("snippets");
+        
+        if !snippets_dir.exists() {
+            return Ok(0);
+        }
+        
+        let count = walkdir::WalkDir::new(&snippets_dir)
+            .min_depth(1)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "json"))
+            .count();
+        
+        Ok(count)
+    }
+    
+    /// Counts the number of repositories in a directory
+    fn count_repositories(&self, dir: &Path) -> Result<usize> {
+        let repos_dir = dir.join("repositories");
+        
+        if !repos_dir.exists() {
+            return Ok(0);
+        }
+        
+        let count = walkdir::WalkDir::new(&repos_dir)
+            .max_depth(1)
+            .min_depth(1)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_dir())
+            .count();
+        
+        Ok(count)
+    }
+    
+    /// Calculates the size of a directory in bytes
+    fn directory_size(&self, dir: &Path) -> Result<u64> {
+        let mut size = 0;
+        
+        for entry in walkdir::WalkDir::new(dir) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                size += entry.metadata()?.len();
+            }
+        }
+        
+        Ok(size)
+    }
+}
+
+impl Card for BackupCard {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn version(&self) -> &str {
+        &self.version
+    }
+    
+    fn description(&self) -> &str {
+        &self.description
+    }
+    
+    fn initialize(&mut self, config: &CardConfig) -> Result<()> {
+        // If there are options in the card config, parse them
+        if let Some(options_value) = config.options.get("backup") {
+            self.config = serde_json::from_value::<BackupCardConfig>(options_value.clone())
+                .map_err(|e| anyhow::anyhow!("Invalid backup card options: {}", e))?;
+        }
+        
+        Ok(())
+    }
+    
+    fn execute(&self, command: &str, args: &[String]) -> Result<()> {
+        match command {
+            "backup" => {
+                let description = args.first().map(|s| s.as_str()).unwrap_or("Manual backup");
+                let metadata = self.create_backup(description)?;
+                println!("Backup created: {}", metadata.id);
+                println!("Description: {}", metadata.description);
+                println!("Created at: {}", metadata.created_at);
+                println!("Snippets: {}", metadata.snippet_count);
+                println!("Repositories: {}", metadata.repository_count);
+                println!("Size: {} bytes", metadata.size);
+                if metadata.compressed {
+                    println!("Compressed: yes");
+                }
+                if metadata.encryption != BackupEncryption::None {
+                    println!("Encrypted: {}", encryption_name(metadata.encryption));
+                }
+                if self.config.verify && self.can_decrypt() {
+                    println!("Verified: yes");
+                }
+                let pushed = self.push_to_remotes(&metadata)?;
+                if !pushed.is_empty() {
+                    println!("Copied to: {}", pushed.join(", "));
+                }
+                Ok(())
+            },
+            // Counts a storage-changing command and backs up when the schedule
+            // is due. Pocket runs it after such commands, so it isn't listed
+            // in `commands()`
+            "auto" => {
+                // Nobody is there to ask for the passphrase
+                if self.config.encryption == BackupEncryption::Passphrase
+                    && !std::env::var(&self.config.passphrase_env).is_ok_and(|passphrase| !passphrase.is_empty()) {
+                    log::warn!("Scheduled backups are encrypted with a passphrase; set ${} so they can run", self.config.passphrase_env);
+                    return Ok(());
+                }
+                if let Some(metadata) = self.record_command()? {
+                    log::info!("Scheduled backup created: {}", metadata.id);
+                    if let Err(e) = self.push_to_remotes(&metadata) {
+                        log::warn!("{}", e);
+                    }
+                }
+                Ok(())
+            },
+            "status" => {
+                let state = self.load_schedule();
+                println!("Backup directory: {}", self.config.backup_dir.display());
+                println!("Scheduled backups: {}", if self.config.auto_backup { "on" } else { "off" });
+                if self.config.every_n_commands > 0 {
+                    println!("  Every {} commands ({} since the last backup)", self.config.every_n_commands, state.commands_since_backup);
+                }
+                if self.config.backup_frequency > 0 {
+                    println!("  Every {} day(s)", self.config.backup_frequency);
+                }
+                match state.last_backup.or(self.config.last_backup) {
+                    Some(last) => println!("Last backup: {}", last),
+                    None => println!("Last backup: never"),
+                }
+                println!(
+                    "Retention: last {}, {} daily, {} weekly",
+                    self.config.max_backups, self.config.keep_daily, self.config.keep_weekly
+                );
+                let compressed = self.config.compress || self.config.encryption != BackupEncryption::None;
+                println!("Compression: {}", if compressed { "on" } else { "off" });
+                println!("Encryption: {}", encryption_name(self.config.encryption));
+                println!("Verification: {}", if self.config.verify { "after every backup" } else { "off" });
+                if self.config.remotes.is_empty() {
+                    println!("Remotes: none");
+                } else {
+                    println!("Remotes:");
+                    for (name, remote) in &self.config.remotes {
+                        println!("  {}: {}", name, remote.describe());
+                    }
+                }
+                Ok(())
+            },
+            "restore" => {
+                if args.is_empty() {
+                    anyhow::bail!("Backup ID is required");
+                }
+                let backup_id = &args[0];
+                self.restore_backup(backup_id)?;
+                println!("Backup '{}' verified and restored successfully", backup_id);
+                Ok(())
+            },
+            "verify" => {
+                let backup_id = match args.first() {
+                    Some(id) => id.clone(),
+                    None => self.list_backups()?.into_iter().next()
+                        .map(|backup| backup.id)
+                        .ok_or_else(|| anyhow::anyhow!("No backups to verify"))?,
+                };
+                let checked = self.verify_backup(&backup_id)?;
+                println!("Backup '{}' can be restored ({} files verified)", backup_id, checked);
+                Ok(())
+            },
+            "list" => {
+                let backups = self.list_backups()?;
+                if backups.is_empty() {
+                    println!("No backups found");
+                } else {
+                    println!("Available backups:");
+                    for backup in backups {
+                        println!("ID: {}", backup.id);
+                        println!("  Description: {}", backup.description);
+                        println!("  Created at: {}", backup.created_at);
+                        println!("  Snippets: {}", backup.snippet_count);
+                        println!("  Repositories: {}", backup.repository_count);
+                        let packing = match backup.encryption {
+                            BackupEncryption::None if backup.compressed => " (compressed)".to_string(),
+                            BackupEncryption::None => String::new(),
+                            encryption => format!(" (compressed, encrypted with {})", encryption_name(encryption)),
+                        };
+                        println!("  Size: {} bytes{}", backup.size, packing);
+                        println!();
+                    }
+                }
+                Ok(())
+            },
+            "delete" => {
+                if args.is_empty() {
+                    anyhow::bail!("Backup ID is required");
+                }
+                let backup_id = &args[0];
+                self.delete_backup(backup_id)?;
+                println!("Backup '{}' deleted successfully", backup_id);
+                Ok(())
+            },
+            _ => anyhow::bail!("Unknown command: {}", command),
+        }
+    }
+    
+    fn commands(&self) -> Vec<CardCommand> {
+        vec![
+            CardCommand {
+                name: "backup".to_string(),
+                description: "Creates a backup of the current state".to_string(),
+                usage: "pocket cards run backup backup [description]".to_string(),
+            },
+            CardCommand {
+                name: "restore".to_string(),
+                description: "Verifies and restores a backup".to_string(),
+                usage: "pocket cards run backup restore <backup-id>".to_string(),
+            },
+            CardCommand {
+                name: "verify".to_string(),
+                description: "Checks that a backup would restore, without restoring it".to_string(),
+                usage: "pocket cards run backup verify [backup-id]".to_string(),
+            },
+            CardCommand {
+                name: "list".to_string(),
+                description: "Lists all available backups".to_string(),
+                usage: "pocket cards run backup list".to_string(),
+            },
+            CardCommand {
+                name: "delete".to_string(),
+                description: "Deletes a backup".to_string(),
+                usage: "pocket cards run backup delete <backup-id>".to_string(),
+            },
+            CardCommand {
+                name: "status".to_string(),
+                description: "Shows the backup schedule and retention policy".to_string(),
+                usage: "pocket cards run backup status".to_string(),
+            },
+        ]
+    }
+    
+    fn cleanup(&mut self) -> Result<()> {
+        // Nothing to clean up
+        Ok(())
+    }
+} 
+
+/// How `encryption` is described to people
+fn encryption_name(encryption: BackupEncryption) -> &'static str {
+    match encryption {
+        BackupEncryption::None => "off",
+        BackupEncryption::Passphrase => "passphrase",
+        BackupEncryption::Age => "age",
+    }
+}
+
+/// Hex-encoded SHA-256 of some bytes
+fn bytes_hash(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Picks the backups to keep: the `keep_last` newest (at least one), plus the
+/// newest backup of each of the `keep_daily` most recent days and the
+/// `keep_weekly` most recent ISO weeks that have any backups
+fn retained_backups(backups: &[BackupMetadata], keep_last: usize, keep_daily: usize, keep_weekly: usize) -> HashSet<String> {
+    let mut newest_first: Vec<&BackupMetadata> = backups.iter().collect();
+    newest_first.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    
+    let mut keep: HashSet<String> = newest_first.iter()
+        .take(keep_last.max(1))
+        .map(|b| b.id.clone())
+        .collect();
+    
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for backup in &newest_first {
+        let day = backup.created_at.date_naive();
+        if days.len() < keep_daily && days.insert(day) {
+            keep.insert(backup.id.clone());
+        }
+        
+        let week = backup.created_at.iso_week();
+        if weeks.len() < keep_weekly && weeks.insert((week.year(), week.week())) {
+            keep.insert(backup.id.clone());
+        }
+    }
+    
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    fn backup(id: &str, y: i32, m: u32, d: u32, h: u32) -> BackupMetadata {
+        BackupMetadata {
+            id: id.to_string(),
+            created_at: Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap(),
+            description: String::new(),
+            snippet_count: 0,
+            repository_count: 0,
+            size: 0,
+            compressed: false,
+            archive_checksum: None,
+            encryption: BackupEncryption::None,
+        }
+    }
+    
+    #[test]
+    fn keeps_last_n() {
+        let backups = vec![
+            backup("a", 2024, 1, 1, 0),
+            backup("b", 2024, 1, 2, 0),
+            backup("c", 2024, 1, 3, 0),
+        ];
+        let keep = retained_backups(&backups, 2, 0, 0);
+        assert_eq!(keep, HashSet::from(["b".to_string(), "c".to_string()]));
+    }
+    
+    #[test]
+    fn always_keeps_newest() {
+        let backups = vec![backup("a", 2024, 1, 1, 0), backup("b", 2024, 1, 2, 0)];
+        assert_eq!(retained_backups(&backups, 0, 0, 0), HashSet::from(["b".to_string()]));
+    }
+    
+    #[test]
+    fn keeps_newest_per_day_and_week() {
+        let backups = vec![
+            // Monday and Tuesday of one ISO week
+            backup("mon-early", 2024, 1, 1, 8),
+            backup("mon-late", 2024, 1, 1, 20),
+            backup("tue", 2024, 1, 2, 9),
+            // Following Monday and Wednesday
+            backup("next-mon", 2024, 1, 8, 9),
+            backup("next-wed", 2024, 1, 10, 9),
+        ];
+        
+        let daily = retained_backups(&backups, 1, 3, 0);
+        assert_eq!(daily, HashSet::from(["next-wed".to_string(), "next-mon".to_string(), "tue".to_string()]));
+        
+        let weekly = retained_backups(&backups, 1, 0, 2);
+        assert_eq!(weekly, HashSet::from(["next-wed".to_string(), "tue".to_string()]));
+        
+        let daily = retained_backups(&backups, 1, 5, 0);
+        assert!(daily.contains("mon-late") && !daily.contains("mon-early"));
+    }
+}
@@ -0,0 +1,343 @@
+//! Copying snapshots off the machine
+//!
+//! A remote gets a snapshot's directory as it is: rsync copies it under its
+//! destination, and S3-compatible stores and WebDAV servers get each file
+//! under `<prefix>/<backup id>/`. Every copy is checked once it's made;
+//! rsync compares checksums, and the HTTP targets compare each file's size.
+//! Pocket never deletes remote copies, so how long they're kept is up to the
+//! remote, like an S3 lifecycle rule.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use anyhow::{Result, Context, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+
+use super::bytes_hash;
+use crate::utils::http;
+
+/// Most time a single upload or check gets
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Somewhere backups are copied to, keyed by name under `backup.remotes`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum RemoteTarget {
+    Rsync(RsyncTarget),
+    S3(S3Target),
+    Webdav(WebdavTarget),
+}
+
+/// A directory rsync can reach, usually over ssh
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RsyncTarget {
+    /// Where snapshots go, like `me@nas:backups/pocket`
+    pub destination: String,
+}
+
+/// A bucket in an S3-compatible object store
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct S3Target {
+    /// The store's address, like `https://s3.eu-west-1.amazonaws.com`
+    pub endpoint: String,
+    
+    pub bucket: String,
+    
+    /// Key prefix snapshots go under
+    #[serde(default)]
+    pub prefix: String,
+    
+    #[serde(default = "default_region")]
+    pub region: String,
+    
+    /// Environment variable holding the access key ID
+    #[serde(default = "default_access_key_env")]
+    pub access_key_env: String,
+    
+    /// Environment variable holding the secret access key
+    #[serde(default = "default_secret_key_env")]
+    pub secret_key_env: String,
+}
+
+/// A collection on a WebDAV server, like a Nextcloud folder
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebdavTarget {
+    /// The collection snapshots go in
+    pub url: String,
+    
+    #[serde(default)]
+    pub username: Option<String>,
+    
+    /// Environment variable holding the password
+    #[serde(default)]
+    pub password_env: Option<String>,
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_access_key_env() -> String {
+    "AWS_ACCESS_KEY_ID".to_string()
+}
+
+fn default_secret_key_env() -> String {
+    "AWS_SECRET_ACCESS_KEY".to_string()
+}
+
+impl RemoteTarget {
+    /// Copies the snapshot in `backup_dir` and checks the copy
+    pub fn push(&self, backup_id: &str, backup_dir: &Path) -> Result<()> {
+        match self {
+            RemoteTarget::Rsync(target) => target.push(backup_dir),
+            RemoteTarget::S3(target) => target.push(backup_id, backup_dir),
+            RemoteTarget::Webdav(target) => target.push(backup_id, backup_dir),
+        }
+    }
+    
+    /// Where the remote is, for `backup status`
+    pub fn describe(&self) -> String {
+        match self {
+            RemoteTarget::Rsync(target) => format!("rsync to {}", target.destination),
+            RemoteTarget::S3(target) => format!("S3 bucket {} at {}", target.bucket, target.endpoint),
+            RemoteTarget::Webdav(target) => format!("WebDAV at {}", target.url),
+        }
+    }
+}
+
+impl RsyncTarget {
+    fn push(&self, backup_dir: &Path) -> Result<()> {
+        // Without a trailing slash, rsync copies the directory itself
+        let status = Command::new("rsync")
+            .arg("--archive")
+            .arg(backup_dir)
+            .arg(&self.destination)
+            .status()
+            .context("Failed to run rsync (is it installed?)")?;
+        if !status.success() {
+            anyhow::bail!("rsync exited with {}", status);
+        }
+        
+        let output = Command::new("rsync")
+            .args(["--archive", "--checksum", "--dry-run", "--itemize-changes"])
+            .arg(backup_dir)
+            .arg(&self.destination)
+            .output()
+            .context("Failed to run rsync (is it installed?)")?;
+        let changes = String::from_utf8_lossy(&output.stdout);
+        if !output.status.success() || !changes.trim().is_empty() {
+            anyhow::bail!("the copy doesn't match the snapshot: {}", changes.trim());
+        }
+        Ok(())
+    }
+}
+
+impl S3Target {
+    fn push(&self, backup_id: &str, backup_dir: &Path) -> Result<()> {
+        let credentials = (env_var(&self.access_key_env)?, env_var(&self.secret_key_env)?);
+        let files = snapshot_files(backup_dir)?;
+        for (name, path) in &files {
+            let body = std::fs::read(path)?;
+            self.send(&credentials, "PUT", &self.key(backup_id, name), &body)?;
+        }
+        for (name, path) in &files {
+            let response = self.send(&credentials, "HEAD", &self.key(backup_id, name), &[])?;
+            check_size(&response, name, path)?;
+        }
+        Ok(())
+    }
+    
+    fn key(&self, backup_id: &str, name: &str) -> String {
+        let prefix = self.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            format!("{}/{}", backup_id, name)
+        } else {
+            format!("{}/{}/{}", prefix, backup_id, name)
+        }
+    }
+    
+    /// Sends a request signed with AWS Signature Version 4, using path-style
+    /// addressing so any S3-compatible store understands it
+    fn send(&self, (access_key, secret_key): &(String, String), method: &str, key: &str, body: &[u8]) -> Result<http::Response> {
+        let path = uri_encode(&format!("/{}/{}", self.bucket, key));
+        let url = format!("{}{}", self.endpoint.trim_end_matches('/'), path);
+        let parsed = url::Url::parse(&url).map_err(|e| anyhow!("Invalid S3 endpoint {}: {}", self.endpoint, e))?;
+        let host = match parsed.port() {
+            Some(port) => format!("{}:{}", parsed.host_str().unwrap_or_default(), port),
+            None => parsed.host_str().unwrap_or_default().to_string(),
+        };
+        
+        let time = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = bytes_hash(body);
+        let headers = [
+            ("host".to_string(), host),
+            ("x-amz-content-sha256".to_string(), payload_hash.clone()),
+            ("x-amz-date".to_string(), time.clone()),
+        ];
+        let canonical = canonical_request(method, &path, &headers, &payload_hash);
+        let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}/{}/s3/aws4_request, SignedHeaders={}, Signature={}",
+            access_key, &time[..8], self.region, signed_headers, signature(secret_key, &time, &self.region, &canonical)
+        );
+        
+        // The client sets Host itself, to the same value
+        let mut request_headers: Vec<(String, String)> = headers.into_iter().skip(1).collect();
+        request_headers.push(("Authorization".to_string(), authorization));
+        let body = (method == "PUT").then_some(("application/octet-stream", body));
+        http::send(method, &url, &request_headers, body, REMOTE_TIMEOUT)
+    }
+}
+
+impl WebdavTarget {
+    fn push(&self, backup_id: &str, backup_dir: &Path) -> Result<()> {
+        let base = format!("{}/{}", self.url.trim_end_matches('/'), uri_encode(backup_id));
+        let mut headers = Vec::new();
+        if let Some(username) = &self.username {
+            let password = self.password_env.as_deref().map(env_var).transpose()?.unwrap_or_default();
+            headers.push(("Authorization".to_string(), format!("Basic {}", STANDARD.encode(format!("{}:{}", username, password)))));
+        }
+        
+        // Collections have to exist before anything goes in them. Creating
+        // one that already exists fails, which the uploads will show if it
+        // matters
+        let mut collections = vec![base.clone()];
+        for entry in walkdir::WalkDir::new(backup_dir).min_depth(1) {
+            let entry = entry?;
+            if entry.file_type().is_dir() {
+                collections.push(format!("{}/{}", base, uri_encode(&relative_name(backup_dir, entry.path())?)));
+            }
+        }
+        for collection in collections {
+            if let Err(e) = http::send("MKCOL", &format!("{}/", collection), &headers, None, REMOTE_TIMEOUT) {
+                log::debug!("MKCOL {}: {}", collection, e);
+            }
+        }
+        
+        let files = snapshot_files(backup_dir)?;
+        for (name, path) in &files {
+            let body = std::fs::read(path)?;
+            http::send("PUT", &format!("{}/{}", base, uri_encode(name)), &headers, Some(("application/octet-stream", &body)), REMOTE_TIMEOUT)?;
+        }
+        for (name, path) in &files {
+            let response = http::send("HEAD", &format!("{}/{}", base, uri_encode(name)), &headers, None, REMOTE_TIMEOUT)?;
+            check_size(&response, name, path)?;
+        }
+        Ok(())
+    }
+}
+
+fn env_var(name: &str) -> Result<String> {
+    std::env::var(name).ok()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| anyhow!("${} isn't set", name))
+}
+
+/// Every file in a snapshot, by its `/`-separated path inside it
+fn snapshot_files(backup_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(backup_dir).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push((relative_name(backup_dir, entry.path())?, entry.path().to_path_buf()));
+        }
+    }
+    Ok(files)
+}
+
+fn relative_name(base: &Path, path: &Path) -> Result<String> {
+    Ok(path.strip_prefix(base)?
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// Fails unless the remote has as many bytes of `name` as there are locally
+fn check_size(response: &http::Response, name: &str, path: &Path) -> Result<()> {
+    let expected = std::fs::metadata(path)?.len();
+    match response.header("Content-Length").and_then(|length| length.parse::<u64>().ok()) {
+        Some(length) if length == expected => Ok(()),
+        Some(length) => Err(anyhow!("{} has {} bytes on the remote instead of {}", name, length, expected)),
+        None => Err(anyhow!("the remote didn't say how big {} is", name)),
+    }
+}
+
+/// Percent-encodes everything in a path but unreserved characters and `/`
+fn uri_encode(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// The canonical request SigV4 signs, for requests without a query string.
+/// `headers` are lowercase and sorted
+fn canonical_request(method: &str, path: &str, headers: &[(String, String)], payload_hash: &str) -> String {
+    let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+    let signed_headers = headers.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(";");
+    format!("{}\n{}\n\n{}\n{}\n{}", method, path, canonical_headers, signed_headers, payload_hash)
+}
+
+/// The SigV4 signature of `canonical_request` made at `time`, like
+/// `20130524T000000Z`
+fn signature(secret_key: &str, time: &str, region: &str, canonical_request: &str) -> String {
+    let date = &time[..8];
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}/{}/s3/aws4_request\n{}",
+        time, date, region, bytes_hash(canonical_request.as_bytes())
+    );
+    let mut key = hmac(format!("AWS4{}", secret_key).as_bytes(), date);
+    for part in [region, "s3", "aws4_request"] {
+        key = hmac(&key, part);
+    }
+    hmac(&key, &string_to_sign).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn signs_like_the_aws_example() {
+        // The GET Object example from the SigV4 documentation for S3
+        let empty = bytes_hash(b"");
+        let headers = [
+            ("host".to_string(), "examplebucket.s3.amazonaws.com".to_string()),
+            ("range".to_string(), "bytes=0-9".to_string()),
+            ("x-amz-content-sha256".to_string(), empty.clone()),
+            ("x-amz-date".to_string(), "20130524T000000Z".to_string()),
+        ];
+        let canonical = canonical_request("GET", "/test.txt", &headers, &empty);
+        assert_eq!(
+            signature("wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY", "20130524T000000Z", "us-east-1", &canonical),
+            "f0e8bdb87c964420e857bd35b5d6ed310bd44f0170aba48dd91039c6036bdb41"
+        );
+    }
+    
+    #[test]
+    fn reads_remotes_from_card_options() {
+        let remote: RemoteTarget = serde_json::from_value(serde_json::json!({
+            "type": "s3",
+            "endpoint": "http://localhost:9000",
+            "bucket": "backups",
+        })).unwrap();
+        let RemoteTarget::S3(s3) = remote else { panic!("expected an S3 remote") };
+        assert_eq!(s3.region, "us-east-1");
+        assert_eq!(s3.key("backup_1", "data.tar.gz"), "backup_1/data.tar.gz");
+        assert_eq!(uri_encode("/my bucket/a+b"), "/my%20bucket/a%2Bb");
+    }
+}
@@ -1,11 +1,24 @@
-use crate::cards::{Card, CardConfig, CardCommand};
+use crate::cards::{events, Card, CardConfig, CardCommand, Event};
+use crate::models::{Backpack, ContentType, Entry};
+use crate::storage::StorageManager;
 use crate::utils;
+use crate::i18n::tr;
 use anyhow::{Result, Context, anyhow};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::io::{Read, Write};
 use std::process::Command;
 
+/// Backpack holding a copy of every blended hook, so hooks travel with
+/// backups and sync and `pocket blend restore` can rebuild them
+pub const HOOKS_BACKPACK: &str = "hooks";
+
+/// Entry metadata naming the hook an entry stores
+const HOOK_NAME_KEY: &str = "blend_hook";
+
+/// Entry metadata recording whether the hook is executable ("true"/"false")
+const HOOK_EXECUTABLE_KEY: &str = "blend_executable";
+
 /// Card for shell integration via the blend command
 pub struct BlendCard {
     /// Name of the card
@@ -26,6 +39,7 @@ pub struct BlendCard {
 
 /// Configuration for the blend card
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct BlendCardConfig {
     /// Path to the hook directory
     pub hook_dir: String,
@@ -36,13 +50,164 @@ pub struct BlendCardConfig {
 
 impl Default for BlendCardConfig {
     fn default() -> Self {
+        // Hooks belong to the profile in use
+        let pocket_dir = crate::storage::pocket_dir()
+            .unwrap_or_else(|_| PathBuf::from("~/.pocket"));
         Self {
-            hook_dir: "~/.pocket/hooks".to_string(),
-            bin_dir: "~/.pocket/bin".to_string(),
+            hook_dir: pocket_dir.join("hooks").display().to_string(),
+            bin_dir: pocket_dir.join("bin").display().to_string(),
         }
     }
 }
 
+/// Shells whose config blend knows how to write
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    /// Anything else gets plain POSIX sh syntax in ~/.profile
+    Posix,
+}
+
+impl Shell {
+    /// Detects the user's shell from `$SHELL`
+    pub fn detect() -> Self {
+        Self::from_path(&std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string()))
+    }
+    
+    /// Works out the shell from its path, e.g. `/usr/bin/fish`
+    pub fn from_path(shell: &str) -> Self {
+        let name = Path::new(shell).file_name().and_then(|n| n.to_str()).unwrap_or(shell);
+        if name.contains("zsh") {
+            Shell::Zsh
+        } else if name.contains("fish") {
+            Shell::Fish
+        } else if name.contains("bash") {
+            Shell::Bash
+        } else {
+            Shell::Posix
+        }
+    }
+}
+
+/// Quotes a string for POSIX shells so it reaches the command as one argument, verbatim
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quotes a string for fish, which allows escaping inside single quotes
+fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// The entry in the hooks backpack storing the named hook
+fn find_stored_hook(storage: &StorageManager, hook_name: &str) -> Result<Option<Entry>> {
+    Ok(storage.list_entries(Some(HOOKS_BACKPACK))?
+        .into_iter()
+        .find(|entry| entry.get_metadata(HOOK_NAME_KEY) == Some(hook_name)))
+}
+
+/// Lines that put the bin directory on PATH, for POSIX shells and for fish
+fn bin_path_lines(bin_dir: &Path) -> [String; 2] {
+    [
+        format!("export PATH=\"{}:$PATH\"", bin_dir.display()),
+        format!("set -gx PATH {} $PATH", fish_quote(&bin_dir.display().to_string())),
+    ]
+}
+
+/// The `@name` wrapper placed in the bin directory. It's plain sh so it runs
+/// from any shell, and `exec ... "$@"` hands every argument over untouched
+pub fn wrapper_script(hook_name: &str, hook_path: &Path) -> String {
+    format!(
+        "#!/bin/sh\n\
+        # Wrapper for Pocket hook: {}\n\
+        exec {} \"$@\"\n",
+        hook_name,
+        shell_quote(&hook_path.display().to_string())
+    )
+}
+
+/// A shell function that makes `@name args...` call the hook's wrapper, for
+/// shells that allow `@` in function names. Plain sh doesn't, so it relies
+/// on the bin directory being on PATH instead
+pub fn shell_function(shell: Shell, hook_name: &str, wrapper_path: &Path) -> Option<String> {
+    let valid_name = !hook_name.is_empty()
+        && hook_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+    if !valid_name {
+        return None;
+    }
+    
+    let wrapper = wrapper_path.display().to_string();
+    match shell {
+        Shell::Bash | Shell::Zsh => Some(format!("@{}() {{ {} \"$@\"; }}", hook_name, shell_quote(&wrapper))),
+        Shell::Fish => Some(format!("function @{}\n    {} $argv\nend", hook_name, fish_quote(&wrapper))),
+        Shell::Posix => None,
+    }
+}
+
+/// Removes every `# comment` + `body` block that `append_to_shell_config`
+/// wrote, along with the blank line before it. Anything the user wrote is
+/// left alone, even if it looks similar. Returns the new content and how many
+/// blocks were removed
+pub fn strip_blocks(content: &str, blocks: &[(String, String)]) -> (String, usize) {
+    let mut content = content.to_string();
+    let mut removed = 0;
+    for (comment, body) in blocks {
+        let block = format!("# {}\n{}\n", comment, body);
+        let mut from = 0;
+        while let Some(offset) = content[from..].find(&block) {
+            let start = from + offset;
+            
+            // Only whole lines count
+            if start > 0 && !content[..start].ends_with('\n') {
+                from = start + 1;
+                continue;
+            }
+            let end = start + block.len();
+            let start = if content[..start].ends_with("\n\n") || &content[..start] == "\n" { start - 1 } else { start };
+            content.replace_range(start..end, "");
+            from = start;
+            removed += 1;
+        }
+    }
+    (content, removed)
+}
+
+/// A hook in the hook directory
+#[derive(Debug, Clone)]
+pub struct Hook {
+    pub name: String,
+    pub path: PathBuf,
+    /// Runs as `@name` rather than being sourced into the shell
+    pub executable: bool,
+}
+
+/// The hooks installed with `config`, by name
+pub fn installed_hooks(config: &BlendCardConfig) -> Result<Vec<Hook>> {
+    let hook_dir = utils::expand_path(&config.hook_dir)?;
+    if !hook_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let bin_dir = utils::expand_path(&config.bin_dir)?;
+    
+    let mut hooks = Vec::new();
+    for entry in fs::read_dir(hook_dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("sh") {
+            let name = path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+            // Executable hooks have a wrapper in the bin directory
+            let executable = bin_dir.join(format!("@{}", name)).exists();
+            hooks.push(Hook { name, path, executable });
+        }
+    }
+    hooks.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(hooks)
+}
+
 impl BlendCard {
     /// Creates a new blend card
     pub fn new(data_dir: impl AsRef<std::path::Path>) -> Self {
@@ -57,15 +222,6 @@ impl BlendCard {
     
     /// Add a shell script as a hook
     pub fn add_hook(&self, script_path: &str, executable: bool) -> Result<()> {
-        // Expand the hook directory path
-        let hook_dir = utils::expand_path(&self.config.hook_dir)?;
-        
-        // Create hook directory if it doesn't exist
-        if !hook_dir.exists() {
-            fs::create_dir_all(&hook_dir)
-                .with_context(|| format!("Failed to create hook directory at {}", hook_dir.display()))?;
-        }
-        
         // Read the script content
         let script_content = fs::read_to_string(script_path)
             .with_context(|| format!("Failed to read script at {}", script_path))?;
@@ -76,6 +232,32 @@ impl BlendCard {
             .and_then(|stem| stem.to_str())
             .ok_or_else(|| anyhow!("Invalid script filename"))?;
         
+        self.install_hook(hook_name, &script_content, executable)?;
+        self.store_hook(hook_name, &script_content, executable)?;
+        
+        if executable {
+            println!("Successfully added executable hook '{}' from {}", hook_name, script_path.display());
+            println!("You can run it with '@{}' or 'pocket blend run {}'", hook_name, hook_name);
+        } else {
+            println!("Successfully added hook '{}' from {}", hook_name, script_path.display());
+            println!("Restart your shell or run 'source {}' to apply changes", self.get_shell_config_path()?.display());
+        }
+        
+        Ok(())
+    }
+    
+    /// Writes a hook's script and wires it into the shell: a wrapper and
+    /// `@name` function for executable hooks, a `source` line otherwise
+    fn install_hook(&self, hook_name: &str, script_content: &str, executable: bool) -> Result<()> {
+        // Expand the hook directory path
+        let hook_dir = utils::expand_path(&self.config.hook_dir)?;
+        
+        // Create hook directory if it doesn't exist
+        if !hook_dir.exists() {
+            fs::create_dir_all(&hook_dir)
+                .with_context(|| format!("Failed to create hook directory at {}", hook_dir.display()))?;
+        }
+        
         // Path to the copied hook script
         let hook_script_path = hook_dir.join(format!("{}.sh", hook_name));
         
@@ -98,20 +280,14 @@ impl BlendCard {
             if !bin_dir.exists() {
                 fs::create_dir_all(&bin_dir)
                     .with_context(|| format!("Failed to create bin directory at {}", bin_dir.display()))?;
-                
-                // Add the bin directory to PATH
-                self.add_bin_to_path(&bin_dir)?;
             }
             
+            // Add the bin directory to PATH (a no-op if it's already there)
+            self.add_bin_to_path(&bin_dir)?;
+            
             // Create a wrapper script that calls the hook
             let wrapper_path = bin_dir.join(format!("@{}", hook_name));
-            let wrapper_content = format!(
-                "#!/bin/bash\n\
-                # Wrapper for Pocket hook: {}\n\
-                exec \"{}\" \"$@\"\n",
-                hook_name,
-                hook_script_path.display()
-            );
+            let wrapper_content = wrapper_script(hook_name, &hook_script_path);
             
             fs::write(&wrapper_path, wrapper_content)
                 .with_context(|| format!("Failed to write wrapper script to {}", wrapper_path.display()))?;
@@ -125,64 +301,100 @@ impl BlendCard {
                 fs::set_permissions(&wrapper_path, perms)?;
             }
             
-            println!("Successfully added executable hook '{}' from {}", hook_name, script_path.display());
-            println!("You can run it with '@{}' or 'pocket blend run {}'", hook_name, hook_name);
+            // Define @name in the shell too, so it works before PATH is reloaded
+            if let Some(function) = shell_function(Shell::detect(), hook_name, &wrapper_path) {
+                self.append_to_shell_config(&format!("Pocket CLI hook: {}", hook_name), &function)?;
+            }
         } else {
             // Add the hook to shell config
             self.add_hook_to_shell_config(hook_name, &hook_script_path)?;
-            println!("Successfully added hook '{}' from {}", hook_name, script_path.display());
-            println!("Restart your shell or run 'source {}' to apply changes", self.get_shell_config_path()?.display());
         }
         
         Ok(())
     }
     
-    /// List all installed hooks
-    pub fn list_hooks(&self) -> Result<()> {
-        // Expand the hook directory path
-        let hook_dir = utils::expand_path(&self.config.hook_dir)?;
-        
-        if !hook_dir.exists() {
-            println!("No hooks installed yet");
-            return Ok(());
+    /// Saves a copy of a hook in the hooks backpack, replacing any earlier copy
+    fn store_hook(&self, hook_name: &str, script_content: &str, executable: bool) -> Result<()> {
+        let storage = StorageManager::new()?;
+        if storage.load_backpack(HOOKS_BACKPACK).is_err() {
+            storage.create_backpack(&Backpack {
+                name: HOOKS_BACKPACK.to_string(),
+                description: Some("Shell hooks added with pocket blend".to_string()),
+                created_at: chrono::Utc::now(),
+            })?;
+            events::emit(Event::BackpackCreated { name: HOOKS_BACKPACK.to_string() });
         }
         
-        let mut hooks = Vec::new();
-        
-        // Read the hook directory
-        for entry in fs::read_dir(hook_dir)? {
-            let entry = entry?;
-            let path = entry.path();
+        let existing = find_stored_hook(&storage, hook_name)?;
+        let is_update = existing.is_some();
+        let mut entry = existing.unwrap_or_else(|| {
+            let mut entry = Entry::new(hook_name.to_string(), ContentType::Script, None, vec!["blend".to_string()]);
+            entry.language = Some("shell".to_string());
+            entry.add_metadata(HOOK_NAME_KEY, hook_name);
+            entry
+        });
+        entry.touch();
+        entry.add_metadata(HOOK_EXECUTABLE_KEY, if executable { "true" } else { "false" });
+        storage.save_entry(&entry, script_content, Some(HOOKS_BACKPACK))?;
+        
+        let backpack = Some(HOOKS_BACKPACK.to_string());
+        events::emit(if is_update {
+            Event::EntryUpdated { id: entry.id, backpack }
+        } else {
+            Event::EntryAdded { id: entry.id, backpack }
+        });
+        Ok(())
+    }
+    
+    /// Deletes the stored copy of a hook, if there is one
+    fn forget_hook(&self, hook_name: &str) -> Result<()> {
+        let storage = StorageManager::new()?;
+        if let Some(entry) = find_stored_hook(&storage, hook_name)? {
+            storage.remove_entry(&entry.id, Some(HOOKS_BACKPACK))?;
+            events::emit(Event::EntryRemoved { id: entry.id, backpack: Some(HOOKS_BACKPACK.to_string()) });
+        }
+        Ok(())
+    }
+    
+    /// Reinstalls every hook stored in the hooks backpack, e.g. on a new
+    /// machine after restoring a backup. Returns how many were restored
+    pub fn restore_hooks(&self) -> Result<usize> {
+        let storage = StorageManager::new()?;
+        let mut restored = 0;
+        
+        for entry in storage.list_entries(Some(HOOKS_BACKPACK))? {
+            let Some(hook_name) = entry.get_metadata(HOOK_NAME_KEY) else {
+                continue;
+            };
+            let executable = entry.get_metadata(HOOK_EXECUTABLE_KEY) == Some("true");
+            let (_, content) = storage.load_entry(&entry.id, Some(HOOKS_BACKPACK))?;
             
-            if path.is_file() && path.extension().and_then(|e| e.to_str()) == Some("sh") {
-                let name = path.file_stem()
-                    .and_then(|stem| stem.to_str())
-                    .unwrap_or("unknown")
-                    .to_string();
-                
-                // Check if it's an executable hook
-                let bin_dir = utils::expand_path(&self.config.bin_dir)?;
-                let wrapper_path = bin_dir.join(format!("@{}", name));
-                let is_executable = wrapper_path.exists();
-                
-                hooks.push((name, path, is_executable));
-            }
+            self.install_hook(hook_name, &content, executable)
+                .with_context(|| format!("Failed to restore hook '{}'", hook_name))?;
+            println!("Restored {} hook '{}'", if executable { "executable" } else { "shell" }, hook_name);
+            restored += 1;
         }
         
+        Ok(restored)
+    }
+    
+    /// List all installed hooks
+    pub fn list_hooks(&self) -> Result<()> {
+        let hooks = installed_hooks(&self.config)?;
         if hooks.is_empty() {
-            println!("No hooks installed yet");
+            println!("{}", tr!("No hooks installed yet"));
             return Ok(());
         }
         
-        println!("Installed hooks:");
-        for (name, path, is_executable) in hooks {
-            let hook_type = if is_executable {
+        println!("{}", tr!("Installed hooks:"));
+        for hook in hooks {
+            let hook_type = if hook.executable {
                 "[executable]"
             } else {
                 "[shell extension]"
             };
             
-            println!("  @{} ({}) {}", name, path.display(), hook_type);
+            println!("  @{} ({}) {}", hook.name, hook.path.display(), hook_type);
         }
         
         Ok(())
@@ -202,10 +414,10 @@ impl BlendCard {
         }
         
         // Get the editor from environment
-        let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+        let editor = std::env::var("EDITOR").unwrap_or_else(|_| utils::default_editor().to_string());
         
         // Open the hook script in the editor
-        let status = Command::new(&editor)
+        let status = utils::editor_command(&editor)?
             .arg(&hook_path)
             .status()
             .with_context(|| format!("Failed to open editor {}", editor))?;
@@ -214,6 +426,10 @@ impl BlendCard {
             return Err(anyhow!("Editor exited with non-zero status"));
         }
         
+        // Keep the stored copy in step
+        let executable = utils::expand_path(&self.config.bin_dir)?.join(format!("@{}", hook_name)).exists();
+        self.store_hook(hook_name, &fs::read_to_string(&hook_path)?, executable)?;
+        
         println!("Hook '{}' edited successfully", hook_name);
         Ok(())
     }
@@ -242,11 +458,17 @@ impl BlendCard {
             fs::set_permissions(&hook_path, perms)?;
         }
         
-        // Run the hook script with arguments
-        let mut command = Command::new(&hook_path);
-        if !args.is_empty() {
-            command.args(args);
-        }
+        // Run the hook script with arguments, using sh for scripts without a
+        // shebang, and always on Windows, which can't run a script directly
+        let has_shebang = fs::read(&hook_path)?.starts_with(b"#!");
+        let mut command = if has_shebang && cfg!(unix) {
+            Command::new(&hook_path)
+        } else {
+            let mut command = Command::new("sh");
+            command.arg(&hook_path);
+            command
+        };
+        command.args(args);
         
         let status = command
             .status()
@@ -259,33 +481,182 @@ impl BlendCard {
         Ok(())
     }
     
+    /// Removes a hook: its script, its `@name` wrapper and whatever blend
+    /// added to the shell config for it
+    pub fn remove_hook(&self, hook_name: &str) -> Result<()> {
+        let hook_name = hook_name.trim_start_matches('@');
+        let hook_dir = utils::expand_path(&self.config.hook_dir)?;
+        let hook_path = hook_dir.join(format!("{}.sh", hook_name));
+        let wrapper_path = utils::expand_path(&self.config.bin_dir)?.join(format!("@{}", hook_name));
+        
+        if !hook_path.exists() && !wrapper_path.exists() {
+            return Err(anyhow!("Hook '{}' not found", hook_name));
+        }
+        
+        // Clean the shell config first, so a failure leaves the hook usable
+        self.strip_from_shell_config(&self.hook_blocks(hook_name, &hook_path, &wrapper_path))?;
+        
+        for path in [&hook_path, &wrapper_path] {
+            if path.exists() {
+                fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+        self.forget_hook(hook_name)?;
+        
+        println!("Removed hook '{}'", hook_name);
+        Ok(())
+    }
+    
+    /// Removes every hook, the bin directory's PATH entry and the
+    /// directories themselves if nothing else is in them. Stored copies are
+    /// kept, so `restore_hooks` can bring them back
+    pub fn uninstall_all(&self) -> Result<()> {
+        let hook_dir = utils::expand_path(&self.config.hook_dir)?;
+        let bin_dir = utils::expand_path(&self.config.bin_dir)?;
+        
+        // Every hook with a script or a wrapper
+        let mut names = std::collections::BTreeSet::new();
+        for (dir, prefix, suffix) in [(&hook_dir, "", ".sh"), (&bin_dir, "@", "")] {
+            if !dir.exists() {
+                continue;
+            }
+            for entry in fs::read_dir(dir)? {
+                let file_name = entry?.file_name().to_string_lossy().to_string();
+                if let Some(name) = file_name.strip_prefix(prefix).and_then(|n| n.strip_suffix(suffix)) {
+                    if !name.is_empty() {
+                        names.insert(name.to_string());
+                    }
+                }
+            }
+        }
+        
+        // One pass over the shell config, so there's only one backup
+        let mut blocks = Vec::new();
+        for name in &names {
+            blocks.extend(self.hook_blocks(name, &hook_dir.join(format!("{}.sh", name)), &bin_dir.join(format!("@{}", name))));
+        }
+        for path_line in bin_path_lines(&bin_dir) {
+            blocks.push(("Pocket hook bin directory".to_string(), path_line));
+        }
+        self.strip_from_shell_config(&blocks)?;
+        
+        for name in &names {
+            for path in [hook_dir.join(format!("{}.sh", name)), bin_dir.join(format!("@{}", name))] {
+                if path.exists() {
+                    fs::remove_file(&path)
+                        .with_context(|| format!("Failed to remove {}", path.display()))?;
+                }
+            }
+        }
+        for dir in [&hook_dir, &bin_dir] {
+            // Only succeeds when empty, which is exactly when it should go
+            let _ = fs::remove_dir(dir);
+        }
+        
+        println!("Removed {} hook(s) from this machine; 'pocket blend restore' brings them back", names.len());
+        Ok(())
+    }
+    
+    /// Everything blend may have written to a shell config for a hook,
+    /// in any of the shells it supports
+    fn hook_blocks(&self, hook_name: &str, hook_path: &Path, wrapper_path: &Path) -> Vec<(String, String)> {
+        let comment = format!("Pocket CLI hook: {}", hook_name);
+        let mut blocks = vec![(comment.clone(), format!("source \"{}\"", hook_path.display()))];
+        for shell in [Shell::Bash, Shell::Fish] {
+            if let Some(function) = shell_function(shell, hook_name, wrapper_path) {
+                blocks.push((comment.clone(), function));
+            }
+        }
+        blocks
+    }
+    
+    /// Strips blend's blocks from the shell config, backing it up first.
+    /// Leaves the file untouched if none of them are there
+    fn strip_from_shell_config(&self, blocks: &[(String, String)]) -> Result<()> {
+        let config_path = self.get_shell_config_path()?;
+        if !config_path.exists() {
+            return Ok(());
+        }
+        
+        let content = fs::read_to_string(&config_path)?;
+        let (stripped, removed) = strip_blocks(&content, blocks);
+        if removed == 0 {
+            return Ok(());
+        }
+        
+        // Never overwrite an earlier backup, even one from the same second
+        let file_name = config_path.file_name().and_then(|n| n.to_str()).unwrap_or("shellrc");
+        let stamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+        let mut backup_path = config_path.with_file_name(format!("{}.pocket-{}.bak", file_name, stamp));
+        let mut counter = 1;
+        while backup_path.exists() {
+            backup_path = config_path.with_file_name(format!("{}.pocket-{}-{}.bak", file_name, stamp, counter));
+            counter += 1;
+        }
+        fs::copy(&config_path, &backup_path)
+            .with_context(|| format!("Failed to back up {}", config_path.display()))?;
+        utils::atomic::write(&config_path, stripped)
+            .with_context(|| format!("Failed to update {}", config_path.display()))?;
+        
+        println!("Cleaned {} (backup at {})", config_path.display(), backup_path.display());
+        Ok(())
+    }
+    
     /// Get the user's shell config file path
     fn get_shell_config_path(&self) -> Result<PathBuf> {
-        // Detect the shell
-        let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
         let home = utils::expand_path("~")?;
         
         // Choose the config file based on the shell
-        let config_path = if shell.contains("zsh") {
-            home.join(".zshrc")
-        } else if shell.contains("bash") {
-            // Check if .bash_profile exists, otherwise use .bashrc
-            let bash_profile = home.join(".bash_profile");
-            if bash_profile.exists() {
-                bash_profile
-            } else {
-                home.join(".bashrc")
-            }
-        } else {
+        let config_path = match Shell::detect() {
+            Shell::Zsh => home.join(".zshrc"),
+            Shell::Fish => home.join(".config").join("fish").join("config.fish"),
+            Shell::Bash => {
+                // Check if .bash_profile exists, otherwise use .bashrc
+                let bash_profile = home.join(".bash_profile");
+                if bash_profile.exists() {
+                    bash_profile
+                } else {
+                    home.join(".bashrc")
+                }
+            },
             // Default to .profile
-            home.join(".profile")
+            Shell::Posix => home.join(".profile"),
         };
         
         Ok(config_path)
     }
     
+    /// Appends `text` under a `# comment` line to the shell config, unless it's
+    /// already there. Returns whether anything was written
+    fn append_to_shell_config(&self, comment: &str, text: &str) -> Result<bool> {
+        let config_path = self.get_shell_config_path()?;
+        
+        // Read the current shell config
+        let config_content = if config_path.exists() {
+            fs::read_to_string(&config_path)?
+        } else {
+            String::new()
+        };
+        if config_content.contains(text) {
+            return Ok(false);
+        }
+        
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&config_path)?;
+        
+        writeln!(file, "\n# {}", comment)?;
+        writeln!(file, "{}", text)?;
+        Ok(true)
+    }
+    
     /// Add hook to shell config
-    fn add_hook_to_shell_config(&self, hook_name: &str, hook_path: &PathBuf) -> Result<()> {
+    fn add_hook_to_shell_config(&self, hook_name: &str, hook_path: &Path) -> Result<()> {
         let config_path = self.get_shell_config_path()?;
         
         // Read the current shell config
@@ -317,33 +688,16 @@ impl BlendCard {
     }
     
     /// Add bin directory to PATH
-    fn add_bin_to_path(&self, bin_dir: &PathBuf) -> Result<()> {
-        let config_path = self.get_shell_config_path()?;
-        
-        // Read the current shell config
-        let mut config_content = String::new();
-        if config_path.exists() {
-            let mut file = fs::File::open(&config_path)?;
-            file.read_to_string(&mut config_content)?;
-        }
+    fn add_bin_to_path(&self, bin_dir: &Path) -> Result<()> {
+        let [posix_line, fish_line] = bin_path_lines(bin_dir);
+        let path_line = match Shell::detect() {
+            Shell::Fish => fish_line,
+            _ => posix_line,
+        };
         
-        // Check if the PATH addition is already in the config
-        let path_line = format!("export PATH=\"{}:$PATH\"", bin_dir.display());
-        if config_content.contains(&path_line) {
-            return Ok(());
+        if self.append_to_shell_config("Pocket hook bin directory", &path_line)? {
+            println!("Added Pocket hook bin directory to your PATH");
         }
-        
-        // Add the bin directory to PATH
-        let mut file = fs::OpenOptions::new()
-            
-            .append(true)
-            .create(true)
-            .open(&config_path)?;
-        
-        writeln!(file, "\n# Pocket hook bin directory")?;
-        writeln!(file, "{}", path_line)?;
-        
-        println!("Added Pocket hook bin directory to your PATH");
         Ok(())
     }
 }
@@ -357,16 +711,15 @@ impl Card for BlendCard {
         &self.version
     }
     
-    fn _description(&self) -> &str {
+    fn description(&self) -> &str {
         &self.description
     }
     
-    fn _initialize(&mut self, config: &CardConfig) -> Result<()> {
-        // If there are options in the card config, try to parse them
+    fn initialize(&mut self, config: &CardConfig) -> Result<()> {
+        // If there are options in the card config, parse them
         if let Some(options_value) = config.options.get("blend") {
-            if let Ok(options) = serde_json::from_value::<BlendCardConfig>(options_value.clone()) {
-                self.config = options;
-            }
+            self.config = serde_json::from_value::<BlendCardConfig>(options_value.clone())
+                .map_err(|e| anyhow::anyhow!("Invalid blend card options: {}", e))?;
         }
         
         Ok(())
@@ -408,6 +761,28 @@ impl Card for BlendCard {
                 let hook_name = &args[0];
                 self.edit_hook(hook_name)?;
             }
+            "remove" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing hook name"));
+                }
+                
+                self.remove_hook(&args[0])?;
+            }
+            "restore" => {
+                let restored = self.restore_hooks()?;
+                if restored == 0 {
+                    println!("No hooks stored in the '{}' backpack", HOOKS_BACKPACK);
+                } else {
+                    println!("Restored {} hook(s). Restart your shell or run 'source {}' to use them", restored, self.get_shell_config_path()?.display());
+                }
+            }
+            "uninstall" => {
+                if !args.iter().any(|a| a == "--all") {
+                    return Err(anyhow!("This removes every hook; pass --all to confirm"));
+                }
+                
+                self.uninstall_all()?;
+            }
             "run" => {
                 if args.is_empty() {
                     return Err(anyhow!("Missing hook name"));
@@ -447,6 +822,21 @@ impl Card for BlendCard {
                 description: "Edit an existing hook".to_string(),
                 usage: "edit <hook_name>".to_string(),
             },
+            CardCommand {
+                name: "remove".to_string(),
+                description: "Remove a hook and its shell config lines".to_string(),
+                usage: "remove <hook_name>".to_string(),
+            },
+            CardCommand {
+                name: "restore".to_string(),
+                description: "Reinstall the hooks stored in the hooks backpack".to_string(),
+                usage: "restore".to_string(),
+            },
+            CardCommand {
+                name: "uninstall".to_string(),
+                description: "Remove every hook and clean up the shell config".to_string(),
+                usage: "uninstall --all".to_string(),
+            },
             CardCommand {
                 name: "run".to_string(),
                 description: "Run a hook command directly".to_string(),
@@ -458,4 +848,109 @@ impl Card for BlendCard {
     fn cleanup(&mut self) -> Result<()> {
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Arguments that tend to get mangled on the way through a shell
+    const TRICKY_ARGS: &[&str] = &["plain", "with space", "it's", "say \"hi\"", "$HOME", "*", "", "--flag", "back\\slash"];
+    
+    #[test]
+    fn detects_shells() {
+        assert_eq!(Shell::from_path("/bin/bash"), Shell::Bash);
+        assert_eq!(Shell::from_path("/usr/local/bin/zsh"), Shell::Zsh);
+        assert_eq!(Shell::from_path("/usr/bin/fish"), Shell::Fish);
+        assert_eq!(Shell::from_path("/bin/dash"), Shell::Posix);
+    }
+    
+    #[test]
+    fn quotes_for_sh_and_fish() {
+        assert_eq!(shell_quote("/a b/it's"), "'/a b/it'\\''s'");
+        assert_eq!(fish_quote("/a b/it's\\x"), "'/a b/it\\'s\\\\x'");
+    }
+    
+    #[test]
+    fn strips_only_blend_blocks() {
+        let blocks = vec![
+            ("Pocket CLI hook: a".to_string(), "source \"/h/a.sh\"".to_string()),
+            ("Pocket hook bin directory".to_string(), "export PATH=\"/b:$PATH\"".to_string()),
+        ];
+        let content = "alias ll='ls -l'\n\n# Pocket CLI hook: a\nsource \"/h/a.sh\"\n\n# Pocket CLI hook: b\nsource \"/h/b.sh\"\n\n# Pocket hook bin directory\nexport PATH=\"/b:$PATH\"\n# mine\nsource \"/h/a.sh\"\n";
+        let (stripped, removed) = strip_blocks(content, &blocks);
+        assert_eq!(removed, 2);
+        assert_eq!(stripped, "alias ll='ls -l'\n\n# Pocket CLI hook: b\nsource \"/h/b.sh\"\n# mine\nsource \"/h/a.sh\"\n");
+        
+        // Nothing to do leaves the content as it was
+        assert_eq!(strip_blocks(&stripped, &blocks[1..]), (stripped.clone(), 0));
+    }
+    
+    #[test]
+    fn generates_shell_functions() {
+        let wrapper = Path::new("/home/me/.pocket/bin/@deploy");
+        assert_eq!(
+            shell_function(Shell::Bash, "deploy", wrapper).unwrap(),
+            "@deploy() { '/home/me/.pocket/bin/@deploy' \"$@\"; }"
+        );
+        assert_eq!(
+            shell_function(Shell::Fish, "deploy", wrapper).unwrap(),
+            "function @deploy\n    '/home/me/.pocket/bin/@deploy' $argv\nend"
+        );
+        assert!(shell_function(Shell::Posix, "deploy", wrapper).is_none());
+        assert!(shell_function(Shell::Bash, "my hook", wrapper).is_none());
+    }
+    
+    /// Runs `script` with `shell -c`, returning each argument the hook saw on its own line
+    #[cfg(unix)]
+    fn run_through(shell: &str, dir: &Path, script: &str) -> Option<Vec<String>> {
+        let output = Command::new(shell).arg("-c").arg(script).current_dir(dir).output().ok()?;
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        Some(String::from_utf8_lossy(&output.stdout).split('\0').filter(|s| !s.is_empty()).map(|s| s[1..].to_string()).collect())
+    }
+    
+    /// Writes a hook that prints each argument it receives, plus its wrapper
+    #[cfg(unix)]
+    fn install_hook(dir: &Path) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        
+        // The hook lives in a directory with awkward characters in its name
+        let hook_dir = dir.join("my hooks, 'quoted'");
+        fs::create_dir_all(&hook_dir).unwrap();
+        let hook_path = hook_dir.join("echo.sh");
+        fs::write(&hook_path, "#!/bin/sh\nfor arg in \"$@\"; do printf '>%s\\0' \"$arg\"; done\n").unwrap();
+        let wrapper_path = dir.join("@echo");
+        fs::write(&wrapper_path, wrapper_script("echo", &hook_path)).unwrap();
+        for path in [&hook_path, &wrapper_path] {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        wrapper_path
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn wrapper_forwards_arguments_verbatim() {
+        let dir = tempfile::tempdir().unwrap();
+        let wrapper = install_hook(dir.path());
+        
+        let quoted: Vec<String> = TRICKY_ARGS.iter().map(|a| shell_quote(a)).collect();
+        let script = format!("{} {}", shell_quote(&wrapper.display().to_string()), quoted.join(" "));
+        assert_eq!(run_through("sh", dir.path(), &script).unwrap(), TRICKY_ARGS);
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn bash_function_forwards_arguments_verbatim() {
+        let dir = tempfile::tempdir().unwrap();
+        let wrapper = install_hook(dir.path());
+        
+        let function = shell_function(Shell::Bash, "echo", &wrapper).unwrap();
+        let quoted: Vec<String> = TRICKY_ARGS.iter().map(|a| shell_quote(a)).collect();
+        let script = format!("{}\n@echo {}", function, quoted.join(" "));
+        
+        // Only meaningful where bash is installed
+        if let Some(received) = run_through("bash", dir.path(), &script) {
+            assert_eq!(received, TRICKY_ARGS);
+        }
+    }
+}
@@ -1,11 +1,36 @@
-use crate::cards::{Card, CardConfig, CardCommand};
-use crate::models::{Entry, Backpack};
+use crate::cards::{events, Card, CardConfig, CardCommand, Event};
+use crate::models::{Entry, EntryAlias, Backpack, SearchAlgorithm, UntrustedPolicy};
 use crate::storage::StorageManager;
-use crate::utils;
+use crate::search::text::Candidate;
+use crate::search::rank::{self, Breakdown};
+use crate::search::project::Project;
+use crate::search::{related, Query, TextQuery};
+use crate::search::launcher::LauncherFormat;
+use crate::utils::{self, time};
+use crate::utils::pager::{self, LineRange};
+use crate::i18n::tr;
 use anyhow::{Result, Context, anyhow};
 use colored::Colorize;
 use std::path::PathBuf;
 use std::fs;
+use std::io::{IsTerminal, Write};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Backpack that `pocket execute --save-output` keeps its run logs in
+pub const RUNS_BACKPACK: &str = "runs";
+
+/// Related entries listed under `pocket show`
+const SEE_ALSO_LIMIT: usize = 3;
+
+/// The most recent search results, as IDs and backpacks, in printed order
+static LAST_RESULTS: Mutex<Vec<(String, Option<String>)>> = Mutex::new(Vec::new());
+
+/// The entries the last search in this process printed, numbered from 1 in
+/// the output. Lets the REPL resolve `show 2`
+pub fn last_results() -> Vec<(String, Option<String>)> {
+    LAST_RESULTS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
 
 /// Card for core commands (search, insert, etc.)
 pub struct CoreCard {
@@ -27,6 +52,7 @@ pub struct CoreCard {
 
 /// Configuration for the core card
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct CoreCardConfig {
     /// Maximum number of search results
     pub max_search_results: usize,
@@ -56,23 +82,74 @@ impl CoreCard {
         }
     }
     
-    /// Search for entries
-    pub fn search(&self, query: &str, limit: usize, backpack: Option<&str>, _exact: bool) -> Result<Vec<Entry>> {
+    /// Search for entries, with the algorithm set in `config.toml` unless
+    /// one is given
+    pub fn search(&self, query: &str, limit: usize, backpack: Option<&str>, algorithm: Option<SearchAlgorithm>, filter: &EntryFilter) -> Result<Vec<(Entry, Option<String>)>> {
+        let results = self.search_ranked(query, limit, backpack, algorithm, filter)?;
+        Ok(results.into_iter().map(|(entry, location, _)| (entry, location)).collect())
+    }
+    
+    /// Search for entries, best first, with how each one's score adds up
+    pub fn search_ranked(&self, query: &str, limit: usize, backpack: Option<&str>, algorithm: Option<SearchAlgorithm>, filter: &EntryFilter) -> Result<Vec<(Entry, Option<String>, Breakdown)>> {
         let storage = StorageManager::new()?;
         
-        // For now, we'll use the built-in search, as the API doesn't have exact/semantic differentiation
-        let search_results = storage.search_entries(query, backpack, limit)?;
+        let config = storage.load_config()?;
+        let algorithm = algorithm.unwrap_or(config.search.algorithm);
+        // `@name` runs a saved search
+        let query = TextQuery::parse(&storage.resolve_search(query)?).fuzzy(algorithm == SearchAlgorithm::Fuzzy);
+        
+        // A --where query, or a search naming backpacks, can look across
+        // every backpack
+        let locations = filter.locations(&storage, backpack, query.mentions_backpack())?;
         
-        // Return just the entries without content
-        Ok(search_results.into_iter().map(|(entry, _)| entry).collect())
+        // Entries from the project we're in rank higher
+        let detected;
+        let project = match &filter.project {
+            Some(project) => Some(project),
+            None if config.search.ranking.project > 0.0 => {
+                detected = Project::current();
+                detected.as_ref()
+            },
+            None => None,
+        };
+        
+        let now = chrono::Utc::now();
+        let mut results = Vec::new();
+        for location in locations {
+            let location = location.as_deref();
+            for (entry, content) in storage.search_entries_matching(&query, location, usize::MAX, |e| filter.matches(e, location))? {
+                let breakdown = rank::score(&query, &Candidate::new(&entry, location, &content), &config.search.ranking, project, now);
+                results.push((entry, location.map(|s| s.to_string()), breakdown));
+            }
+        }
+        
+        // Best scores first, pinned matches above all, then apply the limit
+        results.sort_by(|a, b| b.2.total().total_cmp(&a.2.total()));
+        let pins = storage.pinned_ids()?;
+        results.sort_by_key(|(entry, _, _)| !pins.contains(&entry.id));
+        results.truncate(limit);
+        
+        Ok(results)
     }
     
-    /// Insert an entry into a file
+    /// Insert an entry into a file, or write it to stdout when the file is `-`
     pub fn insert(&self, entry_id: &str, file_path: &str, delimiter: Option<&str>, no_confirm: bool) -> Result<()> {
         let storage = StorageManager::new()?;
         
         // Load the entry and its content
-        let (_entry, content) = storage.load_entry(entry_id, None)?;
+        let (entry_id, backpack) = storage.resolve_entry(entry_id, None)?;
+        let (_entry, content) = storage.load_entry(&entry_id, backpack.as_deref())?;
+        let entry_id = entry_id.as_str();
+        
+        // Stdout gets the content as it is, with nothing to confirm or delimit
+        if file_path == "-" {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(content.as_bytes())
+                .and_then(|_| stdout.flush())
+                .context("Failed to write to stdout")?;
+            storage.record_usage(entry_id, backpack.as_deref())?;
+            return Ok(());
+        }
         
         let delim = delimiter.unwrap_or(&self.config.default_delimiter);
         
@@ -95,38 +172,334 @@ impl CoreCard {
         
         // Confirm with user if needed
         if !no_confirm {
-            println!("Inserting entry {} into {}", entry_id.bold(), file_path.bold());
-            let confirm = utils::confirm("Continue?", true)?;
+            println!("{}", tr!("Inserting entry {} into {}", entry_id.bold(), file_path.bold()));
+            let confirm = utils::confirm(tr!("Continue?"), true)?;
             if !confirm {
-                println!("Operation cancelled");
+                println!("{}", tr!("Operation cancelled"));
                 return Ok(());
             }
         }
         
         // Write the new content
-        fs::write(file_path, new_content)
+        utils::atomic::write(file_path, new_content)
             .with_context(|| format!("Failed to write to file {}", file_path))?;
+        storage.record_usage(entry_id, backpack.as_deref())?;
+        
+        println!("{}", tr!("Successfully inserted entry {} into {}", entry_id.bold(), file_path.bold()));
+        Ok(())
+    }
+    
+    /// Runs a stored script with the given arguments and limits. Scripts not
+    /// tagged `trusted` are subject to the `execution.untrusted` policy.
+    /// `options.interpreter` overrides the one recorded on the entry, found
+    /// on its shebang line or guessed from its language. With `save_output`
+    /// the run is logged to the runs backpack. Fails unless the script exits
+    /// successfully
+    pub fn execute_script(&self, reference: &str, args: &[String], backpack: Option<&str>, options: &utils::exec::ExecOptions, save_output: bool) -> Result<utils::exec::ExecOutcome> {
+        let storage = StorageManager::new()?;
+        let (id, backpack) = storage.resolve_entry(reference, backpack)?;
+        let (entry, content) = storage.load_entry(&id, backpack.as_deref())?;
+        
+        if !entry.tags.iter().any(|tag| tag == "trusted") {
+            match storage.load_config()?.execution.untrusted {
+                UntrustedPolicy::Allow => {},
+                UntrustedPolicy::Confirm => {
+                    if !std::io::stdin().is_terminal() {
+                        return Err(anyhow!("'{}' isn't tagged trusted and there's no terminal to confirm on", entry.title));
+                    }
+                    if !utils::confirm(&format!("'{}' isn't tagged trusted. Run it anyway?", entry.title), false)? {
+                        return Err(anyhow!("Not running '{}'", entry.title));
+                    }
+                },
+                UntrustedPolicy::Deny => {
+                    return Err(anyhow!("'{}' isn't tagged trusted, and execution.untrusted is set to Deny", entry.title));
+                },
+            }
+        }
+        
+        if entry.is_binary() {
+            return Err(anyhow!("'{}' is a binary attachment, not a script", entry.title));
+        }
+        
+        let mut options = options.clone();
+        options.capture_output |= save_output;
+        options.interpreter = utils::exec::pick_interpreter(
+            options.interpreter.as_deref().or(entry.get_metadata("interpreter")),
+            &content,
+            entry.language.as_deref(),
+        );
+        
+        storage.record_usage(&id, backpack.as_deref())?;
+        let outcome = utils::exec::run_script(&entry.title, &content, args, &options)?;
+        
+        if save_output {
+            let run_id = self.save_run(&storage, &entry, backpack.as_deref(), reference, args, &outcome)?;
+            eprintln!("Saved run log {} to the {} backpack", run_id.bold(), RUNS_BACKPACK);
+        }
+        
+        if outcome.timed_out {
+            let seconds = options.timeout.map(|t| t.as_secs_f64()).unwrap_or_default();
+            return Err(anyhow!("Script timed out after {}s", seconds));
+        }
+        if !outcome.status.success() {
+            return Err(match outcome.status.code() {
+                Some(code) => anyhow!("Script exited with status {}", code),
+                None => anyhow!("Script was terminated by a signal"),
+            });
+        }
+        Ok(outcome)
+    }
+    
+    /// Stores a script run as an entry in the runs backpack, linked to the
+    /// script both ways, and returns its ID
+    fn save_run(&self, storage: &StorageManager, script: &Entry, backpack: Option<&str>, reference: &str, args: &[String], outcome: &utils::exec::ExecOutcome) -> Result<String> {
+        if storage.load_backpack(RUNS_BACKPACK).is_err() {
+            storage.create_backpack(&Backpack {
+                name: RUNS_BACKPACK.to_string(),
+                description: Some("Script runs saved with pocket execute --save-output".to_string()),
+                created_at: chrono::Utc::now(),
+            })?;
+            events::emit(Event::BackpackCreated { name: RUNS_BACKPACK.to_string() });
+        }
+        
+        let exit = if outcome.timed_out {
+            "timed out".to_string()
+        } else {
+            outcome.status.code().map(|code| code.to_string()).unwrap_or_else(|| "killed by a signal".to_string())
+        };
+        let command_line = std::iter::once(reference)
+            .chain(args.iter().map(String::as_str))
+            .map(|arg| {
+                let plain = !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c));
+                if plain { arg.to_string() } else { crate::cards::blend::shell_quote(arg) }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let content = format!(
+            "$ pocket execute {}\nExit: {}\nDuration: {:.3}s\n\n--- stdout ---\n{}\n--- stderr ---\n{}",
+            command_line, exit, outcome.duration.as_secs_f64(), outcome.stdout, outcome.stderr
+        );
+        
+        let mut run = Entry::new(
+            format!("Run of {} at {}", script.title, chrono::Local::now().format("%Y-%m-%d %H:%M:%S")),
+            crate::models::ContentType::Text,
+            Some(format!("pocket execute {}", script.id)),
+            vec!["run-log".to_string()],
+        );
+        run.add_metadata("run_of", &script.id);
+        if let Some(name) = backpack {
+            run.add_metadata("run_of_backpack", name);
+        }
+        run.add_metadata("exit", &exit);
+        run.add_metadata("duration_ms", &outcome.duration.as_millis().to_string());
+        run.add_metadata("args", &serde_json::to_string(args)?);
+        
+        storage.save_entry(&run, &content, Some(RUNS_BACKPACK))?;
+        events::emit(Event::EntryAdded { id: run.id.clone(), backpack: Some(RUNS_BACKPACK.to_string()) });
+        
+        storage.update_entry_metadata(&script.id, backpack, |entry| entry.add_metadata("last_run", &run.id))?;
+        Ok(run.id)
+    }
+    
+    /// Show an entry's content, or just the `lines` asked for, through a
+    /// pager when it's too long for the terminal and `pager` allows
+    pub fn show(&self, id: &str, backpack: Option<&str>, lines: Option<LineRange>, pager: bool) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        let (id, backpack) = storage.resolve_entry(id, backpack)?;
+        
+        // Binary content can't go to the terminal, so describe it instead
+        let entry = storage.load_entry_metadata(&id, backpack.as_deref())?;
+        if let Some(mime_type) = &entry.mime_type {
+            let (_, bytes) = storage.load_entry_bytes(&id, backpack.as_deref())?;
+            println!("{} {} {}", entry.id.bold(), entry.title, format!("({})", mime_type).dimmed());
+            println!("{}", "-".repeat(40).dimmed());
+            println!("Binary content, {} bytes. Use `pocket export {} -o <path>` to retrieve it.", bytes.len(), short_id(&entry.id));
+            
+            storage.record_usage(&id, backpack.as_deref())?;
+            return Ok(());
+        }
+        
+        let (entry, content) = storage.load_entry(&id, backpack.as_deref())?;
+        
+        // Entries saved before language detection fall back to detecting now
+        let language = entry.language.clone()
+            .or_else(|| utils::detect_language(None, &content));
+        
+        let (content, part) = match lines {
+            Some(range) => {
+                let (part, total) = range.apply(&content);
+                let end = range.end.unwrap_or(total).min(total);
+                (part, format!(" (lines {}-{} of {})", range.start, end, total).dimmed().to_string())
+            },
+            None => (content, String::new()),
+        };
+        
+        // Piped output is just the content, ready for the next command
+        if !std::io::stdout().is_terminal() {
+            print!("{}", content);
+            if !content.is_empty() && !content.ends_with('\n') {
+                println!();
+            }
+            storage.record_usage(&id, backpack.as_deref())?;
+            return Ok(());
+        }
+        
+        let mut out = String::new();
+        match &language {
+            Some(language) => out.push_str(&format!("{} {} {}{}\n", entry.id.bold(), entry.title, format!("({})", language).dimmed(), part)),
+            None => out.push_str(&format!("{} {}{}\n", entry.id.bold(), entry.title, part)),
+        }
+        out.push_str(&format!("{}\n", "-".repeat(40).dimmed()));
+        
+        match language {
+            Some(language) => out.push_str(&format!("{}\n", utils::language::highlight(&content, &language))),
+            None => out.push_str(&format!("{}\n", content)),
+        }
+        
+        match related::related(&storage, &id, backpack.as_deref(), SEE_ALSO_LIMIT) {
+            Ok(related) if !related.is_empty() => {
+                out.push_str(&format!("{}\n", "-".repeat(40).dimmed()));
+                out.push_str(&format!("{}\n", "See also:".dimmed()));
+                for item in related {
+                    out.push_str(&format!("  {}{} - {}\n", short_id(&item.entry.id).bold(), location_label(&item.backpack), item.entry.title));
+                }
+            },
+            Ok(_) => {},
+            Err(e) => log::debug!("Couldn't find related entries for {}: {}", id, e),
+        }
+        
+        let display = storage.load_config()?.display;
+        if pager && display.pager {
+            pager::page(&out, display.pager_command.as_deref())?;
+        } else {
+            print!("{}", out);
+        }
+        
+        storage.record_usage(&id, backpack.as_deref())?;
+        Ok(())
+    }
+    
+    /// Draws an entry's content, or some of its lines, as a QR code
+    pub fn show_qr(&self, id: &str, backpack: Option<&str>, lines: Option<LineRange>) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        let (id, backpack) = storage.resolve_entry(id, backpack)?;
+        let (entry, content) = storage.load_entry_bytes(&id, backpack.as_deref())?;
+        let mut content = match lines {
+            Some(range) => range.apply(&String::from_utf8_lossy(&content)).0.into_bytes(),
+            None => content,
+        };
+        // A trailing newline would end up in whatever the phone reads
+        while content.last().is_some_and(|byte| *byte == b'\n' || *byte == b'\r') {
+            content.pop();
+        }
+        
+        println!("{}", utils::qr::render(&content)?);
+        println!("{}", entry.title.dimmed());
+        
+        storage.record_usage(&id, backpack.as_deref())?;
+        Ok(())
+    }
+    
+    /// Puts an entry's content, or some of its lines, on the clipboard
+    pub fn copy(&self, id: &str, backpack: Option<&str>, lines: Option<LineRange>) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        let (id, backpack) = storage.resolve_entry(id, backpack)?;
+        let entry = storage.load_entry_metadata(&id, backpack.as_deref())?;
+        if entry.mime_type.is_some() {
+            return Err(anyhow!("Entry {} is binary; use `pocket export {} -o <path>` instead", short_id(&entry.id), short_id(&entry.id)));
+        }
+        
+        let (entry, content) = storage.load_entry(&id, backpack.as_deref())?;
+        let content = match lines {
+            Some(range) => range.apply(&content).0,
+            None => content,
+        };
+        utils::write_clipboard(&content)?;
+        println!("{}", tr!("Copied {} to the clipboard", entry.title.bold()));
+        
+        storage.record_usage(&id, backpack.as_deref())?;
+        Ok(())
+    }
+    
+    /// List the entries most related to one, by shared tags and words
+    pub fn related(&self, id: &str, backpack: Option<&str>, limit: usize, json: bool) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        let (id, backpack) = storage.resolve_entry(id, backpack)?;
+        let related = related::related(&storage, &id, backpack.as_deref(), limit)?;
+        
+        if json {
+            let entries: Vec<serde_json::Value> = related.iter()
+                .map(|item| serde_json::json!({
+                    "id": item.entry.id,
+                    "title": item.entry.title,
+                    "backpack": item.backpack,
+                    "score": item.score,
+                }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+            return Ok(());
+        }
+        
+        if related.is_empty() {
+            println!("{}", tr!("No related entries found"));
+            return Ok(());
+        }
+        
+        for item in related {
+            println!("{}{} - {} {}", item.entry.id.bold(), location_label(&item.backpack), item.entry.title, format!("({:.0}%)", item.score * 100.0).dimmed());
+        }
         
-        println!("Successfully inserted entry {} into {}", entry_id.bold(), file_path.bold());
         Ok(())
     }
     
     /// List all entries
-    pub fn list(&self, include_backpacks: bool, backpack: Option<&str>, json: bool) -> Result<()> {
+    pub fn list(&self, include_backpacks: bool, backpack: Option<&str>, json: bool, sort: &str, limit: Option<usize>, filter: &EntryFilter) -> Result<()> {
         let storage = StorageManager::new()?;
-        let entries = storage.list_entries(backpack)?;
+        
+        // A --where query can look across every backpack
+        let mut entries = Vec::new();
+        for location in filter.locations(&storage, backpack, false)? {
+            for entry in storage.list_entries(location.as_deref())? {
+                if filter.matches(&entry, location.as_deref()) {
+                    entries.push((entry, location.clone()));
+                }
+            }
+        }
+        
+        // Entries come back newest first; re-order if asked to
+        match sort {
+            "used" => entries.sort_by(|(a, _), (b, _)| {
+                b.usage_count.cmp(&a.usage_count).then(b.last_used.cmp(&a.last_used))
+            }),
+            "title" => entries.sort_by_key(|(e, _)| e.title.to_lowercase()),
+            "created" => entries.sort_by_key(|(e, _)| std::cmp::Reverse(e.created_at)),
+            other => return Err(anyhow!("Unknown sort order: {}", other)),
+        }
+        
+        // Pinned entries always come first
+        let pins = storage.pinned_ids()?;
+        entries.sort_by_key(|(e, _)| !pins.contains(&e.id));
+        
+        if let Some(limit) = limit {
+            entries.truncate(limit);
+        }
         
         if json {
+            let entries: Vec<&Entry> = entries.iter().map(|(e, _)| e).collect();
             println!("{}", serde_json::to_string_pretty(&entries)?);
             return Ok(());
         }
         
         if entries.is_empty() {
-            println!("No entries found");
+            println!("{}", tr!("No entries found"));
             return Ok(());
         }
         
-        for entry in entries {
+        for (entry, location) in entries {
             let backpack_name = if include_backpacks {
                 match &entry.source {
                     Some(source) if source.starts_with("backpack:") => {
@@ -135,13 +508,149 @@ impl CoreCard {
                     },
                     _ => "".to_string(),
                 }
+            } else if location.as_deref() != backpack {
+                location_label(&location)
             } else {
                 "".to_string()
             };
             
-            println!("{}{} - {}", entry.id.bold(), backpack_name, entry.title);
+            println!("{}{} - {}{}", entry.id.bold(), backpack_name, entry.title, pin_marker(&entry));
+        }
+        
+        Ok(())
+    }
+    
+    /// Pin or unpin an entry
+    pub fn pin(&self, id: &str, backpack: Option<&str>, pinned: bool) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        let (id, backpack) = storage.resolve_entry(id, backpack)?;
+        storage.set_pinned(&id, backpack.as_deref(), pinned)?;
+        
+        if pinned {
+            println!("{}", tr!("Pinned entry: {}", id.bold()));
+        } else {
+            println!("{}", tr!("Unpinned entry: {}", id.bold()));
+        }
+        
+        Ok(())
+    }
+    
+    /// Interactively pick an entry, pinned entries first
+    pub fn pick(&self, backpack: Option<&str>) -> Result<(String, Option<String>)> {
+        let storage = StorageManager::new()?;
+        
+        let mut entries = storage.list_entries(backpack)?;
+        if entries.is_empty() {
+            return Err(anyhow!("No entries to pick from"));
+        }
+        
+        let pins = storage.pinned_ids()?;
+        entries.sort_by_key(|e| !pins.contains(&e.id));
+        
+        let items: Vec<String> = entries.iter()
+            .map(|e| format!("{} - {}{}", short_id(&e.id), e.title, pin_marker(e)))
+            .collect();
+        let index = utils::select("Select an entry", &items)?;
+        
+        Ok((entries[index].id.clone(), backpack.map(|s| s.to_string())))
+    }
+    
+    /// Edit an entry's content in the editor. Unless `force` is set, the
+    /// changes are shown as a diff and only saved once confirmed
+    pub fn edit(&self, id: &str, backpack: Option<&str>, force: bool) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        let (id, backpack) = storage.resolve_entry(id, backpack)?;
+        let (entry, content) = storage.load_entry(&id, backpack.as_deref())?;
+        if entry.mime_type.is_some() {
+            return Err(anyhow!("Entry {} is binary and can't be edited as text", short_id(&id)));
+        }
+        
+        let edited = utils::open_editor_for_language(entry.language.as_deref(), Some(&content))?;
+        if edited == content {
+            println!("{}", tr!("No changes to {}", id.bold()));
+            return Ok(());
+        }
+        
+        if !force {
+            print!("{}", utils::diff::colored(&content, &edited));
+            // Emptying an entry is more likely a slip than the plan
+            let confirmed = if edited.trim().is_empty() {
+                utils::confirm("This leaves the entry empty. Save anyway?", false)?
+            } else {
+                utils::confirm("Save these changes?", true)?
+            };
+            if !confirmed {
+                println!("{}", tr!("Operation cancelled"));
+                return Ok(());
+            }
         }
         
+        save_edit(&storage, &entry, backpack.as_deref(), &edited)?;
+        events::emit(Event::EntryUpdated { id: id.clone(), backpack: backpack.clone() });
+        println!("{}", tr!("Updated entry: {}", id.bold()));
+        Ok(())
+    }
+    
+    /// Lets the user pick search results and act on them, going back to
+    /// the results after each action until nothing is picked
+    pub fn act_on(&self, mut results: Vec<(Entry, Option<String>)>) -> Result<()> {
+        let storage = StorageManager::new()?;
+        while !results.is_empty() {
+            let items: Vec<String> = results.iter()
+                .map(|(entry, location)| format!("{}{} - {}{}", short_id(&entry.id), location_label(location), entry.title, pin_marker(entry)))
+                .collect();
+            let picked = utils::multi_select("Pick entries with space, then press enter (pick none to finish)", &items, &vec![false; items.len()])?;
+            if picked.is_empty() {
+                break;
+            }
+            
+            let action = SearchAction::ALL[utils::select("Do what with them?", &SearchAction::ALL)?];
+            let picked: Vec<&(Entry, Option<String>)> = picked.iter().map(|&i| &results[i]).collect();
+            match action {
+                SearchAction::Copy => {
+                    let mut contents = Vec::new();
+                    for (entry, location) in &picked {
+                        contents.push(storage.load_entry(&entry.id, location.as_deref())?.1);
+                        storage.record_usage(&entry.id, location.as_deref())?;
+                    }
+                    utils::write_clipboard(&contents.join("\n\n"))?;
+                    println!("Copied {} {} to the clipboard", picked.len(), if picked.len() == 1 { "entry" } else { "entries" });
+                }
+                SearchAction::Insert => {
+                    let file: String = utils::input("Insert into which file?", None)?;
+                    for (entry, _) in &picked {
+                        self.insert(&entry.id, &file, None, true)?;
+                    }
+                }
+                SearchAction::Edit => {
+                    for (entry, location) in &picked {
+                        self.edit(&entry.id, location.as_deref(), false)?;
+                    }
+                }
+                SearchAction::Open => {
+                    for (entry, location) in &picked {
+                        let (_, content) = storage.load_entry(&entry.id, location.as_deref())?;
+                        utils::open_editor_for_language(entry.language.as_deref(), Some(&content))?;
+                    }
+                }
+                SearchAction::Delete => {
+                    for (entry, location) in &picked {
+                        self.remove(&entry.id, false, location.as_deref())?;
+                    }
+                }
+                SearchAction::Done => break,
+            }
+            
+            // Drop what's been deleted, and show edits under their new titles
+            results = results.into_iter()
+                .filter_map(|(entry, location)| {
+                    let entry = storage.load_entry_metadata(&entry.id, location.as_deref()).ok()?;
+                    Some((entry, location))
+                })
+                .collect();
+        }
         Ok(())
     }
     
@@ -158,7 +667,51 @@ impl CoreCard {
         
         // Save the backpack
         storage.create_backpack(&backpack)?;
-        println!("Created backpack: {}", name.bold());
+        events::emit(Event::BackpackCreated { name: name.to_string() });
+        println!("{}", tr!("Created backpack: {}", name.bold()));
+        Ok(())
+    }
+    
+    /// Rename a backpack
+    pub fn rename_backpack(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let storage = StorageManager::new()?;
+        storage.rename_backpack(old_name, new_name)?;
+        events::emit(Event::BackpackRenamed { old_name: old_name.to_string(), new_name: new_name.to_string() });
+        println!("{}", tr!("Renamed backpack {} to {}", old_name.bold(), new_name.bold()));
+        Ok(())
+    }
+    
+    /// Delete a backpack
+    pub fn delete_backpack(&self, name: &str, move_entries: bool, force: bool) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        storage.load_backpack(name)?;
+        let entry_count = storage.list_entries(Some(name))?.len();
+        
+        // Never throw away entries unless asked to
+        if entry_count > 0 && !move_entries && !force {
+            return Err(anyhow!(
+                "Backpack '{}' contains {} entries; use --move-entries to move them to the general pocket or --force to delete them",
+                name, entry_count
+            ));
+        }
+        
+        storage.delete_backpack(name, move_entries)?;
+        events::emit(Event::BackpackDeleted { name: name.to_string() });
+        
+        if move_entries && entry_count > 0 {
+            println!("{}", tr!("Deleted backpack {} and moved {} entries to the general pocket", name.bold(), entry_count));
+        } else {
+            println!("{}", tr!("Deleted backpack {}", name.bold()));
+        }
+        Ok(())
+    }
+    
+    /// Set or clear a backpack's description
+    pub fn describe_backpack(&self, name: &str, description: Option<&str>) -> Result<()> {
+        let storage = StorageManager::new()?;
+        storage.set_backpack_description(name, description)?;
+        println!("{}", tr!("Updated description of backpack {}", name.bold()));
         Ok(())
     }
     
@@ -167,98 +720,561 @@ impl CoreCard {
         let storage = StorageManager::new()?;
         
         // Check if entry exists
-        let (entry, _) = storage.load_entry(id, backpack)?;
+        let (id, backpack) = storage.resolve_entry(id, backpack)?;
+        let (id, backpack) = (id.as_str(), backpack.as_deref());
+        let entry = storage.load_entry_metadata(id, backpack)?;
         
         // Confirm with user if not forced
         if !force {
-            println!("You are about to remove: {}", id.bold());
-            println!("Title: {}", entry.title);
+            println!("{}", tr!("You are about to remove: {}", id.bold()));
+            println!("{}", tr!("Title: {}", entry.title));
             
-            let confirm = utils::confirm("Are you sure?", false)?;
+            let confirm = utils::confirm(tr!("Are you sure?"), false)?;
             if !confirm {
-                println!("Operation cancelled");
+                println!("{}", tr!("Operation cancelled"));
                 return Ok(());
             }
         }
         
-        // Remove the entry
+        // Remove the entry and any aliases pointing at it
         storage.remove_entry(id, backpack)?;
-        println!("Removed entry: {}", id.bold());
+        events::emit(Event::EntryRemoved { id: id.to_string(), backpack: backpack.map(|s| s.to_string()) });
+        let mut aliases = storage.load_aliases()?;
+        let alias_count = aliases.len();
+        aliases.retain(|_, alias| alias.id != id);
+        if aliases.len() != alias_count {
+            storage.save_aliases(&aliases)?;
+        }
+        println!("{}", tr!("Removed entry: {}", id.bold()));
         
         Ok(())
     }
-}
-
-impl Card for CoreCard {
-    fn name(&self) -> &str {
-        &self.name
+    
+    /// Show usage statistics for snippets
+    pub fn stats_snippets(&self, backpack: Option<&str>, limit: usize) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        let entries = storage.list_all_entries(backpack)?;
+        
+        if entries.is_empty() {
+            println!("{}", tr!("No entries found"));
+            return Ok(());
+        }
+        
+        let total_uses: u64 = entries.iter().map(|(e, _)| e.usage_count).sum();
+        let (used, unused): (Vec<_>, Vec<_>) = entries.into_iter().partition(|(e, _)| e.usage_count > 0);
+        
+        println!("{} entries, {} uses, {} never used", used.len() + unused.len(), total_uses, unused.len());
+        
+        let mut used = used;
+        used.sort_by(|(a, _), (b, _)| b.usage_count.cmp(&a.usage_count).then(b.last_used.cmp(&a.last_used)));
+        
+        if !used.is_empty() {
+            println!("\n{}", "Most used:".bold());
+            for (entry, location) in used.iter().take(limit) {
+                let last_used = entry.last_used
+                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                println!("  {:>5}  {}  {}{} - {}", entry.usage_count, last_used.dimmed(), short_id(&entry.id).bold(), location_label(location), entry.title);
+            }
+        }
+        
+        if !unused.is_empty() {
+            println!("\n{}", "Never used:".bold());
+            let mut unused = unused;
+            unused.sort_by_key(|(e, _)| e.created_at);
+            for (entry, location) in unused.iter().take(limit) {
+                println!("  {}  {}{} - {}", entry.created_at.format("%Y-%m-%d").to_string().dimmed(), short_id(&entry.id).bold(), location_label(location), entry.title);
+            }
+            if unused.len() > limit {
+                println!("  ... and {} more", unused.len() - limit);
+            }
+        }
+        
+        Ok(())
     }
     
-    fn version(&self) -> &str {
-        env!("CARGO_PKG_VERSION")
+    /// Write an entry's content, text or binary, to a file or stdout
+    pub fn export(&self, id: &str, output: Option<&str>, backpack: Option<&str>) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        let (id, backpack) = storage.resolve_entry(id, backpack)?;
+        let (entry, content) = storage.load_entry_bytes(&id, backpack.as_deref())?;
+        
+        match output {
+            Some(path) => {
+                utils::atomic::write(path, &content)
+                    .with_context(|| format!("Failed to write to file {}", path))?;
+                eprintln!("Exported {} ({} bytes) to {}", entry.id.bold(), content.len(), path.bold());
+            }
+            None => {
+                std::io::stdout().write_all(&content)?;
+            }
+        }
+        
+        storage.record_usage(&id, backpack.as_deref())?;
+        Ok(())
     }
     
-    fn _description(&self) -> &str {
-        "Core card providing essential functions"
+    /// Publish backpacks as a static HTML site
+    pub fn publish(&self, output: &str, backpacks: &[String]) -> Result<()> {
+        let storage = StorageManager::new()?;
+        let out_dir = utils::expand_path(output)?;
+        
+        let count = utils::site::publish(&storage, backpacks, &out_dir)?;
+        println!("Published {} entries to {}", count, out_dir.display().to_string().bold());
+        Ok(())
     }
     
-    fn _initialize(&mut self, config: &CardConfig) -> Result<()> {
-        // If there are options in the card config, try to parse them
-        if let Some(options_value) = config.options.get("core") {
-            if let Ok(options) = serde_json::from_value::<CoreCardConfig>(options_value.clone()) {
-                self.config = options;
+    /// Find entries with the same content and merge each group into one
+    pub fn dedupe(&self, backpack: Option<&str>, yes: bool) -> Result<()> {
+        let storage = StorageManager::new()?;
+        
+        // Group entries by their normalized content hash
+        let mut groups: BTreeMap<String, Vec<(Entry, Option<String>)>> = BTreeMap::new();
+        for (entry, location) in storage.list_all_entries(backpack)? {
+            let hash = storage.entry_hash(&entry, location.as_deref())?;
+            groups.entry(hash).or_default().push((entry, location));
+        }
+        
+        let groups: Vec<_> = groups.into_values().filter(|group| group.len() > 1).collect();
+        if groups.is_empty() {
+            println!("{}", tr!("No duplicate entries found"));
+            return Ok(());
+        }
+        
+        let mut merged = 0;
+        for group in groups {
+            println!("\n{}", format!("{} duplicate entries:", group.len()).bold());
+            for (entry, location) in &group {
+                println!("  {}{} - {} ({} uses)", short_id(&entry.id).bold(), location_label(location), entry.title, entry.usage_count);
             }
+            
+            let keep = if yes {
+                pick_survivor(&group)
+            } else {
+                let mut items: Vec<String> = group.iter()
+                    .map(|(e, location)| format!("Keep {}{} - {}", short_id(&e.id), location_label(location), e.title))
+                    .collect();
+                items.push("Skip this group".to_string());
+                
+                let choice = utils::select("Merge into which entry?", &items)?;
+                if choice == group.len() {
+                    continue;
+                }
+                choice
+            };
+            
+            merged += merge_duplicates(&storage, group, keep)?;
         }
         
+        println!("\n{}", tr!("Merged {} duplicate entries", merged));
         Ok(())
     }
     
-    fn execute(&self, command: &str, args: &[String]) -> Result<()> {
-        match command {
-            "search" => {
-                if args.is_empty() {
-                    return Err(anyhow!("Missing search query"));
-                }
-                
-                let query = &args[0];
-                let mut limit = self.config.max_search_results;
-                let mut backpack = None;
-                let mut exact = false;
-                
-                // Parse optional arguments
-                let mut i = 1;
+    /// Assign an alias to an entry
+    pub fn add_alias(&self, name: &str, reference: &str, backpack: Option<&str>) -> Result<()> {
+        if name.is_empty() || name.chars().any(|c| c.is_whitespace() || c == '/') {
+            return Err(anyhow!("Invalid alias name '{}': aliases cannot contain whitespace or '/'", name));
+        }
+        
+        let storage = StorageManager::new()?;
+        let (id, backpack) = storage.resolve_entry(reference, backpack)?;
+        
+        let mut aliases = storage.load_aliases()?;
+        if let Some(existing) = aliases.get(name) {
+            if existing.id != id {
+                return Err(anyhow!("Alias '{}' already points to {}", name, existing.id));
+            }
+        }
+        
+        aliases.insert(name.to_string(), EntryAlias { id: id.clone(), backpack });
+        storage.save_aliases(&aliases)?;
+        println!("{}", tr!("Alias {} -> {}", name.bold(), id));
+        Ok(())
+    }
+    
+    /// Remove an alias
+    pub fn remove_alias(&self, name: &str) -> Result<()> {
+        let storage = StorageManager::new()?;
+        let mut aliases = storage.load_aliases()?;
+        
+        if aliases.remove(name).is_none() {
+            let hint = utils::suggest::did_you_mean(name, aliases.keys().map(String::as_str));
+            return Err(anyhow!("Alias '{}' not found{}", name, hint));
+        }
+        
+        storage.save_aliases(&aliases)?;
+        println!("{}", tr!("Removed alias: {}", name.bold()));
+        Ok(())
+    }
+    
+    /// List all aliases
+    pub fn list_aliases(&self) -> Result<()> {
+        let storage = StorageManager::new()?;
+        let aliases = storage.load_aliases()?;
+        
+        if aliases.is_empty() {
+            println!("{}", tr!("No aliases defined"));
+            return Ok(());
+        }
+        
+        for (name, alias) in aliases {
+            match alias.backpack {
+                Some(backpack) => println!("{} -> {} [{}]", name.bold(), alias.id, backpack.bold()),
+                None => println!("{} -> {}", name.bold(), alias.id),
+            }
+        }
+        
+        Ok(())
+    }
+}
+
+/// Shorten an entry ID for display; any unique prefix resolves back to the entry
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(8)]
+}
+
+/// Save the text an entry was edited into. The editor may have been open a
+/// while, so a change made meanwhile isn't trampled; the edit goes to a
+/// temporary file instead, and the error says where
+fn save_edit(storage: &StorageManager, entry: &Entry, backpack: Option<&str>, edited: &str) -> Result<()> {
+    if let Err(e) = storage.update_entry_if_unchanged(&entry.id, backpack, entry.updated_at, Some(edited), |_| {}) {
+        let extension = entry.language.as_deref().map(utils::language::extension_for_language).unwrap_or("txt");
+        let (_, kept) = tempfile::Builder::new().prefix("pocket_edit_").suffix(&format!(".{}", extension)).tempfile()?.keep()?;
+        fs::write(&kept, edited)?;
+        let message = format!("{}. Your edited version is in {}", e, kept.display());
+        return Err(e.context(message));
+    }
+    Ok(())
+}
+
+/// Which of a group of duplicates to keep without asking: the most used,
+/// preferring the oldest on ties
+fn pick_survivor(group: &[(Entry, Option<String>)]) -> usize {
+    group.iter().enumerate()
+        .max_by_key(|(_, (e, _))| (e.usage_count, std::cmp::Reverse(e.created_at)))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Fold a group of duplicate entries into the one at `keep`, carrying over
+/// tags, usage, pins and aliases. Returns how many entries were removed.
+fn merge_duplicates(storage: &StorageManager, mut group: Vec<(Entry, Option<String>)>, keep: usize) -> Result<usize> {
+    let (survivor, survivor_backpack) = group.remove(keep);
+    let (mut survivor, content) = storage.load_entry_bytes(&survivor.id, survivor_backpack.as_deref())?;
+    let mut aliases = storage.load_aliases()?;
+    
+    for (duplicate, _) in &group {
+        for tag in &duplicate.tags {
+            if !survivor.tags.contains(tag) {
+                survivor.tags.push(tag.clone());
+            }
+        }
+        survivor.usage_count += duplicate.usage_count;
+        survivor.last_used = survivor.last_used.max(duplicate.last_used);
+        survivor.pinned |= duplicate.pinned;
+        
+        for alias in aliases.values_mut().filter(|a| a.id == duplicate.id) {
+            alias.id = survivor.id.clone();
+            alias.backpack = survivor_backpack.clone();
+        }
+    }
+    
+    survivor.touch();
+    if survivor.is_binary() {
+        storage.save_binary_entry(&survivor, &content, survivor_backpack.as_deref())?;
+    } else {
+        storage.save_entry(&survivor, &String::from_utf8(content)?, survivor_backpack.as_deref())?;
+    }
+    storage.save_aliases(&aliases)?;
+    if survivor.pinned {
+        storage.set_pinned(&survivor.id, survivor_backpack.as_deref(), true)?;
+    }
+    events::emit(Event::EntryUpdated { id: survivor.id.clone(), backpack: survivor_backpack.clone() });
+    
+    for (duplicate, location) in &group {
+        storage.remove_entry(&duplicate.id, location.as_deref())?;
+        events::emit(Event::EntryRemoved { id: duplicate.id.clone(), backpack: location.clone() });
+    }
+    
+    println!("Kept {}; removed {}", survivor.id.bold(), group.len());
+    Ok(group.len())
+}
+
+/// Filters shared by list and search: `--lang`, `--where`,
+/// `--since`/`--until`, and `--project-only`
+#[derive(Default)]
+pub struct EntryFilter {
+    /// Canonical language name to match
+    lang: Option<&'static str>,
+    
+    /// Metadata query to match
+    query: Option<Query>,
+    
+    /// Earliest and latest (exclusive) times to match
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    
+    /// Whether the times are when entries were created, rather than when
+    /// they last changed
+    created: bool,
+    
+    /// Project the entries must belong to
+    project: Option<Project>,
+}
+
+impl EntryFilter {
+    /// Build a filter from the raw `--lang` and `--where` values
+    pub fn new(lang: Option<&str>, query: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            lang: lang.map(parse_language).transpose()?,
+            query: query.map(Query::parse).transpose()?,
+            ..Self::default()
+        })
+    }
+    
+    /// Only match entries changed (or with `created`, created) between the
+    /// raw `--since` and `--until` values
+    pub fn dated(mut self, since: Option<&str>, until: Option<&str>, created: bool) -> Result<Self> {
+        let now = chrono::Utc::now();
+        self.since = since.map(|raw| time::parse_since(raw, now)).transpose()?;
+        self.until = until.map(|raw| time::parse_until(raw, now)).transpose()?;
+        if let (Some(since), Some(until)) = (self.since, self.until) {
+            if since >= until {
+                return Err(anyhow!("--since is after --until, so nothing could match"));
+            }
+        }
+        self.created = created;
+        Ok(self)
+    }
+    
+    /// Only match entries that belong to the project around the current
+    /// directory
+    pub fn in_project(mut self) -> Result<Self> {
+        let project = Project::current().ok_or_else(|| anyhow!("--project-only needs to be run inside a git repository"))?;
+        self.project = Some(project);
+        Ok(self)
+    }
+    
+    /// Check an entry living in `backpack` against the filter
+    pub fn matches(&self, entry: &Entry, backpack: Option<&str>) -> bool {
+        let time = if self.created { entry.created_at } else { entry.updated_at };
+        self.lang.is_none_or(|lang| entry.language.as_deref() == Some(lang))
+            && self.query.as_ref().is_none_or(|q| q.matches(entry, backpack))
+            && self.since.is_none_or(|since| time >= since)
+            && self.until.is_none_or(|until| time < until)
+            && self.project.as_ref().is_none_or(|project| project.relevance(entry) > 0.0)
+    }
+    
+    /// Locations to look in: the given backpack, or everywhere when a query
+    /// is given without one (so `backpack!=archive` means something), or
+    /// when `everywhere` says so
+    fn locations(&self, storage: &StorageManager, backpack: Option<&str>, everywhere: bool) -> Result<Vec<Option<String>>> {
+        if backpack.is_none() && (self.query.is_some() || everywhere) {
+            let mut locations = vec![None];
+            locations.extend(storage.list_backpacks()?.into_iter().map(|b| Some(b.name)));
+            Ok(locations)
+        } else {
+            Ok(vec![backpack.map(|s| s.to_string())])
+        }
+    }
+}
+
+/// Parse a `--lang` value into a canonical language name
+fn parse_language(lang: &str) -> Result<&'static str> {
+    utils::normalize_language(lang).ok_or_else(|| anyhow!("Unknown language: {}", lang))
+}
+
+/// What `search --interactive` can do with the entries picked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchAction {
+    Copy,
+    Insert,
+    Edit,
+    Open,
+    Delete,
+    Done,
+}
+
+impl SearchAction {
+    const ALL: [SearchAction; 6] = [Self::Copy, Self::Insert, Self::Edit, Self::Open, Self::Delete, Self::Done];
+}
+
+impl std::fmt::Display for SearchAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Self::Copy => "Copy to the clipboard",
+            Self::Insert => "Insert into a file",
+            Self::Edit => "Edit",
+            Self::Open => "Open in the editor (changes aren't kept)",
+            Self::Delete => "Delete",
+            Self::Done => "Done",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Marker appended to pinned entries in listings
+fn pin_marker(entry: &Entry) -> String {
+    if entry.pinned {
+        format!(" {}", "(pinned)".yellow())
+    } else {
+        String::new()
+    }
+}
+
+/// Format a backpack location for display
+fn location_label(backpack: &Option<String>) -> String {
+    match backpack {
+        Some(name) => format!(" [{}]", name),
+        None => String::new(),
+    }
+}
+
+impl Card for CoreCard {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn version(&self) -> &str {
+        env!("CARGO_PKG_VERSION")
+    }
+    
+    fn description(&self) -> &str {
+        "Core card providing essential functions"
+    }
+    
+    fn initialize(&mut self, config: &CardConfig) -> Result<()> {
+        // If there are options in the card config, parse them
+        if let Some(options_value) = config.options.get("core") {
+            self.config = serde_json::from_value::<CoreCardConfig>(options_value.clone())
+                .map_err(|e| anyhow::anyhow!("Invalid core card options: {}", e))?;
+        }
+        
+        Ok(())
+    }
+    
+    fn execute(&self, command: &str, args: &[String]) -> Result<()> {
+        match command {
+            "search" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing search query"));
+                }
+                
+                let query = &args[0];
+                let mut limit = self.config.max_search_results;
+                let mut backpack = None;
+                let mut algorithm = None;
+                let mut lang = None;
+                let mut where_query = None;
+                let mut format = None;
+                let mut explain = false;
+                let mut since = None;
+                let mut until = None;
+                let mut created = false;
+                let mut project_only = false;
+                let mut interactive = false;
+                
+                // Parse optional arguments
+                let mut i = 1;
                 while i < args.len() {
                     match args[i].as_str() {
-                        "--limit" => {
-                            if i + 1 < args.len() {
-                                limit = args[i + 1].parse()?;
-                                i += 1;
-                            }
+                        "--limit" if i + 1 < args.len() => {
+                            limit = args[i + 1].parse()?;
+                            i += 1;
                         }
-                        "--backpack" => {
-                            if i + 1 < args.len() {
-                                backpack = Some(args[i + 1].as_str());
-                                i += 1;
-                            }
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
                         }
                         "--exact" => {
-                            exact = true;
+                            algorithm = Some(SearchAlgorithm::Literal);
+                        }
+                        "--fuzzy" => {
+                            algorithm = Some(SearchAlgorithm::Fuzzy);
+                        }
+                        "--explain" => {
+                            explain = true;
+                        }
+                        "--lang" if i + 1 < args.len() => {
+                            lang = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--where" if i + 1 < args.len() => {
+                            where_query = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--format" if i + 1 < args.len() => {
+                            format = Some(LauncherFormat::parse(&args[i + 1])?);
+                            i += 1;
+                        }
+                        "--since" if i + 1 < args.len() => {
+                            since = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--until" if i + 1 < args.len() => {
+                            until = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--created" => {
+                            created = true;
+                        }
+                        "--project-only" => {
+                            project_only = true;
+                        }
+                        "--interactive" => {
+                            interactive = true;
                         }
                         _ => { /* Ignore unknown args */ }
                     }
                     i += 1;
                 }
                 
-                let results = self.search(query, limit, backpack, exact)?;
+                let mut filter = EntryFilter::new(lang, where_query)?.dated(since, until, created)?;
+                if project_only {
+                    filter = filter.in_project()?;
+                }
+                let ranked = self.search_ranked(query, limit, backpack, algorithm, &filter)?;
+                
+                // Launchers search as the user types, so only searches made
+                // here go in the history
+                if format.is_none() {
+                    if let Err(e) = StorageManager::new().and_then(|storage| storage.record_search(query)) {
+                        log::debug!("Couldn't record the search: {}", e);
+                    }
+                }
+                let (results, breakdowns): (Vec<_>, Vec<_>) = ranked.into_iter()
+                    .map(|(entry, location, breakdown)| ((entry, location), breakdown))
+                    .unzip();
+                *LAST_RESULTS.lock().unwrap_or_else(|e| e.into_inner()) = results.iter()
+                    .map(|(entry, location)| (entry.id.clone(), location.clone()))
+                    .collect();
+                
+                // Launchers read the results as they are, even when there are none
+                if let Some(format) = format {
+                    print!("{}", format.render(&results)?);
+                    if format != LauncherFormat::Lines {
+                        println!();
+                    }
+                    return Ok(());
+                }
                 
                 if results.is_empty() {
-                    println!("No results found for query: {}", query.bold());
+                    println!("{}", tr!("No results found for query: {}", query.bold()));
                     return Ok(());
                 }
                 
-                println!("Search results for: {}", query.bold());
-                for (i, entry) in results.iter().enumerate() {
-                    println!("{}. {} - {}", i + 1, entry.id.bold(), entry.title);
+                if interactive {
+                    return self.act_on(results);
+                }
+                
+                println!("{}", tr!("Search results for: {}", query.bold()));
+                for (i, ((entry, location), breakdown)) in results.iter().zip(&breakdowns).enumerate() {
+                    println!("{}. {}{} - {}{}", i + 1, entry.id.bold(), location_label(location), entry.title, pin_marker(entry));
+                    if explain {
+                        println!("   {}", format!("score {}", breakdown).dimmed());
+                    }
                 }
             }
             "insert" => {
@@ -276,11 +1292,9 @@ impl Card for CoreCard {
                 let mut i = 2;
                 while i < args.len() {
                     match args[i].as_str() {
-                        "--delimiter" => {
-                            if i + 1 < args.len() {
-                                delimiter = Some(args[i + 1].as_str());
-                                i += 1;
-                            }
+                        "--delimiter" if i + 1 < args.len() => {
+                            delimiter = Some(args[i + 1].as_str());
+                            i += 1;
                         }
                         "--no-confirm" => {
                             no_confirm = true;
@@ -292,10 +1306,73 @@ impl Card for CoreCard {
                 
                 self.insert(entry_id, file_path, delimiter, no_confirm)?;
             }
+            "insert-pick" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing file path"));
+                }
+                
+                let file_path = &args[0];
+                
+                let mut backpack = None;
+                let mut delimiter = None;
+                let mut no_confirm = false;
+                
+                // Parse optional arguments
+                let mut i = 1;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--delimiter" if i + 1 < args.len() => {
+                            delimiter = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--no-confirm" => {
+                            no_confirm = true;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                let (entry_id, _) = self.pick(backpack)?;
+                self.insert(&entry_id, file_path, delimiter, no_confirm)?;
+            }
+            "pin" | "unpin" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing entry ID"));
+                }
+                
+                let mut backpack = None;
+                
+                // Parse optional arguments
+                let mut i = 1;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                self.pin(&args[0], backpack, command == "pin")?;
+            }
             "list" => {
                 let mut include_backpacks = false;
                 let mut backpack = None;
                 let mut json = false;
+                let mut sort = "created";
+                let mut limit = None;
+                let mut lang = None;
+                let mut query = None;
+                let mut since = None;
+                let mut until = None;
+                let mut created = false;
                 
                 // Parse optional arguments
                 let mut i = 0;
@@ -304,21 +1381,270 @@ impl Card for CoreCard {
                         "--include-backpacks" => {
                             include_backpacks = true;
                         }
-                        "--backpack" => {
-                            if i + 1 < args.len() {
-                                backpack = Some(args[i + 1].as_str());
-                                i += 1;
-                            }
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
                         }
                         "--json" => {
                             json = true;
                         }
+                        "--sort" if i + 1 < args.len() => {
+                            sort = args[i + 1].as_str();
+                            i += 1;
+                        }
+                        "--limit" if i + 1 < args.len() => {
+                            limit = Some(args[i + 1].parse()?);
+                            i += 1;
+                        }
+                        "--lang" if i + 1 < args.len() => {
+                            lang = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--where" if i + 1 < args.len() => {
+                            query = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--since" if i + 1 < args.len() => {
+                            since = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--until" if i + 1 < args.len() => {
+                            until = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--created" => {
+                            created = true;
+                        }
                         _ => { /* Ignore unknown args */ }
                     }
                     i += 1;
                 }
                 
-                self.list(include_backpacks, backpack, json)?;
+                let filter = EntryFilter::new(lang, query)?.dated(since, until, created)?;
+                self.list(include_backpacks, backpack, json, sort, limit, &filter)?;
+            }
+            "show" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing entry ID"));
+                }
+                
+                let mut backpack = None;
+                let mut lines = None;
+                let mut pager = true;
+                let mut qr = false;
+                
+                // Parse optional arguments
+                let mut i = 1;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--lines" if i + 1 < args.len() => {
+                            lines = Some(LineRange::parse(&args[i + 1])?);
+                            i += 1;
+                        }
+                        "--head" if i + 1 < args.len() => {
+                            match args[i + 1].parse()? {
+                                0 => return Err(anyhow!("--head needs at least one line")),
+                                count => lines = Some(LineRange::head(count)),
+                            }
+                            i += 1;
+                        }
+                        "--no-pager" => {
+                            pager = false;
+                        }
+                        "--qr" => {
+                            qr = true;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                if qr {
+                    self.show_qr(&args[0], backpack, lines)?;
+                } else {
+                    self.show(&args[0], backpack, lines, pager)?;
+                }
+            }
+            "copy" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing entry ID"));
+                }
+                
+                let mut backpack = None;
+                let mut lines = None;
+                
+                let mut i = 1;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--lines" if i + 1 < args.len() => {
+                            lines = Some(LineRange::parse(&args[i + 1])?);
+                            i += 1;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                self.copy(&args[0], backpack, lines)?;
+            }
+            "edit" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing entry ID"));
+                }
+                
+                let mut backpack = None;
+                let mut force = false;
+                
+                // Parse optional arguments
+                let mut i = 1;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--force" => {
+                            force = true;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                self.edit(&args[0], backpack, force)?;
+            }
+            "related" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing entry ID"));
+                }
+                
+                let mut backpack = None;
+                let mut limit = 5;
+                let mut json = false;
+                
+                // Parse optional arguments
+                let mut i = 1;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--limit" if i + 1 < args.len() => {
+                            limit = args[i + 1].parse()?;
+                            i += 1;
+                        }
+                        "--json" => json = true,
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                self.related(&args[0], backpack, limit, json)?;
+            }
+            "export" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing entry ID"));
+                }
+                
+                let mut output = None;
+                let mut backpack = None;
+                
+                // Parse optional arguments
+                let mut i = 1;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--output" if i + 1 < args.len() => {
+                            output = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                self.export(&args[0], output, backpack)?;
+            }
+            "publish" => {
+                let mut output = None;
+                let mut backpacks = Vec::new();
+                
+                // Parse optional arguments
+                let mut i = 0;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--output" if i + 1 < args.len() => {
+                            output = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--backpack" if i + 1 < args.len() => {
+                            backpacks.push(args[i + 1].clone());
+                            i += 1;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                let output = output.ok_or_else(|| anyhow!("Missing output directory"))?;
+                self.publish(output, &backpacks)?;
+            }
+            "dedupe" => {
+                let mut backpack = None;
+                let mut yes = false;
+                
+                // Parse optional arguments
+                let mut i = 0;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--yes" => {
+                            yes = true;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                self.dedupe(backpack, yes)?;
+            }
+            "stats-snippets" => {
+                let mut backpack = None;
+                let mut limit = 10;
+                
+                // Parse optional arguments
+                let mut i = 0;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        "--limit" if i + 1 < args.len() => {
+                            limit = args[i + 1].parse()?;
+                            i += 1;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                self.stats_snippets(backpack, limit)?;
             }
             "create-backpack" => {
                 if args.is_empty() {
@@ -332,11 +1658,9 @@ impl Card for CoreCard {
                 let mut i = 1;
                 while i < args.len() {
                     match args[i].as_str() {
-                        "--description" => {
-                            if i + 1 < args.len() {
-                                description = Some(args[i + 1].as_str());
-                                i += 1;
-                            }
+                        "--description" if i + 1 < args.len() => {
+                            description = Some(args[i + 1].as_str());
+                            i += 1;
                         }
                         _ => { /* Ignore unknown args */ }
                     }
@@ -345,6 +1669,30 @@ impl Card for CoreCard {
                 
                 self.create_backpack(name, description)?;
             }
+            "rename-backpack" => {
+                if args.len() < 2 {
+                    return Err(anyhow!("Missing backpack name"));
+                }
+                
+                self.rename_backpack(&args[0], &args[1])?;
+            }
+            "delete-backpack" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing backpack name"));
+                }
+                
+                let move_entries = args[1..].iter().any(|a| a == "--move-entries");
+                let force = args[1..].iter().any(|a| a == "--force");
+                
+                self.delete_backpack(&args[0], move_entries, force)?;
+            }
+            "describe-backpack" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing backpack name"));
+                }
+                
+                self.describe_backpack(&args[0], args.get(1).map(|s| s.as_str()))?;
+            }
             "remove" => {
                 if args.is_empty() {
                     return Err(anyhow!("Missing entry ID"));
@@ -361,11 +1709,9 @@ impl Card for CoreCard {
                         "--force" => {
                             force = true;
                         }
-                        "--backpack" => {
-                            if i + 1 < args.len() {
-                                backpack = Some(args[i + 1].as_str());
-                                i += 1;
-                            }
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
                         }
                         _ => { /* Ignore unknown args */ }
                     }
@@ -374,6 +1720,86 @@ impl Card for CoreCard {
                 
                 self.remove(id, force, backpack)?;
             }
+            "alias-add" => {
+                if args.len() < 2 {
+                    return Err(anyhow!("Missing alias name or entry ID"));
+                }
+                
+                let mut backpack = None;
+                
+                // Parse optional arguments
+                let mut i = 2;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 1;
+                        }
+                        _ => { /* Ignore unknown args */ }
+                    }
+                    i += 1;
+                }
+                
+                self.add_alias(&args[0], &args[1], backpack)?;
+            }
+            "alias-remove" => {
+                if args.is_empty() {
+                    return Err(anyhow!("Missing alias name"));
+                }
+                
+                self.remove_alias(&args[0])?;
+            }
+            "alias-list" => {
+                self.list_aliases()?;
+            }
+            "execute" => {
+                let mut options = utils::exec::ExecOptions::default();
+                let mut backpack = None;
+                let mut save_output = false;
+                
+                // Options come first; everything after the script goes to the script
+                let mut i = 0;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--timeout" if i + 1 < args.len() => {
+                            let seconds: f64 = args[i + 1].parse()
+                                .map_err(|_| anyhow!("Invalid timeout: {}", args[i + 1]))?;
+                            options.timeout = Some(utils::exec::timeout(seconds)?);
+                            i += 2;
+                        }
+                        "--cwd" if i + 1 < args.len() => {
+                            options.cwd = Some(utils::expand_path(&args[i + 1])?);
+                            i += 2;
+                        }
+                        "--env" if i + 1 < args.len() => {
+                            options.env.push(utils::exec::parse_env(&args[i + 1])?);
+                            i += 2;
+                        }
+                        "--backpack" if i + 1 < args.len() => {
+                            backpack = Some(args[i + 1].as_str());
+                            i += 2;
+                        }
+                        "--with" if i + 1 < args.len() => {
+                            options.interpreter = Some(args[i + 1].clone());
+                            i += 2;
+                        }
+                        "--no-network" => {
+                            options.no_network = true;
+                            i += 1;
+                        }
+                        "--save-output" => {
+                            save_output = true;
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                if i >= args.len() {
+                    return Err(anyhow!("Missing script name or ID"));
+                }
+                
+                self.execute_script(&args[i], &args[i + 1..], backpack, &options, save_output)?;
+            }
             _ => {
                 return Err(anyhow!("Unknown command: {}", command));
             }
@@ -384,35 +1810,215 @@ impl Card for CoreCard {
     
     fn commands(&self) -> Vec<CardCommand> {
         vec![
+            CardCommand {
+                name: "execute".to_string(),
+                description: "Run a stored script".to_string(),
+                usage: "execute [--timeout SECS] [--cwd DIR] [--env KEY=VAL]... [--no-network] [--with INTERPRETER] [--save-output] [--backpack NAME] <name_or_id> [args...]".to_string(),
+            },
             CardCommand {
                 name: "search".to_string(),
                 description: "Search for entries".to_string(),
-                usage: "search <query> [--limit N] [--backpack NAME] [--exact]".to_string(),
+                usage: "search <query> [--limit N] [--backpack NAME] [--exact|--fuzzy] [--explain] [--lang LANG] [--where QUERY] [--since TIME] [--until TIME] [--created] [--project-only] [--interactive] [--format alfred|raycast|lines]".to_string(),
             },
             CardCommand {
                 name: "insert".to_string(),
-                description: "Insert an entry into a file".to_string(),
+                description: "Insert an entry into a file, or write it to stdout with -".to_string(),
                 usage: "insert <entry_id> <file_path> [--delimiter TEXT] [--no-confirm]".to_string(),
             },
             CardCommand {
                 name: "list".to_string(),
                 description: "List all entries".to_string(),
-                usage: "list [--include-backpacks] [--backpack NAME] [--json]".to_string(),
+                usage: "list [--include-backpacks] [--backpack NAME] [--json] [--sort created|used|title] [--limit N] [--lang LANG] [--where QUERY] [--since TIME] [--until TIME] [--created]".to_string(),
+            },
+            CardCommand {
+                name: "insert-pick".to_string(),
+                description: "Pick an entry interactively and insert it into a file".to_string(),
+                usage: "insert-pick <file> [--backpack NAME] [--delimiter TEXT] [--no-confirm]".to_string(),
+            },
+            CardCommand {
+                name: "pin".to_string(),
+                description: "Pin an entry to the top of listings".to_string(),
+                usage: "pin <id> [--backpack NAME]".to_string(),
+            },
+            CardCommand {
+                name: "unpin".to_string(),
+                description: "Unpin an entry".to_string(),
+                usage: "unpin <id> [--backpack NAME]".to_string(),
+            },
+            CardCommand {
+                name: "show".to_string(),
+                description: "Show an entry's content".to_string(),
+                usage: "show <id> [--backpack NAME] [--lines START:END | --head N] [--no-pager] [--qr]".to_string(),
+            },
+            CardCommand {
+                name: "copy".to_string(),
+                description: "Copy an entry's content to the clipboard".to_string(),
+                usage: "copy <id> [--backpack NAME] [--lines START:END]".to_string(),
+            },
+            CardCommand {
+                name: "edit".to_string(),
+                description: "Edit an entry's content, confirming the changes".to_string(),
+                usage: "edit <id> [--backpack NAME] [--force]".to_string(),
+            },
+            CardCommand {
+                name: "related".to_string(),
+                description: "List the entries most related to one".to_string(),
+                usage: "related <id> [--backpack NAME] [--limit N] [--json]".to_string(),
+            },
+            CardCommand {
+                name: "export".to_string(),
+                description: "Write an entry's content to a file or stdout".to_string(),
+                usage: "export <id> [--output PATH] [--backpack NAME]".to_string(),
+            },
+            CardCommand {
+                name: "publish".to_string(),
+                description: "Render backpacks to a static HTML site".to_string(),
+                usage: "publish --output DIR [--backpack NAME]...".to_string(),
+            },
+            CardCommand {
+                name: "dedupe".to_string(),
+                description: "Find and merge duplicate entries".to_string(),
+                usage: "dedupe [--backpack NAME] [--yes]".to_string(),
+            },
+            CardCommand {
+                name: "stats-snippets".to_string(),
+                description: "Show snippet usage statistics".to_string(),
+                usage: "stats-snippets [--backpack NAME] [--limit N]".to_string(),
             },
             CardCommand {
                 name: "create-backpack".to_string(),
                 description: "Create a new backpack".to_string(),
                 usage: "create-backpack <name> [--description TEXT]".to_string(),
             },
+            CardCommand {
+                name: "rename-backpack".to_string(),
+                description: "Rename a backpack".to_string(),
+                usage: "rename-backpack <old> <new>".to_string(),
+            },
+            CardCommand {
+                name: "delete-backpack".to_string(),
+                description: "Delete a backpack".to_string(),
+                usage: "delete-backpack <name> [--move-entries] [--force]".to_string(),
+            },
+            CardCommand {
+                name: "describe-backpack".to_string(),
+                description: "Set or clear a backpack's description".to_string(),
+                usage: "describe-backpack <name> [description]".to_string(),
+            },
             CardCommand {
                 name: "remove".to_string(),
                 description: "Remove an entry".to_string(),
                 usage: "remove <id> [--force] [--backpack NAME]".to_string(),
             },
+            CardCommand {
+                name: "alias-add".to_string(),
+                description: "Assign an alias to an entry".to_string(),
+                usage: "alias-add <name> <id> [--backpack NAME]".to_string(),
+            },
+            CardCommand {
+                name: "alias-remove".to_string(),
+                description: "Remove an entry alias".to_string(),
+                usage: "alias-remove <name>".to_string(),
+            },
+            CardCommand {
+                name: "alias-list".to_string(),
+                description: "List all entry aliases".to_string(),
+                usage: "alias-list".to_string(),
+            },
         ]
     }
     
     fn cleanup(&mut self) -> Result<()> {
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::{ErrorCode, PocketError};
+    use crate::models::ContentType;
+    
+    #[test]
+    fn an_edit_that_loses_the_race_is_kept_in_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data/entries")).unwrap();
+        let storage = StorageManager::at(dir.path());
+        let entry = Entry::new("note".to_string(), ContentType::Text, None, Vec::new());
+        storage.save_entry(&entry, "original", None).unwrap();
+        let (loaded, _) = storage.load_entry(&entry.id, None).unwrap();
+        
+        storage.update_entry(&entry.id, None, Some("theirs"), |_| {}).unwrap();
+        let error = save_edit(&storage, &loaded, None, "mine").unwrap_err();
+        let message = error.to_string();
+        assert_eq!(PocketError::card("Failed to edit entry", error).code(), ErrorCode::Conflict);
+        assert_eq!(storage.load_entry(&entry.id, None).unwrap().1, "theirs");
+        let kept = PathBuf::from(message.rsplit_once("Your edited version is in ").unwrap().1);
+        assert_eq!(fs::read_to_string(&kept).unwrap(), "mine");
+        fs::remove_file(kept).unwrap();
+        
+        save_edit(&storage, &storage.load_entry_metadata(&entry.id, None).unwrap(), None, "mine").unwrap();
+        assert_eq!(storage.load_entry(&entry.id, None).unwrap().1, "mine");
+    }
+    
+    #[test]
+    fn dated_filters_need_a_range_something_can_fall_in() {
+        assert!(EntryFilter::default().dated(Some("2024-05-02"), Some("2024-05-01"), false).is_err());
+        assert!(EntryFilter::default().dated(Some("7d"), Some("2w"), false).is_err());
+        assert!(EntryFilter::default().dated(Some("soon"), None, false).is_err());
+        
+        let filter = EntryFilter::default().dated(Some("2024-05-01"), Some("2024-05-01"), true).unwrap();
+        let mut entry = Entry::new("note".to_string(), ContentType::Text, None, Vec::new());
+        entry.created_at = filter.since.unwrap() + chrono::Duration::hours(23);
+        assert!(filter.matches(&entry, None));
+        entry.created_at = filter.until.unwrap();
+        assert!(!filter.matches(&entry, None));
+    }
+    
+    #[test]
+    fn keeps_the_most_used_or_oldest_duplicate() {
+        let mut old = Entry::new("old".to_string(), ContentType::Text, None, Vec::new());
+        old.created_at -= chrono::Duration::days(1);
+        let new = Entry::new("new".to_string(), ContentType::Text, None, Vec::new());
+        let mut group = vec![(new, None), (old, Some("work".to_string()))];
+        assert_eq!(pick_survivor(&group), 1);
+        
+        group[0].0.usage_count = 2;
+        assert_eq!(pick_survivor(&group), 0);
+    }
+    
+    #[test]
+    fn merging_duplicates_carries_everything_over_to_the_survivor() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data/entries")).unwrap();
+        let storage = StorageManager::at(dir.path());
+        storage.create_backpack(&Backpack { name: "work".to_string(), description: None, created_at: chrono::Utc::now() }).unwrap();
+        
+        let mut kept = Entry::new("kept".to_string(), ContentType::Text, None, vec!["shell".to_string()]);
+        kept.usage_count = 1;
+        storage.save_entry(&kept, "echo hi", None).unwrap();
+        let mut removed = Entry::new("removed".to_string(), ContentType::Text, None, vec!["shell".to_string(), "greeting".to_string()]);
+        removed.usage_count = 3;
+        storage.save_entry(&removed, "echo hi\n", Some("work")).unwrap();
+        storage.set_pinned(&removed.id, Some("work"), true).unwrap();
+        let aliases = BTreeMap::from([
+            ("hi".to_string(), EntryAlias { id: removed.id.clone(), backpack: Some("work".to_string()) }),
+            ("kept".to_string(), EntryAlias { id: kept.id.clone(), backpack: None }),
+        ]);
+        storage.save_aliases(&aliases).unwrap();
+        
+        let group = vec![(kept.clone(), None), (storage.load_entry_metadata(&removed.id, Some("work")).unwrap(), Some("work".to_string()))];
+        assert_eq!(merge_duplicates(&storage, group, 0).unwrap(), 1);
+        
+        assert!(storage.load_entry_metadata(&removed.id, Some("work")).is_err());
+        let (survivor, content) = storage.load_entry(&kept.id, None).unwrap();
+        assert_eq!(content, "echo hi");
+        assert_eq!(survivor.tags, vec!["shell".to_string(), "greeting".to_string()]);
+        assert_eq!(survivor.usage_count, 4);
+        assert!(survivor.pinned);
+        assert_eq!(storage.pinned_ids().unwrap(), [kept.id.clone()].into_iter().collect());
+        for alias in storage.load_aliases().unwrap().values() {
+            assert_eq!((alias.id.as_str(), alias.backpack.as_deref()), (kept.id.as_str(), None));
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Lifecycle events for cards
+//!
+//! Core commands emit events as they change storage. The events are queued
+//! and delivered to every enabled card's `on_event` once the command that
+//! emitted them finishes. They're also kept for workflow triggers, which
+//! look at them once the whole command line has run.
+
+use std::sync::Mutex;
+use serde::Serialize;
+
+/// Something that happened to the pocket
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// An entry was added
+    EntryAdded {
+        id: String,
+        backpack: Option<String>,
+    },
+    
+    /// An entry's content or metadata changed
+    EntryUpdated {
+        id: String,
+        backpack: Option<String>,
+    },
+    
+    /// An entry was removed
+    EntryRemoved {
+        id: String,
+        backpack: Option<String>,
+    },
+    
+    /// A backpack was created
+    BackpackCreated {
+        name: String,
+    },
+    
+    /// A backpack was renamed
+    BackpackRenamed {
+        old_name: String,
+        new_name: String,
+    },
+    
+    /// A backpack was deleted
+    BackpackDeleted {
+        name: String,
+    },
+}
+
+impl Event {
+    /// The event's name, e.g. `entry_added`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::EntryAdded { .. } => "entry_added",
+            Event::EntryUpdated { .. } => "entry_updated",
+            Event::EntryRemoved { .. } => "entry_removed",
+            Event::BackpackCreated { .. } => "backpack_created",
+            Event::BackpackRenamed { .. } => "backpack_renamed",
+            Event::BackpackDeleted { .. } => "backpack_deleted",
+        }
+    }
+    
+    /// The backpack the event happened in, if any (None for the general pocket)
+    pub fn backpack(&self) -> Option<&str> {
+        match self {
+            Event::EntryAdded { backpack, .. }
+            | Event::EntryUpdated { backpack, .. }
+            | Event::EntryRemoved { backpack, .. } => backpack.as_deref(),
+            Event::BackpackCreated { name } | Event::BackpackDeleted { name } => Some(name),
+            Event::BackpackRenamed { new_name, .. } => Some(new_name),
+        }
+    }
+}
+
+/// Every event name, for checking what a trigger listens for
+pub const EVENT_NAMES: &[&str] = &[
+    "entry_added",
+    "entry_updated",
+    "entry_removed",
+    "backpack_created",
+    "backpack_renamed",
+    "backpack_deleted",
+];
+
+/// Events waiting to be delivered
+static PENDING: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+/// Events workflow triggers haven't looked at yet
+static FIRED: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+/// Queues an event for delivery to cards and workflow triggers
+pub fn emit(event: Event) {
+    log::debug!("Event: {}", event.name());
+    FIRED.lock().unwrap_or_else(|e| e.into_inner()).push(event.clone());
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).push(event);
+}
+
+/// Takes every queued event
+pub(crate) fn take_pending() -> Vec<Event> {
+    std::mem::take(&mut *PENDING.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Takes every event emitted since the last call, for workflow triggers
+pub(crate) fn take_fired() -> Vec<Event> {
+    std::mem::take(&mut *FIRED.lock().unwrap_or_else(|e| e.into_inner()))
+}
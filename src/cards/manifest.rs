@@ -0,0 +1,247 @@
+//! Card manifests
+//!
+//! Every external card ships a `card.toml` next to its `Cargo.toml` describing
+//! the card and the commands it provides.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use anyhow::{Result, Context, bail};
+
+/// Name of the manifest file in a card's directory
+pub const MANIFEST_FILE: &str = "card.toml";
+
+/// Contents of a card's `card.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardManifest {
+    /// Information about the card
+    pub card: CardInfo,
+    
+    /// Commands provided by the card, mapped to their descriptions
+    #[serde(default)]
+    pub commands: BTreeMap<String, String>,
+    
+    /// Schema for the card's options, keyed by dotted option path
+    #[serde(default)]
+    pub schema: HashMap<String, OptionSchema>,
+    
+    /// Access the card needs
+    #[serde(default)]
+    pub permissions: CardPermissions,
+    
+    /// How to start the card, for cards that run as their own process
+    #[serde(default)]
+    pub process: Option<ProcessInfo>,
+    
+    /// Command invocations checked by `pocket cards test`
+    #[serde(default)]
+    pub tests: Vec<CardTest>,
+}
+
+/// A `[[tests]]` entry: run a command and check what it prints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardTest {
+    /// Name shown in the report
+    pub name: String,
+    
+    /// Card command to run
+    pub command: String,
+    
+    /// Arguments to pass
+    #[serde(default)]
+    pub args: Vec<String>,
+    
+    /// Exact expected output, ignoring surrounding whitespace
+    #[serde(default)]
+    pub output: Option<String>,
+    
+    /// Text the output must contain
+    #[serde(default)]
+    pub contains: Vec<String>,
+    
+    /// Whether the command is expected to fail
+    #[serde(default)]
+    pub fails: bool,
+}
+
+/// The `[process]` section of a manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    /// Program and arguments to start, run from the card's directory
+    pub command: Vec<String>,
+}
+
+/// Access a card asks for in its manifest, or that the user granted it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CardPermissions {
+    /// Paths the card reads or writes
+    pub filesystem: Vec<String>,
+    
+    /// Whether the card talks to the network
+    pub network: bool,
+    
+    /// Whether the card starts other processes
+    pub process: bool,
+}
+
+impl CardPermissions {
+    /// Whether no access is asked for
+    pub fn is_empty(&self) -> bool {
+        self.filesystem.is_empty() && !self.network && !self.process
+    }
+    
+    /// Whether these permissions include everything in `requested`
+    pub fn covers(&self, requested: &CardPermissions) -> bool {
+        requested.filesystem.iter().all(|path| self.filesystem.contains(path))
+            && (self.network || !requested.network)
+            && (self.process || !requested.process)
+    }
+    
+    /// One line per permission, for showing to the user
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.filesystem.iter()
+            .map(|path| format!("access files in {}", path))
+            .collect();
+        if self.network {
+            lines.push("use the network".to_string());
+        }
+        if self.process {
+            lines.push("run other programs".to_string());
+        }
+        lines
+    }
+}
+
+/// Declared type and constraints of one card option
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionSchema {
+    /// JSON type: string, integer, number, boolean, array or object
+    #[serde(rename = "type")]
+    pub kind: String,
+    
+    /// What the option does
+    #[serde(default)]
+    pub description: String,
+    
+    /// Values the option may take; any value of the right type when empty
+    #[serde(default)]
+    pub allowed: Vec<serde_json::Value>,
+}
+
+/// The `[card]` section of a manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CardInfo {
+    /// Name of the card, which must match its directory name
+    pub name: String,
+    
+    /// Version of the card
+    pub version: String,
+    
+    /// Description of the card
+    #[serde(default)]
+    pub description: String,
+    
+    /// Author of the card
+    #[serde(default)]
+    pub author: String,
+    
+    /// Whether the card is enabled when first registered
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl CardManifest {
+    /// Loads and validates the manifest in a card directory
+    pub fn load(card_dir: &Path, expected_name: &str) -> Result<Self> {
+        let path = card_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            bail!("No {} found in {}", MANIFEST_FILE, card_dir.display());
+        }
+        
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let manifest: CardManifest = toml::from_str(&contents)
+            .with_context(|| format!("Invalid {}", path.display()))?;
+        
+        manifest.validate(expected_name)?;
+        Ok(manifest)
+    }
+    
+    /// Checks that the manifest describes a usable card
+    pub fn validate(&self, expected_name: &str) -> Result<()> {
+        if self.card.name != expected_name {
+            bail!("{} declares card '{}', expected '{}'", MANIFEST_FILE, self.card.name, expected_name);
+        }
+        
+        if self.card.version.trim().is_empty() {
+            bail!("{} is missing a version", MANIFEST_FILE);
+        }
+        
+        Ok(())
+    }
+}
+
+/// Check that a card name is usable as a directory and library name
+pub fn validate_card_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("Invalid card name '{}': use letters, digits, '-' and '_'", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_generated_manifest() {
+        let manifest: CardManifest = toml::from_str(r#"[card]
+name = "hello-card"
+version = "0.1.0"
+description = "Says hello"
+author = ""
+enabled = true
+
+[commands]
+hello = "A simple hello command"
+"#).unwrap();
+        
+        assert!(manifest.validate("hello-card").is_ok());
+        assert!(manifest.validate("other").is_err());
+        assert_eq!(manifest.commands.get("hello").map(String::as_str), Some("A simple hello command"));
+    }
+    
+    #[test]
+    fn grants_cover_requests() {
+        let requested = CardPermissions {
+            filesystem: vec!["~/notes".to_string()],
+            network: true,
+            process: false,
+        };
+        
+        assert!(requested.covers(&requested));
+        assert!(!CardPermissions::default().covers(&requested));
+        assert!(CardPermissions::default().covers(&CardPermissions::default()));
+        
+        let broader = CardPermissions {
+            filesystem: vec!["~/notes".to_string(), "/tmp".to_string()],
+            network: true,
+            process: true,
+        };
+        assert!(broader.covers(&requested));
+        assert!(!requested.covers(&broader));
+    }
+    
+    #[test]
+    fn rejects_bad_names() {
+        assert!(validate_card_name("my-card_2").is_ok());
+        assert!(validate_card_name("../evil").is_err());
+        assert!(validate_card_name("").is_err());
+    }
+}
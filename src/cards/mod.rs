@@ -7,12 +7,28 @@ pub mod backup;
 pub mod snippet;
 pub mod core;
 pub mod blend;
+pub mod events;
+pub mod manifest;
+pub mod options;
+pub mod process;
+pub mod registry;
+pub mod testing;
+pub mod watcher;
+
+pub use events::Event;
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::io::IsTerminal;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use std::fs;
 use serde::{Deserialize, Serialize};
 use anyhow::{Result, anyhow, bail};
+use crate::utils::{atomic, suggest};
+
+/// Rounds of event delivery before giving up on cards that keep emitting events
+const MAX_EVENT_ROUNDS: usize = 10;
 
 /// Trait that all cards must implement
 pub trait Card: Send + Sync {
@@ -23,10 +39,10 @@ pub trait Card: Send + Sync {
     fn version(&self) -> &str;
     
     /// Returns a description of the card
-    fn _description(&self) -> &str;
+    fn description(&self) -> &str;
     
     /// Initializes the card with the given configuration
-    fn _initialize(&mut self, config: &CardConfig) -> Result<()>;
+    fn initialize(&mut self, config: &CardConfig) -> Result<()>;
     
     /// Executes a command provided by the card
     fn execute(&self, command: &str, args: &[String]) -> Result<()>;
@@ -34,6 +50,11 @@ pub trait Card: Send + Sync {
     /// Returns a list of commands provided by the card
     fn commands(&self) -> Vec<CardCommand>;
     
+    /// Handles a lifecycle event, such as an entry being added. Does nothing by default
+    fn on_event(&self, _event: &Event) -> Result<()> {
+        Ok(())
+    }
+    
     /// Cleans up any resources used by the card
     fn cleanup(&mut self) -> Result<()>;
 }
@@ -50,6 +71,10 @@ pub struct CardConfig {
     /// Additional configuration options for the card
     #[serde(default)]
     pub options: HashMap<String, serde_json::Value>,
+    
+    /// Permissions the user granted the card, if it asked for any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<manifest::CardPermissions>,
 }
 
 /// A command provided by a card
@@ -78,6 +103,54 @@ pub struct CardManager {
     
     /// Names of built-in cards that should always be enabled
     builtin_card_names: Vec<String>,
+    
+    /// Where each external card was loaded from
+    external: HashMap<String, ExternalCard>,
+    
+    /// Watches the wallet when auto-reload is on
+    watcher: Option<watcher::WalletWatcher>,
+}
+
+/// What an external card was loaded from
+#[derive(Debug, Clone, PartialEq)]
+enum CardSource {
+    /// A dynamic library built from the card
+    Library(PathBuf),
+    
+    /// The card directory of a card that runs as its own process
+    Process(PathBuf),
+}
+
+/// Bookkeeping for a loaded external card
+struct ExternalCard {
+    /// What the card was loaded from
+    source: CardSource,
+    
+    /// When `source` was last modified, as of loading
+    modified: Option<SystemTime>,
+    
+    /// Keeps a dynamic card's code mapped. Must outlive the card itself
+    _library: Option<libloading::Library>,
+}
+
+/// What changed during a reload
+#[derive(Debug, Default)]
+pub struct ReloadSummary {
+    /// Cards loaded for the first time
+    pub loaded: Vec<String>,
+    
+    /// Cards reloaded because they changed
+    pub reloaded: Vec<String>,
+    
+    /// Cards unloaded because they were removed or no longer load
+    pub unloaded: Vec<String>,
+}
+
+impl ReloadSummary {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.loaded.is_empty() && self.reloaded.is_empty() && self.unloaded.is_empty()
+    }
 }
 
 impl CardManager {
@@ -93,23 +166,40 @@ impl CardManager {
                 "core".to_string(),
                 "blend".to_string(),
             ],
+            external: HashMap::new(),
+            watcher: None,
         }
     }
     
     /// Load all cards
     pub fn load_cards(&mut self) -> Result<()> {
-        // First register built-in cards - these should always be available
-        self.register_builtin_cards()?;
-        
-        // Load card configurations, which will handle both built-in and external cards
+        // Load card configurations first, which will handle both built-in and external cards,
+        // so registering the built-in cards doesn't overwrite stored options
         self.load_configs()?;
+        self.migrate_legacy_plugins()?;
+        
+        // Register built-in cards - these should always be available
+        self.register_builtin_cards()?;
         
         // Load external cards from wallet directory
         self.load_external_cards()?;
         
+        self.initialize_cards();
+        
         Ok(())
     }
     
+    /// Hands each card its stored options
+    fn initialize_cards(&mut self) {
+        for (name, card) in self.cards.iter_mut() {
+            if let Some(config) = self.configs.get(name) {
+                if let Err(e) = card.initialize(config) {
+                    log::error!("Failed to initialize card {}: {}", name, e);
+                }
+            }
+        }
+    }
+    
     /// Load card configurations from the card directory
     fn load_configs(&mut self) -> Result<()> {
         let config_path = self.card_dir.join("cards.json");
@@ -121,6 +211,7 @@ impl CardManager {
                     name: card_name.clone(),
                     enabled: true, // Built-in cards are always enabled by default
                     options: HashMap::new(),
+                    permissions: None,
                 });
             } else {
                 // Ensure built-in cards are always enabled
@@ -132,7 +223,8 @@ impl CardManager {
         if !config_path.exists() {
             // Create a default configuration if none exists
             let json = serde_json::to_string_pretty(&self.configs)?;
-            std::fs::write(&config_path, json)?;
+            fs::create_dir_all(&self.card_dir)?;
+            atomic::write(&config_path, json)?;
             return Ok(());
         }
         
@@ -162,13 +254,65 @@ impl CardManager {
                 
                 // Write the corrected configuration back to the file
                 let json = serde_json::to_string_pretty(&self.configs)?;
-                std::fs::write(&config_path, json)?;
+                atomic::write(&config_path, json)?;
             }
         }
         
         Ok(())
     }
     
+    /// Folds configs from the old plugin system (`~/.pocket/plugins/plugins.json`)
+    /// into the card configs, then renames the old file so this only happens once.
+    /// Plugins and cards share a config shape; the old backup plugin becomes the
+    /// backup card.
+    fn migrate_legacy_plugins(&mut self) -> Result<()> {
+        let legacy_path = self.card_dir.parent().unwrap_or(&self.card_dir).join("plugins").join("plugins.json");
+        if !legacy_path.exists() {
+            return Ok(());
+        }
+        
+        /// A config written by the old plugin manager
+        #[derive(Deserialize)]
+        struct LegacyPluginConfig {
+            #[serde(default)]
+            enabled: Option<bool>,
+            #[serde(default)]
+            options: HashMap<String, serde_json::Value>,
+        }
+        
+        let json = fs::read_to_string(&legacy_path)?;
+        let legacy: HashMap<String, LegacyPluginConfig> = match serde_json::from_str(&json) {
+            Ok(legacy) => legacy,
+            Err(e) => {
+                log::warn!("Couldn't migrate {}: {}", legacy_path.display(), e);
+                return Ok(());
+            }
+        };
+        
+        for (name, plugin) in legacy {
+            let is_builtin = self.is_builtin_card(&name);
+            let config = self.configs.entry(name.clone()).or_insert_with(|| CardConfig {
+                name: name.clone(),
+                enabled: plugin.enabled.unwrap_or(true),
+                options: HashMap::new(),
+                permissions: None,
+            });
+            
+            // Options already set on the card win over the plugin's
+            for (key, value) in plugin.options {
+                config.options.entry(key).or_insert(value);
+            }
+            if is_builtin {
+                config.enabled = true;
+            }
+            log::info!("Migrated plugin {} to a card", name);
+        }
+        
+        self.save_configs()?;
+        fs::rename(&legacy_path, legacy_path.with_extension("json.migrated"))?;
+        Ok(())
+    }
+    
     /// Check if a card is a built-in card
     fn is_builtin_card(&self, name: &str) -> bool {
         self.builtin_card_names.contains(&name.to_string())
@@ -178,7 +322,8 @@ impl CardManager {
     pub fn save_configs(&self) -> Result<()> {
         let config_path = self.card_dir.join("cards.json");
         let json = serde_json::to_string_pretty(&self.configs)?;
-        std::fs::write(&config_path, json)?;
+        fs::create_dir_all(&self.card_dir)?;
+        atomic::write(&config_path, json)?;
         Ok(())
     }
     
@@ -232,6 +377,7 @@ impl CardManager {
                 name: name.to_string(),
                 enabled: true,
                 options: HashMap::new(),
+                permissions: None,
             };
             self.configs.insert(name.to_string(), config);
             self.save_configs()?;
@@ -283,6 +429,18 @@ impl CardManager {
             .collect()
     }
     
+    /// Cards with a config but nothing loaded, and whether their wallet
+    /// directory is still there
+    pub fn unloaded_cards(&self) -> Vec<(String, bool)> {
+        let wallet_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet");
+        let mut unloaded: Vec<(String, bool)> = self.configs.keys()
+            .filter(|name| !self.cards.contains_key(*name))
+            .map(|name| (name.clone(), wallet_dir.join(name).is_dir()))
+            .collect();
+        unloaded.sort();
+        unloaded
+    }
+    
     /// Enables a card by name
     pub fn enable_card(&mut self, name: &str) -> Result<()> {
         if let Some(config) = self.configs.get_mut(name) {
@@ -290,7 +448,7 @@ impl CardManager {
             self.save_configs()?;
             Ok(())
         } else {
-            anyhow::bail!("Card '{}' not found", name)
+            Err(self.card_not_found(name))
         }
     }
     
@@ -306,7 +464,7 @@ impl CardManager {
             self.save_configs()?;
             Ok(())
         } else {
-            anyhow::bail!("Card '{}' not found", name)
+            Err(self.card_not_found(name))
         }
     }
     
@@ -325,8 +483,10 @@ impl CardManager {
                 return Err(anyhow::anyhow!("Card '{}' is disabled", card_name));
             }
             
-            // Execute the command
-            card.execute(command, args)
+            // Execute the command, then let cards react to whatever it did
+            let result = card.execute(command, args);
+            self.dispatch_events();
+            result
         } else {
             // Check if the card exists in the configuration but is not loaded
             if self.configs.contains_key(card_name) {
@@ -335,10 +495,43 @@ impl CardManager {
                 return Err(anyhow::anyhow!("Card '{}' is registered but not loaded. Try rebuilding the card with: pocket cards build {}", card_name, card_name));
             }
             
-            Err(anyhow::anyhow!("Card '{}' not found", card_name))
+            Err(self.card_not_found(card_name))
         }
     }
     
+    /// The error for a card that doesn't exist, suggesting ones that do
+    fn card_not_found(&self, name: &str) -> anyhow::Error {
+        let hint = suggest::did_you_mean(name, self.configs.keys().chain(self.cards.keys()).map(String::as_str));
+        anyhow!("Card '{}' not found{}", name, hint)
+    }
+    
+    /// Delivers queued events to every enabled card
+    pub fn dispatch_events(&self) {
+        let mut names: Vec<&String> = self.cards.keys()
+            .filter(|name| self.configs.get(*name).is_some_and(|c| c.enabled))
+            .collect();
+        names.sort();
+        
+        // Handlers may emit events of their own, so keep going until the queue is empty
+        for _ in 0..MAX_EVENT_ROUNDS {
+            let pending = events::take_pending();
+            if pending.is_empty() {
+                return;
+            }
+            
+            for event in &pending {
+                for name in &names {
+                    if let Err(e) = self.cards[*name].on_event(event) {
+                        log::warn!("Card {} failed to handle {}: {}", name, event.name(), e);
+                    }
+                }
+            }
+        }
+        
+        log::warn!("Cards kept emitting events; dropping the rest");
+        events::take_pending();
+    }
+    
     /// List all commands for all cards
     pub fn list_commands(&self) -> Vec<(String, Vec<CardCommand>)> {
         let mut result = Vec::new();
@@ -358,7 +551,7 @@ impl CardManager {
         if let Some(card) = self.cards.get(name) {
             Ok(card.commands())
         } else {
-            Err(anyhow!("Card not found: {}", name))
+            Err(self.card_not_found(name))
         }
     }
     
@@ -386,6 +579,7 @@ impl CardManager {
                 options.insert("url".to_string(), serde_json::Value::String(url.to_string()));
                 options
             },
+            permissions: None,
         };
         
         // Add the configuration
@@ -415,12 +609,27 @@ impl CardManager {
     
     /// Load external cards from the wallet directory
     fn load_external_cards(&mut self) -> Result<()> {
+        for (card_name, path) in self.wallet_cards()? {
+            // Check if this card is already registered
+            if self.cards.contains_key(&card_name) {
+                continue;
+            }
+            
+            self.load_external_card(&card_name, &path)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Card directories in the wallet, by name
+    fn wallet_cards(&self) -> Result<BTreeMap<String, PathBuf>> {
         // Get the wallet directory (parent of card_dir / .pocket/wallet)
         let wallet_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet");
+        let mut found = BTreeMap::new();
         
         // Skip if wallet directory doesn't exist
         if !wallet_dir.exists() {
-            return Ok(());
+            return Ok(found);
         }
         
         // Scan the wallet directory for card directories
@@ -434,70 +643,215 @@ impl CardManager {
             }
             
             // Get the card name from the directory name
-            let card_name = match path.file_name().and_then(|name| name.to_str()) {
-                Some(name) => name.to_string(),
-                None => continue, // Skip if we can't get the name
-            };
-            
-            // Check if this card is already registered
-            if self.cards.contains_key(&card_name) {
-                continue;
+            if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                found.insert(name.to_string(), path.clone());
             }
-            
-            // Determine the library filename based on the platform
-            #[cfg(target_os = "macos")]
-            let lib_filename = format!("libpocket_card_{}.dylib", card_name.replace('-', "_"));
-            
-            #[cfg(target_os = "linux")]
-            let lib_filename = format!("libpocket_card_{}.so", card_name.replace('-', "_"));
-            
-            #[cfg(target_os = "windows")]
-            let lib_filename = format!("pocket_card_{}.dll", card_name.replace('-', "_"));
-            
-            // Check in release directory first
-            let release_dir = path.join("target").join("release");
-            let release_lib_path = release_dir.join(&lib_filename);
-            
-            // Then check in debug directory
-            let debug_dir = path.join("target").join("debug");
-            let debug_lib_path = debug_dir.join(&lib_filename);
-            
-            // Try to find the library in either directory
-            let lib_path = if release_lib_path.exists() {
-                release_lib_path
-            } else if debug_lib_path.exists() {
-                debug_lib_path
-            } else {
-                // Also check in the deps directory
-                let debug_deps_lib_path = debug_dir.join("deps").join(&lib_filename);
-                if debug_deps_lib_path.exists() {
-                    debug_deps_lib_path
-                } else {
-                    log::debug!("Card {} library not found in release or debug directories", card_name);
+        }
+        
+        Ok(found)
+    }
+    
+    /// Loads one card from its wallet directory. Returns whether it loaded
+    fn load_external_card(&mut self, card_name: &str, path: &Path) -> Result<bool> {
+        let source = match card_source(path, card_name) {
+            Some(source) => source,
+            None => {
+                log::debug!("Card {} library not found in release or debug directories", card_name);
+                return Ok(false);
+            }
+        };
+        
+        // Cards only load once they have the access they ask for
+        if !self.check_permissions(card_name, path)? {
+            return Ok(false);
+        }
+        
+        let modified = source_modified(&source);
+        let result = match &source {
+            // Cards that run as their own process only need a manifest
+            CardSource::Process(_) => manifest::CardManifest::load(path, card_name)
+                .and_then(|manifest| process::ProcessCard::new(path, &manifest))
+                .map(|card| {
+                    self.cards.insert(card_name.to_string(), Box::new(card));
+                    None
+                }),
+            CardSource::Library(lib_path) => self.load_dynamic_card(card_name, lib_path).map(Some),
+        };
+        
+        match result {
+            Ok(library) => {
+                log::info!("Successfully loaded card: {}", card_name);
+                self.external.insert(card_name.to_string(), ExternalCard { source, modified, _library: library });
+                
+                // Ensure the card is enabled by default
+                self.ensure_card_enabled(card_name)?;
+                Ok(true)
+            },
+            Err(e) => {
+                log::error!("Failed to load card {}: {}", card_name, e);
+                Ok(false)
+            }
+        }
+    }
+    
+    /// Cleans up and drops an external card, then releases its library
+    fn unload_card(&mut self, name: &str) {
+        if let Some(mut card) = self.cards.remove(name) {
+            if let Err(e) = card.cleanup() {
+                log::warn!("Failed to clean up card {}: {}", name, e);
+            }
+            // The card's code lives in the library, so it has to go first
+            drop(card);
+        }
+        self.external.remove(name);
+    }
+    
+    /// Re-reads card configs and rescans the wallet, loading new cards,
+    /// reloading ones whose library or manifest changed and unloading ones
+    /// that were removed
+    pub fn reload(&mut self) -> Result<ReloadSummary> {
+        self.load_configs()?;
+        let on_disk = self.wallet_cards()?;
+        let mut summary = ReloadSummary::default();
+        
+        // Cards whose directory is gone
+        let removed: Vec<String> = self.external.keys()
+            .filter(|name| !on_disk.contains_key(*name))
+            .cloned()
+            .collect();
+        for name in removed {
+            self.unload_card(&name);
+            summary.unloaded.push(name);
+        }
+        
+        for (name, path) in &on_disk {
+            if let Some(loaded) = self.external.get(name) {
+                let source = card_source(path, name);
+                let unchanged = source.as_ref().is_some_and(|s| *s == loaded.source && source_modified(s) == loaded.modified);
+                if unchanged {
                     continue;
                 }
-            };
-            
-            // Attempt to load the dynamic library
-            let result = self.load_dynamic_card(&card_name, &lib_path);
-            match result {
-                Ok(_) => {
-                    log::info!("Successfully loaded card: {}", card_name);
-                    
-                    // Ensure the card is enabled by default
-                    self.ensure_card_enabled(&card_name)?;
-                },
-                Err(e) => {
-                    log::error!("Failed to load card {}: {}", card_name, e);
+                
+                self.unload_card(name);
+                if self.load_external_card(name, path)? {
+                    summary.reloaded.push(name.clone());
+                } else {
+                    summary.unloaded.push(name.clone());
                 }
+            } else if !self.cards.contains_key(name) && self.load_external_card(name, path)? {
+                summary.loaded.push(name.clone());
             }
         }
+        summary.unloaded.sort();
         
-        Ok(())
+        // Options may have changed too
+        self.initialize_cards();
+        Ok(summary)
     }
     
-    /// Load a dynamic card from a library file
-    fn load_dynamic_card(&mut self, name: &str, lib_path: &Path) -> Result<()> {
+    /// Watches the wallet and card configs so `reload_if_changed` can pick up
+    /// edits; this is how `extensions.auto_reload` is honored
+    pub fn set_auto_reload(&mut self, enabled: bool) {
+        self.watcher = enabled.then(|| {
+            let wallet_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet");
+            watcher::WalletWatcher::new(vec![wallet_dir, self.card_dir.clone()])
+        });
+    }
+    
+    /// Whether `set_auto_reload` turned on watching
+    pub fn auto_reload(&self) -> bool {
+        self.watcher.is_some()
+    }
+    
+    /// Reloads cards if auto-reload is on and anything changed since last time
+    pub fn reload_if_changed(&mut self) -> Result<Option<ReloadSummary>> {
+        let changed = self.watcher.as_mut().is_some_and(|watcher| watcher.changed());
+        if changed {
+            self.reload().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+    
+    /// Makes sure an external card has been granted the permissions in its
+    /// manifest, asking the user on first load (or when the card asks for
+    /// more). Returns whether the card may be loaded.
+    fn check_permissions(&mut self, name: &str, card_dir: &Path) -> Result<bool> {
+        let requested = match manifest::CardManifest::load(card_dir, name) {
+            Ok(manifest) => manifest.permissions,
+            Err(e) => {
+                log::debug!("No usable manifest for card {}: {}", name, e);
+                manifest::CardPermissions::default()
+            }
+        };
+        if requested.is_empty() {
+            return Ok(true);
+        }
+        
+        let granted = self.configs.get(name).and_then(|c| c.permissions.as_ref());
+        if granted.is_some_and(|g| g.covers(&requested)) {
+            return Ok(true);
+        }
+        
+        if !std::io::stdin().is_terminal() {
+            log::warn!("Card {} needs permissions that haven't been granted; run pocket in a terminal to approve them", name);
+            return Ok(false);
+        }
+        
+        println!("Card {} wants to:", name);
+        for line in requested.describe() {
+            println!("  - {}", line);
+        }
+        if !crate::utils::confirm(&format!("Allow card {}?", name), false)? {
+            log::warn!("Not loading card {}", name);
+            return Ok(false);
+        }
+        
+        let config = self.configs.entry(name.to_string()).or_insert_with(|| CardConfig {
+            name: name.to_string(),
+            enabled: true,
+            options: HashMap::new(),
+            permissions: None,
+        });
+        config.permissions = Some(requested);
+        self.save_configs()?;
+        
+        Ok(true)
+    }
+    
+    /// Permissions a card asks for in its manifest, and those granted to it
+    pub fn card_permissions(&self, name: &str) -> Result<(manifest::CardPermissions, Option<manifest::CardPermissions>)> {
+        let card_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet").join(name);
+        let requested = if card_dir.join(manifest::MANIFEST_FILE).exists() {
+            manifest::CardManifest::load(&card_dir, name)?.permissions
+        } else if self.configs.contains_key(name) {
+            manifest::CardPermissions::default()
+        } else {
+            return Err(self.card_not_found(name));
+        };
+        
+        Ok((requested, self.granted_permissions(name).cloned()))
+    }
+    
+    /// Permissions the user granted a card, for the host to enforce
+    pub fn granted_permissions(&self, name: &str) -> Option<&manifest::CardPermissions> {
+        self.configs.get(name).and_then(|c| c.permissions.as_ref())
+    }
+    
+    /// Withdraws a card's permissions, so it asks again next time it loads
+    pub fn revoke_permissions(&mut self, name: &str) -> Result<()> {
+        if !self.configs.contains_key(name) {
+            return Err(self.card_not_found(name));
+        }
+        if let Some(config) = self.configs.get_mut(name) {
+            config.permissions = None;
+        }
+        self.save_configs()
+    }
+    
+    /// Load a dynamic card from a library file, returning the library handle
+    /// that has to stay alive as long as the card does
+    fn load_dynamic_card(&mut self, name: &str, lib_path: &Path) -> Result<libloading::Library> {
         use libloading::{Library, Symbol};
         
         // Type of the card creation function
@@ -526,19 +880,14 @@ impl CardManager {
             // Register the card
             self.cards.insert(name.to_string(), card);
             
-            // We need to leak the library to keep the symbols valid
-            // This is safe because the card manager will be dropped when the program exits
-            std::mem::forget(lib);
+            Ok(lib)
         }
-        
-        Ok(())
     }
     
     /// Creates a new card in the wallet directory
     pub fn create_card(&self, name: &str, description: &str) -> Result<()> {
         // Get the wallet directory path
-        let home_dir = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
-        let wallet_dir = home_dir.join(".pocket").join("wallet");
+        let wallet_dir = crate::storage::pocket_dir()?.join("wallet");
         
         // Create the wallet directory if it doesn't exist
         if !wallet_dir.exists() {
@@ -730,7 +1079,7 @@ pub extern "C" fn create_card() -> Box<dyn pocket_cli::cards::Card> {{
         // Check if the card directory exists
         let card_dir = wallet_dir.join(name);
         if !card_dir.exists() {
-            return Err(anyhow!("Card '{}' not found", name));
+            return Err(self.card_not_found(name));
         }
         
         // Build the card using cargo
@@ -757,11 +1106,268 @@ pub extern "C" fn create_card() -> Box<dyn pocket_cli::cards::Card> {{
         
         Ok(())
     }
+    
+    /// Returns a card's option at a dotted path, or all of its options
+    pub fn get_card_option(&self, name: &str, key: Option<&str>) -> Result<serde_json::Value> {
+        let config = self.configs.get(name)
+            .ok_or_else(|| self.card_not_found(name))?;
+        
+        match key {
+            Some(key) => options::get(&config.options, key)
+                .cloned()
+                .ok_or_else(|| anyhow!("Option '{}' is not set for card '{}'", key, name)),
+            None => Ok(serde_json::to_value(&config.options)?),
+        }
+    }
+    
+    /// Sets (or with `None`, removes) a card option, validating it against the
+    /// card's schema and re-initializing the card. Nothing is saved if the card
+    /// rejects the new options.
+    pub fn set_card_option(&mut self, name: &str, key: &str, value: Option<serde_json::Value>) -> Result<()> {
+        let old_config = self.configs.get(name)
+            .ok_or_else(|| self.card_not_found(name))?
+            .clone();
+        let mut new_config = old_config.clone();
+        
+        match value {
+            Some(value) => {
+                // External cards can declare a schema in their card.toml
+                let card_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet").join(name);
+                if card_dir.join(manifest::MANIFEST_FILE).exists() {
+                    let manifest = manifest::CardManifest::load(&card_dir, name)?;
+                    options::validate(&manifest.schema, key, &value)?;
+                }
+                options::set(&mut new_config.options, key, value)?;
+            },
+            None => {
+                if !options::unset(&mut new_config.options, key) {
+                    bail!("Option '{}' is not set for card '{}'", key, name);
+                }
+            },
+        }
+        
+        // Let the card check the new options, rolling back if it refuses them
+        if let Some(card) = self.cards.get_mut(name) {
+            if let Err(e) = card.initialize(&new_config) {
+                card.initialize(&old_config)?;
+                return Err(e);
+            }
+        }
+        
+        self.configs.insert(name.to_string(), new_config);
+        self.save_configs()
+    }
+    
+    /// Builds a card and runs the tests declared in its card.toml in an
+    /// isolated data directory, using the `pocket` binary at `pocket_bin`
+    pub fn test_card(&self, name: &str, pocket_bin: &Path) -> Result<Vec<testing::TestOutcome>> {
+        let card_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet").join(name);
+        if !card_dir.exists() {
+            return Err(self.card_not_found(name));
+        }
+        
+        // Process cards have nothing to build
+        let manifest = manifest::CardManifest::load(&card_dir, name)?;
+        if manifest.process.is_none() {
+            self.build_card(name, true)?;
+        }
+        
+        testing::run_card_tests(pocket_bin, &card_dir, name)
+    }
+    
+    /// Installs a card from a git repository into the wallet, builds it and
+    /// registers its configuration. A URL of "local" only registers the config,
+    /// for cards created with `pocket cards create`.
+    pub fn install_card(&mut self, name: &str, url: &str) -> Result<()> {
+        if url == "local" {
+            return self.register_card_config(name, url);
+        }
+        
+        manifest::validate_card_name(name)?;
+        if self.is_builtin_card(name) {
+            bail!("Cannot replace built-in card '{}'", name);
+        }
+        
+        let wallet_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet");
+        let card_dir = wallet_dir.join(name);
+        if card_dir.exists() {
+            bail!("Card '{}' already exists at {}. Use `pocket cards update {}` instead", name, card_dir.display(), name);
+        }
+        fs::create_dir_all(&wallet_dir)?;
+        
+        // Clone the repository
+        log::info!("Cloning {} into {}", url, card_dir.display());
+        run_git(&wallet_dir, &["clone", "--depth", "1", url, name])?;
+        
+        // Validate and build, removing the clone if anything is wrong with it
+        let installed = manifest::CardManifest::load(&card_dir, name)
+            .and_then(|_| self.build_card(name, true));
+        if let Err(e) = installed {
+            let _ = fs::remove_dir_all(&card_dir);
+            return Err(e);
+        }
+        
+        self.register_card_config(name, url)
+    }
+    
+    /// Installs a card from a registry index. `spec` is a card name, optionally
+    /// pinned to a version with `name@version`. The archive's checksum is
+    /// verified before anything is extracted or built. Returns the installed version.
+    pub fn install_from_registry(&mut self, registry_url: &str, spec: &str) -> Result<String> {
+        let (name, pinned) = registry::parse_spec(spec);
+        manifest::validate_card_name(name)?;
+        if self.is_builtin_card(name) {
+            bail!("Cannot replace built-in card '{}'", name);
+        }
+        
+        let wallet_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet");
+        let card_dir = wallet_dir.join(name);
+        if card_dir.exists() {
+            bail!("Card '{}' is already installed at {}", name, card_dir.display());
+        }
+        
+        // Look the card up and download the archive
+        let index = registry::RegistryIndex::fetch(registry_url)?;
+        let card = index.card(name)
+            .ok_or_else(|| anyhow!("Card '{}' not found in the registry", name))?;
+        let version = card.version(pinned)?;
+        let archive = version.download()?;
+        
+        // Extract next to the wallet so the final move is a rename
+        fs::create_dir_all(&wallet_dir)?;
+        let staging = tempfile::tempdir_in(&wallet_dir)?;
+        let archive_path = staging.path().join("card.tar.gz");
+        let extract_dir = staging.path().join("src");
+        fs::write(&archive_path, &archive)?;
+        fs::create_dir(&extract_dir)?;
+        let status = std::process::Command::new("tar")
+            .arg("-xzf")
+            .arg(&archive_path)
+            .arg("-C")
+            .arg(&extract_dir)
+            .status()
+            .map_err(|e| anyhow!("Failed to run tar: {}", e))?;
+        if !status.success() {
+            bail!("Failed to extract the archive for '{}'", name);
+        }
+        
+        // Archives usually wrap everything in a single top-level directory
+        let mut root = extract_dir.clone();
+        let top_level: Vec<_> = fs::read_dir(&extract_dir)?.filter_map(|e| e.ok()).collect();
+        if top_level.len() == 1 && top_level[0].path().is_dir() {
+            root = top_level[0].path();
+        }
+        
+        manifest::CardManifest::load(&root, name)?;
+        fs::rename(&root, &card_dir)?;
+        
+        if let Err(e) = self.build_card(name, true) {
+            let _ = fs::remove_dir_all(&card_dir);
+            return Err(e);
+        }
+        
+        // Remember where the card came from
+        let mut options = HashMap::new();
+        options.insert("url".to_string(), serde_json::Value::String(version.url.clone()));
+        options.insert("registry".to_string(), serde_json::Value::String(registry_url.to_string()));
+        options.insert("version".to_string(), serde_json::Value::String(version.version.clone()));
+        options.insert("sha256".to_string(), serde_json::Value::String(version.sha256.clone()));
+        self.configs.insert(name.to_string(), CardConfig {
+            name: name.to_string(),
+            enabled: true,
+            options,
+            permissions: None,
+        });
+        self.save_configs()?;
+        
+        Ok(version.version.clone())
+    }
+    
+    /// Pulls the latest version of an installed card and rebuilds it
+    pub fn update_card(&self, name: &str) -> Result<()> {
+        let wallet_dir = self.card_dir.parent().unwrap_or(&self.card_dir).join("wallet");
+        let card_dir = wallet_dir.join(name);
+        if !card_dir.exists() {
+            return Err(self.card_not_found(name));
+        }
+        if !card_dir.join(".git").exists() {
+            bail!("Card '{}' was not installed from a git repository", name);
+        }
+        
+        log::info!("Updating card '{}'", name);
+        run_git(&card_dir, &["pull", "--ff-only"])?;
+        
+        manifest::CardManifest::load(&card_dir, name)?;
+        self.build_card(name, true)
+    }
+}
+
+/// Runs a git command in a directory, failing with git's output
+/// Finds what a wallet card would load from: its manifest's process, or a built library
+fn card_source(path: &Path, card_name: &str) -> Option<CardSource> {
+    if let Ok(manifest) = manifest::CardManifest::load(path, card_name) {
+        if manifest.process.is_some() {
+            return Some(CardSource::Process(path.to_path_buf()));
+        }
+    }
+    
+    // Determine the library filename based on the platform
+    #[cfg(target_os = "macos")]
+    let lib_filename = format!("libpocket_card_{}.dylib", card_name.replace('-', "_"));
+    
+    #[cfg(target_os = "linux")]
+    let lib_filename = format!("libpocket_card_{}.so", card_name.replace('-', "_"));
+    
+    #[cfg(target_os = "windows")]
+    let lib_filename = format!("pocket_card_{}.dll", card_name.replace('-', "_"));
+    
+    // Check in release directory first, then debug, then debug deps
+    let release_dir = path.join("target").join("release");
+    let debug_dir = path.join("target").join("debug");
+    [
+        release_dir.join(&lib_filename),
+        debug_dir.join(&lib_filename),
+        debug_dir.join("deps").join(&lib_filename),
+    ].into_iter().find(|candidate| candidate.exists()).map(CardSource::Library)
+}
+
+/// Latest modification time of a card source. For process cards that's the
+/// newest file in the card directory, since any script may have changed
+fn source_modified(source: &CardSource) -> Option<SystemTime> {
+    match source {
+        CardSource::Library(path) => fs::metadata(path).and_then(|m| m.modified()).ok(),
+        CardSource::Process(dir) => walkdir::WalkDir::new(dir)
+            .into_iter()
+            .filter_entry(|e| e.file_name() != "target" && e.file_name() != ".git")
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .filter_map(|e| e.metadata().ok()?.modified().ok())
+            .max(),
+    }
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = std::process::Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("Failed to run git: {}", e))?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("git {} failed: {}", args[0], stderr.trim()));
+    }
+    
+    Ok(())
 }
 
 impl Drop for CardManager {
     fn drop(&mut self) {
         // Attempt to clean up cards when the manager is dropped
         let _ = self.cleanup();
+        
+        // Drop the cards before the libraries holding their code
+        self.cards.clear();
+        self.external.clear();
     }
 } 
\ No newline at end of file
@@ -0,0 +1,164 @@
+//! Reading and writing card options
+//!
+//! Options are addressed by dotted paths into `CardConfig.options`, so
+//! `backup.compress` is the `compress` field of the `backup` object.
+
+use std::collections::HashMap;
+use serde_json::Value;
+use anyhow::{Result, bail};
+
+use crate::cards::manifest::OptionSchema;
+
+/// Parses a value given on the command line as JSON, treating anything that
+/// isn't valid JSON as a plain string
+pub fn parse_value(text: &str) -> Value {
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+/// Looks up the option at a dotted path
+pub fn get<'a>(options: &'a HashMap<String, Value>, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = options.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_object()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Sets the option at a dotted path, creating objects along the way
+pub fn set(options: &mut HashMap<String, Value>, key: &str, new_value: Value) -> Result<()> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        bail!("Invalid option key '{}'", key);
+    }
+    
+    let (last, parents) = parts.split_last().expect("split always yields a part");
+    if parents.is_empty() {
+        options.insert(last.to_string(), new_value);
+        return Ok(());
+    }
+    
+    let mut value = options.entry(parents[0].to_string())
+        .or_insert_with(|| Value::Object(Default::default()));
+    for (i, part) in parents.iter().enumerate().skip(1) {
+        value = match value {
+            Value::Object(map) => map.entry(part.to_string()).or_insert_with(|| Value::Object(Default::default())),
+            _ => bail!("'{}' is not an object", parents[..i].join(".")),
+        };
+    }
+    
+    match value {
+        Value::Object(map) => {
+            map.insert(last.to_string(), new_value);
+            Ok(())
+        },
+        _ => bail!("'{}' is not an object", parents.join(".")),
+    }
+}
+
+/// Removes the option at a dotted path, returning whether it existed
+pub fn unset(options: &mut HashMap<String, Value>, key: &str) -> bool {
+    match key.rsplit_once('.') {
+        None => options.remove(key).is_some(),
+        Some((parent, last)) => {
+            let mut parts = parent.split('.');
+            let Some(mut value) = parts.next().and_then(|first| options.get_mut(first)) else {
+                return false;
+            };
+            for part in parts {
+                match value.as_object_mut().and_then(|map| map.get_mut(part)) {
+                    Some(next) => value = next,
+                    None => return false,
+                }
+            }
+            value.as_object_mut().is_some_and(|map| map.remove(last).is_some())
+        },
+    }
+}
+
+/// Checks a value against the schema a card declares for its options. Cards
+/// without a schema accept anything.
+pub fn validate(schema: &HashMap<String, OptionSchema>, key: &str, value: &Value) -> Result<()> {
+    if schema.is_empty() {
+        return Ok(());
+    }
+    
+    let Some(option) = schema.get(key) else {
+        let mut known: Vec<&String> = schema.keys().collect();
+        known.sort();
+        bail!("Unknown option '{}'. Known options: {}", key, known.into_iter().cloned().collect::<Vec<_>>().join(", "));
+    };
+    
+    let matches_type = match option.kind.as_str() {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    };
+    if !matches_type {
+        bail!("Option '{}' must be of type {}", key, option.kind);
+    }
+    
+    if !option.allowed.is_empty() && !option.allowed.contains(value) {
+        let allowed: Vec<String> = option.allowed.iter().map(|v| v.to_string()).collect();
+        bail!("Option '{}' must be one of: {}", key, allowed.join(", "));
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn parses_values() {
+        assert_eq!(parse_value("3"), json!(3));
+        assert_eq!(parse_value("true"), json!(true));
+        assert_eq!(parse_value("[1, 2]"), json!([1, 2]));
+        assert_eq!(parse_value("hello"), json!("hello"));
+    }
+    
+    #[test]
+    fn gets_and_sets_paths() {
+        let mut options = HashMap::new();
+        set(&mut options, "backup.compress", json!(true)).unwrap();
+        set(&mut options, "backup.max_backups", json!(3)).unwrap();
+        set(&mut options, "url", json!("local")).unwrap();
+        
+        assert_eq!(get(&options, "backup.compress"), Some(&json!(true)));
+        assert_eq!(get(&options, "backup"), Some(&json!({"compress": true, "max_backups": 3})));
+        assert_eq!(get(&options, "url.nope"), None);
+        assert!(set(&mut options, "url.nope", json!(1)).is_err());
+        
+        assert!(unset(&mut options, "backup.compress"));
+        assert!(!unset(&mut options, "backup.compress"));
+        assert_eq!(get(&options, "backup"), Some(&json!({"max_backups": 3})));
+    }
+    
+    #[test]
+    fn validates_against_schema() {
+        let mut schema = HashMap::new();
+        schema.insert("level".to_string(), OptionSchema {
+            kind: "string".to_string(),
+            description: String::new(),
+            allowed: vec![json!("low"), json!("high")],
+        });
+        schema.insert("count".to_string(), OptionSchema {
+            kind: "integer".to_string(),
+            description: String::new(),
+            allowed: Vec::new(),
+        });
+        
+        assert!(validate(&schema, "level", &json!("low")).is_ok());
+        assert!(validate(&schema, "level", &json!("medium")).is_err());
+        assert!(validate(&schema, "count", &json!(2)).is_ok());
+        assert!(validate(&schema, "count", &json!(2.5)).is_err());
+        assert!(validate(&schema, "other", &json!(1)).is_err());
+        assert!(validate(&HashMap::new(), "anything", &json!(1)).is_ok());
+    }
+}
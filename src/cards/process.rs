@@ -0,0 +1,295 @@
+//! Out-of-process cards
+//!
+//! A card can be any executable that speaks JSON-RPC 2.0 over stdin/stdout,
+//! one JSON message per line. Its `card.toml` names the command to start:
+//!
+//! ```toml
+//! [process]
+//! command = ["python3", "card.py"]
+//! ```
+//!
+//! The process is started the first time the card is needed. Pocket calls
+//! `initialize` (the handshake), then `commands`, `execute` and `on_event` as
+//! needed, and finally sends a `shutdown` notification. Anything the card
+//! writes to stderr goes straight to the terminal.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use anyhow::{Result, Context, anyhow, bail};
+
+use crate::cards::{Card, CardCommand, CardConfig, Event};
+use crate::cards::manifest::CardManifest;
+
+/// Version of the protocol spoken with card processes
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A card implemented by a separate executable
+pub struct ProcessCard {
+    /// Name of the card
+    name: String,
+    
+    /// Version of the card
+    version: String,
+    
+    /// Description of the card
+    description: String,
+    
+    /// Directory the process runs in
+    dir: PathBuf,
+    
+    /// Program and arguments to start
+    command: Vec<String>,
+    
+    /// Commands declared in the manifest
+    declared_commands: Vec<CardCommand>,
+    
+    /// Configuration sent with the handshake
+    config: Option<CardConfig>,
+    
+    /// The running process, once started
+    session: Mutex<Option<Session>>,
+}
+
+/// A running card process
+struct Session {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+/// Reply to the `execute` method
+#[derive(Debug, Default, Deserialize)]
+struct ExecuteResult {
+    /// Text to print for the user
+    #[serde(default)]
+    output: Option<String>,
+}
+
+/// A command as described by a card process
+#[derive(Debug, Deserialize)]
+struct RemoteCommand {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    usage: Option<String>,
+}
+
+impl ProcessCard {
+    /// Creates a card for the manifest in `dir`, which must have a `[process]` section
+    pub fn new(dir: &Path, manifest: &CardManifest) -> Result<Self> {
+        let process = manifest.process.as_ref()
+            .ok_or_else(|| anyhow!("Card '{}' has no [process] section", manifest.card.name))?;
+        if process.command.is_empty() {
+            bail!("Card '{}' has an empty process command", manifest.card.name);
+        }
+        
+        let name = manifest.card.name.clone();
+        let declared_commands = manifest.commands.iter()
+            .map(|(command, description)| CardCommand {
+                name: command.clone(),
+                description: description.clone(),
+                usage: format!("pocket cards run {} {}", name, command),
+            })
+            .collect();
+        
+        Ok(Self {
+            name,
+            version: manifest.card.version.clone(),
+            description: manifest.card.description.clone(),
+            dir: dir.to_path_buf(),
+            command: process.command.clone(),
+            declared_commands,
+            config: None,
+            session: Mutex::new(None),
+        })
+    }
+    
+    /// Calls a method on the card process, starting it if needed
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let mut guard = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            *guard = Some(self.start()?);
+        }
+        let session = guard.as_mut().expect("session was just started");
+        
+        let result = session.request(method, params);
+        if result.is_err() {
+            // Don't keep talking to a process that misbehaved
+            if let Some(mut session) = guard.take() {
+                let _ = session.child.kill();
+                let _ = session.child.wait();
+            }
+        }
+        result
+    }
+    
+    /// Starts the process and performs the handshake
+    fn start(&self) -> Result<Session> {
+        // Relative program paths are relative to the card's directory
+        let program = if self.command[0].contains('/') {
+            self.dir.join(&self.command[0]).to_string_lossy().to_string()
+        } else {
+            self.command[0].clone()
+        };
+        
+        let mut child = Command::new(&program)
+            .args(&self.command[1..])
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start card '{}' ({})", self.name, program))?;
+        
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let mut session = Session { child, stdin, stdout, next_id: 1 };
+        
+        let (options, permissions) = match &self.config {
+            Some(config) => (json!(config.options), json!(config.permissions)),
+            None => (json!({}), Value::Null),
+        };
+        let reply = session.request("initialize", json!({
+            "protocol_version": PROTOCOL_VERSION,
+            "name": self.name,
+            "options": options,
+            "permissions": permissions,
+        }));
+        
+        match reply {
+            Ok(reply) => {
+                let version = reply.get("protocol_version").and_then(Value::as_u64).unwrap_or(PROTOCOL_VERSION as u64);
+                if version != PROTOCOL_VERSION as u64 {
+                    let _ = session.child.kill();
+                    bail!("Card '{}' speaks protocol version {}, expected {}", self.name, version, PROTOCOL_VERSION);
+                }
+                Ok(session)
+            },
+            Err(e) => {
+                let _ = session.child.kill();
+                let _ = session.child.wait();
+                Err(e.context(format!("Handshake with card '{}' failed", self.name)))
+            }
+        }
+    }
+}
+
+impl Session {
+    /// Sends a request and waits for its response
+    fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        
+        self.send(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+        
+        loop {
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                bail!("Card process exited during '{}'", method);
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            
+            let message: Value = serde_json::from_str(&line)
+                .with_context(|| format!("Card sent invalid JSON: {}", line.trim()))?;
+            
+            // Skip anything that isn't the response to this request
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            
+            if let Some(error) = message.get("error") {
+                let text = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+                bail!("{}", text);
+            }
+            return Ok(message.get("result").cloned().unwrap_or(Value::Null));
+        }
+    }
+    
+    /// Sends a notification, which gets no response
+    fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        self.send(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+    
+    /// Writes one message
+    fn send(&mut self, message: &Value) -> Result<()> {
+        writeln!(self.stdin, "{}", message)?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+}
+
+impl Card for ProcessCard {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn version(&self) -> &str {
+        &self.version
+    }
+    
+    fn description(&self) -> &str {
+        &self.description
+    }
+    
+    fn initialize(&mut self, config: &CardConfig) -> Result<()> {
+        // Sent to the process with the handshake when it starts
+        self.config = Some(config.clone());
+        Ok(())
+    }
+    
+    fn execute(&self, command: &str, args: &[String]) -> Result<()> {
+        let reply = self.call("execute", json!({ "command": command, "args": args }))?;
+        let result: ExecuteResult = serde_json::from_value(reply).unwrap_or_default();
+        if let Some(output) = result.output {
+            print!("{}", output);
+            if !output.ends_with('\n') {
+                println!();
+            }
+        }
+        Ok(())
+    }
+    
+    fn commands(&self) -> Vec<CardCommand> {
+        // Prefer the manifest, so listing commands doesn't start the process
+        if !self.declared_commands.is_empty() {
+            return self.declared_commands.clone();
+        }
+        
+        match self.call("commands", Value::Null).and_then(|reply| Ok(serde_json::from_value::<Vec<RemoteCommand>>(reply)?)) {
+            Ok(commands) => commands.into_iter()
+                .map(|c| CardCommand {
+                    usage: c.usage.unwrap_or_else(|| format!("pocket cards run {} {}", self.name, c.name)),
+                    name: c.name,
+                    description: c.description,
+                })
+                .collect(),
+            Err(e) => {
+                log::warn!("Failed to get commands from card {}: {}", self.name, e);
+                Vec::new()
+            }
+        }
+    }
+    
+    fn on_event(&self, event: &Event) -> Result<()> {
+        self.call("on_event", serde_json::to_value(event)?)?;
+        Ok(())
+    }
+    
+    fn cleanup(&mut self) -> Result<()> {
+        let session = self.session.get_mut().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(mut session) = session {
+            let _ = session.notify("shutdown", Value::Null);
+            drop(session.stdin);
+            let _ = session.child.wait();
+        }
+        Ok(())
+    }
+}
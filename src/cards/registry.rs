@@ -0,0 +1,183 @@
+//! Card registry
+//!
+//! A registry is an index of installable cards, hosted as a JSON or TOML file.
+//! Each card lists its released versions, and each version points at a
+//! `.tar.gz` source archive along with the archive's SHA-256 checksum:
+//!
+//! ```json
+//! { "cards": [ { "name": "hello", "description": "Says hello",
+//!   "versions": [ { "version": "0.1.0", "url": "https://example.com/hello-0.1.0.tar.gz",
+//!                   "sha256": "..." } ] } ] }
+//! ```
+
+use std::cmp::Ordering;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use anyhow::{Result, Context, anyhow, bail};
+
+use crate::utils::http;
+
+/// A registry index
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    /// Cards available from the registry
+    #[serde(default)]
+    pub cards: Vec<RegistryCard>,
+}
+
+/// A card listed in a registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryCard {
+    /// Name of the card
+    pub name: String,
+    
+    /// Description of the card
+    #[serde(default)]
+    pub description: String,
+    
+    /// Author of the card
+    #[serde(default)]
+    pub author: String,
+    
+    /// Released versions of the card
+    #[serde(default)]
+    pub versions: Vec<RegistryVersion>,
+}
+
+/// A released version of a card
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryVersion {
+    /// Version number
+    pub version: String,
+    
+    /// URL of the `.tar.gz` source archive
+    pub url: String,
+    
+    /// SHA-256 checksum of the archive
+    pub sha256: String,
+}
+
+impl RegistryIndex {
+    /// Downloads and parses the index at a URL or path
+    pub fn fetch(url: &str) -> Result<Self> {
+        let bytes = http::fetch(url)?;
+        let text = String::from_utf8(bytes)
+            .map_err(|_| anyhow!("Registry index at {} is not valid UTF-8", url))?;
+        Self::parse(&text, url.ends_with(".toml"))
+    }
+    
+    /// Parses an index from JSON, or TOML when `toml` is set
+    pub fn parse(text: &str, toml: bool) -> Result<Self> {
+        if toml {
+            toml::from_str(text).context("Invalid registry index")
+        } else {
+            serde_json::from_str(text).context("Invalid registry index")
+        }
+    }
+    
+    /// Cards whose name or description contains the query (case-insensitive)
+    pub fn search(&self, query: &str) -> Vec<&RegistryCard> {
+        let query = query.to_lowercase();
+        self.cards.iter()
+            .filter(|c| c.name.to_lowercase().contains(&query) || c.description.to_lowercase().contains(&query))
+            .collect()
+    }
+    
+    /// Finds a card by name
+    pub fn card(&self, name: &str) -> Option<&RegistryCard> {
+        self.cards.iter().find(|c| c.name == name)
+    }
+}
+
+impl RegistryCard {
+    /// The requested version, or the newest one when none is pinned
+    pub fn version(&self, pinned: Option<&str>) -> Result<&RegistryVersion> {
+        match pinned {
+            Some(wanted) => self.versions.iter()
+                .find(|v| v.version == wanted)
+                .ok_or_else(|| anyhow!("Card '{}' has no version {}", self.name, wanted)),
+            None => self.latest()
+                .ok_or_else(|| anyhow!("Card '{}' has no released versions", self.name)),
+        }
+    }
+    
+    /// The newest released version
+    pub fn latest(&self) -> Option<&RegistryVersion> {
+        self.versions.iter().max_by(|a, b| compare_versions(&a.version, &b.version))
+    }
+}
+
+impl RegistryVersion {
+    /// Downloads the archive and checks it against the published checksum
+    pub fn download(&self) -> Result<Vec<u8>> {
+        let bytes = http::fetch(&self.url)?;
+        let digest: String = Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect();
+        if !digest.eq_ignore_ascii_case(self.sha256.trim()) {
+            bail!("Checksum mismatch for {}: expected {}, got {}", self.url, self.sha256, digest);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Splits `name@version` into its parts
+pub fn parse_spec(spec: &str) -> (&str, Option<&str>) {
+    match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    }
+}
+
+/// Compares dotted version numbers numerically, falling back to text for
+/// parts that aren't numbers
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.trim_start_matches('v').split('.');
+    let mut b_parts = b.trim_start_matches('v').split('.');
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(x), Some(y)) => {
+                let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    _ => x.cmp(y),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const INDEX: &str = r#"{"cards": [
+        {"name": "hello", "description": "Says hello", "versions": [
+            {"version": "0.9.0", "url": "a", "sha256": "x"},
+            {"version": "0.10.0", "url": "b", "sha256": "y"}
+        ]},
+        {"name": "notes", "description": "Note taking helpers"}
+    ]}"#;
+    
+    #[test]
+    fn search_and_pick_versions() {
+        let index = RegistryIndex::parse(INDEX, false).unwrap();
+        assert_eq!(index.search("HELLO").len(), 1);
+        assert_eq!(index.search("note").len(), 1);
+        
+        let hello = index.card("hello").unwrap();
+        assert_eq!(hello.version(None).unwrap().version, "0.10.0");
+        assert_eq!(hello.version(Some("0.9.0")).unwrap().url, "a");
+        assert!(hello.version(Some("1.0.0")).is_err());
+        assert!(index.card("notes").unwrap().version(None).is_err());
+    }
+    
+    #[test]
+    fn parses_specs() {
+        assert_eq!(parse_spec("hello@0.1.0"), ("hello", Some("0.1.0")));
+        assert_eq!(parse_spec("hello"), ("hello", None));
+    }
+}
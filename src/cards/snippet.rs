@@ -1,11 +1,38 @@
-use crate::cards::{Card, CardConfig, CardCommand};
-use crate::utils::{read_clipboard, summarize_text, SummaryMetadata};
-use crate::models::Entry;
+use crate::cards::{events, Card, CardConfig, CardCommand, Event};
+use crate::cli::commands::lintcheck;
+use crate::utils::{read_clipboard, read_selection, summarize_text, summarize_with_config, SummaryMetadata};
+use crate::utils::language::{tokenize, TokenKind};
+use crate::utils::tagging::suggest_with_config;
+use crate::models::{Entry, ContentType};
+use crate::search::project::{self, PROJECT_KEY};
 use crate::storage::StorageManager;
+use crate::logging;
+use crate::i18n::tr;
 use anyhow::{Result, anyhow, Context};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
 use std::fs;
 
+/// Files bigger than this are streamed into storage, after asking
+const LARGE_ENTRY_BYTES: u64 = 1024 * 1024;
+
+/// Files bigger than this aren't stored at all
+const MAX_ENTRY_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Bytes read up front to tell text from binary and detect the language
+const SNIFF_BYTES: usize = 8 * 1024;
+
+/// Lines longer than this get flagged by `snippet lint`
+const MAX_LINE_LENGTH: usize = 120;
+
+/// Languages where unbalanced brackets almost certainly mean a broken snippet.
+/// Shell and markup lean on unpaired brackets too often to check
+const BRACKET_LANGUAGES: &[&str] = &[
+    "rust", "go", "javascript", "typescript", "python", "java", "c", "cpp",
+    "csharp", "php", "lua", "json", "css",
+];
+
 /// Card for enhanced snippet functionality
 pub struct SnippetCard {
     /// Name of the card
@@ -26,6 +53,7 @@ pub struct SnippetCard {
 
 /// Configuration for the snippet card
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct SnippetCardConfig {
     /// Whether to automatically summarize snippets
     pub auto_summarize: bool,
@@ -71,11 +99,35 @@ impl SnippetCard {
               use_clipboard: bool,
               backpack: Option<&str>,
               summarize: Option<&str>) -> Result<String> {
+        // `-` is stdin, so pocket can sit at the end of a pipeline
+        let stdin = file == Some("-");
+        let file = file.filter(|_| !stdin);
+        
         // Initialize content
-        let content = if let Some(file_path) = file {
-            // Read from file
-            fs::read_to_string(file_path)
+        let content = if stdin {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)
+                .context("Failed to read from stdin")?;
+            content
+        } else if let Some(file_path) = file {
+            // Big files never get read into memory whole
+            let size = fs::metadata(file_path)
                 .context(format!("Failed to read file: {}", file_path))?
+                .len();
+            if size > LARGE_ENTRY_BYTES {
+                return self.add_large(file_path, size, message, backpack);
+            }
+            
+            // Read from file
+            let bytes = fs::read(file_path)
+                .context(format!("Failed to read file: {}", file_path))?;
+            match String::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(_) => {
+                    logging::info(&format!("{} isn't UTF-8 text, so it's stored as a binary attachment", file_path));
+                    return self.add_binary(file_path, message, backpack);
+                },
+            }
         } else if use_editor {
             // Open editor
             crate::utils::open_editor(None)
@@ -86,9 +138,9 @@ impl SnippetCard {
                 .context("Failed to read from clipboard")?
         } else {
             // No content source provided
-            return Err(anyhow!("No content source provided. Use --file, --editor, or --clipboard options"));
+            return Err(anyhow!("No content source provided. Give a file (- for stdin), or use --editor or --clipboard"));
         };
-
+        
         if content.trim().is_empty() {
             return Err(anyhow!("Content is empty"));
         }
@@ -112,40 +164,71 @@ impl SnippetCard {
         
         // Create entry
         let mut entry = Entry::new(title, content_type, None, vec![]);
-        
-        // Create summary metadata
-        let summary = if let Some(manual_summary) = summarize {
-            // User provided a summary, use it
-            SummaryMetadata::new(manual_summary.to_string(), false)
-        } else if self.config.auto_summarize {
-            // Auto-generate a summary
-            let summary = summarize_text(&content)
-                .unwrap_or_else(|_| {
-                    // Fallback: use first line or first 100 chars
-                    content.lines().next()
-                        .unwrap_or(&content[..std::cmp::min(100, content.len())])
-                        .to_string()
-                });
-                
-            // Truncate if needed
-            let summary = if summary.len() > self.config.max_summary_length {
-                format!("{}...", &summary[..self.config.max_summary_length - 3])
-            } else {
-                summary
-            };
-            
-            SummaryMetadata::new(summary, true)
-        } else {
-            // No summarization requested
-            SummaryMetadata::new("".to_string(), true)
-        };
+        entry.language = crate::utils::detect_language(file.map(Path::new), &content);
         
         // Add summary metadata to entry
+        let summary = self.summary_for(&content, summarize);
         entry.add_metadata("summary", &summary.to_json());
+        note_project(&mut entry);
         
         // Save the entry
         let storage = StorageManager::new()?;
+        lint(&storage, &entry, &content)?;
+        warn_duplicates(&storage, &content)?;
         storage.save_entry(&entry, &content, backpack)?;
+        events::emit(Event::EntryAdded { id: entry.id.clone(), backpack: backpack.map(|s| s.to_string()) });
+        
+        Ok(entry.id)
+    }
+    
+    /// Adds a file's raw bytes as a binary entry, streaming them into storage
+    pub fn add_binary(&self, file_path: &str, message: Option<&str>, backpack: Option<&str>) -> Result<String> {
+        let size = fs::metadata(file_path)
+            .context(format!("Failed to read file: {}", file_path))?
+            .len();
+        if size == 0 {
+            return Err(anyhow!("Content is empty"));
+        }
+        check_size(file_path, size)?;
+        
+        let path = Path::new(file_path);
+        let head = read_head(path)?;
+        let mut entry = Entry::new(file_title(file_path, message), ContentType::Other("binary".to_string()), Some(file_path.to_string()), vec![]);
+        entry.mime_type = Some(crate::utils::detect_mime_type(path, &head));
+        
+        let storage = StorageManager::new()?;
+        let file = fs::File::open(path).context(format!("Failed to read file: {}", file_path))?;
+        storage.save_entry_from(&entry, file, backpack)?;
+        events::emit(Event::EntryAdded { id: entry.id.clone(), backpack: backpack.map(|s| s.to_string()) });
+        
+        Ok(entry.id)
+    }
+    
+    /// Adds a file too big to read into memory. Text is streamed into
+    /// storage and searched by its start; anything else becomes a binary entry
+    fn add_large(&self, file_path: &str, size: u64, message: Option<&str>, backpack: Option<&str>) -> Result<String> {
+        let path = Path::new(file_path);
+        let head = read_head(path)?;
+        // The start can look like text when the rest isn't, and text entries
+        // have to load as a string
+        if head.contains(&0) || !is_utf8(path)? {
+            return self.add_binary(file_path, message, backpack);
+        }
+        check_size(file_path, size)?;
+        
+        let text = String::from_utf8_lossy(&head);
+        let mut entry = Entry::new(
+            file_title(file_path, message),
+            crate::utils::detect_content_type(Some(path), Some(&text)),
+            Some(file_path.to_string()),
+            vec![],
+        );
+        entry.language = crate::utils::detect_language(Some(path), &text);
+        
+        let storage = StorageManager::new()?;
+        let file = fs::File::open(path).context(format!("Failed to read file: {}", file_path))?;
+        storage.save_entry_from(&entry, file, backpack)?;
+        events::emit(Event::EntryAdded { id: entry.id.clone(), backpack: backpack.map(|s| s.to_string()) });
         
         Ok(entry.id)
     }
@@ -157,7 +240,7 @@ impl SnippetCard {
         // Read content from clipboard
         let content = read_clipboard()
             .context("Failed to read from clipboard")?;
-            
+        
         if content.trim().is_empty() {
             return Err(anyhow!("Clipboard is empty"));
         }
@@ -172,42 +255,280 @@ impl SnippetCard {
         
         // Create entry
         let mut entry = Entry::new(title, content_type, None, vec![]);
+        entry.language = crate::utils::detect_language(None, &content);
+        
+        // Add summary metadata to entry
+        let summary = self.summary_for(&content, user_summary);
+        entry.add_metadata("summary", &summary.to_json());
+        note_project(&mut entry);
+        
+        // Save the entry
+        let storage = StorageManager::new()?;
+        lint(&storage, &entry, &content)?;
+        warn_duplicates(&storage, &content)?;
+        storage.save_entry(&entry, &content, backpack)?;
+        events::emit(Event::EntryAdded { id: entry.id.clone(), backpack: backpack.map(|s| s.to_string()) });
+        
+        Ok(entry.id)
+    }
+    
+    /// Writes a new snippet in the editor, optionally in a given language
+    pub fn new_snippet(&self,
+                       language: Option<&str>,
+                       message: Option<&str>,
+                       backpack: Option<&str>,
+                       tags: Vec<String>) -> Result<String> {
+        let language = match language {
+            Some(name) => Some(crate::utils::normalize_language(name)
+                .ok_or_else(|| anyhow!("Unknown language: {}", name))?),
+            None => None,
+        };
+        
+        let content = crate::utils::open_editor_for_language(language, None)
+            .context("Failed to open editor")?;
+        self.save_text(content, message, language.map(str::to_string), backpack, tags)
+    }
+    
+    /// Adds whatever text is selected: stdin when something is piped in
+    /// (which is how editors hand over a selection), otherwise the primary
+    /// selection
+    pub fn add_from_selection(&self,
+                              language: Option<&str>,
+                              message: Option<&str>,
+                              backpack: Option<&str>,
+                              tags: Vec<String>) -> Result<String> {
+        let content = if std::io::stdin().is_terminal() {
+            read_selection().context("Failed to read the selection")?
+        } else {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)
+                .context("Failed to read the selection from stdin")?;
+            content
+        };
+        
+        let language = match language {
+            Some(name) => Some(crate::utils::normalize_language(name)
+                .ok_or_else(|| anyhow!("Unknown language: {}", name))?.to_string()),
+            None => None,
+        };
+        self.save_text(content, message, language, backpack, tags)
+    }
+    
+    /// Stores text as a snippet the same way `add` does
+    fn save_text(&self,
+                 content: String,
+                 message: Option<&str>,
+                 language: Option<String>,
+                 backpack: Option<&str>,
+                 tags: Vec<String>) -> Result<String> {
+        if content.trim().is_empty() {
+            return Err(anyhow!("Content is empty"));
+        }
+        
+        // Create a title from message or the first line
+        let title = match message {
+            Some(msg) => msg.to_string(),
+            None => content.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().trim().to_string(),
+        };
         
-        // Create summary metadata
-        let summary = if let Some(manual_summary) = user_summary {
+        // A language was asked for, so this is code whatever it looks like
+        let language = language.or_else(|| crate::utils::detect_language(None, &content));
+        let content_type = match crate::utils::detect_content_type(None, Some(&content)) {
+            ContentType::Text if language.is_some() => ContentType::Code,
+            detected => detected,
+        };
+        
+        let mut entry = Entry::new(title, content_type, None, tags);
+        entry.language = language;
+        let summary = self.summary_for(&content, None);
+        entry.add_metadata("summary", &summary.to_json());
+        note_project(&mut entry);
+        
+        let storage = StorageManager::new()?;
+        lint(&storage, &entry, &content)?;
+        warn_duplicates(&storage, &content)?;
+        storage.save_entry(&entry, &content, backpack)?;
+        events::emit(Event::EntryAdded { id: entry.id.clone(), backpack: backpack.map(|s| s.to_string()) });
+        
+        Ok(entry.id)
+    }
+    
+    /// Summary to store with new content: the user's, a generated one, or an empty one
+    fn summary_for(&self, content: &str, manual_summary: Option<&str>) -> SummaryMetadata {
+        if let Some(manual_summary) = manual_summary {
             // User provided a summary, use it
             SummaryMetadata::new(manual_summary.to_string(), false)
         } else if self.config.auto_summarize {
             // Auto-generate a summary
-            let summary = summarize_text(&content)
+            let summary = summarize_text(content)
                 .unwrap_or_else(|_| {
                     // Fallback: use first line or first 100 chars
                     content.lines().next()
                         .unwrap_or(&content[..std::cmp::min(100, content.len())])
                         .to_string()
                 });
-                
-            // Truncate if needed
-            let summary = if summary.len() > self.config.max_summary_length {
-                format!("{}...", &summary[..self.config.max_summary_length - 3])
-            } else {
-                summary
-            };
             
-            SummaryMetadata::new(summary, true)
+            SummaryMetadata::new(self.truncate_summary(summary), true)
         } else {
             // No summarization requested
             SummaryMetadata::new("".to_string(), true)
+        }
+    }
+    
+    /// Cuts a generated summary down to the configured length
+    fn truncate_summary(&self, summary: String) -> String {
+        let max = self.config.max_summary_length.max(3);
+        if summary.chars().count() > max {
+            format!("{}...", summary.chars().take(max - 3).collect::<String>())
+        } else {
+            summary
+        }
+    }
+    
+    /// Summarizes an entry with the backend set up in `config.toml` and stores
+    /// the summary. Returns the summary and what wrote it
+    pub fn summarize_entry(&self, reference: &str, backpack: Option<&str>) -> Result<(String, String)> {
+        let storage = StorageManager::new()?;
+        let (id, backpack) = storage.resolve_entry(reference, backpack)?;
+        let (entry, content) = storage.load_entry(&id, backpack.as_deref())?;
+        if entry.is_binary() {
+            return Err(anyhow!("'{}' is a binary attachment and can't be summarized", entry.title));
+        }
+        
+        let config = storage.load_config()?;
+        let (summary, by) = summarize_with_config(&config.llm, &content);
+        let summary = self.truncate_summary(summary);
+        storage.update_entry(&id, backpack.as_deref(), None, |entry| {
+            entry.add_metadata("summary", &SummaryMetadata::new(summary.clone(), true).to_json());
+        })?;
+        events::emit(Event::EntryUpdated { id, backpack });
+        Ok((summary, by))
+    }
+    
+    /// Suggests tags and a better title for an entry with the backend set up
+    /// in `config.toml`, and saves the ones the user accepts. `yes` accepts
+    /// them all without asking
+    pub fn suggest_tags(&self, reference: &str, backpack: Option<&str>, yes: bool) -> Result<()> {
+        let storage = StorageManager::new()?;
+        let (id, backpack) = storage.resolve_entry(reference, backpack)?;
+        let (entry, content) = storage.load_entry(&id, backpack.as_deref())?;
+        if entry.is_binary() {
+            return Err(anyhow!("'{}' is a binary attachment and can't be tagged automatically", entry.title));
+        }
+        
+        let config = storage.load_config()?;
+        let suggestions = suggest_with_config(&config.llm, &entry.title, &content, entry.language.as_deref());
+        let title = suggestions.title.filter(|title| *title != entry.title);
+        let tags: Vec<String> = suggestions.tags.into_iter()
+            .filter(|tag| !entry.tags.contains(tag))
+            .collect();
+        if title.is_none() && tags.is_empty() {
+            println!("No new suggestions for '{}' ({})", entry.title, suggestions.by);
+            return Ok(());
+        }
+        
+        println!("Suggestions for '{}' ({}):", entry.title, suggestions.by);
+        if let Some(title) = &title {
+            println!("  Title: {}", title);
+        }
+        if !tags.is_empty() {
+            println!("  Tags: {}", tags.join(", "));
+        }
+        
+        let (title, tags) = if yes {
+            (title, tags)
+        } else if !std::io::stdin().is_terminal() {
+            logging::warning(&format!("Suggestions not saved; run pocket tag suggest {} --yes to accept them", id));
+            return Ok(());
+        } else {
+            let title = match title {
+                Some(title) if crate::utils::confirm(&format!("Rename to '{}'?", title), true)? => Some(title),
+                _ => None,
+            };
+            let tags = if tags.is_empty() {
+                tags
+            } else {
+                let picked = crate::utils::multi_select("Tags to add", &tags, &vec![true; tags.len()])?;
+                picked.into_iter().map(|i| tags[i].clone()).collect()
+            };
+            (title, tags)
         };
+        if title.is_none() && tags.is_empty() {
+            println!("Nothing changed");
+            return Ok(());
+        }
         
-        // Add summary metadata to entry
-        entry.add_metadata("summary", &summary.to_json());
+        let entry = storage.update_entry(&id, backpack.as_deref(), None, |entry| {
+            if let Some(title) = title {
+                entry.title = title;
+            }
+            entry.tags.extend(tags);
+            entry.add_metadata("suggested_by", &suggestions.by);
+        })?;
+        events::emit(Event::EntryUpdated { id, backpack });
+        println!("Saved '{}' with tags: {}", entry.title, entry.tags.join(", "));
+        Ok(())
+    }
+    
+    /// Counts snippets by type, language and tag
+    pub fn stats(&self, backpack: Option<&str>) -> Result<SnippetStats> {
+        let storage = StorageManager::new()?;
+        let mut stats = SnippetStats::default();
         
-        // Save the entry
+        for (entry, location) in storage.list_all_entries(backpack)? {
+            stats.total += 1;
+            if entry.is_binary() {
+                stats.binary += 1;
+                continue;
+            }
+            
+            if let Ok((_, content)) = storage.load_entry(&entry.id, location.as_deref()) {
+                stats.lines += content.lines().count();
+                stats.bytes += content.len();
+            }
+            if entry.get_metadata("summary")
+                .and_then(|json| SummaryMetadata::from_json(json).ok())
+                .is_some_and(|summary| !summary.summary.is_empty()) {
+                stats.summarized += 1;
+            }
+            
+            let language = entry.language.clone().unwrap_or_else(|| "plain".to_string());
+            *stats.languages.entry(language).or_default() += 1;
+            for tag in entry.tags {
+                *stats.tags.entry(tag).or_default() += 1;
+            }
+        }
+        
+        Ok(stats)
+    }
+    
+    /// Lints the given snippets, or every text snippet when none are given.
+    /// Returns the snippets that have issues, with their issues
+    pub fn lint(&self, references: &[String], backpack: Option<&str>) -> Result<Vec<(Entry, Vec<LintIssue>)>> {
         let storage = StorageManager::new()?;
-        storage.save_entry(&entry, &content, backpack)?;
         
-        Ok(entry.id)
+        let targets: Vec<(String, Option<String>)> = if references.is_empty() {
+            storage.list_all_entries(backpack)?
+                .into_iter()
+                .filter(|(entry, _)| !entry.is_binary())
+                .map(|(entry, location)| (entry.id, location))
+                .collect()
+        } else {
+            references.iter()
+                .map(|reference| storage.resolve_entry(reference, backpack))
+                .collect::<Result<_>>()?
+        };
+        
+        let mut findings = Vec::new();
+        for (id, location) in targets {
+            let (entry, content) = storage.load_entry(&id, location.as_deref())?;
+            let issues = lint_snippet(&content, entry.language.as_deref());
+            if !issues.is_empty() {
+                findings.push((entry, issues));
+            }
+        }
+        
+        Ok(findings)
     }
     
     /// Searches for snippets, including in summaries if configured
@@ -220,14 +541,8 @@ impl SnippetCard {
         
         for (entry, content) in entries {
             // Load summary metadata if it exists
-            let summary = if let Some(summary_json) = entry.get_metadata("summary") {
-                match SummaryMetadata::from_json(summary_json) {
-                    Ok(summary) => Some(summary),
-                    Err(_) => None,
-                }
-            } else {
-                None
-            };
+            let summary = entry.get_metadata("summary")
+                .and_then(|summary_json| SummaryMetadata::from_json(summary_json).ok());
             
             results.push((entry, content, summary));
         }
@@ -266,6 +581,240 @@ impl SnippetCard {
     }
 }
 
+/// Totals reported by `snippet stats`
+#[derive(Debug, Default)]
+pub struct SnippetStats {
+    /// Number of entries
+    pub total: usize,
+    
+    /// Number of binary attachments
+    pub binary: usize,
+    
+    /// Text entries with a non-empty summary
+    pub summarized: usize,
+    
+    /// Lines across all text entries
+    pub lines: usize,
+    
+    /// Bytes across all text entries
+    pub bytes: usize,
+    
+    /// Text entries per language ("plain" when none was detected)
+    pub languages: HashMap<String, usize>,
+    
+    /// Entries per tag
+    pub tags: HashMap<String, usize>,
+}
+
+/// A problem `snippet lint` found
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    /// 1-based line number, or 0 for the snippet as a whole
+    pub line: usize,
+    
+    /// What's wrong
+    pub message: String,
+}
+
+/// Cheap checks for snippets that were saved broken: leftover merge
+/// conflicts, unbalanced brackets, mixed indentation, trailing whitespace
+/// and overlong lines
+pub fn lint_snippet(content: &str, language: Option<&str>) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        if line.starts_with("<<<<<<<") || line.starts_with(">>>>>>>") || line == "=======" {
+            issues.push(LintIssue { line: number, message: "merge conflict marker".to_string() });
+        }
+        
+        let indent: String = line.chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+        if indent.contains(' ') && indent.contains('\t') {
+            issues.push(LintIssue { line: number, message: "mixed tabs and spaces in indentation".to_string() });
+        }
+        
+        if line.ends_with(' ') || line.ends_with('\t') {
+            issues.push(LintIssue { line: number, message: "trailing whitespace".to_string() });
+        }
+        
+        let length = line.chars().count();
+        if length > MAX_LINE_LENGTH {
+            issues.push(LintIssue { line: number, message: format!("line is {} characters long (max {})", length, MAX_LINE_LENGTH) });
+        }
+    }
+    
+    if let Some(language) = language.filter(|l| BRACKET_LANGUAGES.contains(l)) {
+        issues.extend(check_brackets(content, language));
+    }
+    
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+/// Reports brackets that are never closed or closed by the wrong bracket,
+/// ignoring any inside strings and comments
+fn check_brackets(content: &str, language: &str) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut line = 1;
+    
+    for (kind, text) in tokenize(content, language) {
+        if kind == TokenKind::String || kind == TokenKind::Comment {
+            continue;
+        }
+        for c in text.chars() {
+            match c {
+                '\n' => line += 1,
+                '(' | '[' | '{' => open.push((c, line)),
+                ')' | ']' | '}' => {
+                    let expected = match c { ')' => '(', ']' => '[', _ => '{' };
+                    match open.pop() {
+                        Some((opener, _)) if opener == expected => {},
+                        Some((opener, opened_at)) => issues.push(LintIssue {
+                            line,
+                            message: format!("'{}' doesn't match '{}' opened on line {}", c, opener, opened_at),
+                        }),
+                        None => issues.push(LintIssue { line, message: format!("unmatched '{}'", c) }),
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+    
+    for (opener, opened_at) in open {
+        issues.push(LintIssue { line: opened_at, message: format!("'{}' is never closed", opener) });
+    }
+    issues
+}
+
+/// Flags shared by `new` and `from-selection`
+#[derive(Debug, Default)]
+struct NewSnippetArgs {
+    language: Option<String>,
+    message: Option<String>,
+    backpack: Option<String>,
+    tags: Vec<String>,
+}
+
+/// Parses the `--language/--message/--backpack/--tag` flags
+fn parse_new_args(args: &[String]) -> Result<NewSnippetArgs> {
+    let mut parsed = NewSnippetArgs::default();
+    
+    let mut i = 0;
+    while i < args.len() {
+        let (flag, inline) = match args[i].split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (args[i].as_str(), None),
+        };
+        let value = match inline {
+            Some(value) => value,
+            None if i + 1 < args.len() && matches!(flag, "--language" | "--message" | "--backpack" | "--tag") => {
+                i += 1;
+                args[i].clone()
+            },
+            None => return Err(anyhow!("Unexpected argument: {}", args[i])),
+        };
+        match flag {
+            "--language" => parsed.language = Some(value),
+            "--message" => parsed.message = Some(value),
+            "--backpack" => parsed.backpack = Some(value),
+            "--tag" => parsed.tags.push(value),
+            _ => return Err(anyhow!("Unexpected argument: {}", args[i])),
+        }
+        i += 1;
+    }
+    
+    Ok(parsed)
+}
+
+/// Refuses files over the size limit, and asks before storing big ones
+fn check_size(file_path: &str, size: u64) -> Result<()> {
+    let megabytes = |bytes: u64| format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0));
+    if size > MAX_ENTRY_BYTES {
+        return Err(anyhow!("{} is {}, over the {} limit", file_path, megabytes(size), megabytes(MAX_ENTRY_BYTES)));
+    }
+    if size <= LARGE_ENTRY_BYTES {
+        return Ok(());
+    }
+    
+    let question = format!("{} is {}. Store it anyway?", file_path, megabytes(size));
+    // Scripts can't answer, so they get a warning instead
+    if !std::io::stdin().is_terminal() {
+        logging::warning(&format!("{} is {}", file_path, megabytes(size)));
+        return Ok(());
+    }
+    if !crate::utils::confirm(&question, true)? {
+        return Err(anyhow!("Cancelled"));
+    }
+    Ok(())
+}
+
+/// The first few kilobytes of a file
+fn read_head(path: &Path) -> Result<Vec<u8>> {
+    let file = fs::File::open(path).context(format!("Failed to read file: {}", path.display()))?;
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    file.take(SNIFF_BYTES as u64).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// Whether a file is UTF-8 all the way through, read a chunk at a time
+fn is_utf8(path: &Path) -> Result<bool> {
+    let mut file = fs::File::open(path).context(format!("Failed to read file: {}", path.display()))?;
+    let mut buffer = vec![0; 64 * 1024];
+    let mut carried = 0;
+    loop {
+        let read = file.read(&mut buffer[carried..])?;
+        if read == 0 {
+            return Ok(carried == 0);
+        }
+        let filled = carried + read;
+        match std::str::from_utf8(&buffer[..filled]) {
+            Ok(_) => carried = 0,
+            // A character cut off by the end of the chunk; the next read finishes it
+            Err(e) if e.error_len().is_none() => {
+                buffer.copy_within(e.valid_up_to()..filled, 0);
+                carried = filled - e.valid_up_to();
+            },
+            Err(_) => return Ok(false),
+        }
+    }
+}
+
+/// The title for an entry made from a file: the message, else the file name
+fn file_title(file_path: &str, message: Option<&str>) -> String {
+    match message {
+        Some(msg) => msg.to_string(),
+        None => Path::new(file_path).file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| file_path.to_string()),
+    }
+}
+
+/// Remembers the git repository an entry was added in, so searches run
+/// there can put it first
+fn note_project(entry: &mut Entry) {
+    if let Some(root) = std::env::current_dir().ok().and_then(|dir| project::repository(&dir)) {
+        entry.add_metadata(PROJECT_KEY, &root.to_string_lossy());
+    }
+}
+
+/// Runs the checks set up under `[lint]` on content about to be added
+fn lint(storage: &StorageManager, entry: &Entry, content: &str) -> Result<()> {
+    let config = storage.load_config()?;
+    lintcheck::enforce(content, entry, &config.lint)
+}
+
+/// Warn when content about to be added already exists somewhere
+fn warn_duplicates(storage: &StorageManager, content: &str) -> Result<()> {
+    for (entry, backpack, identical) in storage.find_duplicates(content)? {
+        let location = backpack.map(|b| format!(" in backpack {}", b)).unwrap_or_default();
+        let kind = if identical { "An identical" } else { "A near-identical" };
+        logging::warning(&format!("{} entry already exists{}: {} - {}", kind, location, entry.id, entry.title));
+    }
+    Ok(())
+}
+
 impl Card for SnippetCard {
     fn name(&self) -> &str {
         &self.name
@@ -275,16 +824,15 @@ impl Card for SnippetCard {
         &self.version
     }
     
-    fn _description(&self) -> &str {
+    fn description(&self) -> &str {
         &self.description
     }
     
-    fn _initialize(&mut self, config: &CardConfig) -> Result<()> {
-        // If there are options in the card config, try to parse them
+    fn initialize(&mut self, config: &CardConfig) -> Result<()> {
+        // If there are options in the card config, parse them
         if let Some(options_value) = config.options.get("snippet") {
-            if let Ok(options) = serde_json::from_value::<SnippetCardConfig>(options_value.clone()) {
-                self.config = options;
-            }
+            self.config = serde_json::from_value::<SnippetCardConfig>(options_value.clone())
+                .map_err(|e| anyhow::anyhow!("Invalid snippet card options: {}", e))?;
         }
         
         Ok(())
@@ -299,6 +847,11 @@ impl Card for SnippetCard {
                 let mut use_clipboard = false;
                 let mut backpack = None;
                 let mut summarize = None;
+                let mut generate_summary = false;
+                let mut auto_tag = false;
+                let mut binary = None;
+                let mut interpreter = None;
+                let mut tags = Vec::new();
                 
                 // Parse arguments
                 let mut i = 0;
@@ -323,9 +876,24 @@ impl Card for SnippetCard {
                         } else {
                             return Err(anyhow!("--message requires a message string"));
                         }
+                    } else if args[i].starts_with("--tag=") {
+                        tags.push(args[i][6..].to_string());
+                        i += 1;
+                    } else if args[i].starts_with("--binary=") {
+                        binary = Some(args[i][9..].to_string());
+                        i += 1;
+                    } else if args[i].starts_with("--interpreter=") {
+                        interpreter = Some(args[i][14..].to_string());
+                        i += 1;
                     } else if args[i] == "--editor" {
                         use_editor = true;
                         i += 1;
+                    } else if args[i] == "--generate-summary" {
+                        generate_summary = true;
+                        i += 1;
+                    } else if args[i] == "--auto-tag" {
+                        auto_tag = true;
+                        i += 1;
                     } else if args[i] == "--clipboard" {
                         use_clipboard = true;
                         i += 1;
@@ -354,9 +922,53 @@ impl Card for SnippetCard {
                     }
                 }
                 
-                // Add snippet
-                let id = self.add(file.as_deref(), message.as_deref(), use_editor, use_clipboard, backpack.as_deref(), summarize.as_deref())?;
-                println!("Added snippet with ID: {}", id);
+                // Binary attachments skip all the text handling
+                // Scripts capture the ID, so nothing else goes to stdout for them
+                let script = crate::utils::progress::is_quiet() || crate::utils::output::is_json();
+                
+                let id = if let Some(binary_path) = &binary {
+                    self.add_binary(binary_path, message.as_deref(), backpack.as_deref())?
+                } else {
+                    // Add snippet
+                    self.add(file.as_deref(), message.as_deref(), use_editor, use_clipboard, backpack.as_deref(), summarize.as_deref())?
+                };
+                
+                if !tags.is_empty() || interpreter.is_some() {
+                    let storage = StorageManager::new()?;
+                    storage.update_entry(&id, backpack.as_deref(), None, |entry| {
+                        if !tags.is_empty() {
+                            entry.tags = tags;
+                        }
+                        if let Some(interpreter) = interpreter {
+                            entry.add_metadata("interpreter", &interpreter);
+                        }
+                    })?;
+                }
+                
+                if generate_summary && summarize.is_none() {
+                    let (summary, by) = self.summarize_entry(&id, backpack.as_deref())?;
+                    if !script {
+                        println!("Summary ({}): {}", by, summary);
+                    }
+                }
+                
+                if crate::utils::output::is_json() {
+                    println!("{}", serde_json::to_string_pretty(&serde_json::json!({ "id": id, "backpack": backpack }))?);
+                } else if script {
+                    println!("{}", id);
+                } else if binary.is_some() {
+                    println!("{}", tr!("Added binary entry with ID: {}", id));
+                } else {
+                    println!("{}", tr!("Added snippet with ID: {}", id));
+                }
+                
+                if auto_tag && binary.is_none() {
+                    if script {
+                        logging::warning(&format!("Suggestions not shown; run pocket tag suggest {} to see them", id));
+                    } else {
+                        self.suggest_tags(&id, backpack.as_deref(), false)?;
+                    }
+                }
                 Ok(())
             },
             "add-from-clipboard" => {
@@ -453,6 +1065,139 @@ impl Card for SnippetCard {
                 
                 Ok(())
             },
+            "new" | "from-selection" => {
+                let NewSnippetArgs { language, message, backpack, tags } = parse_new_args(args)?;
+                let id = if command == "new" {
+                    self.new_snippet(language.as_deref(), message.as_deref(), backpack.as_deref(), tags)?
+                } else {
+                    self.add_from_selection(language.as_deref(), message.as_deref(), backpack.as_deref(), tags)?
+                };
+                println!("{}", tr!("Added snippet with ID: {}", id));
+                Ok(())
+            },
+            "stats" => {
+                let backpack = match args {
+                    [] => None,
+                    [flag, name] if flag == "--backpack" => Some(name.as_str()),
+                    _ => return Err(anyhow!("Usage: pocket cards run snippet stats [--backpack BACKPACK]")),
+                };
+                let stats = self.stats(backpack)?;
+                
+                println!("Snippets: {}", stats.total);
+                println!("  Text: {} ({} lines, {} bytes)", stats.total - stats.binary, stats.lines, stats.bytes);
+                println!("  Binary: {}", stats.binary);
+                println!("  Summarized: {}", stats.summarized);
+                
+                if !stats.languages.is_empty() {
+                    println!("Languages:");
+                    for (language, count) in by_count(&stats.languages) {
+                        println!("  {}: {}", language, count);
+                    }
+                }
+                if !stats.tags.is_empty() {
+                    println!("Top tags:");
+                    for (tag, count) in by_count(&stats.tags).into_iter().take(10) {
+                        println!("  {}: {}", tag, count);
+                    }
+                }
+                Ok(())
+            },
+            "lint" => {
+                let mut references = Vec::new();
+                let mut backpack = None;
+                let mut i = 0;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" => {
+                            if i + 1 < args.len() {
+                                backpack = Some(args[i + 1].as_str());
+                                i += 2;
+                            } else {
+                                return Err(anyhow!("--backpack requires a backpack name"));
+                            }
+                        },
+                        reference => {
+                            references.push(reference.to_string());
+                            i += 1;
+                        }
+                    }
+                }
+                
+                let findings = self.lint(&references, backpack)?;
+                if findings.is_empty() {
+                    println!("No issues found");
+                    return Ok(());
+                }
+                
+                let mut count = 0;
+                for (entry, issues) in &findings {
+                    println!("{} {}", entry.id, entry.title);
+                    for issue in issues {
+                        if issue.line == 0 {
+                            println!("  {}", issue.message);
+                        } else {
+                            println!("  line {}: {}", issue.line, issue.message);
+                        }
+                    }
+                    count += issues.len();
+                }
+                Err(anyhow!("Found {} issue(s) in {} snippet(s)", count, findings.len()))
+            },
+            "summarize" => {
+                let mut reference = None;
+                let mut backpack = None;
+                let mut i = 0;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" => {
+                            if i + 1 < args.len() {
+                                backpack = Some(args[i + 1].as_str());
+                                i += 2;
+                            } else {
+                                return Err(anyhow!("--backpack requires a backpack name"));
+                            }
+                        },
+                        other => {
+                            reference = Some(other);
+                            i += 1;
+                        }
+                    }
+                }
+                
+                let reference = reference.ok_or_else(|| anyhow!("summarize requires an entry ID"))?;
+                let (summary, by) = self.summarize_entry(reference, backpack)?;
+                println!("Summary ({}): {}", by, summary);
+                Ok(())
+            },
+            "suggest-tags" => {
+                let mut reference = None;
+                let mut backpack = None;
+                let mut yes = false;
+                let mut i = 0;
+                while i < args.len() {
+                    match args[i].as_str() {
+                        "--backpack" => {
+                            if i + 1 < args.len() {
+                                backpack = Some(args[i + 1].as_str());
+                                i += 2;
+                            } else {
+                                return Err(anyhow!("--backpack requires a backpack name"));
+                            }
+                        },
+                        "--yes" => {
+                            yes = true;
+                            i += 1;
+                        },
+                        other => {
+                            reference = Some(other);
+                            i += 1;
+                        }
+                    }
+                }
+                
+                let reference = reference.ok_or_else(|| anyhow!("suggest-tags requires an entry ID"))?;
+                self.suggest_tags(reference, backpack, yes)
+            },
             "config" => {
                 // Show current configuration
                 println!("Snippet card configuration:");
@@ -471,7 +1216,7 @@ impl Card for SnippetCard {
             CardCommand {
                 name: "add".to_string(),
                 description: "Add a new snippet from a file or editor".to_string(),
-                usage: "pocket cards execute snippet add [--file=FILE] [--message=MESSAGE] [--editor] [--backpack=BACKPACK] [--summarize=SUMMARY]".to_string(),
+                usage: "pocket cards execute snippet add [--file=FILE] [--message=MESSAGE] [--editor] [--backpack=BACKPACK] [--summarize=SUMMARY] [--generate-summary] [--auto-tag] [--binary=FILE] [--tag=TAG]... [--interpreter=INTERPRETER]".to_string(),
             },
             CardCommand {
                 name: "add-from-clipboard".to_string(),
@@ -483,6 +1228,36 @@ impl Card for SnippetCard {
                 description: "Search for snippets, including in summaries".to_string(),
                 usage: "pocket cards execute snippet search QUERY [LIMIT] [--backpack BACKPACK]".to_string(),
             },
+            CardCommand {
+                name: "new".to_string(),
+                description: "Write a new snippet in your editor".to_string(),
+                usage: "pocket cards run snippet new [--language LANG] [--message TITLE] [--backpack BACKPACK] [--tag TAG]...".to_string(),
+            },
+            CardCommand {
+                name: "from-selection".to_string(),
+                description: "Add the selected text (or piped stdin) as a snippet".to_string(),
+                usage: "pocket cards run snippet from-selection [--language LANG] [--message TITLE] [--backpack BACKPACK] [--tag TAG]...".to_string(),
+            },
+            CardCommand {
+                name: "stats".to_string(),
+                description: "Show counts by language and tag".to_string(),
+                usage: "pocket cards run snippet stats [--backpack BACKPACK]".to_string(),
+            },
+            CardCommand {
+                name: "lint".to_string(),
+                description: "Check snippets for conflict markers, unbalanced brackets and whitespace problems".to_string(),
+                usage: "pocket cards run snippet lint [ID]... [--backpack BACKPACK]".to_string(),
+            },
+            CardCommand {
+                name: "summarize".to_string(),
+                description: "Summarize a snippet with the configured language model".to_string(),
+                usage: "pocket cards run snippet summarize ID [--backpack BACKPACK]".to_string(),
+            },
+            CardCommand {
+                name: "suggest-tags".to_string(),
+                description: "Suggest tags and a title for a snippet".to_string(),
+                usage: "pocket cards run snippet suggest-tags ID [--backpack BACKPACK] [--yes]".to_string(),
+            },
             CardCommand {
                 name: "config".to_string(),
                 description: "Show current snippet card configuration".to_string(),
@@ -494,4 +1269,76 @@ impl Card for SnippetCard {
     fn cleanup(&mut self) -> Result<()> {
         Ok(())
     }
-} 
\ No newline at end of file
+} 
+
+/// Counts sorted from most to least common, then by name
+fn by_count(counts: &HashMap<String, usize>) -> Vec<(&str, usize)> {
+    let sorted: BTreeMap<&str, usize> = counts.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+    let mut sorted: Vec<(&str, usize)> = sorted.into_iter().collect();
+    sorted.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn messages(content: &str, language: Option<&str>) -> Vec<(usize, String)> {
+        lint_snippet(content, language).into_iter().map(|i| (i.line, i.message)).collect()
+    }
+    
+    #[test]
+    fn checks_utf8_past_the_first_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = |bytes: &[u8]| {
+            let path = dir.path().join("file");
+            fs::write(&path, bytes).unwrap();
+            is_utf8(&path).unwrap()
+        };
+        
+        // A character straddling two chunks is fine
+        let mut text = vec![b'a'; 64 * 1024 - 1];
+        text.extend("é and more".as_bytes());
+        assert!(check(&text));
+        
+        let mut broken = vec![b'a'; SNIFF_BYTES * 4];
+        broken.push(0xff);
+        assert!(!check(&broken));
+        
+        // Cut off in the middle of a character
+        assert!(!check(&"é".as_bytes()[..1]));
+    }
+    
+    #[test]
+    fn clean_snippet_has_no_issues() {
+        assert!(lint_snippet("fn main() {\n    println!(\"{}\", \"(\");\n}\n", Some("rust")).is_empty());
+    }
+    
+    #[test]
+    fn flags_whitespace_and_conflicts() {
+        let issues = messages("a \n\t  b\n<<<<<<< HEAD\n", None);
+        assert_eq!(issues, vec![
+            (1, "trailing whitespace".to_string()),
+            (2, "mixed tabs and spaces in indentation".to_string()),
+            (3, "merge conflict marker".to_string()),
+        ]);
+    }
+    
+    #[test]
+    fn flags_unbalanced_brackets() {
+        assert_eq!(messages("fn main() {\n", Some("rust")), vec![(1, "'{' is never closed".to_string())]);
+        assert_eq!(messages("foo(]\n", Some("javascript")), vec![(1, "']' doesn't match '(' opened on line 1".to_string())]);
+        assert_eq!(messages("x)\n", Some("python")), vec![(1, "unmatched ')'".to_string())]);
+        
+        // Brackets in comments don't count, and shell isn't checked at all
+        assert!(lint_snippet("x = 1 # (\n", Some("python")).is_empty());
+        assert!(lint_snippet("case $x in\n  a) echo;;\nesac\n", Some("shell")).is_empty());
+    }
+    
+    #[test]
+    fn flags_long_lines() {
+        let issues = messages(&"x".repeat(MAX_LINE_LENGTH + 1), None);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].0, 1);
+    }
+}
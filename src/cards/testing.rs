@@ -0,0 +1,152 @@
+//! Test harness for card authors
+//!
+//! `pocket cards test <name>` runs the `[[tests]]` declared in a card's
+//! `card.toml`. Every test is a real `pocket cards run` invocation against a
+//! throwaway home directory holding only the card under test, so nothing
+//! touches the author's own `~/.pocket`. Tests run in order and share that
+//! directory, so later tests can rely on what earlier ones stored.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use anyhow::{Result, Context, bail};
+
+use crate::cards::CardConfig;
+use crate::cards::manifest::{CardManifest, CardTest};
+use crate::storage;
+
+/// Result of one test
+#[derive(Debug)]
+pub struct TestOutcome {
+    /// Name of the test
+    pub name: String,
+    
+    /// Why the test failed, or `None` if it passed
+    pub failure: Option<String>,
+}
+
+/// Runs a card's declared tests using the `pocket` binary at `pocket_bin`
+pub fn run_card_tests(pocket_bin: &Path, card_dir: &Path, name: &str) -> Result<Vec<TestOutcome>> {
+    let manifest = CardManifest::load(card_dir, name)?;
+    if manifest.tests.is_empty() {
+        bail!("Card '{}' declares no [[tests]] in its card.toml", name);
+    }
+    
+    // A fresh home with only this card in its wallet
+    let home = tempfile::tempdir().context("Failed to create a temporary home directory")?;
+    let pocket_dir = home.path().join(".pocket");
+    let wallet_dir = pocket_dir.join("wallet");
+    fs::create_dir_all(&wallet_dir)?;
+    fs::create_dir_all(pocket_dir.join("cards"))?;
+    link_dir(&card_dir.canonicalize()?, &wallet_dir.join(name))?;
+    
+    // Grant what the card asks for, since nobody is around to approve it
+    let mut configs = HashMap::new();
+    configs.insert(name.to_string(), CardConfig {
+        name: name.to_string(),
+        enabled: true,
+        options: HashMap::new(),
+        permissions: Some(manifest.permissions.clone()),
+    });
+    fs::write(pocket_dir.join("cards").join("cards.json"), serde_json::to_string_pretty(&configs)?)?;
+    
+    let mut outcomes = Vec::new();
+    for test in &manifest.tests {
+        let output = Command::new(pocket_bin)
+            .args(["cards", "run", name, &test.command])
+            .args(&test.args)
+            .env("HOME", home.path())
+            // Nothing may steer it back to the real pocket
+            .env_remove(storage::home::HOME_ENV)
+            .env_remove(storage::profile::PROFILE_ENV)
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .current_dir(home.path())
+            .output()
+            .with_context(|| format!("Failed to run {}", pocket_bin.display()))?;
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        outcomes.push(TestOutcome {
+            name: test.name.clone(),
+            failure: check(test, output.status.success(), &stdout, &stderr),
+        });
+    }
+    
+    Ok(outcomes)
+}
+
+/// Compares a test's run against its expectations
+fn check(test: &CardTest, succeeded: bool, stdout: &str, stderr: &str) -> Option<String> {
+    if succeeded == test.fails {
+        return Some(if test.fails {
+            "expected the command to fail, but it succeeded".to_string()
+        } else {
+            format!("command failed: {}", stderr.trim())
+        });
+    }
+    
+    if let Some(expected) = &test.output {
+        if stdout.trim() != expected.trim() {
+            return Some(format!("expected output {:?}, got {:?}", expected.trim(), stdout.trim()));
+        }
+    }
+    
+    let output = format!("{}{}", stdout, stderr);
+    if let Some(missing) = test.contains.iter().find(|text| !output.contains(text.as_str())) {
+        return Some(format!("output doesn't contain {:?}", missing));
+    }
+    
+    None
+}
+
+/// Makes `dir` appear at `link`, without copying build output where possible
+fn link_dir(dir: &Path, link: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(dir, link)?;
+    }
+    
+    #[cfg(not(unix))]
+    {
+        for entry in walkdir::WalkDir::new(dir) {
+            let entry = entry?;
+            let target = link.join(entry.path().strip_prefix(dir)?);
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&target)?;
+            } else {
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn test(output: Option<&str>, contains: &[&str], fails: bool) -> CardTest {
+        CardTest {
+            name: "t".to_string(),
+            command: "c".to_string(),
+            args: Vec::new(),
+            output: output.map(str::to_string),
+            contains: contains.iter().map(|s| s.to_string()).collect(),
+            fails,
+        }
+    }
+    
+    #[test]
+    fn checks_expectations() {
+        assert!(check(&test(Some("Hello, bob!"), &[], false), true, "Hello, bob!\n", "").is_none());
+        assert!(check(&test(Some("Hello, bob!"), &[], false), true, "Hello, alice!\n", "").is_some());
+        assert!(check(&test(None, &["bob"], false), true, "Hello, bob!\n", "").is_none());
+        assert!(check(&test(None, &["carol"], false), true, "Hello, bob!\n", "").is_some());
+        assert!(check(&test(None, &[], false), false, "", "boom").is_some());
+        assert!(check(&test(None, &["Unknown command"], true), false, "", "Unknown command: x").is_none());
+        assert!(check(&test(None, &[], true), true, "", "").is_some());
+    }
+}
@@ -0,0 +1,94 @@
+//! Change detection for the card wallet
+//!
+//! Polls modification times rather than relying on OS notifications, which
+//! keeps it dependency-free and portable. Build output is ignored except for
+//! the card libraries themselves, so a rebuild shows up once it's finished
+//! rather than for every intermediate artifact.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// Directories under `target` that never hold a loadable card library
+const IGNORED_DIRS: &[&str] = &[".git", "deps", "build", "incremental", ".fingerprint", "examples"];
+
+/// Watches a set of directories for files being added, changed or removed
+pub struct WalletWatcher {
+    /// Directories being watched
+    roots: Vec<PathBuf>,
+    
+    /// Modification time of every watched file as of the last check
+    seen: HashMap<PathBuf, SystemTime>,
+}
+
+impl WalletWatcher {
+    /// Starts watching the given directories; missing ones are fine
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        let seen = scan(&roots);
+        Self { roots, seen }
+    }
+    
+    /// Whether anything changed since the last call (or since creation)
+    pub fn changed(&mut self) -> bool {
+        let current = scan(&self.roots);
+        let changed = current != self.seen;
+        self.seen = current;
+        changed
+    }
+}
+
+/// Collects the modification time of every relevant file under `roots`
+fn scan(roots: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    let mut seen = HashMap::new();
+    for root in roots {
+        let walker = WalkDir::new(root).into_iter().filter_entry(|entry| {
+            !(entry.file_type().is_dir() && entry.file_name().to_str().is_some_and(|name| IGNORED_DIRS.contains(&name)))
+        });
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() || !is_relevant(entry.path()) {
+                continue;
+            }
+            if let Ok(modified) = entry.metadata().map_err(std::io::Error::from).and_then(|m| m.modified()) {
+                seen.insert(entry.path().to_path_buf(), modified);
+            }
+        }
+    }
+    seen
+}
+
+/// Build output only matters when it's a card library
+fn is_relevant(path: &Path) -> bool {
+    if !path.components().any(|c| c.as_os_str() == "target") {
+        return true;
+    }
+    matches!(path.extension().and_then(|e| e.to_str()), Some("so" | "dylib" | "dll"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    
+    #[test]
+    fn notices_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("card/target/debug/deps")).unwrap();
+        let mut watcher = WalletWatcher::new(vec![dir.path().to_path_buf()]);
+        assert!(!watcher.changed());
+        
+        // Intermediate build output is ignored
+        fs::write(dir.path().join("card/target/debug/deps/thing.rlib"), "x").unwrap();
+        assert!(!watcher.changed());
+        
+        fs::write(dir.path().join("card/target/debug/libpocket_card_card.so"), "x").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+        
+        fs::write(dir.path().join("card/card.toml"), "").unwrap();
+        assert!(watcher.changed());
+        
+        fs::remove_file(dir.path().join("card/card.toml")).unwrap();
+        assert!(watcher.changed());
+    }
+}
@@ -0,0 +1,163 @@
+//! Command aliases: shortcuts like `pocket s docker` for
+//! `pocket search --limit 3 docker`
+//!
+//! They're kept under `[aliases]` in `config.toml` and expanded before the
+//! command line is parsed, so anything after the alias is added to the end
+//! of what it stands for. An alias can stand for another alias, but never
+//! for a built-in command or one a card provides; those always win.
+//! `pocket alias add` names entries instead, and `alias list` and
+//! `alias remove` cover both kinds.
+
+use crate::cards::CardManager;
+use crate::errors::{PocketError, PocketResult};
+use crate::storage::StorageManager;
+use crate::i18n::tr;
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Makes `name` stand for `command`
+pub fn set(name: &str, command: &str, cli: &clap::Command) -> PocketResult<()> {
+    if name.is_empty() || name.starts_with('-') || name.chars().any(char::is_whitespace) {
+        return Err(PocketError::Validation(format!("Invalid alias name '{}': aliases can't contain whitespace or start with '-'", name)));
+    }
+    if command_names(cli).contains(name) {
+        return Err(PocketError::Validation(format!("'{}' is already a pocket command", name)));
+    }
+    let words = shell_words::split(command).map_err(|e| PocketError::Validation(format!("Can't parse '{}': {}", command, e)))?;
+    if words.is_empty() {
+        return Err(PocketError::Validation("An alias needs a command to stand for".to_string()));
+    }
+
+    let storage = StorageManager::new().map_err(PocketError::storage)?;
+    let mut config = storage.load_config().map_err(|e| PocketError::Config(e.to_string()))?;
+    config.aliases.insert(name.to_string(), command.to_string());
+    expand(vec![name.to_string()], &config.aliases, cli).map_err(|e| PocketError::Validation(e.to_string()))?;
+    storage.save_config(&config).map_err(|e| PocketError::Config(e.to_string()))?;
+    println!("{}", tr!("Alias {} = {}", name.bold(), command));
+    Ok(())
+}
+
+/// Removes a command alias, or the entry alias of that name when there's
+/// no command alias
+pub fn remove(name: &str, card_manager: &CardManager) -> PocketResult<()> {
+    let storage = StorageManager::new().map_err(PocketError::storage)?;
+    let mut config = storage.load_config().map_err(|e| PocketError::Config(e.to_string()))?;
+    if config.aliases.remove(name).is_none() {
+        return card_manager.execute_command("core", "alias-remove", &[name.to_string()])
+            .map_err(|e| PocketError::Card(format!("Failed to remove alias: {}", e)));
+    }
+    storage.save_config(&config).map_err(|e| PocketError::Config(e.to_string()))?;
+    println!("{}", tr!("Removed alias: {}", name.bold()));
+    Ok(())
+}
+
+/// Lists command aliases, then entry aliases
+pub fn list(card_manager: &CardManager) -> PocketResult<()> {
+    let storage = StorageManager::new().map_err(PocketError::storage)?;
+    let config = storage.load_config().map_err(|e| PocketError::Config(e.to_string()))?;
+    let entries = storage.load_aliases().map_err(PocketError::storage)?;
+    if config.aliases.is_empty() && entries.is_empty() {
+        println!("{}", tr!("No aliases defined"));
+        return Ok(());
+    }
+
+    for (name, command) in &config.aliases {
+        println!("{} = {}", name.bold(), command);
+    }
+    if entries.is_empty() {
+        return Ok(());
+    }
+    card_manager.execute_command("core", "alias-list", &[])
+        .map_err(|e| PocketError::Card(format!("Failed to list aliases: {}", e)))
+}
+
+/// Replaces an alias at the start of `args` (the command line without the
+/// binary name) with what it stands for, over and over while that's an
+/// alias too. Global options before the command are left where they are
+pub fn expand(mut args: Vec<String>, aliases: &BTreeMap<String, String>, cli: &clap::Command) -> Result<Vec<String>> {
+    if aliases.is_empty() {
+        return Ok(args);
+    }
+    let Some(at) = command_position(&args, cli) else {
+        return Ok(args);
+    };
+    let commands = command_names(cli);
+
+    let mut seen: Vec<String> = Vec::new();
+    while let Some(command) = aliases.get(&args[at]).filter(|_| !commands.contains(&args[at])) {
+        if seen.contains(&args[at]) {
+            seen.push(args[at].clone());
+            return Err(anyhow!("Alias cycle: {}", seen.join(" -> ")));
+        }
+        seen.push(args[at].clone());
+        let words = shell_words::split(command).map_err(|e| anyhow!("Can't parse alias '{}': {}", args[at], e))?;
+        if words.is_empty() {
+            return Err(anyhow!("Alias '{}' doesn't stand for anything", args[at]));
+        }
+        args.splice(at..=at, words);
+    }
+    Ok(args)
+}
+
+/// Where the command is in `args`, after any global options
+fn command_position(args: &[String], cli: &clap::Command) -> Option<usize> {
+    // Options whose value is the next argument
+    let mut takes_value = HashSet::new();
+    for arg in cli.get_arguments() {
+        if arg.get_action().takes_values() && !arg.is_require_equals_set() {
+            takes_value.extend(arg.get_long().map(|long| format!("--{}", long)));
+            takes_value.extend(arg.get_short().map(|short| format!("-{}", short)));
+        }
+    }
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--" {
+            return None;
+        }
+        if !arg.starts_with('-') {
+            return Some(i);
+        }
+        i += if takes_value.contains(arg) { 2 } else { 1 };
+    }
+    None
+}
+
+/// Every name that runs a command without any alias
+fn command_names(cli: &clap::Command) -> HashSet<String> {
+    cli.get_subcommands()
+        .flat_map(|command| std::iter::once(command.get_name()).chain(command.get_all_aliases()))
+        .chain(std::iter::once("help"))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn expands_aliases_until_a_command_turns_up() {
+        let cli = crate::cli::handler::cli_command(&BTreeMap::new());
+        let mut aliases = BTreeMap::new();
+        aliases.insert("s".to_string(), "search --limit 3".to_string());
+        aliases.insert("ss".to_string(), "s --fuzzy".to_string());
+        aliases.insert("list".to_string(), "search".to_string());
+
+        assert_eq!(expand(args("ss docker"), &aliases, &cli).unwrap(), args("search --limit 3 --fuzzy docker"));
+        assert_eq!(expand(args("--profile work -v s helm"), &aliases, &cli).unwrap(), args("--profile work -v search --limit 3 helm"));
+        // Built-in commands can't be shadowed
+        assert_eq!(expand(args("list"), &aliases, &cli).unwrap(), args("list"));
+
+        aliases.insert("a".to_string(), "b".to_string());
+        aliases.insert("b".to_string(), "a".to_string());
+        let cycle = expand(args("a"), &aliases, &cli).unwrap_err().to_string();
+        assert_eq!(cycle, "Alias cycle: a -> b -> a");
+    }
+}
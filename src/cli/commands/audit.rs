@@ -0,0 +1,35 @@
+//! `pocket audit`: what changed the pocket, and when
+
+use crate::errors::{PocketError, PocketResult};
+use crate::storage::{self, audit};
+use crate::utils::time;
+use colored::Colorize;
+
+/// Prints the audit log, from `since` on when given
+pub fn show(since: Option<&str>, json: bool) -> PocketResult<()> {
+    let since = since
+        .map(|raw| time::parse_since(raw, chrono::Utc::now()))
+        .transpose()
+        .map_err(|e| PocketError::Validation(e.to_string()))?;
+    let dir = storage::pocket_dir().map_err(PocketError::storage)?;
+    let events = audit::read(&dir, since).map_err(PocketError::storage)?;
+
+    if json {
+        for event in &events {
+            println!("{}", serde_json::to_string(event).map_err(|e| PocketError::Other(e.to_string()))?);
+        }
+        return Ok(());
+    }
+    if events.is_empty() {
+        println!("Nothing changed the pocket{}", if since.is_some() { " since then" } else { " yet" });
+    }
+    for event in &events {
+        let time = event.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+        let outcome = if event.ok { "ok".green() } else { "failed".red() };
+        println!("{}  {:<6}  pocket {}", time.to_string().dimmed(), outcome, event.args.join(" "));
+        if let Some(error) = &event.error {
+            println!("{}", format!("    {}", error).dimmed());
+        }
+    }
+    Ok(())
+}
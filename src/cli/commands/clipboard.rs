@@ -0,0 +1,189 @@
+//! `pocket watch-clipboard`: saving code as it's copied
+
+use crate::cards::{events, CardManager, Event};
+use crate::errors::{PocketError, PocketResult};
+use crate::logging;
+use crate::models::Entry;
+use crate::storage::{self, StorageManager};
+use crate::utils::{self, clipboard};
+use crate::workflow::schedule;
+use colored::Colorize;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+/// Shortest copy worth looking at
+const MIN_CHARS: usize = 8;
+
+/// Lines of a copy shown when asking whether to save it
+const PREVIEW_LINES: usize = 3;
+
+/// How `pocket watch-clipboard` was asked to behave
+pub struct WatchOptions {
+    pub backpack: Option<String>,
+    pub tag: String,
+    pub auto_save: bool,
+    pub interval: u64,
+    pub window: u64,
+}
+
+/// Polls the clipboard until interrupted, offering to save each new copy
+/// that looks like code
+pub fn watch(options: WatchOptions, card_manager: &mut CardManager) -> PocketResult<()> {
+    if !options.auto_save && !std::io::stdin().is_terminal() {
+        return Err(PocketError::Cli("Watching without a terminal to ask on needs --auto".to_string()));
+    }
+    let storage = StorageManager::new().map_err(PocketError::storage)?;
+    // Whatever is on the clipboard already was copied before watching began
+    let mut last = clipboard::read_clipboard().map_err(|e| PocketError::Other(e.to_string()))?;
+    let mut seen = SeenCopies::new(Duration::from_secs(options.window * 60));
+    seen.is_new(&last, Instant::now());
+    
+    let location = options.backpack.as_deref().unwrap_or("the general pocket");
+    println!("Watching the clipboard for code to save in {} (Ctrl-C to stop)", location.bold());
+    
+    loop {
+        std::thread::sleep(Duration::from_secs(options.interval.max(1)));
+        // A copy that can't be read now is read on the next poll
+        let Ok(content) = clipboard::read_clipboard() else {
+            continue;
+        };
+        if content == last {
+            continue;
+        }
+        last = content.clone();
+        if !looks_like_code(&content) || !seen.is_new(&content, Instant::now()) {
+            continue;
+        }
+        
+        let already_saved = storage.find_duplicates(&content)
+            .map(|duplicates| duplicates.iter().any(|(_, _, identical)| *identical))
+            .unwrap_or(false);
+        if already_saved {
+            println!("{}", "Copied something already in your pocket".dimmed());
+            continue;
+        }
+        
+        let language = utils::detect_language(None, &content);
+        if !options.auto_save {
+            println!();
+            println!("{} {}", "Copied".bold(), language.as_deref().unwrap_or("code").cyan());
+            for line in content.lines().take(PREVIEW_LINES) {
+                println!("  {}", line.dimmed());
+            }
+            if content.lines().count() > PREVIEW_LINES {
+                println!("  {}", "...".dimmed());
+            }
+            match utils::confirm("Save it?", true) {
+                Ok(true) => {},
+                Ok(false) => continue,
+                Err(e) => return Err(PocketError::Cli(e.to_string())),
+            }
+        }
+        
+        let mut entry = Entry::new(
+            content.lines().next().unwrap_or_default().trim().to_string(),
+            utils::detect_content_type(None, Some(&content)),
+            None,
+            vec![options.tag.clone()],
+        );
+        entry.language = language;
+        match storage.save_entry(&entry, &content, options.backpack.as_deref()) {
+            Ok(()) => {
+                println!("Saved {} - {}", entry.id, entry.title);
+                events::emit(Event::EntryAdded { id: entry.id.clone(), backpack: options.backpack.clone() });
+                card_manager.dispatch_events();
+                schedule::run_triggers(card_manager);
+            },
+            Err(e) => logging::warning(&format!("Failed to save the copy: {}", e)),
+        }
+    }
+}
+
+/// Copies seen recently, so copying the same thing again doesn't save it twice
+struct SeenCopies {
+    window: Duration,
+    seen: HashMap<String, Instant>,
+}
+
+impl SeenCopies {
+    fn new(window: Duration) -> Self {
+        Self { window, seen: HashMap::new() }
+    }
+    
+    /// Whether `content` wasn't seen within the window before `now`. Either
+    /// way, it counts as seen at `now`
+    fn is_new(&mut self, content: &str, now: Instant) -> bool {
+        let window = self.window;
+        self.seen.retain(|_, at| now.duration_since(*at) < window);
+        self.seen.insert(storage::content_hash(content), now).is_none()
+    }
+}
+
+/// Whether copied text looks like code rather than prose, a link or a word.
+/// At least half its lines have to look like code, or one line does and
+/// the language can be told from the content
+pub fn looks_like_code(content: &str) -> bool {
+    let text = content.trim();
+    if text.chars().count() < MIN_CHARS || url::Url::parse(text).is_ok_and(|_| !text.contains(char::is_whitespace)) {
+        return false;
+    }
+    if text.starts_with("#!") {
+        return true;
+    }
+    
+    let lines: Vec<&str> = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    let code_lines = lines.iter().filter(|line| line_looks_like_code(line)).count();
+    code_lines * 2 >= lines.len() || (code_lines > 0 && utils::detect_language(None, text).is_some())
+}
+
+/// Whether one trimmed line looks like code
+fn line_looks_like_code(line: &str) -> bool {
+    const ENDINGS: [char; 7] = [';', '{', '}', '(', ')', '[', ']'];
+    const MARKERS: [&str; 11] = [" = ", "->", "=>", "::", "()", "&&", "||", "$(", "</", "/>", " | "];
+    const STARTS: [&str; 22] = [
+        "fn ", "pub ", "let ", "const ", "var ", "def ", "class ", "import ", "from ", "use ",
+        "function ", "return ", "if ", "for ", "while ", "#include", "select ", "sudo ", "git ",
+        "cargo ", "npm ", "docker ",
+    ];
+    
+    // Sentences are prose, whatever's in them
+    let words = line.split_whitespace().count();
+    if words > 3 && line.ends_with(['.', '?', '!']) {
+        return false;
+    }
+    let lower = line.to_lowercase();
+    line.ends_with(ENDINGS)
+        || MARKERS.iter().any(|marker| line.contains(marker))
+        || STARTS.iter().any(|start| lower.starts_with(start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn tells_code_from_other_copies() {
+        assert!(looks_like_code("fn main() {\n    println!(\"hi\");\n}"));
+        assert!(looks_like_code("git log --oneline | head -5"));
+        assert!(looks_like_code("#!/bin/sh\necho hello"));
+        assert!(looks_like_code("SELECT id, name FROM users WHERE active = 1;"));
+        
+        assert!(!looks_like_code("hello"));
+        assert!(!looks_like_code("https://example.com/some/long/path"));
+        assert!(!looks_like_code("Thanks for the review, I'll fix that tomorrow."));
+        assert!(!looks_like_code("Meeting moved to Thursday\nBring the slides\nLunch is provided"));
+    }
+    
+    #[test]
+    fn forgets_copies_after_the_window() {
+        let mut seen = SeenCopies::new(Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(seen.is_new("let x = 1;", start));
+        assert!(!seen.is_new("let x = 1;", start + Duration::from_secs(30)));
+        assert!(seen.is_new("let y = 2;", start + Duration::from_secs(30)));
+        // Seen again at 30s, so it's still fresh at 60s
+        assert!(!seen.is_new("let x = 1;", start + Duration::from_secs(60)));
+        assert!(seen.is_new("let x = 1;", start + Duration::from_secs(200)));
+    }
+}
@@ -0,0 +1,204 @@
+//! `pocket doctor`: checking the pocket directory, config, entries, cards
+//! and the tools pocket leans on, and fixing what's safe to fix
+
+use crate::cards::CardManager;
+use crate::errors::{PocketError, PocketResult};
+use crate::models::ClipboardProvider;
+use crate::storage::{self, StorageManager};
+use crate::utils::clipboard;
+use colored::Colorize;
+use std::env;
+use std::path::Path;
+
+/// Tallies what the checks found
+#[derive(Default)]
+struct Report {
+    problems: usize,
+    fixed: usize,
+    warnings: usize,
+}
+
+impl Report {
+    fn ok(&self, message: &str) {
+        println!("  {} {}", "✓".green(), message);
+    }
+
+    fn warn(&mut self, message: &str) {
+        self.warnings += 1;
+        println!("  {} {}", "!".yellow().bold(), message);
+    }
+
+    fn problem(&mut self, message: &str) {
+        self.problems += 1;
+        println!("  {} {}", "✗".red().bold(), message);
+    }
+
+    /// Reports the outcome of fixing the last problem
+    fn fixed(&mut self, outcome: anyhow::Result<String>) {
+        match outcome {
+            Ok(done) => {
+                self.fixed += 1;
+                println!("    {} {}", "fixed:".green(), done);
+            },
+            Err(e) => println!("    {} {}", "couldn't fix:".red(), e),
+        }
+    }
+}
+
+/// Runs every check, repairing what it safely can when `fix` is set
+pub fn run(card_manager: &mut CardManager, fix: bool) -> PocketResult<()> {
+    let mut report = Report::default();
+
+    println!("{}", "Data".bold());
+    let dir = storage::pocket_dir().map_err(|e| PocketError::Config(e.to_string()))?;
+    report.ok(&format!("Pocket directory is {}", dir.display()));
+    let storage = match StorageManager::new() {
+        Ok(storage) => storage,
+        Err(e) => {
+            report.problem(&format!("Can't set up the pocket directory: {}", e));
+            return finish(&report, fix);
+        },
+    };
+    check_entries(&storage, &mut report, fix);
+
+    println!("{}", "Config".bold());
+    let config = match storage.load_config() {
+        Ok(config) => {
+            report.ok("config.toml is valid");
+            Some(config)
+        },
+        Err(e) => {
+            report.problem(&format!("config.toml doesn't load: {} (fix it by hand, or move it away to start fresh)", e));
+            None
+        },
+    };
+
+    println!("{}", "Cards".bold());
+    check_cards(card_manager, &mut report, fix);
+
+    println!("{}", "Tools".bold());
+    check_tools(
+        config.as_ref().map(|config| config.user.editor.as_str()),
+        config.as_ref().map(|config| config.clipboard.provider).unwrap_or_default(),
+        &mut report,
+    );
+
+    finish(&report, fix)
+}
+
+fn check_entries(storage: &StorageManager, report: &mut Report, fix: bool) {
+    let issues = match storage.check_health() {
+        Ok(issues) => issues,
+        Err(e) => {
+            report.problem(&format!("Couldn't check entries: {}", e));
+            return;
+        },
+    };
+    if issues.is_empty() {
+        report.ok("Entries, aliases and pins are consistent");
+    }
+    for issue in issues {
+        report.problem(&issue.to_string());
+        if fix {
+            report.fixed(storage.repair(&issue));
+        }
+    }
+}
+
+fn check_cards(card_manager: &mut CardManager, report: &mut Report, fix: bool) {
+    let unloaded = card_manager.unloaded_cards();
+    if unloaded.is_empty() {
+        report.ok("Every configured card is loaded");
+    }
+    for (name, installed) in unloaded {
+        if installed {
+            report.problem(&format!("Card '{}' is installed but didn't load (try `pocket cards build {}`)", name, name));
+        } else {
+            report.problem(&format!("Card '{}' is configured but not installed", name));
+            if fix {
+                let outcome = card_manager.remove_card_config(&name)
+                    .map(|()| format!("Removed the config for '{}'", name));
+                report.fixed(outcome);
+            }
+        }
+    }
+}
+
+fn check_tools(configured_editor: Option<&str>, clipboard: ClipboardProvider, report: &mut Report) {
+    let editor = configured_editor.filter(|editor| !editor.is_empty()).map(str::to_string)
+        .or_else(|| env::var("EDITOR").ok().filter(|editor| !editor.is_empty()))
+        .or_else(|| env::var("VISUAL").ok().filter(|editor| !editor.is_empty()));
+    match editor {
+        Some(editor) => {
+            let program = editor.split_whitespace().next().unwrap_or_default();
+            if on_path(program) {
+                report.ok(&format!("Editor {} is installed", program));
+            } else {
+                report.warn(&format!("Editor '{}' isn't on your PATH; set user.editor in config.toml or $EDITOR", program));
+            }
+        },
+        None => report.warn("No editor set; set user.editor in config.toml or $EDITOR"),
+    }
+
+    let tools = clipboard::copy_tools(clipboard);
+    if tools.is_empty() {
+        report.ok("Clipboard copies through OSC 52 in the terminal");
+    } else {
+        match tools.iter().find(|tool| on_path(tool)) {
+            Some(tool) => report.ok(&format!("Clipboard tool {} is installed", tool)),
+            None if clipboard == ClipboardProvider::Auto => {
+                report.warn(&format!("No clipboard tool found (install {}); copying falls back to OSC 52 in a terminal and pasting won't work", tools.join(" or ")));
+            },
+            None => report.warn(&format!("Clipboard tool {} isn't installed; clipboard commands won't work", tools.join(" or "))),
+        }
+    }
+
+    if on_path("git") {
+        report.ok("git is installed");
+    } else {
+        report.warn("git isn't installed; installing and updating cards needs it");
+    }
+}
+
+/// Prints the summary, failing when problems are left
+fn finish(report: &Report, fix: bool) -> PocketResult<()> {
+    println!();
+    let left = report.problems - report.fixed;
+    if left == 0 && report.problems > 0 {
+        println!("{}", format!("Fixed {} problem(s)", report.problems).green().bold());
+    } else if left == 0 {
+        println!("{}", "Everything looks healthy".green().bold());
+    }
+    if report.warnings > 0 {
+        println!("{} warning(s)", report.warnings);
+    }
+    if left > 0 {
+        if !fix {
+            println!("Run `pocket doctor --fix` to repair what can be repaired safely");
+        }
+        return Err(PocketError::Storage(format!("{} problem(s) found", left)));
+    }
+    Ok(())
+}
+
+/// Whether a program can be found on the PATH. On Windows it can leave off
+/// an extension from `PATHEXT`, like `.exe` or `.cmd`
+fn on_path(program: &str) -> bool {
+    if Path::new(program).components().count() > 1 {
+        return Path::new(program).is_file();
+    }
+    let Some(paths) = env::var_os("PATH") else {
+        return false;
+    };
+    let extensions: Vec<String> = if cfg!(windows) {
+        env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT;.COM".to_string())
+            .split(';')
+            .map(str::to_lowercase)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    env::split_paths(&paths).any(|dir| {
+        dir.join(program).is_file() || extensions.iter().any(|ext| dir.join(format!("{}{}", program, ext)).is_file())
+    })
+}
@@ -0,0 +1,26 @@
+//! `pocket home`: where pocket keeps its files, and moving them
+
+use crate::errors::{PocketError, PocketResult};
+use crate::storage::{home, profile};
+use colored::Colorize;
+use std::path::Path;
+
+/// Prints the data directory and config file in use
+pub fn show() -> PocketResult<()> {
+    let layout = home::layout().map_err(|e| PocketError::Config(e.to_string()))?;
+    let dir = profile::pocket_dir().map_err(|e| PocketError::Config(e.to_string()))?;
+    println!("{} {}", "Data:".bold(), dir.display());
+    println!("{} {}", "Config:".bold(), layout.config_file(&dir).display());
+    if std::env::var(home::HOME_ENV).is_ok_and(|dir| !dir.trim().is_empty()) {
+        println!("{}", format!("Set by ${}", home::HOME_ENV).dimmed());
+    }
+    Ok(())
+}
+
+/// Moves pocket's files into the XDG layout, or to `path`
+pub fn relocate(path: Option<&Path>) -> PocketResult<()> {
+    let layout = home::relocate(path).map_err(|e| PocketError::Storage(format!("Failed to move pocket's files: {}", e)))?;
+    println!("Moved pocket's data to {}", layout.data_dir().display());
+    println!("Config is now in {}", layout.config_file(layout.data_dir()).display());
+    Ok(())
+}
@@ -0,0 +1,15 @@
+//! Command groups big enough to live outside the handler
+
+pub mod alias;
+pub mod audit;
+pub mod clipboard;
+pub mod doctor;
+pub mod home;
+pub mod lintcheck;
+pub mod package;
+pub mod profile;
+pub mod scaffold;
+pub mod search;
+pub mod share;
+pub mod undo;
+pub mod workflow;
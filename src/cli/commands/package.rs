@@ -0,0 +1,241 @@
+//! `pocket search --package` and `pocket add-dep`: looking packages up in
+//! their registries and adding them to the current project
+
+use crate::cards::{events, CardManager, Event};
+use crate::errors::{PocketError, PocketResult};
+use crate::logging;
+use crate::models::{ContentType, Entry};
+use crate::registry::cache::{self, PackageCache};
+use crate::registry::{project, Package, Registry};
+use crate::storage::StorageManager;
+use crate::utils;
+use chrono::Utc;
+use colored::Colorize;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::Command;
+
+/// How `pocket add-dep` was asked to behave
+pub struct AddDepOptions {
+    pub query: String,
+    pub registry: Option<String>,
+    pub version: Option<String>,
+    pub limit: usize,
+    pub offline: bool,
+    pub dry_run: bool,
+    pub yes: bool,
+    pub backpack: Option<String>,
+    pub no_record: bool,
+}
+
+/// Searches the named registry, or the current project's, and prints what it finds
+pub fn search(query: &str, registry: Option<&str>, limit: usize, offline: bool) -> PocketResult<()> {
+    let registry = resolve_registry(registry)?;
+    let packages = find(registry, query, limit, offline)?;
+    
+    if packages.is_empty() {
+        if registry.looks_up_by_name() {
+            println!("No package named {} on {} ({} can only be searched by exact name)", query.bold(), registry, registry);
+        } else {
+            println!("No packages found on {} for: {}", registry, query.bold());
+        }
+        return Ok(());
+    }
+    
+    println!("Packages on {} for: {}", registry, query.bold());
+    for (i, package) in packages.iter().enumerate() {
+        let version = package.version.as_deref().map(|v| format!(" {}", v.dimmed())).unwrap_or_default();
+        println!("{}. {}{}", i + 1, package.name.bold(), version);
+        if let Some(description) = &package.description {
+            println!("   {}", description.lines().next().unwrap_or_default());
+        }
+        if let Some(url) = &package.url {
+            println!("   {}", url.cyan());
+        }
+    }
+    Ok(())
+}
+
+/// Finds a package, lets the user pick it, and adds it to the current
+/// project with the project's own package manager. The choice is saved as
+/// an entry so the rest of the team can see what was added and why
+pub fn add_dep(options: AddDepOptions, card_manager: &mut CardManager) -> PocketResult<()> {
+    let cwd = std::env::current_dir().map_err(|e| PocketError::Other(e.to_string()))?;
+    let (registry, project) = match &options.registry {
+        Some(name) => {
+            let registry = Registry::parse(name).map_err(|e| PocketError::Cli(e.to_string()))?;
+            let project = registry.project_dir(&cwd)
+                .ok_or_else(|| PocketError::Cli(format!("This isn't a project that gets packages from {}", registry)))?;
+            (registry, project)
+        },
+        None => Registry::find_project(&cwd)
+            .ok_or_else(|| PocketError::Cli("Couldn't find a project here; pick its registry with --registry".to_string()))?,
+    };
+    
+    let packages = find(registry, &options.query, options.limit, options.offline)?;
+    let mut package = pick(&packages, &options.query, options.yes)?;
+    if let Some(version) = &options.version {
+        package.version = Some(version.clone());
+    }
+    
+    let command = registry.install_command(&package, &project);
+    match &command {
+        Some(command) if options.dry_run => {
+            println!("Would run in {}:", project.display().to_string().bold());
+            println!("  {}", shell_words::join(command).cyan());
+            return Ok(());
+        },
+        Some(command) => {
+            println!("Running in {}: {}", project.display().to_string().bold(), shell_words::join(command).cyan());
+            if !options.yes && !utils::confirm("Add it?", true).map_err(|e| PocketError::Cli(e.to_string()))? {
+                println!("Operation cancelled");
+                return Ok(());
+            }
+            install(command, &project)?;
+        },
+        // Maven has no command for it, so show what to paste into the build file
+        None => {
+            println!("Add this to the dependencies in {}:", project.join("pom.xml").display());
+            println!("{}", Registry::maven_dependency(&package));
+            if options.dry_run {
+                return Ok(());
+            }
+        },
+    }
+    
+    if options.no_record {
+        return Ok(());
+    }
+    let entry = record(&package, command.as_deref(), &project, options.backpack.as_deref())
+        .map_err(|e| PocketError::Storage(format!("Added the package, but failed to record it: {}", e)))?;
+    println!("Recorded as {} - {}", entry.id, entry.title);
+    events::emit(Event::EntryAdded { id: entry.id, backpack: options.backpack });
+    card_manager.dispatch_events();
+    Ok(())
+}
+
+/// The package to add: the one named exactly, without asking when there's
+/// no terminal or `yes` is set, or the user's pick otherwise
+fn pick(packages: &[Package], query: &str, yes: bool) -> PocketResult<Package> {
+    if packages.is_empty() {
+        return Err(PocketError::Search(format!("No packages found for: {}", query)));
+    }
+    let exact = packages.iter().find(|package| package.name.eq_ignore_ascii_case(query.trim()));
+    if yes || !std::io::stdin().is_terminal() {
+        return exact.or(if yes { packages.first() } else { None })
+            .cloned()
+            .ok_or_else(|| PocketError::Cli(format!("No package is named exactly '{}'; run in a terminal to pick one", query)));
+    }
+    
+    let labels: Vec<String> = packages.iter()
+        .map(|package| {
+            let version = package.version.as_deref().map(|v| format!(" {}", v)).unwrap_or_default();
+            let description = package.description.as_deref()
+                .map(|d| format!(" - {}", d.lines().next().unwrap_or_default()))
+                .unwrap_or_default();
+            format!("{}{}{}", package.name, version, description)
+        })
+        .collect();
+    let choice = utils::select("Which package?", &labels).map_err(|e| PocketError::Cli(e.to_string()))?;
+    Ok(packages[choice].clone())
+}
+
+/// Runs an install command in the project, failing if it does
+fn install(command: &[String], project: &Path) -> PocketResult<()> {
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(project)
+        .status()
+        .map_err(|e| PocketError::Other(format!("Failed to run {} (is it installed?): {}", command[0], e)))?;
+    if !status.success() {
+        return Err(PocketError::Other(format!("{} failed ({})", shell_words::join(command), status)));
+    }
+    Ok(())
+}
+
+/// Saves an entry noting which package was added to which project, and how
+fn record(package: &Package, command: Option<&[String]>, project: &Path, backpack: Option<&str>) -> anyhow::Result<Entry> {
+    let project_name = project.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| project.display().to_string());
+    
+    let mut content = format!("Added {} from {} to {}", package.name, package.registry, project_name);
+    if let Some(version) = &package.version {
+        content.push_str(&format!(" (version {})", version));
+    }
+    content.push_str(&format!(" on {}.\n", Utc::now().format("%Y-%m-%d")));
+    if let Some(description) = &package.description {
+        content.push_str(&format!("\n{}\n", description));
+    }
+    content.push_str(&format!("\nProject: {}\n", project.display()));
+    match command {
+        Some(command) => content.push_str(&format!("Installed with: {}\n", shell_words::join(command))),
+        None => content.push_str(&format!("\n{}\n", Registry::maven_dependency(package))),
+    }
+    
+    let registry_tag = format!("{:?}", package.registry).to_lowercase();
+    let mut entry = Entry::new(
+        format!("Added {} to {}", package.name, project_name),
+        ContentType::Text,
+        package.url.clone(),
+        vec!["dependency".to_string(), registry_tag],
+    );
+    entry.add_metadata("package", &package.name);
+    StorageManager::new()?.save_entry(&entry, &content, backpack)?;
+    Ok(entry)
+}
+
+/// Searches a registry. Fresh cached results are used as they are, and stale
+/// ones when the registry can't be reached; `offline` never goes online at all
+fn find(registry: Registry, query: &str, limit: usize, offline: bool) -> PocketResult<Vec<Package>> {
+    let cache = PackageCache::open().map_err(PocketError::storage)?;
+    let ttl = StorageManager::new()
+        .and_then(|storage| storage.load_config())
+        .map(|config| cache::ttl(config.search.package_cache_hours))
+        .unwrap_or_else(|_| cache::ttl(None));
+    
+    match cache.get(registry, query, limit) {
+        Some(cached) if offline || cached.is_fresh(ttl, Utc::now()) => Ok(cached.packages),
+        None if offline => Err(PocketError::Search(format!("No cached results on {} for '{}' to use offline", registry, query))),
+        cached => match registry.search(query, limit) {
+            Ok(packages) => {
+                if let Err(e) = cache.put(registry, query, limit, &packages) {
+                    logging::warning(&format!("Failed to cache the results: {}", e));
+                }
+                Ok(packages)
+            },
+            Err(e) => match cached {
+                Some(cached) => {
+                    logging::warning(&format!("Couldn't reach {} ({}), showing results from {}", registry, e, cached.fetched_at.format("%Y-%m-%d %H:%M")));
+                    Ok(cached.packages)
+                },
+                None => Err(PocketError::Search(format!("Failed to search {}: {}", registry, e))),
+            },
+        },
+    }
+}
+
+/// Deletes every cached package search
+pub fn clear_cache() -> PocketResult<()> {
+    let cache = PackageCache::open().map_err(PocketError::storage)?;
+    let cleared = cache.clear().map_err(|e| PocketError::Storage(format!("Failed to clear the cache: {}", e)))?;
+    println!("Cleared {} cached package search{}", cleared, if cleared == 1 { "" } else { "es" });
+    Ok(())
+}
+
+/// The registry named, or the one the biggest ecosystem in the current
+/// project uses
+pub fn resolve_registry(name: Option<&str>) -> PocketResult<Registry> {
+    if let Some(name) = name {
+        return Registry::parse(name).map_err(|e| PocketError::Cli(e.to_string()));
+    }
+    let dir = std::env::current_dir().map_err(|e| PocketError::Other(e.to_string()))?;
+    let ecosystems = project::detect(&dir);
+    let first = ecosystems.first()
+        .ok_or_else(|| PocketError::Cli("Couldn't tell which registry this project uses; pick one with --registry or --lang".to_string()))?;
+    if ecosystems.len() > 1 {
+        let others: Vec<String> = ecosystems[1..].iter().map(|ecosystem| ecosystem.registry.to_string()).collect();
+        logging::info(&format!("This project also uses {}; pick one with --lang", others.join(", ")));
+    }
+    Ok(first.registry)
+}
@@ -0,0 +1,39 @@
+//! `pocket profile`: listing, creating and switching profiles
+
+use crate::errors::{PocketError, PocketResult};
+use crate::storage::profile;
+use colored::Colorize;
+
+/// Lists every profile, marking the one in use
+pub fn list() -> PocketResult<()> {
+    let active = profile::active_profile();
+    let profiles = profile::list_profiles().map_err(PocketError::storage)?;
+    for name in profiles {
+        if name == active {
+            println!("* {}", name.bold());
+        } else {
+            println!("  {}", name);
+        }
+    }
+    Ok(())
+}
+
+/// Creates an empty profile
+pub fn create(name: &str) -> PocketResult<()> {
+    let dir = profile::create_profile(name).map_err(|e| PocketError::Cli(e.to_string()))?;
+    println!("Created profile {} in {}", name.bold(), dir.display());
+    println!("Use it with `pocket profile switch {}` or `pocket --profile {} ...`", name, name);
+    Ok(())
+}
+
+/// Makes a profile the one used from now on
+pub fn switch(name: &str) -> PocketResult<()> {
+    profile::switch_profile(name).map_err(|e| PocketError::Cli(e.to_string()))?;
+    println!("Switched to profile {}", name.bold());
+    if let Ok(env_profile) = std::env::var(profile::PROFILE_ENV) {
+        if !env_profile.trim().is_empty() && env_profile.trim() != name {
+            crate::logging::warning(&format!("${} is set to '{}', which still wins in this shell", profile::PROFILE_ENV, env_profile.trim()));
+        }
+    }
+    Ok(())
+}
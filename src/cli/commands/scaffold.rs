@@ -0,0 +1,322 @@
+//! `pocket scaffold`: creating a project from a backpack of templates
+//!
+//! A template is a backpack whose entries each keep the path they're written
+//! to under the `path` metadata key. `{{name}}` placeholders in those paths
+//! and in text content are filled in from `--set name=value`, or asked for.
+//! Only names made of letters, digits, `_` and `-` are placeholders, so
+//! things like `${{ github.sha }}` in a workflow file come out as they are.
+//! Binary entries are copied without filling anything in.
+
+use crate::cards::{events, Event};
+use crate::errors::{PocketError, PocketResult};
+use crate::i18n::tr;
+use crate::logging;
+use crate::models::{Backpack, Entry};
+use crate::storage::StorageManager;
+use crate::utils;
+use anyhow::{Result, anyhow, bail};
+use chrono::Utc;
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::IsTerminal;
+use std::path::{Component, Path, PathBuf};
+
+/// Entry metadata key holding where in the project a template file goes
+pub const PATH_KEY: &str = "path";
+
+/// Entry metadata key marking a template file as executable
+const EXECUTABLE_KEY: &str = "executable";
+
+/// Directories left out when saving a template
+const SKIPPED_DIRS: &[&str] = &[".git", "target", "node_modules"];
+
+/// How `pocket scaffold` was asked to behave
+pub struct ScaffoldOptions {
+    pub template: String,
+    pub dir: PathBuf,
+    pub set: Vec<String>,
+    pub force: bool,
+    pub dry_run: bool,
+}
+
+/// A template file's content
+enum Content {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Writes out the template backpack `options.template` under `options.dir`
+pub fn scaffold(options: ScaffoldOptions) -> PocketResult<()> {
+    let storage = StorageManager::new().map_err(PocketError::storage)?;
+    storage.load_backpack(&options.template).map_err(|e| PocketError::NotFound(e.to_string()))?;
+    let files = load_template(&storage, &options.template).map_err(PocketError::storage)?;
+    if files.is_empty() {
+        return Err(PocketError::NotFound(format!("Backpack '{}' has no template files; save some with `pocket scaffold {} DIR --save`", options.template, options.template)));
+    }
+
+    let mut values = BTreeMap::new();
+    for pair in &options.set {
+        let (name, value) = pair.split_once('=')
+            .ok_or_else(|| PocketError::Cli(format!("Invalid --set '{}': expected NAME=VALUE", pair)))?;
+        values.insert(name.trim().to_string(), value.to_string());
+    }
+    let mut names = Vec::new();
+    for (path, _, content) in &files {
+        names.extend(placeholders(path));
+        if let Content::Text(text) = content {
+            names.extend(placeholders(text));
+        }
+    }
+    for name in values.keys().filter(|name| !names.contains(name)) {
+        logging::warning(&format!("The template doesn't use {{{{{}}}}}", name));
+    }
+    for name in names {
+        if values.contains_key(&name) {
+            continue;
+        }
+        if !std::io::stdin().is_terminal() {
+            return Err(PocketError::Cli(format!("Missing a value for {{{{{}}}}}; pass --set {}=VALUE", name, name)));
+        }
+        let value: String = utils::input(&name, None).map_err(|e| PocketError::Cli(e.to_string()))?;
+        values.insert(name, value);
+    }
+
+    let mut planned = Vec::new();
+    for (path, entry, content) in files {
+        let relative = project_path(&fill(&path, &values)).map_err(|e| PocketError::Entry(format!("Template file '{}': {}", path, e)))?;
+        let target = options.dir.join(relative);
+        let content = match content {
+            Content::Text(text) => fill(&text, &values).into_bytes(),
+            Content::Binary(bytes) => bytes,
+        };
+        planned.push((target, entry.get_metadata(EXECUTABLE_KEY) == Some("true"), content));
+    }
+    let existing: Vec<String> = planned.iter()
+        .filter(|(target, _, _)| target.exists())
+        .map(|(target, _, _)| target.display().to_string())
+        .collect();
+    if !existing.is_empty() && !options.force && !options.dry_run {
+        return Err(PocketError::Conflict(format!("Already exists: {}. Use --force to overwrite", existing.join(", "))));
+    }
+
+    if options.dry_run {
+        for (target, _, _) in &planned {
+            let note = if existing.contains(&target.display().to_string()) { " (overwrite)" } else { "" };
+            println!("{}{}", target.display(), note.yellow());
+        }
+        return Ok(());
+    }
+    for (target, executable, content) in &planned {
+        write_file(target, content, *executable)
+            .map_err(|e| PocketError::Storage(format!("Failed to write {}: {}", target.display(), e)))?;
+    }
+    println!("{}", tr!("Created {} files in {}", planned.len(), options.dir.display().to_string().bold()));
+    Ok(())
+}
+
+/// Saves the files under `dir` into the backpack `template`, each with its
+/// path relative to `dir`
+pub fn save(dir: &Path, template: &str) -> PocketResult<()> {
+    if !dir.is_dir() {
+        return Err(PocketError::NotFound(format!("Not a directory: {}", dir.display())));
+    }
+    let storage = StorageManager::new().map_err(PocketError::storage)?;
+    if storage.load_backpack(template).is_ok() {
+        let files = load_template(&storage, template).map_err(PocketError::storage)?;
+        if !files.is_empty() {
+            return Err(PocketError::Conflict(format!("Backpack '{}' already holds a template; pick another name", template)));
+        }
+    } else {
+        let backpack = Backpack {
+            name: template.to_string(),
+            description: Some(format!("Scaffold template from {}", dir.display())),
+            created_at: Utc::now(),
+        };
+        storage.create_backpack(&backpack).map_err(PocketError::storage)?;
+    }
+
+    let walker = walkdir::WalkDir::new(dir).sort_by_file_name().into_iter().filter_entry(|entry| {
+        !(entry.file_type().is_dir() && entry.file_name().to_str().is_some_and(|name| SKIPPED_DIRS.contains(&name)))
+    });
+    let mut saved = 0;
+    for file in walker {
+        let file = file.map_err(PocketError::storage)?;
+        if !file.file_type().is_file() {
+            continue;
+        }
+        save_file(&storage, dir, file.path(), template)
+            .map_err(|e| PocketError::Storage(format!("Failed to save {}: {}", file.path().display(), e)))?;
+        saved += 1;
+    }
+    println!("{}", tr!("Saved {} files to template {}", saved, template.bold()));
+    Ok(())
+}
+
+/// The template files in `template`, with their paths, metadata and content
+fn load_template(storage: &StorageManager, template: &str) -> Result<Vec<(String, Entry, Content)>> {
+    let mut files = Vec::new();
+    for entry in storage.list_entries(Some(template))? {
+        let Some(path) = entry.get_metadata(PATH_KEY).map(str::to_string) else {
+            logging::warning(&format!("Skipping {} ({}): it has no {} metadata", entry.id, entry.title, PATH_KEY));
+            continue;
+        };
+        let content = if entry.is_binary() {
+            Content::Binary(storage.load_entry_bytes(&entry.id, Some(template))?.1)
+        } else {
+            Content::Text(storage.load_entry(&entry.id, Some(template))?.1)
+        };
+        files.push((path, entry, content));
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+fn save_file(storage: &StorageManager, dir: &Path, path: &Path, template: &str) -> Result<()> {
+    let relative = path.strip_prefix(dir)?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let bytes = fs::read(path)?;
+    let source = Some(path.display().to_string());
+    let text = std::str::from_utf8(&bytes).ok().filter(|text| !text.contains('\0'));
+
+    let mut entry = match text {
+        Some(text) => {
+            let mut entry = Entry::new(relative.clone(), utils::detect_content_type(Some(path), Some(text)), source, vec![]);
+            entry.language = utils::detect_language(Some(path), text);
+            entry
+        },
+        None => Entry::new(relative.clone(), utils::detect_content_type(Some(path), None), source, vec![]),
+    };
+    entry.add_metadata(PATH_KEY, &relative);
+    if is_executable(path) {
+        entry.add_metadata(EXECUTABLE_KEY, "true");
+    }
+    match text {
+        Some(text) => storage.save_entry(&entry, text, Some(template))?,
+        None => {
+            entry.mime_type = Some(utils::detect_mime_type(path, &bytes));
+            storage.save_binary_entry(&entry, &bytes, Some(template))?;
+        },
+    }
+    events::emit(Event::EntryAdded { id: entry.id.clone(), backpack: Some(template.to_string()) });
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    false
+}
+
+fn write_file(target: &Path, content: &[u8], executable: bool) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(target, content)?;
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(target, fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+    Ok(())
+}
+
+/// `path` as a path inside the project, refusing ones that would land
+/// outside it
+fn project_path(path: &str) -> Result<PathBuf> {
+    let path = Path::new(path);
+    if path.as_os_str().is_empty() {
+        bail!("the path is empty");
+    }
+    for component in path.components() {
+        match component {
+            Component::Normal(_) | Component::CurDir => {},
+            _ => return Err(anyhow!("'{}' would be written outside the project", path.display())),
+        }
+    }
+    Ok(path.to_path_buf())
+}
+
+/// The placeholder at the start of `text`, if there's one, with how long it is
+fn placeholder_at(text: &str) -> Option<(&str, usize)> {
+    let inner = text.strip_prefix("{{")?;
+    let end = inner.find("}}")?;
+    let name = inner[..end].trim();
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then_some((name, end + 4))
+}
+
+/// The placeholder names in `text`, in the order they first appear
+fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start..];
+        match placeholder_at(rest) {
+            Some((name, len)) => {
+                if !names.iter().any(|known| known == name) {
+                    names.push(name.to_string());
+                }
+                rest = &rest[len..];
+            },
+            None => rest = &rest[2..],
+        }
+    }
+    names
+}
+
+/// Replaces each placeholder in `text` that has a value in `values`
+fn fill(text: &str, values: &BTreeMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match placeholder_at(rest).and_then(|(name, len)| values.get(name).map(|value| (value, len))) {
+            Some((value, len)) => {
+                result.push_str(value);
+                rest = &rest[len..];
+            },
+            None => {
+                result.push_str("{{");
+                rest = &rest[2..];
+            },
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_only_identifier_placeholders() {
+        let text = "name = \"{{ project_name }}\"\nsha: ${{ github.sha }}\n{{author}} wrote {{project_name}}";
+        assert_eq!(placeholders(text), vec!["project_name", "author"]);
+
+        let values = BTreeMap::from([("project_name".to_string(), "demo".to_string()), ("author".to_string(), "Ana".to_string())]);
+        assert_eq!(fill(text, &values), "name = \"demo\"\nsha: ${{ github.sha }}\nAna wrote demo");
+        assert_eq!(fill("{{unset}} {{", &values), "{{unset}} {{");
+    }
+
+    #[test]
+    fn keeps_files_inside_the_project() {
+        assert_eq!(project_path("src/main.rs").unwrap(), PathBuf::from("src/main.rs"));
+        assert!(project_path("../escape").is_err());
+        assert!(project_path("/etc/passwd").is_err());
+        assert!(project_path("").is_err());
+    }
+}
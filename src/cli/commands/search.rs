@@ -0,0 +1,66 @@
+//! `pocket search --save/--forget/--saved` and `pocket search --history`: naming
+//! searches to run again as `@name`, and looking back at recent ones
+
+use crate::errors::{PocketError, PocketResult};
+use crate::storage::StorageManager;
+use crate::utils::suggest;
+use colored::Colorize;
+
+fn storage() -> PocketResult<StorageManager> {
+    StorageManager::new().map_err(PocketError::storage)
+}
+
+/// Saves a search under a name
+pub fn save(name: &str, query: &str) -> PocketResult<()> {
+    let name = name.strip_prefix('@').unwrap_or(name);
+    if name.is_empty() || name.chars().any(char::is_whitespace) {
+        return Err(PocketError::Validation(format!("'{}' can't be a search name; use one word, like rust-http", name)));
+    }
+    let storage = storage()?;
+    let mut saved = storage.load_saved_searches().map_err(PocketError::storage)?;
+    let replaced = saved.insert(name.to_string(), query.to_string()).is_some();
+    storage.save_saved_searches(&saved).map_err(PocketError::storage)?;
+
+    let verb = if replaced { "Updated" } else { "Saved" };
+    println!("{} search {}; run it with `pocket search @{}`", verb, name.bold(), name);
+    Ok(())
+}
+
+/// Deletes a saved search
+pub fn forget(name: &str) -> PocketResult<()> {
+    let name = name.strip_prefix('@').unwrap_or(name);
+    let storage = storage()?;
+    let mut saved = storage.load_saved_searches().map_err(PocketError::storage)?;
+    if saved.remove(name).is_none() {
+        let hint = suggest::did_you_mean(name, saved.keys().map(String::as_str));
+        return Err(PocketError::NotFound(format!("Saved search '{}' not found{}", name, hint)));
+    }
+    storage.save_saved_searches(&saved).map_err(PocketError::storage)?;
+    println!("Forgot search {}", name.bold());
+    Ok(())
+}
+
+/// Lists the saved searches
+pub fn list_saved() -> PocketResult<()> {
+    let saved = storage()?.load_saved_searches().map_err(PocketError::storage)?;
+    if saved.is_empty() {
+        println!("No saved searches yet; save one with `pocket search <query> --save <name>`");
+    }
+    for (name, query) in saved {
+        println!("{} - {}", format!("@{}", name).bold(), query);
+    }
+    Ok(())
+}
+
+/// Lists recent searches, latest first
+pub fn history(limit: usize) -> PocketResult<()> {
+    let history = storage()?.search_history().map_err(PocketError::storage)?;
+    if history.is_empty() {
+        println!("No searches yet");
+    }
+    for past in history.iter().rev().take(limit) {
+        let time = past.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+        println!("{}  {}", time.to_string().dimmed(), past.query);
+    }
+    Ok(())
+}
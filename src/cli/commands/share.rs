@@ -0,0 +1,168 @@
+//! `pocket share`: putting an entry on a paste service, revoking it again,
+//! and opening encrypted shares
+
+use crate::errors::{PocketError, PocketResult};
+use crate::logging;
+use crate::models::Entry;
+use crate::storage::StorageManager;
+use crate::utils::{http, qr};
+use crate::utils::language::extension_for_language;
+use crate::utils::share::{self, Provider, Share, Upload};
+use colored::Colorize;
+use std::io::Write;
+use std::time::Duration;
+
+/// Entry metadata key holding an entry's shares, as a JSON array
+const SHARES_KEY: &str = "shares";
+
+/// Most time fetching a share to open gets
+const OPEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How `pocket share` was asked to behave
+pub struct ShareOptions {
+    pub id: String,
+    pub backpack: Option<String>,
+    pub to: Option<String>,
+    pub expires: Option<u64>,
+    pub encrypt: bool,
+    pub qr: bool,
+}
+
+/// Uploads an entry and remembers the link in its metadata
+pub fn share(options: ShareOptions) -> PocketResult<()> {
+    let storage = StorageManager::new().map_err(PocketError::storage)?;
+    let config = storage.load_config().map_err(|e| PocketError::Config(e.to_string()))?;
+    let provider = match &options.to {
+        Some(name) => Provider::from_name(name, &config.share),
+        None => Provider::from_config(&config.share),
+    }.map_err(|e| PocketError::Cli(e.to_string()))?;
+    if options.encrypt && provider == Provider::Gist {
+        return Err(PocketError::Cli("Gists are already secret; encrypted shares need a service that serves the paste as is".to_string()));
+    }
+    
+    let (id, backpack) = storage.resolve_entry(&options.id, options.backpack.as_deref())
+        .map_err(|e| PocketError::Entry(e.to_string()))?;
+    let (entry, content) = storage.load_entry_bytes(&id, backpack.as_deref())
+        .map_err(|e| PocketError::Entry(e.to_string()))?;
+    
+    let filename = filename(&entry.title, entry.language.as_deref());
+    let (content, key) = if options.encrypt {
+        let (sealed, key) = share::encrypt(&content).map_err(|e| PocketError::Other(e.to_string()))?;
+        (sealed.into_bytes(), Some(key))
+    } else {
+        (content, None)
+    };
+    let upload = Upload { filename: &filename, content: &content, description: &entry.title, expires_hours: options.expires };
+    let mut shared = provider.upload(&upload)
+        .map_err(|e| PocketError::Other(format!("Failed to share to {}: {}", provider.name(), e)))?;
+    if let Some(key) = key {
+        shared.url = format!("{}#{}", shared.url, key);
+        shared.encrypted = true;
+    }
+    
+    let mut shares = shares_of(&entry);
+    shares.push(shared.clone());
+    let saved = storage.update_entry_metadata(&id, backpack.as_deref(), |entry| {
+        entry.add_metadata(SHARES_KEY, &serde_json::to_string(&shares).unwrap_or_default());
+    });
+    if let Err(e) = saved {
+        logging::warning(&format!("Shared, but failed to remember the link: {}", e));
+    }
+    
+    println!("Shared {} to {}", entry.title.bold(), provider.name());
+    println!("{}", shared.url.cyan());
+    if options.qr {
+        match qr::render(shared.url.as_bytes()) {
+            Ok(code) => println!("{}", code),
+            Err(e) => logging::warning(&e.to_string()),
+        }
+    }
+    if let Some(expires_at) = shared.expires_at {
+        println!("Expires {}", expires_at.format("%Y-%m-%d %H:%M UTC"));
+    }
+    if shared.encrypted {
+        println!("{}", "Anyone with the whole link can read it; open it with `pocket share --open <link>`".dimmed());
+    }
+    Ok(())
+}
+
+/// Deletes an entry's shares from their services, or just the one at `url`,
+/// and forgets them
+pub fn revoke(id: &str, backpack: Option<&str>, url: Option<&str>) -> PocketResult<()> {
+    let storage = StorageManager::new().map_err(PocketError::storage)?;
+    let config = storage.load_config().map_err(|e| PocketError::Config(e.to_string()))?;
+    let (id, backpack) = storage.resolve_entry(id, backpack)
+        .map_err(|e| PocketError::Entry(e.to_string()))?;
+    let entry = storage.load_entry_metadata(&id, backpack.as_deref())
+        .map_err(|e| PocketError::Entry(e.to_string()))?;
+    
+    let shares = shares_of(&entry);
+    let (targets, mut kept): (Vec<Share>, Vec<Share>) = shares.into_iter()
+        .partition(|share| url.is_none_or(|url| share.url == url || share.url.split('#').next() == Some(url)));
+    if targets.is_empty() {
+        return Err(PocketError::Entry(match url {
+            Some(url) => format!("{} isn't a share of this entry", url),
+            None => "This entry hasn't been shared".to_string(),
+        }));
+    }
+    
+    for target in targets {
+        let result = Provider::from_name(&target.provider, &config.share)
+            .and_then(|provider| provider.revoke(&target));
+        match result {
+            Ok(()) => println!("Revoked {}", target.url.cyan()),
+            Err(e) => {
+                logging::warning(&format!("Failed to revoke {}: {}", target.url, e));
+                kept.push(target);
+            },
+        }
+    }
+    
+    storage.update_entry_metadata(&id, backpack.as_deref(), |entry| {
+        if kept.is_empty() {
+            entry.metadata.remove(SHARES_KEY);
+        } else {
+            entry.add_metadata(SHARES_KEY, &serde_json::to_string(&kept).unwrap_or_default());
+        }
+    }).map_err(PocketError::storage)?;
+    Ok(())
+}
+
+/// Fetches an encrypted share and prints what's in it
+pub fn open(link: &str) -> PocketResult<()> {
+    let (url, key) = link.split_once('#')
+        .ok_or_else(|| PocketError::Cli("The link has no key after '#'; it isn't an encrypted share".to_string()))?;
+    let response = http::send("GET", url, &[], None, OPEN_TIMEOUT)
+        .map_err(|e| PocketError::Other(e.to_string()))?;
+    let content = share::decrypt(&response.body, key).map_err(|e| PocketError::Other(e.to_string()))?;
+    std::io::stdout().write_all(&content).map_err(|e| PocketError::Other(e.to_string()))?;
+    Ok(())
+}
+
+/// The shares recorded for an entry
+fn shares_of(entry: &Entry) -> Vec<Share> {
+    entry.get_metadata(SHARES_KEY)
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+/// A file name for a shared entry, from its title and language
+fn filename(title: &str, language: Option<&str>) -> String {
+    let stem: String = title.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let stem = stem.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-");
+    let stem = if stem.is_empty() { "snippet".to_string() } else { stem };
+    format!("{}.{}", stem, language.map(extension_for_language).unwrap_or("txt"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn names_shared_files_after_the_entry() {
+        assert_eq!(filename("Retry with backoff!", Some("rust")), "retry-with-backoff.rs");
+        assert_eq!(filename("???", None), "snippet.txt");
+    }
+}
@@ -0,0 +1,34 @@
+//! `pocket undo` and `pocket redo`: taking back what commands did to entries
+
+use crate::errors::{PocketError, PocketResult};
+use crate::storage::{self, journal};
+use colored::Colorize;
+
+/// Undoes the latest operation, or lists what can be undone
+pub fn undo(list: bool, force: bool) -> PocketResult<()> {
+    let dir = storage::pocket_dir().map_err(PocketError::storage)?;
+    if list {
+        let records = journal::history(&dir).map_err(PocketError::storage)?;
+        if records.is_empty() {
+            println!("Nothing to undo");
+        }
+        for record in records.iter().rev() {
+            let time = record.time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+            let state = if record.undone { " (undone)".dimmed().to_string() } else { String::new() };
+            println!("{}  {}{}", time.to_string().dimmed(), record.label, state);
+        }
+        return Ok(());
+    }
+
+    let record = journal::undo(&dir, force).map_err(|e| PocketError::Conflict(e.to_string()))?;
+    println!("Undid {} ({} file(s))", record.label.bold(), record.files.len());
+    Ok(())
+}
+
+/// Redoes the operation undone last
+pub fn redo(force: bool) -> PocketResult<()> {
+    let dir = storage::pocket_dir().map_err(PocketError::storage)?;
+    let record = journal::redo(&dir, force).map_err(|e| PocketError::Conflict(e.to_string()))?;
+    println!("Redid {} ({} file(s))", record.label.bold(), record.files.len());
+    Ok(())
+}
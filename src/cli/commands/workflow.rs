@@ -0,0 +1,200 @@
+//! `pocket workflow`: running, saving, editing and scheduling workflows
+
+use crate::cards::CardManager;
+use crate::cli::WorkflowCommands;
+use crate::errors::{PocketError, PocketResult};
+use crate::models::WorkflowSchedule;
+use crate::storage::StorageManager;
+use crate::utils;
+use crate::workflow::{self, schedule, steps, Engine};
+use colored::Colorize;
+
+/// Runs a `pocket workflow` subcommand
+pub fn handle(command: WorkflowCommands, card_manager: &mut CardManager) -> PocketResult<()> {
+    match command {
+        WorkflowCommands::Run { workflow, var, dry_run } => run(&workflow, &var, dry_run, card_manager),
+        WorkflowCommands::List => list(),
+        WorkflowCommands::Save { name, workflow } => save(&name, &workflow),
+        WorkflowCommands::Delete { name } => delete(&name),
+        WorkflowCommands::Edit { name } => edit(&name),
+        WorkflowCommands::Schedule { name, cron, on, backpack } => add_schedule(name, cron, on, backpack),
+        WorkflowCommands::Unschedule { name } => unschedule(&name),
+        WorkflowCommands::Schedules => list_schedules(),
+    }
+}
+
+/// Runs a workflow (saved, from a file, or a one-liner), or with `dry_run`
+/// prints what it would do
+pub fn run(spec: &str, vars: &[String], dry_run: bool, card_manager: &mut CardManager) -> PocketResult<()> {
+    let workflow = workflow::resolve(&storage()?, spec)
+        .map_err(|e| PocketError::Workflow(format!("{:#}", e)))?;
+    let overrides = vars.iter()
+        .map(|pair| utils::exec::parse_env(pair))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|e| PocketError::Cli(e.to_string()))?;
+    
+    let mut engine = Engine::new(&workflow, &overrides);
+    if dry_run {
+        let mut steps = steps::DryRun::new(card_manager)
+            .map_err(|e| PocketError::Workflow(e.to_string()))?;
+        engine.run(&workflow, &mut steps)
+            .map_err(|e| PocketError::Workflow(format!("{} can't run: {}", workflow.name, e)))?;
+        println!("Dry run of {}: nothing was changed", workflow.name.bold());
+    } else {
+        let mut steps = steps::PocketSteps::new(card_manager)
+            .map_err(|e| PocketError::Workflow(e.to_string()))?;
+        engine.run(&workflow, &mut steps)
+            .map_err(|e| PocketError::Workflow(format!("{} failed: {}", workflow.name, e)))?;
+    }
+    Ok(())
+}
+
+/// Lists saved workflows with their steps
+pub fn list() -> PocketResult<()> {
+    let workflows = storage()?.list_workflows()
+        .map_err(PocketError::storage)?;
+    if workflows.is_empty() {
+        println!("No saved workflows");
+    }
+    for workflow in workflows {
+        let steps: Vec<&str> = workflow.steps.iter().map(|s| s.run.as_str()).collect();
+        println!("{} - {}", workflow.name.bold(), steps.join(" > "));
+    }
+    Ok(())
+}
+
+/// Saves a workflow file or one-liner under `name`
+pub fn save(name: &str, spec: &str) -> PocketResult<()> {
+    workflow::validate_name(name)
+        .map_err(|e| PocketError::Workflow(e.to_string()))?;
+    let storage = storage()?;
+    let mut workflow = workflow::resolve(&storage, spec)
+        .map_err(|e| PocketError::Workflow(format!("{:#}", e)))?;
+    workflow.name = name.to_string();
+    storage.save_workflow(&workflow)
+        .map_err(PocketError::storage)?;
+    println!("Saved workflow {} ({} steps)", workflow.name.bold(), workflow.steps.len());
+    Ok(())
+}
+
+/// Deletes a saved workflow, along with its schedules
+pub fn delete(name: &str) -> PocketResult<()> {
+    let storage = storage()?;
+    workflow::validate_name(name)
+        .and_then(|_| storage.delete_workflow(name))
+        .map_err(|e| PocketError::Workflow(e.to_string()))?;
+    
+    let mut schedules = load_schedules(&storage)?;
+    let before = schedules.len();
+    schedules.retain(|s| s.workflow != name);
+    if schedules.len() != before {
+        save_schedules(&storage, &schedules)?;
+    }
+    println!("Deleted workflow {}", name.bold());
+    Ok(())
+}
+
+/// Opens a saved workflow in the editor as TOML, and saves it if it still parses
+pub fn edit(name: &str) -> PocketResult<()> {
+    let storage = storage()?;
+    workflow::validate_name(name)
+        .map_err(|e| PocketError::Workflow(e.to_string()))?;
+    let workflow = storage.load_workflow(name)
+        .map_err(|e| PocketError::Workflow(e.to_string()))?;
+    
+    let text = toml::to_string_pretty(&workflow)
+        .map_err(|e| PocketError::Workflow(format!("Can't write {} as TOML: {}", name, e)))?;
+    let edited = utils::open_editor_for_language(Some("toml"), Some(&text))
+        .map_err(|e| PocketError::Cli(e.to_string()))?;
+    if edited == text {
+        println!("No changes to {}", name.bold());
+        return Ok(());
+    }
+    
+    let mut updated = workflow::parse_toml(name, &edited)
+        .map_err(|e| PocketError::Workflow(format!("{} not saved: {:#}", name, e)))?;
+    updated.name = name.to_string();
+    storage.save_workflow(&updated)
+        .map_err(PocketError::storage)?;
+    println!("Saved workflow {} ({} steps)", name.bold(), updated.steps.len());
+    Ok(())
+}
+
+/// Adds a cron schedule or event trigger for a saved workflow
+fn add_schedule(name: String, cron: Option<String>, on: Option<String>, backpack: Option<String>) -> PocketResult<()> {
+    let storage = storage()?;
+    if !storage.has_workflow(&name) {
+        return Err(PocketError::Workflow(format!("No saved workflow named '{}'; save it first with pocket workflow save", name)));
+    }
+    if let Some(expr) = &cron {
+        schedule::Cron::parse(expr)
+            .map_err(|e| PocketError::Workflow(e.to_string()))?;
+    }
+    let on = on.map(|event| schedule::event_name(&event).map(str::to_string))
+        .transpose()
+        .map_err(|e| PocketError::Workflow(e.to_string()))?;
+    
+    let schedule = WorkflowSchedule { workflow: name, cron, on, backpack };
+    match (&schedule.cron, &schedule.on) {
+        (Some(cron), _) => println!("Scheduled {} for {} (runs while pocket daemon is running)", schedule.workflow.bold(), cron),
+        (_, Some(event)) => println!("{} will run after {}", schedule.workflow.bold(), describe_trigger(event, schedule.backpack.as_deref())),
+        _ => {},
+    }
+    
+    let mut schedules = load_schedules(&storage)?;
+    if !schedules.contains(&schedule) {
+        schedules.push(schedule);
+        save_schedules(&storage, &schedules)?;
+    }
+    Ok(())
+}
+
+/// Removes every schedule and trigger for a workflow
+fn unschedule(name: &str) -> PocketResult<()> {
+    let storage = storage()?;
+    let mut schedules = load_schedules(&storage)?;
+    let before = schedules.len();
+    schedules.retain(|s| s.workflow != name);
+    if schedules.len() == before {
+        return Err(PocketError::Workflow(format!("{} isn't scheduled", name)));
+    }
+    save_schedules(&storage, &schedules)?;
+    println!("Removed {} schedule(s) for {}", before - schedules.len(), name.bold());
+    Ok(())
+}
+
+/// Lists schedules and triggers
+fn list_schedules() -> PocketResult<()> {
+    let schedules = load_schedules(&storage()?)?;
+    if schedules.is_empty() {
+        println!("No scheduled workflows");
+    }
+    for schedule in &schedules {
+        match (&schedule.cron, &schedule.on) {
+            (Some(cron), _) => println!("{} - {}", schedule.workflow.bold(), cron),
+            (_, Some(event)) => println!("{} - after {}", schedule.workflow.bold(), describe_trigger(event, schedule.backpack.as_deref())),
+            _ => {},
+        }
+    }
+    Ok(())
+}
+
+/// Describes a trigger's event for messages, like "entry_added in work"
+fn describe_trigger(event: &str, backpack: Option<&str>) -> String {
+    match backpack {
+        Some(name) => format!("{} in {}", event, name),
+        None => event.to_string(),
+    }
+}
+
+fn storage() -> PocketResult<StorageManager> {
+    StorageManager::new().map_err(PocketError::storage)
+}
+
+fn load_schedules(storage: &StorageManager) -> PocketResult<Vec<WorkflowSchedule>> {
+    storage.load_schedules().map_err(PocketError::storage)
+}
+
+fn save_schedules(storage: &StorageManager, schedules: &[WorkflowSchedule]) -> PocketResult<()> {
+    storage.save_schedules(schedules).map_err(PocketError::storage)
+}
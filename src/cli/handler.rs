@@ -1,38 +1,344 @@
-use crate::cli::{Cli, Commands, CardOperation, BlendCommands};
-use crate::cards::CardManager;
+use crate::cli::{Cli, Commands, AuditCommands, CardOperation, CardConfigCommands, BlendCommands, AliasCommands, StatsCommands, BackpackCommands, TagCommands, CacheCommands, ProfileCommands, HomeCommands};
+use crate::cli::commands;
+use crate::cards::{CardCommand, CardManager, ReloadSummary};
+use crate::cards::options;
+use crate::cards::registry::{self, RegistryIndex};
 use crate::errors::{PocketError, PocketResult};
 use crate::logging;
-use log::{debug, LevelFilter};
+use crate::server;
+use crate::storage::{self, StorageManager};
+use crate::utils::{self, style};
+use crate::workflow;
+use crate::i18n::{self, tr};
+use log::{debug, warn, LevelFilter};
+use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
+use clap::{Arg, ArgMatches, CommandFactory, FromArgMatches};
 use colored::Colorize;
 
-/// Handle the CLI command
-pub fn handle_command(cli: Cli) -> PocketResult<()> {
-    // Set up logging based on verbosity
-    let log_level = match cli.verbose {
+/// Pick the backpack for a command, taking `.pocket-context` and the
+/// configured default into account
+fn resolve_backpack(explicit: Option<String>, use_default: bool) -> PocketResult<Option<String>> {
+    let storage = StorageManager::new()
+        .map_err(PocketError::storage)?;
+    let backpack = storage.resolve_backpack(explicit, use_default)
+        .map_err(|e| PocketError::Config(e.to_string()))?;
+    
+    if let Some(name) = &backpack {
+        debug!("Using backpack {}", name);
+    }
+    Ok(backpack)
+}
+
+/// The registry given on the command line, or the one from the config
+fn resolve_registry(explicit: Option<String>) -> PocketResult<String> {
+    if let Some(url) = explicit {
+        return Ok(url);
+    }
+    
+    let storage = StorageManager::new()
+        .map_err(PocketError::storage)?;
+    let config = storage.load_config()
+        .map_err(|e| PocketError::Config(e.to_string()))?;
+    config.extensions.registry.ok_or_else(|| PocketError::Config(
+        "No card registry configured. Set `registry` under [extensions] in ~/.pocket/config.toml or pass --registry".to_string()
+    ))
+}
+
+/// Cards whose commands already have dedicated CLI commands
+const CLI_BACKED_CARDS: &[&str] = &["core", "snippet", "blend"];
+
+/// Log level for a number of -v flags
+fn log_level(verbose: u8) -> LevelFilter {
+    match verbose {
         0 => LevelFilter::Warn,
         1 => LevelFilter::Info,
         2 => LevelFilter::Debug,
         _ => LevelFilter::Trace,
-    };
-    logging::init(log_level);
+    }
+}
+
+/// Picks the log format and opens the log file, from `--log-format`,
+/// `--log-file` and the `[logging]` config. Problems with the log file are
+/// warnings, not reasons to stop the command
+fn configure_logging(verbose: LevelFilter, log_file: Option<Option<PathBuf>>, log_format: Option<logging::LogFormat>) {
+    let config = StorageManager::new().ok()
+        .and_then(|storage| storage.load_config().ok())
+        .map(|config| config.logging)
+        .unwrap_or_default();
     
-    debug!("Starting pocket CLI with verbosity level {}", cli.verbose);
+    if let Some(format) = log_format.or(config.format) {
+        logging::set_format(format);
+    }
+    
+    // `--log-file` turns the file on for this command, `logging.file` for every one
+    let Some(path) = log_file.or_else(|| config.file.then_some(None)) else {
+        return;
+    };
+    let path = match path.or_else(|| config.path.as_ref().map(PathBuf::from)) {
+        Some(path) => path,
+        None => match storage::pocket_dir() {
+            Ok(dir) => dir.join("logs").join("pocket.log"),
+            Err(_) => return,
+        },
+    };
     
-    // Get the home directory
-    let home_dir = std::env::var("HOME")
-        .map_err(|_| PocketError::Config("HOME environment variable not set".to_string()))?;
-    let data_dir = PathBuf::from(&home_dir).join(".pocket");
+    // The file gets at least info, and more when -v asks for more
+    let level = config.level.as_deref()
+        .and_then(|level| level.parse::<LevelFilter>().ok())
+        .unwrap_or(LevelFilter::Info.max(verbose));
+    let file = logging::LogFile {
+        path,
+        level,
+        max_bytes: config.max_size_kb.map(|kb| kb * 1024).unwrap_or(logging::LogFile::DEFAULT_MAX_BYTES),
+        keep: config.keep.unwrap_or(logging::LogFile::DEFAULT_KEEP),
+    };
+    let path = file.path.clone();
+    if let Err(e) = logging::set_file(file) {
+        logging::warning(&format!("Could not open log file {}: {}", path.display(), e));
+    }
+}
+
+/// Load the card manager from the profile's cards directory
+fn load_card_manager() -> PocketResult<CardManager> {
+    let data_dir = storage::pocket_dir()
+        .map_err(|e| PocketError::Config(e.to_string()))?;
     
     // Initialize the card manager
     let card_dir = data_dir.join("cards");
-    let mut card_manager = CardManager::new(card_dir.clone());
+    let mut card_manager = CardManager::new(card_dir);
     card_manager.load_cards()
-        .map_err(|e| PocketError::Card(format!("Failed to load cards: {}", e)))?;
+        .map_err(|e| PocketError::card("Failed to load cards", e))?;
+    
+    // Watch the wallet for long-running commands if the config asks for it
+    let auto_reload = StorageManager::new()
+        .and_then(|storage| storage.load_config())
+        .map(|config| config.extensions.auto_reload)
+        .unwrap_or(true);
+    card_manager.set_auto_reload(auto_reload);
+    
+    Ok(card_manager)
+}
+
+/// Prints what a card reload changed
+fn print_reload_summary(summary: &ReloadSummary) {
+    if summary.is_empty() {
+        println!("No card changes");
+    }
+    for name in &summary.loaded {
+        println!("  {} {}", "loaded".green(), name);
+    }
+    for name in &summary.reloaded {
+        println!("  {} {}", "reloaded".yellow(), name);
+    }
+    for name in &summary.unloaded {
+        println!("  {} {}", "unloaded".red(), name);
+    }
+}
+
+/// Enabled cards whose commands can be run as `pocket <card> <command>`,
+/// with those commands. A card named like a built-in command is left out;
+/// `pocket cards run` still reaches it.
+pub(crate) fn card_subcommands(card_manager: &CardManager) -> BTreeMap<String, Vec<CardCommand>> {
+    let cli = Cli::command();
+    let builtin: HashSet<String> = cli.get_subcommands()
+        .flat_map(|c| std::iter::once(c.get_name()).chain(c.get_all_aliases()))
+        .map(|name| name.to_string())
+        .chain(std::iter::once("help".to_string()))
+        .collect();
+    
+    card_manager.list_cards().into_iter()
+        .filter(|(card, _, enabled)| *enabled && !CLI_BACKED_CARDS.contains(&card.as_str()) && !builtin.contains(card))
+        .filter_map(|(card, _, _)| {
+            let commands = card_manager.get_card_commands(&card).unwrap_or_default();
+            (!commands.is_empty()).then_some((card, commands))
+        })
+        .collect()
+}
+
+/// How to run a card command as `pocket <card> <command>`. Cards describe
+/// their usage through `pocket cards run`
+fn card_usage(card: &str, command: &CardCommand) -> String {
+    let invocation = format!("pocket {} {}", card, command.name);
+    match command.usage.strip_prefix(&format!("pocket cards run {} {}", card, command.name)) {
+        Some(rest) => format!("{}{}", invocation, rest),
+        None => format!("{} [args]...", invocation),
+    }
+}
+
+/// The CLI with each enabled card's commands registered under its name
+pub(crate) fn cli_command(card_commands: &BTreeMap<String, Vec<CardCommand>>) -> clap::Command {
+    let mut command = Cli::command();
+    for (card, commands) in card_commands {
+        let mut namespace = clap::Command::new(card.clone())
+            .about(format!("Commands from the {} card", card))
+            .subcommand_required(true)
+            .arg_required_else_help(true);
+        for card_command in commands {
+            namespace = namespace.subcommand(
+                clap::Command::new(card_command.name.clone())
+                    .about(card_command.description.clone())
+                    .override_usage(card_usage(card, card_command))
+                    .arg(Arg::new("args").num_args(0..).trailing_var_arg(true).allow_hyphen_values(true))
+            );
+        }
+        command = command.subcommand(namespace);
+    }
+    command
+}
+
+/// The config, for what's needed before the command line is parsed. Nothing
+/// is created when there's no pocket yet, and a config that doesn't load is
+/// left for the command itself to report
+fn early_config() -> Option<crate::models::Config> {
+    StorageManager::existing().and_then(|storage| storage.read_config())
+        .map_err(|e| debug!("Config not loaded before parsing: {}", e))
+        .ok()
+        .flatten()
+}
+
+/// Command aliases from `config.toml`
+pub(crate) fn command_aliases() -> BTreeMap<String, String> {
+    early_config().map(|config| config.aliases).unwrap_or_default()
+}
+
+/// Parse the command line and run it. Built-in commands are parsed before
+/// anything is loaded, so `--help` and `--version` leave the pocket alone;
+/// cards are only loaded to parse a command none of them match
+pub fn run() -> PocketResult<()> {
+    logging::init(LevelFilter::Warn);
+    utils::cancel::install();
+    if let Some(profile) = storage::profile::profile_arg(std::env::args().skip(1)) {
+        storage::profile::set_profile(&profile).map_err(|e| PocketError::Cli(e.to_string()))?;
+    }
+    
+    let config = early_config();
+    let language = config.as_ref().and_then(|config| config.display.language.as_deref());
+    i18n::set_locale(i18n::Locale::detect(language));
+    let color = config.as_ref().is_none_or(|config| config.display.color);
+    if let Some(config) = &config {
+        utils::clipboard::set_provider(config.clipboard.provider);
+    }
+    
+    let aliases = config.map(|config| config.aliases).unwrap_or_default();
+    let line = commands::alias::expand(std::env::args().skip(1).collect(), &aliases, &Cli::command())
+        .map_err(|e| PocketError::Cli(e.to_string()))?;
+    let args: Vec<String> = std::iter::once("pocket".to_string()).chain(line.iter().cloned()).collect();
+    
+    let (matches, mut card_manager, card_commands) = match Cli::command().try_get_matches_from(&args) {
+        Ok(matches) => (matches, load_card_manager()?, BTreeMap::new()),
+        Err(e) if e.kind() == clap::error::ErrorKind::InvalidSubcommand => {
+            let card_manager = load_card_manager()?;
+            let card_commands = card_subcommands(&card_manager);
+            (cli_command(&card_commands).get_matches_from(&args), card_manager, card_commands)
+        },
+        Err(e) => e.exit(),
+    };
+    style::set_color(matches.get_one::<style::ColorChoice>("color").copied().unwrap_or_default(), color);
+    dispatch(&matches, &line, &card_commands, &mut card_manager)?;
+    workflow::schedule::run_triggers(&mut card_manager);
+    Ok(())
+}
+
+/// Run parsed arguments, handing card commands to the card providing them.
+/// `line` is what was parsed, without the `pocket`, for the audit log and undo
+pub(crate) fn dispatch(matches: &ArgMatches, line: &[String], card_commands: &BTreeMap<String, Vec<CardCommand>>, card_manager: &mut CardManager) -> PocketResult<()> {
+    if let Some((card, card_matches)) = matches.subcommand().filter(|(name, _)| card_commands.contains_key(*name)) {
+        logging::init(log_level(matches.get_count("verbose")));
+        configure_logging(
+            log_level(matches.get_count("verbose")),
+            matches.get_one::<Option<PathBuf>>("log_file").cloned(),
+            matches.get_one::<logging::LogFormat>("log_format").copied(),
+        );
+        utils::parallel::set_jobs(matches.get_one::<usize>("jobs").copied());
+        utils::progress::set_quiet(matches.get_flag("quiet"));
+        if let Some(format) = matches.get_one::<utils::output::OutputFormat>("output") {
+            utils::output::set_format(*format);
+        }
+        let Some((name, sub_matches)) = card_matches.subcommand() else {
+            return Err(PocketError::Cli(format!("Which {} command? See pocket {} --help", card, card)));
+        };
+        let args: Vec<String> = sub_matches.get_many::<String>("args")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default();
+            
+        // There's no telling what a card command changes, so they're all audited
+        return audited(line, true, || {
+            card_manager.execute_command(card, name, &args)
+                .map_err(|e| PocketError::card(format!("Failed to run {} {}", card, name), e))
+        });
+    }
+    
+    let cli = Cli::from_arg_matches(matches).map_err(|e| PocketError::Cli(e.to_string()))?;
+    run_command(cli, line, card_manager)
+}
+
+/// Handle the CLI command
+pub fn handle_command(cli: Cli) -> PocketResult<()> {
+    logging::init(log_level(cli.verbose));
+    if let Some(profile) = &cli.profile {
+        storage::profile::set_profile(profile).map_err(|e| PocketError::Cli(e.to_string()))?;
+    }
+    let mut card_manager = load_card_manager()?;
+    let line: Vec<String> = std::env::args().skip(1).collect();
+    run_command(cli, &line, &mut card_manager)?;
+    workflow::schedule::run_triggers(&mut card_manager);
+    Ok(())
+}
+
+/// Run a parsed command with the loaded cards, logging it to the audit log
+/// when it changes the pocket
+fn run_command(cli: Cli, line: &[String], card_manager: &mut CardManager) -> PocketResult<()> {
+    audited(line, cli.command.mutates_storage(), || execute_command(cli, line, card_manager))
+}
+
+/// Runs the command `line`, logging it to the audit log when `audit` is set
+fn audited(line: &[String], audit: bool, run: impl FnOnce() -> PocketResult<()>) -> PocketResult<()> {
+    let started = std::time::Instant::now();
+    let result = run();
+    if audit {
+        let event = storage::audit::AuditEvent {
+            time: chrono::Utc::now(),
+            args: line.to_vec(),
+            ok: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        if let Err(e) = storage::pocket_dir().and_then(|dir| storage::audit::append(&dir, &event)) {
+            warn!("Failed to write the audit log: {}", e);
+        }
+    }
+    result
+}
+
+/// Run a parsed command
+fn execute_command(cli: Cli, line: &[String], card_manager: &mut CardManager) -> PocketResult<()> {
+    // Set up logging based on verbosity
+    logging::init(log_level(cli.verbose));
+    configure_logging(log_level(cli.verbose), cli.log_file.clone(), cli.log_format);
+    utils::parallel::set_jobs(cli.jobs);
+    utils::progress::set_quiet(cli.quiet);
+    utils::output::set_format(cli.output);
+    
+    debug!("Starting pocket CLI with verbosity level {}", cli.verbose);
     
     // Handle the command
+    let mutating = cli.command.mutates_storage();
+    
+    // Record what the command changes, so `pocket undo` can take it back
+    let _transaction = if cli.command.is_undoable() {
+        let label = std::iter::once("pocket").chain(line.iter().map(String::as_str)).collect::<Vec<_>>().join(" ");
+        storage::pocket_dir()
+            .and_then(|dir| storage::journal::begin(&dir, &label))
+            .map_err(|e| warn!("Not recording this change for undo: {}", e))
+            .ok()
+    } else {
+        None
+    };
     match cli.command {
-        Commands::Add { file, message, editor, backpack, clipboard, summarize } => {
+        Commands::Add { file, message, editor, backpack, clipboard, summarize, tag, auto_tag, binary, interpreter } => {
+            let backpack = resolve_backpack(backpack, true)?;
+            
             // Build the arguments for the snippet card
             let mut args = Vec::new();
             
@@ -56,16 +362,55 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                 args.push("--clipboard".to_string());
             }
             
-            if let Some(s) = summarize {
-                args.push(format!("--summarize={}", s));
+            match summarize {
+                Some(Some(s)) => args.push(format!("--summarize={}", s)),
+                Some(None) => args.push("--generate-summary".to_string()),
+                None => {},
+            }
+            
+            if let Some(b) = binary {
+                args.push(format!("--binary={}", b));
+            }
+            
+            for t in tag {
+                args.push(format!("--tag={}", t));
+            }
+            
+            if auto_tag {
+                args.push("--auto-tag".to_string());
+            }
+            
+            if let Some(i) = interpreter {
+                args.push(format!("--interpreter={}", i));
             }
             
             // Execute the command
             card_manager.execute_command("snippet", "add", &args)
-                .map_err(|e| PocketError::Card(format!("Failed to add snippet: {}", e)))?;
+                .map_err(|e| PocketError::card("Failed to add snippet", e))?;
         },
         
-        Commands::List { all, backpack, json, limit } => {
+        Commands::Export { id, output, backpack } => {
+            // Build the arguments for the core card
+            let mut args = vec![id];
+            
+            if let Some(o) = output {
+                args.push("--output".to_string());
+                args.push(o);
+            }
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            // Execute the command
+            card_manager.execute_command("core", "export", &args)
+                .map_err(|e| PocketError::card("Failed to export entry", e))?;
+        },
+        
+        Commands::List { all, backpack, json, limit, sort, lang, r#where, since, until, created } => {
+            let backpack = if all { backpack } else { resolve_backpack(backpack, false)? };
+            
             // Build the arguments for the core card
             let mut args = Vec::new();
             
@@ -85,9 +430,134 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
             args.push("--limit".to_string());
             args.push(limit.to_string());
             
+            args.push("--sort".to_string());
+            args.push(sort);
+            
+            if let Some(l) = lang {
+                args.push("--lang".to_string());
+                args.push(l);
+            }
+            
+            if let Some(w) = r#where {
+                args.push("--where".to_string());
+                args.push(w);
+            }
+            
+            if let Some(s) = since {
+                args.push("--since".to_string());
+                args.push(s);
+            }
+            
+            if let Some(u) = until {
+                args.push("--until".to_string());
+                args.push(u);
+            }
+            
+            if created {
+                args.push("--created".to_string());
+            }
+            
             // Execute the command
             card_manager.execute_command("core", "list", &args)
-                .map_err(|e| PocketError::Card(format!("Failed to list entries: {}", e)))?;
+                .map_err(|e| PocketError::card("Failed to list entries", e))?;
+        },
+        
+        Commands::Show { id, backpack, lines, head, no_pager, qr } => {
+            // Build the arguments for the core card
+            let mut args = vec![id];
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            if let Some(l) = lines {
+                args.push("--lines".to_string());
+                args.push(l);
+            }
+            
+            if let Some(h) = head {
+                args.push("--head".to_string());
+                args.push(h.to_string());
+            }
+            
+            if no_pager {
+                args.push("--no-pager".to_string());
+            }
+            
+            if qr {
+                args.push("--qr".to_string());
+            }
+            
+            // Execute the command
+            card_manager.execute_command("core", "show", &args)
+                .map_err(|e| PocketError::card("Failed to show entry", e))?;
+        },
+        
+        Commands::Copy { id, backpack, lines } => {
+            let mut args = vec![id];
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            if let Some(l) = lines {
+                args.push("--lines".to_string());
+                args.push(l);
+            }
+            
+            card_manager.execute_command("core", "copy", &args)
+                .map_err(|e| PocketError::card("Failed to copy entry", e))?;
+        },
+        
+        Commands::Related { id, backpack, limit, json } => {
+            // Build the arguments for the core card
+            let mut args = vec![id];
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            args.push("--limit".to_string());
+            args.push(limit.to_string());
+            
+            if json {
+                args.push("--json".to_string());
+            }
+            
+            // Execute the command
+            card_manager.execute_command("core", "related", &args)
+                .map_err(|e| PocketError::card("Failed to find related entries", e))?;
+        },
+        
+        Commands::Pin { id, backpack } => {
+            // Build the arguments for the core card
+            let mut args = vec![id];
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            // Execute the command
+            card_manager.execute_command("core", "pin", &args)
+                .map_err(|e| PocketError::card("Failed to pin entry", e))?;
+        },
+        
+        Commands::Unpin { id, backpack } => {
+            // Build the arguments for the core card
+            let mut args = vec![id];
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            // Execute the command
+            card_manager.execute_command("core", "unpin", &args)
+                .map_err(|e| PocketError::card("Failed to unpin entry", e))?;
         },
         
         Commands::Remove { id, force, backpack } => {
@@ -105,7 +575,7 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
             
             // Execute the command
             card_manager.execute_command("core", "remove", &args)
-                .map_err(|e| PocketError::Card(format!("Failed to remove entry: {}", e)))?;
+                .map_err(|e| PocketError::card("Failed to remove entry", e))?;
         },
         
         Commands::Create { name, description } => {
@@ -119,17 +589,65 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
             
             // Execute the command
             card_manager.execute_command("core", "create-backpack", &args)
-                .map_err(|e| PocketError::Card(format!("Failed to create backpack: {}", e)))?;
+                .map_err(|e| PocketError::card("Failed to create backpack", e))?;
+        },
+        
+        Commands::Backpack { command } => {
+            match command {
+                BackpackCommands::Rename { name, new_name } => {
+                    card_manager.execute_command("core", "rename-backpack", &[name, new_name])
+                        .map_err(|e| PocketError::card("Failed to rename backpack", e))?;
+                },
+                
+                BackpackCommands::Delete { name, move_entries, force } => {
+                    let mut args = vec![name];
+                    
+                    if move_entries {
+                        args.push("--move-entries".to_string());
+                    }
+                    
+                    if force {
+                        args.push("--force".to_string());
+                    }
+                    
+                    card_manager.execute_command("core", "delete-backpack", &args)
+                        .map_err(|e| PocketError::card("Failed to delete backpack", e))?;
+                },
+                
+                BackpackCommands::EditDescription { name, description } => {
+                    let mut args = vec![name];
+                    args.extend(description);
+                    
+                    card_manager.execute_command("core", "describe-backpack", &args)
+                        .map_err(|e| PocketError::card("Failed to update backpack", e))?;
+                },
+            }
         },
         
-        Commands::Search { query, limit, backpack, exact, package } => {
+        Commands::Search { query, history, limit, backpack, exact, fuzzy, explain, package, registry, offline, lang, r#where, since, until, created, project_only, interactive, format, save, forget, saved } => {
+            if let Some(name) = forget {
+                return commands::search::forget(&name);
+            }
+            if saved {
+                return commands::search::list_saved();
+            }
+            if history {
+                commands::search::history(limit)?;
+                return Ok(());
+            }
+            let query = query.ok_or_else(|| PocketError::Cli("Missing search query".to_string()))?;
+            if let Some(name) = save {
+                return commands::search::save(&name, &query);
+            }
+            
             if package {
-                // Special case for package search (not yet migrated to card system)
-                logging::warning("Package search is not yet migrated to the card system");
-                logging::warning("This will be implemented in a future version");
+                // With --package, --lang names the ecosystem to search
+                commands::package::search(&query, registry.as_deref().or(lang.as_deref()), limit, offline)?;
                 return Ok(());
             }
             
+            let backpack = resolve_backpack(backpack, false)?;
+            
             // Build the arguments for the core card
             let mut args = vec![query];
             
@@ -145,13 +663,81 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                 args.push("--exact".to_string());
             }
             
+            if fuzzy {
+                args.push("--fuzzy".to_string());
+            }
+            
+            if explain {
+                args.push("--explain".to_string());
+            }
+            
+            if let Some(l) = lang {
+                args.push("--lang".to_string());
+                args.push(l);
+            }
+            
+            if let Some(w) = r#where {
+                args.push("--where".to_string());
+                args.push(w);
+            }
+            
+            if let Some(s) = since {
+                args.push("--since".to_string());
+                args.push(s);
+            }
+            
+            if let Some(u) = until {
+                args.push("--until".to_string());
+                args.push(u);
+            }
+            
+            if created {
+                args.push("--created".to_string());
+            }
+            
+            if project_only {
+                args.push("--project-only".to_string());
+            }
+            
+            if interactive {
+                args.push("--interactive".to_string());
+            }
+            
+            if let Some(f) = format {
+                args.push("--format".to_string());
+                args.push(f);
+            }
+            
             // Execute the command
             card_manager.execute_command("core", "search", &args)
-                .map_err(|e| PocketError::Card(format!("Failed to search entries: {}", e)))?;
+                .map_err(|e| PocketError::card("Failed to search entries", e))?;
         },
         
-        Commands::Insert { id, file, top, no_confirm, delimiter } => {
-            if let Some(id) = id {
+        Commands::Insert { id, file, top, pick, backpack, no_confirm, delimiter } => {
+            if pick {
+                // With --pick the only positional argument is the file
+                let file_path = file.or(id)
+                    .ok_or_else(|| PocketError::Cli("Missing file path for insert".to_string()))?;
+                let mut args = vec![file_path];
+                
+                if let Some(b) = backpack {
+                    args.push("--backpack".to_string());
+                    args.push(b);
+                }
+                
+                if no_confirm {
+                    args.push("--no-confirm".to_string());
+                }
+                
+                if let Some(d) = delimiter {
+                    args.push("--delimiter".to_string());
+                    args.push(d);
+                }
+                
+                // Execute the command
+                card_manager.execute_command("core", "insert-pick", &args)
+                    .map_err(|e| PocketError::card("Failed to insert entry", e))?;
+            } else if let Some(id) = id {
                 if let Some(file_path) = file {
                     // Build the arguments for the core card
                     let mut args = vec![id, file_path];
@@ -167,7 +753,7 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                     
                     // Execute the command
                     card_manager.execute_command("core", "insert", &args)
-                        .map_err(|e| PocketError::Card(format!("Failed to insert entry: {}", e)))?;
+                        .map_err(|e| PocketError::card("Failed to insert entry", e))?;
                 } else {
                     return Err(PocketError::Cli("Missing file path for insert".to_string()));
                 }
@@ -179,30 +765,35 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
             }
         },
         
-        Commands::Reload => {
+        Commands::Reload { watch } => {
             logging::info("Reloading all extensions and cards...");
             
-            // Re-initialize the card manager
-            card_manager = CardManager::new(card_dir.clone());
-            card_manager.load_cards()
-                .map_err(|e| PocketError::Card(format!("Failed to reload cards: {}", e)))?;
-            
+            let summary = card_manager.reload()
+                .map_err(|e| PocketError::card("Failed to reload cards", e))?;
+            print_reload_summary(&summary);
             logging::success("Extensions and cards reloaded successfully");
+            
+            if watch {
+                if !card_manager.auto_reload() {
+                    logging::warning("extensions.auto_reload is off in config.toml; not watching for changes");
+                    return Ok(());
+                }
+                
+                logging::info("Watching for card changes (Ctrl-C to stop)...");
+                loop {
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                    match card_manager.reload_if_changed() {
+                        Ok(Some(summary)) if !summary.is_empty() => print_reload_summary(&summary),
+                        Ok(_) => {},
+                        Err(e) => logging::warning(&format!("Failed to reload cards: {}", e)),
+                    }
+                }
+            }
         },
         
         Commands::ShowHelp { command, extensions } => {
             if extensions {
-                // Show card commands
-                let commands = card_manager.list_commands();
-                
-                println!("{}", logging::header("Available extensions:"));
-                for (card_name, card_commands) in commands {
-                    println!("\n{}", logging::title(&card_name));
-                    for cmd in card_commands {
-                        println!("  {} - {}", logging::key(&cmd.name), cmd.description);
-                        println!("    Usage: {}", cmd.usage);
-                    }
-                }
+                print_extension_help(card_manager);
             } else if let Some(_command) = command {
                 // Show help for a specific command
                 // TODO: Implement this with card system
@@ -210,20 +801,65 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                 logging::warning("This will be improved in a future version");
             } else {
                 // Show general help
-                print_custom_help();
+                print_custom_help(&card_subcommands(card_manager));
+            }
+        },
+        
+        Commands::Lint { workflow, save, var, dry_run } => {
+            logging::warning("`pocket lint` is deprecated; use `pocket workflow run`, `save` or `list`");
+            match (workflow, save) {
+                (None, _) => commands::workflow::list()?,
+                (Some(spec), Some(name)) => commands::workflow::save(&name, &spec)?,
+                (Some(spec), None) => commands::workflow::run(&spec, &var, dry_run, card_manager)?,
             }
         },
         
-        Commands::Lint { workflow: _ } => {
-            // TODO: Migrate to card system
-            logging::warning("Lint command not yet migrated to the card system");
-            logging::warning("This will be implemented in a future version");
+        Commands::DeleteWorkflow { name } => {
+            logging::warning("`pocket delete-workflow` is deprecated; use `pocket workflow delete`");
+            commands::workflow::delete(&name)?;
         },
         
-        Commands::DeleteWorkflow { name: _ } => {
-            // TODO: Migrate to card system
-            logging::warning("DeleteWorkflow command not yet migrated to the card system");
-            logging::warning("This will be implemented in a future version");
+        Commands::Workflow { command } => {
+            commands::workflow::handle(command, card_manager)?;
+        },
+        
+        Commands::Daemon => {
+            workflow::schedule::daemon(card_manager)
+                .map_err(|e| PocketError::Workflow(e.to_string()))?;
+        },
+        
+        Commands::McpServe => {
+            let stdin = std::io::stdin();
+            server::mcp::McpServer::new(card_manager).serve(stdin.lock(), std::io::stdout())
+                .map_err(|e| PocketError::Other(format!("MCP server failed: {}", e)))?;
+        },
+        
+        Commands::Api { port, new_token } => {
+            let token = StorageManager::new()
+                .and_then(|storage| storage.api_token(new_token))
+                .map_err(PocketError::storage)?;
+            println!("Token: {} (send it as Authorization: Bearer TOKEN)", token.bold());
+            server::api::ApiServer::new(card_manager, token).serve(port)
+                .map_err(|e| PocketError::Other(format!("API server failed: {}", e)))?;
+        },
+        
+        Commands::ServeEditor => {
+            let stdin = std::io::stdin();
+            server::editor::EditorServer::new()
+                .and_then(|mut server| server.serve(stdin.lock(), std::io::stdout()))
+                .map_err(|e| PocketError::Other(format!("Editor server failed: {}", e)))?;
+        },
+        
+        Commands::Lsp => {
+            let stdin = std::io::stdin();
+            server::lsp::LspServer::new()
+                .and_then(|mut server| server.serve(stdin.lock(), std::io::stdout()))
+                .map_err(|e| PocketError::Other(format!("Language server failed: {}", e)))?;
+        },
+        
+        Commands::WatchClipboard { backpack, auto, tag, interval, window } => {
+            let options = commands::clipboard::WatchOptions { backpack, tag, auto_save: auto, interval, window };
+            commands::clipboard::watch(options, card_manager)?;
         },
         
         Commands::Version => {
@@ -232,6 +868,10 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
             println!("A powerful tool for managing code snippets and shell integrations");
         },
         
+        Commands::Repl => {
+            crate::cli::repl::run(card_manager)?;
+        },
+        
         Commands::Edit { id, force, backpack } => {
             // Build the arguments for the core card
             let mut args = vec![id];
@@ -245,22 +885,61 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                 args.push(b);
             }
             
-            // TODO: Migrate to card system
-            logging::warning("Edit command not yet fully migrated to the card system");
-            logging::warning("This will be improved in a future version");
+            // Execute the command
+            card_manager.execute_command("core", "edit", &args)
+                .map_err(|e| PocketError::card("Failed to edit entry", e))?;
         },
         
-        Commands::Execute { name: _, args: _ } => {
-            // TODO: Migrate to card system
-            logging::warning("Execute command not yet migrated to the card system");
-            logging::warning("This will be implemented in a future version");
+        Commands::Execute { name, args, timeout, cwd, env, no_network, with, save_output, backpack } => {
+            // Build the arguments for the core card: options, then the script and its arguments
+            let mut exec_args = Vec::new();
+            
+            if let Some(t) = timeout {
+                exec_args.push("--timeout".to_string());
+                exec_args.push(t.to_string());
+            }
+            
+            if let Some(dir) = cwd {
+                exec_args.push("--cwd".to_string());
+                exec_args.push(dir);
+            }
+            
+            for pair in env {
+                exec_args.push("--env".to_string());
+                exec_args.push(pair);
+            }
+            
+            if no_network {
+                exec_args.push("--no-network".to_string());
+            }
+            
+            if let Some(interpreter) = with {
+                exec_args.push("--with".to_string());
+                exec_args.push(interpreter);
+            }
+            
+            if save_output {
+                exec_args.push("--save-output".to_string());
+            }
+            
+            if let Some(b) = backpack {
+                exec_args.push("--backpack".to_string());
+                exec_args.push(b);
+            }
+            
+            exec_args.push(name);
+            exec_args.extend(args);
+            
+            // Execute the command
+            card_manager.execute_command("core", "execute", &exec_args)
+                .map_err(|e| PocketError::card("Failed to execute script", e))?;
         },
         
         Commands::Cards { operation } => {
             match operation {
                 Some(CardOperation::List { detail }) => {
                     // List all cards
-                    println!("{}", logging::header("Available cards:"));
+                    println!("{}", style::header("Available cards:"));
                     for (name, version, enabled) in card_manager.list_cards() {
                         let status = if enabled {
                             "[Enabled]".green().bold()
@@ -268,7 +947,7 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                             "[Disabled]".yellow().bold()
                         };
                         
-                        println!("{} {} v{}", status, logging::title(&name), version);
+                        println!("{} {} v{}", status, style::title(&name), version);
                         
                         // List commands for this card
                         if detail {
@@ -286,7 +965,7 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                 Some(CardOperation::Enable { name }) => {
                     // Enable a card
                     card_manager.enable_card(&name)
-                        .map_err(|e| PocketError::Card(format!("Failed to enable card {}: {}", name, e)))?;
+                        .map_err(|e| PocketError::card(format!("Failed to enable card {}", name), e))?;
                     
                     logging::success(&format!("Card {} enabled", name));
                 },
@@ -294,19 +973,127 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                 Some(CardOperation::Disable { name }) => {
                     // Disable a card
                     card_manager.disable_card(&name)
-                        .map_err(|e| PocketError::Card(format!("Failed to disable card {}: {}", name, e)))?;
+                        .map_err(|e| PocketError::card(format!("Failed to disable card {}", name), e))?;
                     
                     logging::success(&format!("Card {} disabled", name));
                 },
                 
                 Some(CardOperation::Add { name, url }) => {
-                    // Add a new card
-                    card_manager.register_card_config(&name, &url)
-                        .map_err(|e| PocketError::Card(format!("Failed to add card {}: {}", name, e)))?;
+                    // Install the card and register it
+                    card_manager.install_card(&name, &url)
+                        .map_err(|e| PocketError::card(format!("Failed to add card {}", name), e))?;
                     
                     logging::success(&format!("Card {} added from {}", name, url));
                 },
                 
+                Some(CardOperation::Test { name }) => {
+                    let pocket_bin = std::env::current_exe()
+                        .map_err(|e| PocketError::Other(format!("Failed to locate the pocket binary: {}", e)))?;
+                    let outcomes = card_manager.test_card(&name, &pocket_bin)
+                        .map_err(|e| PocketError::card(format!("Failed to test card {}", name), e))?;
+                    
+                    let mut failed = 0;
+                    for outcome in &outcomes {
+                        match &outcome.failure {
+                            None => println!("{} {}", "PASS".green().bold(), outcome.name),
+                            Some(reason) => {
+                                failed += 1;
+                                println!("{} {}: {}", "FAIL".red().bold(), outcome.name, reason);
+                            }
+                        }
+                    }
+                    
+                    println!();
+                    println!("{} passed, {} failed", outcomes.len() - failed, failed);
+                    if failed > 0 {
+                        return Err(PocketError::Card(format!("{} test(s) failed for card {}", failed, name)));
+                    }
+                },
+                
+                Some(CardOperation::Permissions { name, revoke }) => {
+                    if revoke {
+                        card_manager.revoke_permissions(&name)
+                            .map_err(|e| PocketError::card(format!("Failed to revoke permissions of card {}", name), e))?;
+                        logging::success(&format!("Revoked permissions of card {}", name));
+                        return Ok(());
+                    }
+                    
+                    let (requested, granted) = card_manager.card_permissions(&name)
+                        .map_err(|e| PocketError::card(format!("Failed to read permissions of card {}", name), e))?;
+                    
+                    println!("{}", style::header(&format!("Permissions for {}:", name)));
+                    if requested.is_empty() {
+                        println!("  Asks for no special access");
+                    }
+                    for line in requested.describe() {
+                        println!("  - {}", line);
+                    }
+                    match granted {
+                        Some(granted) if granted.covers(&requested) => println!("Status: {}", "granted".green()),
+                        Some(_) => println!("Status: {}", "needs approval (the card asks for more than was granted)".yellow()),
+                        None if requested.is_empty() => {},
+                        None => println!("Status: {}", "not granted".yellow()),
+                    }
+                },
+                
+                Some(CardOperation::Config { name, action }) => {
+                    match action {
+                        CardConfigCommands::Get { key } => {
+                            let value = card_manager.get_card_option(&name, key.as_deref())
+                                .map_err(|e| PocketError::card(format!("Failed to read options of card {}", name), e))?;
+                            let text = serde_json::to_string_pretty(&value)
+                                .map_err(|e| PocketError::Other(e.to_string()))?;
+                            println!("{}", text);
+                        },
+                        CardConfigCommands::Set { key, value } => {
+                            card_manager.set_card_option(&name, &key, Some(options::parse_value(&value)))
+                                .map_err(|e| PocketError::card(format!("Failed to set {} for card {}", key, name), e))?;
+                            logging::success(&format!("Set {} for card {}", key, name));
+                        },
+                        CardConfigCommands::Unset { key } => {
+                            card_manager.set_card_option(&name, &key, None)
+                                .map_err(|e| PocketError::card(format!("Failed to unset {} for card {}", key, name), e))?;
+                            logging::success(&format!("Removed {} from card {}", key, name));
+                        },
+                    }
+                },
+                
+                Some(CardOperation::Search { query, registry }) => {
+                    let url = resolve_registry(registry)?;
+                    let index = RegistryIndex::fetch(&url)
+                        .map_err(|e| PocketError::card("Failed to load registry", e))?;
+                    
+                    let installed: Vec<String> = card_manager.list_cards().into_iter().map(|(name, _, _)| name).collect();
+                    let results = index.search(&query);
+                    if results.is_empty() {
+                        logging::info("No cards found");
+                    }
+                    for card in results {
+                        let version = card.latest().map(|v| format!(" v{}", v.version)).unwrap_or_default();
+                        let status = if installed.contains(&card.name) { " [Installed]".green().to_string() } else { String::new() };
+                        println!("{}{}{}", style::title(&card.name), version, status);
+                        if !card.description.is_empty() {
+                            println!("  {}", card.description);
+                        }
+                    }
+                },
+                
+                Some(CardOperation::Install { spec, registry }) => {
+                    let url = resolve_registry(registry)?;
+                    let version = card_manager.install_from_registry(&url, &spec)
+                        .map_err(|e| PocketError::card(format!("Failed to install card {}", spec), e))?;
+                    
+                    logging::success(&format!("Card {} v{} installed", registry::parse_spec(&spec).0, version));
+                },
+                
+                Some(CardOperation::Update { name }) => {
+                    // Pull and rebuild the card
+                    card_manager.update_card(&name)
+                        .map_err(|e| PocketError::card(format!("Failed to update card {}", name), e))?;
+                    
+                    logging::success(&format!("Card {} updated", name));
+                },
+                
                 Some(CardOperation::Remove { name, force }) => {
                     // Remove a card
                     if !force {
@@ -322,7 +1109,7 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                     }
                     
                     card_manager.remove_card_config(&name)
-                        .map_err(|e| PocketError::Card(format!("Failed to remove card {}: {}", name, e)))?;
+                        .map_err(|e| PocketError::card(format!("Failed to remove card {}", name), e))?;
                     
                     logging::success(&format!("Card {} removed", name));
                 },
@@ -330,7 +1117,7 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                 Some(CardOperation::Build { name, release }) => {
                     // Build a card
                     card_manager.build_card(&name, release)
-                        .map_err(|e| PocketError::Card(format!("Failed to build card {}: {}", name, e)))?;
+                        .map_err(|e| PocketError::card(format!("Failed to build card {}", name), e))?;
                     
                     logging::success(&format!("Card {} built successfully", name));
                 },
@@ -338,28 +1125,207 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                 Some(CardOperation::Create { name, description }) => {
                     // Create a new card
                     card_manager.create_card(&name, &description)
-                        .map_err(|e| PocketError::Card(format!("Failed to create card {}: {}", name, e)))?;
+                        .map_err(|e| PocketError::card(format!("Failed to create card {}", name), e))?;
                     
                     logging::success(&format!("Card {} created successfully", name));
                 },
                 
+                Some(CardOperation::Run { name, command, args }) => {
+                    // Run a card command directly
+                    card_manager.execute_command(&name, &command, &args)
+                        .map_err(|e| PocketError::card(format!("Failed to run {} {}", name, command), e))?;
+                },
+                
                 None => {
                     // Show help for the cards command
-                    println!("{}", logging::header("Card Management:"));
+                    println!("{}", style::header("Card Management:"));
                     println!("  Use the following commands to manage cards:");
                     println!("    pocket cards list       - List all cards");
                     println!("    pocket cards enable     - Enable a card");
                     println!("    pocket cards disable    - Disable a card");
-                    println!("    pocket cards add        - Add a new card");
+                    println!("    pocket cards add        - Install a card from a git repository");
+                    println!("    pocket cards update     - Pull and rebuild an installed card");
+                    println!("    pocket cards config     - Read or change a card's options");
+                    println!("    pocket cards permissions - Show or revoke a card's permissions");
+                    println!("    pocket cards search     - Search the card registry");
+                    println!("    pocket cards install    - Install a card from the registry");
                     println!("    pocket cards remove     - Remove a card");
                     println!("    pocket cards build      - Build a card");
+                    println!("    pocket cards test       - Build a card and run its declared tests");
                     println!("    pocket cards create     - Create a new card template");
+                    println!("    pocket cards run        - Run a command provided by a card");
                     println!();
                     println!("  For more information, run: pocket help cards");
                 }
             }
         },
         
+        Commands::Publish { output, backpack } => {
+            let mut args = vec!["--output".to_string(), output];
+            
+            for b in backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            card_manager.execute_command("core", "publish", &args)
+                .map_err(|e| PocketError::card("Failed to publish site", e))?;
+        },
+        
+        Commands::Dedupe { backpack, yes } => {
+            let mut args = Vec::new();
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            if yes {
+                args.push("--yes".to_string());
+            }
+            
+            card_manager.execute_command("core", "dedupe", &args)
+                .map_err(|e| PocketError::card("Failed to dedupe entries", e))?;
+        },
+        
+        Commands::Summarize { id, backpack } => {
+            let mut args = vec![id];
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            card_manager.execute_command("snippet", "summarize", &args)
+                .map_err(|e| PocketError::card("Failed to summarize entry", e))?;
+        },
+        
+        Commands::Tag { command: TagCommands::Suggest { id, backpack, yes } } => {
+            let mut args = vec![id];
+            
+            if let Some(b) = backpack {
+                args.push("--backpack".to_string());
+                args.push(b);
+            }
+            
+            if yes {
+                args.push("--yes".to_string());
+            }
+            
+            card_manager.execute_command("snippet", "suggest-tags", &args)
+                .map_err(|e| PocketError::card("Failed to suggest tags", e))?;
+        },
+        
+        Commands::Stats { command } => {
+            match command {
+                StatsCommands::Snippets { backpack, limit } => {
+                    let mut args = Vec::new();
+                    
+                    if let Some(b) = backpack {
+                        args.push("--backpack".to_string());
+                        args.push(b);
+                    }
+                    
+                    args.push("--limit".to_string());
+                    args.push(limit.to_string());
+                    
+                    card_manager.execute_command("core", "stats-snippets", &args)
+                        .map_err(|e| PocketError::card("Failed to show snippet stats", e))?;
+                },
+            }
+        },
+        
+        Commands::AddDep { query, registry, version, limit, offline, dry_run, yes, backpack, no_record } => {
+            let backpack = resolve_backpack(backpack, false)?;
+            let options = commands::package::AddDepOptions { query, registry, version, limit, offline, dry_run, yes, backpack, no_record };
+            commands::package::add_dep(options, card_manager)?;
+        },
+        
+        Commands::Share { id, to, expires, encrypt, qr, backpack, revoke, url, open } => {
+            if let Some(link) = open {
+                commands::share::open(&link)?;
+            } else {
+                let id = id.ok_or_else(|| PocketError::Cli("Missing the ID of the entry to share".to_string()))?;
+                if revoke {
+                    commands::share::revoke(&id, backpack.as_deref(), url.as_deref())?;
+                } else {
+                    commands::share::share(commands::share::ShareOptions { id, backpack, to, expires, encrypt, qr })?;
+                }
+            }
+        },
+        
+        Commands::Scaffold { template, dir, set, force, dry_run, save } => {
+            let dir = dir.unwrap_or_else(|| PathBuf::from("."));
+            if save {
+                commands::scaffold::save(&dir, &template)?;
+            } else {
+                commands::scaffold::scaffold(commands::scaffold::ScaffoldOptions { template, dir, set, force, dry_run })?;
+            }
+        },
+        
+        Commands::Profile { command } => {
+            match command {
+                ProfileCommands::List => commands::profile::list()?,
+                ProfileCommands::Create { name } => commands::profile::create(&name)?,
+                ProfileCommands::Switch { name } => commands::profile::switch(&name)?,
+            }
+        },
+        
+        Commands::Home { command } => {
+            match command {
+                None => commands::home::show()?,
+                Some(HomeCommands::Move { path }) => commands::home::relocate(path.as_deref())?,
+            }
+        },
+        
+        Commands::Audit { command: AuditCommands::Show { since, json } } => commands::audit::show(since.as_deref(), json)?,
+        
+        Commands::Undo { list, force } => commands::undo::undo(list, force)?,
+        
+        Commands::Redo { force } => commands::undo::redo(force)?,
+        
+        Commands::Doctor { fix } => commands::doctor::run(card_manager, fix)?,
+        
+        Commands::Cache { command } => {
+            match command {
+                CacheCommands::Clear => commands::package::clear_cache()?,
+                CacheCommands::Rebuild => {
+                    let storage = StorageManager::new()
+                        .map_err(PocketError::storage)?;
+                    let count = storage.rebuild_index()
+                        .map_err(|e| PocketError::Storage(format!("Failed to rebuild the entry index: {}", e)))?;
+                    println!("Indexed {} entr{}", count, if count == 1 { "y" } else { "ies" });
+                },
+            }
+        },
+        
+        Commands::Alias { command } => {
+            match command {
+                AliasCommands::Add { name, id, backpack } => {
+                    // Build the arguments for the core card
+                    let mut args = vec![name, id];
+                    
+                    if let Some(b) = backpack {
+                        args.push("--backpack".to_string());
+                        args.push(b);
+                    }
+                    
+                    // Execute the command
+                    card_manager.execute_command("core", "alias-add", &args)
+                        .map_err(|e| PocketError::card("Failed to add alias", e))?;
+                },
+                
+                AliasCommands::Set { name, command } => {
+                    let cli = cli_command(&card_subcommands(card_manager));
+                    commands::alias::set(&name, &command, &cli)?;
+                },
+                
+                AliasCommands::Remove { name } => commands::alias::remove(&name, card_manager)?,
+                
+                AliasCommands::List => commands::alias::list(card_manager)?,
+            }
+        },
+        
         Commands::Blend { script_file, executable, command } => {
             match command {
                 Some(BlendCommands::Edit { hook_name }) => {
@@ -368,13 +1334,29 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                     
                     // Execute the command
                     card_manager.execute_command("blend", "edit", &args)
-                        .map_err(|e| PocketError::Card(format!("Failed to edit hook: {}", e)))?;
+                        .map_err(|e| PocketError::card("Failed to edit hook", e))?;
                 },
                 
                 Some(BlendCommands::List) => {
                     // Execute the command
                     card_manager.execute_command("blend", "list", &[])
-                        .map_err(|e| PocketError::Card(format!("Failed to list hooks: {}", e)))?;
+                        .map_err(|e| PocketError::card("Failed to list hooks", e))?;
+                },
+                
+                Some(BlendCommands::Remove { hook_name }) => {
+                    card_manager.execute_command("blend", "remove", &[hook_name])
+                        .map_err(|e| PocketError::card("Failed to remove hook", e))?;
+                },
+                
+                Some(BlendCommands::Restore) => {
+                    card_manager.execute_command("blend", "restore", &[])
+                        .map_err(|e| PocketError::card("Failed to restore hooks", e))?;
+                },
+                
+                Some(BlendCommands::Uninstall { all }) => {
+                    let args = if all { vec!["--all".to_string()] } else { Vec::new() };
+                    card_manager.execute_command("blend", "uninstall", &args)
+                        .map_err(|e| PocketError::card("Failed to uninstall hooks", e))?;
                 },
                 
                 Some(BlendCommands::Run { hook_name, args }) => {
@@ -384,7 +1366,7 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                     
                     // Execute the command
                     card_manager.execute_command("blend", "run", &run_args)
-                        .map_err(|e| PocketError::Card(format!("Failed to run hook: {}", e)))?;
+                        .map_err(|e| PocketError::card("Failed to run hook", e))?;
                 },
                 
                 None => {
@@ -398,10 +1380,10 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                         
                         // Execute the command
                         card_manager.execute_command("blend", "add", &args)
-                            .map_err(|e| PocketError::Card(format!("Failed to add hook: {}", e)))?;
+                            .map_err(|e| PocketError::card("Failed to add hook", e))?;
                     } else {
                         // Show help for the blend command
-                        println!("{}", logging::header("Blend Command:"));
+                        println!("{}", style::header("Blend Command:"));
                         println!("  Use the following syntax to blend shell scripts:");
                         println!("    pocket blend <script_file>           - Add a shell extension (sourced at shell startup)");
                         println!("    pocket blend --executable <script>   - Add an executable hook command (run with @name)");
@@ -409,6 +1391,9 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
                         println!("  Other commands:");
                         println!("    pocket blend list                    - List all installed hooks");
                         println!("    pocket blend edit <hook_name>        - Edit an existing hook");
+                        println!("    pocket blend remove <hook_name>      - Remove a hook and its shell config lines");
+                        println!("    pocket blend uninstall --all         - Remove every hook from this machine");
+                        println!("    pocket blend restore                 - Reinstall hooks saved in the hooks backpack");
                         println!("    pocket blend run <hook_name> [args]  - Run a hook directly");
                         println!();
                         println!("  For more information, run: pocket help blend");
@@ -418,36 +1403,120 @@ pub fn handle_command(cli: Cli) -> PocketResult<()> {
         },
     }
     
+    // Give the backup card a chance to take a scheduled backup
+    if mutating {
+        if let Err(e) = card_manager.execute_command("backup", "auto", &[]) {
+            warn!("Scheduled backup failed: {}", e);
+        }
+    }
+    
     Ok(())
 }
 
+/// Lists what enabled cards add, grouped by card, and the installed blend
+/// hooks
+fn print_extension_help(card_manager: &CardManager) {
+    let enabled: HashSet<String> = card_manager.list_cards().into_iter()
+        .filter(|(_, _, enabled)| *enabled)
+        .map(|(name, _, _)| name)
+        .collect();
+    let mut cards: Vec<(String, Vec<CardCommand>)> = card_manager.list_commands().into_iter()
+        .filter(|(name, _)| enabled.contains(name))
+        .collect();
+    cards.sort_by(|a, b| a.0.cmp(&b.0));
+    
+    let blend_config = card_manager.get_card_option("blend", Some("blend")).ok()
+        .and_then(|options| serde_json::from_value(options).ok())
+        .unwrap_or_default();
+    let hooks = crate::cards::blend::installed_hooks(&blend_config).unwrap_or_else(|e| {
+        warn!("Couldn't list blend hooks: {}", e);
+        Vec::new()
+    });
+    
+    if cards.is_empty() && hooks.is_empty() {
+        println!("{}", tr!("No extensions installed yet"));
+        return;
+    }
+    
+    println!("{}", style::header(tr!("Available extensions:")));
+    for (card_name, card_commands) in cards {
+        println!("\n{}", style::title(&card_name));
+        for cmd in card_commands {
+            println!("  {} - {}", style::key(&cmd.name), cmd.description);
+            println!("    Usage: {}", cmd.usage);
+        }
+    }
+    
+    if !hooks.is_empty() {
+        println!("\n{}", style::title("Blend hooks"));
+        for hook in hooks {
+            if hook.executable {
+                println!("  {} - runs {}", style::key(&format!("@{}", hook.name)), hook.path.display());
+                println!("    Usage: @{} [args...] or pocket blend run {} [args...]", hook.name, hook.name);
+            } else {
+                println!("  {} - sourced into your shell from {}", style::key(&hook.name), hook.path.display());
+            }
+        }
+    }
+}
+
 /// Print custom help message
-fn print_custom_help() {
-    println!("{}", logging::header("Pocket CLI Help"));
+fn print_custom_help(card_commands: &BTreeMap<String, Vec<CardCommand>>) {
+    println!("{}", style::header("Pocket CLI Help"));
     println!("A CLI tool for saving, organizing, and retrieving code snippets");
     println!("with integrated version control and shell integration");
     println!();
     
-    println!("{}", logging::header("Core Commands:"));
-    println!("  {} - Add content to your pocket storage", logging::key("add"));
-    println!("  {} - Display all pocket entries", logging::key("list"));
-    println!("  {} - Remove an entry from storage", logging::key("remove"));
-    println!("  {} - Create a new backpack for organizing entries", logging::key("create"));
-    println!("  {} - Find entries across all backpacks", logging::key("search"));
-    println!("  {} - Insert an entry into a file", logging::key("insert"));
-    println!("  {} - Reload all extensions", logging::key("reload"));
-    println!("  {} - Display help information", logging::key("help"));
-    println!("  {} - Lint code before adding", logging::key("lint"));
-    println!("  {} - Display version information", logging::key("version"));
-    println!("  {} - Edit an existing entry", logging::key("edit"));
-    println!("  {} - Execute a script", logging::key("execute"));
+    println!("{}", style::header("Core Commands:"));
+    println!("  {} - Add content to your pocket storage", style::key("add"));
+    println!("  {} - Display all pocket entries", style::key("list"));
+    println!("  {} - Show an entry's content", style::key("show"));
+    println!("  {} - Copy an entry's content to the clipboard", style::key("copy"));
+    println!("  {} - List the entries most related to one", style::key("related"));
+    println!("  {} - Write an entry's content to a file", style::key("export"));
+    println!("  {} - Pin an entry to the top of listings", style::key("pin"));
+    println!("  {} - Unpin an entry", style::key("unpin"));
+    println!("  {} - Remove an entry from storage", style::key("remove"));
+    println!("  {} - Create a new backpack for organizing entries", style::key("create"));
+    println!("  {} - Rename, delete, or describe a backpack", style::key("backpack"));
+    println!("  {} - Find entries across all backpacks", style::key("search"));
+    println!("  {} - Insert an entry into a file", style::key("insert"));
+    println!("  {} - Reload all extensions", style::key("reload"));
+    println!("  {} - Display help information", style::key("help"));
+    println!("  {} - Run, save and schedule workflows", style::key("workflow"));
+    println!("  {} - Run scheduled workflows", style::key("daemon"));
+    println!("  {} - Serve the pocket to AI assistants over MCP", style::key("mcp-serve"));
+    println!("  {} - Serve a local REST API", style::key("api"));
+    println!("  {} - Answer editor plugins over stdio", style::key("serve-editor"));
+    println!("  {} - Offer snippets as completions in any LSP editor", style::key("lsp"));
+    println!("  {} - Save code as you copy it", style::key("watch-clipboard"));
+    println!("  {} - Display version information", style::key("version"));
+    println!("  {} - Edit an existing entry", style::key("edit"));
+    println!("  {} - Execute a script", style::key("execute"));
+    println!("  {} - Manage entry aliases", style::key("alias"));
+    println!("  {} - Show usage statistics", style::key("stats"));
+    println!("  {} - Find and merge duplicate entries", style::key("dedupe"));
+    println!("  {} - Summarize an entry", style::key("summarize"));
+    println!("  {} - Suggest tags and a title for an entry", style::key("tag"));
+    println!("  {} - Publish snippets as a static HTML site", style::key("publish"));
+    println!("  {} - Create a project from a template backpack", style::key("scaffold"));
     println!();
     
-    println!("{}", logging::header("Extension Commands:"));
-    println!("  {} - Manage extensions/cards", logging::key("cards"));
-    println!("  {} - Blend shell scripts into your environment", logging::key("blend"));
+    println!("{}", style::header("Extension Commands:"));
+    println!("  {} - Manage extensions/cards", style::key("cards"));
+    println!("  {} - Blend shell scripts into your environment", style::key("blend"));
     println!();
     
+    if !card_commands.is_empty() {
+        println!("{}", style::header("Card Commands:"));
+        for (card, commands) in card_commands {
+            for command in commands {
+                println!("  {} - {}", style::key(&format!("{} {}", card, command.name)), command.description);
+            }
+        }
+        println!();
+    }
+    
     println!("For more detailed help on a specific command, run:");
     println!("  pocket help <command>");
     println!();
@@ -455,4 +1524,43 @@ fn print_custom_help() {
     println!("To see all extensions and their commands, run:");
     println!("  pocket help --extensions");
     println!();
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn backup_commands() -> BTreeMap<String, Vec<CardCommand>> {
+        let restore = CardCommand {
+            name: "restore".to_string(),
+            description: "Verifies and restores a backup".to_string(),
+            usage: "pocket cards run backup restore <backup-id>".to_string(),
+        };
+        BTreeMap::from([("backup".to_string(), vec![restore])])
+    }
+    
+    #[test]
+    fn card_commands_live_under_their_card() {
+        let card_commands = backup_commands();
+        let matches = cli_command(&card_commands)
+            .try_get_matches_from(["pocket", "backup", "restore", "backup_1", "--force"])
+            .unwrap();
+        let (card, card_matches) = matches.subcommand().unwrap();
+        let (command, command_matches) = card_matches.subcommand().unwrap();
+        let args: Vec<&String> = command_matches.get_many::<String>("args").unwrap().collect();
+        assert_eq!((card, command), ("backup", "restore"));
+        assert_eq!(args, ["backup_1", "--force"]);
+        
+        // Not next to the built-in commands
+        assert!(cli_command(&card_commands).try_get_matches_from(["pocket", "restore", "backup_1"]).is_err());
+    }
+    
+    #[test]
+    fn card_usage_is_how_the_command_is_run() {
+        let card_commands = backup_commands();
+        assert_eq!(card_usage("backup", &card_commands["backup"][0]), "pocket backup restore <backup-id>");
+        
+        let bare = CardCommand { name: "sync".to_string(), description: String::new(), usage: "sync it".to_string() };
+        assert_eq!(card_usage("remote", &bare), "pocket remote sync [args]...");
+    }
+}
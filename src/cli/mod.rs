@@ -1,19 +1,50 @@
 use clap::{Parser, Subcommand, ArgAction};
 
+pub mod commands;
 pub mod handler;
+pub mod repl;
 
 #[derive(Parser)]
 #[command(
     name = "pocket",
     about = "A CLI tool for saving, organizing, and retrieving code snippets with integrated version control",
     version = env!("CARGO_PKG_VERSION"),
-    author
+    author,
+    after_help = "Cards add commands of their own, run as `pocket <card> <command>`. `pocket show-help` lists them."
 )]
 pub struct Cli {
     /// Enable verbose output
     #[arg(short, long, action = ArgAction::Count, global = true)]
     pub verbose: u8,
 
+    /// Use this profile's pocket instead of the current one (also $POCKET_PROFILE)
+    #[arg(long, value_name = "NAME", global = true)]
+    pub profile: Option<String>,
+
+    /// Print less: no progress bars, and just the new ID from add
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// When to color output (auto: on a terminal, unless NO_COLOR or display.color say no)
+    #[arg(long, value_enum, value_name = "WHEN", default_value = "auto", global = true)]
+    pub color: crate::utils::style::ColorChoice,
+
+    /// Threads for searching and hashing entries (default: search.jobs, else one per CPU)
+    #[arg(long, value_name = "N", global = true)]
+    pub jobs: Option<usize>,
+
+    /// Also write logs to a file (default: logs/pocket.log in the pocket directory)
+    #[arg(long, value_name = "PATH", num_args = 0..=1, require_equals = true, global = true)]
+    pub log_file: Option<Option<std::path::PathBuf>>,
+
+    /// Format of log output (default: logging.format, else text)
+    #[arg(long, value_enum, value_name = "FORMAT", global = true)]
+    pub log_format: Option<crate::logging::LogFormat>,
+
+    /// Print errors, and the new ID from add, as JSON for scripts (goes before the command)
+    #[arg(long, value_enum, value_name = "FORMAT", default_value = "text")]
+    pub output: crate::utils::output::OutputFormat,
+
     /// Command to execute
     #[command(subcommand)]
     pub command: Commands,
@@ -25,7 +56,7 @@ pub enum Commands {
     #[command(visible_alias = "snippet")]
     /// Add content to your pocket storage
     Add {
-        /// Path to the file to add
+        /// Path to the file to add, or - to read stdin
         #[arg(value_name = "FILE")]
         file: Option<String>,
 
@@ -45,9 +76,42 @@ pub enum Commands {
         #[arg(long)]
         clipboard: bool,
 
-        /// Generate a summary using LLM
-        #[arg(short, long, value_name = "MODEL")]
-        summarize: Option<String>,
+        /// Summarize with the language model set up in config.toml, or give
+        /// the summary yourself with --summarize="..."
+        #[arg(short, long, value_name = "SUMMARY", num_args = 0..=1, require_equals = true)]
+        summarize: Option<Option<String>>,
+
+        /// Tag the entry (repeatable)
+        #[arg(short, long, value_name = "TAG")]
+        tag: Vec<String>,
+
+        /// Suggest tags and a title with the language model set up in
+        /// config.toml (or from keywords), and confirm them before saving
+        #[arg(long, conflicts_with = "binary")]
+        auto_tag: bool,
+
+        /// Store a binary file (image, PDF, ...) as an attachment
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["file", "editor", "clipboard"])]
+        binary: Option<String>,
+
+        /// Interpreter `pocket execute` should run the snippet with (python3, node, ...)
+        #[arg(long, value_name = "INTERPRETER", conflicts_with = "binary")]
+        interpreter: Option<String>,
+    },
+
+    #[command(about = "Write an entry's content to a file")]
+    /// Retrieve an entry's content, including binary attachments
+    Export {
+        /// ID (or unique ID prefix, or alias) of the entry to export
+        id: String,
+
+        /// File to write to (defaults to stdout)
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<String>,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
     },
 
     #[command(about = "Display all pocket entries")]
@@ -68,6 +132,113 @@ pub enum Commands {
         /// Limit number of entries to display
         #[arg(short, long, value_name = "N", default_value = "10")]
         limit: usize,
+
+        /// Sort order (created, used, or title)
+        #[arg(long, value_name = "ORDER", default_value = "created", value_parser = ["created", "used", "title"])]
+        sort: String,
+
+        /// Only list entries in this language (e.g. rust, py, sh)
+        #[arg(long, value_name = "LANG")]
+        lang: Option<String>,
+
+        /// Filter by metadata, e.g. "tag=rust AND created>2024-01-01"
+        #[arg(short, long = "where", value_name = "QUERY")]
+        r#where: Option<String>,
+
+        /// Only entries changed since then: 2w, 3d, 12h or 2024-05-01
+        #[arg(long, value_name = "TIME")]
+        since: Option<String>,
+
+        /// Only entries changed before then: 2w, 3d, 12h or 2024-05-01 (taking in that day)
+        #[arg(long, value_name = "TIME")]
+        until: Option<String>,
+
+        /// Apply --since and --until to when entries were created, not last changed
+        #[arg(long)]
+        created: bool,
+    },
+
+    #[command(about = "Show an entry's content")]
+    /// Print a snippet to stdout
+    Show {
+        /// ID (or unique ID prefix, or alias) of the entry to show
+        id: String,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+
+        /// Only show these lines, e.g. 10:40, 10: or :40
+        #[arg(long, value_name = "RANGE", conflicts_with = "head")]
+        lines: Option<String>,
+
+        /// Only show the first N lines
+        #[arg(long, value_name = "N")]
+        head: Option<usize>,
+
+        /// Print long entries straight out instead of through $PAGER
+        #[arg(long)]
+        no_pager: bool,
+
+        /// Draw the content as a QR code, to scan it onto a phone
+        #[arg(long)]
+        qr: bool,
+    },
+
+    #[command(about = "Copy an entry's content to the clipboard")]
+    /// Put a snippet on the clipboard, ready to paste
+    Copy {
+        /// ID (or unique ID prefix, or alias) of the entry to copy
+        id: String,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+
+        /// Only copy these lines, e.g. 10:40, 10: or :40
+        #[arg(long, value_name = "RANGE")]
+        lines: Option<String>,
+    },
+
+    #[command(about = "List the entries most related to one")]
+    /// Find snippets that share tags and words with this one
+    Related {
+        /// ID (or unique ID prefix, or alias) of the entry
+        id: String,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+
+        /// Most related entries to list
+        #[arg(short, long, value_name = "N", default_value = "5")]
+        limit: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    #[command(about = "Pin an entry to the top of listings")]
+    /// Keep your favorite snippets within reach
+    Pin {
+        /// ID (or unique ID prefix, or alias) of the entry to pin
+        id: String,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+    },
+
+    #[command(about = "Unpin an entry")]
+    /// Stop ranking a snippet at the top of listings
+    Unpin {
+        /// ID (or unique ID prefix, or alias) of the entry to unpin
+        id: String,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
     },
 
     #[command(about = "Remove an entry from storage")]
@@ -96,11 +267,22 @@ pub enum Commands {
         description: Option<String>,
     },
 
+    #[command(about = "Manage backpacks")]
+    /// Rename, delete, and describe backpacks
+    Backpack {
+        #[command(subcommand)]
+        command: BackpackCommands,
+    },
+
     #[command(about = "Find entries across all backpacks with powerful search algorithms")]
     /// Search for entries in your pocket storage
     Search {
-        /// Search query
-        query: String,
+        /// What to look for, e.g. `docker prune` or `title:docker AND (tag:k8s OR tag:helm) NOT backpack:archive`, or `@name` for a saved search
+        query: Option<String>,
+
+        /// List recent searches instead
+        #[arg(long, conflicts_with = "query")]
+        history: bool,
 
         /// Maximum results to return
         #[arg(short, long, value_name = "N", default_value = "10")]
@@ -111,12 +293,72 @@ pub enum Commands {
         backpack: Option<String>,
 
         /// Use exact matching instead of semantic search
-        #[arg(long)]
+        #[arg(long, conflicts_with = "fuzzy")]
         exact: bool,
 
+        /// Forgive typos, ranking the closest matches first
+        #[arg(long)]
+        fuzzy: bool,
+
+        /// Show how each result's score adds up
+        #[arg(long)]
+        explain: bool,
+
         /// Search for packages instead of entries
         #[arg(short, long)]
         package: bool,
+
+        /// Package registry to search: crates, npm, pypi, go, maven or packagist (default: the current project's)
+        #[arg(long, value_name = "NAME", requires = "package")]
+        registry: Option<String>,
+
+        /// Only use cached package results, without going online
+        #[arg(long, requires = "package")]
+        offline: bool,
+
+        /// Only match entries in this language (e.g. rust, py, sh); with --package, the ecosystem to search
+        #[arg(long, value_name = "LANG")]
+        lang: Option<String>,
+
+        /// Filter by metadata, e.g. "tag=rust AND backpack!=archive"
+        #[arg(short, long = "where", value_name = "QUERY")]
+        r#where: Option<String>,
+
+        /// Only entries changed since then: 2w, 3d, 12h or 2024-05-01
+        #[arg(long, value_name = "TIME")]
+        since: Option<String>,
+
+        /// Only entries changed before then: 2w, 3d, 12h or 2024-05-01 (taking in that day)
+        #[arg(long, value_name = "TIME")]
+        until: Option<String>,
+
+        /// Apply --since and --until to when entries were created, not last changed
+        #[arg(long)]
+        created: bool,
+
+        /// Only entries from the git repository you're in, or in its languages
+        #[arg(long)]
+        project_only: bool,
+
+        /// Pick results from a list and copy, insert, edit, open or delete them
+        #[arg(short, long, conflicts_with_all = ["format", "explain"])]
+        interactive: bool,
+
+        /// Print results for an app launcher: alfred, raycast, or lines (for dmenu, rofi and fzf)
+        #[arg(long, value_name = "FORMAT", value_parser = ["alfred", "raycast", "lines"])]
+        format: Option<String>,
+
+        /// Save the query under this name instead of running it, to run as `pocket search @NAME`
+        #[arg(long, value_name = "NAME", requires = "query")]
+        save: Option<String>,
+
+        /// Delete the saved search with this name
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["query", "save", "saved", "history"])]
+        forget: Option<String>,
+
+        /// List saved searches
+        #[arg(long, conflicts_with_all = ["query", "history"])]
+        saved: bool,
     },
 
     #[command(about = "Insert an entry into a file")]
@@ -125,13 +367,21 @@ pub enum Commands {
         /// ID of the entry to insert
         id: Option<String>,
 
-        /// Path to the file to insert into
+        /// Path to the file to insert into, or - to write to stdout
         file: Option<String>,
 
         /// Use the most recent entry
         #[arg(short, long)]
         top: bool,
 
+        /// Pick the entry interactively (pinned entries first)
+        #[arg(short, long)]
+        pick: bool,
+
+        /// Backpack to pick from
+        #[arg(short, long, value_name = "NAME", requires = "pick")]
+        backpack: Option<String>,
+
         /// Don't ask for confirmation
         #[arg(short = 'f', long)]
         no_confirm: bool,
@@ -143,7 +393,12 @@ pub enum Commands {
 
     #[command(about = "Reload all extensions")]
     /// Reload all extensions and cards
-    Reload,
+    Reload {
+        /// Keep watching the wallet and reload cards as they change
+        /// (needs extensions.auto_reload)
+        #[arg(long)]
+        watch: bool,
+    },
 
     #[command(about = "Display help information")]
     /// Show help information for commands and extensions
@@ -156,24 +411,109 @@ pub enum Commands {
         extensions: bool,
     },
 
-    #[command(about = "Create and execute command chains")]
-    /// Lint code before adding to pocket storage
+    #[command(about = "Run workflows (deprecated: use `pocket workflow`)", hide = true)]
+    /// Run a workflow: a saved one, a TOML workflow file, or a one-liner like
+    /// "search 'query' > insert file.js". Lists saved workflows when given none.
+    /// Deprecated in favour of `pocket workflow run/save/list`
     Lint {
-        /// Optional workflow to run
+        /// Workflow to run
         workflow: Option<String>,
+
+        /// Save the workflow under this name instead of running it
+        #[arg(long, value_name = "NAME")]
+        save: Option<String>,
+
+        /// Set a workflow variable (KEY=VAL, repeatable)
+        #[arg(long = "var", value_name = "KEY=VAL")]
+        var: Vec<String>,
+
+        /// Print what each step would do without doing it
+        #[arg(long)]
+        dry_run: bool,
     },
 
-    #[command(about = "Remove a saved workflow")]
+    #[command(about = "Remove a saved workflow (deprecated: use `pocket workflow delete`)", hide = true)]
     /// Delete a saved workflow
     DeleteWorkflow {
         /// Name of the workflow to delete
         name: String,
     },
 
+    #[command(about = "Run, save and schedule workflows")]
+    /// Chain pocket commands into workflows: one-liners like
+    /// "search 'query' > insert file.js", or TOML files with variables,
+    /// conditions and error handling
+    Workflow {
+        #[command(subcommand)]
+        command: WorkflowCommands,
+    },
+
+    #[command(about = "Run scheduled workflows")]
+    /// Stay running and start scheduled workflows when their time comes
+    Daemon,
+
+    #[command(about = "Serve the pocket to AI assistants over MCP")]
+    /// Speak the Model Context Protocol on stdin and stdout, so assistants
+    /// can search, read and add snippets
+    McpServe,
+
+    #[command(about = "Serve a local REST API")]
+    /// Answer HTTP requests for entries, backpacks and search on localhost,
+    /// for browser extensions and editor plugins
+    Api {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 7620)]
+        port: u16,
+
+        /// Make a new token, so clients with the old one stop working
+        #[arg(long)]
+        new_token: bool,
+    },
+
+    #[command(about = "Answer editor plugins over stdio")]
+    /// Stay running with every entry in memory and answer search, fetch
+    /// and insert queries, one JSON object per line
+    ServeEditor,
+
+    #[command(about = "Offer snippets as completions in any LSP editor")]
+    /// Run a minimal language server on stdio whose completions are entries
+    /// in the current file's language
+    Lsp,
+
+    #[command(about = "Save code as you copy it")]
+    /// Watch the clipboard and offer to save each new copy that looks like
+    /// code, or save them all with --auto
+    WatchClipboard {
+        /// Backpack to save copies in
+        #[arg(short, long)]
+        backpack: Option<String>,
+
+        /// Save without asking
+        #[arg(long)]
+        auto: bool,
+
+        /// Tag given to every saved copy
+        #[arg(short, long, default_value = "clipboard")]
+        tag: String,
+
+        /// Seconds between clipboard checks
+        #[arg(long, default_value_t = 1)]
+        interval: u64,
+
+        /// Minutes during which copying the same thing again is ignored
+        #[arg(long, default_value_t = 10)]
+        window: u64,
+    },
+
     #[command(about = "Display version information")]
     /// Show version information
     Version,
 
+    #[command(about = "Start an interactive pocket shell")]
+    /// Run pocket commands one after another without retyping `pocket`,
+    /// with a current backpack, numbered search results and history
+    Repl,
+
     #[command(about = "Edit an existing entry")]
     /// Edit a snippet in your pocket storage
     Edit {
@@ -192,11 +532,40 @@ pub enum Commands {
     #[command(about = "Execute a script")]
     /// Execute a saved script
     Execute {
-        /// Name of the script to execute
+        /// Name, alias or ID of the script to execute
         name: String,
 
-        /// Arguments to pass to the script
+        /// Arguments to pass to the script, passed through as-is (flags included)
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
+
+        /// Kill the script after this many seconds
+        #[arg(long, value_parser = timeout_seconds)]
+        timeout: Option<f64>,
+
+        /// Directory to run the script in
+        #[arg(long)]
+        cwd: Option<String>,
+
+        /// Set an environment variable for the script (KEY=VAL, repeatable)
+        #[arg(long = "env", value_name = "KEY=VAL")]
+        env: Vec<String>,
+
+        /// Run the script without network access (Linux and macOS)
+        #[arg(long)]
+        no_network: bool,
+
+        /// Interpreter to run the script with, instead of the one it asks for
+        #[arg(long = "with", value_name = "INTERPRETER")]
+        with: Option<String>,
+
+        /// Save the run (output, exit code, duration, arguments) to the runs backpack
+        #[arg(long)]
+        save_output: bool,
+
+        /// Backpack the script is in
+        #[arg(short, long)]
+        backpack: Option<String>,
     },
 
     #[command(about = "🔌 Manage cards")]
@@ -206,6 +575,232 @@ pub enum Commands {
         operation: Option<CardOperation>,
     },
 
+    #[command(about = "Manage entry and command aliases")]
+    /// Assign memorable names to entries, or shortcuts to pocket commands
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommands,
+    },
+
+    #[command(about = "Publish snippets as a static HTML site")]
+    /// Render your snippet library to a browsable static site
+    Publish {
+        /// Directory to write the site to
+        #[arg(short, long, value_name = "DIR")]
+        output: String,
+
+        /// Backpack to publish (repeatable; "general" for the general pocket). Defaults to everything.
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Vec<String>,
+    },
+
+    #[command(about = "Find and merge duplicate entries")]
+    /// Merge entries with the same content, across backpacks
+    Dedupe {
+        /// Only look in this backpack
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+
+        /// Merge without prompting, keeping the most-used entry of each group
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    #[command(about = "Summarize an entry")]
+    /// Write a summary for an entry with the language model set up in
+    /// config.toml (or the offline heuristic) and store it
+    Summarize {
+        /// ID (or alias) of the entry
+        id: String,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+    },
+
+    #[command(about = "Suggest tags for entries")]
+    /// Work with entry tags
+    Tag {
+        #[command(subcommand)]
+        command: TagCommands,
+    },
+
+    #[command(about = "Show usage statistics")]
+    /// See which snippets you actually use
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommands,
+    },
+
+    #[command(about = "Add a package to the current project")]
+    /// Search a package registry, pick a package, and install it with the
+    /// project's package manager (cargo add, npm install, pip install, ...),
+    /// recording what was added as an entry
+    AddDep {
+        /// Package to search for
+        query: String,
+
+        /// Package registry to search: crates, npm, pypi, go, maven or packagist (default: the current project's)
+        #[arg(long, value_name = "NAME")]
+        registry: Option<String>,
+
+        /// Version to install instead of the latest
+        #[arg(long, value_name = "VERSION")]
+        version: Option<String>,
+
+        /// Most packages to choose from
+        #[arg(short, long, value_name = "N", default_value = "10")]
+        limit: usize,
+
+        /// Only use cached package results, without going online
+        #[arg(long)]
+        offline: bool,
+
+        /// Show the install command without running it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Take the package named exactly (or the top result) and install without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Backpack to record the decision in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+
+        /// Don't record the decision as an entry
+        #[arg(long)]
+        no_record: bool,
+    },
+
+    #[command(about = "Share an entry through a paste service")]
+    /// Upload an entry to a gist, paste.rs, 0x0.st or a provider set up in
+    /// config.toml, and keep the link in the entry's metadata
+    Share {
+        /// ID (or alias) of the entry to share
+        id: Option<String>,
+
+        /// Where to share it: gist, paste.rs, 0x0, or a custom provider (default: paste.rs)
+        #[arg(long, value_name = "PROVIDER")]
+        to: Option<String>,
+
+        /// Hours until the paste is deleted, for providers that can expire pastes
+        #[arg(long, value_name = "HOURS")]
+        expires: Option<u64>,
+
+        /// Encrypt before uploading; the key goes in the link's #fragment
+        #[arg(long)]
+        encrypt: bool,
+
+        /// Draw the link as a QR code too
+        #[arg(long)]
+        qr: bool,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+
+        /// Delete the entry's shares from their providers and forget them, instead of sharing it
+        #[arg(long, conflicts_with_all = ["to", "expires", "encrypt", "qr"])]
+        revoke: bool,
+
+        /// With --revoke, only revoke the share at this link
+        #[arg(long, value_name = "URL", requires = "revoke")]
+        url: Option<String>,
+
+        /// Fetch an encrypted share from its whole link, #key included, and print it
+        #[arg(long, value_name = "LINK", conflicts_with_all = ["id", "to", "expires", "encrypt", "qr", "backpack", "revoke"])]
+        open: Option<String>,
+    },
+
+    #[command(about = "Create a project from a template backpack")]
+    /// Write out each entry of a template backpack at the path kept in its
+    /// metadata, filling in {{name}} placeholders in paths and content.
+    /// `pocket scaffold TEMPLATE DIR --save` turns a directory into a template
+    Scaffold {
+        /// Backpack holding the template
+        template: String,
+
+        /// Directory to create the project in, or with --save to save (default: the current one)
+        dir: Option<std::path::PathBuf>,
+
+        /// Value for a placeholder; the rest are asked for
+        #[arg(long = "set", value_name = "NAME=VALUE")]
+        set: Vec<String>,
+
+        /// Overwrite files that already exist
+        #[arg(long)]
+        force: bool,
+
+        /// List the files without writing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Save the directory's files into the template backpack instead
+        #[arg(long, conflicts_with_all = ["set", "force", "dry_run"])]
+        save: bool,
+    },
+
+    #[command(about = "Manage profiles")]
+    /// Keep separate pockets, like work and personal, each with its own
+    /// entries, cards and config
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+
+    #[command(about = "Show or move where pocket keeps its files")]
+    /// Print the data directory and config file in use, or move them
+    Home {
+        #[command(subcommand)]
+        command: Option<HomeCommands>,
+    },
+
+    #[command(about = "See what changed your pocket, and when")]
+    /// Every command that changed the pocket is logged with its arguments,
+    /// when it ran and whether it worked
+    Audit {
+        #[command(subcommand)]
+        command: AuditCommands,
+    },
+
+    #[command(about = "Undo the last change to your entries")]
+    /// Take back what the last add, remove, edit, tag, pin or alias did.
+    /// The last 20 changes are remembered
+    Undo {
+        /// List the changes that can be undone instead
+        #[arg(long)]
+        list: bool,
+
+        /// Undo even if the entries were changed some other way since
+        #[arg(long)]
+        force: bool,
+    },
+
+    #[command(about = "Redo the last undone change")]
+    /// Put back what `pocket undo` took back
+    Redo {
+        /// Redo even if the entries were changed some other way since
+        #[arg(long)]
+        force: bool,
+    },
+
+    #[command(about = "Check pocket's data, config, cards and tools")]
+    /// Look for damaged or orphaned entries, a broken config, cards that
+    /// don't load and missing editors or clipboard tools
+    Doctor {
+        /// Repair what can be repaired without losing anything
+        #[arg(long)]
+        fix: bool,
+    },
+
+    #[command(about = "Manage cached data")]
+    /// Work with pocket's caches
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+
     #[command(about = "🧪 Blend shell scripts into your shell configuration")]
     /// Blend shell scripts into your shell environment
     Blend {
@@ -221,6 +816,76 @@ pub enum Commands {
     },
 }
 
+impl Commands {
+    /// Whether `pocket undo` can take back what the command changes
+    pub fn is_undoable(&self) -> bool {
+        // Servers and watchers run for ages; undo is for single changes
+        self.mutates_storage() && !matches!(
+            self,
+            Commands::McpServe
+                | Commands::Api { .. }
+                | Commands::WatchClipboard { .. }
+                | Commands::Daemon
+                | Commands::Undo { .. }
+                | Commands::Redo { .. }
+                | Commands::Doctor { .. }
+        )
+    }
+
+    /// Whether the command changes what is stored in the pocket
+    pub fn mutates_storage(&self) -> bool {
+        matches!(
+            self,
+            Commands::Add { .. }
+                | Commands::Remove { .. }
+                | Commands::Create { .. }
+                | Commands::Edit { .. }
+                | Commands::Pin { .. }
+                | Commands::Unpin { .. }
+                | Commands::Backpack { .. }
+                | Commands::Dedupe { .. }
+                | Commands::Summarize { .. }
+                | Commands::Tag { .. }
+                | Commands::McpServe
+                | Commands::Api { .. }
+                | Commands::WatchClipboard { .. }
+                | Commands::Daemon
+                | Commands::AddDep { dry_run: false, no_record: false, .. }
+                | Commands::Search { save: Some(_), .. }
+                | Commands::Search { forget: Some(_), .. }
+                | Commands::Search { interactive: true, .. }
+                | Commands::Share { open: None, .. }
+                | Commands::Scaffold { save: true, .. }
+                | Commands::Alias { command: AliasCommands::Add { .. } | AliasCommands::Set { .. } | AliasCommands::Remove { .. } }
+                | Commands::Blend { script_file: Some(_), command: None, .. }
+                | Commands::Blend { command: Some(BlendCommands::Edit { .. } | BlendCommands::Remove { .. } | BlendCommands::Restore | BlendCommands::Uninstall { .. }), .. }
+                | Commands::Cards { operation: Some(CardOperation::Enable { .. } | CardOperation::Disable { .. } | CardOperation::Add { .. }
+                    | CardOperation::Update { .. } | CardOperation::Install { .. } | CardOperation::Remove { .. }
+                    | CardOperation::Permissions { .. } | CardOperation::Create { .. } | CardOperation::Run { .. }) }
+                | Commands::Cards { operation: Some(CardOperation::Config { action: CardConfigCommands::Set { .. } | CardConfigCommands::Unset { .. }, .. }) }
+                | Commands::Profile { command: ProfileCommands::Create { .. } | ProfileCommands::Switch { .. } }
+                | Commands::Home { command: Some(HomeCommands::Move { .. }) }
+                | Commands::Cache { command: CacheCommands::Clear | CacheCommands::Rebuild }
+                | Commands::Execute { save_output: true, .. }
+                | Commands::Doctor { fix: true }
+                | Commands::Undo { list: false, .. }
+                | Commands::Redo { .. }
+                | Commands::Lint { workflow: Some(_), dry_run: false, .. }
+                | Commands::Lint { save: Some(_), .. }
+                | Commands::DeleteWorkflow { .. }
+                | Commands::Workflow { command: WorkflowCommands::Run { dry_run: false, .. } }
+                | Commands::Workflow { command: WorkflowCommands::Save { .. } | WorkflowCommands::Delete { .. } | WorkflowCommands::Edit { .. }
+                    | WorkflowCommands::Schedule { .. } | WorkflowCommands::Unschedule { .. } }
+        )
+    }
+}
+
+/// Seconds for `--timeout`, checked before anything runs
+fn timeout_seconds(value: &str) -> Result<f64, String> {
+    let seconds: f64 = value.parse().map_err(|_| format!("'{}' isn't a number of seconds", value))?;
+    crate::utils::exec::timeout(seconds).map(|_| seconds).map_err(|e| e.to_string())
+}
+
 #[derive(Subcommand)]
 pub enum CardOperation {
     /// List all available cards
@@ -242,15 +907,67 @@ pub enum CardOperation {
         name: String,
     },
 
-    /// Add a new card
+    /// Install a card from a git repository
     Add {
         /// Name of the card
         name: String,
 
-        /// URL of the card repository
+        /// URL of the card repository ("local" to register a card created with `cards create`)
         url: String,
     },
 
+    /// Pull the latest version of an installed card and rebuild it
+    Update {
+        /// Name of the card to update
+        name: String,
+    },
+
+    /// Build a card and run the tests declared in its card.toml
+    Test {
+        /// Name of the card to test
+        name: String,
+    },
+
+    /// Show or revoke the permissions granted to a card
+    Permissions {
+        /// Name of the card
+        name: String,
+
+        /// Withdraw the granted permissions; the card asks again next time it loads
+        #[arg(long)]
+        revoke: bool,
+    },
+
+    /// Read or change a card's options
+    Config {
+        /// Name of the card
+        name: String,
+
+        #[command(subcommand)]
+        action: CardConfigCommands,
+    },
+
+    /// Search the card registry
+    Search {
+        /// Text to look for in card names and descriptions
+        #[arg(default_value = "")]
+        query: String,
+
+        /// Registry index to use instead of the configured one
+        #[arg(long, value_name = "URL")]
+        registry: Option<String>,
+    },
+
+    /// Install a card from the registry
+    Install {
+        /// Card to install, optionally pinned to a version (name@version)
+        spec: String,
+
+        /// Registry index to use instead of the configured one
+        #[arg(long, value_name = "URL")]
+        registry: Option<String>,
+    },
+
     /// Remove a card
     Remove {
         /// Name of the card to remove
@@ -280,6 +997,261 @@ pub enum CardOperation {
         #[arg(short, long)]
         description: String,
     },
+
+    /// Run a command provided by a card
+    Run {
+        /// Name of the card
+        name: String,
+
+        /// Command to run
+        command: String,
+
+        /// Arguments to pass to the command
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BackpackCommands {
+    /// Rename a backpack
+    Rename {
+        /// Current name of the backpack
+        name: String,
+
+        /// New name for the backpack
+        new_name: String,
+    },
+
+    /// Delete a backpack
+    Delete {
+        /// Name of the backpack to delete
+        name: String,
+
+        /// Move the backpack's entries to the general pocket
+        #[arg(short, long)]
+        move_entries: bool,
+
+        /// Delete the backpack even if it still has entries
+        #[arg(short, long, conflicts_with = "move_entries")]
+        force: bool,
+    },
+
+    /// Set or clear a backpack's description
+    EditDescription {
+        /// Name of the backpack
+        name: String,
+
+        /// New description (omit to clear it)
+        description: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CardConfigCommands {
+    /// Show an option, or all options when no key is given
+    Get {
+        /// Dotted path of the option, e.g. backup.compress
+        key: Option<String>,
+    },
+
+    /// Set an option (the value is parsed as JSON, falling back to a string)
+    Set {
+        /// Dotted path of the option, e.g. backup.compress
+        key: String,
+
+        /// New value
+        value: String,
+    },
+
+    /// Remove an option
+    Unset {
+        /// Dotted path of the option
+        key: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AliasCommands {
+    /// Assign an alias to an entry
+    Add {
+        /// Name of the alias
+        name: String,
+
+        /// ID (or unique ID prefix) of the entry
+        id: String,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+    },
+
+    /// Make a shortcut for a pocket command, like `pocket alias set s "search --limit 3"`
+    Set {
+        /// Name of the shortcut
+        name: String,
+
+        /// Command it stands for, without `pocket`
+        command: String,
+    },
+
+    /// Remove an alias
+    Remove {
+        /// Name of the alias to remove
+        name: String,
+    },
+
+    /// List all aliases
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum TagCommands {
+    /// Suggest tags and a better title for an entry, and pick which to keep
+    Suggest {
+        /// ID (or alias) of the entry
+        id: String,
+
+        /// Backpack the entry is in
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+
+        /// Save every suggestion without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorkflowCommands {
+    /// Run a saved workflow, a TOML workflow file, or a one-liner
+    Run {
+        /// Workflow to run
+        workflow: String,
+
+        /// Set a workflow variable (KEY=VAL, repeatable)
+        #[arg(long = "var", value_name = "KEY=VAL")]
+        var: Vec<String>,
+
+        /// Print what each step would do without doing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// List saved workflows
+    List,
+
+    /// Save a workflow file or one-liner under a name
+    Save {
+        /// Name to save it as
+        name: String,
+
+        /// Workflow file or one-liner
+        workflow: String,
+    },
+
+    /// Delete a saved workflow and its schedules
+    Delete {
+        /// Name of the workflow to delete
+        name: String,
+    },
+
+    /// Edit a saved workflow as TOML
+    Edit {
+        /// Name of the workflow to edit
+        name: String,
+    },
+
+    /// Run a saved workflow on a cron schedule or after an event
+    Schedule {
+        /// Name of the saved workflow
+        name: String,
+
+        /// Cron expression, e.g. "0 9 * * *" for 9am every day (needs `pocket daemon` running)
+        #[arg(long, value_name = "EXPR", required_unless_present = "on", conflicts_with = "on")]
+        cron: Option<String>,
+
+        /// Event to run after: add, edit, remove, or an event name like backpack_created
+        #[arg(long, value_name = "EVENT")]
+        on: Option<String>,
+
+        /// Only run after events in this backpack
+        #[arg(short, long, value_name = "NAME", requires = "on")]
+        backpack: Option<String>,
+    },
+
+    /// Remove a workflow's schedules and triggers
+    Unschedule {
+        /// Name of the saved workflow
+        name: String,
+    },
+
+    /// List schedules and triggers
+    Schedules,
+}
+
+#[derive(Subcommand)]
+pub enum StatsCommands {
+    /// Show most-used and never-used snippets
+    Snippets {
+        /// Only include entries from this backpack
+        #[arg(short, long, value_name = "NAME")]
+        backpack: Option<String>,
+
+        /// Number of entries to show in each section
+        #[arg(short, long, value_name = "N", default_value = "10")]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// List profiles, marking the one in use
+    List,
+
+    /// Create a new, empty profile
+    Create {
+        /// Name of the profile
+        name: String,
+    },
+
+    /// Use a profile from now on (when --profile and $POCKET_PROFILE don't say otherwise)
+    Switch {
+        /// Name of the profile, or "default"
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HomeCommands {
+    /// Move pocket's files into the XDG layout, or to PATH
+    Move {
+        /// Directory for the data; the XDG config file remembers it
+        path: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// Show the commands that changed the pocket
+    Show {
+        /// Only those since then: 12h, 7d, 2w, a date or a timestamp
+        #[arg(long, value_name = "WHEN")]
+        since: Option<String>,
+
+        /// Print JSON lines instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommands {
+    /// Delete cached package search results
+    Clear,
+
+    /// Rebuild the entry index from every entry's metadata file
+    Rebuild,
 }
 
 #[derive(Subcommand)]
@@ -293,12 +1265,113 @@ pub enum BlendCommands {
     /// List all installed hooks
     List,
 
+    /// Remove a hook and strip its lines from your shell config
+    Remove {
+        /// Name of the hook to remove (with or without @ prefix)
+        hook_name: String,
+    },
+
+    /// Reinstall the hooks stored in the hooks backpack (e.g. on a new machine)
+    Restore,
+
+    /// Remove every hook and blend's shell config lines. Stored copies
+    /// stay in the hooks backpack for `pocket blend restore`
+    Uninstall {
+        /// Confirm removing everything
+        #[arg(long)]
+        all: bool,
+    },
+
     /// Run a hook command directly
     Run {
         /// Name of the hook to run (with or without @ prefix)
         hook_name: String,
 
-        /// Arguments to pass to the hook
+        /// Arguments to pass to the hook, passed through as-is (flags included)
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    /// Commands that only read the pocket, or write outside it
+    const READ_ONLY: &[&str] = &[
+        "export", "list", "show", "copy", "related", "search", "insert", "reload",
+        "show-help", "lint", "workflow list", "workflow schedules", "serve-editor", "lsp", "version",
+        "repl", "execute", "cards", "cards list", "cards test", "cards search", "cards build",
+        "cards config get", "alias list", "publish", "stats", "stats snippets",
+        "scaffold", "profile list", "home", "audit show", "doctor", "blend", "blend list", "blend run",
+    ];
+
+    /// Arguments commands need that aren't marked required
+    const NEEDS: &[(&str, &[&str])] = &[("workflow schedule", &["--on", "add"])];
+
+    /// Every command line the CLI accepts under `command`, as the
+    /// subcommand path and the arguments it needs to parse. `args` is the
+    /// line so far, `path` its subcommands
+    fn command_lines(command: &clap::Command, path: &[&str], mut args: Vec<String>, lines: &mut Vec<(String, Vec<String>)>) {
+        for arg in command.get_arguments().filter(|arg| arg.is_required_set()) {
+            if !arg.is_positional() {
+                args.push(format!("--{}", arg.get_long().unwrap()));
+            }
+            let value = arg.get_possible_values().first().map(|value| value.get_name().to_string());
+            args.push(value.unwrap_or_else(|| "1".to_string()));
+        }
+        let joined = path.join(" ");
+        if let Some((_, extra)) = NEEDS.iter().find(|(needy, _)| *needy == joined) {
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+        }
+        if !path.is_empty() && !command.is_subcommand_required_set() {
+            lines.push((joined, args.clone()));
+        }
+
+        for subcommand in command.get_subcommands().filter(|c| c.get_name() != "help") {
+            let path = [path, &[subcommand.get_name()]].concat();
+            let mut args = args.clone();
+            args.push(subcommand.get_name().to_string());
+            command_lines(subcommand, &path, args, lines);
+        }
+    }
+
+    #[test]
+    fn every_command_says_whether_it_changes_the_pocket() {
+        let mut lines = Vec::new();
+        command_lines(&Cli::command(), &[], Vec::new(), &mut lines);
+        assert!(lines.len() > READ_ONLY.len());
+
+        for (path, args) in lines {
+            let cli = Cli::try_parse_from(std::iter::once("pocket".to_string()).chain(args))
+                .unwrap_or_else(|e| panic!("pocket {} didn't parse: {}", path, e));
+            assert_eq!(
+                cli.command.mutates_storage(),
+                !READ_ONLY.contains(&path.as_str()),
+                "pocket {} is classified wrongly",
+                path,
+            );
+        }
+    }
+
+    #[test]
+    fn flags_that_change_the_pocket_are_counted() {
+        let lines: &[(&str, bool)] = &[
+            ("search saved", false),
+            ("search saved --save mine", true),
+            ("search --forget mine", true),
+            ("search --saved", false),
+            ("share ID", true),
+            ("share ID --revoke", true),
+            ("share --open LINK", false),
+            ("scaffold rust-cli", false),
+            ("scaffold rust-cli ./starter --save", true),
+        ];
+        for (line, mutates) in lines {
+            let cli = Cli::try_parse_from(std::iter::once("pocket").chain(line.split(' ')))
+                .unwrap_or_else(|e| panic!("pocket {} didn't parse: {}", line, e));
+            assert_eq!(cli.command.mutates_storage(), *mutates, "pocket {}", line);
+        }
+    }
+}
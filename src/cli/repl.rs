@@ -0,0 +1,329 @@
+//! `pocket repl`: an interactive shell for pocket commands
+//!
+//! Lines are parsed like a `pocket` command line without the binary name.
+//! The shell remembers a current backpack (set with `use`) and the results of
+//! the last search, so `show 2` means the second result. History is kept in
+//! `~/.pocket/repl_history`.
+
+use crate::cards::CardManager;
+use crate::cli::{commands, handler};
+use crate::errors::{PocketError, PocketResult};
+use crate::logging;
+use crate::storage::{self, StorageManager};
+use crate::utils::atomic;
+use dialoguer::theme::Theme;
+use dialoguer::{Completion, History, Input};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, IsTerminal};
+use std::path::PathBuf;
+
+/// Most history lines kept on disk
+const MAX_HISTORY: usize = 1000;
+
+/// Commands whose first argument is an entry, which can be given as a number
+/// from the last search results
+const ENTRY_COMMANDS: &[&str] = &["show", "related", "edit", "remove", "insert", "export", "pin", "unpin", "execute"];
+
+/// Commands that only exist inside the REPL
+const REPL_COMMANDS: &[&str] = &["use", "exit", "quit"];
+
+/// What the REPL remembers between lines
+#[derive(Debug, Default)]
+struct ReplState {
+    /// Backpack commands run in unless they name one
+    backpack: Option<String>,
+}
+
+/// Runs the REPL until `exit`, `quit` or the end of input
+pub fn run(card_manager: &mut CardManager) -> PocketResult<()> {
+    let storage = StorageManager::new()
+        .map_err(PocketError::storage)?;
+    let mut state = ReplState {
+        backpack: storage.resolve_backpack(None, false)
+            .map_err(|e| PocketError::Config(e.to_string()))?,
+    };
+    
+    let interactive = std::io::stdin().is_terminal();
+    let mut history = FileHistory::load(history_path()?);
+    let mut lines = std::io::stdin().lock().lines();
+    
+    if interactive {
+        println!("Pocket REPL. Type a pocket command without the `pocket`, `use <backpack>` to switch backpacks, or `exit` to leave.");
+    }
+    
+    loop {
+        // Card commands change as cards get enabled, disabled or reloaded
+        if let Err(e) = card_manager.reload_if_changed() {
+            logging::warning(&format!("Failed to reload cards: {}", e));
+        }
+        let card_commands = handler::card_subcommands(card_manager);
+        
+        let line = if interactive {
+            let completer = Completer {
+                commands: command_names(&card_commands),
+                backpacks: backpack_names(&storage),
+            };
+            let prompt = match &state.backpack {
+                Some(name) => format!("pocket [{}]", name),
+                None => "pocket".to_string(),
+            };
+            match Input::<String>::with_theme(&ReplTheme)
+                .with_prompt(prompt)
+                .allow_empty(true)
+                .history_with(&mut history)
+                .completion_with(&completer)
+                .interact_text()
+            {
+                Ok(line) => line,
+                Err(_) => break,
+            }
+        } else {
+            match lines.next() {
+                Some(line) => line.map_err(|e| PocketError::Cli(e.to_string()))?,
+                None => break,
+            }
+        };
+        
+        let words = match shell_words::split(&line) {
+            Ok(words) => words,
+            Err(e) => {
+                logging::error(&format!("Can't parse that line: {}", e));
+                continue;
+            }
+        };
+        let cli = handler::cli_command(&card_commands);
+        let words = match commands::alias::expand(words, &handler::command_aliases(), &cli) {
+            Ok(words) => words,
+            Err(e) => {
+                logging::error(&e.to_string());
+                continue;
+            }
+        };
+        let Some(command) = words.first() else {
+            continue;
+        };
+        
+        match command.as_str() {
+            "exit" | "quit" => break,
+            "repl" => {
+                logging::warning("Already in the REPL");
+                continue;
+            }
+            "use" => {
+                match words.get(1).map(String::as_str) {
+                    None => println!("Using the {} backpack", state.backpack.as_deref().unwrap_or("general")),
+                    Some("general") => state.backpack = None,
+                    Some(name) if storage.load_backpack(name).is_ok() => state.backpack = Some(name.to_string()),
+                    Some(name) => logging::error(&format!("No backpack named '{}'", name)),
+                }
+                continue;
+            }
+            _ => {}
+        }
+        
+        let takes_backpack = cli.find_subcommand(command)
+            .is_some_and(|sub| sub.get_arguments().any(|arg| arg.get_long() == Some("backpack")));
+        let args = expand_line(words, &state, &crate::cards::core::last_results(), takes_backpack);
+        
+        match cli.try_get_matches_from(std::iter::once("pocket".to_string()).chain(args.iter().cloned())) {
+            Ok(matches) => {
+                if let Err(e) = handler::dispatch(&matches, &args, &card_commands, card_manager) {
+                    logging::error(&e.to_string());
+                }
+                crate::workflow::schedule::run_triggers(card_manager);
+            }
+            Err(e) => {
+                let _ = e.print();
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Turns a REPL line into pocket arguments: a number standing for an entry
+/// becomes that search result's ID (and backpack), and commands that take a
+/// backpack get the current one unless they name their own
+fn expand_line(mut words: Vec<String>, state: &ReplState, results: &[(String, Option<String>)], takes_backpack: bool) -> Vec<String> {
+    let names_backpack = words.iter()
+        .any(|w| w == "-b" || w == "--backpack" || w.starts_with("--backpack="));
+    
+    let mut backpack = state.backpack.clone();
+    if ENTRY_COMMANDS.contains(&words[0].as_str()) && words.len() > 1 {
+        let result = words[1].parse::<usize>().ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|index| results.get(index));
+        if let Some((id, location)) = result {
+            words[1] = id.clone();
+            backpack = location.clone();
+        }
+    }
+    
+    if let (true, false, Some(name)) = (takes_backpack, names_backpack, backpack) {
+        words.push("--backpack".to_string());
+        words.push(name);
+    }
+    words
+}
+
+/// Every command the REPL understands, for completion
+fn command_names(card_commands: &std::collections::BTreeMap<String, Vec<crate::cards::CardCommand>>) -> Vec<String> {
+    let cli = handler::cli_command(card_commands);
+    let mut names: Vec<String> = cli.get_subcommands()
+        .flat_map(|c| std::iter::once(c.get_name()).chain(c.get_visible_aliases()))
+        .chain(REPL_COMMANDS.iter().copied())
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Backpack names, for completing `use`
+fn backpack_names(storage: &StorageManager) -> Vec<String> {
+    let mut names: Vec<String> = storage.list_backpacks()
+        .map(|backpacks| backpacks.into_iter().map(|b| b.name).collect())
+        .unwrap_or_default();
+    names.push("general".to_string());
+    names
+}
+
+/// Where the REPL keeps its history
+fn history_path() -> PocketResult<PathBuf> {
+    let dir = storage::pocket_dir().map_err(|e| PocketError::Config(e.to_string()))?;
+    Ok(dir.join("repl_history"))
+}
+
+/// Tab completion for command names and `use` backpacks
+struct Completer {
+    commands: Vec<String>,
+    backpacks: Vec<String>,
+}
+
+impl Completion for Completer {
+    fn get(&self, input: &str) -> Option<String> {
+        complete(input, &self.commands, &self.backpacks)
+    }
+}
+
+/// Completes the word being typed: the command, or the backpack after `use`.
+/// A unique match is finished off with a space; several matches are filled
+/// in as far as they agree
+fn complete(input: &str, commands: &[String], backpacks: &[String]) -> Option<String> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    let typing_new_word = input.is_empty() || input.ends_with(char::is_whitespace);
+    
+    let (done, partial, candidates) = match (words.as_slice(), typing_new_word) {
+        ([partial], false) => ("", *partial, commands),
+        (["use"], true) => ("use ", "", backpacks),
+        (["use", partial], false) => ("use ", *partial, backpacks),
+        _ => return None,
+    };
+    
+    let matches: Vec<&String> = candidates.iter().filter(|c| c.starts_with(partial)).collect();
+    match matches.as_slice() {
+        [] => None,
+        [only] => Some(format!("{}{} ", done, only)),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |len, other| {
+                first.bytes().zip(other.bytes()).take(len).take_while(|(a, b)| a == b).count()
+            });
+            (common > partial.len()).then(|| format!("{}{}", done, &first[..common]))
+        }
+    }
+}
+
+/// History that's saved to a file as it grows, newest line first in memory
+struct FileHistory {
+    path: PathBuf,
+    lines: VecDeque<String>,
+}
+
+impl FileHistory {
+    fn load(path: PathBuf) -> Self {
+        let lines = fs::read_to_string(&path)
+            .map(|content| content.lines().rev().map(str::to_string).collect())
+            .unwrap_or_default();
+        Self { path, lines }
+    }
+}
+
+impl<T: ToString> History<T> for FileHistory {
+    fn read(&self, pos: usize) -> Option<String> {
+        self.lines.get(pos).cloned()
+    }
+    
+    fn write(&mut self, val: &T) {
+        let line = val.to_string().trim().to_string();
+        if line.is_empty() || self.lines.front() == Some(&line) {
+            return;
+        }
+        self.lines.push_front(line);
+        self.lines.truncate(MAX_HISTORY);
+        
+        let content: String = self.lines.iter().rev().map(|l| format!("{}\n", l)).collect();
+        if let Err(e) = atomic::write(&self.path, content) {
+            log::debug!("Failed to save REPL history: {}", e);
+        }
+    }
+}
+
+/// Renders the prompt as `pocket> ` rather than dialoguer's `pocket: `
+struct ReplTheme;
+
+impl Theme for ReplTheme {
+    fn format_input_prompt(&self, f: &mut dyn fmt::Write, prompt: &str, _default: Option<&str>) -> fmt::Result {
+        write!(f, "{}> ", prompt)
+    }
+    
+    fn format_input_prompt_selection(&self, f: &mut dyn fmt::Write, prompt: &str, sel: &str) -> fmt::Result {
+        write!(f, "{}> {}", prompt, sel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn words(line: &str) -> Vec<String> {
+        shell_words::split(line).unwrap()
+    }
+    
+    #[test]
+    fn numbers_refer_to_the_last_results() {
+        let results = vec![
+            ("aaa".to_string(), None),
+            ("bbb".to_string(), Some("work".to_string())),
+        ];
+        let state = ReplState { backpack: Some("home".to_string()) };
+        
+        assert_eq!(expand_line(words("show 2"), &state, &results, true), words("show bbb --backpack work"));
+        assert_eq!(expand_line(words("show 1"), &state, &results, true), words("show aaa"));
+        assert_eq!(expand_line(words("show 3"), &state, &results, true), words("show 3 --backpack home"));
+        assert_eq!(expand_line(words("search 2"), &state, &results, true), words("search 2 --backpack home"));
+    }
+    
+    #[test]
+    fn current_backpack_fills_in_when_not_given() {
+        let state = ReplState { backpack: Some("home".to_string()) };
+        assert_eq!(expand_line(words("list"), &state, &[], true), words("list --backpack home"));
+        assert_eq!(expand_line(words("list -b work"), &state, &[], true), words("list -b work"));
+        assert_eq!(expand_line(words("version"), &state, &[], false), words("version"));
+        assert_eq!(expand_line(words("list"), &ReplState::default(), &[], true), words("list"));
+    }
+    
+    #[test]
+    fn completes_commands_and_backpacks() {
+        let commands: Vec<String> = ["search", "show", "stats", "use"].iter().map(|s| s.to_string()).collect();
+        let backpacks: Vec<String> = ["work", "general"].iter().map(|s| s.to_string()).collect();
+        
+        assert_eq!(complete("se", &commands, &backpacks).as_deref(), Some("search "));
+        assert_eq!(complete("s", &commands, &backpacks), None);
+        assert_eq!(complete("sh", &commands, &backpacks).as_deref(), Some("show "));
+        assert_eq!(complete("use w", &commands, &backpacks).as_deref(), Some("use work "));
+        assert_eq!(complete("use ", &commands, &["work".to_string()]).as_deref(), Some("use work "));
+        assert_eq!(complete("show ab", &commands, &backpacks), None);
+    }
+}
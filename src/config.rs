@@ -1,4 +1,5 @@
-use crate::errors::{PocketResult, IntoPocketError};
+use crate::errors::{PocketError, PocketResult, IntoPocketError};
+use crate::utils::atomic;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -178,8 +179,8 @@ impl ConfigManager {
         }
         
         // Write the config file
-        fs::write(&self.config_path, config_str)
-            .config_err(&format!("Failed to write config to {}", self.config_path.display()))?;
+        atomic::write(&self.config_path, config_str)
+            .map_err(|e| PocketError::Config(format!("Failed to write config to {}: {}", self.config_path.display(), e)))?;
         
         debug!("Saved config to {}", self.config_path.display());
         Ok(())
@@ -1,3 +1,4 @@
+use crate::i18n::tr;
 use thiserror::Error;
 use std::path::PathBuf;
 
@@ -5,27 +6,27 @@ use std::path::PathBuf;
 #[derive(Error, Debug)]
 pub enum PocketError {
     /// Error related to storage operations
-    #[error("Storage error: {0}")]
+    #[error("{}: {}", tr!("Storage error"), .0)]
     Storage(String),
 
     /// Error related to specific entry operations
-    #[error("Entry error: {0}")]
+    #[error("{}: {}", tr!("Entry error"), .0)]
     Entry(String),
 
     /// Error related to CLI operations
-    #[error("CLI error: {0}")]
+    #[error("{}: {}", tr!("CLI error"), .0)]
     Cli(String),
 
     /// Error related to card operations
-    #[error("Card error: {0}")]
+    #[error("{}: {}", tr!("Card error"), .0)]
     Card(String),
 
     /// Error related to hook/blend operations
-    #[error("Hook error: {0}")]
+    #[error("{}: {}", tr!("Hook error"), .0)]
     Hook(String),
 
     /// Error related to file operations (unused)
-    #[error("File error: {source}")]
+    #[error("{}: {source}", tr!("File error"))]
     _File {
         #[source]
         source: std::io::Error,
@@ -33,26 +34,198 @@ pub enum PocketError {
     },
 
     /// Error related to configuration
-    #[error("Configuration error: {0}")]
+    #[error("{}: {}", tr!("Configuration error"), .0)]
     Config(String),
 
+    /// Something asked for doesn't exist
+    #[error("{}: {}", tr!("Not found"), .0)]
+    NotFound(String),
+
+    /// Something being created already exists, or changed underneath us
+    #[error("{}: {}", tr!("Conflict"), .0)]
+    Conflict(String),
+
+    /// Input that doesn't make sense
+    #[error("{}: {}", tr!("Invalid input"), .0)]
+    Validation(String),
+
     /// Error related to search operations
-    #[error("Search error: {0}")]
+    #[error("{}: {}", tr!("Search error"), .0)]
     Search(String),
 
+    /// Error related to workflows
+    #[error("{}: {}", tr!("Workflow error"), .0)]
+    Workflow(String),
+
     /// User canceled an operation (unused)
-    #[error("Operation canceled by user")]
+    #[error("{}", tr!("Operation canceled by user"))]
     _Canceled,
 
     /// Missing permission (unused)
-    #[error("Permission denied: {0}")]
+    #[error("{}: {}", tr!("Permission denied"), .0)]
     _PermissionDenied(String),
 
     /// Other unexpected errors
-    #[error("Unexpected error: {0}")]
+    #[error("{}: {}", tr!("Unexpected error"), .0)]
     Other(String),
 }
 
+/// Failures storage reports that callers need to tell apart. They travel
+/// through anyhow and keep their kind however much context is added on the
+/// way up, so nothing has to go by the wording
+#[derive(Error, Debug)]
+pub enum StorageError {
+    /// An entry, backpack, workflow or profile that doesn't exist
+    #[error("{0}")]
+    NotFound(String),
+
+    /// Something that already exists, or changed since it was loaded
+    #[error("{0}")]
+    Conflict(String),
+
+    /// A name or reference storage can't use
+    #[error("{0}")]
+    Validation(String),
+}
+
+/// Stable categories for errors, so scripts can tell failures apart
+/// without parsing messages. Each has its own exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// An entry, backpack, card or file that doesn't exist
+    NotFound,
+    /// Something that already exists, or was changed by someone else
+    Conflict,
+    /// Bad arguments, names, options or config
+    Validation,
+    /// Reading or writing files failed
+    Io,
+    /// A network service failed or couldn't be reached
+    Remote,
+    /// Ctrl-C stopped the command
+    Cancelled,
+    /// Anything else
+    Internal,
+}
+
+impl ErrorCode {
+    /// The name used in JSON output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::Validation => "validation",
+            ErrorCode::Io => "io",
+            ErrorCode::Remote => "remote",
+            ErrorCode::Cancelled => "cancelled",
+            ErrorCode::Internal => "internal",
+        }
+    }
+
+    /// The process exit code. 2 matches what clap uses for bad usage, and
+    /// 130 what shells use for Ctrl-C
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCode::Internal => 1,
+            ErrorCode::Validation => 2,
+            ErrorCode::NotFound => 3,
+            ErrorCode::Conflict => 4,
+            ErrorCode::Io => 5,
+            ErrorCode::Remote => 6,
+            ErrorCode::Cancelled => crate::utils::cancel::EXIT_CODE,
+        }
+    }
+
+    /// Best guess at the category of an error known only by its message,
+    /// as errors from cards are
+    pub fn classify(message: &str) -> Self {
+        let message = message.to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|word| message.contains(word));
+        if has(&["cancelled", "canceled"]) {
+            ErrorCode::Cancelled
+        } else if has(&["not found", "no such", "does not exist", "doesn't exist", "no entry", "no backpack", "no profile", "no card"]) {
+            ErrorCode::NotFound
+        } else if has(&["already exists", "already in", "conflict", "changed since"]) {
+            ErrorCode::Conflict
+        } else if has(&["http", "network", "timed out", "failed to fetch", "failed to connect", "registry"]) {
+            ErrorCode::Remote
+        } else if has(&["invalid", "must be", "expected", "unknown", "missing", "required", "cannot be empty"]) {
+            ErrorCode::Validation
+        } else if has(&["permission denied", "failed to read", "failed to write", "failed to create", "os error", "disk"]) {
+            ErrorCode::Io
+        } else {
+            ErrorCode::Internal
+        }
+    }
+}
+
+impl PocketError {
+    /// A command's failure in a card, as `context: error`. Failures storage
+    /// gave a kind keep it
+    pub fn card(context: impl std::fmt::Display, error: anyhow::Error) -> Self {
+        Self::typed(&error, format!("{}: {}", context, error)).unwrap_or_else(PocketError::Card)
+    }
+
+    /// A failure in storage, keeping its kind when it has one
+    pub fn storage(error: impl Into<anyhow::Error>) -> Self {
+        let error = error.into();
+        Self::typed(&error, error.to_string()).unwrap_or_else(PocketError::Storage)
+    }
+
+    /// The variant for an error storage gave a kind, with `message`. The
+    /// message comes back for errors without one
+    fn typed(error: &anyhow::Error, message: String) -> Result<Self, String> {
+        match error.chain().find_map(|cause| cause.downcast_ref::<StorageError>()) {
+            Some(StorageError::NotFound(_)) => Ok(PocketError::NotFound(message)),
+            Some(StorageError::Conflict(_)) => Ok(PocketError::Conflict(message)),
+            Some(StorageError::Validation(_)) => Ok(PocketError::Validation(message)),
+            None => Err(message),
+        }
+    }
+
+    /// The error's category. Anything failing after Ctrl-C was cancelled;
+    /// otherwise errors that say what they are keep it, and the rest are
+    /// sorted by their message
+    pub fn code(&self) -> ErrorCode {
+        if crate::utils::cancel::interrupted() {
+            return ErrorCode::Cancelled;
+        }
+        match self {
+            PocketError::NotFound(_) => ErrorCode::NotFound,
+            PocketError::Conflict(_) => ErrorCode::Conflict,
+            PocketError::Validation(_) | PocketError::Cli(_) => ErrorCode::Validation,
+            PocketError::_File { .. } | PocketError::_PermissionDenied(_) => ErrorCode::Io,
+            PocketError::_Canceled => ErrorCode::Cancelled,
+            PocketError::Storage(message) => match ErrorCode::classify(message) {
+                ErrorCode::Internal => ErrorCode::Io,
+                code => code,
+            },
+            PocketError::Config(message) => match ErrorCode::classify(message) {
+                ErrorCode::Internal => ErrorCode::Validation,
+                code => code,
+            },
+            PocketError::Entry(message)
+            | PocketError::Card(message)
+            | PocketError::Hook(message)
+            | PocketError::Search(message)
+            | PocketError::Workflow(message)
+            | PocketError::Other(message) => ErrorCode::classify(message),
+        }
+    }
+
+    /// The error as a JSON object, for `--output json`
+    pub fn to_json(&self) -> serde_json::Value {
+        let code = self.code();
+        serde_json::json!({
+            "error": {
+                "code": code.as_str(),
+                "exit_code": code.exit_code(),
+                "message": self.to_string(),
+            }
+        })
+    }
+}
+
 /// Result type alias for Pocket CLI
 pub type PocketResult<T> = std::result::Result<T, PocketError>;
 
@@ -106,4 +279,40 @@ impl<T, E: std::error::Error + Send + Sync + 'static> IntoPocketError<T> for Res
     fn _other_err(self, msg: &str) -> PocketResult<T> {
         self.map_err(|e| PocketError::Other(format!("{}: {}", msg, e)))
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_get_stable_codes() {
+        assert_eq!(PocketError::NotFound("entry abc".into()).code(), ErrorCode::NotFound);
+        assert_eq!(PocketError::Cli("bad flag".into()).code(), ErrorCode::Validation);
+        assert_eq!(PocketError::Card("Failed to run snippet show: Entry not found: abc".into()).code(), ErrorCode::NotFound);
+        assert_eq!(PocketError::Card("Backpack 'work' already exists".into()).code(), ErrorCode::Conflict);
+        assert_eq!(PocketError::Other("npm registry returned HTTP 500".into()).code(), ErrorCode::Remote);
+        assert_eq!(PocketError::Storage("out of inodes".into()).code(), ErrorCode::Io);
+        assert_eq!(PocketError::Other("something odd".into()).code(), ErrorCode::Internal);
+    }
+
+    #[test]
+    fn storage_errors_keep_their_kind_whatever_they_say() {
+        let exists = anyhow::Error::from(StorageError::Conflict("Backpack 'x' is taken".into())).context("Renaming x");
+        let err = PocketError::card("Failed to rename backpack", exists);
+        assert_eq!(err.code(), ErrorCode::Conflict);
+        assert_eq!(err.to_string(), "Conflict: Failed to rename backpack: Renaming x");
+
+        assert_eq!(PocketError::storage(StorageError::NotFound("gone".into())).code(), ErrorCode::NotFound);
+        assert_eq!(PocketError::storage(StorageError::Validation("bad name".into())).code(), ErrorCode::Validation);
+        assert_eq!(PocketError::card("Failed", anyhow::anyhow!("something odd")).code(), ErrorCode::Internal);
+    }
+
+    #[test]
+    fn json_errors_carry_code_and_message() {
+        let json = PocketError::Conflict("profile 'work' exists".into()).to_json();
+        assert_eq!(json["error"]["code"], "conflict");
+        assert_eq!(json["error"]["exit_code"], 4);
+        assert_eq!(json["error"]["message"], "Conflict: profile 'work' exists");
+    }
+}
@@ -0,0 +1,68 @@
+//! Spanish
+
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    // Errors
+    ("Storage error", "Error de almacenamiento"),
+    ("Entry error", "Error de entrada"),
+    ("CLI error", "Error de la línea de comandos"),
+    ("Card error", "Error de tarjeta"),
+    ("Hook error", "Error de hook"),
+    ("File error", "Error de archivo"),
+    ("Configuration error", "Error de configuración"),
+    ("Not found", "No encontrado"),
+    ("Conflict", "Conflicto"),
+    ("Invalid input", "Entrada no válida"),
+    ("Search error", "Error de búsqueda"),
+    ("Workflow error", "Error de flujo de trabajo"),
+    ("Operation canceled by user", "Operación cancelada por el usuario"),
+    ("Permission denied", "Permiso denegado"),
+    ("Unexpected error", "Error inesperado"),
+
+    // Adding and changing entries
+    ("Added snippet with ID: {}", "Fragmento añadido con ID: {}"),
+    ("Added binary entry with ID: {}", "Entrada binaria añadida con ID: {}"),
+    ("Inserting entry {} into {}", "Insertando la entrada {} en {}"),
+    ("Continue?", "¿Continuar?"),
+    ("Operation cancelled", "Operación cancelada"),
+    ("Successfully inserted entry {} into {}", "Entrada {} insertada en {}"),
+    ("Pinned entry: {}", "Entrada fijada: {}"),
+    ("Unpinned entry: {}", "Entrada desfijada: {}"),
+    ("No changes to {}", "Sin cambios en {}"),
+    ("Updated entry: {}", "Entrada actualizada: {}"),
+    ("You are about to remove: {}", "Vas a eliminar: {}"),
+    ("Title: {}", "Título: {}"),
+    ("Are you sure?", "¿Seguro?"),
+    ("Removed entry: {}", "Entrada eliminada: {}"),
+    ("Copied {} to the clipboard", "{} copiado al portapapeles"),
+
+    // Listing and searching
+    ("No entries found", "No se encontraron entradas"),
+    ("No related entries found", "No se encontraron entradas relacionadas"),
+    ("No results found for query: {}", "Sin resultados para la búsqueda: {}"),
+    ("Search results for: {}", "Resultados de la búsqueda: {}"),
+    ("No duplicate entries found", "No se encontraron entradas duplicadas"),
+    ("Merged {} duplicate entries", "{} entradas duplicadas fusionadas"),
+
+    // Backpacks
+    ("Created backpack: {}", "Mochila creada: {}"),
+    ("Renamed backpack {} to {}", "Mochila {} renombrada a {}"),
+    ("Deleted backpack {} and moved {} entries to the general pocket", "Mochila {} eliminada y {} entradas movidas al bolsillo general"),
+    ("Deleted backpack {}", "Mochila {} eliminada"),
+    ("Updated description of backpack {}", "Descripción de la mochila {} actualizada"),
+
+    // Scaffolding
+    ("Created {} files in {}", "{} archivos creados en {}"),
+    ("Saved {} files to template {}", "{} archivos guardados en la plantilla {}"),
+
+    // Aliases
+    ("No aliases defined", "No hay alias definidos"),
+    ("Alias {} -> {}", "Alias {} -> {}"),
+    ("Alias {} = {}", "Alias {} = {}"),
+    ("Removed alias: {}", "Alias eliminado: {}"),
+
+    // Extensions
+    ("No extensions installed yet", "Todavía no hay extensiones instaladas"),
+    ("Available extensions:", "Extensiones disponibles:"),
+    ("No hooks installed yet", "Todavía no hay hooks instalados"),
+    ("Installed hooks:", "Hooks instalados:"),
+];
@@ -0,0 +1,129 @@
+//! Translations of what pocket says to people
+//!
+//! Messages are looked up by their English text, so code reads the same
+//! whatever the language, and anything without a translation yet comes out
+//! in English. The language is `display.language` in `config.toml`, else
+//! the one `LC_ALL`, `LC_MESSAGES` or `LANG` asks for. English and Spanish
+//! are available so far; each other language is a catalog like [`es`].
+//!
+//! What scripts rely on, like error codes, JSON keys and IDs, is the same
+//! in every language.
+
+mod es;
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// The language in use, as a [`Locale`] discriminant
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::English as u8);
+
+/// Languages pocket speaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    English = 0,
+    Spanish = 1,
+}
+
+impl Locale {
+    /// The locale for a tag like `es`, `es_MX.UTF-8` or `en-GB`, if pocket
+    /// speaks its language
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['_', '-', '.', '@']).next()?.to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::English),
+            "es" => Some(Locale::Spanish),
+            _ => None,
+        }
+    }
+
+    /// The locale `configured` (`display.language`) asks for, else the
+    /// environment's, else English
+    pub fn detect(configured: Option<&str>) -> Self {
+        configured.into_iter()
+            .map(str::to_string)
+            .chain(["LC_ALL", "LC_MESSAGES", "LANG"].into_iter().filter_map(|name| std::env::var(name).ok()))
+            .filter(|tag| !tag.trim().is_empty())
+            .find_map(|tag| Locale::from_tag(tag.trim()))
+            .unwrap_or(Locale::English)
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::English => &[],
+            Locale::Spanish => es::MESSAGES,
+        }
+    }
+}
+
+/// Uses `locale` for the rest of the process
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+/// The language in use
+pub fn locale() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        1 => Locale::Spanish,
+        _ => Locale::English,
+    }
+}
+
+/// `message` in the language in use
+pub fn translate(message: &'static str) -> &'static str {
+    locale().catalog().iter()
+        .find(|(english, _)| *english == message)
+        .map_or(message, |(_, translated)| translated)
+}
+
+/// Puts `args` into the `{}`s of `template`, in order
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        out.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            out.push_str(&arg.to_string());
+        }
+        out.push_str(part);
+    }
+    out
+}
+
+/// A message in the language in use, with `{}`s filled in like `format!`
+macro_rules! tr {
+    ($message:literal) => {
+        $crate::i18n::translate($message)
+    };
+    ($message:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill($crate::i18n::translate($message), &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_a_locale_and_translates() {
+        assert_eq!(Locale::from_tag("es_MX.UTF-8"), Some(Locale::Spanish));
+        assert_eq!(Locale::from_tag("en-GB"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("C"), Some(Locale::English));
+        assert_eq!(Locale::from_tag("fr_FR"), None);
+        assert_eq!(Locale::detect(Some("es")), Locale::Spanish);
+
+        assert_eq!(fill("Renamed {} to {}", &[&"a", &"b"]), "Renamed a to b");
+        let translated = Locale::Spanish.catalog().iter().find(|(english, _)| *english == "Removed entry: {}").unwrap().1;
+        assert_eq!(fill(translated, &[&"abc"]), "Entrada eliminada: abc");
+    }
+
+    #[test]
+    fn translations_keep_their_placeholders() {
+        for (english, translated) in Locale::Spanish.catalog() {
+            assert_eq!(english.matches("{}").count(), translated.matches("{}").count(), "{}", english);
+        }
+    }
+}
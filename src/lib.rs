@@ -5,12 +5,16 @@ pub mod cards;
 pub mod cli;
 pub mod config;
 pub mod errors;
+pub mod i18n;
 pub mod logging;
 pub mod models;
+pub mod registry;
 pub mod search;
+pub mod server;
 pub mod storage;
 pub mod utils;
 pub mod version;
+pub mod workflow;
 
 pub use errors::{PocketError, PocketResult};
 pub use config::Config;
\ No newline at end of file
@@ -1,94 +1,294 @@
+//! Logging for pocket
+//!
+//! Log records go to stderr at the level `-v` asks for, as text or as JSON
+//! lines (`--log-format json`). They can also go to a log file, by default
+//! `logs/pocket.log` in the pocket directory, which rotates once it gets
+//! big. `--log-file` turns the file on for one command; `[logging]` in
+//! `config.toml` turns it on for good and picks its level.
+
 use colored::{ColoredString, Colorize};
-use log::{Level, LevelFilter};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
 use std::io::Write;
-use chrono::Local;
+use std::path::PathBuf;
+use std::sync::Mutex;
 use std::sync::Once;
+use chrono::{Local, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 
 static INIT: Once = Once::new();
 
-pub fn init(level: LevelFilter) {
-    INIT.call_once(|| {
-        env_logger::Builder::new()
-            .format(|buf, record| {
-                let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-                
-                let level_str = match record.level() {
-                    Level::Error => "ERROR".red().bold(),
-                    Level::Warn => "WARN ".yellow().bold(),
-                    Level::Info => "INFO ".green(),
-                    Level::Debug => "DEBUG".blue(),
-                    Level::Trace => "TRACE".magenta(),
+/// Target for records meant for the log file alone, like errors that are
+/// already reported on stderr in their own form
+pub const FILE_ONLY: &str = "pocket::log_file";
+
+static LOGGER: PocketLogger = PocketLogger {
+    state: Mutex::new(LoggerState {
+        stderr_level: LevelFilter::Warn,
+        format: LogFormat::Text,
+        file: None,
+    }),
+};
+
+/// How log records are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Timestamp, level, module and message, for people
+    Text,
+    /// One JSON object per record, for tools
+    Json,
+}
+
+/// Where the log file goes and how it's kept
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    pub path: PathBuf,
+    pub level: LevelFilter,
+    /// Size the file rotates at
+    pub max_bytes: u64,
+    /// Rotated files kept, as `pocket.log.1` (newest) up to `pocket.log.N`
+    pub keep: usize,
+}
+
+impl LogFile {
+    /// Rotate at 1 MiB unless configured otherwise
+    pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+
+    /// Keep this many rotated files unless configured otherwise
+    pub const DEFAULT_KEEP: usize = 3;
+}
+
+struct LoggerState {
+    stderr_level: LevelFilter,
+    format: LogFormat,
+    file: Option<(LogFile, File)>,
+}
+
+struct PocketLogger {
+    state: Mutex<LoggerState>,
+}
+
+impl Log for PocketLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+
+        if record.level() <= state.stderr_level && record.target() != FILE_ONLY {
+            let line = match state.format {
+                LogFormat::Text => text_line(record, true),
+                LogFormat::Json => json_line(record),
+            };
+            eprintln!("{}", line);
+        }
+
+        let format = state.format;
+        if let Some((config, file)) = &mut state.file {
+            if record.level() <= config.level {
+                let line = match format {
+                    LogFormat::Text => text_line(record, false),
+                    LogFormat::Json => json_line(record),
                 };
-                
-                let target = if let Some(target) = record.module_path() {
-                    if target.contains("::") {
-                        let parts: Vec<&str> = target.split("::").collect();
-                        let prefix = parts[0];
-                        let suffix = parts.last().unwrap_or(&"");
-                        format!("{}.{}", prefix, suffix)
-                    } else {
-                        target.to_string()
+                let _ = writeln!(file, "{}", line);
+                if file.metadata().is_ok_and(|metadata| metadata.len() >= config.max_bytes) {
+                    if let Ok(reopened) = rotate(config) {
+                        *file = reopened;
                     }
-                } else {
-                    "pocket".to_string()
-                };
-                
-                writeln!(
-                    buf,
-                    "{} {} {} > {}",
-                    timestamp.dimmed(),
-                    level_str,
-                    target.dimmed(),
-                    record.args()
-                )
-            })
-            .filter(None, level)
-            .init();
-        
-        log::info!("Logger initialized at level {}", level);
-    });
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some((_, file)) = &mut state.file {
+                let _ = file.flush();
+            }
+        }
+    }
 }
 
-pub fn info(msg: &str) {
-    println!("{} {}", "INFO".green(), msg);
+/// A record as text: timestamp, level, shortened module and message
+fn text_line(record: &Record, color: bool) -> String {
+    let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let level_str = match record.level() {
+        Level::Error => "ERROR".red().bold(),
+        Level::Warn => "WARN ".yellow().bold(),
+        Level::Info => "INFO ".green(),
+        Level::Debug => "DEBUG".blue(),
+        Level::Trace => "TRACE".magenta(),
+    };
+
+    let target = if let Some(target) = record.module_path() {
+        if target.contains("::") {
+            let parts: Vec<&str> = target.split("::").collect();
+            let prefix = parts[0];
+            let suffix = parts.last().unwrap_or(&"");
+            format!("{}.{}", prefix, suffix)
+        } else {
+            target.to_string()
+        }
+    } else {
+        "pocket".to_string()
+    };
+
+    if color {
+        format!("{} {} {} > {}", timestamp.dimmed(), level_str, target.dimmed(), record.args())
+    } else {
+        format!("{} {:<5} {} > {}", timestamp, record.level(), target, record.args())
+    }
 }
 
-pub fn success(msg: &str) {
-    println!("{} {}", "SUCCESS".green().bold(), msg);
+/// A record as a JSON object on one line
+fn json_line(record: &Record) -> String {
+    serde_json::json!({
+        "time": Utc::now().to_rfc3339(),
+        "level": record.level().to_string(),
+        "target": record.target(),
+        "module": record.module_path(),
+        "file": record.file(),
+        "line": record.line(),
+        "message": record.args().to_string(),
+        "pid": std::process::id(),
+    }).to_string()
 }
 
-pub fn warning(msg: &str) {
-    println!("{} {}", "WARNING".yellow().bold(), msg);
+/// Shifts `pocket.log` to `pocket.log.1` and so on, dropping the oldest,
+/// and starts a fresh file
+fn rotate(config: &LogFile) -> std::io::Result<File> {
+    let rotated = |n: usize| PathBuf::from(format!("{}.{}", config.path.display(), n));
+    if config.keep == 0 {
+        let _ = fs::remove_file(&config.path);
+    } else {
+        let _ = fs::remove_file(rotated(config.keep));
+        for n in (1..config.keep).rev() {
+            let _ = fs::rename(rotated(n), rotated(n + 1));
+        }
+        let _ = fs::rename(&config.path, rotated(1));
+    }
+    open(&config.path)
 }
 
-pub fn error(msg: &str) {
-    eprintln!("{} {}", "ERROR".red().bold(), msg);
+fn open(path: &std::path::Path) -> std::io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+pub fn init(level: LevelFilter) {
+    INIT.call_once(|| {
+        let _ = log::set_logger(&LOGGER);
+    });
+    
+    // Later calls adjust the level, e.g. once the command line has been parsed
+    if let Ok(mut state) = LOGGER.state.lock() {
+        state.stderr_level = level;
+    }
+    update_max_level();
+    log::debug!("Logger set to level {}", level);
+}
+
+/// Picks the format records are written in
+pub fn set_format(format: LogFormat) {
+    if let Ok(mut state) = LOGGER.state.lock() {
+        state.format = format;
+    }
+}
+
+/// Starts writing records to a log file as well, rotating it first if it's
+/// already full
+pub fn set_file(config: LogFile) -> std::io::Result<()> {
+    let full = fs::metadata(&config.path).is_ok_and(|metadata| metadata.len() >= config.max_bytes);
+    let file = if full { rotate(&config)? } else { open(&config.path)? };
+    if let Ok(mut state) = LOGGER.state.lock() {
+        state.file = Some((config, file));
+    }
+    update_max_level();
+    Ok(())
 }
 
-pub fn _cmd_text(cmd: &str, args: &[&str]) -> ColoredString {
-    format!("{} {}", cmd, args.join(" ")).cyan()
+/// Lets through the most detailed records any output wants
+fn update_max_level() {
+    if let Ok(state) = LOGGER.state.lock() {
+        let file_level = state.file.as_ref().map(|(config, _)| config.level).unwrap_or(LevelFilter::Off);
+        log::set_max_level(state.stderr_level.max(file_level));
+    }
 }
 
-pub fn header(text: &str) -> ColoredString {
-    text.blue().bold()
+/// Prints a message for people. With `--quiet` or `--output json` stdout
+/// is kept for what scripts capture, so these go to stderr instead
+fn say(label: ColoredString, msg: &str) {
+    if crate::utils::progress::is_quiet() || crate::utils::output::is_json() {
+        eprintln!("{} {}", label, msg);
+    } else {
+        println!("{} {}", label, msg);
+    }
 }
 
-pub fn key(text: &str) -> ColoredString {
-    text.yellow()
+pub fn info(msg: &str) {
+    say("INFO".green(), msg);
 }
 
-pub fn _value(text: &str) -> ColoredString {
-    text.white()
+pub fn success(msg: &str) {
+    say("SUCCESS".green().bold(), msg);
 }
 
-pub fn _path(text: &str) -> ColoredString {
-    text.underline().white()
+pub fn warning(msg: &str) {
+    say("WARNING".yellow().bold(), msg);
 }
 
-pub fn _id(text: &str) -> ColoredString {
-    text.green()
+pub fn error(msg: &str) {
+    eprintln!("{} {}", "ERROR".red().bold(), msg);
 }
 
-pub fn title(text: &str) -> ColoredString {
-    text.cyan().bold()
-} 
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_keeps_the_newest_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = LogFile {
+            path: dir.path().join("logs/pocket.log"),
+            level: LevelFilter::Info,
+            max_bytes: 10,
+            keep: 2,
+        };
+        for generation in ["first", "second", "third"] {
+            let mut file = open(&config.path).unwrap();
+            writeln!(file, "{}", generation).unwrap();
+            rotate(&config).unwrap();
+        }
+
+        let read = |name: &str| fs::read_to_string(dir.path().join("logs").join(name)).unwrap();
+        assert_eq!(read("pocket.log"), "");
+        assert_eq!(read("pocket.log.1"), "third\n");
+        assert_eq!(read("pocket.log.2"), "second\n");
+        assert!(!dir.path().join("logs/pocket.log.3").exists());
+    }
+
+    #[test]
+    fn json_records_are_one_object_per_line() {
+        let line = json_line(&Record::builder()
+            .level(Level::Warn)
+            .target("pocket_cli::storage")
+            .args(format_args!("disk {}", "full"))
+            .build());
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["level"], "WARN");
+        assert_eq!(value["message"], "disk full");
+        assert_eq!(value["target"], "pocket_cli::storage");
+    }
+}
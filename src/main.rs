@@ -1,30 +1,19 @@
-mod cards;
-mod cli;
-mod config;
-mod errors;
-mod logging;
-mod models;
-mod search;
-mod storage;
-mod utils;
-mod version;
-
-use cli::Cli;
-use clap::Parser;
-use errors::PocketResult;
+use pocket_cli::cli;
+use pocket_cli::logging;
+use pocket_cli::utils::output;
 use std::process;
 use log::error;
 
 fn main() {
-    let cli = Cli::parse();
-    
-    if let Err(err) = run_app(cli) {
-        error!("Error: {}", err);
-        logging::error(&format!("{}", err));
-        process::exit(1);
+    // Parses the command line, including commands provided by cards
+    if let Err(err) = cli::handler::run() {
+        // Printed below as JSON or for people; the log file keeps its own copy
+        error!(target: logging::FILE_ONLY, "Error: {}", err);
+        if output::is_json() {
+            eprintln!("{}", err.to_json());
+        } else {
+            logging::error(&format!("{}", err));
+        }
+        process::exit(err.code().exit_code());
     }
 }
-
-fn run_app(cli: Cli) -> PocketResult<()> {
-    cli::handler::handle_command(cli)
-}
@@ -1,8 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::logging::LogFormat;
+use std::collections::{BTreeMap, HashMap};
 
 /// Represents an entry in the pocket storage
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,9 +28,33 @@ pub struct Entry {
     /// Type of content (code, text, etc.)
     pub content_type: ContentType,
     
+    /// Detected programming language, if any
+    #[serde(default)]
+    pub language: Option<String>,
+    
     /// Metadata associated with the entry
     #[serde(default)]
     pub metadata: HashMap<String, String>,
+    
+    /// Number of times the entry has been shown, inserted, copied, or executed
+    #[serde(default)]
+    pub usage_count: u64,
+    
+    /// When the entry was last used
+    #[serde(default)]
+    pub last_used: Option<DateTime<Utc>>,
+    
+    /// Whether the entry is pinned to the top of listings
+    #[serde(default)]
+    pub pinned: bool,
+    
+    /// Hash of the whitespace-normalized content, used to spot duplicates
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    
+    /// MIME type of binary content (None for text entries)
+    #[serde(default)]
+    pub mime_type: Option<String>,
 }
 
 /// Represents the type of content in an entry
@@ -55,27 +79,105 @@ pub struct Backpack {
     pub created_at: DateTime<Utc>,
 }
 
-/// Represents a saved workflow
+/// A user-assigned name that resolves to an entry
 #[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntryAlias {
+    /// ID of the entry the alias points to
+    pub id: String,
+
+    /// Backpack the entry lives in (None for the general pocket)
+    #[serde(default)]
+    pub backpack: Option<String>,
+}
+
+/// Represents a saved workflow
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Workflow {
     /// Name of the workflow
+    #[serde(default)]
     pub name: String,
     
-    /// Commands in the workflow
-    pub commands: Vec<WorkflowCommand>,
+    /// Default values for `{{variables}}` used in the steps
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    
+    /// Steps in the workflow, run in order
+    #[serde(alias = "commands")]
+    pub steps: Vec<WorkflowStep>,
     
     /// When the workflow was created
+    #[serde(default = "Utc::now")]
     pub created_at: DateTime<Utc>,
 }
 
-/// Represents a command in a workflow
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct WorkflowCommand {
-    /// The command to execute
-    pub command: String,
+/// Represents a step in a workflow
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct WorkflowStep {
+    /// Command line to run, such as `insert {{found}} src/main.rs`
+    pub run: String,
+    
+    /// Variable to keep the step's output in
+    #[serde(default, rename = "as", skip_serializing_if = "Option::is_none")]
+    pub save_as: Option<String>,
+    
+    /// Only run if this variable is set and not empty
+    #[serde(default, rename = "if", skip_serializing_if = "Option::is_none")]
+    pub if_set: Option<String>,
+    
+    /// Skip if this variable is set and not empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unless: Option<String>,
+    
+    /// What to do when the step fails
+    #[serde(default, skip_serializing_if = "OnError::is_abort")]
+    pub on_error: OnError,
     
-    /// Arguments for the command
-    pub args: Vec<String>,
+    /// How many more times to try with `on_error = "retry"` (default 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    
+    /// Seconds the step may run for, for steps that run a process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<f64>,
+}
+
+/// What a workflow does when a step fails
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    /// Stop the workflow
+    #[default]
+    Abort,
+    /// Carry on with the next step
+    Continue,
+    /// Run the step again, then stop if it still fails
+    Retry,
+}
+
+impl OnError {
+    fn is_abort(&self) -> bool {
+        *self == OnError::Abort
+    }
+}
+
+/// When a saved workflow runs by itself: on a cron schedule while
+/// `pocket daemon` is running, or whenever an event happens
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorkflowSchedule {
+    /// Name of the saved workflow
+    pub workflow: String,
+    
+    /// Cron expression (minute, hour, day of month, month, day of week)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    
+    /// Event that sets the workflow off, like `entry_added`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on: Option<String>,
+    
+    /// Only events in this backpack set it off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backpack: Option<String>,
 }
 
 impl Entry {
@@ -90,10 +192,32 @@ impl Entry {
             source,
             tags,
             content_type,
+            language: None,
             metadata: HashMap::new(),
+            usage_count: 0,
+            last_used: None,
+            pinned: false,
+            content_hash: None,
+            mime_type: None,
         }
     }
     
+    /// Record that the entry was changed
+    pub fn touch(&mut self) {
+        self.updated_at = Utc::now();
+    }
+    
+    /// Record that the entry was used
+    pub fn record_use(&mut self) {
+        self.usage_count += 1;
+        self.last_used = Some(Utc::now());
+    }
+    
+    /// Whether the entry holds binary content rather than text
+    pub fn is_binary(&self) -> bool {
+        self.mime_type.is_some()
+    }
+    
     /// Add metadata to the entry
     pub fn add_metadata(&mut self, key: &str, value: &str) {
         self.metadata.insert(key.to_string(), value.to_string());
@@ -117,36 +241,31 @@ impl Backpack {
 }
 
 impl Workflow {
-    /// Create a new workflow (unused)
-    pub fn _new(name: String, commands: Vec<WorkflowCommand>) -> Self {
+    /// Create a new workflow
+    pub fn new(name: String, steps: Vec<WorkflowStep>) -> Self {
         Self {
             name,
-            commands,
+            variables: BTreeMap::new(),
+            steps,
             created_at: Utc::now(),
         }
     }
 }
 
-impl WorkflowCommand {
-    /// Parse a command string into a WorkflowCommand (unused)
-    pub fn _parse(command_str: &str) -> Result<Self> {
-        let command_str = command_str.trim();
-        if command_str.is_empty() {
-            return Err(anyhow::anyhow!("Empty command"));
-        }
-        
-        let parts: Vec<&str> = command_str.split_whitespace().collect();
-        
-        Ok(Self {
-            command: parts[0].to_string(),
-            args: parts[1..].iter().map(|s| s.to_string()).collect(),
-        })
+impl WorkflowStep {
+    /// A plain step that runs a command line
+    pub fn new(run: impl Into<String>) -> Self {
+        Self { run: run.into(), ..Default::default() }
     }
 }
 
 /// Configuration for the pocket application
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
+    /// Where the data lives, when this is the XDG config file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
+    
     /// User preferences
     pub user: UserConfig,
     
@@ -158,6 +277,34 @@ pub struct Config {
     
     /// Extension settings
     pub extensions: ExtensionConfig,
+    
+    /// Script execution settings
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    
+    /// Language model used for summaries
+    #[serde(default)]
+    pub llm: LlmConfig,
+    
+    /// Paste services used by `pocket share`
+    #[serde(default)]
+    pub share: ShareConfig,
+    
+    /// Log output and the log file
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    
+    /// Checks run on snippets as they're added
+    #[serde(default)]
+    pub lint: LintConfig,
+    
+    /// How pocket reaches the clipboard
+    #[serde(default)]
+    pub clipboard: ClipboardConfig,
+    
+    /// Shortcuts for pocket commands, set with `pocket alias set`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
 }
 
 /// User configuration
@@ -178,16 +325,81 @@ pub struct DisplayConfig {
     
     /// Tree style (unicode, ascii, or minimal)
     pub tree_style: TreeStyle,
+    
+    /// Page `pocket show` output longer than the terminal
+    #[serde(default = "default_pager")]
+    pub pager: bool,
+    
+    /// Pager to use instead of $PAGER (less -FRX when neither is set)
+    #[serde(default)]
+    pub pager_command: Option<String>,
+    
+    /// Language to print messages in, like "es" (default: $LANG)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+fn default_pager() -> bool {
+    true
 }
 
 /// Search configuration
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchConfig {
-    /// Search algorithm (semantic or literal)
+    /// Search algorithm (Semantic, Literal or Fuzzy)
     pub algorithm: SearchAlgorithm,
     
     /// Maximum number of search results
     pub max_results: usize,
+    
+    /// Hours package search results stay cached (24 when unset)
+    #[serde(default)]
+    pub package_cache_hours: Option<u64>,
+    
+    /// Threads for searching and hashing entries (one per CPU when unset)
+    #[serde(default)]
+    pub jobs: Option<usize>,
+    
+    /// How much each part of a result's score counts
+    #[serde(default)]
+    pub ranking: RankingConfig,
+}
+
+/// Weights of the parts of a search result's score. Each part is from 0
+/// to 1 before it's weighted
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RankingConfig {
+    /// The search terms in the title
+    pub title: f64,
+    
+    /// The search terms in the content
+    pub content: f64,
+    
+    /// The search terms among the tags
+    pub tags: f64,
+    
+    /// Changed or used lately
+    pub recency: f64,
+    
+    /// Used often
+    pub usage: f64,
+    
+    /// From the project the search is run in, or in its languages
+    pub project: f64,
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            title: 3.0,
+            content: 1.0,
+            tags: 2.0,
+            recency: 1.0,
+            usage: 1.0,
+            project: 2.0,
+        }
+    }
 }
 
 /// Extension configuration
@@ -195,6 +407,220 @@ pub struct SearchConfig {
 pub struct ExtensionConfig {
     /// Auto-reload extensions when they change
     pub auto_reload: bool,
+    
+    /// URL or path of the card registry index used by `pocket cards search/install`
+    #[serde(default)]
+    pub registry: Option<String>,
+}
+
+/// Script execution configuration
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExecutionConfig {
+    /// What `pocket execute` does with scripts not tagged `trusted`
+    #[serde(default)]
+    pub untrusted: UntrustedPolicy,
+}
+
+/// How to treat a script that isn't tagged `trusted`
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum UntrustedPolicy {
+    /// Run it
+    #[default]
+    Allow,
+    
+    /// Ask first, and refuse when there's no terminal to ask on
+    Confirm,
+    
+    /// Refuse to run it
+    Deny,
+}
+
+/// Logging configuration
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct LoggingConfig {
+    /// Always write logs to a file, not just with `--log-file`
+    #[serde(default)]
+    pub file: bool,
+    
+    /// Where the log file goes (logs/pocket.log in the pocket directory when unset)
+    #[serde(default)]
+    pub path: Option<String>,
+    
+    /// Format of log output (text when unset)
+    #[serde(default)]
+    pub format: Option<LogFormat>,
+    
+    /// Most detailed level written to the log file (info when unset)
+    #[serde(default)]
+    pub level: Option<String>,
+    
+    /// Size in KiB the log file rotates at (1024 when unset)
+    #[serde(default)]
+    pub max_size_kb: Option<u64>,
+    
+    /// Rotated log files kept (3 when unset)
+    #[serde(default)]
+    pub keep: Option<usize>,
+}
+
+/// Checks run on snippets as they're added
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LintConfig {
+    /// Lines of code ending in spaces or tabs
+    pub trailing_whitespace: LintLevel,
+    
+    /// API keys, tokens, passwords and private keys, in any entry
+    pub secrets: LintLevel,
+    
+    /// Lines of code longer than `max_line_length`
+    pub long_lines: LintLevel,
+    
+    /// Characters a line of code can have before it's too long
+    pub max_line_length: usize,
+    
+    /// Code no language was detected for
+    pub missing_language: LintLevel,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            trailing_whitespace: LintLevel::Warn,
+            secrets: LintLevel::Block,
+            long_lines: LintLevel::Warn,
+            max_line_length: 160,
+            missing_language: LintLevel::Warn,
+        }
+    }
+}
+
+/// What happens when a lint check finds something
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    /// Don't run the check
+    Off,
+    
+    /// Say so, and add the entry anyway
+    #[default]
+    Warn,
+    
+    /// Refuse to add the entry
+    Block,
+}
+
+/// Clipboard configuration
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    /// Tool used to reach the clipboard (auto tries each that fits the system)
+    pub provider: ClipboardProvider,
+}
+
+/// A way to reach the clipboard
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardProvider {
+    /// Whichever of the others works here, in the order that suits the system
+    #[default]
+    Auto,
+    
+    /// wl-copy and wl-paste, on Wayland
+    WlClipboard,
+    
+    /// xclip, on X11
+    Xclip,
+    
+    /// xsel, on X11
+    Xsel,
+    
+    /// pbcopy and pbpaste, on macOS
+    Pbcopy,
+    
+    /// PowerShell's Set-Clipboard and Get-Clipboard, on Windows
+    Windows,
+    
+    /// The OSC 52 escape sequence, which has the terminal set the clipboard,
+    /// even over SSH. Copy only
+    Osc52,
+}
+
+/// Language model configuration
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct LlmConfig {
+    /// Which backend to use
+    #[serde(default)]
+    pub backend: LlmBackend,
+    
+    /// Model name (each backend has a default)
+    #[serde(default)]
+    pub model: Option<String>,
+    
+    /// Base URL of the API (each backend has a default)
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    
+    /// Environment variable holding the API key (each backend has a default)
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+/// Where summaries come from
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmBackend {
+    /// Picks out the key sentences locally, with no model at all
+    #[default]
+    Heuristic,
+    
+    /// A local Ollama server
+    Ollama,
+    
+    /// OpenAI, or any server with an OpenAI-compatible chat API
+    OpenAi,
+    
+    /// Anthropic's messages API
+    Anthropic,
+}
+
+/// Paste service configuration
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ShareConfig {
+    /// Provider used when `--to` isn't given (paste.rs unless set)
+    #[serde(default)]
+    pub default_provider: Option<String>,
+    
+    /// Custom providers, by name
+    #[serde(default)]
+    pub providers: BTreeMap<String, CustomShareProvider>,
+}
+
+/// A paste service of your own, set up under `[share.providers.NAME]`
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CustomShareProvider {
+    /// Where content is POSTed
+    pub url: String,
+    
+    /// Send the content as this multipart form field instead of as the raw body
+    #[serde(default)]
+    pub field: Option<String>,
+    
+    /// Form field taking the hours until the paste expires, if the service has one
+    #[serde(default)]
+    pub expires_field: Option<String>,
+    
+    /// JSON pointer to the link in the answer (the whole answer is the link when unset)
+    #[serde(default)]
+    pub url_pointer: Option<String>,
+    
+    /// Environment variable holding a token, sent as `Authorization: Bearer`
+    #[serde(default)]
+    pub token_env: Option<String>,
+    
+    /// Whether sending DELETE to a paste's link removes it
+    #[serde(default)]
+    pub delete: bool,
 }
 
 /// Tree style for display
@@ -210,26 +636,43 @@ pub enum TreeStyle {
 pub enum SearchAlgorithm {
     Semantic,
     Literal,
+    /// Forgives typos, ranking the closest matches first
+    Fuzzy,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            data_dir: None,
             user: UserConfig {
-                editor: "vim".to_string(),
+                editor: if cfg!(windows) { "notepad" } else { "vim" }.to_string(),
                 default_backpack: "general".to_string(),
             },
             display: DisplayConfig {
                 color: true,
                 tree_style: TreeStyle::Unicode,
+                pager: true,
+                pager_command: None,
+                language: None,
             },
             search: SearchConfig {
                 algorithm: SearchAlgorithm::Semantic,
                 max_results: 10,
+                package_cache_hours: None,
+                jobs: None,
+                ranking: RankingConfig::default(),
             },
             extensions: ExtensionConfig {
                 auto_reload: true,
+                registry: None,
             },
+            execution: ExecutionConfig::default(),
+            llm: LlmConfig::default(),
+            share: ShareConfig::default(),
+            logging: LoggingConfig::default(),
+            lint: LintConfig::default(),
+            clipboard: ClipboardConfig::default(),
+            aliases: BTreeMap::new(),
         }
     }
 } 
\ No newline at end of file
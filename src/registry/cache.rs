@@ -0,0 +1,130 @@
+//! Package search results cached under `~/.pocket/cache/packages/`, one
+//! JSON file per registry and query, so repeated searches don't wait on
+//! the network and still work without it
+
+use super::{Package, Registry};
+use crate::storage;
+use crate::utils::atomic;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+
+/// Hours results stay fresh unless `search.package_cache_hours` says otherwise
+pub const DEFAULT_TTL_HOURS: u64 = 24;
+
+/// A registry's answer as it was cached
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedSearch {
+    pub registry: Registry,
+    pub query: String,
+    pub limit: usize,
+    pub fetched_at: DateTime<Utc>,
+    pub packages: Vec<Package>,
+}
+
+impl CachedSearch {
+    /// Whether it was fetched within `ttl` of `now`
+    pub fn is_fresh(&self, ttl: Duration, now: DateTime<Utc>) -> bool {
+        now - self.fetched_at < ttl
+    }
+}
+
+/// The package search cache
+pub struct PackageCache {
+    dir: PathBuf,
+}
+
+impl PackageCache {
+    /// The cache in `~/.pocket/cache/packages/`
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(storage::pocket_dir()?.join("cache").join("packages")))
+    }
+    
+    /// A cache in `dir`
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+    
+    /// The cached answer to a search, however old it is
+    pub fn get(&self, registry: Registry, query: &str, limit: usize) -> Option<CachedSearch> {
+        let text = fs::read_to_string(self.path(registry, query, limit)).ok()?;
+        // A file that doesn't parse is as good as no file
+        serde_json::from_str(&text).ok()
+    }
+    
+    /// Caches the answer to a search
+    pub fn put(&self, registry: Registry, query: &str, limit: usize, packages: &[Package]) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let cached = CachedSearch {
+            registry,
+            query: query.to_string(),
+            limit,
+            fetched_at: Utc::now(),
+            packages: packages.to_vec(),
+        };
+        atomic::write(self.path(registry, query, limit), serde_json::to_string_pretty(&cached)?)?;
+        Ok(())
+    }
+    
+    /// Deletes every cached search, returning how many there were
+    pub fn clear(&self) -> Result<usize> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+        let mut cleared = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                fs::remove_file(&path)?;
+                cleared += 1;
+            }
+        }
+        Ok(cleared)
+    }
+    
+    /// The file a search is cached in. Queries differing only in
+    /// surrounding space share one
+    fn path(&self, registry: Registry, query: &str, limit: usize) -> PathBuf {
+        let key = format!("{:?}\n{}\n{}", registry, query.trim(), limit);
+        let hash = format!("{:x}", Sha256::digest(key.as_bytes()));
+        self.dir.join(format!("{:?}-{}.json", registry, &hash[..16]).to_lowercase())
+    }
+}
+
+/// How long cached results stay fresh, from the configured hours
+pub fn ttl(hours: Option<u64>) -> Duration {
+    Duration::hours(hours.unwrap_or(DEFAULT_TTL_HOURS) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn package(name: &str) -> Package {
+        Package { registry: Registry::Npm, name: name.to_string(), version: None, description: None, url: None, downloads: None }
+    }
+    
+    #[test]
+    fn caches_searches_until_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::at(dir.path().join("packages"));
+        assert!(cache.get(Registry::Npm, "pad", 10).is_none());
+        assert_eq!(cache.clear().unwrap(), 0);
+        
+        cache.put(Registry::Npm, "pad", 10, &[package("left-pad")]).unwrap();
+        let cached = cache.get(Registry::Npm, " pad ", 10).unwrap();
+        assert_eq!(cached.packages, vec![package("left-pad")]);
+        assert!(cached.is_fresh(ttl(None), Utc::now()));
+        assert!(!cached.is_fresh(ttl(Some(1)), Utc::now() + Duration::hours(2)));
+        
+        // Other registries and limits are other searches
+        assert!(cache.get(Registry::Crates, "pad", 10).is_none());
+        assert!(cache.get(Registry::Npm, "pad", 20).is_none());
+        
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.get(Registry::Npm, "pad", 10).is_none());
+    }
+}
@@ -0,0 +1,369 @@
+//! Package registries: looking up packages in the official JSON APIs of
+//! crates.io, npm, PyPI, the Go module proxy, Maven Central and Packagist
+//!
+//! crates.io, npm, Maven Central and Packagist can be searched. PyPI and the
+//! Go module proxy can't, so for them the query is looked up as an exact
+//! package name or module path.
+
+pub mod cache;
+pub mod project;
+
+use crate::utils::http;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Most time a registry gets to answer
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Files marking the root of a project, and the registry it gets packages from
+const MANIFESTS: [(&str, Registry); 10] = [
+    ("Cargo.toml", Registry::Crates),
+    ("package.json", Registry::Npm),
+    ("pyproject.toml", Registry::PyPi),
+    ("requirements.txt", Registry::PyPi),
+    ("setup.py", Registry::PyPi),
+    ("go.mod", Registry::Go),
+    ("pom.xml", Registry::Maven),
+    ("build.gradle", Registry::Maven),
+    ("build.gradle.kts", Registry::Maven),
+    ("composer.json", Registry::Packagist),
+];
+
+/// A package registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Registry {
+    Crates,
+    Npm,
+    PyPi,
+    Go,
+    Maven,
+    Packagist,
+}
+
+/// A package found in a registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Package {
+    pub registry: Registry,
+    pub name: String,
+    /// Latest version, when the registry says
+    pub version: Option<String>,
+    pub description: Option<String>,
+    /// Where to read more about it
+    pub url: Option<String>,
+    pub downloads: Option<u64>,
+}
+
+impl Registry {
+    /// Parses a registry name, or the name of its language's ecosystem
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "crates" | "crates.io" | "cargo" | "rust" => Ok(Self::Crates),
+            "npm" | "node" | "js" | "javascript" | "typescript" => Ok(Self::Npm),
+            "pypi" | "pip" | "python" | "py" => Ok(Self::PyPi),
+            "go" | "golang" => Ok(Self::Go),
+            "maven" | "java" | "kotlin" => Ok(Self::Maven),
+            "packagist" | "composer" | "php" => Ok(Self::Packagist),
+            _ => Err(anyhow!("Unknown registry: {} (expected crates, npm, pypi, go, maven or packagist)", name)),
+        }
+    }
+    
+    /// The registry of the biggest ecosystem in the project around `dir`
+    pub fn detect(dir: &Path) -> Option<Self> {
+        project::detect(dir).first().map(|ecosystem| ecosystem.registry)
+    }
+    
+    /// The project `dir` is in: the registry it uses and the directory
+    /// holding its manifest
+    pub fn find_project(dir: &Path) -> Option<(Self, PathBuf)> {
+        dir.ancestors().find_map(|dir| {
+            MANIFESTS.iter()
+                .find(|(file, _)| dir.join(file).is_file())
+                .map(|(_, registry)| (*registry, dir.to_path_buf()))
+        })
+    }
+    
+    /// The nearest directory from `dir` up holding a manifest for this
+    /// registry, for projects that use more than one
+    pub fn project_dir(self, dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .find(|dir| MANIFESTS.iter().any(|(file, registry)| *registry == self && dir.join(file).is_file()))
+            .map(Path::to_path_buf)
+    }
+    
+    /// The command adding a package to the project in `project`, picking the
+    /// package manager its lockfile belongs to. None for Maven, whose
+    /// dependencies are added by editing the build file
+    pub fn install_command(self, package: &Package, project: &Path) -> Option<Vec<String>> {
+        let has = |file: &str| project.join(file).is_file();
+        let name = package.name.clone();
+        let command: Vec<&str> = match self {
+            Self::Crates => vec!["cargo", "add"],
+            Self::Npm if has("pnpm-lock.yaml") => vec!["pnpm", "add"],
+            Self::Npm if has("yarn.lock") => vec!["yarn", "add"],
+            Self::Npm if has("bun.lockb") || has("bun.lock") => vec!["bun", "add"],
+            Self::Npm => vec!["npm", "install"],
+            Self::PyPi if has("uv.lock") => vec!["uv", "add"],
+            Self::PyPi if has("poetry.lock") => vec!["poetry", "add"],
+            Self::PyPi => vec!["pip", "install"],
+            Self::Go => vec!["go", "get"],
+            Self::Maven => return None,
+            Self::Packagist => vec!["composer", "require"],
+        };
+        let spec = match (&package.version, self) {
+            (None, _) => name,
+            (Some(version), Self::Crates | Self::Npm | Self::Go) => format!("{}@{}", name, version),
+            (Some(version), Self::PyPi) => format!("{}=={}", name, version),
+            (Some(version), Self::Packagist) => format!("{}:{}", name, version),
+            (Some(_), Self::Maven) => name,
+        };
+        Some(command.into_iter().map(str::to_string).chain([spec]).collect())
+    }
+    
+    /// The `pom.xml` dependency for a Maven package
+    pub fn maven_dependency(package: &Package) -> String {
+        let (group, artifact) = package.name.split_once(':').unwrap_or((&package.name, &package.name));
+        let version = package.version.as_deref()
+            .map(|version| format!("\n    <version>{}</version>", version))
+            .unwrap_or_default();
+        format!("<dependency>\n    <groupId>{}</groupId>\n    <artifactId>{}</artifactId>{}\n</dependency>", group, artifact, version)
+    }
+    
+    /// Searches the registry for up to `limit` packages matching `query`
+    pub fn search(self, query: &str, limit: usize) -> Result<Vec<Package>> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(anyhow!("Nothing to search for"));
+        }
+        let url = self.search_url(query, limit);
+        match http::get_json(&url, TIMEOUT) {
+            Ok(json) => {
+                let mut packages = self.parse_response(query, &json)?;
+                packages.truncate(limit);
+                Ok(packages)
+            },
+            // Lookups by name answer 404 when there's no such package
+            Err(e) if self.looks_up_by_name() && e.to_string().contains("answered 404") => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Whether the registry only finds packages by their exact name
+    pub fn looks_up_by_name(self) -> bool {
+        matches!(self, Self::PyPi | Self::Go)
+    }
+    
+    /// The API address answering a search
+    fn search_url(self, query: &str, limit: usize) -> String {
+        let encoded: String = url::form_urlencoded::byte_serialize(query.as_bytes()).collect();
+        match self {
+            Self::Crates => format!("https://crates.io/api/v1/crates?q={}&per_page={}", encoded, limit),
+            Self::Npm => format!("https://registry.npmjs.org/-/v1/search?text={}&size={}", encoded, limit),
+            Self::PyPi => format!("https://pypi.org/pypi/{}/json", encoded),
+            Self::Go => format!("https://proxy.golang.org/{}/@latest", escape_module_path(query)),
+            Self::Maven => format!("https://search.maven.org/solrsearch/select?q={}&rows={}&wt=json", encoded, limit),
+            Self::Packagist => format!("https://packagist.org/search.json?q={}&per_page={}", encoded, limit),
+        }
+    }
+    
+    /// Reads the packages out of a registry's answer to `query`
+    pub fn parse_response(self, query: &str, json: &Value) -> Result<Vec<Package>> {
+        let packages = match self {
+            Self::Crates => items(json, "/crates")?.iter()
+                .map(|krate| Package {
+                    name: string(krate, "/name"),
+                    version: optional(krate, "/max_stable_version").or_else(|| optional(krate, "/max_version")),
+                    description: optional(krate, "/description"),
+                    url: Some(format!("https://crates.io/crates/{}", string(krate, "/name"))),
+                    downloads: krate.pointer("/downloads").and_then(Value::as_u64),
+                    ..self.package()
+                })
+                .collect(),
+            Self::Npm => items(json, "/objects")?.iter()
+                .map(|object| Package {
+                    name: string(object, "/package/name"),
+                    version: optional(object, "/package/version"),
+                    description: optional(object, "/package/description"),
+                    url: optional(object, "/package/links/npm"),
+                    ..self.package()
+                })
+                .collect(),
+            Self::PyPi => vec![Package {
+                name: string(json, "/info/name"),
+                version: optional(json, "/info/version"),
+                description: optional(json, "/info/summary"),
+                url: optional(json, "/info/package_url"),
+                ..self.package()
+            }],
+            // The proxy only answers with the version of the module asked for
+            Self::Go => vec![Package {
+                name: query.to_string(),
+                version: optional(json, "/Version"),
+                url: Some(format!("https://pkg.go.dev/{}", query)),
+                ..self.package()
+            }],
+            Self::Maven => items(json, "/response/docs")?.iter()
+                .map(|doc| Package {
+                    name: string(doc, "/id"),
+                    version: optional(doc, "/latestVersion"),
+                    url: Some(format!("https://central.sonatype.com/artifact/{}/{}", string(doc, "/g"), string(doc, "/a"))),
+                    ..self.package()
+                })
+                .collect(),
+            Self::Packagist => items(json, "/results")?.iter()
+                .map(|result| Package {
+                    name: string(result, "/name"),
+                    description: optional(result, "/description"),
+                    url: optional(result, "/url"),
+                    downloads: result.pointer("/downloads").and_then(Value::as_u64),
+                    ..self.package()
+                })
+                .collect(),
+        };
+        Ok(packages.into_iter().filter(|package| !package.name.is_empty()).collect())
+    }
+    
+    /// An empty package from this registry
+    fn package(self) -> Package {
+        Package { registry: self, name: String::new(), version: None, description: None, url: None, downloads: None }
+    }
+}
+
+impl fmt::Display for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Crates => "crates.io",
+            Self::Npm => "npm",
+            Self::PyPi => "PyPI",
+            Self::Go => "Go",
+            Self::Maven => "Maven Central",
+            Self::Packagist => "Packagist",
+        })
+    }
+}
+
+/// The array at `pointer`, which the answer has to have
+fn items<'a>(json: &'a Value, pointer: &str) -> Result<&'a Vec<Value>> {
+    json.pointer(pointer)
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("Unexpected answer from the registry (no {})", pointer))
+}
+
+/// The non-empty string at `pointer`
+fn optional(json: &Value, pointer: &str) -> Option<String> {
+    json.pointer(pointer)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// The string at `pointer`, or an empty one
+fn string(json: &Value, pointer: &str) -> String {
+    optional(json, pointer).unwrap_or_default()
+}
+
+/// Escapes a module path the way the Go module proxy wants: capital
+/// letters become `!` and the lowercase letter
+fn escape_module_path(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            escaped.push('!');
+            escaped.push(c.to_ascii_lowercase());
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    
+    #[test]
+    fn parses_search_answers() {
+        let crates = json!({ "crates": [
+            { "name": "serde", "max_version": "1.0.200-rc", "max_stable_version": "1.0.199", "description": "A serialization framework", "downloads": 42 },
+        ]});
+        let packages = Registry::Crates.parse_response("serde", &crates).unwrap();
+        assert_eq!(packages[0].name, "serde");
+        assert_eq!(packages[0].version.as_deref(), Some("1.0.199"));
+        assert_eq!(packages[0].downloads, Some(42));
+        
+        let npm = json!({ "objects": [
+            { "package": { "name": "left-pad", "version": "1.3.0", "links": { "npm": "https://www.npmjs.com/package/left-pad" } } },
+        ]});
+        let packages = Registry::Npm.parse_response("pad", &npm).unwrap();
+        assert_eq!(packages[0].url.as_deref(), Some("https://www.npmjs.com/package/left-pad"));
+        assert_eq!(packages[0].description, None);
+        
+        let maven = json!({ "response": { "docs": [
+            { "id": "com.google.guava:guava", "g": "com.google.guava", "a": "guava", "latestVersion": "33.0.0-jre" },
+        ]}});
+        let packages = Registry::Maven.parse_response("guava", &maven).unwrap();
+        assert_eq!(packages[0].name, "com.google.guava:guava");
+        assert_eq!(packages[0].url.as_deref(), Some("https://central.sonatype.com/artifact/com.google.guava/guava"));
+        
+        let packagist = json!({ "results": [{ "name": "monolog/monolog", "description": "", "downloads": 7 }] });
+        let packages = Registry::Packagist.parse_response("monolog", &packagist).unwrap();
+        assert_eq!(packages[0].description, None);
+        assert_eq!(packages[0].version, None);
+        
+        assert!(Registry::Crates.parse_response("serde", &json!({ "errors": [] })).is_err());
+    }
+    
+    #[test]
+    fn parses_lookup_answers() {
+        let pypi = json!({ "info": { "name": "requests", "version": "2.31.0", "summary": "Python HTTP for Humans." } });
+        let packages = Registry::PyPi.parse_response("requests", &pypi).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].version.as_deref(), Some("2.31.0"));
+        
+        let go = json!({ "Version": "v1.9.1", "Time": "2023-06-01T00:00:00Z" });
+        let packages = Registry::Go.parse_response("github.com/gin-gonic/gin", &go).unwrap();
+        assert_eq!(packages[0].name, "github.com/gin-gonic/gin");
+        assert_eq!(packages[0].version.as_deref(), Some("v1.9.1"));
+        assert_eq!(escape_module_path("github.com/BurntSushi/toml"), "github.com/!burnt!sushi/toml");
+    }
+    
+    #[test]
+    fn detects_the_project_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Registry::detect(dir.path()), None);
+        std::fs::write(dir.path().join("go.mod"), "module example.com/app\n").unwrap();
+        let nested = dir.path().join("cmd/app");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(Registry::detect(&nested), Some(Registry::Go));
+        
+        assert_eq!(Registry::find_project(&nested), Some((Registry::Go, dir.path().to_path_buf())));
+        assert_eq!(Registry::Go.project_dir(&nested), Some(dir.path().to_path_buf()));
+        assert_eq!(Registry::Npm.project_dir(&nested), None);
+        
+        assert_eq!(Registry::parse("composer").unwrap(), Registry::Packagist);
+        assert!(Registry::parse("cpan").is_err());
+    }
+    
+    #[test]
+    fn builds_install_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut package = Package { version: Some("1.3.0".to_string()), ..Registry::Npm.package() };
+        package.name = "left-pad".to_string();
+        assert_eq!(Registry::Npm.install_command(&package, dir.path()).unwrap(), ["npm", "install", "left-pad@1.3.0"]);
+        std::fs::write(dir.path().join("yarn.lock"), "").unwrap();
+        assert_eq!(Registry::Npm.install_command(&package, dir.path()).unwrap(), ["yarn", "add", "left-pad@1.3.0"]);
+        
+        package.version = None;
+        assert_eq!(Registry::PyPi.install_command(&package, dir.path()).unwrap(), ["pip", "install", "left-pad"]);
+        
+        let guava = Package { name: "com.google.guava:guava".to_string(), version: Some("33.0.0-jre".to_string()), ..Registry::Maven.package() };
+        assert!(Registry::Maven.install_command(&guava, dir.path()).is_none());
+        assert!(Registry::maven_dependency(&guava).contains("<artifactId>guava</artifactId>"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Working out which ecosystems a project uses. A web project can be Rust
+//! and JavaScript at once, so every ecosystem with a manifest is reported,
+//! ranked by how many of the project's source files belong to it
+//!
+//! Walking the project skips build output and dependencies, along with
+//! anything matched by the `.gitignore` or `.pocketignore` at its root.
+
+use super::{Registry, MANIFESTS};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Directories never worth walking into
+const IGNORED_DIRS: [&str; 11] = [
+    ".git", "node_modules", "target", "vendor", ".venv", "venv", "__pycache__", "dist", "build", ".gradle", ".idea",
+];
+
+/// How deep to look for manifests and source files
+const MAX_DEPTH: usize = 6;
+
+/// An ecosystem found in a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ecosystem {
+    pub registry: Registry,
+    /// The shallowest directory holding one of its manifests
+    pub dir: PathBuf,
+    /// Source files written in its language
+    pub files: usize,
+}
+
+/// Every ecosystem the project around `dir` uses, the biggest first
+pub fn detect(dir: &Path) -> Vec<Ecosystem> {
+    let root = project_root(dir);
+    let ignore = IgnoreRules::load(&root);
+    let mut manifests: HashMap<Registry, PathBuf> = HashMap::new();
+    let mut files: HashMap<Registry, usize> = HashMap::new();
+    
+    let walker = WalkDir::new(&root).max_depth(MAX_DEPTH).into_iter().filter_entry(|entry| {
+        let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+        let is_dir = entry.file_type().is_dir();
+        let skipped_dir = is_dir && entry.depth() > 0
+            && entry.file_name().to_str().is_some_and(|name| IGNORED_DIRS.contains(&name));
+        !skipped_dir && !ignore.is_ignored(relative, is_dir)
+    });
+    for entry in walker.filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        let name = entry.file_name().to_string_lossy();
+        if let Some((_, registry)) = MANIFESTS.iter().find(|(manifest, _)| *manifest == name) {
+            let dir = entry.path().parent().unwrap_or(&root).to_path_buf();
+            let shallower = manifests.get(registry).is_none_or(|known| dir.components().count() < known.components().count());
+            if shallower {
+                manifests.insert(*registry, dir);
+            }
+        }
+        if let Some(registry) = source_registry(entry.path()) {
+            *files.entry(registry).or_default() += 1;
+        }
+    }
+    
+    let mut ecosystems: Vec<Ecosystem> = manifests.into_iter()
+        .map(|(registry, dir)| Ecosystem { registry, files: files.get(&registry).copied().unwrap_or(0), dir })
+        .collect();
+    // Ties go to the ecosystem whose manifest is closest to the root
+    ecosystems.sort_by_key(|ecosystem| (std::cmp::Reverse(ecosystem.files), ecosystem.dir.components().count()));
+    ecosystems
+}
+
+/// The top of the project `dir` is in: the root of its git repository, or
+/// the nearest directory up with a manifest, or `dir` itself
+fn project_root(dir: &Path) -> PathBuf {
+    if let Some(repo) = dir.ancestors().find(|dir| dir.join(".git").exists()) {
+        return repo.to_path_buf();
+    }
+    dir.ancestors()
+        .find(|dir| MANIFESTS.iter().any(|(file, _)| dir.join(file).is_file()))
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+/// The registry whose packages a source file would use
+fn source_registry(path: &Path) -> Option<Registry> {
+    match path.extension()?.to_str()? {
+        "rs" => Some(Registry::Crates),
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "vue" | "svelte" => Some(Registry::Npm),
+        "py" => Some(Registry::PyPi),
+        "go" => Some(Registry::Go),
+        "java" | "kt" | "kts" | "scala" | "groovy" => Some(Registry::Maven),
+        "php" => Some(Registry::Packagist),
+        _ => None,
+    }
+}
+
+/// Patterns from `.gitignore` and `.pocketignore`, in the usual syntax:
+/// `*` globs, a trailing `/` for directories only, and a leading or inner
+/// `/` to match from the root. Negations aren't supported and are skipped
+#[derive(Debug, Default)]
+struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+#[derive(Debug)]
+struct IgnoreRule {
+    pattern: glob::Pattern,
+    anchored: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRules {
+    fn load(root: &Path) -> Self {
+        let text: String = [".gitignore", ".pocketignore"].iter()
+            .filter_map(|file| fs::read_to_string(root.join(file)).ok())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self::parse(&text)
+    }
+    
+    fn parse(text: &str) -> Self {
+        let rules = text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+            .filter_map(|line| {
+                let dir_only = line.ends_with('/');
+                let line = line.trim_end_matches('/');
+                let anchored = line.contains('/');
+                let pattern = glob::Pattern::new(line.trim_start_matches('/')).ok()?;
+                Some(IgnoreRule { pattern, anchored, dir_only })
+            })
+            .collect();
+        Self { rules }
+    }
+    
+    /// Whether a path relative to the root is ignored. Whatever's inside an
+    /// ignored directory is never walked into, so only the path itself counts
+    fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        let Some(name) = relative.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        let path = relative.to_string_lossy().replace('\\', "/");
+        self.rules.iter()
+            .filter(|rule| is_dir || !rule.dir_only)
+            .any(|rule| if rule.anchored { rule.pattern.matches(&path) } else { rule.pattern.matches(name) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn ranks_every_ecosystem_in_a_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::create_dir_all(root.join("web/src")).unwrap();
+        fs::write(root.join("web/package.json"), "{}").unwrap();
+        for name in ["app.ts", "api.ts"] {
+            fs::write(root.join("web/src").join(name), "export {}\n").unwrap();
+        }
+        // Dependencies and ignored files don't count
+        fs::create_dir_all(root.join("web/node_modules/dep")).unwrap();
+        fs::write(root.join("web/node_modules/dep/index.js"), "").unwrap();
+        fs::create_dir_all(root.join("scripts")).unwrap();
+        fs::write(root.join("scripts/setup.py"), "").unwrap();
+        fs::write(root.join(".pocketignore"), "scripts/\n").unwrap();
+        
+        let ecosystems = detect(&root.join("src"));
+        let found: Vec<(Registry, usize)> = ecosystems.iter().map(|e| (e.registry, e.files)).collect();
+        assert_eq!(found, vec![(Registry::Npm, 2), (Registry::Crates, 1)]);
+        assert_eq!(ecosystems[0].dir, root.join("web"));
+    }
+    
+    #[test]
+    fn matches_ignore_patterns() {
+        let rules = IgnoreRules::parse("# build output\n*.log\n/generated\ncache/\n!keep.log\n");
+        assert!(rules.is_ignored(Path::new("debug.log"), false));
+        assert!(rules.is_ignored(Path::new("deep/down/debug.log"), false));
+        assert!(rules.is_ignored(Path::new("generated"), true));
+        assert!(!rules.is_ignored(Path::new("src/generated"), true));
+        assert!(rules.is_ignored(Path::new("src/cache"), true));
+        assert!(!rules.is_ignored(Path::new("src/cache"), false));
+    }
+}
@@ -0,0 +1,66 @@
+//! Fuzzy matching for `pocket search --fuzzy`, so typos like "dokcer"
+//! still find "docker"
+//!
+//! A search term is lined up against the text with the Smith-Waterman
+//! local alignment: characters that agree score, while ones that don't, or
+//! that are missing or extra, cost. The best-scoring stretch of the text,
+//! out of what the term would score matched perfectly, says how well it
+//! matches.
+
+/// Score of a term matched this well or better counts as found
+pub const THRESHOLD: f64 = 0.65;
+
+/// Score for characters that agree
+const MATCH: i32 = 2;
+
+/// Cost of characters that don't
+const MISMATCH: i32 = -1;
+
+/// Cost of a character missing from one side
+const GAP: i32 = -1;
+
+/// How well `term` matches somewhere in `text`, from 0 (not at all) to 1
+/// (it's in there exactly). Both should already be lower-cased
+pub fn score(term: &str, text: &str) -> f64 {
+    let term: Vec<char> = term.chars().collect();
+    if term.is_empty() {
+        return 1.0;
+    }
+    let perfect = MATCH * term.len() as i32;
+
+    let mut previous = vec![0; term.len() + 1];
+    let mut current = vec![0; term.len() + 1];
+    let mut best = 0;
+    for c in text.chars() {
+        for j in 1..=term.len() {
+            let diagonal = previous[j - 1] + if term[j - 1] == c { MATCH } else { MISMATCH };
+            let cell = diagonal.max(previous[j] + GAP).max(current[j - 1] + GAP).max(0);
+            current[j] = cell;
+            best = best.max(cell);
+        }
+        if best == perfect {
+            break;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    f64::from(best) / f64::from(perfect)
+}
+
+/// Whether `term` is close enough to something in `text`
+pub fn matches(term: &str, text: &str) -> bool {
+    text.contains(term) || score(term, text) >= THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgives_typos() {
+        assert_eq!(score("compose", "docker compose up"), 1.0);
+        assert!(matches("dokcer", "docker compose up"));
+        assert!(matches("composr", "docker compose up"));
+        assert!(!matches("kubernetes", "docker compose up"));
+        assert!(score("dokcer", "docker") > score("dokcer", "deck"));
+    }
+}
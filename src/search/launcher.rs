@@ -0,0 +1,139 @@
+//! Search results in the formats app launchers read, so snippets can be
+//! looked up without opening a terminal
+//!
+//! - `alfred`: Script Filter JSON, `{"items": [...]}`
+//! - `raycast`: list items for a Raycast script or extension
+//! - `lines`: `ID<tab>title` per line, for dmenu, rofi and fzf
+
+use crate::models::{ContentType, Entry};
+use crate::utils::SummaryMetadata;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+
+/// A launcher's output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LauncherFormat {
+    Alfred,
+    Raycast,
+    Lines,
+}
+
+impl LauncherFormat {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "alfred" => Ok(Self::Alfred),
+            "raycast" => Ok(Self::Raycast),
+            "lines" => Ok(Self::Lines),
+            _ => Err(anyhow!("Unknown format: {} (expected alfred, raycast or lines)", name)),
+        }
+    }
+    
+    /// Renders search results, best first
+    pub fn render(self, results: &[(Entry, Option<String>)]) -> Result<String> {
+        match self {
+            Self::Alfred => {
+                let items: Vec<Value> = results.iter()
+                    .map(|(entry, backpack)| json!({
+                        "uid": entry.id,
+                        "title": entry.title,
+                        "subtitle": subtitle(entry, backpack.as_deref()),
+                        "arg": entry.id,
+                        "autocomplete": entry.title,
+                        "icon": { "type": "filetype", "path": alfred_icon(entry) },
+                    }))
+                    .collect();
+                Ok(serde_json::to_string(&json!({ "items": items }))?)
+            },
+            Self::Raycast => {
+                let items: Vec<Value> = results.iter()
+                    .map(|(entry, backpack)| json!({
+                        "id": entry.id,
+                        "title": entry.title,
+                        "subtitle": subtitle(entry, backpack.as_deref()),
+                        "arg": entry.id,
+                        "icon": raycast_icon(entry),
+                        "keywords": entry.tags,
+                        "accessories": backpack.iter().map(|b| json!({ "tag": b })).collect::<Vec<_>>(),
+                    }))
+                    .collect();
+                Ok(serde_json::to_string(&json!({ "items": items }))?)
+            },
+            Self::Lines => Ok(results.iter()
+                .map(|(entry, _)| format!("{}\t{}\n", entry.id, entry.title.replace(['\t', '\n'], " ")))
+                .collect()),
+        }
+    }
+}
+
+/// The line under the title: language, backpack and summary, as far as
+/// the entry has them
+fn subtitle(entry: &Entry, backpack: Option<&str>) -> String {
+    let summary = entry.get_metadata("summary")
+        .and_then(|json| SummaryMetadata::from_json(json).ok())
+        .map(|summary| summary.summary.trim().to_string())
+        .filter(|summary| !summary.is_empty() && *summary != entry.title);
+    [entry.language.clone(), backpack.map(str::to_string), summary].into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// The uniform type identifier whose icon Alfred shows for an entry
+fn alfred_icon(entry: &Entry) -> &'static str {
+    if entry.is_binary() {
+        return "public.data";
+    }
+    match entry.content_type {
+        ContentType::Code => "public.source-code",
+        ContentType::Script => "public.shell-script",
+        ContentType::Text | ContentType::Other(_) => "public.plain-text",
+    }
+}
+
+/// The built-in Raycast icon for an entry
+fn raycast_icon(entry: &Entry) -> &'static str {
+    if entry.is_binary() {
+        return "Document";
+    }
+    match entry.content_type {
+        ContentType::Code => "Code",
+        ContentType::Script => "Terminal",
+        ContentType::Text | ContentType::Other(_) => "Text",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn results() -> Vec<(Entry, Option<String>)> {
+        let mut code = Entry::new("retry loop".to_string(), ContentType::Code, None, vec!["net".to_string()]);
+        code.language = Some("rust".to_string());
+        let notes = Entry::new("standup\tnotes".to_string(), ContentType::Text, None, vec![]);
+        vec![(code, Some("work".to_string())), (notes, None)]
+    }
+    
+    #[test]
+    fn renders_alfred_and_raycast_items() {
+        let results = results();
+        let alfred: Value = serde_json::from_str(&LauncherFormat::Alfred.render(&results).unwrap()).unwrap();
+        let first = &alfred["items"][0];
+        assert_eq!(first["arg"], results[0].0.id.as_str());
+        assert_eq!(first["subtitle"], "rust · work");
+        assert_eq!(first["icon"]["path"], "public.source-code");
+        assert_eq!(alfred["items"][1]["icon"]["path"], "public.plain-text");
+        
+        let raycast: Value = serde_json::from_str(&LauncherFormat::Raycast.render(&results).unwrap()).unwrap();
+        assert_eq!(raycast["items"][0]["icon"], "Code");
+        assert_eq!(raycast["items"][0]["accessories"][0]["tag"], "work");
+        assert_eq!(raycast["items"][1]["accessories"], json!([]));
+    }
+    
+    #[test]
+    fn renders_one_line_per_result() {
+        let results = results();
+        let lines = LauncherFormat::Lines.render(&results).unwrap();
+        assert_eq!(lines, format!("{}\tretry loop\n{}\tstandup notes\n", results[0].0.id, results[1].0.id));
+        assert!(LauncherFormat::parse("dmenu").is_err());
+    }
+}
@@ -1,6 +1,17 @@
 use crate::models::{Entry, SearchAlgorithm};
 use anyhow::Result;
 
+pub mod fuzzy;
+pub mod launcher;
+pub mod project;
+pub mod query;
+pub mod rank;
+pub mod related;
+pub mod text;
+
+pub use query::Query;
+pub use text::TextQuery;
+
 /// Placeholder for future search implementation
 pub fn _search(
     _query: &str, 
@@ -0,0 +1,101 @@
+//! Putting the project you're in first when searching
+//!
+//! Inside a git repository, entries that came from it (added there, or
+//! from one of its files) get a boost, and so do entries in the languages
+//! it's written in. `pocket search --project-only` keeps just those.
+
+use crate::models::Entry;
+use crate::registry::{project, Registry};
+use std::path::{Path, PathBuf};
+
+/// Entry metadata key holding the repository an entry was added in
+pub const PROJECT_KEY: &str = "project";
+
+/// How much an entry in one of the project's languages belongs to it, next
+/// to 1 for an entry that came from it
+const LANGUAGE_RELEVANCE: f64 = 0.5;
+
+/// The project a search is run in
+#[derive(Debug, Clone)]
+pub struct Project {
+    pub root: PathBuf,
+    /// Canonical names of the languages it's written in
+    languages: Vec<&'static str>,
+}
+
+impl Project {
+    /// The project around `dir`, if it's in a git repository
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let root = repository(dir)?;
+        let languages = project::detect(&root).iter()
+            .flat_map(|ecosystem| languages(ecosystem.registry))
+            .copied()
+            .collect();
+        Some(Self { root, languages })
+    }
+    
+    /// The project around the current directory
+    pub fn current() -> Option<Self> {
+        Self::detect(&std::env::current_dir().ok()?)
+    }
+    
+    /// How much an entry belongs to the project: 1 when it came from it,
+    /// less when it's only in one of its languages, 0 otherwise
+    pub fn relevance(&self, entry: &Entry) -> f64 {
+        let from_here = entry.metadata.get(PROJECT_KEY).is_some_and(|root| Path::new(root) == self.root)
+            || entry.source.as_deref().is_some_and(|source| Path::new(source).starts_with(&self.root));
+        if from_here {
+            1.0
+        } else if entry.language.as_deref().is_some_and(|language| self.languages.contains(&language)) {
+            LANGUAGE_RELEVANCE
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The root of the git repository `dir` is in
+pub fn repository(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().find(|dir| dir.join(".git").exists()).map(Path::to_path_buf)
+}
+
+/// Languages an ecosystem's projects are written in
+fn languages(registry: Registry) -> &'static [&'static str] {
+    match registry {
+        Registry::Crates => &["rust"],
+        Registry::Npm => &["javascript", "typescript"],
+        Registry::PyPi => &["python"],
+        Registry::Go => &["go"],
+        Registry::Maven => &["java"],
+        Registry::Packagist => &["php"],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentType;
+    use std::fs;
+
+    #[test]
+    fn ranks_entries_from_the_project_above_ones_in_its_language() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        let project = Project::detect(&dir.path().join("src")).unwrap();
+        assert_eq!(project.root, dir.path());
+
+        let mut added_here = Entry::new("migrate".to_string(), ContentType::Code, None, Vec::new());
+        added_here.add_metadata(PROJECT_KEY, &dir.path().to_string_lossy());
+        let mut rust = Entry::new("retry".to_string(), ContentType::Code, None, Vec::new());
+        rust.language = Some("rust".to_string());
+        let mut python = rust.clone();
+        python.language = Some("python".to_string());
+
+        assert_eq!(project.relevance(&added_here), 1.0);
+        assert_eq!(project.relevance(&rust), LANGUAGE_RELEVANCE);
+        assert_eq!(project.relevance(&python), 0.0);
+    }
+}
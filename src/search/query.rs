@@ -0,0 +1,435 @@
+//! A small query language for filtering entries by their metadata, e.g.
+//! `tag=rust AND created>2024-01-01 AND backpack!=archive`.
+//!
+//! Queries only look at entry metadata, so they can be evaluated without
+//! loading any content.
+
+use crate::models::{ContentType, Entry};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Comparison operators
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    /// Substring match
+    Contains,
+}
+
+/// A value a condition compares against, typed by its field
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Date(NaiveDate),
+    Number(u64),
+    Bool(bool),
+}
+
+/// Entry fields that can be queried
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Id,
+    Title,
+    Tag,
+    Backpack,
+    Language,
+    Type,
+    Source,
+    Mime,
+    Created,
+    Updated,
+    Used,
+    Uses,
+    Pinned,
+}
+
+impl Field {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name.to_lowercase().as_str() {
+            "id" => Field::Id,
+            "title" => Field::Title,
+            "tag" | "tags" => Field::Tag,
+            "backpack" => Field::Backpack,
+            "lang" | "language" => Field::Language,
+            "type" => Field::Type,
+            "source" => Field::Source,
+            "mime" => Field::Mime,
+            "created" => Field::Created,
+            "updated" => Field::Updated,
+            "used" | "last_used" => Field::Used,
+            "uses" | "usage" => Field::Uses,
+            "pinned" => Field::Pinned,
+            _ => return Err(anyhow!("Unknown field '{}'", name)),
+        })
+    }
+
+    /// Parse a literal into the type this field compares against
+    fn parse_value(self, raw: &str) -> Result<Value> {
+        Ok(match self {
+            Field::Created | Field::Updated | Field::Used => Value::Date(
+                NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                    .map_err(|_| anyhow!("Invalid date '{}', expected YYYY-MM-DD", raw))?,
+            ),
+            Field::Uses => Value::Number(raw.parse().map_err(|_| anyhow!("Invalid number '{}'", raw))?),
+            Field::Pinned => Value::Bool(match raw.to_lowercase().as_str() {
+                "true" | "yes" | "1" => true,
+                "false" | "no" | "0" => false,
+                _ => return Err(anyhow!("Invalid boolean '{}'", raw)),
+            }),
+            _ => Value::Text(raw.to_lowercase()),
+        })
+    }
+}
+
+/// A parsed query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query(Expr);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Condition { field: Field, op: Op, value: Value },
+}
+
+impl Query {
+    /// Parse a query string
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(anyhow!("Empty query"));
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(anyhow!("Unexpected '{}' in query", token));
+        }
+        Ok(Query(expr))
+    }
+
+    /// Check whether an entry living in `backpack` (None for the general
+    /// pocket) matches the query
+    pub fn matches(&self, entry: &Entry, backpack: Option<&str>) -> bool {
+        self.0.matches(entry, backpack)
+    }
+}
+
+impl Expr {
+    fn matches(&self, entry: &Entry, backpack: Option<&str>) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(entry, backpack) && b.matches(entry, backpack),
+            Expr::Or(a, b) => a.matches(entry, backpack) || b.matches(entry, backpack),
+            Expr::Not(e) => !e.matches(entry, backpack),
+            Expr::Condition { field, op, value } => evaluate(*field, *op, value, entry, backpack),
+        }
+    }
+}
+
+fn evaluate(field: Field, op: Op, value: &Value, entry: &Entry, backpack: Option<&str>) -> bool {
+    match (field, value) {
+        (Field::Tag, Value::Text(expected)) => {
+            let any = |f: &dyn Fn(&str) -> bool| entry.tags.iter().any(|t| f(&t.to_lowercase()));
+            match op {
+                // `tag!=x` means no tag is x
+                Op::Ne => !any(&|t| t == expected),
+                _ => any(&|t| compare_text(t, op, expected)),
+            }
+        }
+        (Field::Created, Value::Date(date)) => compare_date(Some(entry.created_at), op, *date),
+        (Field::Updated, Value::Date(date)) => compare_date(Some(entry.updated_at), op, *date),
+        (Field::Used, Value::Date(date)) => compare_date(entry.last_used, op, *date),
+        (Field::Uses, Value::Number(n)) => compare_ord(&entry.usage_count, op, n),
+        (Field::Pinned, Value::Bool(b)) => match op {
+            Op::Ne => entry.pinned != *b,
+            _ => entry.pinned == *b,
+        },
+        (field, Value::Text(expected)) => {
+            let actual = match field {
+                Field::Id => Some(entry.id.clone()),
+                Field::Title => Some(entry.title.clone()),
+                Field::Backpack => Some(backpack.unwrap_or("general").to_string()),
+                Field::Language => entry.language.clone(),
+                Field::Type => Some(content_type_name(&entry.content_type)),
+                Field::Source => entry.source.clone(),
+                Field::Mime => entry.mime_type.clone(),
+                _ => None,
+            };
+            match actual {
+                Some(actual) => compare_text(&actual.to_lowercase(), op, expected),
+                // A missing value equals nothing, so only != matches
+                None => op == Op::Ne,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn content_type_name(content_type: &ContentType) -> String {
+    match content_type {
+        ContentType::Code => "code".to_string(),
+        ContentType::Text => "text".to_string(),
+        ContentType::Script => "script".to_string(),
+        ContentType::Other(name) => name.clone(),
+    }
+}
+
+fn compare_text(actual: &str, op: Op, expected: &str) -> bool {
+    match op {
+        Op::Contains => actual.contains(expected),
+        _ => compare_ord(actual, op, expected),
+    }
+}
+
+fn compare_ord<T: PartialOrd + ?Sized>(actual: &T, op: Op, expected: &T) -> bool {
+    match op {
+        Op::Eq => actual == expected,
+        Op::Ne => actual != expected,
+        Op::Gt => actual > expected,
+        Op::Ge => actual >= expected,
+        Op::Lt => actual < expected,
+        Op::Le => actual <= expected,
+        Op::Contains => false,
+    }
+}
+
+/// Dates compare by calendar day, so `created=2024-01-01` matches the whole day
+fn compare_date(actual: Option<DateTime<Utc>>, op: Op, expected: NaiveDate) -> bool {
+    match actual {
+        Some(actual) => compare_ord(&actual.date_naive(), op, &expected),
+        None => op == Op::Ne,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    LParen,
+    RParen,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "{}", w),
+            Token::Quoted(q) => write!(f, "\"{}\"", q),
+            Token::Op(op) => write!(f, "{:?}", op),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(ch) if ch == c => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(ch) => text.push(ch),
+                        None => return Err(anyhow!("Unterminated string in query")),
+                    }
+                }
+                tokens.push(Token::Quoted(text));
+            }
+            '=' | '!' | '<' | '>' | '~' => {
+                chars.next();
+                let followed_by_eq = chars.peek() == Some(&'=');
+                let op = match (c, followed_by_eq) {
+                    ('=', _) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    ('<', true) => Op::Le,
+                    ('>', true) => Op::Ge,
+                    ('<', false) => Op::Lt,
+                    ('>', false) => Op::Gt,
+                    ('~', _) => Op::Contains,
+                    _ => return Err(anyhow!("Unexpected '{}' in query", c)),
+                };
+                if followed_by_eq && c != '=' {
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()=!<>~\"'".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut query = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            query = Expr::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut query = self.parse_unary()?;
+        while self.eat_keyword("AND") {
+            query = Expr::And(Box::new(query), Box::new(self.parse_unary()?));
+        }
+        Ok(query)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let query = self.parse_or()?;
+            if self.next() != Some(Token::RParen) {
+                return Err(anyhow!("Missing ')' in query"));
+            }
+            return Ok(query);
+        }
+
+        self.parse_condition()
+    }
+
+    fn parse_condition(&mut self) -> Result<Expr> {
+        let field = match self.next() {
+            Some(Token::Word(name)) => Field::parse(&name)?,
+            Some(token) => return Err(anyhow!("Expected a field name, found '{}'", token)),
+            None => return Err(anyhow!("Unexpected end of query")),
+        };
+
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => return Err(anyhow!("Expected an operator (=, !=, >, >=, <, <=, ~) after field")),
+        };
+
+        let raw = match self.next() {
+            Some(Token::Word(w)) | Some(Token::Quoted(w)) => w,
+            _ => return Err(anyhow!("Expected a value after operator")),
+        };
+
+        Ok(Expr::Condition {
+            field,
+            op,
+            value: field.parse_value(&raw)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry() -> Entry {
+        let mut entry = Entry::new("Deploy script".to_string(), ContentType::Script, None, vec!["rust".to_string(), "ops".to_string()]);
+        entry.created_at = Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap();
+        entry.language = Some("shell".to_string());
+        entry.usage_count = 4;
+        entry
+    }
+
+    #[test]
+    fn test_simple_conditions() {
+        let e = entry();
+        assert!(Query::parse("tag=rust").unwrap().matches(&e, None));
+        assert!(!Query::parse("tag=python").unwrap().matches(&e, None));
+        assert!(Query::parse("tag!=python").unwrap().matches(&e, None));
+        assert!(Query::parse("title~deploy").unwrap().matches(&e, None));
+        assert!(Query::parse("uses>=4").unwrap().matches(&e, None));
+        assert!(Query::parse("lang=shell").unwrap().matches(&e, None));
+    }
+
+    #[test]
+    fn test_dates_and_backpacks() {
+        let e = entry();
+        assert!(Query::parse("created>2024-01-01").unwrap().matches(&e, None));
+        assert!(Query::parse("created=2024-03-15").unwrap().matches(&e, None));
+        assert!(!Query::parse("created<2024-03-15").unwrap().matches(&e, None));
+        assert!(Query::parse("backpack=general").unwrap().matches(&e, None));
+        assert!(!Query::parse("backpack!=archive").unwrap().matches(&e, Some("archive")));
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        let e = entry();
+        let q = Query::parse("tag=rust AND created>2024-01-01 AND backpack!=archive").unwrap();
+        assert!(q.matches(&e, Some("work")));
+        assert!(!q.matches(&e, Some("archive")));
+
+        assert!(Query::parse("tag=python OR tag=ops").unwrap().matches(&e, None));
+        assert!(Query::parse("NOT (tag=python OR uses<2)").unwrap().matches(&e, None));
+        assert!(Query::parse("title = \"Deploy script\"").unwrap().matches(&e, None));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Query::parse("").is_err());
+        assert!(Query::parse("color=red").is_err());
+        assert!(Query::parse("created>yesterday").is_err());
+        assert!(Query::parse("tag=rust AND").is_err());
+        assert!(Query::parse("(tag=rust").is_err());
+        assert!(Query::parse("tag rust").is_err());
+    }
+}
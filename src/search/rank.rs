@@ -0,0 +1,111 @@
+//! Ordering search results, and explaining the order with `--explain`
+//!
+//! An entry's score adds up what it has going for it, each part scaled by
+//! its weight under `[search.ranking]` in `config.toml`: the query's terms
+//! in its title, content and tags, how recently it was changed or used,
+//! how often it's been used, and whether it belongs to the project the
+//! search is run in. Fuzzy matches are then scaled down by how far off
+//! they were.
+
+use super::project::Project;
+use super::text::{Candidate, TextQuery};
+use crate::models::RankingConfig;
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+/// Days after which an entry counts half as recent
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Uses at which an entry gets half the usage score
+const USAGE_HALF_POINT: f64 = 5.0;
+
+/// How an entry's score adds up
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Breakdown {
+    pub title: f64,
+    pub content: f64,
+    pub tags: f64,
+    pub recency: f64,
+    pub usage: f64,
+    pub project: f64,
+    /// How close a fuzzy match is, from 0 to 1 (always 1 otherwise)
+    pub closeness: f64,
+}
+
+impl Breakdown {
+    pub fn total(&self) -> f64 {
+        (self.title + self.content + self.tags + self.recency + self.usage + self.project) * self.closeness
+    }
+}
+
+impl fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.2} = title {:.2} + content {:.2} + tags {:.2} + recency {:.2} + usage {:.2}",
+            self.total(), self.title, self.content, self.tags, self.recency, self.usage
+        )?;
+        if self.project > 0.0 {
+            write!(f, " + project {:.2}", self.project)?;
+        }
+        if self.closeness < 1.0 {
+            write!(f, ", x closeness {:.2}", self.closeness)?;
+        }
+        Ok(())
+    }
+}
+
+/// Scores an entry that matched `query`, in `project` when the search is
+/// run in one
+pub fn score(query: &TextQuery, candidate: &Candidate, weights: &RankingConfig, project: Option<&Project>, now: DateTime<Utc>) -> Breakdown {
+    let entry = candidate.entry;
+    let hits = query.hits(candidate);
+
+    // Using an entry makes it as fresh as changing it does
+    let touched = entry.last_used.map_or(entry.updated_at, |used| used.max(entry.updated_at));
+    let days = (now - touched).num_seconds().max(0) as f64 / 86_400.0;
+    let recency = 0.5_f64.powf(days / RECENCY_HALF_LIFE_DAYS);
+
+    let uses = entry.usage_count as f64;
+    let usage = uses / (uses + USAGE_HALF_POINT);
+
+    Breakdown {
+        title: weights.title * hits.title,
+        content: weights.content * hits.content,
+        tags: weights.tags * hits.tags,
+        recency: weights.recency * recency,
+        usage: weights.usage * usage,
+        project: weights.project * project.map_or(0.0, |project| project.relevance(entry)),
+        closeness: query.closeness(candidate),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentType, Entry};
+    use chrono::Duration;
+
+    #[test]
+    fn weighs_where_terms_are_and_how_the_entry_is_used() {
+        let now = Utc::now();
+        let mut titled = Entry::new("Docker cleanup".to_string(), ContentType::Text, None, Vec::new());
+        titled.updated_at = now - Duration::days(RECENCY_HALF_LIFE_DAYS as i64);
+        let mut used = Entry::new("Prune".to_string(), ContentType::Text, None, Vec::new());
+        used.usage_count = 5;
+        used.updated_at = now;
+
+        let query = TextQuery::parse("docker");
+        let weights = RankingConfig::default();
+        let titled = score(&query, &Candidate::new(&titled, None, "docker system prune"), &weights, None, now);
+        let used = score(&query, &Candidate::new(&used, None, "docker system prune"), &weights, None, now);
+
+        assert_eq!(titled.title, weights.title);
+        assert_eq!(used.title, 0.0);
+        assert!((titled.recency - weights.recency / 2.0).abs() < 0.01);
+        assert_eq!(used.usage, weights.usage / 2.0);
+        assert!(titled.total() > used.total());
+
+        assert!(used.to_string().starts_with(&format!("{:.2} = title 0.00", used.total())));
+    }
+}
@@ -0,0 +1,191 @@
+//! Finding entries related to one another, for `pocket related` and the
+//! "See also" section of `pocket show`
+//!
+//! Two entries are related when they share tags and words. Comparing an
+//! entry with every other one means reading all their content, so the
+//! answer is kept in the entry's metadata along with a stamp of every
+//! entry's ID, tags and content hash. It's only worked out again once that
+//! stamp changes, i.e. when entries are added, removed, edited or retagged.
+
+use crate::models::Entry;
+use crate::storage::StorageManager;
+use crate::utils::tagging;
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// Metadata key holding the related entries, as `ID:score` pairs
+pub const RELATED_KEY: &str = "related";
+
+/// Metadata key holding the stamp the related entries were worked out for
+pub const RELATED_STAMP_KEY: &str = "related_stamp";
+
+/// Most related entries kept for an entry
+const MAX_RELATED: usize = 10;
+
+/// Scores below this aren't worth suggesting
+const MIN_SCORE: f64 = 0.1;
+
+/// How much shared tags count for, next to shared words
+const TAG_WEIGHT: f64 = 0.4;
+
+/// An entry related to another one
+#[derive(Debug, Clone)]
+pub struct Related {
+    pub entry: Entry,
+    /// Backpack the entry is in
+    pub backpack: Option<String>,
+    /// From 0 (nothing in common) to 1 (same tags and words)
+    pub score: f64,
+}
+
+/// Up to `limit` entries most related to `id`, most related first
+pub fn related(storage: &StorageManager, id: &str, backpack: Option<&str>, limit: usize) -> Result<Vec<Related>> {
+    let mut everything = Vec::new();
+    let mut locations = vec![None];
+    locations.extend(storage.list_backpacks()?.into_iter().map(|b| Some(b.name)));
+    for location in locations {
+        for entry in storage.list_entries(location.as_deref())? {
+            everything.push((entry, location.clone()));
+        }
+    }
+
+    let stamp = stamp(&everything);
+    let entry = storage.load_entry_metadata(id, backpack)?;
+    let scores = match cached(&entry, &stamp) {
+        Some(scores) => scores,
+        None => {
+            let scores = score_all(storage, &entry, backpack, &everything)?;
+            let saved = scores.iter().map(|(id, score)| format!("{}:{:.3}", id, score)).collect::<Vec<_>>().join(",");
+            storage.update_entry_metadata(id, backpack, |entry| {
+                entry.add_metadata(RELATED_KEY, &saved);
+                entry.add_metadata(RELATED_STAMP_KEY, &stamp);
+            })?;
+            scores
+        },
+    };
+
+    let mut related = Vec::new();
+    for (related_id, score) in scores {
+        if let Some((entry, backpack)) = everything.iter().find(|(entry, _)| entry.id == related_id) {
+            related.push(Related { entry: entry.clone(), backpack: backpack.clone(), score });
+        }
+        if related.len() == limit {
+            break;
+        }
+    }
+    Ok(related)
+}
+
+/// The related entries saved in an entry's metadata, if they're up to date
+fn cached(entry: &Entry, stamp: &str) -> Option<Vec<(String, f64)>> {
+    if entry.get_metadata(RELATED_STAMP_KEY) != Some(stamp) {
+        return None;
+    }
+    let saved = entry.get_metadata(RELATED_KEY)?;
+    saved.split(',')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (id, score) = pair.rsplit_once(':')?;
+            Some((id.to_string(), score.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Compares `entry` with every other entry, keeping the best matches
+fn score_all(storage: &StorageManager, entry: &Entry, backpack: Option<&str>, everything: &[(Entry, Option<String>)]) -> Result<Vec<(String, f64)>> {
+    let words = |entry: &Entry, location: Option<&str>| -> Result<HashSet<String>> {
+        let mut text = entry.title.clone();
+        if !entry.is_binary() {
+            text.push('\n');
+            text.push_str(&storage.load_entry(&entry.id, location)?.1);
+        }
+        Ok(tagging::words(&text).into_iter().collect())
+    };
+    let tags = |entry: &Entry| -> HashSet<String> {
+        entry.tags.iter().map(|tag| tag.to_lowercase()).collect()
+    };
+
+    let own_words = words(entry, backpack)?;
+    let own_tags = tags(entry);
+    let mut scores = Vec::new();
+    for (other, location) in everything {
+        if other.id == entry.id {
+            continue;
+        }
+        let score = similarity(&own_tags, &own_words, &tags(other), &words(other, location.as_deref())?);
+        if score >= MIN_SCORE {
+            scores.push((other.id.clone(), score));
+        }
+    }
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scores.truncate(MAX_RELATED);
+    Ok(scores)
+}
+
+/// How related two entries are, from their tags and words. When neither
+/// has tags, the words decide alone
+fn similarity(tags: &HashSet<String>, words: &HashSet<String>, other_tags: &HashSet<String>, other_words: &HashSet<String>) -> f64 {
+    let word_score = jaccard(words, other_words);
+    if tags.is_empty() && other_tags.is_empty() {
+        return word_score;
+    }
+    TAG_WEIGHT * jaccard(tags, other_tags) + (1.0 - TAG_WEIGHT) * word_score
+}
+
+/// Shared items over all items
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// A hash of what related entries depend on, which changes whenever any
+/// entry's content or tags do
+fn stamp(everything: &[(Entry, Option<String>)]) -> String {
+    let mut parts: Vec<String> = everything.iter()
+        .map(|(entry, _)| {
+            let version = entry.content_hash.clone().unwrap_or_else(|| entry.updated_at.to_rfc3339());
+            format!("{} {} {}", entry.id, version, entry.tags.join(" "))
+        })
+        .collect();
+    parts.sort();
+    let digest = Sha256::digest(parts.join("\n").as_bytes());
+    digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentType;
+
+    #[test]
+    fn finds_entries_sharing_tags_and_words() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageManager::at(dir.path());
+        for required in ["data/entries", "data/backpacks"] {
+            std::fs::create_dir_all(dir.path().join(required)).unwrap();
+        }
+        let add = |title: &str, content: &str, tags: &[&str]| {
+            let entry = Entry::new(title.to_string(), ContentType::Text, None, tags.iter().map(|t| t.to_string()).collect());
+            storage.save_entry(&entry, content, None).unwrap();
+            entry.id
+        };
+        let resize = add("Resize images", "convert image.png -resize 50% small.png", &["images"]);
+        let crop = add("Crop images", "convert image.png -crop 100x100 cropped.png", &["images"]);
+        let docker = add("Prune docker", "docker system prune --volumes", &["docker"]);
+
+        let related = related(&storage, &resize, None, 5).unwrap();
+        assert_eq!(related.iter().map(|r| r.entry.id.as_str()).collect::<Vec<_>>(), [crop.as_str()]);
+
+        // The answer is saved, and worked out again once entries change
+        let entry = storage.load_entry_metadata(&resize, None).unwrap();
+        assert!(entry.get_metadata(RELATED_KEY).unwrap().starts_with(&crop));
+        storage.update_entry_metadata(&docker, None, |entry| entry.tags.push("images".to_string())).unwrap();
+        let stale = entry.get_metadata(RELATED_STAMP_KEY).unwrap().to_string();
+        super::related(&storage, &resize, None, 5).unwrap();
+        assert_ne!(storage.load_entry_metadata(&resize, None).unwrap().get_metadata(RELATED_STAMP_KEY), Some(stale.as_str()));
+    }
+}
@@ -0,0 +1,491 @@
+//! The query language of `pocket search`, e.g.
+//! `title:docker AND (tag:k8s OR tag:helm) NOT backpack:archive`.
+//!
+//! Plain words match anywhere in an entry's title or content, and words
+//! next to each other must all match. `field:value` looks at just one
+//! field, `"quoted phrases"` match as a whole, and a trailing `*` matches
+//! words starting with what comes before it. Anything that doesn't parse
+//! is searched for as it was typed, so `fn main()` still finds itself.
+//!
+//! A fuzzy query lets plain words and phrases match text that's only close
+//! to them, and scores entries by how close (see [`super::fuzzy`]).
+
+use super::fuzzy;
+use crate::models::Entry;
+use anyhow::{Result, anyhow};
+
+/// Fields a search term can be scoped to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Title,
+    Content,
+    Tag,
+    Backpack,
+    Language,
+    Id,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "title" => Field::Title,
+            "content" | "body" => Field::Content,
+            "tag" | "tags" => Field::Tag,
+            "backpack" => Field::Backpack,
+            "lang" | "language" => Field::Language,
+            "id" => Field::Id,
+            _ => return None,
+        })
+    }
+}
+
+/// What a term looks for
+#[derive(Debug, Clone, PartialEq)]
+enum Pattern {
+    /// Text anywhere in the field
+    Text(String),
+    /// A word in the field starting with this
+    Prefix(String),
+}
+
+impl Pattern {
+    fn new(text: &str, quoted: bool) -> Self {
+        match text.strip_suffix('*') {
+            Some(prefix) if !quoted && !prefix.is_empty() => Pattern::Prefix(prefix.to_lowercase()),
+            _ => Pattern::Text(text.to_lowercase()),
+        }
+    }
+
+    /// Matches lower-cased text containing the pattern, or something close
+    /// to it when `fuzzy`
+    fn found_in(&self, text: &str, fuzzy: bool) -> bool {
+        match self {
+            Pattern::Text(wanted) if fuzzy => fuzzy::matches(wanted, text),
+            Pattern::Text(wanted) => text.contains(wanted.as_str()),
+            Pattern::Prefix(prefix) => text
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .any(|word| word.starts_with(prefix.as_str())),
+        }
+    }
+
+    /// Matches a lower-cased value that's exactly the pattern
+    fn is(&self, value: &str) -> bool {
+        match self {
+            Pattern::Text(wanted) => value == wanted,
+            Pattern::Prefix(prefix) => value.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// A parsed search
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextQuery {
+    expr: Option<Expr>,
+    fuzzy: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// A pattern in one field, or in the title or content when there's none
+    Term { field: Option<Field>, pattern: Pattern },
+}
+
+/// Where in an entry a query's terms turned up, each from 0 (none of
+/// them) to 1 (all of them)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Hits {
+    pub title: f64,
+    pub content: f64,
+    pub tags: f64,
+}
+
+/// An entry as the query sees it, with its text lower-cased once
+pub struct Candidate<'a> {
+    pub entry: &'a Entry,
+    pub backpack: Option<&'a str>,
+    title: String,
+    content: String,
+}
+
+impl<'a> Candidate<'a> {
+    pub fn new(entry: &'a Entry, backpack: Option<&'a str>, content: &str) -> Self {
+        Self { entry, backpack, title: entry.title.to_lowercase(), content: content.to_lowercase() }
+    }
+}
+
+impl TextQuery {
+    /// Parses a search. Searches that aren't valid queries, like ones with
+    /// unbalanced brackets, look for the text as typed
+    pub fn parse(input: &str) -> Self {
+        Self::parse_strict(input).unwrap_or_else(|_| TextQuery {
+            expr: Some(Expr::Term { field: None, pattern: Pattern::Text(input.trim().to_lowercase()) }),
+            fuzzy: false,
+        })
+    }
+
+    /// Parses a search, failing on anything that isn't a valid query
+    pub fn parse_strict(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(TextQuery { expr: None, fuzzy: false });
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(anyhow!("Unexpected {:?} in search", token));
+        }
+        Ok(TextQuery { expr: Some(expr), fuzzy: false })
+    }
+
+    /// Makes plain words and phrases match text close to them
+    pub fn fuzzy(mut self, fuzzy: bool) -> Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        self.fuzzy
+    }
+
+    /// Whether the query only needs entry metadata, so content can go unread
+    pub fn needs_content(&self) -> bool {
+        self.expr.as_ref().is_some_and(Expr::needs_content)
+    }
+
+    /// Whether the query says which backpacks to look in, so every
+    /// backpack should be searched
+    pub fn mentions_backpack(&self) -> bool {
+        self.expr.as_ref().is_some_and(Expr::mentions_backpack)
+    }
+
+    /// Whether an entry matches. An empty search matches everything
+    pub fn matches(&self, candidate: &Candidate) -> bool {
+        self.expr.as_ref().is_none_or(|expr| expr.matches(candidate, self.fuzzy))
+    }
+
+    /// How many of the terms an entry has to match are in its title, its
+    /// content and its tags, as fractions of all of them
+    pub fn hits(&self, candidate: &Candidate) -> Hits {
+        let mut terms = Vec::new();
+        if let Some(expr) = &self.expr {
+            expr.wanted_terms(&mut terms);
+        }
+        if terms.is_empty() {
+            return Hits::default();
+        }
+        let fraction = |found: &dyn Fn(Option<Field>, &Pattern) -> bool| {
+            terms.iter().filter(|(field, pattern)| found(*field, pattern)).count() as f64 / terms.len() as f64
+        };
+        Hits {
+            title: fraction(&|field, pattern| matches!(field, None | Some(Field::Title)) && pattern.found_in(&candidate.title, self.fuzzy)),
+            content: fraction(&|field, pattern| matches!(field, None | Some(Field::Content)) && pattern.found_in(&candidate.content, self.fuzzy)),
+            tags: fraction(&|field, pattern| {
+                matches!(field, None | Some(Field::Tag)) && candidate.entry.tags.iter().any(|tag| pattern.is(&tag.to_lowercase()))
+            }),
+        }
+    }
+
+    /// How closely a matching entry matches, from 0 to 1. Only fuzzy
+    /// queries match anything less than exactly
+    pub fn closeness(&self, candidate: &Candidate) -> f64 {
+        match &self.expr {
+            Some(expr) if self.fuzzy => expr.closeness(candidate),
+            _ => 1.0,
+        }
+    }
+}
+
+impl Expr {
+    fn matches(&self, candidate: &Candidate, fuzzy: bool) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(candidate, fuzzy) && b.matches(candidate, fuzzy),
+            Expr::Or(a, b) => a.matches(candidate, fuzzy) || b.matches(candidate, fuzzy),
+            Expr::Not(e) => !e.matches(candidate, fuzzy),
+            Expr::Term { field, pattern } => {
+                let entry = candidate.entry;
+                match field {
+                    None => pattern.found_in(&candidate.title, fuzzy) || pattern.found_in(&candidate.content, fuzzy),
+                    Some(Field::Title) => pattern.found_in(&candidate.title, fuzzy),
+                    Some(Field::Content) => pattern.found_in(&candidate.content, fuzzy),
+                    Some(Field::Tag) => entry.tags.iter().any(|tag| pattern.is(&tag.to_lowercase())),
+                    Some(Field::Backpack) => pattern.is(&candidate.backpack.unwrap_or("general").to_lowercase()),
+                    Some(Field::Language) => entry.language.as_ref().is_some_and(|language| pattern.is(&language.to_lowercase())),
+                    // IDs are usually typed as a prefix
+                    Some(Field::Id) => match pattern {
+                        Pattern::Text(id) | Pattern::Prefix(id) => entry.id.to_lowercase().starts_with(id.as_str()),
+                    },
+                }
+            },
+        }
+    }
+
+    /// The average closeness of the terms that have to match, the best of
+    /// alternatives, and 1 for terms that match exactly or not at all
+    fn closeness(&self, candidate: &Candidate) -> f64 {
+        match self {
+            Expr::And(a, b) => (a.closeness(candidate) + b.closeness(candidate)) / 2.0,
+            Expr::Or(a, b) => a.closeness(candidate).max(b.closeness(candidate)),
+            Expr::Not(_) => 1.0,
+            Expr::Term { field, pattern: Pattern::Text(wanted) } => {
+                let in_title = || fuzzy::score(wanted, &candidate.title);
+                let in_content = || fuzzy::score(wanted, &candidate.content);
+                match field {
+                    None => in_title().max(in_content()),
+                    Some(Field::Title) => in_title(),
+                    Some(Field::Content) => in_content(),
+                    Some(_) => 1.0,
+                }
+            },
+            Expr::Term { .. } => 1.0,
+        }
+    }
+
+    /// Collects the terms that count towards matching, which are all of
+    /// them except the ones under a NOT
+    fn wanted_terms<'a>(&'a self, terms: &mut Vec<(Option<Field>, &'a Pattern)>) {
+        match self {
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                a.wanted_terms(terms);
+                b.wanted_terms(terms);
+            },
+            Expr::Not(_) => {},
+            Expr::Term { field, pattern } => terms.push((*field, pattern)),
+        }
+    }
+
+    fn needs_content(&self) -> bool {
+        match self {
+            Expr::And(a, b) | Expr::Or(a, b) => a.needs_content() || b.needs_content(),
+            Expr::Not(e) => e.needs_content(),
+            Expr::Term { field, .. } => matches!(field, None | Some(Field::Content)),
+        }
+    }
+
+    fn mentions_backpack(&self) -> bool {
+        match self {
+            Expr::And(a, b) | Expr::Or(a, b) => a.mentions_backpack() || b.mentions_backpack(),
+            Expr::Not(e) => e.mentions_backpack(),
+            Expr::Term { field, .. } => *field == Some(Field::Backpack),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A term, with the field it's scoped to and whether it was quoted
+    Term { field: Option<Field>, text: String, quoted: bool },
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => tokens.push(Token::Term { field: None, text: quoted(&mut chars)?, quoted: true }),
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()\"".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+
+                // `field:value`, but only for fields there are, so URLs and
+                // `std::fs` stay plain words
+                let scoped = word.split_once(':')
+                    .and_then(|(name, value)| Some((Field::parse(name)?, value.to_string())));
+                let (field, text) = match scoped {
+                    Some((field, value)) => (Some(field), value),
+                    None => (None, word),
+                };
+                if text.is_empty() && chars.peek() == Some(&'"') {
+                    tokens.push(Token::Term { field, text: quoted(&mut chars)?, quoted: true });
+                } else if text.is_empty() {
+                    return Err(anyhow!("Nothing to search for"));
+                } else {
+                    tokens.push(Token::Term { field, text, quoted: false });
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a quoted phrase, starting at the opening quote
+fn quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String> {
+    chars.next();
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => text.extend(chars.next()),
+            Some(ch) => text.push(ch),
+            None => return Err(anyhow!("Unterminated phrase in search")),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Term { field: None, text, quoted: false }) if text == keyword => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut query = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            query = Expr::Or(Box::new(query), Box::new(self.parse_and()?));
+        }
+        Ok(query)
+    }
+
+    /// Terms next to each other are ANDed, with or without the AND
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut query = self.parse_unary()?;
+        loop {
+            if !self.eat_keyword("AND") {
+                let ends_group = match self.peek() {
+                    None | Some(Token::RParen) => true,
+                    Some(Token::Term { field: None, text, quoted: false }) => text == "OR",
+                    _ => false,
+                };
+                if ends_group {
+                    return Ok(query);
+                }
+            }
+            query = Expr::And(Box::new(query), Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+
+        match self.tokens.get(self.pos).cloned() {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let query = self.parse_or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(anyhow!("Missing ')' in search"));
+                }
+                self.pos += 1;
+                Ok(query)
+            }
+            Some(Token::Term { field, text, quoted }) => {
+                self.pos += 1;
+                Ok(Expr::Term { field, pattern: Pattern::new(&text, quoted) })
+            }
+            Some(Token::RParen) => Err(anyhow!("Unexpected ')' in search")),
+            None => Err(anyhow!("Unexpected end of search")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentType;
+
+    fn entry() -> Entry {
+        let mut entry = Entry::new("Docker cleanup".to_string(), ContentType::Script, None, vec!["k8s".to_string(), "ops".to_string()]);
+        entry.language = Some("shell".to_string());
+        entry
+    }
+
+    fn matches(query: &str, backpack: Option<&str>) -> bool {
+        let entry = entry();
+        TextQuery::parse_strict(query).unwrap()
+            .matches(&Candidate::new(&entry, backpack, "docker system prune --volumes\nhelm list"))
+    }
+
+    #[test]
+    fn scopes_terms_to_fields() {
+        assert!(matches("title:docker AND (tag:k8s OR tag:helm) NOT backpack:archive", Some("work")));
+        assert!(!matches("title:docker AND (tag:k8s OR tag:helm) NOT backpack:archive", Some("archive")));
+        assert!(matches("tag:k8s lang:shell", None));
+        assert!(!matches("title:helm", None));
+        assert!(matches("content:helm", None));
+        assert!(matches("backpack:general", None));
+    }
+
+    #[test]
+    fn matches_words_phrases_and_prefixes() {
+        assert!(matches("prune docker", None));
+        assert!(!matches("prune kubernetes", None));
+        assert!(matches("\"system prune\"", None));
+        assert!(!matches("\"prune system\"", None));
+        assert!(matches("title:\"docker cleanup\"", None));
+        assert!(matches("vol*", None));
+        assert!(!matches("olumes*", None));
+        assert!(matches("tag:k*", None));
+        assert!(matches("kubernetes OR helm", None));
+        assert!(matches("", None));
+    }
+
+    #[test]
+    fn searches_for_what_does_not_parse_as_typed() {
+        assert!(TextQuery::parse_strict("(docker").is_err());
+        let entry = entry();
+        let candidate = Candidate::new(&entry, None, "fn main() {");
+        assert!(TextQuery::parse("fn main(").matches(&candidate));
+        assert!(TextQuery::parse("http://example.com").needs_content());
+        assert!(!TextQuery::parse("tag:k8s NOT backpack:archive").needs_content());
+        assert!(TextQuery::parse("tag:k8s NOT backpack:archive").mentions_backpack());
+    }
+
+    #[test]
+    fn counts_where_terms_turn_up() {
+        let entry = entry();
+        let candidate = Candidate::new(&entry, None, "docker system prune");
+        let hits = TextQuery::parse("docker ops NOT helm").hits(&candidate);
+        assert_eq!(hits, Hits { title: 0.5, content: 0.5, tags: 0.5 });
+    }
+
+    #[test]
+    fn fuzzy_queries_forgive_typos() {
+        let entry = entry();
+        let candidate = Candidate::new(&entry, None, "docker compose up -d");
+        let query = TextQuery::parse("dokcer compose");
+        assert!(!query.matches(&candidate));
+        let query = query.fuzzy(true);
+        assert!(query.matches(&candidate));
+        assert!(query.closeness(&candidate) < 1.0);
+        assert_eq!(TextQuery::parse("docker compose").fuzzy(true).closeness(&candidate), 1.0);
+        assert!(!TextQuery::parse("tag:k9s").fuzzy(true).matches(&candidate));
+    }
+}
@@ -0,0 +1,372 @@
+//! `pocket api`: a small REST API on localhost
+//!
+//! Browser extensions and editor plugins can use it instead of starting
+//! pocket for every request. Every request needs the token from
+//! `~/.pocket/data/api_token`, sent as `Authorization: Bearer TOKEN`.
+//!
+//! - `GET /entries?backpack=&limit=` lists entries
+//! - `POST /entries` adds one from `{"content", "title", "language", "tags", "backpack"}`
+//! - `GET /entries/ID?backpack=` gets one, with its content
+//! - `DELETE /entries/ID?backpack=` removes one
+//! - `GET /backpacks` lists backpacks
+//! - `GET /search?q=&backpack=&limit=` searches every backpack, or just one
+
+use super::{add_entry, describe, search};
+use crate::cards::core::CoreCard;
+use crate::cards::CardManager;
+use crate::logging;
+use crate::storage::{self, StorageManager};
+use crate::workflow::schedule;
+use anyhow::{Result, anyhow, bail};
+use colored::Colorize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// How long a client gets to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Results a listing or search returns when the request doesn't say
+const DEFAULT_LIMIT: usize = 50;
+
+/// A parsed HTTP request
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    
+    /// Header names are lower-cased
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// What to send back: a status and a JSON body, if any
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: Option<Value>,
+}
+
+impl Response {
+    fn json(status: u16, body: Value) -> Self {
+        Self { status, body: Some(body) }
+    }
+    
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self::json(status, json!({ "error": message.to_string() }))
+    }
+}
+
+/// Answers API requests, one at a time
+pub struct ApiServer<'a> {
+    card_manager: &'a mut CardManager,
+    token: String,
+}
+
+impl<'a> ApiServer<'a> {
+    pub fn new(card_manager: &'a mut CardManager, token: String) -> Self {
+        Self { card_manager, token }
+    }
+    
+    /// Listens on localhost until interrupted
+    pub fn serve(&mut self, port: u16) -> Result<()> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .map_err(|e| anyhow!("Can't listen on port {}: {}", port, e))?;
+        println!("Serving the pocket API on http://127.0.0.1:{} (Ctrl-C to stop)", port);
+        
+        for stream in listener.incoming() {
+            let result = stream.map_err(anyhow::Error::from)
+                .and_then(|stream| self.connection(stream));
+            if let Err(e) = result {
+                logging::warning(&format!("Request failed: {}", e));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Reads one request from a connection and answers it
+    fn connection(&mut self, stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let response = match read_request(&mut reader) {
+            Ok(request) => {
+                let response = self.respond(&request);
+                println!("{} {} {}", request.method, request.path, status_text(response.status).dimmed());
+                response
+            },
+            Err(e) => Response::error(400, e),
+        };
+        write_response(stream, &response)
+    }
+    
+    /// Checks a request's token and routes it
+    fn respond(&mut self, request: &Request) -> Response {
+        // Browsers ask before cross-origin requests, without credentials
+        if request.method == "OPTIONS" {
+            return Response { status: 204, body: None };
+        }
+        if !authorized(request, &self.token) {
+            return Response::error(401, "Missing or wrong token; send Authorization: Bearer TOKEN");
+        }
+        // Backpack names become directory names, so ones like ../x stop here
+        if let Some(Err(e)) = backpack(request).map(storage::validate_backpack_name) {
+            return Response::error(400, e);
+        }
+        
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').filter(|s| !s.is_empty()).collect();
+        let mutating = matches!(request.method.as_str(), "POST" | "DELETE");
+        let response = match (request.method.as_str(), segments.as_slice()) {
+            ("GET", []) => Ok(Response::json(200, json!({ "name": "pocket", "version": env!("CARGO_PKG_VERSION") }))),
+            ("GET", ["entries"]) => self.list(request),
+            ("POST", ["entries"]) => self.add(request),
+            ("GET", ["entries", id]) => self.get(id, request),
+            ("DELETE", ["entries", id]) => self.remove(id, request),
+            ("GET", ["backpacks"]) => self.backpacks(),
+            ("GET", ["search"]) => self.search(request),
+            (_, ["entries" | "backpacks" | "search", ..]) | (_, []) => Ok(Response::error(405, "Method not allowed")),
+            _ => Ok(Response::error(404, "Not found")),
+        };
+        let response = response.unwrap_or_else(|e| Response::error(500, e));
+        
+        // A refused request changed nothing
+        if mutating && response.status < 400 {
+            self.card_manager.dispatch_events();
+            schedule::run_triggers(self.card_manager);
+        }
+        response
+    }
+    
+    fn list(&self, request: &Request) -> Result<Response> {
+        let storage = StorageManager::new()?;
+        let backpack = backpack(request);
+        let entries: Vec<Value> = storage.list_entries(backpack)?.iter()
+            .take(limit(request))
+            .map(|entry| describe(entry, backpack))
+            .collect();
+        Ok(Response::json(200, json!(entries)))
+    }
+    
+    fn add(&self, request: &Request) -> Result<Response> {
+        let body: Value = match serde_json::from_slice(&request.body) {
+            Ok(body) => body,
+            Err(e) => return Ok(Response::error(400, format!("Body isn't valid JSON: {}", e))),
+        };
+        let Some(content) = body["content"].as_str() else {
+            return Ok(Response::error(400, "Missing content"));
+        };
+        let backpack = body["backpack"].as_str().filter(|name| !name.is_empty());
+        if let Some(Err(e)) = backpack.map(storage::validate_backpack_name) {
+            return Ok(Response::error(400, e));
+        }
+        
+        let storage = StorageManager::new()?;
+        let id = match add_entry(&storage, content, &body, backpack) {
+            Ok(id) => id,
+            Err(e) => return Ok(Response::error(400, e)),
+        };
+        let entry = storage.load_entry_metadata(&id, backpack)?;
+        Ok(Response::json(201, describe(&entry, backpack)))
+    }
+    
+    fn get(&self, reference: &str, request: &Request) -> Result<Response> {
+        let storage = StorageManager::new()?;
+        let (id, backpack) = match storage.resolve_entry(reference, backpack(request)) {
+            Ok(found) => found,
+            Err(e) => return Ok(Response::error(404, e)),
+        };
+        let entry = storage.load_entry_metadata(&id, backpack.as_deref())?;
+        let mut body = describe(&entry, backpack.as_deref());
+        if entry.is_binary() {
+            body["binary"] = json!(true);
+        } else {
+            body["content"] = json!(storage.load_entry(&id, backpack.as_deref())?.1);
+        }
+        Ok(Response::json(200, body))
+    }
+    
+    fn remove(&self, reference: &str, request: &Request) -> Result<Response> {
+        let storage = StorageManager::new()?;
+        if let Err(e) = storage.resolve_entry(reference, backpack(request)) {
+            return Ok(Response::error(404, e));
+        }
+        CoreCard::new(storage::pocket_dir()?).remove(reference, true, backpack(request))?;
+        Ok(Response { status: 204, body: None })
+    }
+    
+    fn backpacks(&self) -> Result<Response> {
+        let backpacks: Vec<Value> = StorageManager::new()?.list_backpacks()?.into_iter()
+            .map(|b| json!({ "name": b.name, "description": b.description }))
+            .collect();
+        Ok(Response::json(200, json!(backpacks)))
+    }
+    
+    fn search(&self, request: &Request) -> Result<Response> {
+        let Some(query) = request.query.get("q").filter(|q| !q.is_empty()) else {
+            return Ok(Response::error(400, "Missing query; use /search?q=TEXT"));
+        };
+        let results = search(&StorageManager::new()?, query, backpack(request), limit(request))?;
+        Ok(Response::json(200, json!(results)))
+    }
+}
+
+/// Whether a request carries the token. It's compared in constant time, so
+/// how long a wrong guess takes says nothing about how close it was
+fn authorized(request: &Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    let Some(given) = request.headers.get("authorization") else {
+        return false;
+    };
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// The backpack a request's query names, if it names one
+fn backpack(request: &Request) -> Option<&str> {
+    request.query.get("backpack").map(String::as_str).filter(|name| !name.is_empty())
+}
+
+/// The `limit` a request asks for, or the default
+fn limit(request: &Request) -> usize {
+    request.query.get("limit").and_then(|n| n.parse().ok()).unwrap_or(DEFAULT_LIMIT)
+}
+
+/// Reads a request line, headers and body
+fn read_request(reader: &mut impl BufRead) -> Result<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+    
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: url::form_urlencoded::parse(query.as_bytes()).into_owned().collect(),
+        ..Default::default()
+    };
+    
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            request.headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+    
+    let length: usize = request.headers.get("content-length")
+        .map(|n| n.parse())
+        .transpose()
+        .map_err(|_| anyhow!("Bad Content-Length"))?
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        bail!("Request body is over {} bytes", MAX_BODY_BYTES);
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body)?;
+    Ok(request)
+}
+
+/// Writes a response and closes the connection
+fn write_response(mut stream: impl Write, response: &Response) -> Result<()> {
+    let body = response.body.as_ref().map(|body| body.to_string()).unwrap_or_default();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+         Access-Control-Allow-Methods: GET, POST, DELETE, OPTIONS\r\n\
+         Connection: close\r\n\r\n{}",
+        status_text(response.status),
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// A status code with its reason phrase
+fn status_text(status: u16) -> String {
+    let reason = match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    format!("{} {}", status, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn request(raw: &str) -> Request {
+        read_request(&mut raw.as_bytes()).unwrap()
+    }
+    
+    #[test]
+    fn parses_requests() {
+        let parsed = request("POST /search?q=deploy%20staging&limit=5 HTTP/1.1\r\nAuthorization: Bearer abc\r\nContent-Length: 4\r\n\r\n{}xxEXTRA");
+        assert_eq!(parsed.method, "POST");
+        assert_eq!(parsed.path, "/search");
+        assert_eq!(parsed.query["q"], "deploy staging");
+        assert_eq!(limit(&parsed), 5);
+        assert_eq!(parsed.headers["authorization"], "Bearer abc");
+        assert_eq!(parsed.body, b"{}xx");
+        
+        assert!(read_request(&mut "\r\n".as_bytes()).is_err());
+        assert!(read_request(&mut "GET / HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n".as_bytes()).is_err());
+    }
+    
+    #[test]
+    fn checks_tokens_before_routing() {
+        let mut card_manager = CardManager::new(std::env::temp_dir().join("pocket-api-test"));
+        let mut server = ApiServer::new(&mut card_manager, "secret".to_string());
+        
+        let anonymous = server.respond(&request("GET /entries HTTP/1.1\r\n\r\n"));
+        assert_eq!(anonymous.status, 401);
+        let wrong = server.respond(&request("GET /entries HTTP/1.1\r\nAuthorization: Bearer guess\r\n\r\n"));
+        assert_eq!(wrong.status, 401);
+        
+        // Preflights don't carry the token
+        assert_eq!(server.respond(&request("OPTIONS /entries HTTP/1.1\r\n\r\n")).status, 204);
+        
+        let authorized = "HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n";
+        assert_eq!(server.respond(&request(&format!("GET /nothing {}", authorized))).status, 404);
+        assert_eq!(server.respond(&request(&format!("PUT /entries {}", authorized))).status, 405);
+        assert_eq!(server.respond(&request(&format!("GET /search {}", authorized))).status, 400);
+    }
+    
+    #[test]
+    fn refuses_backpacks_outside_the_pocket() {
+        let mut card_manager = CardManager::new(std::env::temp_dir().join("pocket-api-test"));
+        let mut server = ApiServer::new(&mut card_manager, "secret".to_string());
+        
+        let headers = "HTTP/1.1\r\nAuthorization: Bearer secret";
+        for target in ["/entries?backpack=../../x", "/entries/abc?backpack=..", "/search?q=a&backpack=..%2F..%2Fx"] {
+            assert_eq!(server.respond(&request(&format!("GET {} {}\r\n\r\n", target, headers))).status, 400, "{}", target);
+        }
+        assert_eq!(server.respond(&request(&format!("DELETE /entries/abc?backpack=../x {}\r\n\r\n", headers))).status, 400);
+        
+        let body = r#"{"content": "escaped", "backpack": "../../x"}"#;
+        let add = request(&format!("POST /entries {}\r\nContent-Length: {}\r\n\r\n{}", headers, body.len(), body));
+        assert_eq!(server.respond(&add).status, 400);
+    }
+}
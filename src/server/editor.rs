@@ -0,0 +1,171 @@
+//! `pocket serve-editor`: answers editor plugins over stdio
+//!
+//! Starting pocket for every keystroke is too slow for completion as you
+//! type, so this stays running with every entry in memory. Each line in is
+//! a JSON request, `{"id": 1, "method": "search", "params": {...}}`, and
+//! each line out answers one, `{"id": 1, "result": ...}` or
+//! `{"id": 1, "error": "..."}`.
+//!
+//! - `search` takes `query`, and optionally `language`, `backpack` and `limit`
+//! - `fetch` takes `id` and optionally `backpack`, and returns the content
+//! - `insert` takes `id`, and optionally `backpack`, `indent` (put before
+//!   every line but the first) and `delimiter` (a line to put first), and
+//!   returns the `text` to insert
+//! - `ping` answers with nothing, to check the server is up
+
+use super::describe;
+use super::index::{IndexFilter, IndexedEntry, SnippetIndex};
+use crate::storage::StorageManager;
+use crate::utils;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// Results a search returns when the request doesn't say
+const DEFAULT_LIMIT: usize = 20;
+
+/// Longest preview line sent with search results
+const PREVIEW_CHARS: usize = 80;
+
+/// Answers editor queries from an index it keeps up to date
+pub struct EditorServer {
+    storage: StorageManager,
+    index: SnippetIndex,
+}
+
+impl EditorServer {
+    pub fn new() -> Result<Self> {
+        let storage = StorageManager::new()?;
+        let mut index = SnippetIndex::new();
+        index.refresh(&storage)?;
+        Ok(Self { storage, index })
+    }
+    
+    /// Reads requests from `input` and writes answers to `output` until the
+    /// input closes
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(request) => self.handle(&request),
+                Err(e) => json!({ "id": null, "error": format!("Invalid JSON: {}", e) }),
+            };
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+        Ok(())
+    }
+    
+    /// Answers one request
+    pub fn handle(&mut self, request: &Value) -> Value {
+        let id = request["id"].clone();
+        match self.answer(request["method"].as_str().unwrap_or_default(), &request["params"]) {
+            Ok(result) => json!({ "id": id, "result": result }),
+            Err(e) => json!({ "id": id, "error": e.to_string() }),
+        }
+    }
+    
+    fn answer(&mut self, method: &str, params: &Value) -> Result<Value> {
+        if method == "ping" {
+            return Ok(Value::Null);
+        }
+        self.index.refresh(&self.storage)?;
+        let backpack = params["backpack"].as_str();
+        
+        match method {
+            "search" => {
+                let language = params["language"].as_str()
+                    .map(|name| utils::normalize_language(name).ok_or_else(|| anyhow!("Unknown language: {}", name)))
+                    .transpose()?;
+                let limit = params["limit"].as_u64().map(|n| n as usize).unwrap_or(DEFAULT_LIMIT);
+                let query = params["query"].as_str().unwrap_or_default();
+                let results: Vec<Value> = self.index.search(query, IndexFilter { language, backpack }, limit)
+                    .into_iter()
+                    .map(|indexed| {
+                        let mut result = describe(&indexed.entry, indexed.backpack.as_deref());
+                        result["preview"] = json!(preview(&indexed.content));
+                        result
+                    })
+                    .collect();
+                Ok(json!(results))
+            },
+            "fetch" => {
+                let indexed = self.find(params, backpack)?;
+                let mut result = describe(&indexed.entry, indexed.backpack.as_deref());
+                result["content"] = json!(indexed.content);
+                Ok(result)
+            },
+            "insert" => {
+                let indexed = self.find(params, backpack)?;
+                let text = render_insert(&indexed.content, params["indent"].as_str(), params["delimiter"].as_str());
+                let (id, backpack) = (indexed.entry.id.clone(), indexed.backpack.clone());
+                self.storage.record_usage(&id, backpack.as_deref())?;
+                Ok(json!({ "text": text }))
+            },
+            _ => Err(anyhow!("Unknown method: {}", method)),
+        }
+    }
+    
+    /// The entry a request's `id` names
+    fn find(&self, params: &Value, backpack: Option<&str>) -> Result<&IndexedEntry> {
+        let reference = params["id"].as_str().ok_or_else(|| anyhow!("Missing id"))?;
+        self.index.find(&self.storage, reference, backpack)
+    }
+}
+
+/// The first line with something on it, shortened to fit a completion menu
+fn preview(content: &str) -> String {
+    let line = content.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+    if line.chars().count() > PREVIEW_CHARS {
+        format!("{}...", line.chars().take(PREVIEW_CHARS - 3).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Content as it should go in at the cursor: after an optional delimiter
+/// line, with every line after the first indented to match where it goes
+pub(super) fn render_insert(content: &str, indent: Option<&str>, delimiter: Option<&str>) -> String {
+    let content = content.trim_end_matches(['\n', '\r']);
+    let mut lines: Vec<&str> = Vec::new();
+    if let Some(delimiter) = delimiter {
+        lines.push(delimiter);
+    }
+    lines.extend(content.lines());
+    
+    let indent = indent.unwrap_or_default();
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+            if !line.is_empty() {
+                text.push_str(indent);
+            }
+        }
+        text.push_str(line);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn renders_inserts_to_fit_the_cursor() {
+        let content = "if ready {\n    go();\n\n}\n";
+        assert_eq!(render_insert(content, None, None), "if ready {\n    go();\n\n}");
+        assert_eq!(render_insert(content, Some("    "), None), "if ready {\n        go();\n\n    }");
+        assert_eq!(render_insert("x = 1\n", Some("  "), Some("# from pocket")), "# from pocket\n  x = 1");
+    }
+    
+    #[test]
+    fn previews_the_first_real_line() {
+        assert_eq!(preview("\n\n   let x = 1;\nlet y = 2;"), "let x = 1;");
+        assert_eq!(preview(&"a".repeat(100)).chars().count(), PREVIEW_CHARS);
+        assert_eq!(preview(""), "");
+    }
+}
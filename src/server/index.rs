@@ -0,0 +1,159 @@
+//! Every text entry held in memory, for servers that answer queries as
+//! fast as someone types
+
+use crate::models::Entry;
+use crate::storage::StorageManager;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::time::SystemTime;
+
+/// An entry with its content, ready to search
+#[derive(Debug, Clone)]
+pub struct IndexedEntry {
+    pub entry: Entry,
+    pub backpack: Option<String>,
+    pub content: String,
+    title_lower: String,
+    content_lower: String,
+}
+
+impl IndexedEntry {
+    pub fn new(entry: Entry, backpack: Option<String>, content: String) -> Self {
+        Self {
+            title_lower: entry.title.to_lowercase(),
+            content_lower: content.to_lowercase(),
+            entry,
+            backpack,
+            content,
+        }
+    }
+    
+    /// How well the entry matches lower-cased search terms, or None if some
+    /// term isn't in it at all. Title matches count for more than content
+    /// matches, and a title starting with the first term most of all
+    fn score(&self, terms: &[String]) -> Option<u32> {
+        let mut score = 0;
+        for term in terms {
+            score += match (self.title_lower.contains(term.as_str()), self.content_lower.contains(term.as_str())) {
+                (true, _) => 3,
+                (false, true) => 1,
+                (false, false) => return None,
+            };
+        }
+        if terms.first().is_some_and(|term| self.title_lower.starts_with(term.as_str())) {
+            score += 5;
+        }
+        Some(score)
+    }
+}
+
+/// What a search is limited to
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IndexFilter<'a> {
+    /// Canonical language name entries must be in
+    pub language: Option<&'a str>,
+    
+    /// Backpack entries must be in. None searches everywhere
+    pub backpack: Option<&'a str>,
+}
+
+/// Every text entry in the pocket, reloaded when storage changes
+#[derive(Default)]
+pub struct SnippetIndex {
+    entries: Vec<IndexedEntry>,
+    pins: HashSet<String>,
+    fingerprint: Option<(usize, Option<SystemTime>)>,
+}
+
+impl SnippetIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Reloads everything if storage changed since the last load. Returns
+    /// whether it did
+    pub fn refresh(&mut self, storage: &StorageManager) -> Result<bool> {
+        let fingerprint = storage.data_fingerprint()?;
+        if self.fingerprint == Some(fingerprint) {
+            return Ok(false);
+        }
+        
+        let mut entries = Vec::new();
+        for (entry, backpack) in storage.list_all_entries(None)? {
+            if entry.is_binary() {
+                continue;
+            }
+            if let Ok((entry, content)) = storage.load_entry(&entry.id, backpack.as_deref()) {
+                entries.push(IndexedEntry::new(entry, backpack, content));
+            }
+        }
+        self.entries = entries;
+        self.pins = storage.pinned_ids()?;
+        self.fingerprint = Some(fingerprint);
+        Ok(true)
+    }
+    
+    /// Entries matching every word of `query`, best first. Pinned entries
+    /// win ties, then the most recently updated. An empty query matches all
+    pub fn search(&self, query: &str, filter: IndexFilter, limit: usize) -> Vec<&IndexedEntry> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let mut matches: Vec<(u32, &IndexedEntry)> = self.entries.iter()
+            .filter(|indexed| filter.language.is_none_or(|language| indexed.entry.language.as_deref() == Some(language)))
+            .filter(|indexed| filter.backpack.is_none_or(|backpack| indexed.backpack.as_deref() == Some(backpack)))
+            .filter_map(|indexed| indexed.score(&terms).map(|score| (score, indexed)))
+            .collect();
+        
+        matches.sort_by(|(score_a, a), (score_b, b)| {
+            score_b.cmp(score_a)
+                .then_with(|| self.pins.contains(&b.entry.id).cmp(&self.pins.contains(&a.entry.id)))
+                .then_with(|| b.entry.updated_at.cmp(&a.entry.updated_at))
+        });
+        matches.into_iter().take(limit).map(|(_, indexed)| indexed).collect()
+    }
+    
+    /// Looks an entry up by ID, ID prefix or alias
+    pub fn find(&self, storage: &StorageManager, reference: &str, backpack: Option<&str>) -> Result<&IndexedEntry> {
+        let (id, backpack) = storage.resolve_entry(reference, backpack)?;
+        self.entries.iter()
+            .find(|indexed| indexed.entry.id == id && indexed.backpack == backpack)
+            .ok_or_else(|| anyhow::anyhow!("'{}' is a binary attachment", reference))
+    }
+    
+    #[cfg(test)]
+    pub(crate) fn with_entries(entries: Vec<IndexedEntry>) -> Self {
+        Self { entries, ..Self::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentType;
+    
+    fn indexed(title: &str, language: &str, backpack: Option<&str>, content: &str) -> IndexedEntry {
+        let mut entry = Entry::new(title.to_string(), ContentType::Code, None, vec![]);
+        entry.language = Some(language.to_string());
+        IndexedEntry::new(entry, backpack.map(str::to_string), content.to_string())
+    }
+    
+    #[test]
+    fn ranks_title_matches_first() {
+        let mut older = indexed("retry loop", "rust", None, "loop { fetch() }");
+        older.entry.updated_at -= chrono::Duration::minutes(1);
+        let index = SnippetIndex::with_entries(vec![
+            older,
+            indexed("fetch with retry", "rust", Some("work"), "fn fetch() {}"),
+            indexed("Download json", "javascript", None, "fetch(url).then(r => r.json())"),
+        ]);
+        
+        let titles = |results: Vec<&IndexedEntry>| results.iter().map(|i| i.entry.title.clone()).collect::<Vec<_>>();
+        assert_eq!(titles(index.search("fetch", IndexFilter::default(), 10)), ["fetch with retry", "Download json", "retry loop"]);
+        assert_eq!(titles(index.search("fetch retry", IndexFilter::default(), 10)), ["fetch with retry", "retry loop"]);
+        
+        let rust = IndexFilter { language: Some("rust"), ..Default::default() };
+        assert_eq!(titles(index.search("fetch", rust, 1)), ["fetch with retry"]);
+        let work = IndexFilter { backpack: Some("work"), ..Default::default() };
+        assert_eq!(index.search("", work, 10).len(), 1);
+        assert!(index.search("nothing like this", IndexFilter::default(), 10).is_empty());
+    }
+}
@@ -0,0 +1,279 @@
+//! `pocket lsp`: a minimal language server offering snippets as completions
+//!
+//! Editors start it over stdio like any other language server. It keeps the
+//! text of open files, and when asked for completions offers entries in the
+//! file's language that match the word before the cursor. Everything else
+//! in the protocol is left to real language servers.
+
+use super::editor::render_insert;
+use super::index::{IndexFilter, SnippetIndex};
+use crate::storage::StorageManager;
+use crate::utils;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// Most completions offered at once
+const MAX_COMPLETIONS: usize = 30;
+
+/// LSP's `CompletionItemKind.Snippet`
+const SNIPPET_KIND: u32 = 15;
+
+/// JSON-RPC error code for methods the server doesn't handle
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// A file the editor has open
+struct Document {
+    language: Option<&'static str>,
+    text: String,
+}
+
+/// Answers an editor's language server requests
+pub struct LspServer {
+    storage: StorageManager,
+    index: SnippetIndex,
+    documents: HashMap<String, Document>,
+}
+
+impl LspServer {
+    pub fn new() -> Result<Self> {
+        Ok(Self { storage: StorageManager::new()?, index: SnippetIndex::new(), documents: HashMap::new() })
+    }
+    
+    /// Answers messages until the editor sends `exit` or closes the input
+    pub fn serve(&mut self, mut input: impl BufRead, mut output: impl Write) -> Result<()> {
+        while let Some(message) = read_message(&mut input)? {
+            if message["method"] == "exit" {
+                break;
+            }
+            if let Some(response) = self.handle(&message) {
+                write_message(&mut output, &response)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Answers one message. Notifications get no answer
+    fn handle(&mut self, message: &Value) -> Option<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "completionProvider": { "resolveProvider": false },
+                },
+                "serverInfo": { "name": "pocket", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "shutdown" => Value::Null,
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                let uri = document["uri"].as_str().unwrap_or_default();
+                self.documents.insert(uri.to_string(), Document {
+                    language: file_language(document["languageId"].as_str().unwrap_or_default(), uri),
+                    text: document["text"].as_str().unwrap_or_default().to_string(),
+                });
+                return None;
+            },
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                // Full sync: the last change holds the whole text
+                let text = params["contentChanges"].as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let (Some(document), Some(text)) = (self.documents.get_mut(uri), text) {
+                    document.text = text.to_string();
+                }
+                return None;
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(params["textDocument"]["uri"].as_str().unwrap_or_default());
+                return None;
+            },
+            "textDocument/completion" => match self.complete(params) {
+                Ok(result) => result,
+                Err(e) => {
+                    log::warn!("Completion failed: {}", e);
+                    json!({ "isIncomplete": true, "items": [] })
+                }
+            },
+            _ => {
+                // Unknown notifications are fine to ignore; unknown requests need an answer
+                let id = message.get("id")?;
+                return Some(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": METHOD_NOT_FOUND, "message": format!("Unsupported method: {}", method) },
+                }));
+            },
+        };
+        message.get("id").map(|id| json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+    
+    fn complete(&mut self, params: &Value) -> Result<Value> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let document = self.documents.get(uri).ok_or_else(|| anyhow!("{} isn't open", uri))?;
+        let line = params["position"]["line"].as_u64().unwrap_or_default() as usize;
+        let character = params["position"]["character"].as_u64().unwrap_or_default() as usize;
+        
+        self.index.refresh(&self.storage)?;
+        let items = completion_items(&self.index, document, line, character);
+        // The list is filtered here rather than by the editor, so ask again as the word grows
+        Ok(json!({ "isIncomplete": true, "items": items }))
+    }
+}
+
+/// Completion items for the word that ends at a position, in LSP's
+/// UTF-16 columns
+fn completion_items(index: &SnippetIndex, document: &Document, line: usize, character: usize) -> Vec<Value> {
+    let text = document.text.lines().nth(line).unwrap_or_default();
+    let (word, start) = word_before(text, character);
+    if word.is_empty() {
+        return Vec::new();
+    }
+    let indent: String = text.chars().take_while(|c| c.is_whitespace()).collect();
+    let range = json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": character },
+    });
+    
+    let filter = IndexFilter { language: document.language, backpack: None };
+    index.search(word, filter, MAX_COMPLETIONS).into_iter().enumerate()
+        .map(|(rank, indexed)| {
+            let language = indexed.entry.language.as_deref().unwrap_or_default();
+            json!({
+                "label": indexed.entry.title,
+                "kind": SNIPPET_KIND,
+                "detail": match &indexed.backpack {
+                    Some(backpack) => format!("pocket ({})", backpack),
+                    None => "pocket".to_string(),
+                },
+                "documentation": { "kind": "markdown", "value": format!("```{}\n{}\n```", language, indexed.content.trim_end()) },
+                // Ranked and filtered already, so the editor shows them as they are
+                "filterText": word,
+                "sortText": format!("{:04}", rank),
+                "textEdit": { "range": range, "newText": render_insert(&indexed.content, Some(&indent), None) },
+            })
+        })
+        .collect()
+}
+
+/// The word (letters, digits, `_` and `-`) ending at a UTF-16 column, and
+/// the column it starts at
+fn word_before(line: &str, character: usize) -> (&str, usize) {
+    let mut column = 0;
+    let mut end = line.len();
+    for (index, c) in line.char_indices() {
+        if column >= character {
+            end = index;
+            break;
+        }
+        column += c.len_utf16();
+    }
+    
+    let before = &line[..end];
+    let start = before.char_indices().rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .last()
+        .map(|(index, _)| index)
+        .unwrap_or(end);
+    let start_column = line[..start].chars().map(char::len_utf16).sum();
+    (&line[start..end], start_column)
+}
+
+/// The canonical language of a file, from the editor's language ID or else
+/// the file's extension
+fn file_language(language_id: &str, uri: &str) -> Option<&'static str> {
+    let extension = uri.rsplit('/').next().and_then(|name| name.rsplit_once('.')).map(|(_, ext)| ext);
+    utils::normalize_language(language_id)
+        .or_else(|| (language_id == "shellscript").then_some("shell"))
+        .or_else(|| extension.and_then(utils::normalize_language))
+}
+
+/// Reads one `Content-Length`-framed message, or None at the end of input
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = Some(value.trim().parse::<usize>().map_err(|_| anyhow!("Bad Content-Length: {}", value.trim()))?);
+            }
+        }
+    }
+    
+    let mut body = vec![0; length.unwrap_or_default()];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Writes one `Content-Length`-framed message
+fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentType, Entry};
+    use crate::server::index::IndexedEntry;
+    
+    #[test]
+    fn finds_the_word_before_the_cursor() {
+        assert_eq!(word_before("    let ret", 11), ("ret", 8));
+        assert_eq!(word_before("foo(bar_baz", 11), ("bar_baz", 4));
+        assert_eq!(word_before("x = ", 4), ("", 4));
+        // Columns count UTF-16 code units: the emoji takes two
+        assert_eq!(word_before("🎉 héllo", 5), ("hé", 3));
+    }
+    
+    #[test]
+    fn offers_snippets_in_the_files_language() {
+        let entry = |title: &str, language: &str, content: &str| {
+            let mut entry = Entry::new(title.to_string(), ContentType::Code, None, vec![]);
+            entry.language = Some(language.to_string());
+            IndexedEntry::new(entry, None, content.to_string())
+        };
+        let index = SnippetIndex::with_entries(vec![
+            entry("retry loop", "rust", "loop {\n    try_it();\n}\n"),
+            entry("retry decorator", "python", "def retry(f):\n    return f\n"),
+        ]);
+        
+        let document = Document { language: file_language("rust", "file:///src/main.rs"), text: "fn main() {\n    ret\n}".to_string() };
+        let items = completion_items(&index, &document, 1, 7);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0]["label"], "retry loop");
+        assert_eq!(items[0]["textEdit"]["range"]["start"]["character"], 4);
+        assert_eq!(items[0]["textEdit"]["newText"], "loop {\n        try_it();\n    }");
+        
+        assert!(completion_items(&index, &document, 0, 0).is_empty());
+        assert_eq!(file_language("shellscript", "file:///x"), Some("shell"));
+        assert_eq!(file_language("plaintext", "file:///notes/a.py"), Some("python"));
+    }
+    
+    #[test]
+    fn frames_messages() {
+        let mut output = Vec::new();
+        write_message(&mut output, &json!({ "id": 1 })).unwrap();
+        assert_eq!(String::from_utf8(output.clone()).unwrap(), "Content-Length: 8\r\n\r\n{\"id\":1}");
+        
+        let mut input = output.as_slice();
+        assert_eq!(read_message(&mut input).unwrap().unwrap()["id"], 1);
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+}
@@ -0,0 +1,234 @@
+//! `pocket mcp-serve`: the Model Context Protocol over stdio
+//!
+//! Assistants that speak MCP start this as a subprocess and send it
+//! newline-delimited JSON-RPC 2.0 messages. Stdout carries nothing but
+//! responses, so nothing the tools call may print.
+
+use super::{add_entry, describe, search};
+use crate::cards::{events, CardManager};
+use crate::storage::StorageManager;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// The protocol revision this server speaks
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Results a search returns when the caller doesn't say
+const DEFAULT_LIMIT: usize = 10;
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Answers MCP requests about the pocket
+pub struct McpServer<'a> {
+    card_manager: &'a CardManager,
+}
+
+impl<'a> McpServer<'a> {
+    pub fn new(card_manager: &'a CardManager) -> Self {
+        Self { card_manager }
+    }
+    
+    /// Reads requests from `input` and writes responses to `output` until
+    /// the input closes
+    pub fn serve(&self, input: impl BufRead, mut output: impl Write) -> Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(&message),
+                Err(e) => Some(error(Value::Null, PARSE_ERROR, &format!("Invalid JSON: {}", e))),
+            };
+            if let Some(response) = response {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Answers one message. Notifications, which have no ID, get no answer
+    pub fn handle(&self, message: &Value) -> Option<Value> {
+        let id = message.get("id").cloned()?;
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        
+        let result = match method {
+            "initialize" => json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "pocket", "version": env!("CARGO_PKG_VERSION") },
+            }),
+            "ping" => json!({}),
+            "tools/list" => json!({ "tools": tools() }),
+            "tools/call" => {
+                let name = params["name"].as_str().unwrap_or_default();
+                if !tools().iter().any(|tool| tool["name"] == name) {
+                    return Some(error(id, INVALID_PARAMS, &format!("Unknown tool: {}", name)));
+                }
+                let empty = json!({});
+                let arguments = params.get("arguments").unwrap_or(&empty);
+                match self.call(name, arguments) {
+                    Ok(text) => json!({ "content": [{ "type": "text", "text": text }], "isError": false }),
+                    Err(e) => json!({ "content": [{ "type": "text", "text": e.to_string() }], "isError": true }),
+                }
+            },
+            _ => return Some(error(id, METHOD_NOT_FOUND, &format!("Unknown method: {}", method))),
+        };
+        Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+    }
+    
+    /// Runs a tool and returns its answer as text
+    fn call(&self, name: &str, arguments: &Value) -> Result<String> {
+        let storage = StorageManager::new()?;
+        let backpack = arguments["backpack"].as_str();
+        
+        match name {
+            "search_snippets" => {
+                let query = required(arguments, "query")?;
+                let limit = arguments["limit"].as_u64().map(|n| n as usize).unwrap_or(DEFAULT_LIMIT);
+                let results = search(&storage, query, backpack, limit)?;
+                Ok(serde_json::to_string_pretty(&results)?)
+            },
+            "get_snippet" => {
+                let (id, backpack) = storage.resolve_entry(required(arguments, "id")?, backpack)?;
+                let (entry, content) = storage.load_entry(&id, backpack.as_deref())?;
+                if entry.is_binary() {
+                    return Err(anyhow!("'{}' is a binary attachment", entry.title));
+                }
+                let mut snippet = describe(&entry, backpack.as_deref());
+                snippet["content"] = json!(content);
+                Ok(serde_json::to_string_pretty(&snippet)?)
+            },
+            "add_snippet" => {
+                let content = required(arguments, "content")?;
+                let id = add_entry(&storage, content, arguments, backpack)?;
+                self.card_manager.dispatch_events();
+                // Workflow triggers print as they run, which would garble
+                // the protocol, so they don't run for entries added here
+                events::take_fired();
+                Ok(format!("Added snippet with ID: {}", id))
+            },
+            "list_backpacks" => {
+                let backpacks: Vec<Value> = storage.list_backpacks()?.into_iter()
+                    .map(|b| json!({ "name": b.name, "description": b.description }))
+                    .collect();
+                Ok(serde_json::to_string_pretty(&backpacks)?)
+            },
+            _ => Err(anyhow!("Unknown tool: {}", name)),
+        }
+    }
+}
+
+/// The tools the server offers, with the JSON Schema of their arguments
+fn tools() -> Vec<Value> {
+    let backpack = json!({ "type": "string", "description": "Backpack the snippet is in" });
+    vec![
+        json!({
+            "name": "search_snippets",
+            "description": "Search saved snippets by title and content. Searches every backpack unless one is given",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to look for" },
+                    "backpack": { "type": "string", "description": "Only search this backpack" },
+                    "limit": { "type": "integer", "description": "Most results to return (default 10)" },
+                },
+                "required": ["query"],
+            },
+        }),
+        json!({
+            "name": "get_snippet",
+            "description": "Get a snippet's content and details by ID, ID prefix or alias",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "id": { "type": "string" }, "backpack": backpack },
+                "required": ["id"],
+            },
+        }),
+        json!({
+            "name": "add_snippet",
+            "description": "Save a new snippet",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "content": { "type": "string" },
+                    "title": { "type": "string", "description": "Defaults to the first line" },
+                    "language": { "type": "string", "description": "Detected from the content when left out" },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "backpack": { "type": "string", "description": "Backpack to save in" },
+                },
+                "required": ["content"],
+            },
+        }),
+        json!({
+            "name": "list_backpacks",
+            "description": "List backpacks and their descriptions",
+            "inputSchema": { "type": "object", "properties": {} },
+        }),
+    ]
+}
+
+/// A string argument a tool can't do without
+fn required<'v>(arguments: &'v Value, name: &str) -> Result<&'v str> {
+    arguments[name].as_str().ok_or_else(|| anyhow!("Missing argument: {}", name))
+}
+
+/// A JSON-RPC error response
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn request(method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params })
+    }
+    
+    #[test]
+    fn answers_protocol_requests() {
+        let card_manager = CardManager::new(std::env::temp_dir().join("pocket-mcp-test"));
+        let server = McpServer::new(&card_manager);
+        
+        let init = server.handle(&request("initialize", json!({ "protocolVersion": PROTOCOL_VERSION }))).unwrap();
+        assert_eq!(init["id"], 1);
+        assert_eq!(init["result"]["serverInfo"]["name"], "pocket");
+        
+        // Notifications get no answer
+        assert!(server.handle(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).is_none());
+        
+        let list = server.handle(&request("tools/list", json!({}))).unwrap();
+        let names: Vec<&str> = list["result"]["tools"].as_array().unwrap().iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["search_snippets", "get_snippet", "add_snippet", "list_backpacks"]);
+        
+        let unknown = server.handle(&request("resources/list", json!({}))).unwrap();
+        assert_eq!(unknown["error"]["code"], METHOD_NOT_FOUND);
+        let unknown = server.handle(&request("tools/call", json!({ "name": "shove" }))).unwrap();
+        assert_eq!(unknown["error"]["code"], INVALID_PARAMS);
+    }
+    
+    #[test]
+    fn writes_one_line_per_response() {
+        let card_manager = CardManager::new(std::env::temp_dir().join("pocket-mcp-test"));
+        let server = McpServer::new(&card_manager);
+        let input = "{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"ping\"}\nnot json\n\n";
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).unwrap();
+        
+        let lines: Vec<Value> = String::from_utf8(output).unwrap().lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["id"], 7);
+        assert_eq!(lines[1]["error"]["code"], PARSE_ERROR);
+    }
+}
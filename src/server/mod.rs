@@ -0,0 +1,80 @@
+//! Long-running servers that let other programs use the pocket
+
+pub mod api;
+pub mod editor;
+pub mod index;
+pub mod lsp;
+pub mod mcp;
+
+use crate::cards::{events, Event};
+use crate::models::Entry;
+use crate::storage::{validate_backpack_name, StorageManager};
+use crate::utils::{self, SummaryMetadata};
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+
+/// Searches one backpack, or the general pocket and every backpack
+fn search(storage: &StorageManager, query: &str, backpack: Option<&str>, limit: usize) -> Result<Vec<Value>> {
+    let locations = match backpack {
+        Some(name) => vec![Some(name.to_string())],
+        None => std::iter::once(None)
+            .chain(storage.list_backpacks()?.into_iter().map(|b| Some(b.name)))
+            .collect(),
+    };
+    
+    let mut results = Vec::new();
+    for location in &locations {
+        for (entry, _) in storage.search_entries(query, location.as_deref(), limit)? {
+            results.push(describe(&entry, location.as_deref()));
+        }
+    }
+    results.truncate(limit);
+    Ok(results)
+}
+
+/// An entry's details, without its content
+fn describe(entry: &Entry, backpack: Option<&str>) -> Value {
+    let summary = entry.get_metadata("summary")
+        .and_then(|json| SummaryMetadata::from_json(json).ok())
+        .map(|summary| summary.summary.trim().to_string())
+        .filter(|summary| !summary.is_empty());
+    json!({
+        "id": entry.id,
+        "title": entry.title,
+        "backpack": backpack,
+        "language": entry.language,
+        "tags": entry.tags,
+        "summary": summary,
+        "updated_at": entry.updated_at.to_rfc3339(),
+    })
+}
+
+/// Saves a new text entry from a JSON object with its `title`, `language`
+/// and `tags`, any of which can be left out
+fn add_entry(storage: &StorageManager, content: &str, arguments: &Value, backpack: Option<&str>) -> Result<String> {
+    if content.trim().is_empty() {
+        return Err(anyhow!("Content is empty"));
+    }
+    if let Some(name) = backpack {
+        validate_backpack_name(name)?;
+    }
+    let title = arguments["title"].as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| content.lines().next().unwrap_or_default().to_string());
+    let tags = arguments["tags"].as_array().into_iter().flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect();
+    
+    let mut entry = Entry::new(title, utils::detect_content_type(None, Some(content)), None, tags);
+    entry.language = match arguments["language"].as_str() {
+        Some(language) => Some(utils::normalize_language(language)
+            .ok_or_else(|| anyhow!("Unknown language: {}", language))?
+            .to_string()),
+        None => utils::detect_language(None, content),
+    };
+    
+    storage.save_entry(&entry, content, backpack)?;
+    events::emit(Event::EntryAdded { id: entry.id.clone(), backpack: backpack.map(str::to_string) });
+    Ok(entry.id)
+}
@@ -0,0 +1,84 @@
+//! The audit log: one line for every command that changed the pocket
+//!
+//! `audit.log` in the pocket directory is only ever appended to, one JSON
+//! object per line, so it can be read with `pocket audit show` or any tool
+//! that handles JSON lines.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// The audit log, in the pocket directory
+pub const AUDIT_FILE: &str = "audit.log";
+
+/// A command that changed the pocket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub time: DateTime<Utc>,
+    /// The command line, without the program name
+    pub args: Vec<String>,
+    /// Whether it succeeded
+    pub ok: bool,
+    /// Why it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Adds an event to the end of the log
+pub fn append(base: &Path, event: &AuditEvent) -> Result<()> {
+    let path = base.join(AUDIT_FILE);
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    // One write, so lines from pockets running side by side don't interleave
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Events from `since` on, oldest first. Lines that don't parse are skipped
+pub fn read(base: &Path, since: Option<DateTime<Utc>>) -> Result<Vec<AuditEvent>> {
+    let path = base.join(AUDIT_FILE);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    Ok(text.lines()
+        .filter_map(|line| serde_json::from_str::<AuditEvent>(line).ok())
+        .filter(|event| since.is_none_or(|since| event.time >= since))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(args: &str, time: DateTime<Utc>) -> AuditEvent {
+        AuditEvent {
+            time,
+            args: args.split_whitespace().map(str::to_string).collect(),
+            ok: true,
+            error: None,
+            duration_ms: 3,
+        }
+    }
+
+    #[test]
+    fn appends_and_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let earlier = Utc::now() - chrono::Duration::days(2);
+        append(dir.path(), &event("add notes.md", earlier)).unwrap();
+        append(dir.path(), &event("remove 94b3", Utc::now())).unwrap();
+        fs::OpenOptions::new().append(true).open(dir.path().join(AUDIT_FILE)).unwrap()
+            .write_all(b"not json\n").unwrap();
+
+        assert_eq!(read(dir.path(), None).unwrap().len(), 2);
+        let recent = read(dir.path(), Some(Utc::now() - chrono::Duration::days(1))).unwrap();
+        assert_eq!(recent, vec![event("remove 94b3", recent[0].time)]);
+    }
+}
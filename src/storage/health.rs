@@ -0,0 +1,208 @@
+//! Finding damage in the stored data, and repairing what can be repaired
+//! without losing anything, for `pocket doctor`
+//!
+//! Nothing is ever deleted: files that can't be made sense of are moved to
+//! `lost+found/` in the pocket directory, where they can be looked at and
+//! put back by hand.
+
+use super::StorageManager;
+use crate::models::Entry;
+use crate::utils::atomic;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory in the pocket directory that repairs move files to
+pub const LOST_AND_FOUND: &str = "lost+found";
+
+/// Directories every pocket has
+const REQUIRED_DIRS: &[&str] = &["data/entries", "data/backpacks", "data/workflows"];
+
+/// Something wrong with the stored data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// A directory pocket expects is missing
+    MissingDir(PathBuf),
+    /// Entry metadata with no content file next to it
+    MissingContent(PathBuf),
+    /// A content file with no metadata next to it
+    OrphanedContent(PathBuf),
+    /// Entry metadata that doesn't parse
+    BrokenMetadata { path: PathBuf, error: String },
+    /// A temporary file an interrupted write left behind
+    LeftoverTempFile(PathBuf),
+    /// An alias for an entry that's gone
+    DanglingAlias { name: String, id: String },
+    /// A pin on an entry that's gone
+    DanglingPin(String),
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MissingDir(path) => write!(f, "Missing directory {}", path.display()),
+            Issue::MissingContent(path) => write!(f, "Entry metadata without content: {}", path.display()),
+            Issue::OrphanedContent(path) => write!(f, "Content without entry metadata: {}", path.display()),
+            Issue::BrokenMetadata { path, error } => write!(f, "Unreadable entry metadata {}: {}", path.display(), error),
+            Issue::LeftoverTempFile(path) => write!(f, "Leftover from an interrupted write: {}", path.display()),
+            Issue::DanglingAlias { name, id } => write!(f, "Alias '{}' points at missing entry {}", name, id),
+            Issue::DanglingPin(id) => write!(f, "Pinned entry {} no longer exists", id),
+        }
+    }
+}
+
+impl StorageManager {
+    /// Everything wrong with the stored data
+    pub fn check_health(&self) -> Result<Vec<Issue>> {
+        let mut issues = Vec::new();
+        for dir in REQUIRED_DIRS {
+            let path = self.base_path.join(dir);
+            if !path.is_dir() {
+                issues.push(Issue::MissingDir(path));
+            }
+        }
+
+        let mut locations = vec![None];
+        if self.base_path.join("data/backpacks").is_dir() {
+            locations.extend(self.list_backpacks()?.into_iter().map(|b| Some(b.name)));
+        }
+        let mut ids = HashSet::new();
+        for location in &locations {
+            ids.extend(check_entry_dir(&self.get_entries_dir(location.as_deref()), &mut issues)?);
+        }
+
+        for (name, alias) in self.load_aliases()? {
+            if !ids.contains(&alias.id) {
+                issues.push(Issue::DanglingAlias { name, id: alias.id });
+            }
+        }
+        for id in self.load_pins()?.into_keys() {
+            if !ids.contains(&id) {
+                issues.push(Issue::DanglingPin(id));
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Repairs an issue, returning what was done
+    pub fn repair(&self, issue: &Issue) -> Result<String> {
+        match issue {
+            Issue::MissingDir(path) => {
+                fs::create_dir_all(path)?;
+                Ok(format!("Created {}", path.display()))
+            },
+            Issue::MissingContent(path)
+            | Issue::OrphanedContent(path)
+            | Issue::BrokenMetadata { path, .. } => {
+                // The index notices the file is gone on its own
+                let moved = self.move_to_lost_and_found(path)?;
+                Ok(format!("Moved {} to {}", path.display(), moved.display()))
+            },
+            Issue::LeftoverTempFile(path) => {
+                fs::remove_file(path)?;
+                Ok(format!("Removed {}", path.display()))
+            },
+            Issue::DanglingAlias { name, .. } => {
+                let mut aliases = self.load_aliases()?;
+                aliases.remove(name);
+                self.save_aliases(&aliases)?;
+                Ok(format!("Removed alias '{}'", name))
+            },
+            Issue::DanglingPin(id) => {
+                let mut pins = self.load_pins()?;
+                pins.remove(id);
+                self.save_pins(&pins)?;
+                Ok(format!("Unpinned {}", id))
+            },
+        }
+    }
+
+    /// Moves a file to `lost+found/`, keeping where it was in the name
+    fn move_to_lost_and_found(&self, path: &Path) -> Result<PathBuf> {
+        let relative = path.strip_prefix(&self.base_path).unwrap_or(path);
+        let target = self.base_path.join(LOST_AND_FOUND).join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(path, &target)
+            .with_context(|| format!("Failed to move {}", path.display()))?;
+        Ok(target)
+    }
+}
+
+/// Checks the entries in one directory, returning the IDs of the good ones
+fn check_entry_dir(dir: &Path, issues: &mut Vec<Issue>) -> Result<HashSet<String>> {
+    let mut ids = HashSet::new();
+    if !dir.is_dir() {
+        return Ok(ids);
+    }
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        if !path.is_file() {
+            continue;
+        }
+        if atomic::is_temp(&path) {
+            issues.push(Issue::LeftoverTempFile(path));
+            continue;
+        }
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => {
+                let parsed = fs::read_to_string(&path).map_err(anyhow::Error::from)
+                    .and_then(|json| Ok(serde_json::from_str::<Entry>(&json)?));
+                match parsed {
+                    Err(e) => issues.push(Issue::BrokenMetadata { path, error: e.to_string() }),
+                    Ok(_) if !path.with_extension("content").is_file() => issues.push(Issue::MissingContent(path)),
+                    Ok(entry) => {
+                        ids.insert(entry.id);
+                    },
+                }
+            },
+            Some("content") if !path.with_extension("json").is_file() => issues.push(Issue::OrphanedContent(path)),
+            _ => {},
+        }
+    }
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentType, EntryAlias};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn finds_and_repairs_damage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageManager { base_path: dir.path().to_path_buf() };
+        for required in REQUIRED_DIRS {
+            fs::create_dir_all(dir.path().join(required)).unwrap();
+        }
+        let entries = dir.path().join("data/entries");
+
+        let good = Entry::new("good".to_string(), ContentType::Text, None, Vec::new());
+        storage.save_entry(&good, "fine", None).unwrap();
+        let lost = Entry::new("lost".to_string(), ContentType::Text, None, Vec::new());
+        storage.save_entry(&lost, "gone soon", None).unwrap();
+        fs::remove_file(entries.join(format!("{}.content", lost.id))).unwrap();
+        fs::write(entries.join("stray.content"), "who am I").unwrap();
+        fs::write(entries.join("broken.json"), "{ not json").unwrap();
+        let mut aliases = BTreeMap::new();
+        aliases.insert("old".to_string(), EntryAlias { id: "missing".to_string(), backpack: None });
+        storage.save_aliases(&aliases).unwrap();
+
+        let issues = storage.check_health().unwrap();
+        assert_eq!(issues.len(), 4, "{:?}", issues);
+        assert!(issues.contains(&Issue::MissingContent(entries.join(format!("{}.json", lost.id)))));
+        assert!(issues.contains(&Issue::OrphanedContent(entries.join("stray.content"))));
+        assert!(issues.contains(&Issue::DanglingAlias { name: "old".to_string(), id: "missing".to_string() }));
+
+        for issue in &issues {
+            storage.repair(issue).unwrap();
+        }
+        assert!(storage.check_health().unwrap().is_empty());
+        assert!(dir.path().join(LOST_AND_FOUND).join("data/entries/stray.content").exists());
+        assert_eq!(storage.load_entry(&good.id, None).unwrap().1, "fine");
+    }
+}
@@ -0,0 +1,259 @@
+//! Where pocket keeps its data and config
+//!
+//! By default that's all in `~/.pocket`. `$POCKET_HOME` puts it all in
+//! another directory instead. Pocket also follows the XDG base directory
+//! layout, with data in `$XDG_DATA_HOME/pocket` and config in
+//! `$XDG_CONFIG_HOME/pocket/config.toml`, when there's no `~/.pocket` and
+//! either that config file exists or the XDG variables are set. The
+//! `data_dir` key in that config file moves the data anywhere else.
+
+use crate::utils::atomic;
+use anyhow::{Result, anyhow};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the directory for all of pocket's files
+pub const HOME_ENV: &str = "POCKET_HOME";
+
+/// Key in the XDG config file saying where the data lives
+pub const DATA_DIR_KEY: &str = "data_dir";
+
+/// Where pocket's files are
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layout {
+    /// Data and config together in one directory
+    Home(PathBuf),
+    /// Data in one place, the default profile's config in another
+    Xdg { data: PathBuf, config: PathBuf },
+}
+
+impl Layout {
+    /// The directory holding the default profile and every other one
+    pub fn data_dir(&self) -> &Path {
+        match self {
+            Layout::Home(dir) => dir,
+            Layout::Xdg { data, .. } => data,
+        }
+    }
+    
+    /// The config file for the pocket whose data is in `dir`. Only the
+    /// default profile's config moves out in the XDG layout
+    pub fn config_file(&self, dir: &Path) -> PathBuf {
+        match self {
+            Layout::Xdg { data, config } if data == dir => config.clone(),
+            _ => dir.join("config.toml"),
+        }
+    }
+}
+
+/// Where pocket's files are, from the environment
+pub fn layout() -> Result<Layout> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+    Ok(locate(|name| env::var(name).ok(), &home))
+}
+
+/// The directory holding the default profile and every other one
+pub fn pocket_root() -> Result<PathBuf> {
+    Ok(layout()?.data_dir().to_path_buf())
+}
+
+/// The config file for the XDG layout
+pub fn xdg_config_file(var: impl Fn(&str) -> Option<String>, home: &Path) -> PathBuf {
+    let config_home = var("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".config"));
+    config_home.join("pocket").join("config.toml")
+}
+
+/// The data directory for the XDG layout
+pub fn xdg_data_dir(var: impl Fn(&str) -> Option<String>, home: &Path) -> PathBuf {
+    let data_home = var("XDG_DATA_HOME").filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local").join("share"));
+    data_home.join("pocket")
+}
+
+/// Works out the layout from environment variables (through `var`) and
+/// what's in the home directory
+fn locate(var: impl Fn(&str) -> Option<String>, home: &Path) -> Layout {
+    if let Some(dir) = var(HOME_ENV).filter(|dir| !dir.trim().is_empty()) {
+        return Layout::Home(expand(dir.trim(), home));
+    }
+    
+    let config = xdg_config_file(&var, home);
+    if let Some(data) = configured_data_dir(&config) {
+        return Layout::Xdg { data: expand(&data, home), config };
+    }
+    
+    // Existing pockets stay where they are
+    let legacy = home.join(".pocket");
+    if legacy.exists() {
+        return Layout::Home(legacy);
+    }
+    
+    let wants_xdg = config.exists()
+        || var("XDG_DATA_HOME").is_some_and(|dir| !dir.is_empty())
+        || var("XDG_CONFIG_HOME").is_some_and(|dir| !dir.is_empty());
+    if wants_xdg {
+        return Layout::Xdg { data: xdg_data_dir(&var, home), config };
+    }
+    Layout::Home(legacy)
+}
+
+/// The `data_dir` set in a config file, if any
+fn configured_data_dir(config: &Path) -> Option<String> {
+    let text = fs::read_to_string(config).ok()?;
+    let table: toml::Table = toml::from_str(&text).ok()?;
+    table.get(DATA_DIR_KEY)?.as_str()
+        .map(str::to_string)
+        .filter(|dir| !dir.trim().is_empty())
+}
+
+/// A path with a leading `~` expanded. On Windows `~\` works as well as `~/`
+pub(crate) fn expand(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some("") => home.to_path_buf(),
+        Some(rest) if rest.starts_with(std::path::is_separator) => home.join(rest.trim_start_matches(std::path::is_separator)),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Moves pocket's files to a new place and makes sure pocket finds them
+/// there: into the XDG layout when `to` is `None`, or into `to` with the
+/// XDG config file's `data_dir` pointing at it. Returns the new layout
+pub fn relocate(to: Option<&Path>) -> Result<Layout> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+    let var = |name: &str| env::var(name).ok();
+    if var(HOME_ENV).is_some_and(|dir| !dir.trim().is_empty()) {
+        return Err(anyhow!("${} decides where pocket's files are; move them yourself and update it", HOME_ENV));
+    }
+    
+    let current = locate(var, &home);
+    let config = xdg_config_file(var, &home);
+    let data = match to {
+        Some(dir) => std::path::absolute(dir)?,
+        None => xdg_data_dir(var, &home),
+    };
+    if data == current.data_dir() {
+        return Err(anyhow!("Pocket's data is already in {}", data.display()));
+    }
+    if data.exists() && fs::read_dir(&data)?.next().is_some() {
+        return Err(anyhow!("{} isn't empty", data.display()));
+    }
+    
+    // The default profile's config joins the XDG config file, carrying
+    // `data_dir` when the data goes somewhere the layout wouldn't look
+    let old_config = current.config_file(current.data_dir());
+    let mut table: toml::Table = match fs::read_to_string(&old_config) {
+        Ok(text) => toml::from_str(&text)?,
+        Err(_) => toml::Table::new(),
+    };
+    if to.is_some() {
+        table.insert(DATA_DIR_KEY.to_string(), toml::Value::String(data.to_string_lossy().into_owned()));
+    } else {
+        table.remove(DATA_DIR_KEY);
+    }
+    
+    if current.data_dir().exists() {
+        move_dir(current.data_dir(), &data)?;
+    } else {
+        fs::create_dir_all(&data)?;
+    }
+    let moved_config = data.join("config.toml");
+    if moved_config.exists() {
+        fs::remove_file(moved_config)?;
+    }
+    if let Some(parent) = config.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    atomic::write(&config, toml::to_string_pretty(&table)?)?;
+    
+    Ok(Layout::Xdg { data, config })
+}
+
+/// Moves a directory, copying it when it has to cross filesystems
+fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if to.exists() {
+        // Already checked to be empty
+        fs::remove_dir(to)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_dir(from, to)?;
+    fs::remove_dir_all(from)?;
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+    
+    #[test]
+    fn finds_where_pocket_lives() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = dir.path();
+        
+        // Nothing set up yet
+        assert_eq!(locate(vars(&[]), home), Layout::Home(home.join(".pocket")));
+        let xdg = vars(&[("XDG_DATA_HOME", "/data"), ("XDG_CONFIG_HOME", "/config")]);
+        assert_eq!(locate(xdg, home), Layout::Xdg {
+            data: PathBuf::from("/data/pocket"),
+            config: PathBuf::from("/config/pocket/config.toml"),
+        });
+        
+        // An existing ~/.pocket wins over XDG, and POCKET_HOME over both
+        fs::create_dir(home.join(".pocket")).unwrap();
+        assert_eq!(locate(vars(&[("XDG_DATA_HOME", "/data")]), home), Layout::Home(home.join(".pocket")));
+        assert_eq!(locate(vars(&[("POCKET_HOME", "~/stuff")]), home), Layout::Home(home.join("stuff")));
+        
+        // data_dir in the XDG config file points anywhere
+        fs::create_dir_all(home.join(".config/pocket")).unwrap();
+        fs::write(home.join(".config/pocket/config.toml"), "data_dir = \"~/sync/pocket\"\n").unwrap();
+        let layout = locate(vars(&[]), home);
+        assert_eq!(layout.data_dir(), home.join("sync/pocket"));
+        assert_eq!(layout.config_file(&home.join("sync/pocket")), home.join(".config/pocket/config.toml"));
+        assert_eq!(layout.config_file(&home.join("sync/pocket/profiles/work")), home.join("sync/pocket/profiles/work/config.toml"));
+    }
+
+    #[test]
+    fn expands_the_home_directory() {
+        let home = Path::new("/home/me");
+        assert_eq!(expand("~", home), home);
+        assert_eq!(expand("~/notes", home), home.join("notes"));
+        assert_eq!(expand("~me/notes", home), PathBuf::from("~me/notes"));
+        assert_eq!(expand("/srv/pocket", home), PathBuf::from("/srv/pocket"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn expands_the_home_directory_with_backslashes() {
+        let home = Path::new(r"C:\Users\me");
+        assert_eq!(expand(r"~\notes\pocket", home), home.join("notes").join("pocket"));
+        assert_eq!(expand(r"~/notes", home), home.join("notes"));
+    }
+}
@@ -0,0 +1,191 @@
+//! An index of entry metadata, so listing and searching don't have to read
+//! and parse every entry's JSON file each time
+//!
+//! The index lives in `cache/index.json` and remembers each metadata file's
+//! size and modification time along with the entry in it. Listing a
+//! location only stats the files there, and re-reads just the ones whose
+//! size or time changed, so edits made behind pocket's back are picked up.
+//! Deleting the index is always safe; it gets rebuilt on the next listing.
+
+use crate::models::Entry;
+use crate::utils::atomic;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever the layout changes, so old indexes are thrown away
+const INDEX_VERSION: u32 = 1;
+
+/// Entries by location (`""` for the general pocket, else the backpack),
+/// then by ID
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EntryIndex {
+    version: u32,
+    locations: BTreeMap<String, BTreeMap<String, IndexedEntry>>,
+}
+
+/// An entry as it was when its metadata file was read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedEntry {
+    stamp: Stamp,
+    entry: Entry,
+}
+
+/// What tells a metadata file has changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    len: u64,
+    modified_nanos: u128,
+}
+
+impl Stamp {
+    fn of(metadata: &fs::Metadata) -> Self {
+        let modified_nanos = metadata.modified().ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos())
+            .unwrap_or_default();
+        Self { len: metadata.len(), modified_nanos }
+    }
+}
+
+impl EntryIndex {
+    /// Loads the index at `path`. A missing, unreadable or outdated index
+    /// is just an empty one
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path).ok()
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_default()
+    }
+    
+    /// Saves the index to `path`
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        self.version = INDEX_VERSION;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        atomic::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+    
+    /// The entries in `dir`, with the index brought up to date with the
+    /// files there. The flag says whether the index changed
+    pub fn refresh(&mut self, location: Option<&str>, dir: &Path) -> Result<(Vec<Entry>, bool)> {
+        let key = location.unwrap_or_default().to_string();
+        if !dir.exists() {
+            let changed = self.locations.remove(&key).is_some();
+            return Ok((Vec::new(), changed));
+        }
+        
+        let known = self.locations.entry(key).or_default();
+        let mut seen = HashSet::new();
+        let mut changed = false;
+        for file in fs::read_dir(dir)? {
+            let path = file?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            
+            seen.insert(id.to_string());
+            let stamp = Stamp::of(&metadata);
+            if known.get(id).is_some_and(|indexed| indexed.stamp == stamp) {
+                continue;
+            }
+            let json = fs::read_to_string(&path)?;
+            let entry: Entry = serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse entry metadata from {}", path.display()))?;
+            known.insert(id.to_string(), IndexedEntry { stamp, entry });
+            changed = true;
+        }
+        
+        let before = known.len();
+        known.retain(|id, _| seen.contains(id));
+        changed |= known.len() != before;
+        
+        Ok((known.values().map(|indexed| indexed.entry.clone()).collect(), changed))
+    }
+    
+    /// Records an entry just written to `path`
+    pub fn insert(&mut self, location: Option<&str>, entry: &Entry, path: &Path) -> Result<()> {
+        let stamp = Stamp::of(&fs::metadata(path)?);
+        self.locations.entry(location.unwrap_or_default().to_string())
+            .or_default()
+            .insert(entry.id.clone(), IndexedEntry { stamp, entry: entry.clone() });
+        Ok(())
+    }
+    
+    /// Forgets an entry
+    pub fn remove(&mut self, location: Option<&str>, id: &str) -> bool {
+        self.locations.get_mut(location.unwrap_or_default())
+            .is_some_and(|entries| entries.remove(id).is_some())
+    }
+    
+    /// Forgets every entry in a location
+    pub fn remove_location(&mut self, location: Option<&str>) -> bool {
+        self.locations.remove(location.unwrap_or_default()).is_some()
+    }
+    
+    /// How many entries are indexed
+    pub fn len(&self) -> usize {
+        self.locations.values().map(BTreeMap::len).sum()
+    }
+    
+    /// Whether nothing is indexed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentType;
+    
+    fn write_entry(dir: &Path, title: &str) -> Entry {
+        let entry = Entry::new(title.to_string(), ContentType::Text, None, Vec::new());
+        fs::write(dir.join(format!("{}.json", entry.id)), serde_json::to_string(&entry).unwrap()).unwrap();
+        entry
+    }
+    
+    #[test]
+    fn keeps_up_with_the_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = dir.path().join("entries");
+        fs::create_dir(&entries).unwrap();
+        let first = write_entry(&entries, "first");
+        fs::write(entries.join(format!("{}.content", first.id)), "content isn't metadata").unwrap();
+        
+        let mut index = EntryIndex::default();
+        let (listed, changed) = index.refresh(None, &entries).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(changed);
+        
+        // Nothing new on disk, nothing to re-read
+        let (_, changed) = index.refresh(None, &entries).unwrap();
+        assert!(!changed);
+        
+        // Files added or deleted behind the index's back are noticed
+        let second = write_entry(&entries, "second");
+        fs::remove_file(entries.join(format!("{}.json", first.id))).unwrap();
+        let (listed, changed) = index.refresh(None, &entries).unwrap();
+        assert!(changed);
+        assert_eq!(listed.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec![second.id.as_str()]);
+        
+        // And survive a round trip through the index file
+        let path = dir.path().join("cache/index.json");
+        index.save(&path).unwrap();
+        let mut loaded = EntryIndex::load(&path);
+        assert_eq!(loaded.len(), 1);
+        assert!(!loaded.refresh(None, &entries).unwrap().1);
+    }
+}
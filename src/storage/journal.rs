@@ -0,0 +1,307 @@
+//! The operation journal behind `pocket undo` and `pocket redo`
+//!
+//! A command that changes entries opens a [`Transaction`]. While it's open,
+//! storage hands every file it's about to write or delete to [`touch`],
+//! which saves the file as it was. When the command is done the files are
+//! saved again as they ended up, and both sets go into `journal/` with a
+//! record of the command. Undoing puts the "before" files back and redoing
+//! the "after" ones, after checking nothing else changed them since.
+
+use crate::utils::atomic;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Operations kept for undoing
+pub const UNDO_LIMIT: usize = 20;
+
+/// File describing a journaled operation
+const RECORD_FILE: &str = "record.json";
+
+/// The transaction open in this process, if any
+static ACTIVE: Mutex<Option<Pending>> = Mutex::new(None);
+
+/// Tests that open transactions take turns, since a process has one at a time
+#[cfg(test)]
+pub(crate) static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// An operation in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    /// Sorts in the order operations happened
+    pub id: String,
+    pub time: DateTime<Utc>,
+    /// The command that did it
+    pub label: String,
+    /// Files it changed, relative to the pocket directory
+    pub files: Vec<JournaledFile>,
+    /// Whether it's been undone (and can be redone)
+    #[serde(default)]
+    pub undone: bool,
+}
+
+/// A file an operation changed, and whether it existed before and after
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournaledFile {
+    pub path: PathBuf,
+    pub before: bool,
+    pub after: bool,
+}
+
+/// A transaction being recorded
+struct Pending {
+    base: PathBuf,
+    dir: PathBuf,
+    label: String,
+    files: Vec<JournaledFile>,
+}
+
+/// Records what storage changes until it's finished or dropped
+pub struct Transaction {
+    finished: bool,
+}
+
+/// Starts recording changes to the pocket in `base` as one operation
+pub fn begin(base: &Path, label: &str) -> Result<Transaction> {
+    let dir = journal_dir(base).join(format!(".pending-{}", std::process::id()));
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    let mut active = ACTIVE.lock().map_err(|_| anyhow!("Journal lock poisoned"))?;
+    *active = Some(Pending { base: base.to_path_buf(), dir, label: label.to_string(), files: Vec::new() });
+    Ok(Transaction { finished: false })
+}
+
+/// Saves `path` as it is now, if a transaction is open and hasn't saved it
+/// yet. Storage calls this before writing or deleting a file
+pub(crate) fn touch(path: &Path) -> Result<()> {
+    let Ok(mut active) = ACTIVE.lock() else {
+        return Ok(());
+    };
+    let Some(pending) = active.as_mut() else {
+        return Ok(());
+    };
+    let Ok(relative) = path.strip_prefix(&pending.base) else {
+        return Ok(());
+    };
+    if pending.files.iter().any(|file| file.path == relative) {
+        return Ok(());
+    }
+
+    let before = path.is_file();
+    if before {
+        copy_into(path, &pending.dir.join("before").join(relative))?;
+    }
+    pending.files.push(JournaledFile { path: relative.to_path_buf(), before, after: false });
+    Ok(())
+}
+
+impl Transaction {
+    /// Saves the operation to the journal, if it changed anything
+    pub fn finish(mut self) -> Result<Option<Record>> {
+        self.commit()
+    }
+
+    fn commit(&mut self) -> Result<Option<Record>> {
+        if self.finished {
+            return Ok(None);
+        }
+        self.finished = true;
+        let pending = ACTIVE.lock().map_err(|_| anyhow!("Journal lock poisoned"))?.take();
+        let Some(mut pending) = pending else {
+            return Ok(None);
+        };
+        if pending.files.is_empty() {
+            let _ = fs::remove_dir_all(&pending.dir);
+            return Ok(None);
+        }
+
+        for file in &mut pending.files {
+            let path = pending.base.join(&file.path);
+            file.after = path.is_file();
+            if file.after {
+                copy_into(&path, &pending.dir.join("after").join(&file.path))?;
+            }
+        }
+
+        // A new operation ends whatever could have been redone
+        for record in history(&pending.base)? {
+            if record.undone {
+                fs::remove_dir_all(journal_dir(&pending.base).join(&record.id))?;
+            }
+        }
+
+        let time = Utc::now();
+        let record = Record {
+            id: format!("{}-{}", time.format("%Y%m%d%H%M%S%6f"), std::process::id()),
+            time,
+            label: pending.label,
+            files: pending.files,
+            undone: false,
+        };
+        atomic::write(pending.dir.join(RECORD_FILE), serde_json::to_string_pretty(&record)?)?;
+        fs::rename(&pending.dir, journal_dir(&pending.base).join(&record.id))?;
+
+        let records = history(&pending.base)?;
+        for old in records.iter().take(records.len().saturating_sub(UNDO_LIMIT)) {
+            fs::remove_dir_all(journal_dir(&pending.base).join(&old.id))?;
+        }
+        Ok(Some(record))
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if let Err(e) = self.commit() {
+            log::warn!("Failed to record the operation for undo: {}", e);
+        }
+    }
+}
+
+/// Every journaled operation, oldest first
+pub fn history(base: &Path) -> Result<Vec<Record>> {
+    let dir = journal_dir(base);
+    let mut records = Vec::new();
+    if !dir.is_dir() {
+        return Ok(records);
+    }
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path().join(RECORD_FILE);
+        if let Ok(json) = fs::read_to_string(&path) {
+            let record: Record = serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            records.push(record);
+        }
+    }
+    records.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(records)
+}
+
+/// Undoes the latest operation not undone yet. Refuses when its files were
+/// changed since, unless `force` is set
+pub fn undo(base: &Path, force: bool) -> Result<Record> {
+    let mut record = history(base)?.into_iter().rev()
+        .find(|record| !record.undone)
+        .ok_or_else(|| anyhow!("Nothing to undo"))?;
+    apply(base, &record, "after", "before", force)?;
+    record.undone = true;
+    save_record(base, &record)?;
+    Ok(record)
+}
+
+/// Redoes the earliest undone operation
+pub fn redo(base: &Path, force: bool) -> Result<Record> {
+    let mut record = history(base)?.into_iter()
+        .find(|record| record.undone)
+        .ok_or_else(|| anyhow!("Nothing to redo"))?;
+    apply(base, &record, "before", "after", force)?;
+    record.undone = false;
+    save_record(base, &record)?;
+    Ok(record)
+}
+
+/// Checks the files are in the `expected` state, then puts them in the
+/// `wanted` one
+fn apply(base: &Path, record: &Record, expected: &str, wanted: &str, force: bool) -> Result<()> {
+    let saved = journal_dir(base).join(&record.id);
+    let exists_in = |file: &JournaledFile, state: &str| if state == "before" { file.before } else { file.after };
+
+    if !force {
+        for file in &record.files {
+            let current = fs::read(base.join(&file.path)).ok();
+            let expected_contents = exists_in(file, expected)
+                .then(|| fs::read(saved.join(expected).join(&file.path)))
+                .transpose()?;
+            if current != expected_contents {
+                return Err(anyhow!("{} changed since `{}`; use --force to overwrite it anyway", file.path.display(), record.label));
+            }
+        }
+    }
+
+    for file in &record.files {
+        let path = base.join(&file.path);
+        if exists_in(file, wanted) {
+            let contents = fs::read(saved.join(wanted).join(&file.path))?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            atomic::write(&path, contents)?;
+        } else if path.exists() {
+            fs::remove_file(&path)?;
+            remove_empty_parents(base, &path);
+        }
+    }
+    Ok(())
+}
+
+/// Removes the directories above `path` left empty, up to the backpack's
+/// own, so taking back a backpack's last file takes the backpack with it
+fn remove_empty_parents(base: &Path, path: &Path) {
+    let backpacks = base.join("data/backpacks");
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        if current == backpacks || !current.starts_with(&backpacks) || fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+fn save_record(base: &Path, record: &Record) -> Result<()> {
+    atomic::write(journal_dir(base).join(&record.id).join(RECORD_FILE), serde_json::to_string_pretty(record)?)
+}
+
+fn journal_dir(base: &Path) -> PathBuf {
+    base.join("journal")
+}
+
+fn copy_into(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(from, to).with_context(|| format!("Failed to save {} for undo", from.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoes_and_redoes_operations() {
+        let _turn = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        let entry = base.join("data/entries/a.json");
+        fs::create_dir_all(entry.parent().unwrap()).unwrap();
+
+        let add = begin(base, "add").unwrap();
+        touch(&entry).unwrap();
+        fs::write(&entry, "first").unwrap();
+        add.finish().unwrap();
+
+        let edit = begin(base, "edit").unwrap();
+        touch(&entry).unwrap();
+        fs::write(&entry, "second").unwrap();
+        drop(edit);
+        assert_eq!(history(base).unwrap().len(), 2);
+
+        assert_eq!(undo(base, false).unwrap().label, "edit");
+        assert_eq!(fs::read_to_string(&entry).unwrap(), "first");
+        assert_eq!(undo(base, false).unwrap().label, "add");
+        assert!(!entry.exists());
+        assert!(undo(base, false).is_err());
+
+        assert_eq!(redo(base, false).unwrap().label, "add");
+        assert_eq!(fs::read_to_string(&entry).unwrap(), "first");
+
+        // Changes made outside the journal aren't clobbered without --force
+        fs::write(&entry, "meddled").unwrap();
+        assert!(redo(base, false).is_err());
+        assert_eq!(redo(base, true).unwrap().label, "edit");
+        assert_eq!(fs::read_to_string(&entry).unwrap(), "second");
+    }
+}
@@ -0,0 +1,85 @@
+//! Advisory locks on entries, so two pocket processes (or the API server
+//! and the command line) changing the same entry take turns instead of
+//! interleaving its metadata and content writes
+//!
+//! Each entry's lock is a file under `cache/locks`, held with the operating
+//! system's advisory file locks. They're let go when the lock is dropped,
+//! or when the process holding them dies.
+
+use super::{StorageManager, validate_location};
+use crate::errors::StorageError;
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest to wait for another process to finish with an entry
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often to check whether it has
+const LOCK_RETRY: Duration = Duration::from_millis(20);
+
+/// A held lock on an entry, let go when dropped
+#[derive(Debug)]
+pub struct EntryLock {
+    _file: File,
+}
+
+impl StorageManager {
+    fn get_entry_lock_path(&self, id: &str, backpack: Option<&str>) -> PathBuf {
+        let name = match backpack {
+            Some(backpack) => format!("{}.{}.lock", backpack, id),
+            None => format!("{}.lock", id),
+        };
+        self.base_path.join("cache/locks").join(name)
+    }
+
+    /// Takes the lock on an entry, waiting for whoever holds it. Locks
+    /// aren't reentrant: taking the same one twice in a process waits out
+    /// the timeout
+    pub fn lock_entry(&self, id: &str, backpack: Option<&str>) -> Result<EntryLock> {
+        validate_location(backpack)?;
+        let path = self.get_entry_lock_path(id, backpack);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).truncate(false).write(true).open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(EntryLock { _file: file }),
+                Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_TIMEOUT => thread::sleep(LOCK_RETRY),
+                Err(TryLockError::WouldBlock) => {
+                    return Err(StorageError::Conflict(format!("Entry {} is being changed by another pocket process; try again in a moment", id)).into());
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_holder_at_a_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = StorageManager::at(dir.path());
+        let lock = storage.lock_entry("abc", None).unwrap();
+
+        // A second handle is what another process would have
+        let path = storage.get_entry_lock_path("abc", None);
+        let other = OpenOptions::new().write(true).open(&path).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+        assert!(storage.lock_entry("abc", Some("work")).is_ok());
+
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+    }
+}
@@ -1,9 +1,93 @@
-use crate::models::{Entry, Backpack, Config, ContentType, Workflow};
+use crate::errors::StorageError;
+use crate::models::{Entry, EntryAlias, Backpack, Config, ContentType, SearchAlgorithm, Workflow, WorkflowSchedule};
 use anyhow::{Result, Context, anyhow};
-use dirs::home_dir;
 use std::fs::{self, create_dir_all};
 use std::path::{Path, PathBuf};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashSet};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::sync::Once;
+use crate::utils::{atomic, parallel, suggest};
+use crate::search::text::{Candidate, TextQuery};
+use rayon::prelude::*;
+use index::EntryIndex;
+
+pub mod audit;
+pub mod health;
+pub mod home;
+pub mod index;
+pub mod journal;
+pub mod lock;
+pub mod profile;
+pub mod searches;
+
+pub use profile::pocket_dir;
+
+/// Entry metadata key holding the start of an entry too big to search whole
+pub const EXCERPT_KEY: &str = "excerpt";
+
+/// Bytes of a large entry kept as its excerpt
+pub const EXCERPT_BYTES: usize = 64 * 1024;
+
+/// Entry metadata key holding the size of a streamed entry's content
+pub const SIZE_KEY: &str = "size";
+
+/// Contents of a project's `.pocket-context` file
+#[derive(Debug, Deserialize)]
+struct ProjectContext {
+    /// Backpack that add/list/search use inside the project
+    backpack: Option<String>,
+}
+
+/// Hash content with runs of whitespace collapsed, so entries that differ
+/// only in indentation or line endings hash the same
+pub fn content_hash(content: &str) -> String {
+    let normalized = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    bytes_hash(normalized.as_bytes())
+}
+
+/// Hex SHA-256 of raw bytes
+fn bytes_hash(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+/// Lowercase hex of a digest
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The text at the start of an excerpt, dropping a character cut in half
+/// at the end
+fn excerpt_text(bytes: &[u8]) -> String {
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => bytes.len(),
+    };
+    String::from_utf8_lossy(&bytes[..valid]).into_owned()
+}
+
+/// Drops what a streamed save noted about content that's being replaced, so
+/// search stops reading an excerpt of what's no longer there
+fn forget_streamed(entry: &mut Entry) {
+    entry.metadata.remove(EXCERPT_KEY);
+    entry.metadata.remove(SIZE_KEY);
+}
+
+/// Check that a backpack name is usable as a directory name
+pub fn validate_backpack_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) || name.chars().any(char::is_whitespace) {
+        return Err(StorageError::Validation(format!("Invalid backpack name '{}'", name)).into());
+    }
+    Ok(())
+}
+
+/// Check the backpack of a location, if it's one, before it goes into a path
+fn validate_location(backpack: Option<&str>) -> Result<()> {
+    backpack.map_or(Ok(()), validate_backpack_name)
+}
 
 /// Storage manager for pocket data
 #[derive(Clone)]
@@ -18,16 +102,39 @@ impl StorageManager {
         Ok(Self { base_path })
     }
 
+    /// A storage manager for the active profile's pocket that doesn't set it
+    /// up, for looking at what's there before a command needs the pocket
+    pub fn existing() -> Result<Self> {
+        Ok(Self { base_path: pocket_dir()? })
+    }
+
+    /// A storage manager for the pocket in `base_path`, for tests elsewhere
+    #[cfg(test)]
+    pub(crate) fn at(base_path: impl Into<PathBuf>) -> Self {
+        Self { base_path: base_path.into() }
+    }
+
     /// Get the base path for pocket data
     fn get_base_path() -> Result<PathBuf> {
-        let home = home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
-        let pocket_dir = home.join(".pocket");
+        let pocket_dir = pocket_dir()?;
         
         // Create directories if they don't exist
         create_dir_all(pocket_dir.join("data/entries"))?;
         create_dir_all(pocket_dir.join("data/backpacks"))?;
         create_dir_all(pocket_dir.join("data/workflows"))?;
         create_dir_all(pocket_dir.join("wallet"))?;
+
+        // Clear out what writes cut short by a crash left behind, once a run
+        static RECOVERY: Once = Once::new();
+        RECOVERY.call_once(|| {
+            for dir in ["data", "cards"] {
+                match atomic::recover(&pocket_dir.join(dir)) {
+                    Ok(0) => {},
+                    Ok(count) => log::debug!("Removed {} temporary files left by interrupted writes", count),
+                    Err(e) => log::warn!("Failed to clean up after interrupted writes: {}", e),
+                }
+            }
+        });
         
         Ok(pocket_dir)
     }
@@ -39,6 +146,14 @@ impl StorageManager {
         Ok(dir)
     }
 
+    /// Get the directory holding a location's entries
+    fn get_entries_dir(&self, backpack: Option<&str>) -> PathBuf {
+        match backpack {
+            Some(name) => self.base_path.join(format!("data/backpacks/{}/entries", name)),
+            None => self.base_path.join("data/entries"),
+        }
+    }
+
     /// Get the path for an entry's metadata
     fn get_entry_metadata_path(&self, id: &str, backpack: Option<&str>) -> PathBuf {
         match backpack {
@@ -60,43 +175,260 @@ impl StorageManager {
         self.base_path.join(format!("data/backpacks/{}/manifest.json", name))
     }
 
+    /// Get the path to the entry alias table
+    fn get_aliases_path(&self) -> PathBuf {
+        self.base_path.join("data/aliases.json")
+    }
+
+    /// Get the path to the pinned entry index
+    fn get_pins_path(&self) -> PathBuf {
+        self.base_path.join("data/pins.json")
+    }
+
+    /// Get the path to the entry metadata index
+    fn get_index_path(&self) -> PathBuf {
+        self.base_path.join("cache/index.json")
+    }
+
     /// Get the config file path
     fn get_config_path(&self) -> PathBuf {
-        self.base_path.join("config.toml")
+        match home::layout() {
+            Ok(layout) => layout.config_file(&self.base_path),
+            Err(_) => self.base_path.join("config.toml"),
+        }
     }
 
     /// Get the path to a workflow
-    fn _get_workflow_path(&self, name: &str) -> PathBuf {
+    fn get_workflow_path(&self, name: &str) -> PathBuf {
         self.base_path.join("data/workflows").join(format!("{}.json", name))
     }
 
+    /// Get the path to the workflow schedules
+    fn get_schedules_path(&self) -> PathBuf {
+        self.base_path.join("data/schedules.json")
+    }
+
+    /// Get the path to the API server's token
+    fn get_api_token_path(&self) -> PathBuf {
+        self.base_path.join("data/api_token")
+    }
+
     /// Save an entry to storage
     pub fn save_entry(&self, entry: &Entry, content: &str, backpack: Option<&str>) -> Result<()> {
+        let _lock = self.lock_entry(&entry.id, backpack)?;
+        self.write_entry(entry, content, backpack)
+    }
+
+    /// Save an entry, with its lock already held
+    fn write_entry(&self, entry: &Entry, content: &str, backpack: Option<&str>) -> Result<()> {
         // Create backpack directory if needed
         if let Some(name) = backpack {
             create_dir_all(self.base_path.join(format!("data/backpacks/{}/entries", name)))?;
         }
 
-        // Save metadata
-        let metadata_path = self.get_entry_metadata_path(&entry.id, backpack);
-        let metadata_json = serde_json::to_string_pretty(entry)?;
-        fs::write(metadata_path, metadata_json)?;
+        // Save metadata, hashing the content so duplicates can be found later
+        let mut entry = entry.clone();
+        entry.content_hash = Some(content_hash(content));
+        forget_streamed(&mut entry);
+        self.save_entry_metadata(&entry, backpack)?;
 
         // Save content
         let content_path = self.get_entry_content_path(&entry.id, backpack);
-        fs::write(content_path, content)?;
+        journal::touch(&content_path)?;
+        atomic::write(content_path, content)?;
 
         Ok(())
     }
 
-    /// Load an entry from storage
-    pub fn load_entry(&self, id: &str, backpack: Option<&str>) -> Result<(Entry, String)> {
-        // Load metadata
+    /// Save an entry whose content is raw bytes (images, PDFs, binaries)
+    pub fn save_binary_entry(&self, entry: &Entry, content: &[u8], backpack: Option<&str>) -> Result<()> {
+        let _lock = self.lock_entry(&entry.id, backpack)?;
+        if let Some(name) = backpack {
+            create_dir_all(self.base_path.join(format!("data/backpacks/{}/entries", name)))?;
+        }
+
+        // Binary content is hashed as-is; whitespace means nothing here
+        let mut entry = entry.clone();
+        entry.content_hash = Some(bytes_hash(content));
+        forget_streamed(&mut entry);
+        self.save_entry_metadata(&entry, backpack)?;
+
+        let content_path = self.get_entry_content_path(&entry.id, backpack);
+        journal::touch(&content_path)?;
+        atomic::write(content_path, content)?;
+        Ok(())
+    }
+
+    /// Save an entry whose content is streamed from `reader` instead of held
+    /// in memory, returning its size. Text entries keep the start of their
+    /// content as an excerpt, which search reads instead of the whole thing
+    pub fn save_entry_from(&self, entry: &Entry, mut reader: impl Read, backpack: Option<&str>) -> Result<u64> {
+        let _lock = self.lock_entry(&entry.id, backpack)?;
+        if let Some(name) = backpack {
+            create_dir_all(self.base_path.join(format!("data/backpacks/{}/entries", name)))?;
+        }
+
+        // Content goes first, since its hash is only known once it's all read
+        let mut hasher = Sha256::new();
+        let mut excerpt = Vec::new();
+        let mut size = 0u64;
+        let content_path = self.get_entry_content_path(&entry.id, backpack);
+        journal::touch(&content_path)?;
+        atomic::write_with(content_path, |file| {
+            let mut buffer = vec![0; 64 * 1024];
+            loop {
+                let read = reader.read(&mut buffer)?;
+                if read == 0 {
+                    return Ok(());
+                }
+                let chunk = &buffer[..read];
+                hasher.update(chunk);
+                if excerpt.len() < EXCERPT_BYTES {
+                    excerpt.extend_from_slice(&chunk[..chunk.len().min(EXCERPT_BYTES - excerpt.len())]);
+                }
+                file.write_all(chunk)?;
+                size += read as u64;
+            }
+        })?;
+
+        let mut entry = entry.clone();
+        entry.content_hash = Some(hex(&hasher.finalize()));
+        forget_streamed(&mut entry);
+        entry.add_metadata(SIZE_KEY, &size.to_string());
+        if !entry.is_binary() {
+            entry.add_metadata(EXCERPT_KEY, &excerpt_text(&excerpt));
+        }
+        self.save_entry_metadata(&entry, backpack)?;
+        Ok(size)
+    }
+
+    /// Save only an entry's metadata, leaving its content untouched
+    fn save_entry_metadata(&self, entry: &Entry, backpack: Option<&str>) -> Result<()> {
+        let metadata_path = self.get_entry_metadata_path(&entry.id, backpack);
+        let metadata_json = serde_json::to_string_pretty(entry)?;
+        journal::touch(&metadata_path)?;
+        atomic::write(&metadata_path, metadata_json)?;
+
+        // Keep the index current so the next listing doesn't re-read this file
+        let index_path = self.get_index_path();
+        if index_path.exists() {
+            let mut index = EntryIndex::load(&index_path);
+            index.insert(backpack, entry, &metadata_path)?;
+            index.save(&index_path)?;
+        }
+        Ok(())
+    }
+
+    /// Record a use of an entry (shown, inserted, copied, or executed)
+    pub fn record_usage(&self, id: &str, backpack: Option<&str>) -> Result<()> {
+        self.update_entry_metadata(id, backpack, |entry| entry.record_use())?;
+        Ok(())
+    }
+
+    /// Change an entry: its metadata through `update` and, when given, its
+    /// content. This counts as modifying the entry, so `updated_at` moves on
+    pub fn update_entry(&self, id: &str, backpack: Option<&str>, content: Option<&str>, update: impl FnOnce(&mut Entry)) -> Result<Entry> {
+        self.change_entry(id, backpack, None, content, update)
+    }
+
+    /// Like `update_entry`, for a change worked out from the entry as it was
+    /// when its `updated_at` was `seen`, like an edit. If something else
+    /// changed the entry since, this fails instead of overwriting that
+    pub fn update_entry_if_unchanged(&self, id: &str, backpack: Option<&str>, seen: DateTime<Utc>, content: Option<&str>, update: impl FnOnce(&mut Entry)) -> Result<Entry> {
+        self.change_entry(id, backpack, Some(seen), content, update)
+    }
+
+    fn change_entry(&self, id: &str, backpack: Option<&str>, seen: Option<DateTime<Utc>>, content: Option<&str>, update: impl FnOnce(&mut Entry)) -> Result<Entry> {
+        let _lock = self.lock_entry(id, backpack)?;
+        let mut entry = self.load_entry_metadata(id, backpack)?;
+        if seen.is_some_and(|seen| seen != entry.updated_at) {
+            return Err(StorageError::Conflict(format!("Entry {} was changed by something else since it was loaded; not overwriting that change", id)).into());
+        }
+        update(&mut entry);
+        entry.touch();
+        match content {
+            Some(content) => {
+                entry.content_hash = Some(content_hash(content));
+                forget_streamed(&mut entry);
+                self.write_entry(&entry, content, backpack)?;
+            },
+            None => self.save_entry_metadata(&entry, backpack)?,
+        }
+        Ok(entry)
+    }
+
+    /// Apply bookkeeping to an entry's metadata, like usage counts, pins or
+    /// cached results, and save it. Content and `updated_at` are left alone;
+    /// use `update_entry` for changes the user makes
+    pub fn update_entry_metadata(&self, id: &str, backpack: Option<&str>, update: impl FnOnce(&mut Entry)) -> Result<Entry> {
+        let _lock = self.lock_entry(id, backpack)?;
+        let mut entry = self.load_entry_metadata(id, backpack)?;
+        update(&mut entry);
+        self.save_entry_metadata(&entry, backpack)?;
+        Ok(entry)
+    }
+
+    /// Pin or unpin an entry, keeping the pin index in sync
+    pub fn set_pinned(&self, id: &str, backpack: Option<&str>, pinned: bool) -> Result<()> {
+        self.update_entry_metadata(id, backpack, |entry| entry.pinned = pinned)?;
+
+        let mut pins = self.load_pins()?;
+        if pinned {
+            pins.insert(id.to_string(), backpack.map(|s| s.to_string()));
+        } else {
+            pins.remove(id);
+        }
+        self.save_pins(&pins)
+    }
+
+    /// Get the IDs of all pinned entries
+    pub fn pinned_ids(&self) -> Result<HashSet<String>> {
+        Ok(self.load_pins()?.into_keys().collect())
+    }
+
+    /// Load the pin index (entry ID to backpack)
+    fn load_pins(&self) -> Result<BTreeMap<String, Option<String>>> {
+        let path = self.get_pins_path();
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read pin index from {}", path.display()))?;
+        let pins = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse pin index from {}", path.display()))?;
+        Ok(pins)
+    }
+
+    /// Save the pin index
+    fn save_pins(&self, pins: &BTreeMap<String, Option<String>>) -> Result<()> {
+        let json = serde_json::to_string_pretty(pins)?;
+        journal::touch(&self.get_pins_path())?;
+        atomic::write(self.get_pins_path(), json)?;
+        Ok(())
+    }
+
+    /// Load just an entry's metadata
+    pub fn load_entry_metadata(&self, id: &str, backpack: Option<&str>) -> Result<Entry> {
+        validate_location(backpack)?;
         let metadata_path = self.get_entry_metadata_path(id, backpack);
+        if !metadata_path.exists() {
+            return Err(StorageError::NotFound(format!("Entry '{}' not found", id)).into());
+        }
         let metadata_json = fs::read_to_string(&metadata_path)
             .with_context(|| format!("Failed to read entry metadata from {}", metadata_path.display()))?;
-        let entry: Entry = serde_json::from_str(&metadata_json)
+        let entry = serde_json::from_str(&metadata_json)
             .with_context(|| format!("Failed to parse entry metadata from {}", metadata_path.display()))?;
+        Ok(entry)
+    }
+
+    /// Load an entry from storage
+    pub fn load_entry(&self, id: &str, backpack: Option<&str>) -> Result<(Entry, String)> {
+        // Load metadata
+        let entry = self.load_entry_metadata(id, backpack)?;
+        if let Some(mime_type) = &entry.mime_type {
+            return Err(anyhow!("Entry '{}' holds binary content ({}); use `pocket export` to retrieve it", id, mime_type));
+        }
 
         // Load content
         let content_path = self.get_entry_content_path(id, backpack);
@@ -108,53 +440,63 @@ impl StorageManager {
 
     /// Remove an entry from storage
     pub fn remove_entry(&self, id: &str, backpack: Option<&str>) -> Result<()> {
+        let _lock = self.lock_entry(id, backpack)?;
+
         // Remove metadata
         let metadata_path = self.get_entry_metadata_path(id, backpack);
         if metadata_path.exists() {
+            journal::touch(&metadata_path)?;
             fs::remove_file(&metadata_path)?;
         }
 
         // Remove content
         let content_path = self.get_entry_content_path(id, backpack);
         if content_path.exists() {
+            journal::touch(&content_path)?;
             fs::remove_file(&content_path)?;
         }
 
+        // Drop it from the pin index
+        let mut pins = self.load_pins()?;
+        if pins.remove(id).is_some() {
+            self.save_pins(&pins)?;
+        }
+
+        let index_path = self.get_index_path();
+        let mut index = EntryIndex::load(&index_path);
+        if index.remove(backpack, id) {
+            index.save(&index_path)?;
+        }
+
         Ok(())
     }
 
-    /// List all entries in a backpack or the general pocket
+    /// List all entries in a backpack or the general pocket. Metadata comes
+    /// from the index, which re-reads only files that changed since
     pub fn list_entries(&self, backpack: Option<&str>) -> Result<Vec<Entry>> {
-        let entries_dir = match backpack {
-            Some(name) => self.base_path.join(format!("data/backpacks/{}/entries", name)),
-            None => self.base_path.join("data/entries"),
-        };
+        validate_location(backpack)?;
+        let entries_dir = self.get_entries_dir(backpack);
 
-        if !entries_dir.exists() {
-            return Ok(Vec::new());
-        }
-
-        let mut entries = Vec::new();
-        for entry in fs::read_dir(entries_dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            
-            // Only process JSON files (metadata)
-            if path.is_file() && path.extension().map_or(false, |ext| ext == "json") {
-                let metadata_json = fs::read_to_string(&path)?;
-                let entry: Entry = serde_json::from_str(&metadata_json)?;
-                entries.push(entry);
+        let index_path = self.get_index_path();
+        let mut index = EntryIndex::load(&index_path);
+        let (mut entries, changed) = index.refresh(backpack, &entries_dir)?;
+        if changed {
+            // A stale index only costs time, so failing to save it isn't fatal
+            if let Err(e) = index.save(&index_path) {
+                log::debug!("Failed to save the entry index: {}", e);
             }
         }
 
         // Sort by creation date (newest first)
-        entries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        entries.sort_by_key(|e| std::cmp::Reverse(e.created_at));
         
         Ok(entries)
     }
 
     /// Create a new backpack
     pub fn create_backpack(&self, backpack: &Backpack) -> Result<()> {
+        validate_backpack_name(&backpack.name)?;
+
         // Create backpack directory
         let backpack_dir = self.base_path.join(format!("data/backpacks/{}", backpack.name));
         create_dir_all(backpack_dir.join("entries"))?;
@@ -162,13 +504,13 @@ impl StorageManager {
         // Save backpack metadata
         let manifest_path = self.get_backpack_path(&backpack.name);
         let manifest_json = serde_json::to_string_pretty(backpack)?;
-        fs::write(manifest_path, manifest_json)?;
+        atomic::write(manifest_path, manifest_json)?;
 
         Ok(())
     }
 
     /// List all backpacks
-    pub fn _list_backpacks(&self) -> Result<Vec<Backpack>> {
+    pub fn list_backpacks(&self) -> Result<Vec<Backpack>> {
         let backpacks_dir = self.base_path.join("data/backpacks");
         let mut backpacks = Vec::new();
         
@@ -181,6 +523,11 @@ impl StorageManager {
                     .and_then(|n| n.to_str())
                     .ok_or_else(|| anyhow!("Invalid backpack path"))?;
                 
+                // Skip leftovers from interrupted deletes
+                if name.starts_with('.') {
+                    continue;
+                }
+                
                 // Each backpack is a subdirectory with entries
                 let meta_path = path.join("manifest.json");
                 if meta_path.exists() {
@@ -200,6 +547,378 @@ impl StorageManager {
         Ok(backpacks)
     }
 
+    /// List entries in one backpack, or in the general pocket and every
+    /// backpack, along with where each one lives
+    pub fn list_all_entries(&self, backpack: Option<&str>) -> Result<Vec<(Entry, Option<String>)>> {
+        let locations: Vec<Option<String>> = match backpack {
+            Some(name) => vec![Some(name.to_string())],
+            None => {
+                let mut locations = vec![None];
+                locations.extend(self.list_backpacks()?.into_iter().map(|b| Some(b.name)));
+                locations
+            }
+        };
+
+        let mut entries = Vec::new();
+        for location in locations {
+            for entry in self.list_entries(location.as_deref())? {
+                entries.push((entry, location.clone()));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Get an entry's content hash, computing it for entries saved before hashing existed
+    pub fn entry_hash(&self, entry: &Entry, backpack: Option<&str>) -> Result<String> {
+        match &entry.content_hash {
+            Some(hash) => Ok(hash.clone()),
+            None => {
+                let (_, content) = self.load_entry_bytes(&entry.id, backpack)?;
+                match String::from_utf8(content) {
+                    Ok(text) => Ok(content_hash(&text)),
+                    Err(e) => Ok(bytes_hash(e.as_bytes())),
+                }
+            }
+        }
+    }
+
+    /// Find entries anywhere whose content matches, ignoring whitespace differences.
+    /// The flag is true when the content is byte-for-byte identical.
+    pub fn find_duplicates(&self, content: &str) -> Result<Vec<(Entry, Option<String>, bool)>> {
+        let hash = content_hash(content);
+
+        // Entries saved before hashing existed get hashed here, one file each
+        parallel::init(|| self.load_config().ok().and_then(|config| config.search.jobs));
+        self.list_all_entries(None)?
+            .into_par_iter()
+            .filter(|(entry, _)| !entry.is_binary())
+            .map(|(entry, backpack)| {
+                if self.entry_hash(&entry, backpack.as_deref())? != hash {
+                    return Ok(None);
+                }
+                let (_, existing) = self.load_entry(&entry.id, backpack.as_deref())?;
+                let identical = existing == content;
+                Ok(Some((entry, backpack, identical)))
+            })
+            .filter_map(Result::transpose)
+            .collect()
+    }
+
+    /// Load a single backpack's manifest
+    pub fn load_backpack(&self, name: &str) -> Result<Backpack> {
+        validate_backpack_name(name)?;
+        let backpack_dir = self.base_path.join(format!("data/backpacks/{}", name));
+        if !backpack_dir.is_dir() {
+            return Err(self.backpack_not_found(name));
+        }
+
+        let manifest_path = self.get_backpack_path(name);
+        if !manifest_path.exists() {
+            return Ok(Backpack {
+                name: name.to_string(),
+                description: None,
+                created_at: Utc::now(),
+            });
+        }
+
+        let manifest_json = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read backpack manifest from {}", manifest_path.display()))?;
+        let backpack = serde_json::from_str(&manifest_json)
+            .with_context(|| format!("Failed to parse backpack manifest from {}", manifest_path.display()))?;
+        Ok(backpack)
+    }
+
+    /// The error for a backpack that doesn't exist, suggesting ones that do
+    pub fn backpack_not_found(&self, name: &str) -> anyhow::Error {
+        let names: Vec<String> = self.list_backpacks().unwrap_or_default().into_iter().map(|b| b.name).collect();
+        StorageError::NotFound(format!("Backpack '{}' not found{}", name, suggest::did_you_mean(name, names.iter().map(String::as_str)))).into()
+    }
+
+    /// Update a backpack's description
+    pub fn set_backpack_description(&self, name: &str, description: Option<&str>) -> Result<()> {
+        let mut backpack = self.load_backpack(name)?;
+        backpack.description = description.map(|s| s.to_string());
+        self.write_backpack_manifest(&backpack)
+    }
+
+    /// Rename a backpack, moving its directory and updating references to it
+    pub fn rename_backpack(&self, old_name: &str, new_name: &str) -> Result<()> {
+        validate_backpack_name(new_name)?;
+
+        let mut backpack = self.load_backpack(old_name)?;
+        let old_dir = self.base_path.join(format!("data/backpacks/{}", old_name));
+        let new_dir = self.base_path.join(format!("data/backpacks/{}", new_name));
+        if new_dir.exists() {
+            return Err(StorageError::Conflict(format!("Backpack '{}' already exists", new_name)).into());
+        }
+
+        self.journal_backpack_dir(&old_dir, Some(&new_dir))?;
+
+        // A directory rename is atomic, so entries are never split between the two names
+        fs::rename(&old_dir, &new_dir)
+            .with_context(|| format!("Failed to rename {} to {}", old_dir.display(), new_dir.display()))?;
+
+        backpack.name = new_name.to_string();
+        self.write_backpack_manifest(&backpack)?;
+        self.forget_indexed_location(Some(old_name))?;
+        self.retarget_backpack_refs(old_name, Some(new_name))
+    }
+
+    /// Delete a backpack. Entries are moved to the general pocket when
+    /// `move_entries` is set, otherwise deleted along with the backpack.
+    pub fn delete_backpack(&self, name: &str, move_entries: bool) -> Result<()> {
+        self.load_backpack(name)?;
+        let backpack_dir = self.base_path.join(format!("data/backpacks/{}", name));
+        self.journal_backpack_dir(&backpack_dir, None)?;
+
+        if move_entries {
+            let entries = self.list_entries(Some(name))?;
+            if let Some(entry) = entries.iter().find(|entry| self.get_entry_metadata_path(&entry.id, None).exists()) {
+                return Err(StorageError::Conflict(format!("Entry '{}' already exists in the general pocket", entry.id)).into());
+            }
+            for entry in entries {
+                let target = self.get_entry_metadata_path(&entry.id, None);
+                journal::touch(&self.get_entry_content_path(&entry.id, None))?;
+                journal::touch(&target)?;
+                fs::rename(self.get_entry_content_path(&entry.id, Some(name)), self.get_entry_content_path(&entry.id, None))?;
+                fs::rename(self.get_entry_metadata_path(&entry.id, Some(name)), target)?;
+            }
+            self.retarget_backpack_refs(name, None)?;
+        } else {
+            // Drop aliases and pins that would be left dangling
+            let mut aliases = self.load_aliases()?;
+            aliases.retain(|_, alias| alias.backpack.as_deref() != Some(name));
+            self.save_aliases(&aliases)?;
+
+            let mut pins = self.load_pins()?;
+            pins.retain(|_, backpack| backpack.as_deref() != Some(name));
+            self.save_pins(&pins)?;
+        }
+
+        // Move the directory out of the way first so a failed delete can't leave half a backpack
+        let trash_dir = self.base_path.join(format!("data/backpacks/.{}.deleted", name));
+        fs::rename(&backpack_dir, &trash_dir)?;
+        fs::remove_dir_all(&trash_dir)?;
+
+        self.forget_indexed_location(Some(name))
+    }
+
+    /// Hand every file in a backpack's directory to the journal before the
+    /// directory is deleted, or moved to `moving_to`, so undo can put it back
+    fn journal_backpack_dir(&self, dir: &Path, moving_to: Option<&Path>) -> Result<()> {
+        for file in walkdir::WalkDir::new(dir) {
+            let file = file?;
+            if !file.file_type().is_file() {
+                continue;
+            }
+            journal::touch(file.path())?;
+            if let Some(to) = moving_to {
+                journal::touch(&to.join(file.path().strip_prefix(dir)?))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop a location's entries from the index after they moved away
+    fn forget_indexed_location(&self, backpack: Option<&str>) -> Result<()> {
+        let index_path = self.get_index_path();
+        let mut index = EntryIndex::load(&index_path);
+        if index.remove_location(backpack) {
+            index.save(&index_path)?;
+        }
+        Ok(())
+    }
+
+    /// Throw the entry index away and build it again from every entry's
+    /// metadata file, returning how many entries it holds
+    pub fn rebuild_index(&self) -> Result<usize> {
+        let index_path = self.get_index_path();
+        if index_path.exists() {
+            fs::remove_file(&index_path)?;
+        }
+        // Listing everything fills it back in
+        Ok(self.list_all_entries(None)?.len())
+    }
+
+    /// Write a backpack manifest so it is replaced atomically
+    fn write_backpack_manifest(&self, backpack: &Backpack) -> Result<()> {
+        atomic::write(self.get_backpack_path(&backpack.name), serde_json::to_string_pretty(backpack)?)?;
+        Ok(())
+    }
+
+    /// Point aliases and pins for one backpack at another location
+    fn retarget_backpack_refs(&self, from: &str, to: Option<&str>) -> Result<()> {
+        let mut aliases = self.load_aliases()?;
+        for alias in aliases.values_mut() {
+            if alias.backpack.as_deref() == Some(from) {
+                alias.backpack = to.map(|s| s.to_string());
+            }
+        }
+        self.save_aliases(&aliases)?;
+
+        let mut pins = self.load_pins()?;
+        for backpack in pins.values_mut() {
+            if backpack.as_deref() == Some(from) {
+                *backpack = to.map(|s| s.to_string());
+            }
+        }
+        self.save_pins(&pins)
+    }
+
+    /// Resolve an entry reference to its full ID and the backpack it lives in
+    ///
+    /// A reference can be an alias, a full ID, or a unique ID prefix. When no
+    /// backpack is given, the general pocket and every backpack are searched.
+    pub fn resolve_entry(&self, reference: &str, backpack: Option<&str>) -> Result<(String, Option<String>)> {
+        let reference = reference.trim();
+        if reference.is_empty() {
+            return Err(StorageError::Validation("Entry ID cannot be empty".to_string()).into());
+        }
+
+        // Aliases take precedence over IDs
+        if let Some(alias) = self.load_aliases()?.get(reference) {
+            if backpack.is_none() || backpack == alias.backpack.as_deref() {
+                return Ok((alias.id.clone(), alias.backpack.clone()));
+            }
+        }
+
+        let locations: Vec<Option<String>> = match backpack {
+            Some(name) => vec![Some(name.to_string())],
+            None => {
+                let mut locations = vec![None];
+                locations.extend(self.list_backpacks()?.into_iter().map(|b| Some(b.name)));
+                locations
+            }
+        };
+
+        let mut matches = Vec::new();
+        let mut known = Vec::new();
+        for location in locations {
+            // An exact ID always wins over prefix matches
+            if self.get_entry_metadata_path(reference, location.as_deref()).exists() {
+                return Ok((reference.to_string(), location));
+            }
+
+            for entry in self.list_entries(location.as_deref())? {
+                if entry.id.starts_with(reference) {
+                    matches.push((entry.id, location.clone()));
+                } else {
+                    known.push(entry.id);
+                }
+            }
+        }
+
+        match matches.len() {
+            0 => {
+                known.extend(self.load_aliases()?.into_keys());
+                let hint = suggest::did_you_mean(reference, known.iter().map(String::as_str));
+                Err(StorageError::NotFound(format!("No entry found matching '{}'{}", reference, hint)).into())
+            }
+            1 => Ok(matches.remove(0)),
+            _ => {
+                let candidates = matches.iter()
+                    .map(|(id, backpack)| match backpack {
+                        Some(name) => format!("{} [{}]", id, name),
+                        None => id.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Err(StorageError::Validation(format!("Entry ID '{}' is ambiguous; candidates: {}", reference, candidates)).into())
+            }
+        }
+    }
+
+    /// Load all entry aliases
+    pub fn load_aliases(&self) -> Result<BTreeMap<String, EntryAlias>> {
+        let path = self.get_aliases_path();
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read aliases from {}", path.display()))?;
+        let aliases = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse aliases from {}", path.display()))?;
+        Ok(aliases)
+    }
+
+    /// Save all entry aliases
+    pub fn save_aliases(&self, aliases: &BTreeMap<String, EntryAlias>) -> Result<()> {
+        let json = serde_json::to_string_pretty(aliases)?;
+        journal::touch(&self.get_aliases_path())?;
+        atomic::write(self.get_aliases_path(), json)?;
+        Ok(())
+    }
+
+    /// Load every workflow schedule and trigger
+    pub fn load_schedules(&self) -> Result<Vec<WorkflowSchedule>> {
+        let path = self.get_schedules_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read schedules from {}", path.display()))?;
+        let schedules = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse schedules from {}", path.display()))?;
+        Ok(schedules)
+    }
+
+    /// Save every workflow schedule and trigger
+    pub fn save_schedules(&self, schedules: &[WorkflowSchedule]) -> Result<()> {
+        let json = serde_json::to_string_pretty(schedules)?;
+        atomic::write(self.get_schedules_path(), json)?;
+        Ok(())
+    }
+
+    /// Something that changes whenever an entry, backpack, alias or pin
+    /// does: the number of data files and the latest time one was written.
+    /// Long-running servers compare it to know when to reload
+    pub fn data_fingerprint(&self) -> Result<(usize, Option<std::time::SystemTime>)> {
+        let mut count = 0;
+        let mut latest = None;
+        for file in walkdir::WalkDir::new(self.base_path.join("data")) {
+            let file = file?;
+            // Workflows don't change what's stored
+            if file.path().starts_with(self.base_path.join("data/workflows")) {
+                continue;
+            }
+            let modified = file.metadata()?.modified()?;
+            count += 1;
+            latest = latest.max(Some(modified));
+        }
+        Ok((count, latest))
+    }
+
+    /// The token API clients must send, made the first time it's asked for.
+    /// `regenerate` replaces it, locking out every client that has the old one
+    pub fn api_token(&self, regenerate: bool) -> Result<String> {
+        let path = self.get_api_token_path();
+        if !regenerate {
+            if let Ok(token) = fs::read_to_string(&path) {
+                if !token.trim().is_empty() {
+                    return Ok(token.trim().to_string());
+                }
+            }
+        }
+
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        // Only the owner should be able to read it, from before the token is
+        // written. A write keeps the old file's permissions, so those go too
+        #[cfg(unix)]
+        use std::os::unix::fs::PermissionsExt;
+        #[cfg(unix)]
+        if path.exists() {
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+        atomic::write_with(&path, |file| {
+            #[cfg(unix)]
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+            Ok(file.write_all(token.as_bytes())?)
+        }).with_context(|| format!("Failed to write API token to {}", path.display()))?;
+        Ok(token)
+    }
+
     /// Load the configuration
     pub fn load_config(&self) -> Result<Config> {
         let config_path = self.get_config_path();
@@ -217,11 +936,61 @@ impl StorageManager {
         Ok(config)
     }
 
+    /// Load the configuration if there is one, without writing the default
+    pub fn read_config(&self) -> Result<Option<Config>> {
+        let config_path = self.get_config_path();
+        if !config_path.exists() {
+            return Ok(None);
+        }
+        let config_str = fs::read_to_string(config_path)?;
+        Ok(Some(toml::from_str(&config_str)?))
+    }
+
+    /// Find the backpack bound to the current directory by a `.pocket-context`
+    /// file in it or any of its parents
+    pub fn context_backpack() -> Result<Option<String>> {
+        let cwd = std::env::current_dir()?;
+
+        for dir in cwd.ancestors() {
+            let path = dir.join(".pocket-context");
+            if !path.is_file() {
+                continue;
+            }
+
+            let context_str = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let context: ProjectContext = toml::from_str(&context_str)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            return Ok(context.backpack);
+        }
+
+        Ok(None)
+    }
+
+    /// Work out which backpack a command should use: an explicit choice wins,
+    /// then the project's `.pocket-context`, then (for new entries) the
+    /// configured default. "general" always means the general pocket.
+    pub fn resolve_backpack(&self, explicit: Option<String>, use_default: bool) -> Result<Option<String>> {
+        let backpack = match explicit {
+            Some(name) => Some(name),
+            None => match Self::context_backpack()? {
+                Some(name) => Some(name),
+                None if use_default => Some(self.load_config()?.user.default_backpack),
+                None => None,
+            },
+        };
+
+        Ok(backpack.filter(|name| name != "general"))
+    }
+
     /// Save the configuration
     pub fn save_config(&self, config: &Config) -> Result<()> {
         let config_path = self.get_config_path();
         let config_str = toml::to_string_pretty(config)?;
-        fs::write(config_path, config_str)?;
+        if let Some(parent) = config_path.parent() {
+            create_dir_all(parent)?;
+        }
+        atomic::write(config_path, config_str)?;
         
         Ok(())
     }
@@ -242,19 +1011,25 @@ impl StorageManager {
     }
 
     /// Save a workflow
-    pub fn _save_workflow(&self, workflow: &Workflow) -> Result<()> {
-        let workflow_path = self._get_workflow_path(&workflow.name);
-        println!("Saving workflow to: {}", workflow_path.display());
-        
+    pub fn save_workflow(&self, workflow: &Workflow) -> Result<()> {
+        let workflow_path = self.get_workflow_path(&workflow.name);
         let workflow_json = serde_json::to_string_pretty(workflow)?;
-        fs::write(workflow_path, workflow_json)?;
+        atomic::write(workflow_path, workflow_json)?;
         
         Ok(())
     }
     
+    /// Whether a workflow with this name has been saved
+    pub fn has_workflow(&self, name: &str) -> bool {
+        self.get_workflow_path(name).is_file()
+    }
+    
     /// Load a workflow
-    pub fn _load_workflow(&self, name: &str) -> Result<Workflow> {
-        let workflow_path = self._get_workflow_path(name);
+    pub fn load_workflow(&self, name: &str) -> Result<Workflow> {
+        let workflow_path = self.get_workflow_path(name);
+        if !workflow_path.exists() {
+            return Err(self.workflow_not_found(name));
+        }
         let workflow_json = fs::read_to_string(&workflow_path)
             .with_context(|| format!("Failed to read workflow '{}'", name))?;
         
@@ -265,18 +1040,24 @@ impl StorageManager {
     }
     
     /// Delete a workflow
-    pub fn _delete_workflow(&self, name: &str) -> Result<()> {
-        let workflow_path = self._get_workflow_path(name);
+    pub fn delete_workflow(&self, name: &str) -> Result<()> {
+        let workflow_path = self.get_workflow_path(name);
         if workflow_path.exists() {
             fs::remove_file(&workflow_path)?;
             Ok(())
         } else {
-            Err(anyhow!("Workflow '{}' not found", name))
+            Err(self.workflow_not_found(name))
         }
     }
+
+    /// The error for a workflow that doesn't exist, suggesting ones that do
+    fn workflow_not_found(&self, name: &str) -> anyhow::Error {
+        let names: Vec<String> = self.list_workflows().unwrap_or_default().into_iter().map(|w| w.name).collect();
+        StorageError::NotFound(format!("Workflow '{}' not found{}", name, suggest::did_you_mean(name, names.iter().map(String::as_str)))).into()
+    }
     
     /// List all workflows
-    pub fn _list_workflows(&self) -> Result<Vec<Workflow>> {
+    pub fn list_workflows(&self) -> Result<Vec<Workflow>> {
         let dir = self.base_path.join("data/workflows");
         if !dir.exists() {
             return Ok(Vec::new());
@@ -306,45 +1087,264 @@ impl StorageManager {
 
     /// Search for entries by query string
     pub fn search_entries(&self, query: &str, backpack: Option<&str>, limit: usize) -> Result<Vec<(Entry, String)>> {
-        let mut results = Vec::new();
-        
+        self.search_entries_where(query, backpack, limit, |_| true)
+    }
+
+    /// Search for entries, skipping any the filter rejects before their content is loaded
+    pub fn search_entries_where(&self, query: &str, backpack: Option<&str>, limit: usize, filter: impl Fn(&Entry) -> bool) -> Result<Vec<(Entry, String)>> {
+        let fuzzy = self.load_config().is_ok_and(|config| config.search.algorithm == SearchAlgorithm::Fuzzy);
+        self.search_entries_matching(&TextQuery::parse(query).fuzzy(fuzzy), backpack, limit, filter)
+    }
+
+    /// Search for entries with a parsed query. Fuzzy matches come closest first
+    pub fn search_entries_matching(&self, query: &TextQuery, backpack: Option<&str>, limit: usize, filter: impl Fn(&Entry) -> bool) -> Result<Vec<(Entry, String)>> {
         // Get entries to search
-        let entries = self.list_entries(backpack)?;
+        let entries: Vec<Entry> = self.list_entries(backpack)?.into_iter().filter(|e| filter(e)).collect();
         
-        // Simple case-insensitive search
-        let query_lower = query.to_lowercase();
+        let pins = self.pinned_ids()?;
         
-        for entry in entries {
-            // Load the content
-            let content = match fs::read_to_string(self.get_entry_content_path(&entry.id, backpack)) {
-                Ok(content) => content,
-                Err(_) => continue, // Skip entries with missing content
-            };
+        // Reading and scanning content is per-file work, so it's spread over
+        // the thread pool; collecting keeps the entries in order
+        parallel::init(|| self.load_config().ok().and_then(|config| config.search.jobs));
+        let mut results: Vec<(Entry, String, f64)> = entries.into_par_iter()
+            .filter_map(|entry| {
+                // Large entries are searched by their excerpt. Skip entries with missing content
+                let content = match entry.get_metadata(EXCERPT_KEY) {
+                    Some(excerpt) => excerpt.to_string(),
+                    None => fs::read_to_string(self.get_entry_content_path(&entry.id, backpack)).ok()?,
+                };
             
-            // Check if query matches title or content
-            if entry.title.to_lowercase().contains(&query_lower) || 
-               content.to_lowercase().contains(&query_lower) {
-                results.push((entry, content));
-                
-                // Check if we've reached the limit
-                if results.len() >= limit {
-                    break;
+                // Content is only lower-cased for queries that look at it
+                let searched = if query.needs_content() { content.as_str() } else { "" };
+                let candidate = Candidate::new(&entry, backpack, searched);
+                if !query.matches(&candidate) {
+                    return None;
                 }
-            }
+                let closeness = query.closeness(&candidate);
+                Some((entry, content, closeness))
+            })
+            .collect();
+        
+        // Closest matches first, but pinned matches rank above all, then apply the limit
+        if query.is_fuzzy() {
+            results.sort_by(|a, b| b.2.total_cmp(&a.2));
         }
+        results.sort_by_key(|(entry, _, _)| !pins.contains(&entry.id));
+        results.truncate(limit);
         
-        Ok(results)
+        Ok(results.into_iter().map(|(entry, content, _)| (entry, content)).collect())
     }
     
+    /// Load an entry along with its raw content bytes, text or binary
+    pub fn load_entry_bytes(&self, id: &str, backpack: Option<&str>) -> Result<(Entry, Vec<u8>)> {
+        let entry = self.load_entry_metadata(id, backpack)?;
+
+        let content_path = self.get_entry_content_path(id, backpack);
+        let content = fs::read(&content_path)
+            .with_context(|| format!("Failed to read entry content from {}", content_path.display()))?;
+
+        Ok((entry, content))
+    }
+
     /// Load the content of an entry
     pub fn _load_entry_content(&self, id: &str, backpack: Option<&str>) -> Result<String> {
         let content_path = self.get_entry_content_path(id, backpack);
         
         if !content_path.exists() {
-            return Err(anyhow!("Content not found for entry '{}'", id));
+            return Err(StorageError::NotFound(format!("Content not found for entry '{}'", id)).into());
         }
         
         let content = fs::read_to_string(&content_path)?;
         Ok(content)
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentType;
+
+    #[test]
+    fn only_real_changes_move_updated_at() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data/entries")).unwrap();
+        let storage = StorageManager::at(dir.path());
+        let mut entry = Entry::new("note".to_string(), ContentType::Text, None, Vec::new());
+        entry.updated_at -= chrono::Duration::days(1);
+        storage.save_entry(&entry, "old", None).unwrap();
+
+        storage.record_usage(&entry.id, None).unwrap();
+        assert_eq!(storage.load_entry_metadata(&entry.id, None).unwrap().updated_at, entry.updated_at);
+
+        let updated = storage.update_entry(&entry.id, None, Some("new"), |e| e.tags.push("edited".to_string())).unwrap();
+        assert!(updated.updated_at > entry.updated_at);
+        let (saved, content) = storage.load_entry(&entry.id, None).unwrap();
+        assert_eq!((saved.tags, saved.usage_count, content.as_str()), (vec!["edited".to_string()], 1, "new"));
+        assert_eq!(saved.content_hash, updated.content_hash);
+
+        // A change based on what the entry was before that one is refused
+        let stale = storage.update_entry_if_unchanged(&entry.id, None, entry.updated_at, Some("mine"), |_| {});
+        assert!(stale.unwrap_err().downcast_ref::<StorageError>().is_some_and(|e| matches!(e, StorageError::Conflict(_))));
+        assert!(storage.update_entry_if_unchanged(&entry.id, None, updated.updated_at, Some("mine"), |_| {}).is_ok());
+    }
+
+    #[test]
+    fn new_content_replaces_a_streamed_entrys_excerpt() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data/entries")).unwrap();
+        let storage = StorageManager::at(dir.path());
+        let entry = Entry::new("log".to_string(), ContentType::Text, None, Vec::new());
+        storage.save_entry_from(&entry, "first draft".as_bytes(), None).unwrap();
+        assert_eq!(storage.search_entries("draft", None, 10).unwrap().len(), 1);
+
+        let updated = storage.update_entry(&entry.id, None, Some("final version"), |_| {}).unwrap();
+        let saved = storage.load_entry_metadata(&entry.id, None).unwrap();
+        for entry in [&updated, &saved] {
+            assert_eq!((entry.get_metadata(EXCERPT_KEY), entry.get_metadata(SIZE_KEY)), (None, None));
+        }
+        assert!(storage.search_entries("draft", None, 10).unwrap().is_empty());
+        assert_eq!(storage.search_entries("final", None, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn an_edit_based_on_a_stale_entry_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data/entries")).unwrap();
+        let storage = StorageManager::at(dir.path());
+        let entry = Entry::new("note".to_string(), ContentType::Text, None, Vec::new());
+        storage.save_entry(&entry, "original", None).unwrap();
+
+        // Someone else changes the entry between loading it and saving the edit
+        let loaded = storage.load_entry_metadata(&entry.id, None).unwrap();
+        let theirs = storage.update_entry(&entry.id, None, Some("theirs"), |e| e.tags.push("theirs".to_string())).unwrap();
+        let mine = storage.update_entry_if_unchanged(&entry.id, None, loaded.updated_at, Some("mine"), |e| e.title = "mine".to_string());
+        assert!(mine.unwrap_err().to_string().contains("changed by something else"));
+
+        let (saved, content) = storage.load_entry(&entry.id, None).unwrap();
+        assert_eq!(content, "theirs");
+        assert_eq!((saved.title.as_str(), saved.updated_at), ("note", theirs.updated_at));
+    }
+
+    /// A pocket in a temporary directory with a `work` backpack holding one
+    /// pinned, aliased entry
+    fn pocket_with_backpack() -> (tempfile::TempDir, StorageManager, Entry) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data/entries")).unwrap();
+        let storage = StorageManager::at(dir.path());
+        storage.create_backpack(&Backpack { name: "work".to_string(), description: None, created_at: Utc::now() }).unwrap();
+        let entry = Entry::new("deploy".to_string(), ContentType::Script, None, Vec::new());
+        storage.save_entry(&entry, "make deploy", Some("work")).unwrap();
+        storage.set_pinned(&entry.id, Some("work"), true).unwrap();
+        let alias = EntryAlias { id: entry.id.clone(), backpack: Some("work".to_string()) };
+        storage.save_aliases(&BTreeMap::from([("dep".to_string(), alias)])).unwrap();
+        (dir, storage, entry)
+    }
+
+    #[test]
+    fn deleting_a_backpack_moves_or_drops_its_entries() {
+        let (_dir, storage, entry) = pocket_with_backpack();
+        storage.delete_backpack("work", true).unwrap();
+        assert!(storage.load_backpack("work").is_err());
+        assert_eq!(storage.load_entry(&entry.id, None).unwrap().1, "make deploy");
+        assert_eq!(storage.resolve_entry("dep", None).unwrap(), (entry.id.clone(), None));
+        assert_eq!(storage.load_pins().unwrap().get(&entry.id), Some(&None));
+
+        let (_dir, storage, entry) = pocket_with_backpack();
+        storage.delete_backpack("work", false).unwrap();
+        assert!(storage.load_entry(&entry.id, None).is_err());
+        assert!(storage.list_all_entries(None).unwrap().is_empty());
+        assert!(storage.load_aliases().unwrap().is_empty());
+        assert!(storage.load_pins().unwrap().is_empty());
+    }
+
+    #[test]
+    fn renaming_a_backpack_follows_its_references() {
+        let (_dir, storage, entry) = pocket_with_backpack();
+        storage.create_backpack(&Backpack { name: "taken".to_string(), description: None, created_at: Utc::now() }).unwrap();
+        assert!(storage.rename_backpack("work", "taken").is_err());
+
+        storage.rename_backpack("work", "ops").unwrap();
+        assert!(storage.load_backpack("work").is_err());
+        assert_eq!(storage.load_backpack("ops").unwrap().name, "ops");
+        assert_eq!(storage.load_entry(&entry.id, Some("ops")).unwrap().1, "make deploy");
+        assert_eq!(storage.resolve_entry("dep", None).unwrap(), (entry.id.clone(), Some("ops".to_string())));
+        assert_eq!(storage.load_pins().unwrap().get(&entry.id), Some(&Some("ops".to_string())));
+    }
+
+    #[test]
+    fn undoing_a_backpack_delete_brings_its_entries_back() {
+        let _turn = journal::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        for move_entries in [false, true] {
+            let (dir, storage, entry) = pocket_with_backpack();
+            let delete = journal::begin(dir.path(), "backpack delete work").unwrap();
+            storage.delete_backpack("work", move_entries).unwrap();
+            delete.finish().unwrap();
+
+            journal::undo(dir.path(), false).unwrap();
+            assert_eq!(storage.load_backpack("work").unwrap().name, "work");
+            assert_eq!(storage.load_entry(&entry.id, Some("work")).unwrap().1, "make deploy");
+            assert!(storage.load_entry(&entry.id, None).is_err());
+            assert_eq!(storage.resolve_entry("dep", None).unwrap(), (entry.id.clone(), Some("work".to_string())));
+            assert_eq!(storage.load_pins().unwrap().get(&entry.id), Some(&Some("work".to_string())));
+
+            journal::redo(dir.path(), false).unwrap();
+            assert!(storage.load_backpack("work").is_err());
+            assert_eq!(storage.load_entry(&entry.id, None).is_ok(), move_entries);
+        }
+    }
+
+    #[test]
+    fn undoing_a_backpack_rename_puts_the_old_name_back() {
+        let _turn = journal::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (dir, storage, entry) = pocket_with_backpack();
+        let rename = journal::begin(dir.path(), "backpack rename work ops").unwrap();
+        storage.rename_backpack("work", "ops").unwrap();
+        rename.finish().unwrap();
+
+        journal::undo(dir.path(), false).unwrap();
+        assert!(!dir.path().join("data/backpacks/ops").exists());
+        assert_eq!(storage.load_backpack("work").unwrap().name, "work");
+        assert_eq!(storage.load_entry(&entry.id, Some("work")).unwrap().1, "make deploy");
+        assert_eq!(storage.resolve_entry("dep", None).unwrap(), (entry.id.clone(), Some("work".to_string())));
+
+        journal::redo(dir.path(), false).unwrap();
+        assert!(storage.load_backpack("work").is_err());
+        assert_eq!(storage.load_backpack("ops").unwrap().name, "ops");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_the_owner_can_read_the_api_token() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data")).unwrap();
+        let storage = StorageManager::at(dir.path());
+        let path = dir.path().join("data/api_token");
+        let mode = || fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+
+        let token = storage.api_token(false).unwrap();
+        assert_eq!(mode(), 0o600);
+        assert_eq!(storage.api_token(false).unwrap(), token);
+
+        // A token left readable by an older version is locked down when replaced
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert_ne!(storage.api_token(true).unwrap(), token);
+        assert_eq!(mode(), 0o600);
+    }
+
+    #[test]
+    fn refuses_backpack_names_that_leave_the_pocket() {
+        let (dir, storage, entry) = pocket_with_backpack();
+        fs::create_dir_all(dir.path().join("outside/entries")).unwrap();
+        for name in ["..", "../outside", "work/../..", ".hidden", ""] {
+            assert!(storage.load_backpack(name).is_err(), "{}", name);
+            assert!(storage.delete_backpack(name, false).is_err(), "{}", name);
+            assert!(storage.rename_backpack(name, "elsewhere").is_err(), "{}", name);
+            assert!(storage.rename_backpack("work", name).is_err(), "{}", name);
+            assert!(storage.list_entries(Some(name)).is_err(), "{}", name);
+            assert!(storage.save_entry(&entry, "escaped", Some(name)).is_err(), "{}", name);
+        }
+        assert!(dir.path().join("outside/entries").is_dir());
+        assert!(storage.load_backpack("work").is_ok());
+    }
+}
@@ -0,0 +1,178 @@
+//! Profiles: separate pockets for separate lives, like work and personal
+//!
+//! The default profile keeps its data in the pocket directory (`~/.pocket`
+//! unless it was moved, see [`super::home`]). Every other profile gets its
+//! own directory under `profiles/` in there,
+//! with its own entries, cards, config and caches. The profile in use is
+//! the one given with `--profile`, else `$POCKET_PROFILE`, else the one
+//! picked with `pocket profile switch`, else the default.
+
+use super::home::pocket_root;
+use crate::errors::StorageError;
+use crate::utils::{atomic, suggest};
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// The profile whose data lives straight in the pocket directory
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Environment variable naming the profile to use
+pub const PROFILE_ENV: &str = "POCKET_PROFILE";
+
+/// File in the pocket directory remembering the profile `pocket profile switch` picked
+const CURRENT_PROFILE_FILE: &str = "profile";
+
+/// Profile given with `--profile`, which wins over everything else
+static PROFILE_OVERRIDE: OnceCell<String> = OnceCell::new();
+
+/// Uses `name` for the rest of the process, as `--profile` asks. Only the
+/// first call counts
+pub fn set_profile(name: &str) -> Result<()> {
+    validate_profile_name(name)?;
+    let _ = PROFILE_OVERRIDE.set(name.to_string());
+    Ok(())
+}
+
+/// The profile in use
+pub fn active_profile() -> String {
+    if let Some(name) = PROFILE_OVERRIDE.get() {
+        return name.clone();
+    }
+    if let Some(name) = env::var(PROFILE_ENV).ok().filter(|name| !name.trim().is_empty()) {
+        return name.trim().to_string();
+    }
+    current_profile().unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// The profile `pocket profile switch` picked, if any
+pub fn current_profile() -> Option<String> {
+    let root = pocket_root().ok()?;
+    fs::read_to_string(root.join(CURRENT_PROFILE_FILE)).ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Where a profile keeps its data
+pub fn profile_dir(name: &str) -> Result<PathBuf> {
+    validate_profile_name(name)?;
+    let root = pocket_root()?;
+    if name == DEFAULT_PROFILE {
+        Ok(root)
+    } else {
+        Ok(root.join("profiles").join(name))
+    }
+}
+
+/// Where the profile in use keeps its data
+pub fn pocket_dir() -> Result<PathBuf> {
+    profile_dir(&active_profile())
+}
+
+/// Every profile, the default one first
+pub fn list_profiles() -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    if let Ok(entries) = fs::read_dir(pocket_root()?.join("profiles")) {
+        names = entries.filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .filter_map(|e| e.file_name().to_str().map(str::to_string))
+            .filter(|name| validate_profile_name(name).is_ok() && name != DEFAULT_PROFILE)
+            .collect();
+        names.sort();
+    }
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    Ok(names)
+}
+
+/// Makes a new, empty profile
+pub fn create_profile(name: &str) -> Result<PathBuf> {
+    let dir = profile_dir(name)?;
+    if name == DEFAULT_PROFILE || dir.exists() {
+        return Err(StorageError::Conflict(format!("Profile '{}' already exists", name)).into());
+    }
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Makes `name` the profile used when neither `--profile` nor
+/// `$POCKET_PROFILE` say otherwise
+pub fn switch_profile(name: &str) -> Result<()> {
+    let dir = profile_dir(name)?;
+    if !dir.exists() {
+        let profiles = list_profiles()?;
+        let hint = suggest::did_you_mean(name, profiles.iter().map(String::as_str));
+        if !hint.is_empty() {
+            return Err(StorageError::NotFound(format!("No profile named '{}'{}", name, hint)).into());
+        }
+        return Err(StorageError::NotFound(format!("No profile named '{}' (create it with `pocket profile create {}`)", name, name)).into());
+    }
+    let root = pocket_root()?;
+    fs::create_dir_all(&root)?;
+    if name == DEFAULT_PROFILE {
+        let path = root.join(CURRENT_PROFILE_FILE);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    atomic::write(root.join(CURRENT_PROFILE_FILE), format!("{}\n", name))?;
+    Ok(())
+}
+
+/// Check that a profile name is usable as a directory name
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(StorageError::Validation(format!("Invalid profile name '{}' (use letters, digits, '-' and '_')", name)).into());
+    }
+    Ok(())
+}
+
+/// The value of `--profile` in raw command-line arguments. Cards are loaded
+/// from the profile's directory before the command line is parsed, so the
+/// profile has to be known first
+pub fn profile_arg(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Everything after `--` belongs to something else
+        if arg == "--" {
+            return None;
+        }
+        if arg == "--profile" {
+            return args.next();
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+    
+    #[test]
+    fn finds_the_profile_argument() {
+        assert_eq!(profile_arg(args("pocket --profile work list")), Some("work".to_string()));
+        assert_eq!(profile_arg(args("pocket list --profile=home")), Some("home".to_string()));
+        assert_eq!(profile_arg(args("pocket execute deploy -- --profile work")), None);
+        assert_eq!(profile_arg(args("pocket list")), None);
+    }
+    
+    #[test]
+    fn checks_profile_names() {
+        assert!(validate_profile_name("work").is_ok());
+        assert!(validate_profile_name("client_2-staging").is_ok());
+        assert!(validate_profile_name("").is_err());
+        assert!(validate_profile_name("../etc").is_err());
+        assert!(validate_profile_name("my work").is_err());
+    }
+}
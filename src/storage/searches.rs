@@ -0,0 +1,120 @@
+//! Saved searches and the searches made lately
+//!
+//! Saved searches live in `data/searches.json`, by name, and can be run as
+//! `pocket search @name` anywhere a search is taken, workflows included.
+//! The history of searches typed at `pocket search` is in
+//! `search_history.json` in the pocket directory.
+
+use super::{journal, StorageManager};
+use crate::utils::{atomic, suggest};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Searches kept in the history
+pub const HISTORY_LIMIT: usize = 100;
+
+/// A search made with `pocket search`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PastSearch {
+    pub query: String,
+    pub time: DateTime<Utc>,
+}
+
+impl StorageManager {
+    fn get_saved_searches_path(&self) -> PathBuf {
+        self.base_path.join("data/searches.json")
+    }
+
+    fn get_search_history_path(&self) -> PathBuf {
+        self.base_path.join("search_history.json")
+    }
+
+    /// Saved searches, by name
+    pub fn load_saved_searches(&self) -> Result<BTreeMap<String, String>> {
+        let path = self.get_saved_searches_path();
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read saved searches from {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse saved searches from {}", path.display()))
+    }
+
+    pub fn save_saved_searches(&self, searches: &BTreeMap<String, String>) -> Result<()> {
+        let json = serde_json::to_string_pretty(searches)?;
+        journal::touch(&self.get_saved_searches_path())?;
+        atomic::write(self.get_saved_searches_path(), json)?;
+        Ok(())
+    }
+
+    /// The query to run for a search: the saved one for `@name`, else the
+    /// search as given
+    pub fn resolve_search(&self, search: &str) -> Result<String> {
+        let Some(name) = search.strip_prefix('@') else {
+            return Ok(search.to_string());
+        };
+        let saved = self.load_saved_searches()?;
+        match saved.get(name) {
+            Some(query) => Ok(query.clone()),
+            None => {
+                let hint = suggest::did_you_mean(name, saved.keys().map(String::as_str));
+                Err(anyhow!("Saved search '{}' not found{}", name, hint))
+            },
+        }
+    }
+
+    /// Searches made lately, oldest first
+    pub fn search_history(&self) -> Result<Vec<PastSearch>> {
+        let path = self.get_search_history_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read search history from {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse search history from {}", path.display()))
+    }
+
+    /// Adds a search to the history. Searching for the same thing again
+    /// moves it to the end rather than listing it twice
+    pub fn record_search(&self, query: &str) -> Result<()> {
+        let mut history = self.search_history().unwrap_or_default();
+        history.retain(|past| past.query != query);
+        history.push(PastSearch { query: query.to_string(), time: Utc::now() });
+        let excess = history.len().saturating_sub(HISTORY_LIMIT);
+        history.drain(..excess);
+        atomic::write(self.get_search_history_path(), serde_json::to_string_pretty(&history)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_and_remembers_searches() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("data")).unwrap();
+        let storage = StorageManager::at(dir.path());
+
+        let mut saved = BTreeMap::new();
+        saved.insert("rust-http".to_string(), "tag:rust http client".to_string());
+        storage.save_saved_searches(&saved).unwrap();
+        assert_eq!(storage.resolve_search("@rust-http").unwrap(), "tag:rust http client");
+        assert_eq!(storage.resolve_search("plain words").unwrap(), "plain words");
+        let missing = storage.resolve_search("@rust-htp").unwrap_err().to_string();
+        assert!(missing.contains("did you mean 'rust-http'"), "{}", missing);
+
+        storage.record_search("docker").unwrap();
+        storage.record_search("helm").unwrap();
+        storage.record_search("docker").unwrap();
+        let queries: Vec<String> = storage.search_history().unwrap().into_iter().map(|past| past.query).collect();
+        assert_eq!(queries, ["helm", "docker"]);
+    }
+}
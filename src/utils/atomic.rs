@@ -0,0 +1,137 @@
+//! Writing files so a crash or a full disk never leaves half of one behind
+//!
+//! The new contents go to a temporary file next to the real one, get synced
+//! to disk, and then replace the real file with a rename, which either
+//! happens completely or not at all. A temporary file left by an interrupted
+//! write is harmless, and [`recover`] cleans them up.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Suffix of the temporary files writes go through
+const TEMP_SUFFIX: &str = ".pocket-tmp";
+
+/// Temporary files younger than this may belong to a write in progress
+const ABANDONED_AFTER: Duration = Duration::from_secs(60);
+
+/// Replaces the contents of `path` in one step. A symlink gets its target
+/// replaced, and an existing file keeps its permissions
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+    let contents = contents.as_ref();
+    write_with(path, |file| Ok(file.write_all(contents)?))
+}
+
+/// Replaces the contents of `path` with whatever `fill` writes, in one
+/// step, for contents too big to hold in memory
+pub fn write_with(path: impl AsRef<Path>, fill: impl FnOnce(&mut File) -> Result<()>) -> Result<()> {
+    let path = path.as_ref();
+    // Write through symlinks (like dotfiles kept in a repo) instead of over them
+    let path = if path.is_symlink() { fs::canonicalize(path)? } else { path.to_path_buf() };
+    let temp = temp_path(&path);
+    
+    let result = write_temp(&path, &temp, fill)
+        .and_then(|()| fs::rename(&temp, &path).with_context(|| format!("Failed to replace {}", path.display())));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+    
+    sync_dir(&path);
+    Ok(())
+}
+
+/// Deletes temporary files abandoned by interrupted writes in `dir` and the
+/// directories below it, returning how many there were
+pub fn recover(dir: &Path) -> Result<usize> {
+    let mut removed = 0;
+    if !dir.is_dir() {
+        return Ok(removed);
+    }
+    let now = SystemTime::now();
+    for entry in walkdir::WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() || !is_temp(entry.path()) {
+            continue;
+        }
+        let abandoned = entry.metadata().ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= ABANDONED_AFTER);
+        if abandoned && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Whether a file is one of the temporary files writes go through
+pub fn is_temp(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(TEMP_SUFFIX))
+}
+
+fn write_temp(path: &Path, temp: &Path, fill: impl FnOnce(&mut File) -> Result<()>) -> Result<()> {
+    let mut file = File::create(temp)
+        .with_context(|| format!("Failed to create {}", temp.display()))?;
+    fill(&mut file)
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    if let Ok(metadata) = fs::metadata(path) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    file.sync_all()
+        .with_context(|| format!("Failed to sync {}", temp.display()))?;
+    Ok(())
+}
+
+/// The temporary file for a write to `path`. The process ID keeps two
+/// pockets writing the same file from sharing one
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    path.with_file_name(format!(".{}.{}{}", name, std::process::id(), TEMP_SUFFIX))
+}
+
+/// Makes the rename itself durable. Only Unix can sync a directory
+fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn replaces_files_without_leaving_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entry.json");
+        write(&path, "{}").unwrap();
+        write(&path, "{\"id\": 1}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"id\": 1}");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+    
+    #[test]
+    fn recovers_only_abandoned_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("entries")).unwrap();
+        let fresh = dir.path().join(format!(".config.toml.1{}", TEMP_SUFFIX));
+        let abandoned = dir.path().join("entries").join(format!(".a.json.2{}", TEMP_SUFFIX));
+        fs::write(&fresh, "").unwrap();
+        fs::write(&abandoned, "").unwrap();
+        File::options().write(true).open(&abandoned).unwrap()
+            .set_modified(SystemTime::now() - ABANDONED_AFTER * 2).unwrap();
+        
+        assert_eq!(recover(dir.path()).unwrap(), 1);
+        assert!(fresh.exists());
+        assert!(!abandoned.exists());
+    }
+}
@@ -0,0 +1,83 @@
+//! Stopping long operations cleanly on Ctrl-C
+//!
+//! While an operation started with [`begin`] runs, Ctrl-C only asks it to
+//! stop: it checks [`check`] between steps and cleans up after itself on
+//! the way out. A second Ctrl-C gives up waiting and exits, except inside
+//! [`shielded`] steps, which would leave things half done. With nothing
+//! running, Ctrl-C exits straight away as usual.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Once;
+
+/// Exit code for a process stopped by Ctrl-C
+pub const EXIT_CODE: i32 = 130;
+
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+static SHIELDED: AtomicUsize = AtomicUsize::new(0);
+static INSTALL: Once = Once::new();
+
+/// The error an operation stopped by Ctrl-C returns
+#[derive(Debug, thiserror::Error)]
+#[error("Cancelled")]
+pub struct Cancelled;
+
+/// Takes over Ctrl-C. Only the first call does anything
+pub fn install() {
+    INSTALL.call_once(|| {
+        let result = ctrlc::set_handler(|| {
+            INTERRUPTED.store(true, Ordering::SeqCst);
+            let active = ACTIVE.load(Ordering::SeqCst) > 0;
+            let shielded = SHIELDED.load(Ordering::SeqCst) > 0;
+            if active && (shielded || !CANCELLED.swap(true, Ordering::SeqCst)) {
+                eprintln!("\nStopping...");
+                return;
+            }
+            std::process::exit(EXIT_CODE);
+        });
+        if let Err(e) = result {
+            log::debug!("Failed to handle Ctrl-C: {}", e);
+        }
+    });
+}
+
+/// Marks a cancellable operation as running until the guard drops
+pub fn begin() -> Operation {
+    ACTIVE.fetch_add(1, Ordering::SeqCst);
+    Operation { _private: () }
+}
+
+/// A running cancellable operation
+pub struct Operation {
+    _private: (),
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        if ACTIVE.fetch_sub(1, Ordering::SeqCst) == 1 {
+            CANCELLED.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Fails with [`Cancelled`] once Ctrl-C was pressed, except in shielded steps
+pub fn check() -> Result<(), Cancelled> {
+    if CANCELLED.load(Ordering::SeqCst) && SHIELDED.load(Ordering::SeqCst) == 0 {
+        return Err(Cancelled);
+    }
+    Ok(())
+}
+
+/// Whether Ctrl-C was pressed at any point
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Runs a step that mustn't be stopped halfway
+pub fn shielded<T>(step: impl FnOnce() -> T) -> T {
+    SHIELDED.fetch_add(1, Ordering::SeqCst);
+    let result = step();
+    SHIELDED.fetch_sub(1, Ordering::SeqCst);
+    result
+}
@@ -1,182 +1,264 @@
+//! Reading and writing the system clipboard
+//!
+//! Each tool that can reach the clipboard is a [`ClipboardProvider`]. Left
+//! on `auto`, `clipboard.provider` in `config.toml` tries them in the order
+//! that suits the system: wl-clipboard first under Wayland, xclip, xsel,
+//! pbcopy on macOS and PowerShell on Windows. When none of them can copy,
+//! as over SSH or on a server without a display, the text is sent to the
+//! terminal in an OSC 52 escape sequence and the terminal sets its own
+//! clipboard. There's no telling whether the terminal did, and OSC 52 can't
+//! read the clipboard back, so pasting still needs one of the tools.
+
+use crate::models::ClipboardProvider;
 use anyhow::{Result, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use std::io::{IsTerminal, Write};
 use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// The provider set in `config.toml`
+static PROVIDER: OnceLock<ClipboardProvider> = OnceLock::new();
+
+/// Uses `provider` (`clipboard.provider`) for the rest of the process
+pub fn set_provider(provider: ClipboardProvider) {
+    let _ = PROVIDER.set(provider);
+}
 
 /// Read content from the system clipboard
-/// 
-/// Supports macOS (pbpaste), Windows (PowerShell), and Linux (xclip/wl-paste)
 pub fn read_clipboard() -> Result<String> {
-    #[cfg(target_os = "macos")]
-    {
-        let output = Command::new("pbpaste")
-            .output()
-            .map_err(|_| anyhow!("Failed to access clipboard. Make sure 'pbpaste' is available."))?;
-        
-        if !output.status.success() {
-            return Err(anyhow!("Failed to read from clipboard"));
-        }
-        
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    }
-    
-    #[cfg(target_os = "windows")]
-    {
-        let output = Command::new("powershell.exe")
-            .args(["-command", "Get-Clipboard"])
-            .output()
-            .map_err(|_| anyhow!("Failed to access clipboard. Make sure PowerShell is available."))?;
-        
-        if !output.status.success() {
-            return Err(anyhow!("Failed to read from clipboard"));
-        }
-        
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    }
-    
-    #[cfg(all(unix, not(target_os = "macos")))]
-    {
-        // Try XClip first (X11)
-        let xclip_result = Command::new("xclip")
-            .args(["-selection", "clipboard", "-o"])
-            .output();
-        
-        // If XClip works, use it
-        if let Ok(output) = xclip_result {
-            if output.status.success() {
-                return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
-            }
-        }
-        
-        // Try wl-paste (Wayland)
-        let wl_paste_result = Command::new("wl-paste")
-            .output();
-        
-        // If wl-paste works, use it
-        if let Ok(output) = wl_paste_result {
-            if output.status.success() {
-                return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    let providers = chain(configured(), wayland());
+    for provider in &providers {
+        if let Some((program, args)) = paste_command(*provider) {
+            if let Some(content) = run_paste(program, args) {
+                return Ok(content.trim().to_string());
             }
         }
-        
-        // If neither worked, raise an error
-        Err(anyhow!("Failed to access clipboard. Please install xclip (for X11) or wl-paste (for Wayland)."))
-    }
-    
-    // Fallback for unsupported platforms
-    #[cfg(not(any(target_os = "macos", target_os = "windows", all(unix, not(target_os = "macos")))))]
-    {
-        Err(anyhow!("Clipboard functionality is not supported on this platform"))
-    }
-}
-
-/// Write content to the system clipboard (unused)
-/// 
-/// Supports macOS (pbcopy), Windows (PowerShell), and Linux (xclip/wl-copy)
-pub fn _write_clipboard(content: &str) -> Result<()> {
-    #[cfg(target_os = "macos")]
-    {
-        let mut child = Command::new("pbcopy")
-            .stdin(Stdio::piped())
-            .spawn()
-            .map_err(|_| anyhow!("Failed to access clipboard. Make sure 'pbcopy' is available."))?;
-        
-        if let Some(mut stdin) = child.stdin.take() {
-            use std::io::Write;
-            stdin.write_all(content.as_bytes())?;
-        }
-        
-        let status = child.wait()?;
-        if !status.success() {
-            return Err(anyhow!("Failed to write to clipboard"));
-        }
-        
-        Ok(())
-    }
-    
-    #[cfg(target_os = "windows")]
-    {
-        // PowerShell command to set clipboard
-        let mut child = Command::new("powershell.exe")
-            .args(["-command", "Set-Clipboard -Value $input"])
-            .stdin(Stdio::piped())
-            .spawn()
-            .map_err(|_| anyhow!("Failed to access clipboard. Make sure PowerShell is available."))?;
-        
-        if let Some(mut stdin) = child.stdin.take() {
-            use std::io::Write;
-            stdin.write_all(content.as_bytes())?;
-        }
-        
-        let status = child.wait()?;
-        if !status.success() {
-            return Err(anyhow!("Failed to write to clipboard"));
-        }
-        
-        Ok(())
-    }
-    
-    #[cfg(all(unix, not(target_os = "macos")))]
-    {
-        // Try XClip first (X11)
-        let mut xclip_child = Command::new("xclip")
-            .args(["-selection", "clipboard"])
-            .stdin(Stdio::piped())
-            .spawn();
-        
-        if let Ok(mut child) = xclip_child {
-            if let Some(mut stdin) = child.stdin.take() {
-                use std::io::Write;
-                stdin.write_all(content.as_bytes())?;
-            }
-            
-            let status = child.wait()?;
-            if status.success() {
-                return Ok(());
+    }
+    Err(anyhow!("Failed to read from the clipboard. {}", hint(&providers, "paste")))
+}
+
+/// Read the currently selected text
+///
+/// On Linux this is the primary selection. Other platforms have no separate
+/// selection, so the clipboard is used instead
+pub fn read_selection() -> Result<String> {
+    if !cfg!(all(unix, not(target_os = "macos"))) {
+        return read_clipboard();
+    }
+
+    let providers = chain(configured(), wayland());
+    for provider in &providers {
+        if let Some((program, args)) = selection_command(*provider) {
+            if let Some(content) = run_paste(program, args) {
+                return Ok(content);
             }
         }
-        
-        // Try wl-copy (Wayland)
-        let mut wl_copy_child = Command::new("wl-copy")
-            .stdin(Stdio::piped())
-            .spawn();
-        
-        if let Ok(mut child) = wl_copy_child {
-            if let Some(mut stdin) = child.stdin.take() {
-                use std::io::Write;
-                stdin.write_all(content.as_bytes())?;
-            }
-            
-            let status = child.wait()?;
-            if status.success() {
-                return Ok(());
-            }
+    }
+    Err(anyhow!("Failed to read the selection. {} Or pipe the text in.", hint(&providers, "paste")))
+}
+
+/// Write content to the system clipboard
+pub fn write_clipboard(content: &str) -> Result<()> {
+    let providers = chain(configured(), wayland());
+    for provider in &providers {
+        let copied = match copy_command(*provider) {
+            Some((program, args)) => run_copy(program, args, content),
+            None => write_osc52(content),
+        };
+        match copied {
+            Ok(()) => return Ok(()),
+            Err(e) => log::debug!("Copying with {} failed: {}", name(*provider), e),
         }
-        
-        // If neither worked, raise an error
-        Err(anyhow!("Failed to access clipboard. Please install xclip (for X11) or wl-copy (for Wayland)."))
     }
-    
-    // Fallback for unsupported platforms
-    #[cfg(not(any(target_os = "macos", target_os = "windows", all(unix, not(target_os = "macos")))))]
-    {
-        Err(anyhow!("Clipboard functionality is not supported on this platform"))
+    Err(anyhow!("Failed to copy to the clipboard. {}", hint(&providers, "copy")))
+}
+
+/// The programs `provider` copies with, in the order they're tried. OSC 52
+/// needs none
+pub fn copy_tools(provider: ClipboardProvider) -> Vec<&'static str> {
+    chain(provider, wayland()).into_iter()
+        .filter_map(copy_command)
+        .map(|(program, _)| program)
+        .collect()
+}
+
+fn configured() -> ClipboardProvider {
+    PROVIDER.get().copied().unwrap_or_default()
+}
+
+fn wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty())
+}
+
+/// The providers to try for `provider`, in order
+fn chain(provider: ClipboardProvider, wayland: bool) -> Vec<ClipboardProvider> {
+    use ClipboardProvider::*;
+
+    if provider != Auto {
+        return vec![provider];
+    }
+    if cfg!(target_os = "macos") {
+        vec![Pbcopy, Osc52]
+    } else if cfg!(windows) {
+        vec![Windows, Osc52]
+    } else if wayland {
+        vec![WlClipboard, Xclip, Xsel, Osc52]
+    } else {
+        vec![Xclip, Xsel, WlClipboard, Osc52]
+    }
+}
+
+fn name(provider: ClipboardProvider) -> &'static str {
+    match provider {
+        ClipboardProvider::Auto => "auto",
+        ClipboardProvider::WlClipboard => "wl-clipboard",
+        ClipboardProvider::Xclip => "xclip",
+        ClipboardProvider::Xsel => "xsel",
+        ClipboardProvider::Pbcopy => "pbcopy",
+        ClipboardProvider::Windows => "PowerShell",
+        ClipboardProvider::Osc52 => "OSC 52",
+    }
+}
+
+/// What to do about none of `providers` working
+fn hint(providers: &[ClipboardProvider], action: &str) -> String {
+    if let [provider] = providers {
+        return match provider {
+            ClipboardProvider::Osc52 if action == "paste" => "OSC 52 can only copy; set clipboard.provider in config.toml to a tool that can paste.".to_string(),
+            ClipboardProvider::Osc52 => "OSC 52 needs pocket to be run in a terminal.".to_string(),
+            provider => format!("Make sure {} is installed and working, or set clipboard.provider = \"auto\" in config.toml.", name(*provider)),
+        };
+    }
+    let tools: Vec<&str> = providers.iter()
+        .filter(|provider| **provider != ClipboardProvider::Osc52)
+        .map(|provider| name(*provider))
+        .collect();
+    format!("Tried {}. Install one of them, or set clipboard.provider in config.toml.", tools.join(", "))
+}
+
+fn copy_command(provider: ClipboardProvider) -> Option<(&'static str, &'static [&'static str])> {
+    match provider {
+        ClipboardProvider::WlClipboard => Some(("wl-copy", &[])),
+        ClipboardProvider::Xclip => Some(("xclip", &["-selection", "clipboard"])),
+        ClipboardProvider::Xsel => Some(("xsel", &["--clipboard", "--input"])),
+        ClipboardProvider::Pbcopy => Some(("pbcopy", &[])),
+        ClipboardProvider::Windows => Some(("powershell.exe", &["-command", "Set-Clipboard -Value $input"])),
+        ClipboardProvider::Auto | ClipboardProvider::Osc52 => None,
+    }
+}
+
+fn paste_command(provider: ClipboardProvider) -> Option<(&'static str, &'static [&'static str])> {
+    match provider {
+        ClipboardProvider::WlClipboard => Some(("wl-paste", &["--no-newline"])),
+        ClipboardProvider::Xclip => Some(("xclip", &["-selection", "clipboard", "-o"])),
+        ClipboardProvider::Xsel => Some(("xsel", &["--clipboard", "--output"])),
+        ClipboardProvider::Pbcopy => Some(("pbpaste", &[])),
+        ClipboardProvider::Windows => Some(("powershell.exe", &["-command", "Get-Clipboard"])),
+        ClipboardProvider::Auto | ClipboardProvider::Osc52 => None,
+    }
+}
+
+fn selection_command(provider: ClipboardProvider) -> Option<(&'static str, &'static [&'static str])> {
+    match provider {
+        ClipboardProvider::WlClipboard => Some(("wl-paste", &["--primary", "--no-newline"])),
+        ClipboardProvider::Xclip => Some(("xclip", &["-selection", "primary", "-o"])),
+        ClipboardProvider::Xsel => Some(("xsel", &["--primary", "--output"])),
+        _ => None,
+    }
+}
+
+/// What `program` printed, if it ran and succeeded
+fn run_paste(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).stderr(Stdio::null()).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn run_copy(program: &str, args: &[&str], content: &str) -> Result<()> {
+    // xclip and xsel stay behind to serve the clipboard, so they mustn't
+    // hold on to our output
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("{} exited with {}", program, status));
+    }
+    Ok(())
+}
+
+/// The escape sequence asking the terminal to put `content` on the
+/// clipboard, wrapped so tmux passes it on to the terminal outside
+fn osc52_sequence(content: &str, tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(content));
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
     }
 }
 
+fn write_osc52(content: &str) -> Result<()> {
+    let sequence = osc52_sequence(content, std::env::var_os("TMUX").is_some());
+
+    // The terminal, even when output is piped
+    #[cfg(unix)]
+    if let Ok(mut tty) = std::fs::OpenOptions::new().write(true).open("/dev/tty") {
+        tty.write_all(sequence.as_bytes())?;
+        tty.flush()?;
+        return Ok(());
+    }
+
+    let mut stderr = std::io::stderr();
+    if !stderr.is_terminal() {
+        return Err(anyhow!("not running in a terminal"));
+    }
+    stderr.write_all(sequence.as_bytes())?;
+    stderr.flush()?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+
     #[test]
     #[ignore] // Ignore by default as it interacts with system clipboard
     fn test_clipboard_write_read() {
         let test_content = "Test clipboard content";
-        
+
         // Write to clipboard
-        _write_clipboard(test_content).expect("Failed to write to clipboard");
-        
+        write_clipboard(test_content).expect("Failed to write to clipboard");
+
         // Read from clipboard
         let read_content = read_clipboard().expect("Failed to read from clipboard");
-        
+
         assert_eq!(read_content, test_content);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn tries_the_configured_provider_or_each_that_fits() {
+        assert_eq!(chain(ClipboardProvider::Xsel, true), vec![ClipboardProvider::Xsel]);
+
+        let auto = chain(ClipboardProvider::Auto, true);
+        assert_eq!(auto.last(), Some(&ClipboardProvider::Osc52));
+        if cfg!(all(unix, not(target_os = "macos"))) {
+            assert_eq!(auto[0], ClipboardProvider::WlClipboard);
+            assert_eq!(chain(ClipboardProvider::Auto, false)[0], ClipboardProvider::Xclip);
+        }
+    }
+
+    #[test]
+    fn encodes_osc52_for_the_terminal_and_for_tmux() {
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(osc52_sequence("hi", true), "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+    }
+}
@@ -0,0 +1,49 @@
+//! Showing what an edit changes, as a colored unified diff
+
+use colored::Colorize;
+use similar::{ChangeTag, TextDiff};
+
+/// Lines of unchanged context shown around each change
+const CONTEXT_LINES: usize = 3;
+
+/// The changes from `old` to `new`, removed lines in red and added ones in
+/// green, with a few unchanged lines around each
+pub fn colored(old: &str, new: &str) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = String::new();
+    for group in diff.grouped_ops(CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let (old_range, new_range) = (first.old_range().start..last.old_range().end, first.new_range().start..last.new_range().end);
+        let header = format!("@@ -{},{} +{},{} @@", old_range.start + 1, old_range.len(), new_range.start + 1, new_range.len());
+        out.push_str(&format!("{}\n", header.cyan()));
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let line = change.value().trim_end_matches('\n');
+                let line = match change.tag() {
+                    ChangeTag::Delete => format!("-{}", line).red().to_string(),
+                    ChangeTag::Insert => format!("+{}", line).green().to_string(),
+                    ChangeTag::Equal => format!(" {}", line).dimmed().to_string(),
+                };
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_changed_lines_with_context() {
+        colored::control::set_override(false);
+        let old = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\n";
+        let new = "one\ntwo\nthree\nfour\nfive\nsix\nseven\nEIGHT\n";
+        assert_eq!(colored(old, new), "@@ -5,4 +5,4 @@\n five\n six\n seven\n-eight\n+EIGHT\n");
+        assert_eq!(colored(old, old), "");
+    }
+}
@@ -0,0 +1,395 @@
+//! Running stored scripts, with optional guardrails
+//!
+//! Scripts run through `sh -c` (`cmd /C` on Windows) unless an interpreter is
+//! picked for them, in which case they're written to a temporary file and
+//! handed to it. Callers can cap how long they run, pick the working
+//! directory, add environment variables and cut off network access where the
+//! platform can do that without root.
+
+use anyhow::{Result, anyhow, bail, Context};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a script with a timeout is checked on
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Limits applied to a script run
+#[derive(Debug, Default, Clone)]
+pub struct ExecOptions {
+    /// Kill the script if it runs longer than this
+    pub timeout: Option<Duration>,
+    
+    /// Directory to run in, instead of the current one
+    pub cwd: Option<PathBuf>,
+    
+    /// Extra environment variables
+    pub env: Vec<(String, String)>,
+    
+    /// Run without network access
+    pub no_network: bool,
+    
+    /// Command line of the interpreter to run the script with, such as
+    /// `python3` or `node --harmony`. `None` runs it through the shell
+    pub interpreter: Option<String>,
+    
+    /// Keep a copy of what the script prints, while still passing it through
+    pub capture_output: bool,
+}
+
+/// How a script run went
+#[derive(Debug)]
+pub struct ExecOutcome {
+    /// The script's exit status
+    pub status: ExitStatus,
+    
+    /// Whether the script was killed for running past its timeout
+    pub timed_out: bool,
+    
+    /// How long the script ran
+    pub duration: Duration,
+    
+    /// What the script printed to stdout, when capturing
+    pub stdout: String,
+    
+    /// What the script printed to stderr, when capturing
+    pub stderr: String,
+}
+
+/// The interpreter a script asks for on its `#!` line. `/usr/bin/env` is
+/// looked through, so `#!/usr/bin/env -S node --harmony` gives `node --harmony`
+pub fn shebang_interpreter(content: &str) -> Option<String> {
+    let line = content.lines().next()?.strip_prefix("#!")?.trim();
+    let mut parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.is_empty() {
+        return None;
+    }
+    
+    if parts[0].rsplit('/').next() == Some("env") {
+        parts.remove(0);
+        while parts.first().is_some_and(|p| p.starts_with('-')) {
+            parts.remove(0);
+        }
+        if parts.is_empty() {
+            return None;
+        }
+    }
+    Some(parts.join(" "))
+}
+
+/// The usual interpreter for a detected language, for scripts without a shebang
+pub fn language_interpreter(language: &str) -> Option<&'static str> {
+    match language {
+        "python" => Some("python3"),
+        "javascript" => Some("node"),
+        "typescript" => Some("ts-node"),
+        "ruby" => Some("ruby"),
+        "perl" => Some("perl"),
+        "php" => Some("php"),
+        "lua" => Some("lua"),
+        "powershell" => Some("pwsh"),
+        _ => None,
+    }
+}
+
+/// Picks the interpreter for a script: an explicit choice wins, then the
+/// shebang, then the script's language. `None` means the platform shell
+pub fn pick_interpreter(explicit: Option<&str>, content: &str, language: Option<&str>) -> Option<String> {
+    explicit
+        .map(str::to_string)
+        .or_else(|| shebang_interpreter(content))
+        .or_else(|| language.and_then(language_interpreter).map(str::to_string))
+}
+
+/// Parses a `KEY=VALUE` pair as given to `--env`
+pub fn parse_env(pair: &str) -> Result<(String, String)> {
+    match pair.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(anyhow!("Expected KEY=VALUE, got '{}'", pair)),
+    }
+}
+
+/// Runs `content` as a shell script named `name` (its `$0`), passing `args`
+/// as `$1`, `$2`, ... and waits for it under the given limits
+pub fn run_script(name: &str, content: &str, args: &[String], options: &ExecOptions) -> Result<ExecOutcome> {
+    // Interpreters get the script as a file, which has to outlive the run
+    let mut script_file = None;
+    let mut argv = match &options.interpreter {
+        Some(interpreter) => {
+            let file = write_script(name, content)?;
+            let argv = interpreter_argv(interpreter, file.path(), args)?;
+            script_file = Some(file);
+            argv
+        },
+        None => shell_argv(name, content, args),
+    };
+    if options.no_network {
+        argv = isolate_network(argv)?;
+    }
+    
+    let mut command = Command::new(&argv[0]);
+    command.args(&argv[1..]);
+    if let Some(cwd) = &options.cwd {
+        if !cwd.is_dir() {
+            bail!("Working directory {} doesn't exist", cwd.display());
+        }
+        command.current_dir(cwd);
+    }
+    command.envs(options.env.iter().map(|(k, v)| (k, v)));
+    
+    // A process group of its own lets a timeout take down anything the script started
+    #[cfg(unix)]
+    if options.timeout.is_some() {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    
+    if options.capture_output {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    
+    let started = Instant::now();
+    let mut child = command.spawn()
+        .with_context(|| format!("Failed to start {}", argv[0]))?;
+    
+    // Drain both pipes as the script runs so it never blocks on a full one
+    let stdout = child.stdout.take().map(|pipe| thread::spawn(move || tee(pipe, std::io::stdout())));
+    let stderr = child.stderr.take().map(|pipe| thread::spawn(move || tee(pipe, std::io::stderr())));
+    
+    let (status, timed_out) = match options.timeout {
+        Some(timeout) => wait_with_timeout(child, timeout)?,
+        None => (child.wait()?, false),
+    };
+    let duration = started.elapsed();
+    drop(script_file);
+    
+    let collect = |handle: Option<thread::JoinHandle<Vec<u8>>>| {
+        handle
+            .and_then(|handle| handle.join().ok())
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default()
+    };
+    Ok(ExecOutcome {
+        status,
+        timed_out,
+        duration,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
+
+/// Copies a pipe to `out` as it fills, returning everything that went through
+fn tee(mut pipe: impl Read, mut out: impl Write) -> Vec<u8> {
+    let mut captured = Vec::new();
+    let mut buffer = [0u8; 8192];
+    while let Ok(read) = pipe.read(&mut buffer) {
+        if read == 0 {
+            break;
+        }
+        let _ = out.write_all(&buffer[..read]);
+        let _ = out.flush();
+        captured.extend_from_slice(&buffer[..read]);
+    }
+    captured
+}
+
+/// Writes a script to a temporary file named after it
+fn write_script(name: &str, content: &str) -> Result<tempfile::NamedTempFile> {
+    let prefix: String = name.chars()
+        .take(32)
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let mut file = tempfile::Builder::new()
+        .prefix(&format!("pocket-{}-", prefix.trim_matches('-')))
+        .tempfile()
+        .context("Failed to create a temporary script file")?;
+    file.write_all(content.as_bytes())?;
+    file.flush()?;
+    Ok(file)
+}
+
+/// The command line running a script file through an interpreter. An
+/// absolute interpreter path that doesn't exist here (a shebang written on
+/// another machine) falls back to looking the program up on the PATH
+fn interpreter_argv(interpreter: &str, script: &Path, args: &[String]) -> Result<Vec<String>> {
+    let mut argv: Vec<String> = interpreter.split_whitespace().map(str::to_string).collect();
+    if argv.is_empty() {
+        bail!("Interpreter can't be empty");
+    }
+    
+    let program = Path::new(&argv[0]);
+    if program.is_absolute() && !program.exists() {
+        if let Some(file_name) = program.file_name() {
+            argv[0] = file_name.to_string_lossy().into_owned();
+        }
+    }
+    
+    argv.push(script.to_string_lossy().into_owned());
+    argv.extend(args.iter().cloned());
+    Ok(argv)
+}
+
+/// The command line running a script through the platform shell
+fn shell_argv(name: &str, content: &str, args: &[String]) -> Vec<String> {
+    #[cfg(windows)]
+    let mut argv = {
+        let _ = name;
+        vec!["cmd".to_string(), "/C".to_string(), content.to_string()]
+    };
+    
+    #[cfg(not(windows))]
+    let mut argv = vec!["sh".to_string(), "-c".to_string(), content.to_string(), name.to_string()];
+    
+    argv.extend(args.iter().cloned());
+    argv
+}
+
+/// Wraps a command line so it runs without network access: a fresh network
+/// namespace on Linux, a sandbox profile on macOS. Fails where neither exists
+/// rather than running the script unprotected
+fn isolate_network(argv: Vec<String>) -> Result<Vec<String>> {
+    #[cfg(target_os = "linux")]
+    let prefix = vec!["unshare", "--user", "--map-root-user", "--net", "--"];
+    
+    #[cfg(target_os = "macos")]
+    let prefix = vec!["sandbox-exec", "-p", "(version 1)(allow default)(deny network*)"];
+    
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let prefix: Vec<&str> = {
+        let _ = &argv;
+        bail!("--no-network isn't supported on this platform");
+    };
+    
+    // Make sure the tool is there, and allowed, before relying on it
+    let usable = Command::new(prefix[0])
+        .args(&prefix[1..])
+        .arg("true")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if !usable {
+        bail!("--no-network needs a working '{}', which isn't available here", prefix[0]);
+    }
+    
+    Ok(prefix.into_iter().map(str::to_string).chain(argv).collect())
+}
+
+/// A timeout of `seconds`, which has to be a positive, finite number
+pub fn timeout(seconds: f64) -> Result<Duration> {
+    Duration::try_from_secs_f64(seconds).ok()
+        .filter(|timeout| !timeout.is_zero())
+        .ok_or_else(|| anyhow!("Timeout must be a positive number of seconds, not {}", seconds))
+}
+
+/// Waits for a child, killing it (and its process group) once `timeout` is
+/// up. The flag says whether it had to be killed
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> Result<(ExitStatus, bool)> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, false));
+        }
+        if Instant::now() >= deadline {
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    
+    #[cfg(unix)]
+    {
+        let _ = Command::new("kill").args(["-KILL", "--", &format!("-{}", child.id())]).status();
+    }
+    let _ = child.kill();
+    Ok((child.wait()?, true))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn parses_env_pairs() {
+        assert_eq!(parse_env("A=b=c").unwrap(), ("A".to_string(), "b=c".to_string()));
+        assert_eq!(parse_env("EMPTY=").unwrap(), ("EMPTY".to_string(), String::new()));
+        assert!(parse_env("=x").is_err());
+        assert!(parse_env("novalue").is_err());
+    }
+    
+    #[test]
+    fn passes_arguments_cwd_and_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let options = ExecOptions {
+            cwd: Some(dir.path().to_path_buf()),
+            env: vec![("GREETING".to_string(), "hi".to_string())],
+            ..Default::default()
+        };
+        let script = "printf '%s|%s|%s|%s' \"$0\" \"$1\" \"$GREETING\" \"$(basename \"$PWD\")\" > out";
+        let outcome = run_script("demo", script, &["a b".to_string()], &options).unwrap();
+        assert!(outcome.status.success());
+        
+        let expected = format!("demo|a b|hi|{}", dir.path().file_name().unwrap().to_string_lossy());
+        assert_eq!(std::fs::read_to_string(out).unwrap(), expected);
+    }
+    
+    #[test]
+    fn reads_interpreters_from_shebangs() {
+        assert_eq!(shebang_interpreter("#!/usr/bin/env python3\nprint(1)").as_deref(), Some("python3"));
+        assert_eq!(shebang_interpreter("#!/usr/bin/env -S node --harmony\n").as_deref(), Some("node --harmony"));
+        assert_eq!(shebang_interpreter("#! /bin/bash -e\n").as_deref(), Some("/bin/bash -e"));
+        assert_eq!(shebang_interpreter("echo hi"), None);
+        assert_eq!(shebang_interpreter("#!/usr/bin/env\n"), None);
+    }
+    
+    #[test]
+    fn picks_explicit_then_shebang_then_language() {
+        let script = "#!/usr/bin/env ruby\nputs 1";
+        assert_eq!(pick_interpreter(Some("python3"), script, Some("ruby")).as_deref(), Some("python3"));
+        assert_eq!(pick_interpreter(None, script, Some("python")).as_deref(), Some("ruby"));
+        assert_eq!(pick_interpreter(None, "print(1)", Some("python")).as_deref(), Some("python3"));
+        assert_eq!(pick_interpreter(None, "echo hi", Some("shell")), None);
+    }
+    
+    #[test]
+    fn runs_scripts_through_their_interpreter() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ExecOptions {
+            cwd: Some(dir.path().to_path_buf()),
+            interpreter: Some("/nonexistent/bin/sh -e".to_string()),
+            ..Default::default()
+        };
+        let script = "printf '%s' \"$1\" > out\nfalse\necho unreachable >> out";
+        let outcome = run_script("demo", script, &["a b".to_string()], &options).unwrap();
+        assert!(!outcome.status.success());
+        assert_eq!(std::fs::read_to_string(dir.path().join("out")).unwrap(), "a b");
+    }
+    
+    #[test]
+    fn timeouts_are_positive_and_finite() {
+        assert_eq!(timeout(1.5).unwrap(), Duration::from_millis(1500));
+        for seconds in [0.0, -1.0, f64::INFINITY, f64::NAN, 1e300] {
+            assert!(timeout(seconds).is_err(), "{} was taken", seconds);
+        }
+    }
+    
+    #[test]
+    fn kills_scripts_that_run_too_long() {
+        let options = ExecOptions { timeout: Some(Duration::from_millis(200)), ..Default::default() };
+        let started = Instant::now();
+        let outcome = run_script("slow", "sleep 5", &[], &options).unwrap();
+        assert!(outcome.timed_out);
+        assert!(!outcome.status.success());
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+    
+    #[test]
+    fn captures_output_when_asked() {
+        let options = ExecOptions { capture_output: true, ..Default::default() };
+        let outcome = run_script("loud", "echo out; echo err >&2; exit 4", &[], &options).unwrap();
+        assert_eq!(outcome.stdout, "out\n");
+        assert_eq!(outcome.stderr, "err\n");
+        assert_eq!(outcome.status.code(), Some(4));
+        assert!(!outcome.timed_out);
+    }
+}
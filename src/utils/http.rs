@@ -0,0 +1,173 @@
+use anyhow::{Result, anyhow, Context};
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Fetch the body of a URL. Plain paths and `file://` URLs are read from disk;
+/// anything else is downloaded with `curl`, failing on HTTP errors.
+pub fn fetch(url: &str) -> Result<Vec<u8>> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return fs::read(path).with_context(|| format!("Failed to read {}", path));
+    }
+    if !url.contains("://") {
+        return fs::read(url).with_context(|| format!("Failed to read {}", url));
+    }
+    
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", url])
+        .output()
+        .map_err(|e| anyhow!("Failed to run curl (is it installed?): {}", e))?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Failed to fetch {}: {}", url, stderr.trim()));
+    }
+    
+    Ok(output.stdout)
+}
+
+/// GET a JSON API with the built-in client, so lookups work where curl
+/// isn't installed. Fails on HTTP errors and answers that aren't JSON
+pub fn get_json(url: &str, timeout: Duration) -> Result<serde_json::Value> {
+    let response = send("GET", url, &[("Accept".to_string(), "application/json".to_string())], None, timeout)?;
+    serde_json::from_str(&response.body)
+        .map_err(|e| anyhow!("{} didn't answer with JSON: {}", url, e))
+}
+
+/// What a server answered to `send`
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+    headers: Vec<(String, String)>,
+}
+
+impl Response {
+    /// A header's value, whatever case its name is in
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Sends a request with the built-in client, with an optional body and its
+/// content type. Fails on HTTP errors, with whatever the server said
+pub fn send(method: &str, url: &str, headers: &[(String, String)], body: Option<(&str, &[u8])>, timeout: Duration) -> Result<Response> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .user_agent(concat!("pocket-cli/", env!("CARGO_PKG_VERSION"), " (https://github.com/frgmt0/pocket-cli)"))
+        .build();
+    let mut request = agent.request(method, url);
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    let result = match body {
+        Some((content_type, bytes)) => request.set("Content-Type", content_type).send_bytes(bytes),
+        None => request.call(),
+    };
+    
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            let headers = response.headers_names().into_iter()
+                .filter_map(|name| response.header(&name).map(|value| (name.clone(), value.to_string())))
+                .collect();
+            let body = response.into_string()?;
+            Ok(Response { status, body, headers })
+        },
+        Err(ureq::Error::Status(status, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            let message: String = body.trim().chars().take(200).collect();
+            if message.is_empty() {
+                Err(anyhow!("{} answered {}", url, status))
+            } else {
+                Err(anyhow!("{} answered {}: {}", url, status, message))
+            }
+        },
+        Err(e) => Err(anyhow!("Failed to reach {}: {}", url, e)),
+    }
+}
+
+/// A `multipart/form-data` body of text fields and at most one file,
+/// returned with its content type
+pub fn multipart(fields: &[(&str, &str)], file: Option<(&str, &str, &[u8])>) -> (String, Vec<u8>) {
+    let boundary = format!("pocket-{}", uuid::Uuid::new_v4().simple());
+    let mut body = Vec::new();
+    for (name, value) in fields {
+        body.extend_from_slice(format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", boundary, name, value).as_bytes());
+    }
+    if let Some((name, filename, content)) = file {
+        body.extend_from_slice(format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            boundary, name, filename.replace('"', "")
+        ).as_bytes());
+        body.extend_from_slice(content);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    (format!("multipart/form-data; boundary={}", boundary), body)
+}
+
+/// Most seconds to wait for a JSON API to answer
+const POST_TIMEOUT_SECS: u32 = 120;
+
+/// POST a JSON body to a URL with `curl` and parse the JSON it answers with.
+/// Headers are handed to curl on stdin rather than the command line, so API
+/// keys don't show up in the process list.
+pub fn post_json(url: &str, headers: &[(String, String)], body: &serde_json::Value) -> Result<serde_json::Value> {
+    let mut body_file = tempfile::NamedTempFile::new()?;
+    body_file.write_all(body.to_string().as_bytes())?;
+    body_file.flush()?;
+    
+    let mut config = format!("url = {}\n", curl_quote(url));
+    config.push_str(&format!("header = {}\n", curl_quote("Content-Type: application/json")));
+    for (name, value) in headers {
+        config.push_str(&format!("header = {}\n", curl_quote(&format!("{}: {}", name, value))));
+    }
+    config.push_str(&format!("data-binary = {}\n", curl_quote(&format!("@{}", body_file.path().display()))));
+    config.push_str(&format!("max-time = {}\n", POST_TIMEOUT_SECS));
+    config.push_str("write-out = \"\\n%{http_code}\"\n");
+    
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--location", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run curl (is it installed?): {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Failed to reach {}: {}", url, stderr.trim()));
+    }
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (response, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let status: u16 = status.trim().parse().unwrap_or(0);
+    let json: serde_json::Value = serde_json::from_str(response).unwrap_or(serde_json::Value::Null);
+    
+    if !(200..300).contains(&status) {
+        // APIs put their complaint in `error.message` or `error`
+        let message = json["error"]["message"].as_str()
+            .or_else(|| json["error"].as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| response.chars().take(200).collect());
+        return Err(anyhow!("{} answered {}: {}", url, status, message));
+    }
+    if json.is_null() {
+        return Err(anyhow!("{} didn't answer with JSON", url));
+    }
+    Ok(json)
+}
+
+/// Quotes a value for a curl config file
+fn curl_quote(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+    format!("\"{}\"", escaped)
+}
@@ -0,0 +1,329 @@
+use colored::Colorize;
+use std::path::Path;
+
+/// Canonical language names, with the extension used for editor temp files
+/// and any aliases accepted by `--lang`
+const LANGUAGES: &[(&str, &str, &[&str])] = &[
+    ("rust", "rs", &["rs"]),
+    ("go", "go", &["golang"]),
+    ("javascript", "js", &["js", "jsx", "mjs", "cjs", "node"]),
+    ("typescript", "ts", &["ts", "tsx"]),
+    ("python", "py", &["py", "python3"]),
+    ("java", "java", &[]),
+    ("c", "c", &["h"]),
+    ("cpp", "cpp", &["c++", "cc", "cxx", "hpp", "hh"]),
+    ("csharp", "cs", &["cs", "c#"]),
+    ("ruby", "rb", &["rb"]),
+    ("php", "php", &[]),
+    ("perl", "pl", &["pl"]),
+    ("lua", "lua", &[]),
+    ("shell", "sh", &["sh", "bash", "zsh", "fish", "ksh"]),
+    ("powershell", "ps1", &["ps1", "pwsh"]),
+    ("html", "html", &["htm"]),
+    ("css", "css", &["scss", "sass", "less"]),
+    ("json", "json", &[]),
+    ("yaml", "yml", &["yml"]),
+    ("toml", "toml", &[]),
+    ("markdown", "md", &["md"]),
+    ("sql", "sql", &[]),
+    ("dockerfile", "dockerfile", &["docker"]),
+    ("makefile", "mk", &["make", "mk"]),
+];
+
+/// Map a language name, alias, or file extension to its canonical name
+pub fn normalize_language(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_lowercase();
+    LANGUAGES.iter()
+        .find(|(canonical, _, aliases)| *canonical == name || aliases.contains(&name.as_str()))
+        .map(|(canonical, _, _)| *canonical)
+}
+
+/// File extension to use when editing content of the given language
+pub fn extension_for_language(language: &str) -> &'static str {
+    LANGUAGES.iter()
+        .find(|(canonical, _, _)| *canonical == language)
+        .map(|(_, extension, _)| *extension)
+        .unwrap_or("txt")
+}
+
+/// Detect the language of some content from its file name, shebang, or
+/// recognizable patterns in the content itself
+pub fn detect_language(path: Option<&Path>, content: &str) -> Option<String> {
+    detect_from_path(path)
+        .or_else(|| detect_from_shebang(content))
+        .or_else(|| detect_from_content(content))
+        .map(|language| language.to_string())
+}
+
+fn detect_from_path(path: Option<&Path>) -> Option<&'static str> {
+    let path = path?;
+    let filename = path.file_name()?.to_str()?;
+
+    if filename.starts_with("Dockerfile") {
+        return Some("dockerfile");
+    }
+    if filename.eq_ignore_ascii_case("makefile") || filename == "GNUmakefile" {
+        return Some("makefile");
+    }
+
+    normalize_language(path.extension()?.to_str()?)
+}
+
+fn detect_from_shebang(content: &str) -> Option<&'static str> {
+    let first_line = content.lines().next()?.strip_prefix("#!")?;
+    let mut parts = first_line.split_whitespace();
+
+    // `#!/usr/bin/env python3` names the interpreter after env
+    let mut interpreter = parts.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = parts.find(|p| !p.starts_with('-'))?;
+    }
+
+    // Strip version suffixes like python3.11 or ruby2
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match interpreter {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" | "fish" => Some("shell"),
+        "python" => Some("python"),
+        "node" | "deno" | "bun" => Some("javascript"),
+        "ts-node" => Some("typescript"),
+        "ruby" => Some("ruby"),
+        "perl" => Some("perl"),
+        "php" => Some("php"),
+        "lua" => Some("lua"),
+        "pwsh" => Some("powershell"),
+        _ => None,
+    }
+}
+
+fn detect_from_content(content: &str) -> Option<&'static str> {
+    let trimmed = content.trim_start();
+    let lower = trimmed.to_lowercase();
+
+    if trimmed.starts_with("<?php") {
+        return Some("php");
+    }
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        return Some("html");
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(trimmed).is_ok()
+    {
+        return Some("json");
+    }
+
+    let has_line = |pred: &dyn Fn(&str) -> bool| content.lines().map(str::trim_start).any(pred);
+
+    if has_line(&|l| l.starts_with("package main") || (l.starts_with("func ") && l.contains(") {"))) {
+        return Some("go");
+    }
+    if has_line(&|l| l.starts_with("fn ") || l.starts_with("pub fn ") || l.starts_with("use std::") || l.starts_with("impl ")) {
+        return Some("rust");
+    }
+    if has_line(&|l| l.starts_with("#include")) {
+        return Some(if content.contains("std::") || content.contains("class ") { "cpp" } else { "c" });
+    }
+    if has_line(&|l| (l.starts_with("def ") || l.starts_with("class ")) && l.trim_end().ends_with(':'))
+        || has_line(&|l| l.starts_with("from ") && l.contains(" import "))
+    {
+        return Some("python");
+    }
+    if has_line(&|l| l.starts_with("interface ") || l.contains(": string") || l.contains(": number")) {
+        return Some("typescript");
+    }
+    if has_line(&|l| l.starts_with("function ") || (l.starts_with("const ") && l.contains("=>")) || l.contains("console.log(")) {
+        return Some("javascript");
+    }
+    if has_line(&|l| l.starts_with("public class ") || l.starts_with("import java.")) {
+        return Some("java");
+    }
+    if ["select ", "insert into ", "create table ", "update ", "delete from "].iter().any(|kw| lower.starts_with(kw)) {
+        return Some("sql");
+    }
+    if trimmed.starts_with("# ") && content.contains("\n\n") {
+        return Some("markdown");
+    }
+
+    None
+}
+
+/// Keywords and line comment marker for the languages we highlight
+fn syntax_for(language: &str) -> Option<(&'static [&'static str], &'static str)> {
+    let syntax: (&[&str], &str) = match language {
+        "rust" => (&["fn", "let", "mut", "pub", "use", "mod", "struct", "enum", "impl", "trait", "match", "if", "else", "for", "while", "loop", "return", "self", "Self", "const", "static", "where", "async", "await", "move", "ref", "in", "as", "crate", "true", "false"], "//"),
+        "go" => (&["func", "package", "import", "var", "const", "type", "struct", "interface", "if", "else", "for", "range", "return", "go", "defer", "chan", "map", "switch", "case", "default", "nil", "true", "false"], "//"),
+        "javascript" | "typescript" => (&["function", "const", "let", "var", "return", "if", "else", "for", "while", "class", "new", "this", "import", "export", "from", "async", "await", "try", "catch", "throw", "null", "undefined", "true", "false", "interface", "type"], "//"),
+        "java" | "csharp" => (&["public", "private", "protected", "class", "static", "void", "new", "return", "if", "else", "for", "while", "import", "package", "using", "namespace", "try", "catch", "throw", "null", "true", "false"], "//"),
+        "c" | "cpp" => (&["int", "char", "void", "return", "if", "else", "for", "while", "struct", "typedef", "const", "static", "class", "public", "private", "namespace", "template", "auto", "include", "define"], "//"),
+        "python" => (&["def", "class", "return", "if", "elif", "else", "for", "while", "in", "import", "from", "as", "with", "try", "except", "finally", "raise", "lambda", "yield", "None", "True", "False", "and", "or", "not", "pass"], "#"),
+        "ruby" => (&["def", "end", "class", "module", "if", "elsif", "else", "unless", "do", "return", "require", "yield", "nil", "true", "false"], "#"),
+        "shell" => (&["if", "then", "else", "elif", "fi", "for", "while", "do", "done", "case", "esac", "function", "return", "export", "local", "echo", "in"], "#"),
+        "perl" | "php" => (&["function", "sub", "my", "return", "if", "else", "elsif", "foreach", "for", "while", "use", "echo", "class", "new"], "#"),
+        "sql" => (&["SELECT", "FROM", "WHERE", "INSERT", "INTO", "VALUES", "UPDATE", "SET", "DELETE", "CREATE", "TABLE", "JOIN", "ON", "AND", "OR", "NOT", "NULL", "ORDER", "BY", "GROUP", "LIMIT", "AS"], "--"),
+        "yaml" | "toml" | "dockerfile" | "makefile" | "powershell" => (&[], "#"),
+        "lua" => (&["function", "local", "end", "if", "then", "else", "for", "while", "do", "return", "nil", "true", "false"], "--"),
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+/// Kinds of token the highlighter distinguishes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Plain,
+}
+
+/// Split content into highlightable tokens. Content in languages we don't
+/// know about comes back as a single plain token.
+pub fn tokenize<'a>(content: &'a str, language: &str) -> Vec<(TokenKind, &'a str)> {
+    let Some((keywords, comment)) = syntax_for(language) else {
+        return vec![(TokenKind::Plain, content)];
+    };
+    let case_insensitive = language == "sql";
+
+    let mut tokens = Vec::new();
+    for (index, line) in content.split('\n').enumerate() {
+        if index > 0 {
+            tokens.push((TokenKind::Plain, "\n"));
+        }
+        tokenize_line(line, keywords, comment, case_insensitive, &mut tokens);
+    }
+    tokens
+}
+
+fn tokenize_line<'a>(line: &'a str, keywords: &[&str], comment: &str, case_insensitive: bool, tokens: &mut Vec<(TokenKind, &'a str)>) {
+    let mut rest = line;
+
+    while !rest.is_empty() {
+        if rest.starts_with(comment) {
+            tokens.push((TokenKind::Comment, rest));
+            return;
+        }
+
+        let first = rest.chars().next().unwrap_or_default();
+        let (kind, end) = if first == '"' || first == '\'' || first == '`' {
+            // Find the closing quote, skipping escaped characters
+            let mut end = rest.len();
+            let mut escaped = false;
+            for (i, c) in rest.char_indices().skip(1) {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == first {
+                    end = i + c.len_utf8();
+                    break;
+                }
+            }
+            (TokenKind::String, end)
+        } else if first.is_alphanumeric() || first == '_' {
+            let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            let word = &rest[..end];
+            let is_keyword = keywords.iter()
+                .any(|kw| if case_insensitive { kw.eq_ignore_ascii_case(word) } else { *kw == word });
+            if is_keyword {
+                (TokenKind::Keyword, end)
+            } else if word.chars().all(|c| c.is_ascii_digit()) {
+                (TokenKind::Number, end)
+            } else {
+                (TokenKind::Plain, end)
+            }
+        } else {
+            (TokenKind::Plain, first.len_utf8())
+        };
+
+        tokens.push((kind, &rest[..end]));
+        rest = &rest[end..];
+    }
+}
+
+/// Apply simple terminal syntax highlighting (keywords, strings, comments)
+pub fn highlight(content: &str, language: &str) -> String {
+    tokenize(content, language).into_iter()
+        .map(|(kind, text)| match kind {
+            TokenKind::Keyword => text.blue().bold().to_string(),
+            TokenKind::String => text.green().to_string(),
+            TokenKind::Number => text.yellow().to_string(),
+            TokenKind::Comment => text.dimmed().to_string(),
+            TokenKind::Plain => text.to_string(),
+        })
+        .collect()
+}
+
+/// Render highlighted, HTML-escaped content using `<span class="...">` tags
+pub fn highlight_html(content: &str, language: &str) -> String {
+    tokenize(content, language).into_iter()
+        .map(|(kind, text)| {
+            let class = match kind {
+                TokenKind::Keyword => "kw",
+                TokenKind::String => "str",
+                TokenKind::Number => "num",
+                TokenKind::Comment => "com",
+                TokenKind::Plain => return escape_html(text),
+            };
+            format!("<span class=\"{}\">{}</span>", class, escape_html(text))
+        })
+        .collect()
+}
+
+/// Escape text for inclusion in HTML
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_detect_from_extension() {
+        let path = PathBuf::from("src/main.rs");
+        assert_eq!(detect_language(Some(&path), ""), Some("rust".to_string()));
+
+        let path = PathBuf::from("Dockerfile.dev");
+        assert_eq!(detect_language(Some(&path), ""), Some("dockerfile".to_string()));
+    }
+
+    #[test]
+    fn test_detect_from_shebang() {
+        assert_eq!(detect_language(None, "#!/usr/bin/env python3\nprint(1)"), Some("python".to_string()));
+        assert_eq!(detect_language(None, "#!/bin/bash\necho hi"), Some("shell".to_string()));
+        assert_eq!(detect_language(None, "#!/usr/bin/env -S node --harmony\n"), Some("javascript".to_string()));
+    }
+
+    #[test]
+    fn test_detect_from_content() {
+        assert_eq!(detect_language(None, "fn main() {\n    println!(\"hi\");\n}"), Some("rust".to_string()));
+        assert_eq!(detect_language(None, "def greet(name):\n    return name"), Some("python".to_string()));
+        assert_eq!(detect_language(None, "{\"key\": [1, 2]}"), Some("json".to_string()));
+        assert_eq!(detect_language(None, "just some notes"), None);
+    }
+
+    #[test]
+    fn test_normalize_language() {
+        assert_eq!(normalize_language("RS"), Some("rust"));
+        assert_eq!(normalize_language("bash"), Some("shell"));
+        assert_eq!(normalize_language("klingon"), None);
+    }
+
+    #[test]
+    fn test_highlight_unknown_language_is_unchanged() {
+        assert_eq!(highlight("hello world", "markdown"), "hello world");
+    }
+
+    #[test]
+    fn test_highlight_html_escapes_and_marks_tokens() {
+        assert_eq!(
+            highlight_html("let x = \"<a>\"; // done", "rust"),
+            "<span class=\"kw\">let</span> x = <span class=\"str\">&quot;&lt;a&gt;&quot;</span>; <span class=\"com\">// done</span>"
+        );
+    }
+}
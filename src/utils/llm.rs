@@ -0,0 +1,158 @@
+//! Talking to language models: a local Ollama server, OpenAI-compatible
+//! APIs, or Anthropic, as set up in the `[llm]` section of `config.toml`
+
+use crate::models::{LlmBackend, LlmConfig};
+use crate::utils::http;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use std::env;
+
+/// Most tokens to ask for in an answer
+const MAX_TOKENS: u32 = 300;
+
+/// The HTTP APIs a client can speak
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Api {
+    Ollama,
+    OpenAi,
+    Anthropic,
+}
+
+/// A language model reachable over HTTP
+#[derive(Debug, Clone)]
+pub struct LlmClient {
+    api: Api,
+    model: String,
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl LlmClient {
+    /// Sets up the configured backend, or None when it's the heuristic one.
+    /// Fails if the backend needs an API key and its variable isn't set
+    pub fn from_config(config: &LlmConfig) -> Result<Option<Self>> {
+        let (api, model, endpoint, key_env) = match config.backend {
+            LlmBackend::Heuristic => return Ok(None),
+            LlmBackend::Ollama => (Api::Ollama, "llama3.2", "http://localhost:11434", None),
+            LlmBackend::OpenAi => (Api::OpenAi, "gpt-4o-mini", "https://api.openai.com/v1", Some("OPENAI_API_KEY")),
+            LlmBackend::Anthropic => (Api::Anthropic, "claude-3-5-haiku-latest", "https://api.anthropic.com", Some("ANTHROPIC_API_KEY")),
+        };
+        
+        let key_env = config.api_key_env.as_deref().or(key_env);
+        let api_key = key_env.and_then(|name| env::var(name).ok()).filter(|key| !key.is_empty());
+        // OpenAI-compatible servers running locally often don't want a key
+        let custom_endpoint = config.endpoint.is_some();
+        if api_key.is_none() && (api == Api::Anthropic || (api == Api::OpenAi && !custom_endpoint)) {
+            return Err(anyhow!("This backend needs an API key in ${}", key_env.unwrap_or_default()));
+        }
+        
+        Ok(Some(Self {
+            api,
+            model: config.model.clone().unwrap_or_else(|| model.to_string()),
+            endpoint: config.endpoint.clone().unwrap_or_else(|| endpoint.to_string()).trim_end_matches('/').to_string(),
+            api_key,
+        }))
+    }
+    
+    /// What the client talks to, like `ollama/llama3.2`
+    pub fn name(&self) -> String {
+        format!("{:?}/{}", self.api, self.model).to_lowercase()
+    }
+    
+    /// Sends a prompt and returns the model's answer
+    pub fn complete(&self, prompt: &str) -> Result<String> {
+        let (url, headers, body) = self.request(prompt);
+        let response = http::post_json(&url, &headers, &body)?;
+        answer_from(self.api, &response)
+    }
+    
+    /// The URL, headers and body to send a prompt with
+    fn request(&self, prompt: &str) -> (String, Vec<(String, String)>, Value) {
+        let mut headers = Vec::new();
+        match self.api {
+            Api::Ollama => {
+                let body = json!({ "model": self.model, "prompt": prompt, "stream": false });
+                (format!("{}/api/generate", self.endpoint), headers, body)
+            },
+            Api::OpenAi => {
+                if let Some(key) = &self.api_key {
+                    headers.push(("Authorization".to_string(), format!("Bearer {}", key)));
+                }
+                let body = json!({
+                    "model": self.model,
+                    "max_tokens": MAX_TOKENS,
+                    "messages": [{ "role": "user", "content": prompt }],
+                });
+                (format!("{}/chat/completions", self.endpoint), headers, body)
+            },
+            Api::Anthropic => {
+                headers.push(("x-api-key".to_string(), self.api_key.clone().unwrap_or_default()));
+                headers.push(("anthropic-version".to_string(), "2023-06-01".to_string()));
+                let body = json!({
+                    "model": self.model,
+                    "max_tokens": MAX_TOKENS,
+                    "messages": [{ "role": "user", "content": prompt }],
+                });
+                (format!("{}/v1/messages", self.endpoint), headers, body)
+            },
+        }
+    }
+}
+
+/// Pulls the answer's text out of a backend's response
+fn answer_from(api: Api, response: &Value) -> Result<String> {
+    let text = match api {
+        Api::Ollama => response["response"].as_str(),
+        Api::OpenAi => response["choices"][0]["message"]["content"].as_str(),
+        Api::Anthropic => response["content"][0]["text"].as_str(),
+    };
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .ok_or_else(|| anyhow!("The model's response had no answer in it"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn config(backend: LlmBackend) -> LlmConfig {
+        LlmConfig { backend, model: None, endpoint: None, api_key_env: Some("POCKET_TEST_NO_SUCH_KEY".to_string()) }
+    }
+    
+    #[test]
+    fn builds_requests_for_each_backend() {
+        assert!(LlmClient::from_config(&config(LlmBackend::Heuristic)).unwrap().is_none());
+        
+        let ollama = LlmClient::from_config(&config(LlmBackend::Ollama)).unwrap().unwrap();
+        let (url, headers, body) = ollama.request("hi");
+        assert_eq!(url, "http://localhost:11434/api/generate");
+        assert!(headers.is_empty());
+        assert_eq!(body["model"], "llama3.2");
+        assert_eq!(body["stream"], false);
+        
+        // Anthropic and hosted OpenAI need a key; a local OpenAI-compatible server doesn't
+        assert!(LlmClient::from_config(&config(LlmBackend::Anthropic)).is_err());
+        assert!(LlmClient::from_config(&config(LlmBackend::OpenAi)).is_err());
+        let local = LlmConfig { endpoint: Some("http://localhost:8080/v1/".to_string()), model: Some("qwen".to_string()), ..config(LlmBackend::OpenAi) };
+        let local = LlmClient::from_config(&local).unwrap().unwrap();
+        let (url, headers, body) = local.request("hi");
+        assert_eq!(url, "http://localhost:8080/v1/chat/completions");
+        assert!(headers.is_empty());
+        assert_eq!(body["messages"][0]["content"], "hi");
+        assert_eq!(local.name(), "openai/qwen");
+    }
+    
+    #[test]
+    fn reads_answers_from_each_backend() {
+        let ollama = json!({ "response": " A summary. " });
+        assert_eq!(answer_from(Api::Ollama, &ollama).unwrap(), "A summary.");
+        
+        let openai = json!({ "choices": [{ "message": { "role": "assistant", "content": "From OpenAI" } }] });
+        assert_eq!(answer_from(Api::OpenAi, &openai).unwrap(), "From OpenAI");
+        
+        let anthropic = json!({ "content": [{ "type": "text", "text": "From Anthropic" }] });
+        assert_eq!(answer_from(Api::Anthropic, &anthropic).unwrap(), "From Anthropic");
+        
+        assert!(answer_from(Api::Anthropic, &openai).is_err());
+    }
+}
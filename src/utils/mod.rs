@@ -1,6 +1,6 @@
 use anyhow::{Result, anyhow, Context};
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
-use owo_colors::OwoColorize;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
+use colored::Colorize;
 use std::fs;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
@@ -9,7 +9,6 @@ use std::env;
 use std::time::SystemTime;
 
 use crate::models::ContentType;
-use tempfile::NamedTempFile;
 
 // Add clipboard module
 pub mod clipboard;
@@ -17,11 +16,68 @@ pub mod clipboard;
 // Add summarization module
 pub mod summarization;
 
+// Language detection and highlighting
+pub mod language;
+
+// Static site generation for publishing a snippet library
+pub mod site;
+
+// Fetching files over HTTP
+pub mod http;
+
+// Running stored scripts
+pub mod exec;
+
+// Talking to language models
+pub mod llm;
+
+// Suggesting tags and titles
+pub mod tagging;
+
+// Sharing through paste services
+pub mod share;
+
+// Crash-safe file writes
+pub mod atomic;
+
+// Sizing the thread pool for per-file work
+pub mod parallel;
+
+// Progress bars for long operations
+pub mod progress;
+
+// Stopping long operations on Ctrl-C
+pub mod cancel;
+
+// The output format picked with --output
+pub mod output;
+
+// Colored diffs of edits
+pub mod diff;
+
+// Paging long content, and showing only some of its lines
+pub mod pager;
+
+// "Did you mean" suggestions for unknown names
+pub mod suggest;
+
+// Color, and the styles output is colored with
+pub mod style;
+
+// Reading times like 7d or 2024-05-01
+pub mod time;
+
+// QR codes drawn in the terminal
+pub mod qr;
+
 // Re-export clipboard functions for convenience
-pub use clipboard::read_clipboard;
+pub use clipboard::{read_clipboard, read_selection, write_clipboard};
 
 // Re-export summarization functions for convenience
-pub use summarization::{summarize_text, SummaryMetadata};
+pub use summarization::{summarize_text, summarize_with_config, Summarizer, SummaryMetadata};
+
+// Re-export language functions for convenience
+pub use language::{detect_language, normalize_language};
 
 /// Read content from a file (unused)
 pub fn _read_file_content(path: &Path) -> Result<String> {
@@ -37,11 +93,21 @@ pub fn _read_stdin_content() -> Result<String> {
 
 /// Open the system editor and return the content
 pub fn open_editor(initial_content: Option<&str>) -> Result<String> {
+    open_editor_for_language(None, initial_content)
+}
+
+/// Open the system editor on a temporary file whose extension matches the
+/// language, so the editor picks the right syntax mode
+pub fn open_editor_for_language(language: Option<&str>, initial_content: Option<&str>) -> Result<String> {
     // Find the user's preferred editor
     let editor = get_editor()?;
     
     // Create a temporary file
-    let mut temp_file = NamedTempFile::new()?;
+    let extension = language.map(language::extension_for_language).unwrap_or("txt");
+    let mut temp_file = tempfile::Builder::new()
+        .prefix("pocket_")
+        .suffix(&format!(".{}", extension))
+        .tempfile()?;
     
     // Write initial content if provided
     if let Some(content) = initial_content {
@@ -53,7 +119,7 @@ pub fn open_editor(initial_content: Option<&str>) -> Result<String> {
     let temp_path = temp_file.path().to_path_buf();
     
     // Open the editor
-    let status = Command::new(&editor)
+    let status = editor_command(&editor)?
         .arg(&temp_path)
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
@@ -137,7 +203,7 @@ pub fn _open_editor_with_type(content_type: ContentType, initial_content: Option
     }
     
     // Open the editor
-    let status = Command::new(&editor)
+    let status = editor_command(&editor)?
         .arg(&temp_path)
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
@@ -201,6 +267,38 @@ fn get_editor() -> Result<String> {
     Ok(editor)
 }
 
+/// Guess the MIME type of binary content from its magic bytes, falling back
+/// to the file extension
+pub fn detect_mime_type(path: &Path, content: &[u8]) -> String {
+    let magic: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x7fELF", "application/x-elf"),
+        (b"BM", "image/bmp"),
+    ];
+    
+    if let Some((_, mime)) = magic.iter().find(|(prefix, _)| content.starts_with(prefix)) {
+        return mime.to_string();
+    }
+    
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "wasm" => "application/wasm",
+        "tar" => "application/x-tar",
+        _ => "application/octet-stream",
+    }.to_string()
+}
+
 /// Detect content type from extension or content
 pub fn detect_content_type(path: Option<&Path>, content: Option<&str>) -> ContentType {
     // Check file extension first if path is provided
@@ -299,8 +397,8 @@ where
     Ok(input.interact()?)
 }
 
-/// Prompt the user to select from a list of options (unused)
-pub fn _select<T>(message: &str, options: &[T]) -> Result<usize>
+/// Prompt the user to select from a list of options
+pub fn select<T>(message: &str, options: &[T]) -> Result<usize>
 where
     T: std::fmt::Display,
 {
@@ -311,6 +409,19 @@ where
         .interact()?)
 }
 
+/// Prompt the user to pick any number of options, with `checked` picked to
+/// begin with. Returns the indexes picked
+pub fn multi_select<T>(message: &str, options: &[T], checked: &[bool]) -> Result<Vec<usize>>
+where
+    T: std::fmt::Display,
+{
+    Ok(MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(message)
+        .items(options)
+        .defaults(checked)
+        .interact()?)
+}
+
 /// Format content with tag (unused)
 pub fn _format_with_tag(tag: &str, content: &str) -> String {
     format!("--- {} ---\n{}\n--- end {} ---\n", tag, content, tag)
@@ -342,12 +453,26 @@ pub fn _get_title_from_content(content: &str) -> String {
 
 /// Get the path with ~ expanded to the home directory
 pub fn expand_path(path: &str) -> Result<PathBuf> {
-    if path.starts_with("~/") {
-        let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
-        Ok(home.join(&path[2..]))
-    } else {
-        Ok(PathBuf::from(path))
+    if !path.starts_with('~') {
+        return Ok(PathBuf::from(path));
     }
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("Could not determine home directory"))?;
+    Ok(crate::storage::home::expand(path, &home))
+}
+
+/// A command running `editor`, which can carry arguments of its own like
+/// `code --wait`
+pub fn editor_command(editor: &str) -> Result<Command> {
+    let words = shell_words::split(editor).with_context(|| format!("Can't parse the editor command: {}", editor))?;
+    let (program, args) = words.split_first().ok_or_else(|| anyhow!("No editor set"))?;
+    let mut command = Command::new(program);
+    command.args(args);
+    Ok(command)
+}
+
+/// The editor to fall back on when none is set: one every system has
+pub fn default_editor() -> &'static str {
+    if cfg!(windows) { "notepad" } else { "vi" }
 }
 
 /// Find the cursor position in a file if marked with a special comment
@@ -0,0 +1,28 @@
+//! The output format chosen with `--output`, for the parts of pocket that
+//! print outside any one command, like errors
+
+use clap::ValueEnum;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by `--output json`
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// What pocket prints in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// For people
+    #[default]
+    Text,
+    /// For scripts
+    Json,
+}
+
+/// Uses `format` for the rest of the process
+pub fn set_format(format: OutputFormat) {
+    JSON.store(format == OutputFormat::Json, Ordering::Relaxed);
+}
+
+/// Whether `--output json` was given
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
@@ -0,0 +1,109 @@
+//! Showing long content: through a pager, or just some of its lines
+
+use anyhow::{Result, anyhow};
+use dialoguer::console::Term;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Pager used when neither config.toml nor `$PAGER` names one. `-F` quits
+/// right away when everything fits on one screen, `-R` keeps colors, and
+/// `-X` leaves the output on screen afterwards
+const DEFAULT_PAGER: &str = "less -FRX";
+
+/// Lines of some content, counted from 1 with `end` included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl LineRange {
+    /// Reads `10:40`, `10:` (to the end) or `:40` (from the start)
+    pub fn parse(raw: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid line range '{}', expected something like 10:40, 10: or :40", raw);
+        let (start, end) = raw.split_once(':').ok_or_else(invalid)?;
+        let start = match start.trim() {
+            "" => 1,
+            start => start.parse().map_err(|_| invalid())?,
+        };
+        let end = match end.trim() {
+            "" => None,
+            end => Some(end.parse().map_err(|_| invalid())?),
+        };
+        if start == 0 || end.is_some_and(|end| end < start) {
+            return Err(invalid());
+        }
+        Ok(Self { start, end })
+    }
+    
+    /// The first `count` lines
+    pub fn head(count: usize) -> Self {
+        Self { start: 1, end: Some(count) }
+    }
+    
+    /// The lines of `content` in the range, and how many lines it has in all
+    pub fn apply(&self, content: &str) -> (String, usize) {
+        let total = content.lines().count();
+        let take = self.end.map_or(usize::MAX, |end| end + 1 - self.start);
+        let lines: Vec<&str> = content.lines().skip(self.start - 1).take(take).collect();
+        (lines.join("\n"), total)
+    }
+}
+
+/// Prints `text`, through a pager when stdout is a terminal too short to
+/// hold it. `command` is the pager set in config.toml, which wins over
+/// `$PAGER`
+pub fn page(text: &str, command: Option<&str>) -> Result<()> {
+    let stdout = io::stdout();
+    let (rows, _) = Term::stdout().size();
+    if !stdout.is_terminal() || text.lines().count() < usize::from(rows) {
+        print!("{}", text);
+        return Ok(());
+    }
+    
+    let command = command.map(str::to_string)
+        .or_else(|| std::env::var("PAGER").ok())
+        .filter(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let words = shell_words::split(&command)?;
+    let Some((program, args)) = words.split_first() else {
+        print!("{}", text);
+        return Ok(());
+    };
+    let mut child = match Command::new(program).args(args).stdin(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            // No pager is no reason not to show the content
+            log::debug!("Couldn't start pager '{}': {}", command, e);
+            print!("{}", text);
+            return Ok(());
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe; that's not an error
+        if let Err(e) = stdin.write_all(text.as_bytes()) {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                return Err(e.into());
+            }
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_applies_line_ranges() {
+        let content = "one\ntwo\nthree\nfour\n";
+        assert_eq!(LineRange::parse("2:3").unwrap().apply(content), ("two\nthree".to_string(), 4));
+        assert_eq!(LineRange::parse("3:").unwrap().apply(content).0, "three\nfour");
+        assert_eq!(LineRange::parse(":1").unwrap(), LineRange::head(1));
+        assert_eq!(LineRange::parse("3:10").unwrap().apply(content).0, "three\nfour");
+        assert!(LineRange::parse("0:2").is_err());
+        assert!(LineRange::parse("5:2").is_err());
+        assert!(LineRange::parse("10").is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! How many threads per-file work like searching and hashing gets
+//!
+//! The work runs on rayon's global pool, sized the first time it's needed:
+//! by `--jobs` when given, else `search.jobs` from `config.toml`, else one
+//! thread per CPU.
+
+use once_cell::sync::OnceCell;
+use std::sync::Once;
+
+/// Threads asked for with `--jobs`
+static JOBS: OnceCell<usize> = OnceCell::new();
+
+static POOL: Once = Once::new();
+
+/// Uses `jobs` threads, as `--jobs` asks. Only the first call counts, and
+/// only before the pool is up
+pub fn set_jobs(jobs: Option<usize>) {
+    if let Some(jobs) = jobs {
+        let _ = JOBS.set(jobs);
+    }
+}
+
+/// Sizes the pool if it isn't up yet. `configured` gives the configured
+/// number of threads, and is only asked when `--jobs` wasn't given
+pub fn init(configured: impl FnOnce() -> Option<usize>) {
+    POOL.call_once(|| {
+        let jobs = JOBS.get().copied().or_else(configured).filter(|&jobs| jobs > 0);
+        if let Some(jobs) = jobs {
+            // Fails only when rayon's pool already started, which leaves it
+            // at its default size
+            if let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(jobs).build_global() {
+                log::debug!("Failed to size the thread pool: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,75 @@
+//! Progress bars for long operations, drawn on stderr
+//!
+//! Bars only show up on a terminal, and never with `--quiet`, so piped
+//! output and scripts see nothing extra.
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by `--quiet`
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Hides progress for the rest of the process, as `--quiet` asks
+pub fn set_quiet(quiet: bool) {
+    if quiet {
+        QUIET.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Whether `--quiet` was given
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Progress through a long operation. Does nothing when progress is hidden,
+/// and clears itself when dropped
+pub struct Progress {
+    bar: Option<ProgressBar>,
+}
+
+impl Progress {
+    /// Progress through `len` steps
+    pub fn bar(len: u64, message: &str) -> Self {
+        Self::styled(ProgressBar::new(len), "{spinner} {msg} [{bar:30}] {pos}/{len}", message)
+    }
+    
+    /// Progress through an unknown number of steps
+    pub fn spinner(message: &str) -> Self {
+        Self::styled(ProgressBar::new_spinner(), "{spinner} {msg} ({pos})", message)
+    }
+    
+    fn styled(bar: ProgressBar, template: &str, message: &str) -> Self {
+        if is_quiet() || !std::io::stderr().is_terminal() {
+            return Self { bar: None };
+        }
+        if let Ok(style) = ProgressStyle::with_template(template) {
+            bar.set_style(style.progress_chars("=> "));
+        }
+        bar.set_message(message.to_string());
+        bar.enable_steady_tick(std::time::Duration::from_millis(120));
+        Self { bar: Some(bar) }
+    }
+    
+    /// Moves on by `steps`
+    pub fn inc(&self, steps: u64) {
+        if let Some(bar) = &self.bar {
+            bar.inc(steps);
+        }
+    }
+    
+    /// Says what's happening now
+    pub fn set_message(&self, message: &str) {
+        if let Some(bar) = &self.bar {
+            bar.set_message(message.to_string());
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
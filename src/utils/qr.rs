@@ -0,0 +1,40 @@
+//! QR codes drawn in the terminal, for getting text onto a phone
+//!
+//! Each character cell holds two rows of modules. Light modules are drawn
+//! in the terminal's text color, so codes come out right on a dark
+//! background, the way `qrencode -t utf8` draws them.
+
+use anyhow::{Result, anyhow};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::types::QrError;
+use qrcode::{EcLevel, QrCode};
+
+/// `data` as a QR code, ready to print. Low error correction keeps the code
+/// small enough for a terminal, and a screen doesn't get smudged
+pub fn render(data: &[u8]) -> Result<String> {
+    let code = QrCode::with_error_correction_level(data, EcLevel::L).map_err(|e| match e {
+        QrError::DataTooLong => anyhow!("{} bytes is too long for a QR code; they hold at most 2953", data.len()),
+        e => anyhow!("Failed to make a QR code: {}", e),
+    })?;
+    Ok(code.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .quiet_zone(true)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_short_text_and_refuses_long_text() {
+        let code = render(b"WIFI:S:home;T:WPA;P:hunter2;;").unwrap();
+        let rows: Vec<&str> = code.lines().collect();
+        assert!(rows.len() > 10);
+        assert!(rows.iter().all(|row| row.chars().count() == rows[0].chars().count()));
+
+        let error = render(&[b'x'; 3000]).unwrap_err().to_string();
+        assert!(error.contains("too long"), "{}", error);
+    }
+}
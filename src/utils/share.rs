@@ -0,0 +1,306 @@
+//! Sharing snippets through paste services: GitHub gists, paste.rs, 0x0.st,
+//! or a service of your own set up under `[share.providers.NAME]` in
+//! `config.toml`
+//!
+//! Content can be encrypted before it leaves the machine. The key goes in
+//! the link's `#fragment`, which browsers and HTTP clients never send to the
+//! server, so only people given the whole link can read it.
+
+use crate::models::{CustomShareProvider, ShareConfig};
+use crate::utils::http;
+use anyhow::{Result, anyhow};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::env;
+use std::time::Duration;
+
+/// Most time a paste service gets to answer
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// First line of an encrypted paste, so it can be recognized
+const ENCRYPTED_HEADER: &str = "pocket-encrypted:v1";
+
+/// Bytes of nonce in front of the ciphertext
+const NONCE_LEN: usize = 12;
+
+/// A paste service
+#[derive(Debug, Clone, PartialEq)]
+pub enum Provider {
+    Gist,
+    PasteRs,
+    ZeroX0,
+    Custom(String, CustomShareProvider),
+}
+
+/// Something to share
+pub struct Upload<'a> {
+    /// File name to give it, for services that keep one
+    pub filename: &'a str,
+    pub content: &'a [u8],
+    pub description: &'a str,
+    /// Hours until the service deletes it
+    pub expires_hours: Option<u64>,
+}
+
+/// A shared copy of an entry, as kept in the entry's `shares` metadata
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Share {
+    pub provider: String,
+    pub url: String,
+    /// What the service needs to delete it: a token or an ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_token: Option<String>,
+    pub shared_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+impl Provider {
+    /// The provider called `name`: a built-in one or a custom one from the config
+    pub fn from_name(name: &str, config: &ShareConfig) -> Result<Self> {
+        if let Some(custom) = config.providers.get(name) {
+            return Ok(Self::Custom(name.to_string(), custom.clone()));
+        }
+        match name.to_lowercase().as_str() {
+            "gist" | "github" => Ok(Self::Gist),
+            "paste.rs" | "pasters" => Ok(Self::PasteRs),
+            "0x0" | "0x0.st" => Ok(Self::ZeroX0),
+            _ => Err(anyhow!("Unknown share provider: {} (expected gist, paste.rs, 0x0, or one under [share.providers])", name)),
+        }
+    }
+    
+    /// The provider to use when none is named
+    pub fn from_config(config: &ShareConfig) -> Result<Self> {
+        Self::from_name(config.default_provider.as_deref().unwrap_or("paste.rs"), config)
+    }
+    
+    /// What it's called in `--to` and in an entry's shares
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Gist => "gist",
+            Self::PasteRs => "paste.rs",
+            Self::ZeroX0 => "0x0",
+            Self::Custom(name, _) => name,
+        }
+    }
+    
+    /// Whether a share can be deleted again with `pocket share --revoke`
+    pub fn can_revoke(&self) -> bool {
+        match self {
+            Self::Gist | Self::PasteRs | Self::ZeroX0 => true,
+            Self::Custom(_, custom) => custom.delete,
+        }
+    }
+    
+    /// Uploads content, returning the share to remember it by
+    pub fn upload(&self, upload: &Upload) -> Result<Share> {
+        if upload.expires_hours.is_some() && !self.can_expire() {
+            return Err(anyhow!("{} can't expire pastes", self.name()));
+        }
+        
+        let (url, delete_token) = match self {
+            Self::Gist => {
+                let content = std::str::from_utf8(upload.content)
+                    .map_err(|_| anyhow!("Gists can only hold text"))?;
+                let body = json!({
+                    "description": upload.description,
+                    "public": false,
+                    "files": { upload.filename: { "content": content } },
+                });
+                let response = http::send("POST", "https://api.github.com/gists", &github_headers()?, Some(("application/json", body.to_string().as_bytes())), TIMEOUT)?;
+                let answer: serde_json::Value = serde_json::from_str(&response.body)?;
+                let url = answer["html_url"].as_str().ok_or_else(|| anyhow!("GitHub's answer had no link in it"))?;
+                (url.to_string(), answer["id"].as_str().map(str::to_string))
+            },
+            Self::PasteRs => {
+                let response = http::send("POST", "https://paste.rs/", &[], Some(("text/plain", upload.content)), TIMEOUT)?;
+                // 206 means the paste was cut short
+                if response.status == 206 {
+                    return Err(anyhow!("paste.rs only took part of it; it's too big"));
+                }
+                (response.body.trim().to_string(), None)
+            },
+            Self::ZeroX0 => {
+                let hours = upload.expires_hours.map(|hours| hours.to_string());
+                let fields: Vec<(&str, &str)> = hours.iter().map(|hours| ("expires", hours.as_str())).collect();
+                let (content_type, body) = http::multipart(&fields, Some(("file", upload.filename, upload.content)));
+                let response = http::send("POST", "https://0x0.st", &[], Some((content_type.as_str(), body.as_slice())), TIMEOUT)?;
+                (response.body.trim().to_string(), response.header("X-Token").map(str::to_string))
+            },
+            Self::Custom(name, custom) => {
+                let headers = custom_headers(name, custom)?;
+                let response = match &custom.field {
+                    Some(field) => {
+                        let hours = upload.expires_hours.map(|hours| hours.to_string());
+                        let fields: Vec<(&str, &str)> = custom.expires_field.as_deref().zip(hours.as_deref()).into_iter().collect();
+                        let (content_type, body) = http::multipart(&fields, Some((field, upload.filename, upload.content)));
+                        http::send("POST", &custom.url, &headers, Some((content_type.as_str(), body.as_slice())), TIMEOUT)?
+                    },
+                    None => http::send("POST", &custom.url, &headers, Some(("application/octet-stream", upload.content)), TIMEOUT)?,
+                };
+                (link_from(custom, &response.body)?, None)
+            },
+        };
+        
+        if !url.starts_with("http") {
+            return Err(anyhow!("{} didn't answer with a link: {}", self.name(), url.chars().take(200).collect::<String>()));
+        }
+        let shared_at = Utc::now();
+        Ok(Share {
+            provider: self.name().to_string(),
+            url,
+            delete_token,
+            shared_at,
+            expires_at: upload.expires_hours.map(|hours| shared_at + ChronoDuration::hours(hours as i64)),
+            encrypted: false,
+        })
+    }
+    
+    /// Deletes a share from the service
+    pub fn revoke(&self, share: &Share) -> Result<()> {
+        // The key in the fragment is ours, not the service's
+        let url = share.url.split('#').next().unwrap_or(&share.url);
+        match self {
+            Self::Gist => {
+                let id = share.delete_token.as_deref().ok_or_else(|| anyhow!("Don't know the gist's ID"))?;
+                http::send("DELETE", &format!("https://api.github.com/gists/{}", id), &github_headers()?, None, TIMEOUT)?;
+            },
+            Self::PasteRs => {
+                http::send("DELETE", url, &[], None, TIMEOUT)?;
+            },
+            Self::ZeroX0 => {
+                let token = share.delete_token.as_deref().ok_or_else(|| anyhow!("0x0.st didn't give a token to delete it with"))?;
+                let (content_type, body) = http::multipart(&[("token", token), ("delete", "")], None);
+                http::send("POST", url, &[], Some((content_type.as_str(), body.as_slice())), TIMEOUT)?;
+            },
+            Self::Custom(name, custom) if custom.delete => {
+                http::send("DELETE", url, &custom_headers(name, custom)?, None, TIMEOUT)?;
+            },
+            Self::Custom(name, _) => return Err(anyhow!("{} isn't set up to delete pastes (set delete = true)", name)),
+        }
+        Ok(())
+    }
+    
+    /// Whether the service can delete a paste after a while
+    fn can_expire(&self) -> bool {
+        match self {
+            Self::ZeroX0 => true,
+            Self::Gist | Self::PasteRs => false,
+            Self::Custom(_, custom) => custom.field.is_some() && custom.expires_field.is_some(),
+        }
+    }
+}
+
+/// Headers for GitHub's API, with the token from `$GITHUB_TOKEN` or `$GH_TOKEN`
+fn github_headers() -> Result<Vec<(String, String)>> {
+    let token = ["GITHUB_TOKEN", "GH_TOKEN"].iter()
+        .find_map(|name| env::var(name).ok().filter(|token| !token.is_empty()))
+        .ok_or_else(|| anyhow!("Sharing to a gist needs a GitHub token in $GITHUB_TOKEN"))?;
+    Ok(vec![
+        ("Authorization".to_string(), format!("Bearer {}", token)),
+        ("Accept".to_string(), "application/vnd.github+json".to_string()),
+    ])
+}
+
+/// Headers for a custom provider, with its token when it has one
+fn custom_headers(name: &str, custom: &CustomShareProvider) -> Result<Vec<(String, String)>> {
+    let Some(token_env) = &custom.token_env else {
+        return Ok(Vec::new());
+    };
+    let token = env::var(token_env).ok().filter(|token| !token.is_empty())
+        .ok_or_else(|| anyhow!("{} needs a token in ${}", name, token_env))?;
+    Ok(vec![("Authorization".to_string(), format!("Bearer {}", token))])
+}
+
+/// The link in a custom provider's answer
+fn link_from(custom: &CustomShareProvider, body: &str) -> Result<String> {
+    let Some(pointer) = &custom.url_pointer else {
+        return Ok(body.trim().to_string());
+    };
+    let answer: serde_json::Value = serde_json::from_str(body)
+        .map_err(|_| anyhow!("Expected a JSON answer with the link at {}", pointer))?;
+    answer.pointer(pointer)
+        .and_then(|url| url.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("The answer had no link at {}", pointer))
+}
+
+/// Encrypts content for sharing, returning the text to upload and the key
+/// to put in the link's fragment
+pub fn encrypt(content: &[u8]) -> Result<(String, String)> {
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(&key).encrypt(&nonce, content)
+        .map_err(|_| anyhow!("Failed to encrypt the content"))?;
+    
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok((format!("{}\n{}\n", ENCRYPTED_HEADER, STANDARD.encode(sealed)), URL_SAFE_NO_PAD.encode(key)))
+}
+
+/// Decrypts a paste made by `encrypt`, given the key from its link
+pub fn decrypt(paste: &str, key: &str) -> Result<Vec<u8>> {
+    let sealed = paste.trim().strip_prefix(ENCRYPTED_HEADER)
+        .ok_or_else(|| anyhow!("This isn't an encrypted pocket share"))?;
+    let sealed = STANDARD.decode(sealed.trim()).map_err(|_| anyhow!("The encrypted share is damaged"))?;
+    let key = URL_SAFE_NO_PAD.decode(key.trim()).ok()
+        .filter(|key| key.len() == 32)
+        .ok_or_else(|| anyhow!("That isn't a valid key"))?;
+    if sealed.len() < NONCE_LEN {
+        return Err(anyhow!("The encrypted share is damaged"));
+    }
+    
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(&key)).decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt the share; the key doesn't match"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn picks_providers_by_name() {
+        let mut config = ShareConfig::default();
+        assert_eq!(Provider::from_config(&config).unwrap(), Provider::PasteRs);
+        assert_eq!(Provider::from_name("0x0.st", &config).unwrap(), Provider::ZeroX0);
+        assert!(Provider::from_name("pastebin", &config).is_err());
+        
+        let custom = CustomShareProvider { url: "https://paste.example.com".to_string(), ..Default::default() };
+        config.providers.insert("work".to_string(), custom);
+        config.default_provider = Some("work".to_string());
+        let work = Provider::from_config(&config).unwrap();
+        assert_eq!(work.name(), "work");
+        assert!(!work.can_revoke());
+        assert!(!work.can_expire());
+        assert!(Provider::ZeroX0.can_expire());
+    }
+    
+    #[test]
+    fn reads_links_from_custom_answers() {
+        let mut custom = CustomShareProvider { url: "https://paste.example.com".to_string(), ..Default::default() };
+        assert_eq!(link_from(&custom, " https://paste.example.com/abc\n").unwrap(), "https://paste.example.com/abc");
+        custom.url_pointer = Some("/data/link".to_string());
+        assert_eq!(link_from(&custom, r#"{"data": {"link": "https://p.example/1"}}"#).unwrap(), "https://p.example/1");
+        assert!(link_from(&custom, "https://p.example/1").is_err());
+    }
+    
+    #[test]
+    fn encrypts_for_the_key_holder_only() {
+        let (paste, key) = encrypt(b"let secret = 42;").unwrap();
+        assert!(paste.starts_with(ENCRYPTED_HEADER));
+        assert!(!paste.contains("secret"));
+        assert_eq!(decrypt(&paste, &key).unwrap(), b"let secret = 42;");
+        
+        let (_, other_key) = encrypt(b"").unwrap();
+        assert!(decrypt(&paste, &other_key).is_err());
+        assert!(decrypt("just some text", &key).is_err());
+    }
+}
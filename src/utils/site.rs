@@ -0,0 +1,207 @@
+use crate::models::Entry;
+use crate::storage::StorageManager;
+use crate::utils::language::{escape_html, highlight_html};
+use anyhow::{Result, Context};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const STYLE_CSS: &str = r#"body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; max-width: 960px; margin: 2rem auto; padding: 0 1rem; color: #222; }
+a { color: #0969da; text-decoration: none; }
+a:hover { text-decoration: underline; }
+header { border-bottom: 1px solid #ddd; margin-bottom: 1.5rem; padding-bottom: 0.5rem; }
+.meta { color: #666; font-size: 0.9rem; }
+.tag { display: inline-block; background: #eef; border-radius: 3px; padding: 0 0.4rem; margin-right: 0.3rem; font-size: 0.85rem; }
+pre { background: #f6f8fa; padding: 1rem; overflow-x: auto; border-radius: 6px; }
+.kw { color: #cf222e; font-weight: bold; }
+.str { color: #0a3069; }
+.num { color: #0550ae; }
+.com { color: #6e7781; font-style: italic; }
+#search { width: 100%; padding: 0.5rem; font-size: 1rem; margin-bottom: 1rem; }
+ul.entries { list-style: none; padding: 0; }
+ul.entries li { padding: 0.3rem 0; }
+"#;
+
+const SEARCH_JS: &str = r#"(function () {
+  var input = document.getElementById("search");
+  var results = document.getElementById("results");
+  var listing = document.getElementById("listing");
+  if (!input || !window.POCKET_INDEX) return;
+  input.addEventListener("input", function () {
+    var query = input.value.trim().toLowerCase();
+    results.innerHTML = "";
+    listing.style.display = query ? "none" : "";
+    if (!query) return;
+    window.POCKET_INDEX.filter(function (e) {
+      return (e.title + " " + e.tags.join(" ") + " " + e.text).toLowerCase().indexOf(query) !== -1;
+    }).forEach(function (e) {
+      var li = document.createElement("li");
+      var a = document.createElement("a");
+      a.href = e.url;
+      a.textContent = e.title;
+      li.appendChild(a);
+      if (e.backpack) {
+        var span = document.createElement("span");
+        span.className = "meta";
+        span.textContent = " [" + e.backpack + "]";
+        li.appendChild(span);
+      }
+      results.appendChild(li);
+    });
+  });
+})();
+"#;
+
+/// An entry being published along with where it lives
+struct PublishedEntry {
+    entry: Entry,
+    backpack: Option<String>,
+}
+
+/// Render the given backpacks (or everything when none are given) to a
+/// static HTML site in `out_dir`. "general" selects the general pocket.
+/// Returns the number of entries published.
+pub fn publish(storage: &StorageManager, backpacks: &[String], out_dir: &Path) -> Result<usize> {
+    let mut entries: Vec<PublishedEntry> = storage.list_all_entries(None)?
+        .into_iter()
+        .filter(|(_, backpack)| {
+            backpacks.is_empty() || backpacks.iter().any(|b| Some(b.as_str()) == backpack.as_deref().or(Some("general")))
+        })
+        .map(|(entry, backpack)| PublishedEntry { entry, backpack })
+        .collect();
+    entries.sort_by_key(|e| e.entry.title.to_lowercase());
+
+    fs::create_dir_all(out_dir.join("entries"))
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    fs::create_dir_all(out_dir.join("tags"))?;
+    fs::create_dir_all(out_dir.join("files"))?;
+
+    fs::write(out_dir.join("style.css"), STYLE_CSS)?;
+    fs::write(out_dir.join("search.js"), SEARCH_JS)?;
+
+    let mut index = Vec::new();
+    let mut tags: BTreeMap<String, Vec<&PublishedEntry>> = BTreeMap::new();
+
+    for published in &entries {
+        let entry = &published.entry;
+        let backpack = published.backpack.as_deref();
+
+        let (body, text) = if entry.is_binary() {
+            // Copy attachments next to the site and link to them
+            let (_, bytes) = storage.load_entry_bytes(&entry.id, backpack)?;
+            let file_name = format!("{}-{}", entry.id, slug(&entry.title));
+            fs::write(out_dir.join("files").join(&file_name), &bytes)?;
+            let body = format!(
+                "<p>Binary attachment ({}, {} bytes): <a href=\"../files/{}\">download</a></p>",
+                escape_html(entry.mime_type.as_deref().unwrap_or("")), bytes.len(), file_name
+            );
+            (body, String::new())
+        } else {
+            let (_, content) = storage.load_entry(&entry.id, backpack)?;
+            let highlighted = match &entry.language {
+                Some(language) => highlight_html(&content, language),
+                None => escape_html(&content),
+            };
+            (format!("<pre><code>{}</code></pre>", highlighted), content.chars().take(500).collect())
+        };
+
+        let page = page(&entry.title, "../", &format!(
+            "<h1>{}</h1>\n<p class=\"meta\">{}{}{}</p>\n<p>{}</p>\n{}",
+            escape_html(&entry.title),
+            backpack.map(|b| format!("Backpack: {} &middot; ", escape_html(b))).unwrap_or_default(),
+            entry.language.as_deref().map(|l| format!("Language: {} &middot; ", escape_html(l))).unwrap_or_default(),
+            entry.created_at.format("%Y-%m-%d"),
+            tag_links(&entry.tags, "../"),
+            body
+        ));
+        fs::write(out_dir.join("entries").join(format!("{}.html", entry.id)), page)?;
+
+        for tag in &entry.tags {
+            tags.entry(tag.clone()).or_default().push(published);
+        }
+
+        index.push(serde_json::json!({
+            "id": entry.id,
+            "title": entry.title,
+            "backpack": backpack,
+            "tags": entry.tags,
+            "language": entry.language,
+            "url": format!("entries/{}.html", entry.id),
+            "text": text,
+        }));
+    }
+
+    fs::write(
+        out_dir.join("search-index.js"),
+        format!("window.POCKET_INDEX = {};\n", serde_json::to_string(&index)?),
+    )?;
+
+    // One page per tag
+    for (tag, tagged) in &tags {
+        let page = page(&format!("Tag: {}", tag), "../", &format!(
+            "<h1>Tag: {}</h1>\n{}",
+            escape_html(tag),
+            entry_list(tagged.iter().copied(), "../")
+        ));
+        fs::write(out_dir.join("tags").join(format!("{}.html", slug(tag))), page)?;
+    }
+
+    // Index page with every entry grouped by backpack, plus search
+    let mut groups: BTreeMap<String, Vec<&PublishedEntry>> = BTreeMap::new();
+    for published in &entries {
+        let name = published.backpack.clone().unwrap_or_else(|| "general".to_string());
+        groups.entry(name).or_default().push(published);
+    }
+
+    let mut listing = String::new();
+    if !tags.is_empty() {
+        listing.push_str(&format!("<h2>Tags</h2>\n<p>{}</p>\n", tag_links(&tags.keys().cloned().collect::<Vec<_>>(), "")));
+    }
+    for (name, grouped) in &groups {
+        listing.push_str(&format!("<h2>{}</h2>\n{}", escape_html(name), entry_list(grouped.iter().copied(), "")));
+    }
+
+    let index_page = page("Snippet Library", "", &format!(
+        "<h1>Snippet Library</h1>\n<input id=\"search\" type=\"search\" placeholder=\"Search {} entries...\">\n<ul id=\"results\" class=\"entries\"></ul>\n<div id=\"listing\">\n{}</div>\n<script src=\"search-index.js\"></script>\n<script src=\"search.js\"></script>",
+        entries.len(), listing
+    ));
+    fs::write(out_dir.join("index.html"), index_page)?;
+
+    Ok(entries.len())
+}
+
+/// Wrap page content in the shared layout
+fn page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<link rel=\"stylesheet\" href=\"{}style.css\">\n</head>\n<body>\n<header><a href=\"{}index.html\">Pocket</a></header>\n{}\n</body>\n</html>\n",
+        escape_html(title), root, root, body
+    )
+}
+
+/// Render a list of links to entry pages
+fn entry_list<'a>(entries: impl Iterator<Item = &'a PublishedEntry>, root: &str) -> String {
+    let items: String = entries
+        .map(|e| format!(
+            "<li><a href=\"{}entries/{}.html\">{}</a> {}</li>\n",
+            root, e.entry.id, escape_html(&e.entry.title), tag_links(&e.entry.tags, root)
+        ))
+        .collect();
+    format!("<ul class=\"entries\">\n{}</ul>\n", items)
+}
+
+/// Render tags as links to their tag pages
+fn tag_links(tags: &[String], root: &str) -> String {
+    tags.iter()
+        .map(|t| format!("<a class=\"tag\" href=\"{}tags/{}.html\">{}</a>", root, slug(t), escape_html(t)))
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// Turn arbitrary text into a safe file name
+fn slug(text: &str) -> String {
+    let slug: String = text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-').to_string();
+    if slug.is_empty() { "untitled".to_string() } else { slug }
+}
@@ -0,0 +1,75 @@
+//! Whether output is colored, and the styles pocket colors it with
+//!
+//! `--color always` or `--color never` decides. Left on `auto`, color is
+//! off when `NO_COLOR` is set or `display.color` is false in
+//! `config.toml`, and otherwise on only for a terminal. Prompts follow the
+//! same choice as everything else.
+
+use clap::ValueEnum;
+use colored::{ColoredString, Colorize};
+use dialoguer::console;
+
+/// When to color output, as `--color` asks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+    /// On a terminal, unless NO_COLOR or display.color say otherwise
+    #[default]
+    Auto,
+    /// Even when piped
+    Always,
+    /// Never
+    Never,
+}
+
+/// Colors output, or doesn't, for the rest of the process. `configured` is
+/// `display.color` from `config.toml`
+pub fn set_color(choice: ColorChoice, configured: bool) {
+    let enabled = match choice {
+        ColorChoice::Always => Some(true),
+        ColorChoice::Never => Some(false),
+        ColorChoice::Auto if no_color() || !configured => Some(false),
+        ColorChoice::Auto => None,
+    };
+    match enabled {
+        Some(enabled) => {
+            colored::control::set_override(enabled);
+            console::set_colors_enabled(enabled);
+            console::set_colors_enabled_stderr(enabled);
+        },
+        // Each decides by whether it's writing to a terminal
+        None => colored::control::unset_override(),
+    }
+}
+
+/// Whether `NO_COLOR` asks for no color (see no-color.org)
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+pub fn _cmd_text(cmd: &str, args: &[&str]) -> ColoredString {
+    format!("{} {}", cmd, args.join(" ")).cyan()
+}
+
+pub fn header(text: &str) -> ColoredString {
+    text.blue().bold()
+}
+
+pub fn key(text: &str) -> ColoredString {
+    text.yellow()
+}
+
+pub fn _value(text: &str) -> ColoredString {
+    text.white()
+}
+
+pub fn _path(text: &str) -> ColoredString {
+    text.underline().white()
+}
+
+pub fn _id(text: &str) -> ColoredString {
+    text.green()
+}
+
+pub fn title(text: &str) -> ColoredString {
+    text.cyan().bold()
+}
@@ -0,0 +1,103 @@
+//! "Did you mean" suggestions for names and IDs that don't exist
+
+/// Suggestions offered at most
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The candidates closest to `input`, best first: those it's a prefix of
+/// (or that are a prefix of it), then those a few typos away
+pub fn closest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() {
+        return Vec::new();
+    }
+    // Roughly one typo per three characters, so short names need near matches
+    let length = input.chars().count();
+    let allowed = (length / 3).max(1);
+    
+    let mut scored: Vec<(usize, &str)> = candidates.into_iter()
+        .filter_map(|candidate| {
+            let lower = candidate.to_lowercase();
+            if lower == input {
+                return None;
+            }
+            if lower.starts_with(&input) || input.starts_with(&lower) {
+                return Some((0, candidate));
+            }
+            // IDs are long and typed as prefixes; compare as much as was typed
+            let compared: String = if lower.chars().count() > length + allowed {
+                lower.chars().take(length).collect()
+            } else {
+                lower
+            };
+            let distance = distance(&input, &compared);
+            (distance <= allowed).then_some((distance, candidate))
+        })
+        .collect();
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored.into_iter().take(MAX_SUGGESTIONS).map(|(_, candidate)| candidate).collect()
+}
+
+/// A "did you mean" hint to end an error message with, or nothing when
+/// nothing is close
+pub fn did_you_mean<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> String {
+    let suggestions: Vec<String> = closest(input, candidates).into_iter()
+        .map(|candidate| format!("'{}'", candidate))
+        .collect();
+    match suggestions.as_slice() {
+        [] => String::new(),
+        [only] => format!("; did you mean {}?", only),
+        [rest @ .., last] => format!("; did you mean {} or {}?", rest.join(", "), last),
+    }
+}
+
+/// Edits (insertions, deletions, substitutions and swaps of neighbouring
+/// characters) between two strings
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn suggests_close_names() {
+        let backpacks = ["work", "personal", "scripts", "snippets"];
+        assert_eq!(closest("wrok", backpacks), vec!["work"]);
+        assert_eq!(closest("scrpits", backpacks), vec!["scripts"]);
+        assert_eq!(closest("pers", backpacks), vec!["personal"]);
+        assert!(closest("zzz", backpacks).is_empty());
+        assert!(closest("work", backpacks).is_empty());
+    }
+    
+    #[test]
+    fn suggests_ids_from_a_mistyped_prefix() {
+        let ids = ["3f2a9c10-aaaa", "7b41e0d2-bbbb"];
+        assert_eq!(closest("3f2b9c", ids), vec!["3f2a9c10-aaaa"]);
+        assert_eq!(did_you_mean("7b41f0", ids), "; did you mean '7b41e0d2-bbbb'?");
+        assert_eq!(did_you_mean("nothing-like-it", ids), "");
+    }
+    
+    #[test]
+    fn lists_several_suggestions() {
+        assert_eq!(did_you_mean("deplo", ["deploy", "deploy-prod", "build"]), "; did you mean 'deploy' or 'deploy-prod'?");
+    }
+}
@@ -1,4 +1,6 @@
 use anyhow::{Result, anyhow};
+use crate::models::LlmConfig;
+use crate::utils::llm::LlmClient;
 use std::collections::HashMap;
 
 #[cfg(feature = "ml-summarization")]
@@ -82,6 +84,73 @@ pub fn initialize_summarization_model() -> Result<()> {
     Ok(())
 }
 
+/// Most characters of a snippet sent to a language model
+const MAX_PROMPT_CHARS: usize = 8000;
+
+/// Turns text into a short summary
+pub trait Summarizer {
+    /// What's doing the summarizing, like `heuristic` or `ollama/llama3.2`
+    fn name(&self) -> String;
+    
+    /// Summarizes `text` in a sentence or so
+    fn summarize(&self, text: &str) -> Result<String>;
+}
+
+/// Summarizes without a language model: with the bundled model when built
+/// with `ml-summarization`, and otherwise by picking out key sentences
+pub struct HeuristicSummarizer;
+
+impl Summarizer for HeuristicSummarizer {
+    fn name(&self) -> String {
+        "heuristic".to_string()
+    }
+    
+    fn summarize(&self, text: &str) -> Result<String> {
+        summarize_text(text)
+    }
+}
+
+impl Summarizer for LlmClient {
+    fn name(&self) -> String {
+        LlmClient::name(self)
+    }
+    
+    fn summarize(&self, text: &str) -> Result<String> {
+        let text: String = text.chars().take(MAX_PROMPT_CHARS).collect();
+        let prompt = format!(
+            "Summarize what this snippet is or does in one short sentence. Reply with the sentence only.\n\n{}",
+            text
+        );
+        self.complete(&prompt)
+    }
+}
+
+/// The summarizer `config.toml` asks for
+pub fn configured_summarizer(config: &LlmConfig) -> Result<Box<dyn Summarizer>> {
+    Ok(match LlmClient::from_config(config)? {
+        Some(client) => Box::new(client),
+        None => Box::new(HeuristicSummarizer),
+    })
+}
+
+/// Summarizes with the configured backend, falling back to the heuristic one
+/// when the backend isn't set up or can't be reached. Returns the summary
+/// and the name of whatever wrote it
+pub fn summarize_with_config(config: &LlmConfig, text: &str) -> (String, String) {
+    let attempt = configured_summarizer(config)
+        .and_then(|summarizer| Ok((summarizer.summarize(text)?, summarizer.name())));
+    let (summary, by) = match attempt {
+        Ok(result) => result,
+        Err(e) => {
+            let backend = format!("{:?}", config.backend).to_lowercase();
+            crate::logging::warning(&format!("Couldn't summarize with {}, using the heuristic summary instead: {}", backend, e));
+            let summary = summarize_text(text).unwrap_or_else(|_| text.lines().next().unwrap_or_default().to_string());
+            (summary, HeuristicSummarizer.name())
+        }
+    };
+    (summary.trim().to_string(), by)
+}
+
 /// Summarize text content - returns short summary
 /// 
 /// Optimized for minimal memory usage
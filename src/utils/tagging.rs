@@ -0,0 +1,210 @@
+//! Suggesting tags and a title for a snippet, with the language model set up
+//! in `config.toml` or, without one, from the words the snippet uses most
+
+use crate::models::LlmConfig;
+use crate::utils::language::{tokenize, TokenKind};
+use crate::utils::llm::LlmClient;
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Most tags to suggest at once
+const MAX_TAGS: usize = 5;
+
+/// Most characters of a snippet sent to a language model
+const MAX_PROMPT_CHARS: usize = 8000;
+
+/// Words too common to say anything about a snippet
+const STOPWORDS: &[&str] = &[
+    "about", "after", "also", "because", "been", "before", "being", "could", "does", "each",
+    "else", "every", "from", "have", "here", "into", "just", "like", "make", "more", "most",
+    "much", "must", "only", "other", "over", "same", "should", "some", "such", "than", "that",
+    "their", "them", "then", "there", "these", "they", "this", "those", "through", "very",
+    "what", "when", "where", "which", "while", "will", "with", "would", "your", "args",
+    "value", "result", "data", "todo", "none", "null", "true", "false", "self", "string",
+];
+
+/// Tags and a title suggested for an entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestions {
+    /// A better title, if there's one to suggest
+    pub title: Option<String>,
+    
+    /// Tags, most fitting first
+    pub tags: Vec<String>,
+    
+    /// What made the suggestions, like `keywords` or `ollama/llama3.2`
+    pub by: String,
+}
+
+/// Asks the configured language model for suggestions, falling back to
+/// keywords from the content when there's no model or it can't be reached
+pub fn suggest_with_config(config: &LlmConfig, title: &str, content: &str, language: Option<&str>) -> Suggestions {
+    let attempt = LlmClient::from_config(config).and_then(|client| match client {
+        Some(client) => {
+            let answer = client.complete(&prompt(title, content, language))?;
+            let (title, tags) = parse_answer(&answer)?;
+            Ok(Some(Suggestions { title, tags, by: client.name() }))
+        },
+        None => Ok(None),
+    });
+    
+    match attempt {
+        Ok(Some(suggestions)) => suggestions,
+        Ok(None) => keyword_suggestions(title, content, language),
+        Err(e) => {
+            let backend = format!("{:?}", config.backend).to_lowercase();
+            crate::logging::warning(&format!("Couldn't get suggestions from {}, using keywords instead: {}", backend, e));
+            keyword_suggestions(title, content, language)
+        }
+    }
+}
+
+/// Suggests the language and the words a snippet uses most as tags, and its
+/// first comment as a title when the current one is just the first line
+pub fn keyword_suggestions(title: &str, content: &str, language: Option<&str>) -> Suggestions {
+    let tokens = tokenize(content, language.unwrap_or_default());
+    
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut first_seen: HashMap<String, usize> = HashMap::new();
+    for (kind, text) in &tokens {
+        if !matches!(kind, TokenKind::Plain | TokenKind::Comment) {
+            continue;
+        }
+        for word in words(text) {
+            let position = first_seen.len();
+            first_seen.entry(word.clone()).or_insert(position);
+            *counts.entry(word).or_insert(0) += 1;
+        }
+    }
+    
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by_key(|(word, count)| (std::cmp::Reverse(*count), first_seen[word]));
+    
+    let mut tags: Vec<String> = language.into_iter().map(normalize_tag).collect();
+    for (word, _) in ranked {
+        if tags.len() >= MAX_TAGS {
+            break;
+        }
+        if !tags.contains(&word) {
+            tags.push(word);
+        }
+    }
+    
+    // Titles that are just the first line of content can do better
+    let first_line = content.lines().next().unwrap_or_default().trim();
+    let title = tokens.iter()
+        .find(|(kind, _)| *kind == TokenKind::Comment)
+        .map(|(_, comment)| comment.trim_start_matches(['/', '#', '-', '!', ' ']).trim().to_string())
+        .filter(|comment| comment.len() > 3 && comment != title)
+        .filter(|_| title.trim() == first_line || title.trim().is_empty());
+    
+    Suggestions { title, tags, by: "keywords".to_string() }
+}
+
+/// Lower-cased words worth tagging with: four letters or more, not a
+/// number and not a stopword. `camelCase` and `snake_case` split into words
+pub(crate) fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for raw in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut word = String::new();
+        let mut previous_lower = false;
+        for c in raw.chars() {
+            if c.is_uppercase() && previous_lower {
+                words.push(std::mem::take(&mut word));
+            }
+            previous_lower = c.is_lowercase();
+            word.extend(c.to_lowercase());
+        }
+        words.push(word);
+    }
+    words.retain(|word| {
+        word.chars().count() >= 4
+            && word.chars().all(char::is_alphabetic)
+            && !STOPWORDS.contains(&word.as_str())
+    });
+    words
+}
+
+/// Makes a tag look like the rest: lower case, dashes for spaces
+fn normalize_tag(tag: &str) -> String {
+    tag.trim()
+        .trim_start_matches('#')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// The prompt asking a model for tags and a title
+fn prompt(title: &str, content: &str, language: Option<&str>) -> String {
+    let content: String = content.chars().take(MAX_PROMPT_CHARS).collect();
+    format!(
+        "Suggest up to {} short lowercase tags and a concise descriptive title for this {}snippet, currently titled \"{}\". \
+         Reply with JSON only, like {{\"title\": \"...\", \"tags\": [\"...\"]}}.\n\n{}",
+        MAX_TAGS,
+        language.map(|l| format!("{} ", l)).unwrap_or_default(),
+        title,
+        content
+    )
+}
+
+/// Reads the title and tags out of a model's answer, which may wrap its JSON
+/// in prose or a code fence
+fn parse_answer(answer: &str) -> Result<(Option<String>, Vec<String>)> {
+    let start = answer.find('{');
+    let end = answer.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &answer[start..=end],
+        _ => return Err(anyhow!("The model didn't answer with JSON")),
+    };
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| anyhow!("The model's answer isn't valid JSON: {}", e))?;
+    
+    let title = value["title"].as_str()
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+    let mut tags: Vec<String> = Vec::new();
+    for tag in value["tags"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+        let tag = normalize_tag(tag);
+        if !tag.is_empty() && !tags.contains(&tag) && tags.len() < MAX_TAGS {
+            tags.push(tag);
+        }
+    }
+    Ok((title, tags))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn suggests_keywords_from_content() {
+        let content = "# Resize every image in a folder\nfor image in *.png; do\n  convert \"$image\" -resize 50% \"small_$image\"\ndone\n";
+        let suggestions = keyword_suggestions("# Resize every image in a folder", content, Some("shell"));
+        // The language first, then the most used words, earliest first on a tie
+        assert_eq!(suggestions.tags[..3], ["shell", "resize", "image"]);
+        assert!(!suggestions.tags.iter().any(|tag| tag == "every" || tag == "done"));
+        assert_eq!(suggestions.title.as_deref(), Some("Resize every image in a folder"));
+        
+        // A title someone chose is left alone
+        let suggestions = keyword_suggestions("Batch resize", content, Some("shell"));
+        assert_eq!(suggestions.title, None);
+        
+        assert_eq!(words("parseHttpRequest max_retries 42"), ["parse", "http", "request", "retries"]);
+    }
+    
+    #[test]
+    fn reads_suggestions_from_model_answers() {
+        let answer = "Sure!\n```json\n{\"title\": \"Resize images\", \"tags\": [\"Image Processing\", \"#shell\", \"shell\"]}\n```";
+        let (title, tags) = parse_answer(answer).unwrap();
+        assert_eq!(title.as_deref(), Some("Resize images"));
+        assert_eq!(tags, ["image-processing", "shell"]);
+        
+        let (title, tags) = parse_answer("{\"title\": \" \", \"tags\": []}").unwrap();
+        assert_eq!(title, None);
+        assert!(tags.is_empty());
+        
+        assert!(parse_answer("no json here").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Reading points in time off the command line
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+
+/// A point in time given as how long ago (`30m`, `12h`, `7d`, `2w`), a date
+/// (`2024-05-01`, midnight local time) or a full RFC 3339 timestamp
+pub fn parse_since(raw: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(raw) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        return Local.from_local_datetime(&midnight).earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| anyhow!("'{}' isn't a valid local time", raw));
+    }
+
+    let invalid = || anyhow!("Invalid time '{}', expected something like 12h, 7d, 2w or 2024-05-01", raw);
+    let split = raw.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let amount: i64 = raw[..split].parse().map_err(|_| invalid())?;
+    let ago = match &raw[split..] {
+        "m" | "min" | "mins" => Duration::try_minutes(amount),
+        "h" | "hour" | "hours" => Duration::try_hours(amount),
+        "d" | "day" | "days" => Duration::try_days(amount),
+        "w" | "week" | "weeks" => Duration::try_weeks(amount),
+        _ => return Err(invalid()),
+    };
+    ago.and_then(|ago| now.checked_sub_signed(ago))
+        .ok_or_else(|| anyhow!("'{}' is further back than pocket can count", raw))
+}
+
+/// The end of a time range, read like `parse_since` except that a date
+/// means the end of that day, so `--until 2024-05-01` takes in May 1st
+pub fn parse_until(raw: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let time = parse_since(raw, now)?;
+    if NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d").is_ok() {
+        return Ok(time + Duration::days(1));
+    }
+    Ok(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_relative_and_absolute_times() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        assert_eq!(parse_since("12h", now).unwrap(), now - Duration::hours(12));
+        assert_eq!(parse_since("2w", now).unwrap(), now - Duration::days(14));
+        assert_eq!(parse_since("2024-05-01T08:00:00Z", now).unwrap(), Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap());
+        assert!(parse_since("2024-05-01", now).is_ok());
+        assert!(parse_since("soon", now).is_err());
+        assert!(parse_since("5y", now).is_err());
+        assert_eq!(parse_until("2024-05-01", now).unwrap() - parse_since("2024-05-01", now).unwrap(), Duration::days(1));
+        assert_eq!(parse_until("2w", now).unwrap(), parse_since("2w", now).unwrap());
+    }
+
+    #[test]
+    fn reads_every_unit_of_how_long_ago() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        for (raw, ago) in [
+            ("30m", Duration::minutes(30)),
+            ("5min", Duration::minutes(5)),
+            ("1hour", Duration::hours(1)),
+            ("3days", Duration::days(3)),
+            ("1week", Duration::weeks(1)),
+            ("0d", Duration::zero()),
+            ("  7d ", Duration::days(7)),
+        ] {
+            assert_eq!(parse_since(raw, now).unwrap(), now - ago, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn reads_dates_as_local_midnight_and_timestamps_with_their_offset() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 12, 0, 0).unwrap();
+        let midnight = Local.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).earliest().unwrap().with_timezone(&Utc);
+        assert_eq!(parse_since("2024-05-01", now).unwrap(), midnight);
+        assert_eq!(parse_until("2024-05-01", now).unwrap(), midnight + Duration::days(1));
+        assert_eq!(parse_since("2024-05-01T08:00:00+02:00", now).unwrap(), Utc.with_ymd_and_hms(2024, 5, 1, 6, 0, 0).unwrap());
+        assert_eq!(parse_until("2024-05-01T08:00:00Z", now).unwrap(), Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_time() {
+        let now = Utc::now();
+        for raw in ["", "d", "12", "-3d", "12 h", "3.5d", "2024-13-01", "2024-05-01 08:00", "yesterday", "99999999999999999999d", "9999999999999d", "999999999999w"] {
+            assert!(parse_since(raw, now).is_err(), "{}", raw);
+            assert!(parse_until(raw, now).is_err(), "{}", raw);
+        }
+    }
+}
@@ -16,8 +16,8 @@ pub static CURRENT_VERSION: Version = Version {
 
 impl std::fmt::Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Pocket CLI {}\n", CURRENT_VERSION.letter)?;
-        write!(f, "Release: {}\n", CURRENT_VERSION.name)?;
+        writeln!(f, "Pocket CLI {}", CURRENT_VERSION.letter)?;
+        writeln!(f, "Release: {}", CURRENT_VERSION.name)?;
         write!(f, "Author: {}", CURRENT_VERSION.author)?;
 
         if let Some(compat) = CURRENT_VERSION.compatibility {
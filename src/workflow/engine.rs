@@ -0,0 +1,319 @@
+//! Runs workflows step by step
+//!
+//! The engine owns variables, conditions and error handling. What a step
+//! actually does is up to a [`StepRunner`], so the same workflow can run for
+//! real or be checked against a stand-in.
+
+use crate::models::{OnError, Workflow, WorkflowStep};
+use crate::utils;
+use anyhow::{Result, anyhow, bail};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Retries for `on_error = "retry"` when the step doesn't say
+const DEFAULT_RETRIES: u32 = 2;
+
+/// Carries out workflow steps
+pub trait StepRunner {
+    /// Runs one step, given as its arguments with variables already filled
+    /// in, and returns what it produced. `previous` is the output of the last
+    /// step that ran
+    fn run(&mut self, args: &[String], previous: Option<&str>, timeout: Option<Duration>) -> Result<String>;
+}
+
+/// How a step went
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepStatus {
+    /// The step ran and succeeded
+    Done,
+    /// The step's condition wasn't met
+    Skipped,
+    /// The step failed and the workflow carried on
+    Failed(String),
+}
+
+/// A step as it was run
+#[derive(Debug, Clone)]
+pub struct StepReport {
+    /// The step's command line, with variables filled in where they could be
+    pub run: String,
+    
+    /// How it went
+    pub status: StepStatus,
+}
+
+/// Runs a workflow's steps in order, keeping track of variables
+pub struct Engine {
+    /// Variables available to `{{name}}`: the workflow's defaults, overrides
+    /// from the command line, and the outputs of earlier steps
+    variables: BTreeMap<String, String>,
+    
+    /// Output of the last step that ran
+    previous: Option<String>,
+}
+
+impl Engine {
+    /// Sets up a run of `workflow`, with `overrides` replacing its default variables
+    pub fn new(workflow: &Workflow, overrides: &[(String, String)]) -> Self {
+        let mut variables = workflow.variables.clone();
+        variables.extend(overrides.iter().cloned());
+        Self { variables, previous: None }
+    }
+    
+    /// Runs every step. Fails on the first step that fails without
+    /// `on_error = "continue"`
+    pub fn run(&mut self, workflow: &Workflow, runner: &mut dyn StepRunner) -> Result<Vec<StepReport>> {
+        let total = workflow.steps.len();
+        let mut reports = Vec::new();
+        
+        for (i, step) in workflow.steps.iter().enumerate() {
+            let counter = format!("[{}/{}]", i + 1, total);
+            
+            if let Some(reason) = self.skip_reason(step) {
+                println!("{} {} {}", counter.dimmed(), step.run, format!("(skipped: {})", reason).dimmed());
+                reports.push(StepReport { run: step.run.clone(), status: StepStatus::Skipped });
+                continue;
+            }
+            
+            let args = self.expand(&step.run);
+            let run = args.as_ref().map(shell_words::join).unwrap_or_else(|_| step.run.clone());
+            println!("{} {}", counter.dimmed(), run);
+            
+            match args.and_then(|args| self.attempt(step, &args, runner)) {
+                Ok(output) => {
+                    let output = output.trim().to_string();
+                    if let Some(name) = &step.save_as {
+                        self.variables.insert(name.clone(), output.clone());
+                    }
+                    self.previous = Some(output);
+                    reports.push(StepReport { run, status: StepStatus::Done });
+                },
+                Err(e) if step.on_error == OnError::Continue => {
+                    println!("{} {}", "failed, continuing:".yellow(), e);
+                    reports.push(StepReport { run, status: StepStatus::Failed(e.to_string()) });
+                },
+                Err(e) => {
+                    return Err(anyhow!("Step {} ({}) failed: {}", i + 1, run, e));
+                },
+            }
+        }
+        
+        Ok(reports)
+    }
+    
+    /// Why a step's `if`/`unless` condition says not to run it, if it does
+    fn skip_reason(&self, step: &WorkflowStep) -> Option<String> {
+        if let Some(name) = &step.if_set {
+            if !self.is_set(name) {
+                return Some(format!("{} is empty", name));
+            }
+        }
+        if let Some(name) = &step.unless {
+            if self.is_set(name) {
+                return Some(format!("{} is set", name));
+            }
+        }
+        None
+    }
+    
+    /// Whether a variable has a non-empty value
+    fn is_set(&self, name: &str) -> bool {
+        self.variables.get(name).is_some_and(|value| !value.trim().is_empty())
+    }
+    
+    /// Splits a step into arguments and fills in variables. Splitting first
+    /// keeps a value with spaces in it as a single argument
+    fn expand(&self, run: &str) -> Result<Vec<String>> {
+        let words = shell_words::split(run)
+            .map_err(|e| anyhow!("Can't parse step: {}", e))?;
+        words.iter().map(|word| substitute(word, &self.variables)).collect()
+    }
+    
+    /// Runs a step, trying again as often as `on_error = "retry"` allows
+    fn attempt(&self, step: &WorkflowStep, args: &[String], runner: &mut dyn StepRunner) -> Result<String> {
+        let tries = match step.on_error {
+            OnError::Retry => 1 + step.retries.unwrap_or(DEFAULT_RETRIES),
+            _ => 1,
+        };
+        let timeout = step.timeout.map(utils::exec::timeout).transpose()?;
+        
+        let mut attempt = 1;
+        loop {
+            match runner.run(args, self.previous.as_deref(), timeout) {
+                Ok(output) => return Ok(output),
+                Err(e) if attempt < tries => {
+                    attempt += 1;
+                    println!("{} {} (attempt {}/{})", "failed, retrying:".yellow(), e, attempt, tries);
+                },
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Replaces every `{{name}}` in `text` with its value. Unknown names are an
+/// error rather than quietly becoming empty
+pub fn substitute(text: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            bail!("Unclosed {{{{ in '{}'", text);
+        };
+        let name = rest[start + 2..start + end].trim();
+        let value = variables.get(name)
+            .ok_or_else(|| anyhow!("Unknown variable {{{{{}}}}}", name))?;
+        result.push_str(&rest[..start]);
+        result.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workflow::parse_toml;
+    
+    /// Answers steps from a script: `echo X` prints X, `fail` fails, and
+    /// `flaky` fails until it's been tried three times
+    #[derive(Default)]
+    struct Scripted {
+        ran: Vec<String>,
+        flaky_tries: u32,
+        timeouts: Vec<Option<Duration>>,
+    }
+    
+    impl StepRunner for Scripted {
+        fn run(&mut self, args: &[String], previous: Option<&str>, timeout: Option<Duration>) -> Result<String> {
+            self.ran.push(args.join(" "));
+            self.timeouts.push(timeout);
+            match args[0].as_str() {
+                "echo" => Ok(args[1..].join(" ")),
+                "prev" => Ok(previous.unwrap_or("none").to_string()),
+                "flaky" => {
+                    self.flaky_tries += 1;
+                    if self.flaky_tries < 3 { bail!("not yet") } else { Ok("finally".to_string()) }
+                },
+                _ => bail!("boom"),
+            }
+        }
+    }
+    
+    fn workflow(toml: &str) -> Workflow {
+        parse_toml("test", toml).unwrap()
+    }
+    
+    #[test]
+    fn fills_in_variables_and_step_outputs() {
+        let workflow = workflow(r#"
+            [variables]
+            greeting = "hello there"
+            
+            [[steps]]
+            run = "echo {{greeting}}"
+            as = "said"
+            
+            [[steps]]
+            run = "echo '{{said}}, again'"
+            
+            [[steps]]
+            run = "prev"
+        "#);
+        let mut runner = Scripted::default();
+        Engine::new(&workflow, &[]).run(&workflow, &mut runner).unwrap();
+        assert_eq!(runner.ran, ["echo hello there", "echo hello there, again", "prev"]);
+        
+        let mut runner = Scripted::default();
+        let overrides = [("greeting".to_string(), "hi".to_string())];
+        Engine::new(&workflow, &overrides).run(&workflow, &mut runner).unwrap();
+        assert_eq!(runner.ran[0], "echo hi");
+    }
+    
+    #[test]
+    fn conditions_skip_steps() {
+        let workflow = workflow(r#"
+            [[steps]]
+            run = "echo"
+            as = "empty"
+            
+            [[steps]]
+            run = "echo found"
+            as = "found"
+            
+            [[steps]]
+            run = "echo a"
+            if = "empty"
+            
+            [[steps]]
+            run = "echo b"
+            if = "found"
+            
+            [[steps]]
+            run = "echo c"
+            unless = "found"
+            
+            [[steps]]
+            run = "echo d"
+            if = "never_set"
+        "#);
+        let mut runner = Scripted::default();
+        let reports = Engine::new(&workflow, &[]).run(&workflow, &mut runner).unwrap();
+        assert_eq!(runner.ran, ["echo", "echo found", "echo b"]);
+        let skipped = reports.iter().filter(|r| r.status == StepStatus::Skipped).count();
+        assert_eq!(skipped, 3);
+    }
+    
+    #[test]
+    fn handles_errors_as_each_step_asks() {
+        let workflow = workflow(r#"
+            [[steps]]
+            run = "fail"
+            on_error = "continue"
+            
+            [[steps]]
+            run = "flaky"
+            on_error = "retry"
+            timeout = 1.5
+            
+            [[steps]]
+            run = "fail"
+            
+            [[steps]]
+            run = "echo unreachable"
+        "#);
+        let mut runner = Scripted::default();
+        let err = Engine::new(&workflow, &[]).run(&workflow, &mut runner).unwrap_err();
+        assert!(err.to_string().contains("Step 3"));
+        assert_eq!(runner.ran, ["fail", "flaky", "flaky", "flaky", "fail"]);
+        assert_eq!(runner.timeouts[1], Some(Duration::from_millis(1500)));
+    }
+    
+    #[test]
+    fn refuses_timeouts_that_cant_be_waited_out() {
+        let workflow = workflow("[[steps]]\nrun = \"echo hi\"\ntimeout = -1.0");
+        let mut runner = Scripted::default();
+        let err = Engine::new(&workflow, &[]).run(&workflow, &mut runner).unwrap_err();
+        assert!(format!("{:#}", err).contains("positive number of seconds"));
+        assert!(runner.ran.is_empty());
+    }
+    
+    #[test]
+    fn retries_give_up_eventually() {
+        let workflow = workflow("[[steps]]\nrun = \"flaky\"\non_error = \"retry\"\nretries = 1");
+        let mut runner = Scripted::default();
+        assert!(Engine::new(&workflow, &[]).run(&workflow, &mut runner).is_err());
+        assert_eq!(runner.ran.len(), 2);
+    }
+    
+    #[test]
+    fn substitutes_variables() {
+        let variables = BTreeMap::from([("name".to_string(), "pocket".to_string())]);
+        assert_eq!(substitute("hi {{name}}, {{ name }}!", &variables).unwrap(), "hi pocket, pocket!");
+        assert_eq!(substitute("no braces", &variables).unwrap(), "no braces");
+        assert!(substitute("{{missing}}", &variables).is_err());
+        assert!(substitute("{{name", &variables).is_err());
+    }
+}
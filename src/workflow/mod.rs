@@ -0,0 +1,152 @@
+//! Workflows: pocket commands chained to run one after another
+//!
+//! A workflow is either a one-liner (`search 'query' > insert file.js`) or a
+//! TOML file with variables, conditions and error handling:
+//!
+//! ```toml
+//! [variables]
+//! file = "src/main.rs"
+//!
+//! [[steps]]
+//! run = "search {{query}}"
+//! as = "found"
+//!
+//! [[steps]]
+//! run = "insert {{found}} {{file}}"
+//! if = "found"
+//! on_error = "continue"
+//! ```
+//!
+//! Saved workflows live in `~/.pocket/data/workflows`, and can be scheduled or
+//! triggered by events (see [`schedule`]).
+
+pub mod engine;
+pub mod schedule;
+pub mod steps;
+
+pub use engine::{Engine, StepReport, StepRunner, StepStatus};
+
+use crate::models::{Workflow, WorkflowStep};
+use crate::storage::StorageManager;
+use anyhow::{Result, Context, anyhow, bail};
+use std::fs;
+use std::path::Path;
+
+/// Parses a one-liner, with steps separated by a standalone `>`
+pub fn parse_chain(name: &str, text: &str) -> Result<Workflow> {
+    let words = shell_words::split(text)
+        .with_context(|| format!("Can't parse workflow '{}'", text))?;
+    
+    let mut steps = Vec::new();
+    for step in words.split(|word| word == ">") {
+        if step.is_empty() {
+            bail!("Workflow '{}' has an empty step", text);
+        }
+        steps.push(WorkflowStep::new(shell_words::join(step)));
+    }
+    Ok(Workflow::new(name.to_string(), steps))
+}
+
+/// Parses a workflow file. A missing `name` is filled in from `name`
+pub fn parse_toml(name: &str, text: &str) -> Result<Workflow> {
+    let mut workflow: Workflow = toml::from_str(text)?;
+    if workflow.name.is_empty() {
+        workflow.name = name.to_string();
+    }
+    if workflow.steps.is_empty() {
+        bail!("Workflow '{}' has no steps", workflow.name);
+    }
+    for (i, step) in workflow.steps.iter().enumerate() {
+        if step.run.trim().is_empty() {
+            bail!("Step {} of workflow '{}' has nothing to run", i + 1, workflow.name);
+        }
+    }
+    Ok(workflow)
+}
+
+/// Finds the workflow `spec` refers to: a workflow file, a saved workflow,
+/// or else a one-liner
+pub fn resolve(storage: &StorageManager, spec: &str) -> Result<Workflow> {
+    let path = Path::new(spec);
+    if path.is_file() {
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        return parse_toml(&name, &text)
+            .with_context(|| format!("Invalid workflow file {}", path.display()));
+    }
+    
+    if validate_name(spec).is_ok() && storage.has_workflow(spec) {
+        return storage.load_workflow(spec);
+    }
+    
+    if spec.contains(char::is_whitespace) {
+        return parse_chain("inline", spec);
+    }
+    Err(anyhow!("No workflow named '{}'", spec))
+}
+
+/// Checks a workflow name can be used as a file name
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !name.starts_with('.');
+    if !valid {
+        bail!("Invalid workflow name '{}': use letters, numbers, '-', '_' and '.'", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::OnError;
+    
+    #[test]
+    fn splits_chains_on_standalone_arrows() {
+        let workflow = parse_chain("quick", "search 'a > b' > insert file.js").unwrap();
+        let runs: Vec<&str> = workflow.steps.iter().map(|s| s.run.as_str()).collect();
+        assert_eq!(runs, ["search 'a > b'", "insert file.js"]);
+        
+        assert!(parse_chain("bad", "search x > > insert y").is_err());
+        assert!(parse_chain("bad", "search x >").is_err());
+    }
+    
+    #[test]
+    fn parses_workflow_files() {
+        let workflow = parse_toml("release", r#"
+            [variables]
+            file = "src/main.rs"
+            
+            [[steps]]
+            run = "search {{query}}"
+            as = "found"
+            
+            [[steps]]
+            run = "insert {{found}} {{file}}"
+            if = "found"
+            on_error = "retry"
+            retries = 1
+            timeout = 2.5
+        "#).unwrap();
+        
+        assert_eq!(workflow.name, "release");
+        assert_eq!(workflow.variables["file"], "src/main.rs");
+        assert_eq!(workflow.steps[0].save_as.as_deref(), Some("found"));
+        assert_eq!(workflow.steps[1].if_set.as_deref(), Some("found"));
+        assert_eq!(workflow.steps[1].on_error, OnError::Retry);
+        assert_eq!(workflow.steps[1].retries, Some(1));
+        assert_eq!(workflow.steps[1].timeout, Some(2.5));
+        
+        assert!(parse_toml("empty", "steps = []").is_err());
+        assert!(parse_toml("typo", "[[steps]]\nrun = \"x\"\non_error = \"explode\"").is_err());
+    }
+    
+    #[test]
+    fn rejects_names_that_escape_the_workflows_directory() {
+        assert!(validate_name("release-1.2").is_ok());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name(".hidden").is_err());
+        assert!(validate_name("").is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! Running saved workflows by themselves
+//!
+//! A workflow can run on a cron schedule, which `pocket daemon` keeps, or be
+//! triggered by an event, like an entry being added to a backpack. Triggers
+//! are checked once each pocket command finishes, and events raised by a
+//! triggered workflow don't set off more triggers.
+
+use crate::cards::events::{self, Event, EVENT_NAMES};
+use crate::cards::CardManager;
+use crate::logging;
+use crate::models::WorkflowSchedule;
+use crate::storage::StorageManager;
+use crate::workflow::{steps::PocketSteps, Engine};
+use anyhow::{Result, anyhow, bail};
+use chrono::{Datelike, Local, Timelike};
+use colored::Colorize;
+use std::time::Duration;
+
+/// Short names for the events people trigger on most
+const EVENT_ALIASES: &[(&str, &str)] = &[
+    ("add", "entry_added"),
+    ("edit", "entry_updated"),
+    ("remove", "entry_removed"),
+];
+
+/// A parsed cron expression: minute, hour, day of month, month and day of
+/// week, each kept as a bit set of the values it matches
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    
+    /// Whether the day of month or day of week was `*`. Cron matches either
+    /// day field when both are restricted
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    /// Parses a five-field cron expression like `0 9 * * 1-5`. Fields take
+    /// `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`) and lists (`1,15`)
+    pub fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            bail!("Expected 5 cron fields (minute hour day month weekday), got {} in '{}'", fields.len(), expr);
+        };
+        
+        // Sunday is both 0 and 7
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+    
+    /// Whether the schedule runs in the minute `time` falls in
+    pub fn matches(&self, time: &(impl Datelike + Timelike)) -> bool {
+        let has = |set: u64, value: u32| set & (1 << value) != 0;
+        let day = has(self.days, time.day());
+        let weekday = has(self.weekdays, time.weekday().num_days_from_sunday());
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        };
+        
+        has(self.minutes, time.minute())
+            && has(self.hours, time.hour())
+            && has(self.months, time.month())
+            && day_matches
+    }
+}
+
+/// Parses one cron field into the set of values it matches
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let number = |text: &str| text.parse::<u32>()
+        .map_err(|_| anyhow!("'{}' isn't a number in cron field '{}'", text, field));
+    
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, number(step)?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            bail!("Cron step can't be 0 in '{}'", field);
+        }
+        
+        let (low, high) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((low, high)) => (number(low)?, number(high)?),
+            // `5/10` means from 5 to the end, every 10
+            None if part.contains('/') => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        if low < min || high > max || low > high {
+            bail!("Cron field '{}' is out of range {}-{}", field, min, max);
+        }
+        
+        for value in (low..=high).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+/// The event name a trigger listens for, from a name or short alias
+pub fn event_name(name: &str) -> Result<&'static str> {
+    if let Some((_, event)) = EVENT_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Ok(event);
+    }
+    EVENT_NAMES.iter().copied().find(|event| *event == name)
+        .ok_or_else(|| anyhow!("Unknown event '{}'; use add, edit, remove or one of {}", name, EVENT_NAMES.join(", ")))
+}
+
+/// Whether a schedule is a trigger for this event
+fn fires_on(schedule: &WorkflowSchedule, event: &Event) -> bool {
+    schedule.on.as_deref() == Some(event.name())
+        && (schedule.backpack.is_none() || schedule.backpack.as_deref() == event.backpack())
+}
+
+/// Runs the workflows triggered by events since the last check
+pub fn run_triggers(card_manager: &mut CardManager) {
+    let fired = events::take_fired();
+    if fired.is_empty() {
+        return;
+    }
+    
+    let schedules = match StorageManager::new().and_then(|storage| storage.load_schedules()) {
+        Ok(schedules) => schedules,
+        Err(e) => {
+            logging::warning(&format!("Failed to load workflow triggers: {}", e));
+            return;
+        }
+    };
+    
+    let mut due: Vec<(&str, &str)> = Vec::new();
+    for schedule in &schedules {
+        if let Some(event) = fired.iter().find(|event| fires_on(schedule, event)) {
+            if !due.iter().any(|(name, _)| *name == schedule.workflow) {
+                due.push((&schedule.workflow, event.name()));
+            }
+        }
+    }
+    
+    for (name, event) in due {
+        println!("{} {} (after {})", "Running workflow".dimmed(), name.bold(), event);
+        if let Err(e) = run_saved(name, card_manager) {
+            logging::warning(&format!("Triggered workflow {} failed: {}", name, e));
+        }
+    }
+    
+    // Triggered workflows don't trigger more workflows
+    events::take_fired();
+}
+
+/// Runs scheduled workflows as their time comes, until interrupted
+pub fn daemon(card_manager: &mut CardManager) -> Result<()> {
+    let storage = StorageManager::new()?;
+    println!("Running scheduled workflows (Ctrl-C to stop)");
+    
+    loop {
+        let now = Local::now();
+        
+        // Schedules are read every minute, so changes apply without a restart
+        if let Err(e) = card_manager.reload_if_changed() {
+            logging::warning(&format!("Failed to reload cards: {}", e));
+        }
+        let schedules = storage.load_schedules().unwrap_or_else(|e| {
+            logging::warning(&format!("Failed to load workflow schedules: {}", e));
+            Vec::new()
+        });
+        
+        for schedule in &schedules {
+            let Some(expr) = &schedule.cron else {
+                continue;
+            };
+            match Cron::parse(expr) {
+                Ok(cron) if cron.matches(&now) => {
+                    println!("{} {} {}", now.format("%Y-%m-%d %H:%M").to_string().dimmed(), "Running workflow".dimmed(), schedule.workflow.bold());
+                    if let Err(e) = run_saved(&schedule.workflow, card_manager) {
+                        logging::warning(&format!("Scheduled workflow {} failed: {}", schedule.workflow, e));
+                    }
+                },
+                Ok(_) => {},
+                Err(e) => logging::warning(&format!("Skipping {}: {}", schedule.workflow, e)),
+            }
+        }
+        run_triggers(card_manager);
+        
+        // Sleep until the start of the next minute
+        let now = Local::now();
+        let into_minute = Duration::new(now.second() as u64, now.nanosecond() % 1_000_000_000);
+        std::thread::sleep(Duration::from_secs(60).saturating_sub(into_minute));
+    }
+}
+
+/// Runs a saved workflow with its default variables
+fn run_saved(name: &str, card_manager: &mut CardManager) -> Result<()> {
+    let workflow = StorageManager::new()?.load_workflow(name)?;
+    let mut steps = PocketSteps::new(card_manager)?;
+    Engine::new(&workflow, &[]).run(&workflow, &mut steps)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    
+    fn at(date: (i32, u32, u32), hour: u32, minute: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+    
+    #[test]
+    fn matches_cron_expressions() {
+        // 2026-10-14 is a Wednesday
+        let daily = Cron::parse("0 9 * * *").unwrap();
+        assert!(daily.matches(&at((2026, 10, 14), 9, 0)));
+        assert!(!daily.matches(&at((2026, 10, 14), 9, 1)));
+        
+        let weekdays = Cron::parse("*/15 8-18 * * 1-5").unwrap();
+        assert!(weekdays.matches(&at((2026, 10, 14), 12, 45)));
+        assert!(!weekdays.matches(&at((2026, 10, 14), 12, 50)));
+        assert!(!weekdays.matches(&at((2026, 10, 18), 12, 45)));
+        
+        let sundays = Cron::parse("30 6 * * 7").unwrap();
+        assert!(sundays.matches(&at((2026, 10, 18), 6, 30)));
+        
+        // Both day fields restricted: either one will do
+        let either = Cron::parse("0 0 1,15 * 3").unwrap();
+        assert!(either.matches(&at((2026, 10, 14), 0, 0)));
+        assert!(either.matches(&at((2026, 10, 15), 0, 0)));
+        assert!(!either.matches(&at((2026, 10, 16), 0, 0)));
+    }
+    
+    #[test]
+    fn rejects_bad_cron_expressions() {
+        assert!(Cron::parse("0 9 * *").is_err());
+        assert!(Cron::parse("60 * * * *").is_err());
+        assert!(Cron::parse("*/0 * * * *").is_err());
+        assert!(Cron::parse("5-1 * * * *").is_err());
+        assert!(Cron::parse("x * * * *").is_err());
+        assert!(Cron::parse("5/20 * * * *").is_ok());
+    }
+    
+    #[test]
+    fn triggers_match_events_and_backpacks() {
+        assert_eq!(event_name("add").unwrap(), "entry_added");
+        assert_eq!(event_name("backpack_created").unwrap(), "backpack_created");
+        assert!(event_name("shove").is_err());
+        
+        let trigger = WorkflowSchedule {
+            workflow: "publish".to_string(),
+            cron: None,
+            on: Some("entry_added".to_string()),
+            backpack: Some("work".to_string()),
+        };
+        let added = |backpack: Option<&str>| Event::EntryAdded { id: "x".to_string(), backpack: backpack.map(str::to_string) };
+        assert!(fires_on(&trigger, &added(Some("work"))));
+        assert!(!fires_on(&trigger, &added(None)));
+        assert!(!fires_on(&trigger, &Event::EntryRemoved { id: "x".to_string(), backpack: Some("work".to_string()) }));
+        
+        let anywhere = WorkflowSchedule { backpack: None, ..trigger };
+        assert!(fires_on(&anywhere, &added(None)));
+    }
+}
@@ -0,0 +1,296 @@
+//! The steps a workflow can run for real
+
+use crate::cards::core::{CoreCard, EntryFilter};
+use crate::cards::{events, CardCommand, CardManager, Event};
+use crate::cli::handler;
+use crate::models::{ContentType, Entry};
+use crate::storage::{self, StorageManager};
+use crate::utils;
+use crate::workflow::StepRunner;
+use anyhow::{Result, anyhow, bail};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Commands that can't be workflow steps, because they'd run workflows or
+/// wait for input themselves
+const NOT_STEPS: &[&str] = &["lint", "workflow", "daemon", "repl", "mcp-serve", "api", "serve-editor", "lsp", "watch-clipboard"];
+
+/// Runs workflow steps against the pocket:
+///
+/// - `search <query> [--backpack NAME]` prints the matches (`@name` runs a saved search); its output is the top match's ID
+/// - `insert [ID] <file>` inserts an entry (the previous step's, when no ID is given)
+/// - `execute [ID] [args...]` runs a script; its output is what the script printed
+/// - `save [title] [--backpack NAME]` stores the previous step's output as a new entry
+/// - `shell: <command>` runs a shell command; its output is what it printed
+/// - any other pocket command (`add`, `remove`, a card's command, ...) runs as
+///   it would on the command line, with no output
+pub struct PocketSteps<'a> {
+    core: CoreCard,
+    card_manager: &'a mut CardManager,
+}
+
+impl<'a> PocketSteps<'a> {
+    pub fn new(card_manager: &'a mut CardManager) -> Result<Self> {
+        Ok(Self { core: CoreCard::new(storage::pocket_dir()?), card_manager })
+    }
+    
+    fn insert(&self, args: &[String], previous: Option<&str>) -> Result<String> {
+        let (id, file) = match args {
+            [file] => (entry_from(previous)?, file.as_str()),
+            [id, file] => (id.as_str(), file.as_str()),
+            _ => bail!("insert takes an entry and a file"),
+        };
+        self.core.insert(id, file, None, true)?;
+        Ok(id.to_string())
+    }
+    
+    fn execute(&self, args: &[String], previous: Option<&str>, timeout: Option<Duration>) -> Result<String> {
+        let (id, script_args) = match args.split_first() {
+            Some((id, rest)) => (id.as_str(), rest),
+            None => (entry_from(previous)?, args),
+        };
+        let options = utils::exec::ExecOptions {
+            timeout,
+            capture_output: true,
+            ..Default::default()
+        };
+        let outcome = self.core.execute_script(id, script_args, None, &options, false)?;
+        Ok(outcome.stdout)
+    }
+    
+    fn save(&self, args: &[String], previous: Option<&str>) -> Result<String> {
+        let content = previous.filter(|p| !p.is_empty())
+            .ok_or_else(|| anyhow!("save needs a previous step with some output"))?;
+        let (title, backpack) = split_backpack(args)?;
+        let title = if title.is_empty() {
+            content.lines().next().unwrap_or(content).to_string()
+        } else {
+            title.join(" ")
+        };
+        
+        let mut entry = Entry::new(title, ContentType::Text, None, vec![]);
+        entry.language = utils::detect_language(None, content);
+        
+        let storage = StorageManager::new()?;
+        storage.save_entry(&entry, content, backpack.as_deref())?;
+        events::emit(Event::EntryAdded { id: entry.id.clone(), backpack });
+        println!("Saved entry {}", entry.id.bold());
+        Ok(entry.id)
+    }
+    
+    /// Runs a shell command line, with the previous step's output in
+    /// `$POCKET_PREVIOUS`
+    fn shell(&self, args: &[String], previous: Option<&str>, timeout: Option<Duration>) -> Result<String> {
+        if args.is_empty() {
+            bail!("shell: needs a command");
+        }
+        let options = utils::exec::ExecOptions {
+            timeout,
+            capture_output: true,
+            env: vec![("POCKET_PREVIOUS".to_string(), previous.unwrap_or_default().to_string())],
+            ..Default::default()
+        };
+        let outcome = utils::exec::run_script("shell", &shell_words::join(args), &[], &options)?;
+        if outcome.timed_out {
+            bail!("Timed out after {}s", timeout.map(|t| t.as_secs_f64()).unwrap_or_default());
+        }
+        if !outcome.status.success() {
+            bail!("Command exited with {}", outcome.status);
+        }
+        Ok(outcome.stdout)
+    }
+    
+    /// Runs any other pocket command through the CLI, as if it had been typed
+    fn pocket(&mut self, args: &[String]) -> Result<String> {
+        if NOT_STEPS.contains(&args[0].as_str()) {
+            bail!("'{}' can't be used as a workflow step", args[0]);
+        }
+        let card_commands = handler::card_subcommands(self.card_manager);
+        let matches = parse_pocket_command(&card_commands, args)?;
+        handler::dispatch(&matches, args, &card_commands, self.card_manager)?;
+        Ok(String::new())
+    }
+}
+
+impl StepRunner for PocketSteps<'_> {
+    fn run(&mut self, args: &[String], previous: Option<&str>, timeout: Option<Duration>) -> Result<String> {
+        let Some((command, rest)) = args.split_first() else {
+            bail!("Empty step");
+        };
+        
+        // `shell: cmd` and `shell:cmd` both work
+        if let Some(first) = command.strip_prefix("shell:") {
+            let mut line: Vec<String> = rest.to_vec();
+            if !first.is_empty() {
+                line.insert(0, first.to_string());
+            }
+            return self.shell(&line, previous, timeout);
+        }
+        
+        match command.as_str() {
+            "search" => search(&self.core, rest),
+            "insert" => self.insert(rest, previous),
+            "execute" => self.execute(rest, previous, timeout),
+            "save" => self.save(rest, previous),
+            _ => self.pocket(args),
+        }
+    }
+}
+
+/// Plans workflow steps without carrying them out, for `workflow run --dry-run`.
+/// Searches still run, since they change nothing and later steps need the
+/// IDs they find; every other step says what it would do
+pub struct DryRun<'a> {
+    core: CoreCard,
+    card_manager: &'a CardManager,
+}
+
+impl<'a> DryRun<'a> {
+    pub fn new(card_manager: &'a CardManager) -> Result<Self> {
+        Ok(Self { core: CoreCard::new(storage::pocket_dir()?), card_manager })
+    }
+    
+    fn insert(&self, args: &[String], previous: Option<&str>) -> Result<String> {
+        let (reference, file) = match args {
+            [file] => (entry_from(previous)?, file.as_str()),
+            [id, file] => (id.as_str(), file.as_str()),
+            _ => bail!("insert takes an entry and a file"),
+        };
+        let (entry, _) = describe_entry(reference, None)?;
+        let note = if Path::new(file).is_file() { "" } else { " (doesn't exist yet)" };
+        would(&format!("insert {} into {}{}", entry, file.bold(), note));
+        Ok(reference.to_string())
+    }
+    
+    fn execute(&self, args: &[String], previous: Option<&str>) -> Result<String> {
+        let (reference, script_args) = match args.split_first() {
+            Some((id, rest)) => (id.as_str(), rest),
+            None => (entry_from(previous)?, args),
+        };
+        let (entry, interpreter) = describe_entry(reference, None)?;
+        let mut plan = format!("execute {}", entry);
+        if let Some(interpreter) = interpreter {
+            plan.push_str(&format!(" with {}", interpreter));
+        }
+        if !script_args.is_empty() {
+            plan.push_str(&format!(" passing {}", shell_words::join(script_args)));
+        }
+        would(&plan);
+        Ok(placeholder("execute"))
+    }
+    
+    fn save(&self, args: &[String], previous: Option<&str>) -> Result<String> {
+        if previous.is_none_or(str::is_empty) {
+            bail!("save needs a previous step with some output");
+        }
+        let (_, backpack) = split_backpack(args)?;
+        would(&format!("save the previous output as a new entry in {}", backpack.as_deref().unwrap_or("general").bold()));
+        Ok(placeholder("save"))
+    }
+    
+    fn pocket(&self, args: &[String]) -> Result<String> {
+        if NOT_STEPS.contains(&args[0].as_str()) {
+            bail!("'{}' can't be used as a workflow step", args[0]);
+        }
+        parse_pocket_command(&handler::card_subcommands(self.card_manager), args)?;
+        would(&format!("run pocket {}", shell_words::join(args)));
+        Ok(String::new())
+    }
+}
+
+impl StepRunner for DryRun<'_> {
+    fn run(&mut self, args: &[String], previous: Option<&str>, _timeout: Option<Duration>) -> Result<String> {
+        let Some((command, rest)) = args.split_first() else {
+            bail!("Empty step");
+        };
+        
+        if let Some(first) = command.strip_prefix("shell:") {
+            let mut line: Vec<String> = rest.to_vec();
+            if !first.is_empty() {
+                line.insert(0, first.to_string());
+            }
+            if line.is_empty() {
+                bail!("shell: needs a command");
+            }
+            would(&format!("run in the shell: {}", shell_words::join(&line)));
+            return Ok(placeholder("shell"));
+        }
+        
+        match command.as_str() {
+            "search" => search(&self.core, rest),
+            "insert" => self.insert(rest, previous),
+            "execute" => self.execute(rest, previous),
+            "save" => self.save(rest, previous),
+            _ => self.pocket(args),
+        }
+    }
+}
+
+/// Runs a `search` step: prints the matches and hands on the top one's ID
+fn search(core: &CoreCard, args: &[String]) -> Result<String> {
+    let (query, backpack) = split_backpack(args)?;
+    if query.is_empty() {
+        bail!("search needs a query");
+    }
+    let query = query.join(" ");
+    
+    let results = core.search(&query, 10, backpack.as_deref(), None, &EntryFilter::default())?;
+    if results.is_empty() {
+        println!("No results found for query: {}", query.bold());
+    }
+    for (i, (entry, _)) in results.iter().enumerate() {
+        println!("{}. {} - {}", i + 1, entry.id.bold(), entry.title);
+    }
+    Ok(results.first().map(|(entry, _)| entry.id.clone()).unwrap_or_default())
+}
+
+/// Parses a step as a pocket command line, without running it
+fn parse_pocket_command(card_commands: &BTreeMap<String, Vec<CardCommand>>, args: &[String]) -> Result<clap::ArgMatches> {
+    handler::cli_command(card_commands)
+        .try_get_matches_from(std::iter::once("pocket").chain(args.iter().map(String::as_str)))
+        .map_err(|e| anyhow!("{}", e.render().to_string().trim_end()))
+}
+
+/// Looks up an entry for a dry run: how to refer to it, and the interpreter
+/// it would run with
+fn describe_entry(reference: &str, backpack: Option<&str>) -> Result<(String, Option<String>)> {
+    let storage = StorageManager::new()?;
+    let (id, backpack) = storage.resolve_entry(reference, backpack)?;
+    let (entry, content) = storage.load_entry(&id, backpack.as_deref())?;
+    let interpreter = utils::exec::pick_interpreter(entry.get_metadata("interpreter"), &content, entry.language.as_deref());
+    Ok((format!("'{}' ({})", entry.title, id.bold()), interpreter))
+}
+
+/// Stands in for output a dry run can't know, so later steps still have
+/// something to refer to
+fn placeholder(command: &str) -> String {
+    format!("<output of {}>", command)
+}
+
+/// Prints a dry-run plan line
+fn would(plan: &str) {
+    println!("    {} {}", "would".cyan(), plan);
+}
+
+/// The entry the previous step produced, for steps that leave out the ID
+fn entry_from(previous: Option<&str>) -> Result<&str> {
+    previous.filter(|p| !p.is_empty())
+        .ok_or_else(|| anyhow!("No entry given, and the previous step didn't produce one"))
+}
+
+/// Pulls `--backpack NAME` out of a step's arguments
+fn split_backpack(args: &[String]) -> Result<(Vec<String>, Option<String>)> {
+    let mut rest = Vec::new();
+    let mut backpack = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--backpack" || arg == "-b" {
+            backpack = Some(iter.next().ok_or_else(|| anyhow!("--backpack needs a name"))?.clone());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((rest, backpack))
+}